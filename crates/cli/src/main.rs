@@ -219,6 +219,34 @@ impl Client {
         method: Method,
         url: &str,
         body: Option<B>,
+    ) -> Option<R> {
+        self.try_http_request_raw(
+            method,
+            url,
+            body.map(|body| serde_json::to_vec(&body).unwrap_result("serialize body")),
+        )
+        .await
+    }
+
+    pub async fn http_request_raw<R: DeserializeOwned>(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<Vec<u8>>,
+    ) -> R {
+        self.try_http_request_raw(method, url, body)
+            .await
+            .unwrap_or_else(|| {
+                eprintln!("Request failed: No data returned.");
+                std::process::exit(1);
+            })
+    }
+
+    pub async fn try_http_request_raw<R: DeserializeOwned>(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<Vec<u8>>,
     ) -> Option<R> {
         let url = format!(
             "{}{}{}",
@@ -245,7 +273,7 @@ impl Client {
             );
 
         if let Some(body) = body {
-            request = request.body(serde_json::to_string(&body).unwrap_result("serialize body"));
+            request = request.body(body);
         }
 
         let response = request.send().await.unwrap_result("send HTTP request");
@@ -375,6 +375,19 @@ pub enum ImportCommands {
         /// Path to the exported account directory
         path: String,
     },
+    /// Import calendars, address books and files into an account
+    Groupware {
+        /// Import a Google Takeout or iCloud export instead of a groupware archive
+        #[clap(value_enum)]
+        #[clap(short, long)]
+        source: Option<GroupwareSource>,
+
+        /// Account name or email to import into
+        account: String,
+
+        /// Path to the groupware archive or export, or '-' for stdin
+        path: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -391,6 +404,14 @@ pub enum ExportCommands {
         /// Path to export the account to
         path: String,
     },
+    /// Export an account's calendars, address books and files
+    Groupware {
+        /// Account name or email to export
+        account: String,
+
+        /// Path of the archive file to create
+        path: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -423,6 +444,12 @@ pub enum ServerCommands {
         /// Prefix to filter configuration entries by
         prefix: Option<String>,
     },
+
+    /// Rebuild the calendar, contact and file hierarchy of an account
+    RebuildGroupware {
+        /// Account name or email to rebuild
+        account: String,
+    },
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -435,6 +462,14 @@ pub enum MailboxFormat {
     MaildirNested,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum GroupwareSource {
+    /// Google Takeout archive or ICS/vCard file
+    Google,
+    /// iCloud export archive or ICS/vCard file
+    Icloud,
+}
+
 #[derive(Subcommand)]
 pub enum QueueCommands {
     /// Shows messages queued for delivery
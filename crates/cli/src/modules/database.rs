@@ -112,6 +112,43 @@ impl ServerCommands {
                     if results.len() == 1 { "" } else { "s" }
                 );
             }
+            ServerCommands::RebuildGroupware { account } => {
+                let results = client
+                    .http_request::<HashMap<String, Value>, String>(
+                        Method::GET,
+                        &format!("/api/store/groupware/{account}/rebuild"),
+                        None,
+                    )
+                    .await;
+
+                for (collection, repair) in &results {
+                    for (key, label) in [
+                        ("missing", "Added missing"),
+                        ("stale", "Removed stale"),
+                        ("changed", "Updated"),
+                    ] {
+                        for path in repair
+                            .get(key)
+                            .and_then(Value::as_array)
+                            .into_iter()
+                            .flatten()
+                            .filter_map(Value::as_str)
+                        {
+                            eprintln!("{collection}: {label} resource {path}");
+                        }
+                    }
+                    let previous_change_id = repair.get("previousChangeId").and_then(Value::as_u64);
+                    let change_id = repair.get("changeId").and_then(Value::as_u64);
+                    if previous_change_id.is_some() && previous_change_id != change_id {
+                        eprintln!(
+                            "{collection}: Updated sync token from {} to {}",
+                            previous_change_id.unwrap_or_default(),
+                            change_id.unwrap_or_default()
+                        );
+                    }
+                }
+                eprintln!("Successfully rebuilt groupware data of {account}.");
+            }
         }
     }
 }
@@ -17,7 +17,9 @@ use jmap_client::{
     sieve::{self, SieveScript},
     vacation_response::{self, VacationResponse},
 };
+use reqwest::Method;
 use serde::Serialize;
+use serde_json::Value;
 use tokio::io::AsyncWriteExt;
 
 use crate::modules::RETRY_ATTEMPTS;
@@ -30,13 +32,13 @@ use super::{
 
 impl ExportCommands {
     pub async fn exec(self, client: Client) {
-        let mut client = client.into_jmap_client().await;
         match self {
             ExportCommands::Account {
                 num_concurrent,
                 account,
                 path,
             } => {
+                let mut client = client.into_jmap_client().await;
                 client.set_default_account_id(name_to_id(&client, &account).await);
                 let max_objects_in_get = client
                     .session()
@@ -122,6 +124,21 @@ impl ExportCommands {
                 // Wait for remaining futures
                 while futures.next().await.is_some() {}
             }
+            ExportCommands::Groupware { account, path } => {
+                let archive = client
+                    .http_request::<Value, String>(
+                        Method::GET,
+                        &format!("/api/store/groupware/{account}"),
+                        None,
+                    )
+                    .await;
+                std::fs::write(
+                    &path,
+                    serde_json::to_vec_pretty(&archive).unwrap_result("serialize archive"),
+                )
+                .unwrap_result(&format!("write {path}"));
+                eprintln!("Exported groupware data of {account} to {path}.");
+            }
         }
     }
 }
@@ -27,13 +27,15 @@ use mail_parser::mailbox::{
     mbox::{self, MessageIterator},
 };
 use rand::Rng;
+use reqwest::Method;
 use serde::de::DeserializeOwned;
+use serde_json::Value;
 use tokio::{fs::File, io::AsyncReadExt};
 
 use crate::modules::{RETRY_ATTEMPTS, UnwrapResult, name_to_id};
 
 use super::{
-    cli::{Client, ImportCommands, MailboxFormat},
+    cli::{Client, GroupwareSource, ImportCommands, MailboxFormat},
     export::{
         fetch_emails, fetch_identities, fetch_mailboxes, fetch_sieve_scripts,
         fetch_vacation_responses,
@@ -63,8 +65,6 @@ struct Message {
 }
 impl ImportCommands {
     pub async fn exec(self, client: Client) {
-        let mut client = client.into_jmap_client().await;
-
         match self {
             ImportCommands::Messages {
                 num_concurrent,
@@ -72,6 +72,7 @@ impl ImportCommands {
                 account,
                 path,
             } => {
+                let mut client = client.into_jmap_client().await;
                 client.set_default_account_id(name_to_id(&client, &account).await);
                 let mut create_mailboxes = Vec::new();
                 let mut create_mailbox_names = Vec::new();
@@ -423,6 +424,7 @@ impl ImportCommands {
                 account,
                 path,
             } => {
+                let mut client = client.into_jmap_client().await;
                 client.set_default_account_id(name_to_id(&client, &account).await);
                 let path = PathBuf::from(path);
                 if !path.exists() {
@@ -443,6 +445,57 @@ impl ImportCommands {
                 import_identities(&client, &path).await;
                 import_vacation_responses(&client, &path).await;
             }
+            ImportCommands::Groupware {
+                source,
+                account,
+                path,
+            } => {
+                let result = if let Some(source) = source {
+                    let source = match source {
+                        GroupwareSource::Google => "google",
+                        GroupwareSource::Icloud => "icloud",
+                    };
+                    client
+                        .http_request_raw::<Value>(
+                            Method::POST,
+                            &format!("/api/store/groupware/{account}/{source}"),
+                            Some(read_file(&path)),
+                        )
+                        .await
+                } else {
+                    let archive = serde_json::from_slice::<Value>(&read_file(&path))
+                        .unwrap_result("parse groupware archive");
+                    client
+                        .http_request::<Value, _>(
+                            Method::POST,
+                            &format!("/api/store/groupware/{account}"),
+                            Some(archive),
+                        )
+                        .await
+                };
+
+                for (key, label) in [
+                    ("calendars", "calendars"),
+                    ("events", "events"),
+                    ("addressBooks", "address books"),
+                    ("cards", "contacts"),
+                    ("files", "files"),
+                ] {
+                    eprintln!(
+                        "Imported {} {label}.",
+                        result.get(key).and_then(Value::as_u64).unwrap_or_default()
+                    );
+                }
+                for reason in result
+                    .get("skipped")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                {
+                    eprintln!("Skipped: {reason}");
+                }
+            }
         }
     }
 }
@@ -123,6 +123,11 @@ impl Server {
                 .jmap
                 .upload_max_concurrent
                 .map(ConcurrencyLimiter::new),
+            concurrent_dav_expensive: self
+                .core
+                .groupware
+                .max_concurrent_expensive
+                .map(ConcurrencyLimiter::new),
             obj_size: 0,
             revision,
         };
@@ -522,6 +527,12 @@ impl AccessToken {
         self.is_member(to_account_id) || self.access_to.iter().any(|(id, _)| *id == to_account_id)
     }
 
+    // Tenant administrators can only act on members of their own tenant
+    pub fn has_tenant_access(&self, other: &AccessToken) -> bool {
+        self.tenant
+            .is_none_or(|tenant| other.tenant.is_some_and(|other| other.id == tenant.id))
+    }
+
     pub fn assert_has_access(
         &self,
         to_account_id: Id,
@@ -571,6 +582,17 @@ impl AccessToken {
                 }
                 jmap_proto::method::get::RequestArguments::Quota => Permission::JmapQuotaGet,
                 jmap_proto::method::get::RequestArguments::Blob(_) => Permission::JmapBlobGet,
+                jmap_proto::method::get::RequestArguments::Calendar => Permission::JmapCalendarGet,
+                jmap_proto::method::get::RequestArguments::CalendarEvent => {
+                    Permission::JmapCalendarEventGet
+                }
+                jmap_proto::method::get::RequestArguments::AddressBook => {
+                    Permission::JmapAddressBookGet
+                }
+                jmap_proto::method::get::RequestArguments::ContactCard => {
+                    Permission::JmapContactCardGet
+                }
+                jmap_proto::method::get::RequestArguments::FileNode => Permission::JmapFileNodeGet,
             },
             RequestMethod::Set(m) => match &m.arguments {
                 jmap_proto::method::set::RequestArguments::Email => Permission::JmapEmailSet,
@@ -588,6 +610,21 @@ impl AccessToken {
                 jmap_proto::method::set::RequestArguments::VacationResponse => {
                     Permission::JmapVacationResponseSet
                 }
+                jmap_proto::method::set::RequestArguments::Calendar(_) => {
+                    Permission::JmapCalendarSet
+                }
+                jmap_proto::method::set::RequestArguments::CalendarEvent => {
+                    Permission::JmapCalendarEventSet
+                }
+                jmap_proto::method::set::RequestArguments::AddressBook(_) => {
+                    Permission::JmapAddressBookSet
+                }
+                jmap_proto::method::set::RequestArguments::ContactCard => {
+                    Permission::JmapContactCardSet
+                }
+                jmap_proto::method::set::RequestArguments::FileNode(_) => {
+                    Permission::JmapFileNodeSet
+                }
             },
             RequestMethod::Changes(m) => match m.arguments {
                 jmap_proto::method::changes::RequestArguments::Email => {
@@ -608,6 +645,21 @@ impl AccessToken {
                 jmap_proto::method::changes::RequestArguments::Quota => {
                     Permission::JmapQuotaChanges
                 }
+                jmap_proto::method::changes::RequestArguments::Calendar => {
+                    Permission::JmapCalendarChanges
+                }
+                jmap_proto::method::changes::RequestArguments::CalendarEvent => {
+                    Permission::JmapCalendarEventChanges
+                }
+                jmap_proto::method::changes::RequestArguments::AddressBook => {
+                    Permission::JmapAddressBookChanges
+                }
+                jmap_proto::method::changes::RequestArguments::ContactCard => {
+                    Permission::JmapContactCardChanges
+                }
+                jmap_proto::method::changes::RequestArguments::FileNode => {
+                    Permission::JmapFileNodeChanges
+                }
             },
             RequestMethod::Copy(m) => match m.arguments {
                 jmap_proto::method::copy::RequestArguments::Email => Permission::JmapEmailCopy,
@@ -634,6 +686,15 @@ impl AccessToken {
                 jmap_proto::method::query::RequestArguments::Quota => {
                     Permission::JmapQuotaQueryChanges
                 }
+                jmap_proto::method::query::RequestArguments::CalendarEvent => {
+                    Permission::JmapCalendarEventQueryChanges
+                }
+                jmap_proto::method::query::RequestArguments::ContactCard => {
+                    Permission::JmapContactCardQueryChanges
+                }
+                jmap_proto::method::query::RequestArguments::FileNode => {
+                    Permission::JmapFileNodeQueryChanges
+                }
             },
             RequestMethod::Query(m) => match m.arguments {
                 jmap_proto::method::query::RequestArguments::Email(_) => Permission::JmapEmailQuery,
@@ -650,6 +711,15 @@ impl AccessToken {
                     Permission::JmapPrincipalQuery
                 }
                 jmap_proto::method::query::RequestArguments::Quota => Permission::JmapQuotaQuery,
+                jmap_proto::method::query::RequestArguments::CalendarEvent => {
+                    Permission::JmapCalendarEventQuery
+                }
+                jmap_proto::method::query::RequestArguments::ContactCard => {
+                    Permission::JmapContactCardQuery
+                }
+                jmap_proto::method::query::RequestArguments::FileNode => {
+                    Permission::JmapFileNodeQuery
+                }
             },
             RequestMethod::SearchSnippet(_) => Permission::JmapSearchSnippet,
             RequestMethod::ValidateScript(_) => Permission::JmapSieveScriptValidate,
@@ -694,6 +764,12 @@ impl AccessToken {
             .map_or(LimiterResult::Disabled, |limiter| limiter.is_allowed())
     }
 
+    pub fn is_dav_expensive_request_allowed(&self) -> LimiterResult {
+        self.concurrent_dav_expensive
+            .as_ref()
+            .map_or(LimiterResult::Disabled, |limiter| limiter.is_allowed())
+    }
+
     pub fn update_size(mut self) -> Self {
         self.obj_size = (std::mem::size_of::<AccessToken>()
             + (self.member_of.len() * std::mem::size_of::<u32>())
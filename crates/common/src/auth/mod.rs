@@ -39,6 +39,7 @@ pub struct AccessToken {
     pub concurrent_http_requests: Option<ConcurrencyLimiter>,
     pub concurrent_imap_requests: Option<ConcurrencyLimiter>,
     pub concurrent_uploads: Option<ConcurrencyLimiter>,
+    pub concurrent_dav_expensive: Option<ConcurrencyLimiter>,
     pub revision: u64,
     pub obj_size: u64,
 }
@@ -92,4 +92,21 @@ impl Server {
             LimiterResult::Disabled => Ok(None),
         }
     }
+
+    pub fn is_dav_expensive_request_allowed(
+        &self,
+        access_token: &AccessToken,
+    ) -> trc::Result<Option<InFlight>> {
+        match access_token.is_dav_expensive_request_allowed() {
+            LimiterResult::Allowed(in_flight) => Ok(Some(in_flight)),
+            LimiterResult::Forbidden => {
+                if access_token.has_permission(Permission::UnlimitedRequests) {
+                    Ok(None)
+                } else {
+                    Err(trc::LimitEvent::ConcurrentRequest.into_err())
+                }
+            }
+            LimiterResult::Disabled => Ok(None),
+        }
+    }
 }
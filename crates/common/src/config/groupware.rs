@@ -4,19 +4,38 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::time::Duration;
+use std::{path::PathBuf, sync::OnceLock, time::Duration};
 
+use ahash::{AHashMap, AHashSet};
+use calcard::vcard::VCardVersion;
+use hyper::HeaderMap;
 use utils::config::Config;
 
+use super::parse_http_headers;
+
 #[derive(Debug, Clone, Default)]
 pub struct GroupwareConfig {
     // DAV settings
     pub max_request_size: usize,
+    pub max_request_depth: usize,
+    pub max_request_elements: usize,
     pub dead_property_size: Option<usize>,
     pub live_property_size: usize,
     pub max_lock_timeout: u64,
     pub max_locks_per_user: usize,
     pub max_results: usize,
+    pub max_infinity_results: Option<usize>,
+    pub max_response_size: usize,
+    pub max_concurrent_expensive: Option<u64>,
+    pub slow_request_threshold: Option<Duration>,
+    pub event_source_throttle: Duration,
+    pub apns: Option<ApnsConfig>,
+    pub web_push: Option<WebPushConfig>,
+    pub compression_min_size: Option<usize>,
+    pub client_policies: Vec<DavClientPolicy>,
+    pub item_limits: ItemLimits,
+    pub item_limits_tenants: AHashMap<u32, ItemLimits>,
+    pub item_limits_accounts: AHashMap<u32, ItemLimits>,
 
     // Calendar settings
     pub max_ical_size: usize,
@@ -24,22 +43,215 @@ pub struct GroupwareConfig {
     pub max_ical_attendees_per_instance: usize,
     pub default_calendar_name: Option<String>,
     pub default_calendar_display_name: Option<String>,
+    pub outlook_compat_export: bool,
+    pub google_compat_import: bool,
+    pub google_compat_tenants: AHashSet<u32>,
+    pub invites_from_attachments: bool,
+    pub scheduling_enabled: bool,
+    pub resource_auto_accept: ResourceAutoAccept,
+    pub calendar_mirrors: Vec<CalendarMirror>,
+    pub birthday_calendar: Option<String>,
 
     // Addressbook settings
     pub max_vcard_size: usize,
     pub default_addressbook_name: Option<String>,
     pub default_addressbook_display_name: Option<String>,
+    pub directory_addressbooks: Vec<DirectoryAddressBook>,
+    pub directory_gateway: Option<String>,
 
     // File storage settings
     pub max_file_size: usize,
+    pub max_file_versions: usize,
+    pub file_trash: bool,
+    pub file_trash_retention: Option<Duration>,
+    pub file_mounts: Vec<FileMount>,
+
+    // Content inspection settings
+    pub icap: Option<IcapServer>,
+    pub icap_tenants: AHashMap<u32, IcapServer>,
+
+    // Provisioning settings
+    pub provisioning_enabled: bool,
+    pub provisioning: ProvisioningTemplate,
+    pub provisioning_tenants: AHashMap<u32, ProvisioningTemplate>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProvisioningTemplate {
+    pub calendar_name: Option<String>,
+    pub calendar_display_name: Option<String>,
+    pub addressbook_name: Option<String>,
+    pub addressbook_display_name: Option<String>,
+    pub folders: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ItemLimits {
+    pub events_per_calendar: Option<usize>,
+    pub cards_per_addressbook: Option<usize>,
+    pub children_per_folder: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DavClientPolicy {
+    pub user_agents: Vec<String>,
+    pub vcard_version: Option<VCardVersion>,
+    pub alternate_formats: bool,
+    pub compression: bool,
+    pub prefer: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct CalendarMirror {
+    pub id: String,
+    pub account: String,
+    pub calendar: String,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub conflict_policy: MirrorConflictPolicy,
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub tls_allow_invalid_certs: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorConflictPolicy {
+    LocalWins,
+    RemoteWins,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResourceAutoAccept {
+    #[default]
+    Disabled,
+    IfFree,
+    Always,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApnsConfig {
+    pub url: String,
+    pub environment: ApnsEnvironment,
+    pub calendar_topic: Option<String>,
+    pub contacts_topic: Option<String>,
+    pub identity: Vec<u8>,
+    pub refresh_interval: Duration,
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApnsEnvironment {
+    Production,
+    Sandbox,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebPushConfig {
+    pub max_expiry: Duration,
+    pub max_subscriptions: usize,
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct IcapServer {
+    pub id: String,
+    pub host: String,
+    pub port: u16,
+    pub service: String,
+    pub timeout: Duration,
+    pub failure_policy: IcapFailurePolicy,
+    pub max_size: usize,
+    pub scan_files: bool,
+    pub scan_calendars: bool,
+    pub scan_contacts: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcapFailurePolicy {
+    Allow,
+    Reject,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileMount {
+    pub id: String,
+    pub account: String,
+    // Resolved on first use, the account may not exist yet when the config loads
+    pub account_id: OnceLock<u32>,
+    pub path: String,
+    pub root: PathBuf,
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DirectoryAddressBook {
+    pub id: String,
+    pub account: String,
+    pub name: String,
+    pub display_name: Option<String>,
+    pub directory: String,
+    pub members: Vec<String>,
+    pub members_query: Option<MembersQuery>,
+    pub share_with: Vec<String>,
+    pub refresh: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct MembersQuery {
+    pub store: String,
+    pub query: String,
 }
 
 impl GroupwareConfig {
     pub fn parse(config: &mut Config) -> Self {
+        let item_limits = ItemLimits {
+            events_per_calendar: config.property("calendar.max-events"),
+            cards_per_addressbook: config.property("contacts.max-cards"),
+            children_per_folder: config.property("file-storage.max-children"),
+        };
+        let (item_limits_tenants, item_limits_accounts) =
+            ItemLimits::parse_overrides(config, item_limits);
+
+        let default_calendar_name = config
+            .property_or_default::<Option<String>>("calendar.default.href-name", "default")
+            .unwrap_or_default();
+        let default_calendar_display_name = config
+            .property_or_default::<Option<String>>(
+                "calendar.default.display-name",
+                "Stalwart Calendar",
+            )
+            .unwrap_or_default();
+        let default_addressbook_name = config
+            .property_or_default::<Option<String>>("contacts.default.href-name", "default")
+            .unwrap_or_default();
+        let default_addressbook_display_name = config
+            .property_or_default::<Option<String>>(
+                "contacts.default.display-name",
+                "Stalwart Address Book",
+            )
+            .unwrap_or_default();
+        let provisioning = ProvisioningTemplate {
+            calendar_name: default_calendar_name.clone(),
+            calendar_display_name: default_calendar_display_name.clone(),
+            addressbook_name: default_addressbook_name.clone(),
+            addressbook_display_name: default_addressbook_display_name.clone(),
+            folders: config
+                .values("groupware.provisioning.folders")
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .collect(),
+        };
+        let provisioning_tenants = ProvisioningTemplate::parse_overrides(config, &provisioning);
+        let (icap, icap_tenants) = IcapServer::parse_all(config);
+
         GroupwareConfig {
             max_request_size: config
                 .property("dav.request.max-size")
                 .unwrap_or(25 * 1024 * 1024),
+            max_request_depth: config.property("dav.request.max-depth").unwrap_or(64),
+            max_request_elements: config
+                .property("dav.request.max-elements")
+                .unwrap_or(100_000),
             dead_property_size: config
                 .property_or_default::<Option<usize>>("dav.property.max-size.dead", "1024")
                 .unwrap_or(Some(1024)),
@@ -50,24 +262,34 @@ impl GroupwareConfig {
                 .unwrap_or(3600),
             max_locks_per_user: config.property("dav.locks.max-per-user").unwrap_or(10),
             max_results: config.property("dav.response.max-results").unwrap_or(2000),
-            default_calendar_name: config
-                .property_or_default::<Option<String>>("calendar.default.href-name", "default")
-                .unwrap_or_default(),
-            default_calendar_display_name: config
-                .property_or_default::<Option<String>>(
-                    "calendar.default.display-name",
-                    "Stalwart Calendar",
-                )
-                .unwrap_or_default(),
-            default_addressbook_name: config
-                .property_or_default::<Option<String>>("contacts.default.href-name", "default")
-                .unwrap_or_default(),
-            default_addressbook_display_name: config
-                .property_or_default::<Option<String>>(
-                    "contacts.default.display-name",
-                    "Stalwart Address Book",
-                )
+            max_infinity_results: config
+                .property_or_default::<Option<usize>>("dav.response.max-results-infinity", "false")
                 .unwrap_or_default(),
+            max_concurrent_expensive: config
+                .property_or_default::<Option<u64>>("dav.request.max-concurrent-expensive", "2")
+                .unwrap_or(Some(2)),
+            slow_request_threshold: config
+                .property_or_default::<Option<Duration>>("dav.request.slow-threshold", "5s")
+                .unwrap_or(Some(Duration::from_secs(5))),
+            event_source_throttle: config
+                .property_or_default("dav.event-source.throttle", "1s")
+                .unwrap_or_else(|| Duration::from_secs(1)),
+            apns: ApnsConfig::parse(config),
+            web_push: WebPushConfig::parse(config),
+            max_response_size: config
+                .property("dav.response.max-size")
+                .unwrap_or(50 * 1024 * 1024),
+            compression_min_size: config
+                .property_or_default::<Option<usize>>("dav.response.compression.min-size", "1024")
+                .unwrap_or(Some(1024)),
+            client_policies: DavClientPolicy::parse_all(config),
+            item_limits,
+            item_limits_tenants,
+            item_limits_accounts,
+            default_calendar_name,
+            default_calendar_display_name,
+            default_addressbook_name,
+            default_addressbook_display_name,
             max_ical_size: config.property("calendar.max-size").unwrap_or(512 * 1024),
             max_ical_instances: config
                 .property("calendar.max-recurrence-expansions")
@@ -75,10 +297,733 @@ impl GroupwareConfig {
             max_ical_attendees_per_instance: config
                 .property("calendar.max-attendees-per-instance")
                 .unwrap_or(20),
+            outlook_compat_export: config
+                .property("calendar.compat.outlook.export")
+                .unwrap_or(false),
+            google_compat_import: config
+                .property("calendar.compat.google.import")
+                .unwrap_or(false),
+            google_compat_tenants: config
+                .properties::<u32>("calendar.compat.google.tenants")
+                .into_iter()
+                .map(|(_, tenant_id)| tenant_id)
+                .collect(),
+            invites_from_attachments: config
+                .property("calendar.invites.from-attachments")
+                .unwrap_or(false),
+            scheduling_enabled: config
+                .property("calendar.scheduling.enable")
+                .unwrap_or(false),
+            resource_auto_accept: match config
+                .value("calendar.scheduling.resources.auto-accept")
+                .unwrap_or("if-free")
+            {
+                "if-free" => ResourceAutoAccept::IfFree,
+                "always" => ResourceAutoAccept::Always,
+                "disabled" => ResourceAutoAccept::Disabled,
+                value => {
+                    let value = value.to_string();
+                    config.new_parse_error(
+                        "calendar.scheduling.resources.auto-accept",
+                        format!("Invalid auto-accept policy {value:?}"),
+                    );
+                    ResourceAutoAccept::Disabled
+                }
+            },
+            calendar_mirrors: CalendarMirror::parse_all(config),
+            birthday_calendar: config
+                .property("calendar.birthdays.enable")
+                .unwrap_or(false)
+                .then(|| {
+                    config
+                        .value("calendar.birthdays.href-name")
+                        .unwrap_or("birthdays")
+                        .trim_matches('/')
+                        .to_string()
+                }),
             max_vcard_size: config.property("contacts.max-size").unwrap_or(512 * 1024),
+            directory_addressbooks: DirectoryAddressBook::parse_all(config),
+            directory_gateway: config
+                .property("contacts.directory-gateway.enable")
+                .unwrap_or(false)
+                .then(|| {
+                    config
+                        .value("contacts.directory-gateway.href-name")
+                        .unwrap_or("directory")
+                        .trim_matches('/')
+                        .to_string()
+                }),
             max_file_size: config
                 .property("file-storage.max-size")
                 .unwrap_or(25 * 1024 * 1024),
+            max_file_versions: config.property("file-storage.max-versions").unwrap_or(0),
+            file_trash: config
+                .property("file-storage.trash.enable")
+                .unwrap_or(false),
+            file_trash_retention: config
+                .property_or_default::<Option<Duration>>("file-storage.trash.retention", "30d")
+                .unwrap_or_default(),
+            file_mounts: FileMount::parse_all(config),
+            icap,
+            icap_tenants,
+            provisioning_enabled: config
+                .property("groupware.provisioning.enable")
+                .unwrap_or(false),
+            provisioning,
+            provisioning_tenants,
+        }
+    }
+
+    /// Whether Google Calendar quirks are tolerated on import, either for all
+    /// accounts or only for those belonging to the listed tenants.
+    pub fn google_compat(&self, tenant_id: Option<u32>) -> bool {
+        self.google_compat_import
+            || tenant_id.is_some_and(|tenant_id| self.google_compat_tenants.contains(&tenant_id))
+    }
+
+    /// Returns the item-count limits for an account, account overrides take
+    /// precedence over tenant overrides and the server-wide defaults.
+    pub fn item_limits(&self, account_id: u32, tenant_id: Option<u32>) -> &ItemLimits {
+        self.item_limits_accounts
+            .get(&account_id)
+            .or_else(|| tenant_id.and_then(|tenant_id| self.item_limits_tenants.get(&tenant_id)))
+            .unwrap_or(&self.item_limits)
+    }
+
+    /// Returns the collections created for new principals of a tenant,
+    /// falling back to the server-wide template.
+    pub fn provisioning_template(&self, tenant_id: Option<u32>) -> &ProvisioningTemplate {
+        tenant_id
+            .and_then(|tenant_id| self.provisioning_tenants.get(&tenant_id))
+            .unwrap_or(&self.provisioning)
+    }
+
+    /// Returns the ICAP server that inspects uploads of a tenant, falling
+    /// back to the server-wide one.
+    pub fn icap_server(&self, tenant_id: Option<u32>) -> Option<&IcapServer> {
+        tenant_id
+            .and_then(|tenant_id| self.icap_tenants.get(&tenant_id))
+            .or(self.icap.as_ref())
+    }
+
+    /// Returns the first client policy whose User-Agent patterns match.
+    pub fn client_policy(&self, user_agent: &str) -> Option<&DavClientPolicy> {
+        let user_agent = user_agent.to_ascii_lowercase();
+        self.client_policies.iter().find(|policy| {
+            policy
+                .user_agents
+                .iter()
+                .any(|pattern| user_agent.contains(pattern.as_str()))
+        })
+    }
+}
+
+impl DavClientPolicy {
+    fn parse_all(config: &mut Config) -> Vec<Self> {
+        let mut policies = Vec::new();
+        for id in config
+            .sub_keys("dav.client-policy", "")
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+        {
+            if let Some(policy) = DavClientPolicy::parse(config, &id) {
+                policies.push(policy);
+            }
+        }
+        policies
+    }
+
+    fn parse(config: &mut Config, id: &str) -> Option<Self> {
+        if !config
+            .property_or_default(("dav.client-policy", id, "enable"), "true")
+            .unwrap_or(true)
+        {
+            return None;
+        }
+
+        let user_agents = config
+            .values(("dav.client-policy", id, "user-agent"))
+            .map(|(_, value)| value.trim().to_ascii_lowercase())
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<_>>();
+        if user_agents.is_empty() {
+            config.new_missing_property(("dav.client-policy", id, "user-agent"));
+            return None;
+        }
+
+        let vcard_version = if let Some(version) = config
+            .value(("dav.client-policy", id, "vcard-version"))
+            .map(|version| version.trim().to_string())
+        {
+            let vcard_version = VCardVersion::try_parse(&version);
+            if vcard_version.is_none() {
+                config.new_parse_error(
+                    ("dav.client-policy", id, "vcard-version"),
+                    format!("Invalid vCard version {version:?}"),
+                );
+            }
+            vcard_version
+        } else {
+            None
+        };
+
+        Some(DavClientPolicy {
+            user_agents,
+            vcard_version,
+            alternate_formats: config
+                .property_or_default(("dav.client-policy", id, "alternate-formats"), "true")
+                .unwrap_or(true),
+            compression: config
+                .property_or_default(("dav.client-policy", id, "compression"), "true")
+                .unwrap_or(true),
+            prefer: config
+                .property_or_default(("dav.client-policy", id, "prefer"), "true")
+                .unwrap_or(true),
+        })
+    }
+}
+
+impl CalendarMirror {
+    fn parse_all(config: &mut Config) -> Vec<Self> {
+        let mut mirrors = Vec::new();
+        for id in config
+            .sub_keys("calendar.mirror", "")
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+        {
+            if let Some(mirror) = CalendarMirror::parse(config, &id) {
+                mirrors.push(mirror);
+            }
+        }
+        mirrors
+    }
+
+    fn parse(config: &mut Config, id: &str) -> Option<Self> {
+        if !config
+            .property_or_default(("calendar.mirror", id, "enable"), "true")
+            .unwrap_or(true)
+        {
+            return None;
+        }
+
+        let account = config
+            .value_require_non_empty(("calendar.mirror", id, "account"))?
+            .to_string();
+        let calendar = config
+            .value_require_non_empty(("calendar.mirror", id, "calendar"))?
+            .to_string();
+        let mut url = config
+            .value_require_non_empty(("calendar.mirror", id, "url"))?
+            .to_string();
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+        let conflict_policy = match config
+            .value(("calendar.mirror", id, "conflict"))
+            .unwrap_or("remote-wins")
+        {
+            "remote-wins" => MirrorConflictPolicy::RemoteWins,
+            "local-wins" => MirrorConflictPolicy::LocalWins,
+            other => {
+                let err = format!("Invalid conflict policy {other:?}");
+                config.new_parse_error(("calendar.mirror", id, "conflict"), err);
+                return None;
+            }
+        };
+
+        Some(CalendarMirror {
+            id: id.to_string(),
+            account,
+            calendar,
+            url,
+            headers: parse_http_headers(config, ("calendar.mirror", id)),
+            conflict_policy,
+            interval: config
+                .property_or_default(("calendar.mirror", id, "interval"), "15m")
+                .unwrap_or_else(|| Duration::from_secs(15 * 60)),
+            timeout: config
+                .property_or_default(("calendar.mirror", id, "timeout"), "30s")
+                .unwrap_or_else(|| Duration::from_secs(30)),
+            tls_allow_invalid_certs: config
+                .property_or_default(("calendar.mirror", id, "allow-invalid-certs"), "false")
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl ApnsConfig {
+    fn parse(config: &mut Config) -> Option<Self> {
+        if !config
+            .property_or_default("dav.push.apns.enable", "false")
+            .unwrap_or(false)
+        {
+            return None;
+        }
+
+        let calendar_topic = config
+            .value("dav.push.apns.topic.calendar")
+            .map(|topic| topic.trim().to_string())
+            .filter(|topic| !topic.is_empty());
+        let contacts_topic = config
+            .value("dav.push.apns.topic.contacts")
+            .map(|topic| topic.trim().to_string())
+            .filter(|topic| !topic.is_empty());
+        if calendar_topic.is_none() && contacts_topic.is_none() {
+            config.new_missing_property("dav.push.apns.topic.calendar");
+            return None;
+        }
+
+        // The client certificate and its private key are presented together
+        let mut identity = config
+            .value_require_non_empty("dav.push.apns.certificate")?
+            .as_bytes()
+            .to_vec();
+        identity.push(b'\n');
+        identity.extend_from_slice(
+            config
+                .value_require_non_empty("dav.push.apns.private-key")?
+                .as_bytes(),
+        );
+
+        let environment = match config
+            .value("dav.push.apns.environment")
+            .unwrap_or("production")
+        {
+            "production" => ApnsEnvironment::Production,
+            "sandbox" => ApnsEnvironment::Sandbox,
+            value => {
+                let value = value.to_string();
+                config.new_parse_error(
+                    "dav.push.apns.environment",
+                    format!("Invalid APNs environment {value:?}"),
+                );
+                return None;
+            }
+        };
+
+        Some(ApnsConfig {
+            url: config
+                .value("dav.push.apns.url")
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_else(|| match environment {
+                    ApnsEnvironment::Production => "https://api.push.apple.com".to_string(),
+                    ApnsEnvironment::Sandbox => "https://api.sandbox.push.apple.com".to_string(),
+                }),
+            environment,
+            calendar_topic,
+            contacts_topic,
+            identity,
+            refresh_interval: config
+                .property_or_default("dav.push.apns.refresh-interval", "2d")
+                .unwrap_or_else(|| Duration::from_secs(2 * 86400)),
+            timeout: config
+                .property_or_default("dav.push.apns.timeout", "10s")
+                .unwrap_or_else(|| Duration::from_secs(10)),
+        })
+    }
+}
+
+impl WebPushConfig {
+    fn parse(config: &mut Config) -> Option<Self> {
+        if !config
+            .property_or_default("dav.push.web.enable", "true")
+            .unwrap_or(true)
+        {
+            return None;
+        }
+
+        Some(WebPushConfig {
+            max_expiry: config
+                .property_or_default("dav.push.web.max-expiry", "7d")
+                .unwrap_or_else(|| Duration::from_secs(7 * 86400)),
+            max_subscriptions: config
+                .property("dav.push.web.max-subscriptions")
+                .unwrap_or(15),
+            timeout: config
+                .property_or_default("dav.push.web.timeout", "10s")
+                .unwrap_or_else(|| Duration::from_secs(10)),
+        })
+    }
+}
+
+impl IcapServer {
+    fn parse_all(config: &mut Config) -> (Option<Self>, AHashMap<u32, Self>) {
+        let mut default = None;
+        let mut tenants = AHashMap::new();
+
+        for id in config
+            .sub_keys("dav.icap", "")
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+        {
+            let Some(server) = IcapServer::parse(config, &id) else {
+                continue;
+            };
+            let tenant_ids = config
+                .properties::<u32>(("dav.icap", id.as_str(), "tenants"))
+                .into_iter()
+                .map(|(_, tenant_id)| tenant_id)
+                .collect::<Vec<_>>();
+            if tenant_ids.is_empty() {
+                if default.is_some() {
+                    config.new_build_error(
+                        ("dav.icap", id.as_str()),
+                        "Only one ICAP server can be used for all tenants",
+                    );
+                } else {
+                    default = Some(server);
+                }
+            } else {
+                tenants.extend(
+                    tenant_ids
+                        .into_iter()
+                        .map(|tenant_id| (tenant_id, server.clone())),
+                );
+            }
+        }
+
+        (default, tenants)
+    }
+
+    fn parse(config: &mut Config, id: &str) -> Option<Self> {
+        if !config
+            .property_or_default(("dav.icap", id, "enable"), "true")
+            .unwrap_or(true)
+        {
+            return None;
+        }
+
+        let url = config.value_require_non_empty(("dav.icap", id, "url"))?;
+        let Some((host, port, service)) = url.strip_prefix("icap://").and_then(|url| {
+            let (authority, service) = url.split_once('/').unwrap_or((url, ""));
+            let (host, port) = match authority.rsplit_once(':') {
+                Some((host, port)) => (host, port.parse::<u16>().ok()?),
+                None => (authority, 1344),
+            };
+            (!host.is_empty()).then(|| (host.to_string(), port, service.to_string()))
+        }) else {
+            let url = url.to_string();
+            config.new_parse_error(("dav.icap", id, "url"), format!("Invalid ICAP URL {url:?}"));
+            return None;
+        };
+
+        let mut scan_files = false;
+        let mut scan_calendars = false;
+        let mut scan_contacts = false;
+        let scan = config
+            .values(("dav.icap", id, "scan"))
+            .map(|(_, value)| value.to_string())
+            .collect::<Vec<_>>();
+        for value in &scan {
+            match value.as_str() {
+                "files" => scan_files = true,
+                "calendars" => scan_calendars = true,
+                "contacts" => scan_contacts = true,
+                _ => {
+                    config.new_parse_error(
+                        ("dav.icap", id, "scan"),
+                        format!("Invalid resource type {value:?}"),
+                    );
+                }
+            }
+        }
+        if scan.is_empty() {
+            scan_files = true;
+        }
+
+        Some(IcapServer {
+            id: id.to_string(),
+            host,
+            port,
+            service,
+            timeout: config
+                .property_or_default(("dav.icap", id, "timeout"), "30s")
+                .unwrap_or_else(|| Duration::from_secs(30)),
+            failure_policy: match config
+                .value(("dav.icap", id, "failure-policy"))
+                .unwrap_or("reject")
+            {
+                "allow" => IcapFailurePolicy::Allow,
+                "reject" => IcapFailurePolicy::Reject,
+                value => {
+                    let value = value.to_string();
+                    config.new_parse_error(
+                        ("dav.icap", id, "failure-policy"),
+                        format!("Invalid failure policy {value:?}"),
+                    );
+                    IcapFailurePolicy::Reject
+                }
+            },
+            max_size: config
+                .property(("dav.icap", id, "max-size"))
+                .unwrap_or(25 * 1024 * 1024),
+            scan_files,
+            scan_calendars,
+            scan_contacts,
+        })
+    }
+}
+
+impl FileMount {
+    fn parse_all(config: &mut Config) -> Vec<Self> {
+        let mut mounts = Vec::new();
+        for id in config
+            .sub_keys("file-storage.mount", "")
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+        {
+            if let Some(mount) = FileMount::parse(config, &id) {
+                mounts.push(mount);
+            }
+        }
+        mounts
+    }
+
+    fn parse(config: &mut Config, id: &str) -> Option<Self> {
+        if !config
+            .property_or_default(("file-storage.mount", id, "enable"), "true")
+            .unwrap_or(true)
+        {
+            return None;
+        }
+
+        let account = config
+            .value_require_non_empty(("file-storage.mount", id, "account"))?
+            .to_string();
+        let path = config
+            .value_require_non_empty(("file-storage.mount", id, "path"))?
+            .trim_matches('/')
+            .to_string();
+        let root =
+            PathBuf::from(config.value_require_non_empty(("file-storage.mount", id, "root"))?);
+        // Resolved paths are confined to the canonical root
+        let root = match root.canonicalize() {
+            Ok(root) if root.is_dir() => root,
+            _ => {
+                config.new_build_error(
+                    ("file-storage.mount", id, "root"),
+                    format!("Directory {} does not exist", root.display()),
+                );
+                return None;
+            }
+        };
+
+        Some(FileMount {
+            id: id.to_string(),
+            account,
+            account_id: OnceLock::new(),
+            path,
+            root,
+            read_only: config
+                .property_or_default(("file-storage.mount", id, "read-only"), "false")
+                .unwrap_or(false),
+        })
+    }
+}
+
+impl DirectoryAddressBook {
+    fn parse_all(config: &mut Config) -> Vec<Self> {
+        let mut books = Vec::new();
+        for id in config
+            .sub_keys("contacts.directory-sync", "")
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+        {
+            if let Some(book) = DirectoryAddressBook::parse(config, &id) {
+                books.push(book);
+            }
+        }
+        books
+    }
+
+    fn parse(config: &mut Config, id: &str) -> Option<Self> {
+        if !config
+            .property_or_default(("contacts.directory-sync", id, "enable"), "true")
+            .unwrap_or(true)
+        {
+            return None;
+        }
+
+        let account = config
+            .value_require_non_empty(("contacts.directory-sync", id, "account"))?
+            .to_string();
+        let members = config
+            .values(("contacts.directory-sync", id, "members"))
+            .map(|(_, value)| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<_>>();
+        let members_query = config
+            .value(("contacts.directory-sync", id, "query"))
+            .map(|query| query.to_string())
+            .map(|query| MembersQuery {
+                store: config
+                    .value(("contacts.directory-sync", id, "store"))
+                    .unwrap_or_default()
+                    .to_string(),
+                query,
+            });
+        if members.is_empty() && members_query.is_none() {
+            config.new_missing_property(("contacts.directory-sync", id, "members"));
+            return None;
         }
+
+        Some(DirectoryAddressBook {
+            id: id.to_string(),
+            account,
+            name: config
+                .value(("contacts.directory-sync", id, "href-name"))
+                .unwrap_or(id)
+                .to_string(),
+            display_name: config
+                .value(("contacts.directory-sync", id, "display-name"))
+                .map(|name| name.to_string()),
+            directory: config
+                .value(("contacts.directory-sync", id, "directory"))
+                .unwrap_or_default()
+                .to_string(),
+            members,
+            members_query,
+            share_with: config
+                .values(("contacts.directory-sync", id, "share-with"))
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .collect(),
+            refresh: config
+                .property_or_default(("contacts.directory-sync", id, "refresh"), "1h")
+                .unwrap_or_else(|| Duration::from_secs(3600)),
+        })
+    }
+}
+
+impl ItemLimits {
+    fn parse_overrides(
+        config: &mut Config,
+        defaults: ItemLimits,
+    ) -> (AHashMap<u32, ItemLimits>, AHashMap<u32, ItemLimits>) {
+        let mut tenants = AHashMap::new();
+        let mut accounts = AHashMap::new();
+
+        for id in config
+            .sub_keys("dav.item-limits", "")
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+        {
+            let limits = ItemLimits {
+                events_per_calendar: config
+                    .property(("dav.item-limits", id.as_str(), "max-events"))
+                    .or(defaults.events_per_calendar),
+                cards_per_addressbook: config
+                    .property(("dav.item-limits", id.as_str(), "max-cards"))
+                    .or(defaults.cards_per_addressbook),
+                children_per_folder: config
+                    .property(("dav.item-limits", id.as_str(), "max-children"))
+                    .or(defaults.children_per_folder),
+            };
+            let tenant_ids = config
+                .properties::<u32>(("dav.item-limits", id.as_str(), "tenants"))
+                .into_iter()
+                .map(|(_, tenant_id)| tenant_id)
+                .collect::<Vec<_>>();
+            let account_ids = config
+                .properties::<u32>(("dav.item-limits", id.as_str(), "accounts"))
+                .into_iter()
+                .map(|(_, account_id)| account_id)
+                .collect::<Vec<_>>();
+            if tenant_ids.is_empty() && account_ids.is_empty() {
+                config.new_missing_property(("dav.item-limits", id.as_str(), "tenants"));
+                continue;
+            }
+
+            tenants.extend(tenant_ids.into_iter().map(|tenant_id| (tenant_id, limits)));
+            accounts.extend(
+                account_ids
+                    .into_iter()
+                    .map(|account_id| (account_id, limits)),
+            );
+        }
+
+        (tenants, accounts)
+    }
+}
+
+impl ProvisioningTemplate {
+    fn parse_overrides(
+        config: &mut Config,
+        defaults: &ProvisioningTemplate,
+    ) -> AHashMap<u32, ProvisioningTemplate> {
+        let mut tenants = AHashMap::new();
+
+        for id in config
+            .sub_keys("groupware.provisioning.template", "")
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+        {
+            let tenant_ids = config
+                .properties::<u32>(("groupware.provisioning.template", id.as_str(), "tenants"))
+                .into_iter()
+                .map(|(_, tenant_id)| tenant_id)
+                .collect::<Vec<_>>();
+            if tenant_ids.is_empty() {
+                config.new_missing_property((
+                    "groupware.provisioning.template",
+                    id.as_str(),
+                    "tenants",
+                ));
+                continue;
+            }
+
+            let folders = config
+                .values(("groupware.provisioning.template", id.as_str(), "folders"))
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .collect::<Vec<_>>();
+            let template = ProvisioningTemplate {
+                calendar_name: config
+                    .value((
+                        "groupware.provisioning.template",
+                        id.as_str(),
+                        "calendar.href-name",
+                    ))
+                    .map(|name| name.to_string())
+                    .or_else(|| defaults.calendar_name.clone()),
+                calendar_display_name: config
+                    .value((
+                        "groupware.provisioning.template",
+                        id.as_str(),
+                        "calendar.display-name",
+                    ))
+                    .map(|name| name.to_string())
+                    .or_else(|| defaults.calendar_display_name.clone()),
+                addressbook_name: config
+                    .value((
+                        "groupware.provisioning.template",
+                        id.as_str(),
+                        "contacts.href-name",
+                    ))
+                    .map(|name| name.to_string())
+                    .or_else(|| defaults.addressbook_name.clone()),
+                addressbook_display_name: config
+                    .value((
+                        "groupware.provisioning.template",
+                        id.as_str(),
+                        "contacts.display-name",
+                    ))
+                    .map(|name| name.to_string())
+                    .or_else(|| defaults.addressbook_display_name.clone()),
+                folders: if folders.is_empty() {
+                    defaults.folders.clone()
+                } else {
+                    folders
+                },
+            };
+
+            tenants.extend(
+                tenant_ids
+                    .into_iter()
+                    .map(|tenant_id| (tenant_id, template.clone())),
+            );
+        }
+
+        tenants
     }
 }
@@ -6,8 +6,8 @@
 
 use super::server::tls::{build_self_signed_cert, parse_certificates};
 use crate::{
-    CacheSwap, Caches, Data, DavResource, DavResources, MailboxCache, MessageStoreCache,
-    MessageUidCache, TlsConnectors,
+    CacheSwap, Caches, Data, DavResource, DavResources, EventExpansion, EventExpansionKey,
+    MailboxCache, MessageStoreCache, MessageUidCache, TlsConnectors,
     auth::{AccessToken, roles::RolePermissions},
     config::smtp::resolver::{Policy, Tlsa},
     listener::blocked::BlockedIps,
@@ -15,6 +15,7 @@ use crate::{
 };
 use ahash::{AHashMap, AHashSet};
 use arc_swap::ArcSwap;
+use calcard::icalendar::dates::CalendarEvent;
 use mail_auth::{MX, Parameters, Txt};
 use mail_send::smtp::tls::build_tls_connector;
 use nlp::bayes::{TokenHash, Weights};
@@ -69,6 +70,7 @@ impl Data {
                 .map(|path| WebAdminManager::new(path.into()))
                 .unwrap_or_default(),
             logos: Default::default(),
+            dav_captures: Default::default(),
             smtp_connectors: TlsConnectors::default(),
             asn_geo_data: Default::default(),
         }
@@ -124,6 +126,15 @@ impl Caches {
                 (std::mem::size_of::<DavResources>() + (500 * std::mem::size_of::<DavResource>()))
                     as u64,
             ),
+            event_expansions: Cache::from_config(
+                config,
+                "event-expansions",
+                MB_10,
+                (std::mem::size_of::<EventExpansionKey>()
+                    + std::mem::size_of::<EventExpansion>()
+                    + (100 * std::mem::size_of::<CalendarEvent<i64, i64>>()))
+                    as u64,
+            ),
             contacts: Cache::from_config(
                 config,
                 "contacts",
@@ -225,6 +236,7 @@ impl Default for Data {
             queue_status: true.into(),
             webadmin: Default::default(),
             logos: Default::default(),
+            dav_captures: Default::default(),
             smtp_connectors: Default::default(),
             asn_geo_data: Default::default(),
         }
@@ -45,6 +45,8 @@ pub struct ClusterRoles {
     pub renew_acme: bool,
     pub calculate_metrics: bool,
     pub push_metrics: bool,
+    pub sync_addressbooks: bool,
+    pub mirror_calendars: bool,
 }
 
 #[derive(Clone, Default)]
@@ -110,6 +112,8 @@ impl Default for Network {
                 renew_acme: true,
                 calculate_metrics: true,
                 push_metrics: true,
+                sync_addressbooks: true,
+                mirror_calendars: true,
             },
         }
     }
@@ -228,6 +232,14 @@ impl Network {
                 &mut network.roles.push_metrics,
                 "cluster.roles.metrics.push",
             ),
+            (
+                &mut network.roles.sync_addressbooks,
+                "cluster.roles.contacts.sync",
+            ),
+            (
+                &mut network.roles.mirror_calendars,
+                "cluster.roles.calendar.mirror",
+            ),
         ] {
             let node_ids = config
                 .properties::<u64>(key)
@@ -22,7 +22,7 @@ use jmap_proto::types::{
     type_state::DataType,
 };
 use sieve::Sieve;
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 use store::{
     BitmapKey, BlobClass, BlobStore, Deserialize, FtsStore, InMemoryStore, IndexKey, IterateParams,
     Key, LogKey, SUBSPACE_LOGS, SerializeInfallible, Store, U32_LEN, U64_LEN, ValueKey,
@@ -612,6 +612,17 @@ impl Server {
                         .caused_by(trc::location!())?;
                 }
 
+                // Delete per-container changes
+                if matches!(
+                    sync_collection,
+                    SyncCollection::Calendar | SyncCollection::AddressBook
+                ) {
+                    self.store()
+                        .delete_shard_changes(account_id, collection, first_change_id)
+                        .await
+                        .caused_by(trc::location!())?;
+                }
+
                 // Write truncation entry for cache
                 let mut batch = BatchBuilder::new();
                 batch
@@ -676,15 +687,49 @@ impl Server {
         }
     }
 
-    #[allow(clippy::blocks_in_conditions)]
     pub async fn put_blob(
         &self,
         account_id: u32,
         data: &[u8],
         set_quota: bool,
+    ) -> trc::Result<BlobId> {
+        self.put_blob_with_hash(account_id, data, BlobHash::generate(data), set_quota)
+            .await
+    }
+
+    pub async fn put_blob_with_hash(
+        &self,
+        account_id: u32,
+        data: &[u8],
+        hash: BlobHash,
+        set_quota: bool,
+    ) -> trc::Result<BlobId> {
+        self.put_blob_source(account_id, BlobSource::Bytes(data), hash, set_quota)
+            .await
+    }
+
+    /// Stores a blob that was spooled to a file and hashed while writing it.
+    pub async fn put_blob_file_with_hash(
+        &self,
+        account_id: u32,
+        path: &Path,
+        size: usize,
+        hash: BlobHash,
+        set_quota: bool,
+    ) -> trc::Result<BlobId> {
+        self.put_blob_source(account_id, BlobSource::File { path, size }, hash, set_quota)
+            .await
+    }
+
+    #[allow(clippy::blocks_in_conditions)]
+    async fn put_blob_source(
+        &self,
+        account_id: u32,
+        source: BlobSource<'_>,
+        hash: BlobHash,
+        set_quota: bool,
     ) -> trc::Result<BlobId> {
         // First reserve the hash
-        let hash = BlobHash::generate(data);
         let mut batch = BatchBuilder::new();
         let until = now() + self.core.jmap.upload_tmp_ttl;
 
@@ -693,7 +738,7 @@ impl Server {
                 hash: hash.clone(),
                 until,
             },
-            (if set_quota { data.len() as u32 } else { 0u32 }).serialize(),
+            (if set_quota { source.len() as u32 } else { 0u32 }).serialize(),
         );
         self.core
             .storage
@@ -711,12 +756,14 @@ impl Server {
             .caused_by(trc::location!())?
         {
             // Upload blob to store
-            self.core
-                .storage
-                .blob
-                .put_blob(hash.as_ref(), data)
-                .await
-                .caused_by(trc::location!())?;
+            let blob_store = &self.core.storage.blob;
+            match source {
+                BlobSource::Bytes(data) => blob_store.put_blob(hash.as_ref(), data).await,
+                BlobSource::File { path, size } => {
+                    blob_store.put_blob_file(hash.as_ref(), path, size).await
+                }
+            }
+            .caused_by(trc::location!())?;
 
             // Commit blob
             let mut batch = BatchBuilder::new();
@@ -766,3 +813,17 @@ impl BuildServer for Arc<Inner> {
         }
     }
 }
+
+enum BlobSource<'x> {
+    Bytes(&'x [u8]),
+    File { path: &'x Path, size: usize },
+}
+
+impl BlobSource<'_> {
+    fn len(&self) -> usize {
+        match self {
+            BlobSource::Bytes(data) => data.len(),
+            BlobSource::File { size, .. } => *size,
+        }
+    }
+}
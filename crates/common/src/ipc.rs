@@ -71,6 +71,7 @@ pub enum BroadcastEvent {
     StateChange(StateChange),
     ReloadSettings,
     ReloadBlockedIps,
+    ReloadGroupware,
 }
 
 #[derive(Debug)]
@@ -9,7 +9,7 @@
 use ahash::{AHashMap, AHashSet};
 use arc_swap::ArcSwap;
 use auth::{AccessToken, oauth::config::OAuthConfig, roles::RolePermissions};
-use calcard::common::timezone::Tz;
+use calcard::{common::timezone::Tz, icalendar::dates::CalendarEvent};
 use config::{
     groupware::GroupwareConfig,
     imap::ImapConfig,
@@ -32,12 +32,14 @@ use manager::webadmin::{Resource, WebAdminManager};
 use nlp::bayes::{TokenHash, Weights};
 use parking_lot::{Mutex, RwLock};
 use rustls::sign::CertifiedKey;
+use store::roaring::RoaringBitmap;
 use std::{
     hash::{BuildHasher, Hash, Hasher},
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
     sync::{Arc, atomic::AtomicBool},
     time::Duration,
 };
+use telemetry::capture::DavCapture;
 use tinyvec::TinyVec;
 use tokio::sync::{Notify, Semaphore, mpsc};
 use tokio_rustls::TlsConnector;
@@ -105,9 +107,17 @@ pub const KV_LOCK_EMAIL_TASK: u8 = 23;
 pub const KV_LOCK_HOUSEKEEPER: u8 = 24;
 pub const KV_LOCK_DAV: u8 = 25;
 pub const KV_SIEVE_ID: u8 = 26;
+pub const KV_CALENDAR_MIRROR: u8 = 27;
+pub const KV_APNS_DEVICES: u8 = 28;
+pub const KV_DAV_PUSH: u8 = 29;
 
 pub const IDX_UID: u8 = 0;
 pub const IDX_EMAIL: u8 = 1;
+pub const IDX_CARD_TEXT: u8 = 2;
+pub const IDX_CARD_FN: u8 = 3;
+pub const IDX_CARD_EMAIL: u8 = 4;
+pub const IDX_CARD_TEL: u8 = 5;
+pub const IDX_CARD_ORG: u8 = 6;
 
 #[derive(Clone)]
 pub struct Server {
@@ -137,6 +147,7 @@ pub struct Data {
 
     pub webadmin: WebAdminManager,
     pub logos: Mutex<AHashMap<String, Option<Resource<Vec<u8>>>>>,
+    pub dav_captures: RwLock<AHashMap<u32, DavCapture>>,
 
     pub smtp_connectors: TlsConnectors,
 }
@@ -150,6 +161,7 @@ pub struct Caches {
     pub files: Cache<u32, CacheSwap<DavResources>>,
     pub contacts: Cache<u32, CacheSwap<DavResources>>,
     pub events: Cache<u32, CacheSwap<DavResources>>,
+    pub event_expansions: Cache<EventExpansionKey, Arc<EventExpansion>>,
 
     pub bayes: CacheWithTtl<TokenHash, Weights>,
 
@@ -166,6 +178,21 @@ pub struct Caches {
 #[derive(Debug, Clone)]
 pub struct CacheSwap<T>(pub Arc<ArcSwap<T>>);
 
+// Events are keyed by the hash of their archived version, so updated
+// events are never served a stale expansion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventExpansionKey {
+    pub account_id: u32,
+    pub document_id: u32,
+    pub hash: u32,
+    pub default_tz: u16,
+}
+
+#[derive(Debug)]
+pub struct EventExpansion {
+    pub events: Vec<CalendarEvent<i64, i64>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct MessageStoreCache {
     pub emails: Arc<MessagesCache>,
@@ -251,8 +278,21 @@ pub struct DavResources {
     pub item_change_id: u64,
     pub container_change_id: u64,
     pub highest_change_id: u64,
+    pub sync_epoch: u64,
     pub size: u64,
     pub update_lock: Arc<Semaphore>,
+    pub acl_cache: DavAclCache,
+}
+
+// Shared container ids keyed by (principal id, token revision, acls, match any).
+// Entries are only valid for the resources they were computed from, so clones start empty.
+#[derive(Debug, Default)]
+pub struct DavAclCache(pub Mutex<AHashMap<(u32, u64, u64, bool), Arc<RoaringBitmap>>>);
+
+impl Clone for DavAclCache {
+    fn clone(&self) -> Self {
+        DavAclCache::default()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -275,6 +315,20 @@ pub struct DavResourcePath<'x> {
     pub resource: &'x DavResource,
 }
 
+#[derive(Debug, Default)]
+pub struct DavResourceUsage<'x> {
+    pub bytes: u64,
+    pub items: u64,
+    pub largest: Vec<DavResourcePath<'x>>,
+}
+
+/// Scheduling collections of a calendar home (RFC 6638, section 2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleCollection {
+    Inbox,
+    Outbox,
+}
+
 #[derive(Debug, Clone)]
 pub enum DavResourceMetadata {
     File {
@@ -282,23 +336,31 @@ pub enum DavResourceMetadata {
         size: Option<u32>,
         parent_id: Option<u32>,
         acls: TinyVec<[AclGrant; 2]>,
+        bindings: Vec<DavName>,
     },
     Calendar {
         name: String,
+        display_name: Option<String>,
         acls: TinyVec<[AclGrant; 2]>,
         tz: Tz,
+        schedule: Option<ScheduleCollection>,
+        is_birthdays: bool,
     },
     CalendarEvent {
         names: TinyVec<[DavName; 2]>,
         start: i64,
         duration: u32,
+        size: u32,
+        time_buckets: Box<[i32]>,
     },
     AddressBook {
         name: String,
+        display_name: Option<String>,
         acls: TinyVec<[AclGrant; 2]>,
     },
     ContactCard {
         names: TinyVec<[DavName; 2]>,
+        size: u32,
     },
 }
 
@@ -352,6 +414,19 @@ impl CacheItemWeight for DavResources {
     }
 }
 
+impl CacheItemWeight for EventExpansionKey {
+    fn weight(&self) -> u64 {
+        std::mem::size_of::<EventExpansionKey>() as u64
+    }
+}
+
+impl CacheItemWeight for EventExpansion {
+    fn weight(&self) -> u64 {
+        (std::mem::size_of::<EventExpansion>()
+            + (self.events.len() * std::mem::size_of::<CalendarEvent<i64, i64>>())) as u64
+    }
+}
+
 pub trait IntoString: Sized {
     fn into_string(self) -> String;
 }
@@ -454,6 +529,7 @@ impl Default for Caches {
             files: Cache::new(1024, 10 * 1024 * 1024),
             contacts: Cache::new(1024, 10 * 1024 * 1024),
             events: Cache::new(1024, 10 * 1024 * 1024),
+            event_expansions: Cache::new(1024, 10 * 1024 * 1024),
             bayes: CacheWithTtl::new(1024, 10 * 1024 * 1024),
             dns_rbl: CacheWithTtl::new(1024, 10 * 1024 * 1024),
             dns_txt: CacheWithTtl::new(1024, 10 * 1024 * 1024),
@@ -527,6 +603,16 @@ impl DavResourcePath<'_> {
         self.resource.is_container()
     }
 
+    #[inline(always)]
+    pub fn schedule_collection(&self) -> Option<ScheduleCollection> {
+        self.resource.schedule_collection()
+    }
+
+    #[inline(always)]
+    pub fn is_birthday_calendar(&self) -> bool {
+        self.resource.is_birthday_calendar()
+    }
+
     #[inline(always)]
     pub fn hierarchy_seq(&self) -> u32 {
         self.path.hierarchy_seq
@@ -552,6 +638,14 @@ impl DavResources {
             .find(|res| res.document_id == id && res.is_container())
     }
 
+    pub fn container_by_id(&self, id: u32) -> Option<DavResourcePath<'_>> {
+        self.paths.iter().find_map(|path| {
+            let resource = &self.resources[path.resource_idx];
+            (resource.document_id == id && resource.is_container())
+                .then_some(DavResourcePath { path, resource })
+        })
+    }
+
     pub fn subtree(&self, search_path: &str) -> impl Iterator<Item = DavResourcePath<'_>> {
         let prefix = format!("{search_path}/");
         self.paths.iter().filter_map(move |path| {
@@ -566,6 +660,26 @@ impl DavResources {
         })
     }
 
+    pub fn usage(
+        &self,
+        container: DavResourcePath<'_>,
+        max_largest: usize,
+    ) -> DavResourceUsage<'_> {
+        let mut usage = DavResourceUsage::default();
+        for item in self.subtree(container.path()) {
+            if !item.is_container() {
+                usage.bytes += item.size() as u64;
+                usage.items += 1;
+                usage.largest.push(item);
+            }
+        }
+        usage
+            .largest
+            .sort_unstable_by(|a, b| b.size().cmp(&a.size()).then_with(|| a.path().cmp(b.path())));
+        usage.largest.truncate(max_largest);
+        usage
+    }
+
     pub fn subtree_with_depth(
         &self,
         search_path: &str,
@@ -612,6 +726,26 @@ impl DavResources {
             })
     }
 
+    pub fn schedule_collection(
+        &self,
+        collection: ScheduleCollection,
+    ) -> Option<DavResourcePath<'_>> {
+        self.paths.iter().find_map(|path| {
+            let resource = &self.resources[path.resource_idx];
+            (resource.schedule_collection() == Some(collection))
+                .then_some(DavResourcePath { path, resource })
+        })
+    }
+
+    pub fn birthday_calendar(&self) -> Option<DavResourcePath<'_>> {
+        self.paths.iter().find_map(|path| {
+            let resource = &self.resources[path.resource_idx];
+            resource
+                .is_birthday_calendar()
+                .then_some(DavResourcePath { path, resource })
+        })
+    }
+
     pub fn format_resource(&self, resource: DavResourcePath<'_>) -> String {
         if resource.resource.is_container() {
             format!("{}{}/", self.base_path, resource.path.path)
@@ -632,11 +766,18 @@ impl DavResources {
 impl DavResource {
     pub fn is_child_of(&self, parent_id: u32) -> bool {
         match &self.data {
-            DavResourceMetadata::File { parent_id: id, .. } => id.is_some_and(|id| id == parent_id),
+            DavResourceMetadata::File {
+                parent_id: id,
+                bindings,
+                ..
+            } => {
+                id.is_some_and(|id| id == parent_id)
+                    || bindings.iter().any(|name| name.parent_id == parent_id + 1)
+            }
             DavResourceMetadata::CalendarEvent { names, .. } => {
                 names.iter().any(|name| name.parent_id == parent_id)
             }
-            DavResourceMetadata::ContactCard { names } => {
+            DavResourceMetadata::ContactCard { names, .. } => {
                 names.iter().any(|name| name.parent_id == parent_id)
             }
             _ => false,
@@ -646,7 +787,7 @@ impl DavResource {
     pub fn child_names(&self) -> Option<&[DavName]> {
         match &self.data {
             DavResourceMetadata::CalendarEvent { names, .. } => Some(names.as_slice()),
-            DavResourceMetadata::ContactCard { names } => Some(names.as_slice()),
+            DavResourceMetadata::ContactCard { names, .. } => Some(names.as_slice()),
             _ => None,
         }
     }
@@ -666,14 +807,16 @@ impl DavResource {
                 DavResourceMetadata::File {
                     name: a,
                     parent_id: c,
+                    bindings: e,
                     ..
                 },
                 DavResourceMetadata::File {
                     name: b,
                     parent_id: d,
+                    bindings: f,
                     ..
                 },
-            ) => a != b || c != d,
+            ) => a != b || c != d || e != f,
             (
                 DavResourceMetadata::Calendar { name: a, .. },
                 DavResourceMetadata::Calendar { name: b, .. },
@@ -703,6 +846,23 @@ impl DavResource {
         }
     }
 
+    // Sorted week long intervals covered by the instances of a recurring
+    // event, empty when only the start and duration are known.
+    pub fn event_time_buckets(&self) -> &[i32] {
+        match &self.data {
+            DavResourceMetadata::CalendarEvent { time_buckets, .. } => time_buckets,
+            _ => &[],
+        }
+    }
+
+    pub fn display_name(&self) -> Option<&str> {
+        match &self.data {
+            DavResourceMetadata::Calendar { display_name, .. }
+            | DavResourceMetadata::AddressBook { display_name, .. } => display_name.as_deref(),
+            _ => None,
+        }
+    }
+
     pub fn timezone(&self) -> Option<Tz> {
         match &self.data {
             DavResourceMetadata::Calendar { tz, .. } => Some(*tz),
@@ -710,6 +870,24 @@ impl DavResource {
         }
     }
 
+    pub fn schedule_collection(&self) -> Option<ScheduleCollection> {
+        match &self.data {
+            DavResourceMetadata::Calendar { schedule, .. } => *schedule,
+            _ => None,
+        }
+    }
+
+    // Birthday calendars are generated from the contacts of the account
+    pub fn is_birthday_calendar(&self) -> bool {
+        matches!(
+            &self.data,
+            DavResourceMetadata::Calendar {
+                is_birthdays: true,
+                ..
+            }
+        )
+    }
+
     pub fn is_container(&self) -> bool {
         match &self.data {
             DavResourceMetadata::File { size, .. } => size.is_none(),
@@ -721,6 +899,8 @@ impl DavResource {
     pub fn size(&self) -> u32 {
         match &self.data {
             DavResourceMetadata::File { size, .. } => size.unwrap_or_default(),
+            DavResourceMetadata::CalendarEvent { size, .. }
+            | DavResourceMetadata::ContactCard { size, .. } => *size,
             _ => 0,
         }
     }
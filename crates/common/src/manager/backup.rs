@@ -15,7 +15,7 @@ use std::{
 use ahash::{AHashMap, AHashSet};
 use jmap_proto::types::{collection::Collection, property::Property};
 use store::{
-    BitmapKey, Deserialize, IndexKey, IterateParams, LogKey, SUBSPACE_BITMAP_ID,
+    BitmapKey, Deserialize, IndexKey, IterateParams, LogKey, LogShardKey, SUBSPACE_BITMAP_ID,
     SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, SerializeInfallible, U32_LEN, U64_LEN, ValueKey,
     write::{
         AnyKey, BitmapClass, BitmapHash, BlobOp, DirectoryClass, InMemoryClass, QueueClass,
@@ -1055,7 +1055,14 @@ impl Core {
                             let collection = key.deserialize_u8(U32_LEN)?;
                             let key = key.range(U32_LEN + 1..usize::MAX)?.to_vec();
 
-                            if key.len() != U64_LEN {
+                            // Per-container logs and epochs are keyed by shard id and change id
+                            let key_len = if LogShardKey::is_shard_collection(collection) {
+                                U32_LEN + U64_LEN
+                            } else {
+                                U64_LEN
+                            };
+
+                            if key.len() != key_len {
                                 failed(&format!("Found invalid log entry {key:?} {value:?}"));
                             }
 
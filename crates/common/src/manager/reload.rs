@@ -12,6 +12,7 @@ use utils::config::Config;
 use crate::{
     Core, Server,
     config::{
+        groupware::GroupwareConfig,
         server::{Listeners, tls::parse_certificates},
         telemetry::Telemetry,
     },
@@ -67,6 +68,25 @@ impl Server {
         })
     }
 
+    /// Re-parses the DAV, calendar, contacts and file storage settings
+    /// without rebuilding the rest of the core.
+    pub async fn reload_groupware(&self) -> trc::Result<ReloadResult> {
+        let mut config = self.core.storage.config.build_config("").await?;
+        let groupware = GroupwareConfig::parse(&mut config);
+        if !config.errors.is_empty() {
+            return Ok(config.into());
+        }
+
+        let mut core = self.core.as_ref().clone();
+        core.groupware = groupware;
+
+        Ok(ReloadResult {
+            config,
+            new_core: core.into(),
+            tracers: None,
+        })
+    }
+
     pub async fn reload(&self) -> trc::Result<ReloadResult> {
         let mut config = self.core.storage.config.build_config("").await?;
 
@@ -13,7 +13,8 @@ use crate::Core;
 use ahash::AHashMap;
 use jmap_proto::types::{collection::Collection, property::Property};
 use store::{
-    BlobStore, Key, LogKey, SUBSPACE_LOGS, SerializeInfallible, Store, U32_LEN,
+    BlobStore, Key, LOG_SHARD_FLAG, LogKey, LogShardKey, SUBSPACE_LOGS, SerializeInfallible, Store,
+    U32_LEN,
     roaring::RoaringBitmap,
     write::{
         AnyClass, BatchBuilder, BitmapClass, BitmapHash, BlobOp, DirectoryClass, InMemoryClass,
@@ -341,22 +342,40 @@ async fn restore_file(store: Store, blob_store: BlobStore, path: &Path) {
                         }
                     }
                     Family::Log => {
-                        let change_id = key
-                            .as_slice()
-                            .deserialize_be_u64(0)
-                            .expect("Failed to deserialize change id");
-                        let change_ids = change_ids.entry(account_id).or_default();
-                        *change_ids = std::cmp::max(*change_ids, change_id);
+                        let key = if !LogShardKey::is_shard_collection(collection) {
+                            let change_id = key
+                                .as_slice()
+                                .deserialize_be_u64(0)
+                                .expect("Failed to deserialize change id");
+                            let change_ids = change_ids.entry(account_id).or_default();
+                            *change_ids = std::cmp::max(*change_ids, change_id);
+
+                            LogKey {
+                                account_id,
+                                collection,
+                                change_id,
+                            }
+                            .serialize(0)
+                        } else {
+                            LogShardKey {
+                                account_id,
+                                collection: collection & !LOG_SHARD_FLAG,
+                                shard_id: key
+                                    .as_slice()
+                                    .deserialize_be_u32(0)
+                                    .expect("Failed to deserialize shard id"),
+                                change_id: key
+                                    .as_slice()
+                                    .deserialize_be_u64(U32_LEN)
+                                    .expect("Failed to deserialize change id"),
+                            }
+                            .serialize(0)
+                        };
 
                         batch.any_op(Operation::Value {
                             class: ValueClass::Any(AnyClass {
                                 subspace: SUBSPACE_LOGS,
-                                key: LogKey {
-                                    account_id,
-                                    collection,
-                                    change_id,
-                                }
-                                .serialize(0),
+                                key,
                             }),
                             op: ValueOp::Set {
                                 value,
@@ -0,0 +1,19 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use sieve::FunctionMap;
+
+// Executed by the message ingestion pipeline, which has access to the groupware stores
+pub const PLUGIN_FILE_INTO_CALENDAR: u32 = 14;
+pub const PLUGIN_FILE_INTO_ADDRESSBOOK: u32 = 15;
+
+pub fn register_calendar(fnc_map: &mut FunctionMap) {
+    fnc_map.set_external_function("file_into_calendar", PLUGIN_FILE_INTO_CALENDAR, 1);
+}
+
+pub fn register_addressbook(fnc_map: &mut FunctionMap) {
+    fnc_map.set_external_function("file_into_addressbook", PLUGIN_FILE_INTO_ADDRESSBOOK, 1);
+}
@@ -6,6 +6,7 @@
 
 pub mod dns;
 pub mod exec;
+pub mod groupware;
 pub mod headers;
 pub mod http;
 pub mod llm_prompt;
@@ -67,6 +68,8 @@ impl RegisterSievePlugins for FunctionMap {
 
     fn register_plugins_untrusted(mut self) -> Self {
         llm_prompt::register(12, &mut self);
+        groupware::register_calendar(&mut self);
+        groupware::register_addressbook(&mut self);
         self
     }
 }
@@ -93,6 +96,9 @@ impl Core {
             10 => text::exec_tokenize(ctx),
             11 => text::exec_domain_part(ctx),
             12 => llm_prompt::exec(ctx).await,
+            groupware::PLUGIN_FILE_INTO_CALENDAR | groupware::PLUGIN_FILE_INTO_ADDRESSBOOK => {
+                Ok(false.into())
+            }
             _ => unreachable!(),
         };
 
@@ -6,9 +6,12 @@
 
 use crate::{DavResources, auth::AccessToken};
 use jmap_proto::types::acl::Acl;
+use std::sync::Arc;
 use store::roaring::RoaringBitmap;
 use utils::map::bitmap::Bitmap;
 
+const MAX_ACL_CACHE_ENTRIES: usize = 64;
+
 impl DavResources {
     pub fn shared_containers(
         &self,
@@ -17,6 +20,16 @@ impl DavResources {
         match_any: bool,
     ) -> RoaringBitmap {
         let check_acls = Bitmap::<Acl>::from_iter(check_acls);
+        let cache_key = (
+            access_token.primary_id,
+            access_token.revision,
+            check_acls.bitmap,
+            match_any,
+        );
+        if let Some(document_ids) = self.acl_cache.0.lock().get(&cache_key) {
+            return document_ids.as_ref().clone();
+        }
+
         let mut document_ids = RoaringBitmap::new();
 
         for resource in &self.resources {
@@ -33,6 +46,12 @@ impl DavResources {
             }
         }
 
+        let mut acl_cache = self.acl_cache.0.lock();
+        if acl_cache.len() >= MAX_ACL_CACHE_ENTRIES {
+            acl_cache.clear();
+        }
+        acl_cache.insert(cache_key, Arc::new(document_ids.clone()));
+
         document_ids
     }
 
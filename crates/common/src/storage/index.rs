@@ -51,6 +51,13 @@ pub enum IndexValue<'x> {
         sync_collection: u8,
         prefix: Option<u32>,
     },
+    LogItemShard {
+        sync_collection: u8,
+        ids: Vec<u32>,
+    },
+    LogContainerShard {
+        sync_collection: u8,
+    },
     Acl {
         value: Cow<'x, [AclGrant]>,
     },
@@ -319,6 +326,17 @@ impl<C: IndexableObject, N: IndexableAndSerializableObject> IntoOperations
                             } => {
                                 batch.log_item_update(sync_collection, prefix);
                             }
+                            IndexValue::LogItemShard {
+                                sync_collection,
+                                ids,
+                            } => {
+                                for shard_id in ids {
+                                    batch.log_item_shard_update(sync_collection, shard_id);
+                                }
+                            }
+                            IndexValue::LogContainerShard { sync_collection } => {
+                                batch.log_container_shard_update(sync_collection);
+                            }
                             _ => (),
                         }
                     }
@@ -427,6 +445,25 @@ fn build_index(batch: &mut BatchBuilder, item: IndexValue<'_>, tenant_id: Option
                 batch.log_container_property_change(sync_collection, parent_id);
             }
         }
+        IndexValue::LogItemShard {
+            sync_collection,
+            ids,
+        } => {
+            for shard_id in ids {
+                if set {
+                    batch.log_item_shard_insert(sync_collection, shard_id);
+                } else {
+                    batch.log_item_shard_delete(sync_collection, shard_id);
+                }
+            }
+        }
+        IndexValue::LogContainerShard { sync_collection } => {
+            if set {
+                batch.log_container_shard_update(sync_collection);
+            } else {
+                batch.log_container_shard_delete(sync_collection);
+            }
+        }
     }
 }
 
@@ -584,6 +621,26 @@ fn merge_index(
                 }
             }
         }
+        (
+            IndexValue::LogItemShard {
+                sync_collection,
+                ids: old_ids,
+            },
+            IndexValue::LogItemShard { ids: new_ids, .. },
+        ) => {
+            for shard_id in &old_ids {
+                if !new_ids.contains(shard_id) {
+                    batch.log_item_shard_delete(sync_collection, *shard_id);
+                }
+            }
+            for shard_id in new_ids {
+                if !old_ids.contains(&shard_id) {
+                    batch.log_item_shard_insert(sync_collection, shard_id);
+                } else {
+                    batch.log_item_shard_update(sync_collection, shard_id);
+                }
+            }
+        }
         _ => unreachable!(),
     }
 
@@ -0,0 +1,333 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use hyper::HeaderMap;
+use serde::Serialize;
+
+use crate::Server;
+
+const MAX_CAPTURED_REQUESTS: usize = 256;
+const REDACTED: &str = "[redacted]";
+
+// Headers are redacted unless they are known not to carry credentials
+const CAPTURED_HEADERS: [&str; 38] = [
+    "accept",
+    "accept-encoding",
+    "accept-language",
+    "allow",
+    "brief",
+    "cache-control",
+    "content-disposition",
+    "content-encoding",
+    "content-language",
+    "content-length",
+    "content-location",
+    "content-range",
+    "content-type",
+    "dav",
+    "date",
+    "depth",
+    "destination",
+    "etag",
+    "host",
+    "if",
+    "if-match",
+    "if-modified-since",
+    "if-none-match",
+    "if-schedule-tag-match",
+    "if-unmodified-since",
+    "last-modified",
+    "location",
+    "lock-token",
+    "overwrite",
+    "prefer",
+    "preference-applied",
+    "range",
+    "schedule-reply",
+    "schedule-tag",
+    "timeout",
+    "transfer-encoding",
+    "user-agent",
+    "vary",
+];
+
+// Values following these names are redacted from captured bodies
+const CREDENTIAL_NAMES: [&str; 7] = [
+    "password",
+    "passwd",
+    "secret",
+    "access_token",
+    "refresh_token",
+    "api_key",
+    "apikey",
+];
+
+pub struct DavCapture {
+    pub expires: Instant,
+    pub max_body_size: usize,
+    pub requests: VecDeque<CapturedRequest>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedRequest {
+    pub timestamp: u64,
+    pub method: String,
+    pub uri: String,
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: Option<String>,
+    pub status: u16,
+    pub response_headers: Vec<(String, String)>,
+    pub response_body: Option<String>,
+}
+
+impl Server {
+    pub fn start_dav_capture(&self, account_id: u32, duration: Duration, max_body_size: usize) {
+        self.inner.data.dav_captures.write().insert(
+            account_id,
+            DavCapture {
+                expires: Instant::now() + duration,
+                max_body_size,
+                requests: VecDeque::new(),
+            },
+        );
+    }
+
+    pub fn stop_dav_capture(&self, account_id: u32) -> Option<Vec<CapturedRequest>> {
+        self.inner
+            .data
+            .dav_captures
+            .write()
+            .remove(&account_id)
+            .map(|capture| capture.requests.into())
+    }
+
+    // Expired captures stop recording but remain available until stopped
+    pub fn dav_captured_requests(
+        &self,
+        account_id: u32,
+    ) -> Option<(Option<Duration>, Vec<CapturedRequest>)> {
+        self.inner
+            .data
+            .dav_captures
+            .read()
+            .get(&account_id)
+            .map(|capture| {
+                (
+                    capture.expires.checked_duration_since(Instant::now()),
+                    capture.requests.iter().cloned().collect(),
+                )
+            })
+    }
+
+    pub fn dav_capture_body_size(&self, account_id: u32) -> Option<usize> {
+        let captures = self.inner.data.dav_captures.read();
+        if !captures.is_empty() {
+            captures
+                .get(&account_id)
+                .filter(|capture| capture.expires > Instant::now())
+                .map(|capture| capture.max_body_size)
+        } else {
+            None
+        }
+    }
+
+    pub fn record_dav_capture(&self, account_id: u32, request: CapturedRequest) {
+        if let Some(capture) = self
+            .inner
+            .data
+            .dav_captures
+            .write()
+            .get_mut(&account_id)
+            .filter(|capture| capture.expires > Instant::now())
+        {
+            if capture.requests.len() >= MAX_CAPTURED_REQUESTS {
+                capture.requests.pop_front();
+            }
+            capture.requests.push_back(request);
+        }
+    }
+}
+
+impl CapturedRequest {
+    pub fn capture_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let name = name.as_str();
+                (
+                    name.to_string(),
+                    if CAPTURED_HEADERS.contains(&name) {
+                        String::from_utf8_lossy(value.as_bytes()).into_owned()
+                    } else {
+                        REDACTED.to_string()
+                    },
+                )
+            })
+            .collect()
+    }
+
+    pub fn capture_body(body: &[u8], max_size: usize) -> Option<String> {
+        if body.is_empty() {
+            None
+        } else if let Ok(text) = std::str::from_utf8(body) {
+            let text = redact_credentials(text);
+            if text.len() > max_size {
+                let mut end = max_size;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                Some(format!(
+                    "{}\n[truncated, {} bytes total]",
+                    &text[..end],
+                    body.len()
+                ))
+            } else {
+                Some(text.into_owned())
+            }
+        } else {
+            Some(format!("[binary, {} bytes]", body.len()))
+        }
+    }
+}
+
+// Replaces the values of credential-like fields in XML, JSON and form encoded bodies
+fn redact_credentials(text: &str) -> Cow<'_, str> {
+    let lower = text.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let mut redacted = String::new();
+    let mut last = 0;
+    let mut pos = 0;
+
+    while let Some((start, name)) = CREDENTIAL_NAMES
+        .iter()
+        .filter_map(|name| lower[pos..].find(name).map(|start| (pos + start, name)))
+        .min_by_key(|(start, _)| *start)
+    {
+        // Skip the rest of the field name and the separator
+        let mut value_start = start + name.len();
+        while value_start < bytes.len()
+            && (bytes[value_start].is_ascii_alphanumeric()
+                || matches!(bytes[value_start], b'_' | b'-'))
+        {
+            value_start += 1;
+        }
+        while value_start < bytes.len()
+            && matches!(
+                bytes[value_start],
+                b'"' | b'\'' | b':' | b'=' | b'>' | b' ' | b'\t'
+            )
+        {
+            value_start += 1;
+        }
+        let value_end = bytes[value_start..]
+            .iter()
+            .position(|ch| matches!(ch, b'"' | b'\'' | b'&' | b'<' | b',' | b'}' | b'\r' | b'\n'))
+            .map_or(bytes.len(), |end| value_start + end);
+
+        if value_end > value_start {
+            redacted.push_str(&text[last..value_start]);
+            redacted.push_str(REDACTED);
+            last = value_end;
+        }
+        pos = value_end.max(start + name.len());
+    }
+
+    if last > 0 {
+        redacted.push_str(&text[last..]);
+        Cow::Owned(redacted)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{HeaderMap, header::HeaderValue};
+
+    use super::CapturedRequest;
+
+    #[test]
+    fn capture_headers_deny_by_default() {
+        let mut headers = HeaderMap::new();
+        for (name, value) in [
+            ("authorization", "Basic am9objpzZWNyZXQ="),
+            ("cookie", "session=abc"),
+            ("x-auth-token", "abc"),
+            ("x-custom", "abc"),
+            ("depth", "1"),
+            ("content-type", "text/xml"),
+        ] {
+            headers.insert(name, HeaderValue::from_static(value));
+        }
+
+        let mut captured = CapturedRequest::capture_headers(&headers);
+        captured.sort();
+        assert_eq!(
+            captured,
+            [
+                ("authorization", "[redacted]"),
+                ("content-type", "text/xml"),
+                ("cookie", "[redacted]"),
+                ("depth", "1"),
+                ("x-auth-token", "[redacted]"),
+                ("x-custom", "[redacted]"),
+            ]
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+        );
+    }
+
+    #[test]
+    fn capture_body_redacts_credentials() {
+        for (body, expected) in [
+            (
+                "<A:password>hunter2</A:password><A:name>john</A:name>",
+                "<A:password>[redacted]</A:password><A:name>john</A:name>",
+            ),
+            (
+                r#"{"user": "john", "password": "hunter2", "apiKey": "abc"}"#,
+                r#"{"user": "john", "password": "[redacted]", "apiKey": "[redacted]"}"#,
+            ),
+            (
+                "user=john&passwd=hunter2&client_secret=abc",
+                "user=john&passwd=[redacted]&client_secret=[redacted]",
+            ),
+            (
+                "<D:sync-token>http://example.com/sync/1</D:sync-token>",
+                "<D:sync-token>http://example.com/sync/1</D:sync-token>",
+            ),
+        ] {
+            assert_eq!(
+                CapturedRequest::capture_body(body.as_bytes(), 1024).unwrap(),
+                expected,
+                "{body}"
+            );
+        }
+    }
+
+    #[test]
+    fn capture_body_size_cap() {
+        assert_eq!(CapturedRequest::capture_body(b"", 16), None);
+        assert_eq!(
+            CapturedRequest::capture_body(b"hello world", 16).unwrap(),
+            "hello world"
+        );
+        assert_eq!(
+            CapturedRequest::capture_body("0123456789ábcdef".as_bytes(), 11).unwrap(),
+            "0123456789\n[truncated, 17 bytes total]"
+        );
+        assert_eq!(
+            CapturedRequest::capture_body(&[0xff, 0xfe, 0x00], 16).unwrap(),
+            "[binary, 3 bytes]"
+        );
+    }
+}
@@ -6,6 +6,7 @@
 
 use calcard::vcard::VCardVersion;
 use compact_str::{CompactString, ToCompactString};
+use schema::property::CalendarDataType;
 use trc::Value;
 
 pub mod parser;
@@ -33,7 +34,7 @@ pub fn xml_pretty_print(xml_string: &str) -> String {
     String::from_utf8(result).unwrap()
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
 pub struct RequestHeaders<'x> {
     pub uri: &'x str,
     pub depth: Depth,
@@ -42,11 +43,18 @@ pub struct RequestHeaders<'x> {
     pub destination: Option<&'x str>,
     pub lock_token: Option<&'x str>,
     pub max_vcard_version: Option<VCardVersion>,
+    pub jcard: bool,
+    pub calendar_data_type: Option<CalendarDataType>,
     pub overwrite_fail: bool,
     pub no_timezones: bool,
     pub ret: Return,
     pub depth_no_root: bool,
+    pub default_alarms: bool,
     pub if_: Vec<If<'x>>,
+    pub if_none_match: Option<&'x str>,
+    pub range: Option<ByteRange>,
+    pub if_range: Option<&'x str>,
+    pub content_range: Option<ContentRange>,
 }
 
 pub struct ResourceState<T: AsRef<str>> {
@@ -96,6 +104,20 @@ pub enum Depth {
     None,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ByteRange {
+    Bounded { first: u64, last: u64 },
+    From(u64),
+    Suffix(u64),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ContentRange {
+    pub first: u64,
+    pub last: u64,
+    pub complete_length: Option<u64>,
+}
+
 impl From<&RequestHeaders<'_>> for Value {
     fn from(headers: &RequestHeaders<'_>) -> Self {
         let mut values = Vec::with_capacity(4);
@@ -130,6 +152,7 @@ impl From<&RequestHeaders<'_>> for Value {
             ("Overwrite", headers.overwrite_fail),
             ("No-Timezones", headers.no_timezones),
             ("Depth-No-Root", headers.depth_no_root),
+            ("Default-Alarms", headers.default_alarms),
         ] {
             if is_set {
                 values.push(CompactString::const_new(name).into());
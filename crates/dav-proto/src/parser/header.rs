@@ -6,7 +6,12 @@
 
 use calcard::vcard::VCardVersion;
 
-use crate::{Condition, Depth, If, RequestHeaders, ResourceState, Return, Timeout};
+use std::ops::Range;
+
+use crate::{
+    schema::property::CalendarDataType, ByteRange, Condition, ContentRange, Depth, If,
+    RequestHeaders, ResourceState, Return, Timeout,
+};
 
 impl<'x> RequestHeaders<'x> {
     pub fn new(uri: &'x str) -> Self {
@@ -44,6 +49,7 @@ impl<'x> RequestHeaders<'x> {
             },
             "If-None-Match" => {
                 let num = self.if_.len();
+                self.if_none_match = Some(value.trim());
                 self.parse_if_match(value, true);
                 return self.if_.len() != num;
             },
@@ -73,10 +79,27 @@ impl<'x> RequestHeaders<'x> {
                         "return=minimal" => self.ret = Return::Minimal,
                         "return=representation" => self.ret = Return::Representation,
                         "depth-noroot" => self.depth_no_root = true,
+                        "default-alarms" => self.default_alarms = true,
                         _ => {}
                     }
                 }
             },
+            "Range" => {
+                if let Some(range) = ByteRange::parse(value) {
+                    self.range = Some(range);
+                    return true;
+                }
+            },
+            "If-Range" => {
+                self.if_range = Some(value.trim());
+                return true;
+            },
+            "Content-Range" => {
+                if let Some(range) = ContentRange::parse(value) {
+                    self.content_range = Some(range);
+                    return true;
+                }
+            },
             "Content-Type" => {
                 let value = value.trim();
                 if (2..=127).contains(&value.len()) {
@@ -85,8 +108,17 @@ impl<'x> RequestHeaders<'x> {
                 return true;
             },
             "Accept" => {
+                let mut has_vcard = false;
                 for value in value.split(',') {
+                    if self.calendar_data_type.is_none() {
+                        self.calendar_data_type = CalendarDataType::try_parse(value);
+                    }
+                    // jCard is returned when it is listed before vCard
+                    if value.trim().starts_with("application/vcard+json") && !has_vcard {
+                        self.jcard = true;
+                    }
                     if value.trim().starts_with("text/vcard") {
+                        has_vcard = true;
                         if let Some(version) = value.split_once("version=")
                                                .and_then(|(_, version)| VCardVersion::try_parse(version.trim())) {
                             if let Some(max_vcard_version) = &mut self.max_vcard_version {
@@ -147,7 +179,7 @@ impl<'x> RequestHeaders<'x> {
                         }
                     }
                     Condition::ETag { is_not, tag } => {
-                        if !((current_etag == *tag) ^ is_not) {
+                        if !(etag_matches(current_etag, tag, !is_not) ^ is_not) {
                             continue 'outer;
                         }
                     }
@@ -170,9 +202,10 @@ impl<'x> RequestHeaders<'x> {
         let mut iter = value.iter().enumerate();
         let mut resource = None;
 
+        // A resource tag applies to all the lists that follow it
         while let Some((idx, ch)) = iter.next() {
             match ch {
-                b'<' if resource.is_none() => {
+                b'<' => {
                     for (to_idx, ch) in iter.by_ref() {
                         if *ch == b'>' {
                             resource = Some(std::str::from_utf8(&value[idx + 1..to_idx]).unwrap());
@@ -221,7 +254,7 @@ impl<'x> RequestHeaders<'x> {
                             }
                             b')' => {
                                 self.if_.push(If {
-                                    resource: resource.take(),
+                                    resource,
                                     list: conditions,
                                 });
                                 break;
@@ -244,33 +277,36 @@ impl<'x> RequestHeaders<'x> {
     }
 
     pub fn parse_if_match(&mut self, value: &'x str, is_not: bool) {
+        let value = value.trim();
+        let etags = value
+            .split(',')
+            .map(|etag| etag.trim())
+            .filter(|etag| !etag.is_empty());
+
         if value == "*" {
             self.if_.push(If {
                 resource: None,
                 list: vec![Condition::Exists { is_not }],
             });
         } else if !is_not {
-            for etag in value.split(',') {
+            // Any of the listed entity tags may match
+            for etag in etags {
                 self.if_.push(If {
                     resource: None,
-                    list: vec![Condition::ETag {
-                        is_not,
-                        tag: etag.trim(),
-                    }],
+                    list: vec![Condition::ETag { is_not, tag: etag }],
                 });
             }
         } else {
-            let mut etags = Vec::new();
-            for etag in value.split(',') {
-                etags.push(Condition::ETag {
-                    is_not,
-                    tag: etag.trim(),
+            // None of the listed entity tags may match
+            let etags = etags
+                .map(|etag| Condition::ETag { is_not, tag: etag })
+                .collect::<Vec<_>>();
+            if !etags.is_empty() {
+                self.if_.push(If {
+                    resource: None,
+                    list: etags,
                 });
             }
-            self.if_.push(If {
-                resource: None,
-                list: etags,
-            });
         }
     }
 
@@ -279,6 +315,21 @@ impl<'x> RequestHeaders<'x> {
     }
 }
 
+/// Compares an entity tag against a validator received in a conditional header.
+/// Strong comparison requires both tags to be strong, while weak comparison
+/// ignores the `W/` prefix (RFC 9110, section 8.8.3.2).
+pub fn etag_matches(etag: &str, tag: &str, strong: bool) -> bool {
+    let (etag, etag_is_weak) = split_weak_etag(etag);
+    let (tag, tag_is_weak) = split_weak_etag(tag);
+
+    etag == tag && !(strong && (etag_is_weak || tag_is_weak))
+}
+
+fn split_weak_etag(etag: &str) -> (&str, bool) {
+    etag.strip_prefix("W/")
+        .map_or((etag, false), |etag| (etag, true))
+}
+
 pub fn dav_base_uri(uri: &str) -> Option<&str> {
     // From a path ../dav/collection/account/..
     // returns ../dav/collection/account without the trailing slash
@@ -321,6 +372,68 @@ impl Depth {
     }
 }
 
+impl ByteRange {
+    // Only single byte ranges are supported, servers are free to ignore
+    // a Range header they do not understand (RFC 9110, section 14.2)
+    pub fn parse(value: &str) -> Option<Self> {
+        let (first, last) = value
+            .trim()
+            .strip_prefix("bytes=")?
+            .split_once('-')
+            .filter(|(_, last)| !last.contains(','))?;
+        let (first, last) = (first.trim(), last.trim());
+
+        if first.is_empty() {
+            last.parse()
+                .ok()
+                .filter(|len| *len > 0)
+                .map(ByteRange::Suffix)
+        } else if last.is_empty() {
+            first.parse().ok().map(ByteRange::From)
+        } else {
+            let first = first.parse().ok()?;
+            let last = last.parse().ok()?;
+            (first <= last).then_some(ByteRange::Bounded { first, last })
+        }
+    }
+
+    /// Returns the byte offsets selected from a representation of the given
+    /// size, or `None` when the range cannot be satisfied.
+    pub fn resolve(&self, size: u64) -> Option<Range<u64>> {
+        match *self {
+            ByteRange::Bounded { first, last } if first < size => Some(first..(last + 1).min(size)),
+            ByteRange::From(first) if first < size => Some(first..size),
+            ByteRange::Suffix(len) if size > 0 => Some(size.saturating_sub(len)..size),
+            _ => None,
+        }
+    }
+}
+
+impl ContentRange {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (range, complete_length) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+        let (first, last) = range.split_once('-')?;
+        let first = first.trim().parse().ok()?;
+        let last = last.trim().parse().ok()?;
+        let complete_length = match complete_length.trim() {
+            "*" => None,
+            length => Some(length.parse().ok()?),
+        };
+
+        (first <= last && complete_length.is_none_or(|length| last < length)).then_some(
+            ContentRange {
+                first,
+                last,
+                complete_length,
+            },
+        )
+    }
+
+    pub fn len(&self) -> u64 {
+        self.last - self.first + 1
+    }
+}
+
 fn try_unwrap_coded_url(url: &str) -> &str {
     url.strip_prefix("<")
         .and_then(|url| url.strip_suffix(">"))
@@ -458,6 +571,63 @@ mod tests {
             state_token: "urn:other-token",
             etag: ""
         }]));
+
+        let mut headers = RequestHeaders::default();
+        assert!(headers.parse(
+            "If",
+            r#"</dav/file/john/source> (<urn:uuid:1234>) (["source ETag"])
+       </dav/file/john/destination> (Not ["destination ETag"])"#
+        ));
+        assert_eq!(
+            headers.if_,
+            vec![
+                If {
+                    resource: Some("/dav/file/john/source"),
+                    list: vec![Condition::StateToken {
+                        is_not: false,
+                        token: "urn:uuid:1234"
+                    }]
+                },
+                If {
+                    resource: Some("/dav/file/john/source"),
+                    list: vec![Condition::ETag {
+                        is_not: false,
+                        tag: "\"source ETag\""
+                    }]
+                },
+                If {
+                    resource: Some("/dav/file/john/destination"),
+                    list: vec![Condition::ETag {
+                        is_not: true,
+                        tag: "\"destination ETag\""
+                    }]
+                },
+            ]
+        );
+        assert!(headers.eval_if(&[
+            ResourceState {
+                resource: Some("/dav/file/john/source"),
+                state_token: "",
+                etag: "\"source ETag\""
+            },
+            ResourceState {
+                resource: Some("/dav/file/john/destination"),
+                state_token: "",
+                etag: "\"destination ETag\""
+            }
+        ]));
+        assert!(!headers.eval_if(&[
+            ResourceState {
+                resource: Some("/dav/file/john/source"),
+                state_token: "",
+                etag: "\"other ETag\""
+            },
+            ResourceState {
+                resource: Some("/dav/file/john/destination"),
+                state_token: "",
+                etag: "\"destination ETag\""
+            }
+        ]));
     }
 
     #[test]
@@ -472,6 +642,62 @@ mod tests {
         assert!(headers.parse("Lock-Token", "<urn:uuid:1234>"));
         assert_eq!(headers.lock_token, Some("urn:uuid:1234"));
 
+        assert!(headers.parse("Accept", "application/vcard+json, text/vcard"));
+        assert!(headers.jcard);
+        let mut vcard_headers = RequestHeaders::default();
+        assert!(vcard_headers.parse("Accept", "text/vcard;version=4.0, application/vcard+json"));
+        assert!(!vcard_headers.jcard);
+
+        for (input, expected) in [
+            (
+                "bytes=0-499",
+                Some(ByteRange::Bounded {
+                    first: 0,
+                    last: 499,
+                }),
+            ),
+            ("bytes=500-", Some(ByteRange::From(500))),
+            ("bytes=-500", Some(ByteRange::Suffix(500))),
+            ("bytes=500-499", None),
+            ("bytes=0-1,5-9", None),
+            ("items=0-1", None),
+        ] {
+            assert_eq!(ByteRange::parse(input), expected, "failed for {input}");
+        }
+        assert_eq!(
+            ByteRange::Bounded {
+                first: 10,
+                last: 999
+            }
+            .resolve(100),
+            Some(10..100)
+        );
+        assert_eq!(ByteRange::Suffix(500).resolve(100), Some(0..100));
+        assert_eq!(ByteRange::From(100).resolve(100), None);
+
+        for (input, expected) in [
+            (
+                "bytes 0-499/1234",
+                Some(ContentRange {
+                    first: 0,
+                    last: 499,
+                    complete_length: Some(1234),
+                }),
+            ),
+            (
+                "bytes 500-999/*",
+                Some(ContentRange {
+                    first: 500,
+                    last: 999,
+                    complete_length: None,
+                }),
+            ),
+            ("bytes */1234", None),
+            ("bytes 0-1234/1234", None),
+        ] {
+            assert_eq!(ContentRange::parse(input), expected, "failed for {input}");
+        }
+
         for (input, expected) in [
             (
                 "<urn:uuid:1234>(<urn:uuid:1234>)",
@@ -651,6 +877,7 @@ mod tests {
         headers.if_.clear();
 
         assert!(headers.parse("If-None-Match", "etag1, etag2"));
+        assert_eq!(headers.if_none_match, Some("etag1, etag2"));
         assert_eq!(
             headers.if_,
             vec![If {
@@ -667,6 +894,45 @@ mod tests {
                 ],
             },]
         );
+        headers.if_.clear();
+
+        assert!(headers.parse("If-Match", r#"W/"etag1", "etag2","#));
+        assert_eq!(
+            headers.if_,
+            vec![
+                If {
+                    resource: None,
+                    list: vec![Condition::ETag {
+                        is_not: false,
+                        tag: "W/\"etag1\"",
+                    }],
+                },
+                If {
+                    resource: None,
+                    list: vec![Condition::ETag {
+                        is_not: false,
+                        tag: "\"etag2\"",
+                    }],
+                }
+            ]
+        );
+        headers.if_.clear();
+
+        assert!(headers.parse("If-None-Match", " * "));
+        assert_eq!(
+            headers.if_,
+            vec![If {
+                resource: None,
+                list: vec![Condition::Exists { is_not: true }],
+            }]
+        );
+        headers.if_.clear();
+
+        assert!(etag_matches("\"1234\"", "\"1234\"", true));
+        assert!(!etag_matches("\"1234\"", "W/\"1234\"", true));
+        assert!(etag_matches("\"1234\"", "W/\"1234\"", false));
+        assert!(etag_matches("W/\"1234\"", "W/\"1234\"", false));
+        assert!(!etag_matches("\"1234\"", "\"4321\"", false));
 
         assert!(headers.parse("Timeout", "Second-10"));
         assert_eq!(headers.timeout, Timeout::Second(10));
@@ -22,6 +22,8 @@ pub mod tokenizer;
 pub enum Error {
     Xml(Box<quick_xml::Error>),
     UnexpectedToken(Box<UnexpectedToken>),
+    MaxDepthExceeded(usize),
+    MaxElementsExceeded(usize),
 }
 
 #[derive(Debug, Clone)]
@@ -204,6 +206,10 @@ impl Display for Error {
                 }
                 Ok(())
             }
+            Error::MaxDepthExceeded(max) => write!(f, "Maximum element depth of {max} exceeded"),
+            Error::MaxElementsExceeded(max) => {
+                write!(f, "Maximum number of elements of {max} exceeded")
+            }
         }
     }
 }
@@ -14,8 +14,9 @@ use mail_parser::DateTime;
 
 use crate::schema::{
     property::{
-        CalDavProperty, CalDavPropertyName, CalendarData, CardDavProperty, CardDavPropertyName,
-        Comp, DavProperty, DavValue, PrincipalProperty, ResourceType, TimeRange, WebDavProperty,
+        CalDavProperty, CalDavPropertyName, CalendarData, CalendarDataType, CardDavProperty,
+        CardDavPropertyName, Comp, DavProperty, DavValue, PrincipalProperty, ResourceType,
+        ScheduleCalendarTransp, TimeRange, WebDavProperty,
     },
     request::{DavPropertyValue, DeadProperty, VCardPropertyWithGroup},
     response::List,
@@ -37,10 +38,18 @@ impl Tokenizer<'_> {
                             ns: Namespace::CalDav,
                             element: Element::CalendarData,
                         },
-                    ..
+                    raw,
                 } => {
+                    let mut data_type = CalendarDataType::default();
+                    for attribute in raw.attributes::<String>() {
+                        if let Attribute::ContentType(content_type) = attribute? {
+                            data_type =
+                                CalendarDataType::try_parse(&content_type).unwrap_or_default();
+                        }
+                    }
+
                     elements.push(DavProperty::CalDav(CalDavProperty::CalendarData(
-                        self.collect_calendar_data()?,
+                        self.collect_calendar_data(data_type)?,
                     )));
                 }
                 Token::ElementStart {
@@ -75,15 +84,21 @@ impl Tokenizer<'_> {
         Ok(elements)
     }
 
-    pub(crate) fn collect_calendar_data(&mut self) -> crate::parser::Result<CalendarData> {
+    pub(crate) fn collect_calendar_data(
+        &mut self,
+        data_type: CalendarDataType,
+    ) -> crate::parser::Result<CalendarData> {
         let mut depth = 1;
         let mut data = CalendarData {
             properties: Vec::with_capacity(4),
+            all_components: Vec::new(),
             expand: None,
             limit_recurrence: None,
             limit_freebusy: None,
+            data_type,
         };
-        let mut components: Vec<ICalendarComponentType> = Vec::new();
+        // Selected components and whether they include a selection of their own
+        let mut components: Vec<(ICalendarComponentType, bool)> = Vec::new();
 
         loop {
             match self.token()? {
@@ -95,6 +110,12 @@ impl Tokenizer<'_> {
                         },
                     ..
                 } => {
+                    if let Some((component, has_selection)) = components.last_mut() {
+                        *has_selection = true;
+                        if !data.all_components.contains(component) {
+                            data.all_components.push(component.clone());
+                        }
+                    }
                     self.expect_element_end()?;
                 }
                 Token::ElementStart {
@@ -105,9 +126,10 @@ impl Tokenizer<'_> {
                         },
                     ..
                 } => {
-                    if let Some(component) = components.last().cloned() {
+                    if let Some((component, has_selection)) = components.last_mut() {
+                        *has_selection = true;
                         data.properties.push(CalDavPropertyName {
-                            component: Some(component),
+                            component: Some(component.clone()),
                             name: None,
                             no_value: false,
                         });
@@ -124,9 +146,12 @@ impl Tokenizer<'_> {
                 } => {
                     depth += 1;
 
+                    if let Some((_, has_selection)) = components.last_mut() {
+                        *has_selection = true;
+                    }
                     for attribute in raw.attributes::<ICalendarComponentType>() {
                         if let Attribute::Name(name) = attribute? {
-                            components.push(name);
+                            components.push((name, false));
                         }
                     }
                 }
@@ -154,8 +179,11 @@ impl Tokenizer<'_> {
                     }
 
                     if let Some(name) = name {
+                        if let Some((_, has_selection)) = components.last_mut() {
+                            *has_selection = true;
+                        }
                         data.properties.push(CalDavPropertyName {
-                            component: components.last().cloned(),
+                            component: components.last().map(|(component, _)| component.clone()),
                             name: Some(name),
                             no_value,
                         });
@@ -201,7 +229,11 @@ impl Tokenizer<'_> {
                     if depth == 0 {
                         break;
                     }
-                    if let Some(last_component) = components.pop() {
+                    if let Some((last_component, has_selection)) = components.pop() {
+                        // Components requested without a selection are returned in full
+                        if !has_selection && !data.all_components.contains(&last_component) {
+                            data.all_components.push(last_component.clone());
+                        }
                         if last_component != ICalendarComponentType::VCalendar
                             && !matches!(data.properties.last(), Some(CalDavPropertyName { component: Some(component), .. }) if component == &last_component)
                         {
@@ -298,6 +330,12 @@ impl Tokenizer<'_> {
                             DavProperty::WebDav(WebDavProperty::ResourceType) => {
                                 DavValue::ResourceTypes(List(self.collect_elements()?))
                             }
+                            DavProperty::CalDav(CalDavProperty::ScheduleCalendarTransp) => self
+                                .collect_elements()?
+                                .into_iter()
+                                .next()
+                                .map(DavValue::ScheduleCalendarTransp)
+                                .unwrap_or(DavValue::Null),
                             DavProperty::WebDav(WebDavProperty::CreationDate) => {
                                 match self.parse_value::<DateTime>()? {
                                     Some(Ok(value)) => DavValue::Timestamp(value.to_timestamp()),
@@ -305,13 +343,14 @@ impl Tokenizer<'_> {
                                     None => DavValue::Null,
                                 }
                             }
-                            DavProperty::CalDav(CalDavProperty::CalendarTimezone) => {
-                                match self.parse_value()? {
-                                    Some(Ok(value)) => DavValue::ICalendar(value),
-                                    Some(Err(value)) => DavValue::String(value),
-                                    None => DavValue::Null,
-                                }
-                            }
+                            DavProperty::CalDav(
+                                CalDavProperty::CalendarTimezone
+                                | CalDavProperty::CalendarAvailability,
+                            ) => match self.parse_value()? {
+                                Some(Ok(value)) => DavValue::ICalendar(value),
+                                Some(Err(value)) => DavValue::String(value),
+                                None => DavValue::Null,
+                            },
                             DavProperty::CalDav(CalDavProperty::SupportedCalendarComponentSet) => {
                                 let mut components = Vec::new();
 
@@ -346,7 +385,8 @@ impl Tokenizer<'_> {
                             }
                             DavProperty::CalDav(
                                 CalDavProperty::MaxInstances
-                                | CalDavProperty::MaxAttendeesPerInstance,
+                                | CalDavProperty::MaxAttendeesPerInstance
+                                | CalDavProperty::CalendarOrder,
                             ) => match self.parse_value()? {
                                 Some(Ok(value)) => DavValue::Uint64(value),
                                 Some(Err(value)) => DavValue::String(value),
@@ -478,6 +518,15 @@ impl DavProperty {
             (Namespace::Dav, Element::SyncToken) => {
                 Some(DavProperty::WebDav(WebDavProperty::SyncToken))
             }
+            (Namespace::Dav, Element::AddMember) => {
+                Some(DavProperty::WebDav(WebDavProperty::AddMember))
+            }
+            (Namespace::Dav, Element::VersionHistory) => {
+                Some(DavProperty::WebDav(WebDavProperty::VersionHistory))
+            }
+            (Namespace::Dav, Element::VersionName) => {
+                Some(DavProperty::WebDav(WebDavProperty::VersionName))
+            }
             (Namespace::Dav, Element::AlternateUriSet) => {
                 Some(DavProperty::Principal(PrincipalProperty::AlternateURISet))
             }
@@ -523,6 +572,9 @@ impl DavProperty {
             (Namespace::CardDav, Element::PrincipalAddress) => {
                 Some(DavProperty::Principal(PrincipalProperty::PrincipalAddress))
             }
+            (Namespace::CardDav, Element::DirectoryGateway) => {
+                Some(DavProperty::Principal(PrincipalProperty::DirectoryGateway))
+            }
             (Namespace::CardDav, Element::AddressData) => Some(DavProperty::CardDav(
                 CardDavProperty::AddressData(Default::default()),
             )),
@@ -562,6 +614,15 @@ impl DavProperty {
             (Namespace::CalDav, Element::CalendarHomeSet) => {
                 Some(DavProperty::Principal(PrincipalProperty::CalendarHomeSet))
             }
+            (Namespace::CalDav, Element::ScheduleInboxUrl) => {
+                Some(DavProperty::Principal(PrincipalProperty::ScheduleInboxURL))
+            }
+            (Namespace::CalDav, Element::ScheduleOutboxUrl) => {
+                Some(DavProperty::Principal(PrincipalProperty::ScheduleOutboxURL))
+            }
+            (Namespace::CalDav, Element::CalendarUserAddressSet) => Some(DavProperty::Principal(
+                PrincipalProperty::CalendarUserAddressSet,
+            )),
             (Namespace::CalDav, Element::CalendarData) => Some(DavProperty::CalDav(
                 CalDavProperty::CalendarData(Default::default()),
             )),
@@ -571,14 +632,74 @@ impl DavProperty {
             (Namespace::CalDav, Element::CalendarTimezoneId) => {
                 Some(DavProperty::CalDav(CalDavProperty::TimezoneId))
             }
+            (Namespace::CalDav, Element::ScheduleCalendarTransp) => {
+                Some(DavProperty::CalDav(CalDavProperty::ScheduleCalendarTransp))
+            }
+            (Namespace::CalDav, Element::CalendarAvailability) => {
+                Some(DavProperty::CalDav(CalDavProperty::CalendarAvailability))
+            }
+            (Namespace::CalDav, Element::DefaultAlarmVeventDate) => {
+                Some(DavProperty::CalDav(CalDavProperty::DefaultAlarmVEventDate))
+            }
+            (Namespace::CalDav, Element::DefaultAlarmVeventDatetime) => Some(DavProperty::CalDav(
+                CalDavProperty::DefaultAlarmVEventDateTime,
+            )),
+            (Namespace::AppleIcal, Element::CalendarColor) => {
+                Some(DavProperty::CalDav(CalDavProperty::CalendarColor))
+            }
+            (Namespace::AppleIcal, Element::CalendarOrder) => {
+                Some(DavProperty::CalDav(CalDavProperty::CalendarOrder))
+            }
             (Namespace::CalendarServer, Element::Getctag) => {
                 Some(DavProperty::WebDav(WebDavProperty::GetCTag))
             }
+            (Namespace::CalendarServer, Element::PushTransports) => {
+                Some(DavProperty::WebDav(WebDavProperty::PushTransports))
+            }
+            (Namespace::CalendarServer, Element::Pushkey) => {
+                Some(DavProperty::WebDav(WebDavProperty::PushKey))
+            }
+            (Namespace::CalendarServer, Element::Invite) => {
+                Some(DavProperty::CalDav(CalDavProperty::Invite))
+            }
+            (Namespace::CalendarServer, Element::AllowedSharingModes) => {
+                Some(DavProperty::CalDav(CalDavProperty::AllowedSharingModes))
+            }
+            (Namespace::CalendarServer, Element::Notificationtype) => {
+                Some(DavProperty::CalDav(CalDavProperty::NotificationType))
+            }
+            (Namespace::CalendarServer, Element::NotificationUrl) => {
+                Some(DavProperty::Principal(PrincipalProperty::NotificationURL))
+            }
+            (Namespace::WebDavPush, Element::Transports) => {
+                Some(DavProperty::WebDav(WebDavProperty::Transports))
+            }
+            (Namespace::WebDavPush, Element::Topic) => {
+                Some(DavProperty::WebDav(WebDavProperty::Topic))
+            }
+            (Namespace::WebDavPush, Element::SupportedTriggers) => {
+                Some(DavProperty::WebDav(WebDavProperty::SupportedTriggers))
+            }
+            (Namespace::Stalwart, Element::CollectionUsage) => {
+                Some(DavProperty::WebDav(WebDavProperty::CollectionUsage))
+            }
             _ => None,
         }
     }
 }
 
+impl TryFrom<NamedElement> for ScheduleCalendarTransp {
+    type Error = ();
+
+    fn try_from(value: NamedElement) -> Result<Self, Self::Error> {
+        match (value.ns, value.element) {
+            (Namespace::CalDav, Element::Opaque) => Ok(ScheduleCalendarTransp::Opaque),
+            (Namespace::CalDav, Element::Transparent) => Ok(ScheduleCalendarTransp::Transparent),
+            _ => Err(()),
+        }
+    }
+}
+
 impl TryFrom<NamedElement> for ResourceType {
     type Error = ();
 
@@ -588,6 +709,10 @@ impl TryFrom<NamedElement> for ResourceType {
             (Namespace::Dav, Element::Principal) => Ok(ResourceType::Principal),
             (Namespace::CardDav, Element::Addressbook) => Ok(ResourceType::AddressBook),
             (Namespace::CalDav, Element::Calendar) => Ok(ResourceType::Calendar),
+            (Namespace::CalDav, Element::ScheduleInbox) => Ok(ResourceType::ScheduleInbox),
+            (Namespace::CalDav, Element::ScheduleOutbox) => Ok(ResourceType::ScheduleOutbox),
+            (Namespace::CardDav, Element::Directory) => Ok(ResourceType::Directory),
+            (Namespace::CalendarServer, Element::Notification) => Ok(ResourceType::Notification),
             _ => Err(()),
         }
     }
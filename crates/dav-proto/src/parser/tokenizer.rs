@@ -17,6 +17,14 @@ use super::{Error, RawElement, Token, UnexpectedToken, XmlValueParser};
 pub struct Tokenizer<'x> {
     xml: NsReader<&'x [u8]>,
     last_is_end: bool,
+    limits: ElementLimits,
+}
+
+struct ElementLimits {
+    depth: usize,
+    elements: usize,
+    max_depth: usize,
+    max_elements: usize,
 }
 
 impl<'x> Tokenizer<'x> {
@@ -26,9 +34,23 @@ impl<'x> Tokenizer<'x> {
         Self {
             xml,
             last_is_end: false,
+            limits: ElementLimits {
+                depth: 0,
+                elements: 0,
+                max_depth: usize::MAX,
+                max_elements: usize::MAX,
+            },
         }
     }
 
+    /// Limits the nesting depth and the total number of elements of the
+    /// document, which are enforced as the input is tokenized.
+    pub fn with_limits(mut self, max_depth: usize, max_elements: usize) -> Self {
+        self.limits.max_depth = max_depth;
+        self.limits.max_elements = max_elements;
+        self
+    }
+
     pub fn token(&mut self) -> super::Result<Token> {
         loop {
             if self.last_is_end {
@@ -38,12 +60,17 @@ impl<'x> Tokenizer<'x> {
 
             let (resolve_result, event) = self.xml.read_resolved_event()?;
             let tag = match event {
-                Event::Start(tag) => tag,
+                Event::Start(tag) => {
+                    self.limits.enter(true)?;
+                    tag
+                }
                 Event::Empty(tag) => {
+                    self.limits.enter(false)?;
                     self.last_is_end = true;
                     tag
                 }
                 Event::End(_) => {
+                    self.limits.depth = self.limits.depth.saturating_sub(1);
                     return Ok(Token::ElementEnd);
                 }
                 Event::Text(text) if text.iter().any(|ch| !ch.is_ascii_whitespace()) => {
@@ -265,6 +292,162 @@ impl RawElement<'_> {
     }
 }
 
+impl ElementLimits {
+    fn enter(&mut self, is_open: bool) -> super::Result<()> {
+        self.elements += 1;
+        if self.elements > self.max_elements {
+            Err(Error::MaxElementsExceeded(self.max_elements))
+        } else if self.depth >= self.max_depth {
+            Err(Error::MaxDepthExceeded(self.max_depth))
+        } else {
+            if is_open {
+                self.depth += 1;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Enforces the element limits of a document as its bytes arrive, before it is
+/// fully read and tokenized. Only markup is tracked, the document is not
+/// validated.
+pub struct ElementScanner {
+    limits: ElementLimits,
+    state: ScanState,
+}
+
+enum ScanState {
+    Text,
+    Markup,
+    Tag {
+        is_close: bool,
+        quote: Option<u8>,
+        last: u8,
+    },
+    Declaration {
+        prefix: [u8; 7],
+        len: usize,
+    },
+    Skip {
+        end: &'static [u8],
+        matched: usize,
+    },
+}
+
+impl ElementScanner {
+    pub fn new(max_depth: usize, max_elements: usize) -> Self {
+        Self {
+            limits: ElementLimits {
+                depth: 0,
+                elements: 0,
+                max_depth,
+                max_elements,
+            },
+            state: ScanState::Text,
+        }
+    }
+
+    pub fn scan(&mut self, bytes: &[u8]) -> super::Result<()> {
+        for &ch in bytes {
+            self.state = match std::mem::replace(&mut self.state, ScanState::Text) {
+                ScanState::Text if ch == b'<' => ScanState::Markup,
+                ScanState::Text => ScanState::Text,
+                ScanState::Markup => match ch {
+                    b'/' => ScanState::Tag {
+                        is_close: true,
+                        quote: None,
+                        last: ch,
+                    },
+                    b'?' => ScanState::Skip {
+                        end: b"?>",
+                        matched: 0,
+                    },
+                    b'!' => ScanState::Declaration {
+                        prefix: [0; 7],
+                        len: 0,
+                    },
+                    _ => ScanState::Tag {
+                        is_close: false,
+                        quote: None,
+                        last: ch,
+                    },
+                },
+                ScanState::Tag {
+                    is_close,
+                    quote: Some(quote),
+                    last,
+                } => ScanState::Tag {
+                    is_close,
+                    quote: (ch != quote).then_some(quote),
+                    last,
+                },
+                ScanState::Tag { is_close, last, .. } if ch == b'>' => {
+                    if is_close {
+                        self.limits.depth = self.limits.depth.saturating_sub(1);
+                    } else {
+                        self.limits.enter(last != b'/')?;
+                    }
+                    ScanState::Text
+                }
+                ScanState::Tag { is_close, last, .. } => ScanState::Tag {
+                    is_close,
+                    quote: matches!(ch, b'"' | b'\'').then_some(ch),
+                    last: if ch.is_ascii_whitespace() { last } else { ch },
+                },
+                ScanState::Declaration { mut prefix, len } => {
+                    prefix[len] = ch;
+                    let prefix_ = &prefix[..len + 1];
+                    if prefix_ == b"--" {
+                        ScanState::Skip {
+                            end: b"-->",
+                            matched: 0,
+                        }
+                    } else if prefix_ == b"[CDATA[" {
+                        ScanState::Skip {
+                            end: b"]]>",
+                            matched: 0,
+                        }
+                    } else if b"--".starts_with(prefix_) || b"[CDATA[".starts_with(prefix_) {
+                        ScanState::Declaration {
+                            prefix,
+                            len: len + 1,
+                        }
+                    } else if ch == b'>' {
+                        ScanState::Text
+                    } else {
+                        ScanState::Skip {
+                            end: b">",
+                            matched: 0,
+                        }
+                    }
+                }
+                ScanState::Skip { end, matched } => {
+                    if ch == end[matched] {
+                        if matched + 1 == end.len() {
+                            ScanState::Text
+                        } else {
+                            ScanState::Skip {
+                                end,
+                                matched: matched + 1,
+                            }
+                        }
+                    } else if matched > 1 && end[0] == end[1] && ch == end[0] {
+                        // Runs such as "--->" still close a comment
+                        ScanState::Skip { end, matched }
+                    } else {
+                        ScanState::Skip {
+                            end,
+                            matched: usize::from(ch == end[0]),
+                        }
+                    }
+                }
+            };
+        }
+
+        Ok(())
+    }
+}
+
 impl From<quick_xml::Error> for Error {
     fn from(err: quick_xml::Error) -> Self {
         Error::Xml(Box::new(err))
@@ -474,4 +657,64 @@ mod tests {
             assert_eq!(result, expected);
         }
     }
+
+    #[test]
+    fn test_tokenizer_limits() {
+        let input = r#"<D:propfind xmlns:D="DAV:"><D:prop><D:getetag/><D:displayname/></D:prop></D:propfind>"#;
+
+        for (max_depth, max_elements, expected) in [
+            (3, 4, None),
+            (2, 4, Some(Error::MaxDepthExceeded(2))),
+            (3, 3, Some(Error::MaxElementsExceeded(3))),
+        ] {
+            let mut tokenizer =
+                Tokenizer::new(input.as_bytes()).with_limits(max_depth, max_elements);
+            let result = loop {
+                match tokenizer.token() {
+                    Ok(Token::Eof) => break None,
+                    Ok(_) => {}
+                    Err(err) => break Some(err),
+                }
+            };
+
+            assert_eq!(
+                result.map(|err| err.to_string()),
+                expected.map(|err| err.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_element_scanner() {
+        let input = concat!(
+            r#"<?xml version="1.0" encoding="utf-8" ?>"#,
+            r#"<!DOCTYPE propfind>"#,
+            r#"<D:propfind xmlns:D="DAV:" xmlns:X="urn:x">"#,
+            r#"<!-- <D:prop><D:prop/> --->"#,
+            r#"<D:prop><D:getetag/><X:value a="/>" b='<c>'><![CDATA[<a><b/>]]></X:value>"#,
+            r#"</D:prop></D:propfind>"#
+        );
+
+        for (max_depth, max_elements, expected) in [
+            (3, 4, None),
+            (2, 4, Some(Error::MaxDepthExceeded(2))),
+            (3, 3, Some(Error::MaxElementsExceeded(3))),
+        ] {
+            // Documents can be split at any byte
+            for chunk_size in [1, 2, 3, 7, input.len()] {
+                let mut scanner = ElementScanner::new(max_depth, max_elements);
+                let result = input
+                    .as_bytes()
+                    .chunks(chunk_size)
+                    .try_for_each(|chunk| scanner.scan(chunk))
+                    .err();
+
+                assert_eq!(
+                    result.map(|err| err.to_string()),
+                    expected.as_ref().map(|err| err.to_string()),
+                    "chunk size {chunk_size}"
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,117 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{
+    parser::{tokenizer::Tokenizer, DavParser, Token},
+    schema::{
+        request::{Bind, Unbind},
+        Element, NamedElement, Namespace,
+    },
+};
+
+impl DavParser for Bind {
+    fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
+        let mut bind = Bind {
+            is_rebind: false,
+            segment: None,
+            href: None,
+        };
+        match stream.token()? {
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::Dav,
+                        element: Element::Bind,
+                    },
+                ..
+            } => {}
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::Dav,
+                        element: Element::Rebind,
+                    },
+                ..
+            } => {
+                bind.is_rebind = true;
+            }
+            Token::Eof => {
+                return Ok(bind);
+            }
+            other => return Err(other.into_unexpected()),
+        };
+
+        loop {
+            match stream.token()? {
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::Dav,
+                            element: Element::Segment,
+                        },
+                    ..
+                } => {
+                    bind.segment = stream.collect_string_value()?;
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::Dav,
+                            element: Element::Href,
+                        },
+                    ..
+                } => {
+                    bind.href = stream.collect_string_value()?;
+                }
+                Token::ElementStart { .. } | Token::UnknownElement(_) => {
+                    // Ignore unknown elements
+                    stream.seek_element_end()?;
+                }
+                Token::ElementEnd | Token::Eof => {
+                    break;
+                }
+                token => return Err(token.into_unexpected()),
+            }
+        }
+
+        Ok(bind)
+    }
+}
+
+impl DavParser for Unbind {
+    fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
+        let mut unbind = Unbind { segment: None };
+
+        if stream.expect_named_element_or_eof(NamedElement::dav(Element::Unbind))? {
+            loop {
+                match stream.token()? {
+                    Token::ElementStart {
+                        name:
+                            NamedElement {
+                                ns: Namespace::Dav,
+                                element: Element::Segment,
+                            },
+                        ..
+                    } => {
+                        unbind.segment = stream.collect_string_value()?;
+                    }
+                    Token::ElementStart { .. } | Token::UnknownElement(_) => {
+                        // Ignore unknown elements
+                        stream.seek_element_end()?;
+                    }
+                    Token::ElementEnd | Token::Eof => {
+                        break;
+                    }
+                    other => {
+                        return Err(other.into_unexpected());
+                    }
+                }
+            }
+        }
+
+        Ok(unbind)
+    }
+}
@@ -13,11 +13,14 @@ use crate::{
 };
 
 pub mod acl;
+pub mod bind;
 pub mod lockinfo;
 pub mod mkcol;
 pub mod propertyupdate;
 pub mod propfind;
+pub mod push;
 pub mod report;
+pub mod share;
 
 impl DavParser for DeadProperty {
     fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
@@ -192,7 +195,10 @@ impl Default for DeadProperty {
 mod tests {
     use crate::{
         parser::{tokenizer::Tokenizer, DavParser},
-        schema::request::{Acl, LockInfo, MkCol, PropFind, PropertyUpdate, Report},
+        schema::request::{
+            Acl, Bind, LockInfo, MkCol, PropFind, PropertyUpdate, PushRegister, Report,
+            ShareRequest, Unbind,
+        },
     };
 
     #[test]
@@ -227,9 +233,24 @@ mod tests {
                         serde_json::to_string_pretty(&Report::parse(&mut tokenizer).unwrap())
                             .unwrap()
                     }
+                    "bind" | "rebind" => {
+                        serde_json::to_string_pretty(&Bind::parse(&mut tokenizer).unwrap()).unwrap()
+                    }
+                    "unbind" => {
+                        serde_json::to_string_pretty(&Unbind::parse(&mut tokenizer).unwrap())
+                            .unwrap()
+                    }
                     "acl" => {
                         serde_json::to_string_pretty(&Acl::parse(&mut tokenizer).unwrap()).unwrap()
                     }
+                    "share" => {
+                        serde_json::to_string_pretty(&ShareRequest::parse(&mut tokenizer).unwrap())
+                            .unwrap()
+                    }
+                    "push" => {
+                        serde_json::to_string_pretty(&PushRegister::parse(&mut tokenizer).unwrap())
+                            .unwrap()
+                    }
                     _ => {
                         panic!("Unknown method: {}", filename);
                     }
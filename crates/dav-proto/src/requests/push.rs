@@ -0,0 +1,225 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use mail_parser::DateTime;
+
+use crate::{
+    parser::{tokenizer::Tokenizer, DavParser, Token},
+    schema::{request::PushRegister, Element, NamedElement, Namespace},
+    Depth,
+};
+
+impl DavParser for PushRegister {
+    fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
+        stream.expect_named_element(NamedElement {
+            ns: Namespace::WebDavPush,
+            element: Element::PushRegister,
+        })?;
+
+        let mut register = PushRegister::default();
+
+        loop {
+            match stream.token()? {
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::WebDavPush,
+                            element: Element::Subscription,
+                        },
+                    ..
+                } => {
+                    parse_subscription(stream, &mut register)?;
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::WebDavPush,
+                            element: Element::Trigger,
+                        },
+                    ..
+                } => {
+                    parse_trigger(stream, &mut register)?;
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::WebDavPush,
+                            element: Element::Expires,
+                        },
+                    ..
+                } => {
+                    register.expires = stream
+                        .collect_string_value()?
+                        .and_then(|value| DateTime::parse_rfc822(value.trim()))
+                        .map(|dt| dt.to_timestamp());
+                }
+                Token::ElementStart { .. } | Token::UnknownElement(_) => {
+                    // Ignore unknown elements
+                    stream.seek_element_end()?;
+                }
+                Token::ElementEnd | Token::Eof => {
+                    break;
+                }
+                token => return Err(token.into_unexpected()),
+            }
+        }
+
+        Ok(register)
+    }
+}
+
+fn parse_subscription(
+    stream: &mut Tokenizer<'_>,
+    register: &mut PushRegister,
+) -> crate::parser::Result<()> {
+    let mut depth = 1;
+
+    // Only Web Push subscriptions are supported, the elements are read
+    // regardless of the transport element they are wrapped in
+    loop {
+        match stream.token()? {
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::WebDavPush,
+                        element: Element::PushResource,
+                    },
+                ..
+            } => {
+                register.push_resource = stream.collect_string_value()?.unwrap_or_default();
+            }
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::WebDavPush,
+                        element: Element::ContentEncoding,
+                    },
+                ..
+            } => {
+                register.content_encoding = stream.collect_string_value()?;
+            }
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::WebDavPush,
+                        element: Element::SubscriptionPublicKey,
+                    },
+                ..
+            } => {
+                register.public_key = stream.collect_string_value()?;
+            }
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::WebDavPush,
+                        element: Element::AuthSecret,
+                    },
+                ..
+            } => {
+                register.auth_secret = stream.collect_string_value()?;
+            }
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::WebDavPush,
+                        element: Element::WebPushSubscription,
+                    },
+                ..
+            } => {
+                depth += 1;
+            }
+            Token::ElementStart { .. } | Token::UnknownElement(_) => {
+                // Ignore unknown elements
+                stream.seek_element_end()?;
+            }
+            Token::ElementEnd => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            Token::Eof => {
+                break;
+            }
+            token => return Err(token.into_unexpected()),
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_trigger(
+    stream: &mut Tokenizer<'_>,
+    register: &mut PushRegister,
+) -> crate::parser::Result<()> {
+    loop {
+        match stream.token()? {
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::WebDavPush,
+                        element: Element::ContentUpdate,
+                    },
+                ..
+            } => {
+                register.content_update = Some(parse_trigger_depth(stream)?);
+            }
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::WebDavPush,
+                        element: Element::PropertyUpdate,
+                    },
+                ..
+            } => {
+                register.property_update = Some(parse_trigger_depth(stream)?);
+            }
+            Token::ElementStart { .. } | Token::UnknownElement(_) => {
+                // Ignore unknown elements
+                stream.seek_element_end()?;
+            }
+            Token::ElementEnd | Token::Eof => {
+                break;
+            }
+            token => return Err(token.into_unexpected()),
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_trigger_depth(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Depth> {
+    let mut depth = Depth::Zero;
+
+    loop {
+        match stream.token()? {
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::Dav,
+                        element: Element::Depth,
+                    },
+                ..
+            } => {
+                depth = match stream.collect_string_value()?.as_deref().map(str::trim) {
+                    Some("1") => Depth::One,
+                    Some("infinity") => Depth::Infinity,
+                    _ => Depth::Zero,
+                };
+            }
+            Token::ElementStart { .. } | Token::UnknownElement(_) => {
+                // Ignore unknown elements, such as the properties to monitor
+                stream.seek_element_end()?;
+            }
+            Token::ElementEnd | Token::Eof => {
+                break;
+            }
+            token => return Err(token.into_unexpected()),
+        }
+    }
+
+    Ok(depth)
+}
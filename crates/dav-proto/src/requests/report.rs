@@ -17,7 +17,7 @@ use crate::{
             AclPrincipalPropSet, AddressbookQuery, CalendarQuery, DeadElementTag, ExpandProperty,
             ExpandPropertyItem, Filter, FilterOp, FreeBusyQuery, MultiGet, PrincipalMatch,
             PrincipalPropertySearch, PropFind, Report, SyncCollection, TextMatch, Timezone,
-            VCardPropertyWithGroup,
+            VCardPropertyWithGroup, VersionTree,
         },
         Attribute, Collation, Element, MatchType, NamedElement, Namespace,
     },
@@ -73,6 +73,10 @@ impl DavParser for Report {
                 ns: Namespace::Dav,
                 element: Element::ExpandProperty,
             } => ExpandProperty::parse(stream).map(Report::ExpandProperty),
+            NamedElement {
+                ns: Namespace::Dav,
+                element: Element::VersionTree,
+            } => VersionTree::parse(stream).map(Report::VersionTree),
             other => Err(other.into_unexpected()),
         }
     }
@@ -445,6 +449,50 @@ impl DavParser for MultiGet {
     }
 }
 
+impl DavParser for VersionTree {
+    fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
+        let mut vt = VersionTree {
+            properties: PropFind::AllProp(vec![]),
+        };
+
+        loop {
+            match stream.token()? {
+                Token::ElementStart { name, .. } => match name {
+                    NamedElement {
+                        ns: Namespace::Dav,
+                        element: Element::Propname,
+                    } => {
+                        vt.properties = PropFind::PropName;
+                        stream.expect_element_end()?;
+                    }
+                    NamedElement {
+                        ns: Namespace::Dav,
+                        element: Element::Allprop,
+                    } => {
+                        stream.expect_element_end()?;
+                    }
+                    NamedElement {
+                        ns: Namespace::Dav,
+                        element: Element::Prop,
+                    } => {
+                        vt.properties = PropFind::Prop(stream.collect_properties(Vec::new())?);
+                    }
+                    name => return Err(name.into_unexpected()),
+                },
+                Token::ElementEnd => {
+                    break;
+                }
+                Token::UnknownElement(_) => {
+                    stream.seek_element_end()?;
+                }
+                element => return Err(element.into_unexpected()),
+            }
+        }
+
+        Ok(vt)
+    }
+}
+
 impl DavParser for SyncCollection {
     fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
         let mut sc = SyncCollection {
@@ -0,0 +1,254 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{
+    parser::{tokenizer::Tokenizer, DavParser, Token},
+    schema::{
+        request::{InviteReply, Share, ShareRequest, ShareSet},
+        Element, NamedElement, Namespace,
+    },
+};
+
+impl DavParser for ShareRequest {
+    fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
+        match stream.token()? {
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::CalendarServer,
+                        element: Element::Share,
+                    },
+                ..
+            } => Share::parse(stream).map(ShareRequest::Share),
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::CalendarServer,
+                        element: Element::InviteReply,
+                    },
+                ..
+            } => InviteReply::parse(stream).map(ShareRequest::InviteReply),
+            other => Err(other.into_unexpected()),
+        }
+    }
+}
+
+impl DavParser for Share {
+    fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
+        let mut share = Share::default();
+
+        loop {
+            match stream.token()? {
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::CalendarServer,
+                            element: Element::Set,
+                        },
+                    ..
+                } => {
+                    share.set.push(ShareSet::parse(stream)?);
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::CalendarServer,
+                            element: Element::Remove,
+                        },
+                    ..
+                } => {
+                    if let Some(href) = parse_href(stream)? {
+                        share.remove.push(href);
+                    }
+                }
+                Token::ElementStart { .. } | Token::UnknownElement(_) => {
+                    // Ignore unknown elements
+                    stream.seek_element_end()?;
+                }
+                Token::ElementEnd | Token::Eof => {
+                    break;
+                }
+                token => return Err(token.into_unexpected()),
+            }
+        }
+
+        Ok(share)
+    }
+}
+
+impl DavParser for ShareSet {
+    fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
+        let mut set = ShareSet::default();
+
+        loop {
+            match stream.token()? {
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::Dav,
+                            element: Element::Href,
+                        },
+                    ..
+                } => {
+                    set.href = stream.collect_string_value()?.unwrap_or_default();
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::CalendarServer,
+                            element: Element::CommonName,
+                        },
+                    ..
+                } => {
+                    set.common_name = stream.collect_string_value()?;
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::CalendarServer,
+                            element: Element::Summary,
+                        },
+                    ..
+                } => {
+                    set.summary = stream.collect_string_value()?;
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::CalendarServer,
+                            element: Element::ReadWrite,
+                        },
+                    ..
+                } => {
+                    set.read_write = true;
+                    stream.seek_element_end()?;
+                }
+                Token::ElementStart { .. } | Token::UnknownElement(_) => {
+                    // Ignore unknown elements
+                    stream.seek_element_end()?;
+                }
+                Token::ElementEnd | Token::Eof => {
+                    break;
+                }
+                token => return Err(token.into_unexpected()),
+            }
+        }
+
+        Ok(set)
+    }
+}
+
+impl DavParser for InviteReply {
+    fn parse(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Self> {
+        let mut reply = InviteReply::default();
+
+        loop {
+            match stream.token()? {
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::Dav,
+                            element: Element::Href,
+                        },
+                    ..
+                } => {
+                    reply.href = stream.collect_string_value()?;
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::CalendarServer,
+                            element: Element::InviteAccepted,
+                        },
+                    ..
+                } => {
+                    reply.accepted = true;
+                    stream.seek_element_end()?;
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::CalendarServer,
+                            element: Element::InviteDeclined,
+                        },
+                    ..
+                } => {
+                    reply.accepted = false;
+                    stream.seek_element_end()?;
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::CalendarServer,
+                            element: Element::Hosturl,
+                        },
+                    ..
+                } => {
+                    reply.host_url = parse_href(stream)?;
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::CalendarServer,
+                            element: Element::InReplyTo,
+                        },
+                    ..
+                } => {
+                    reply.in_reply_to = stream.collect_string_value()?;
+                }
+                Token::ElementStart {
+                    name:
+                        NamedElement {
+                            ns: Namespace::CalendarServer,
+                            element: Element::Summary,
+                        },
+                    ..
+                } => {
+                    reply.summary = stream.collect_string_value()?;
+                }
+                Token::ElementStart { .. } | Token::UnknownElement(_) => {
+                    // Ignore unknown elements
+                    stream.seek_element_end()?;
+                }
+                Token::ElementEnd | Token::Eof => {
+                    break;
+                }
+                token => return Err(token.into_unexpected()),
+            }
+        }
+
+        Ok(reply)
+    }
+}
+
+fn parse_href(stream: &mut Tokenizer<'_>) -> crate::parser::Result<Option<String>> {
+    let mut href = None;
+
+    loop {
+        match stream.token()? {
+            Token::ElementStart {
+                name:
+                    NamedElement {
+                        ns: Namespace::Dav,
+                        element: Element::Href,
+                    },
+                ..
+            } => {
+                href = stream.collect_string_value()?;
+            }
+            Token::ElementStart { .. } | Token::UnknownElement(_) => {
+                // Ignore unknown elements
+                stream.seek_element_end()?;
+            }
+            Token::ElementEnd | Token::Eof => {
+                break;
+            }
+            token => return Err(token.into_unexpected()),
+        }
+    }
+
+    Ok(href)
+}
@@ -6,11 +6,17 @@
 
 use std::fmt::Display;
 
+use hyper::StatusCode;
+
 use crate::schema::{
-    response::{BaseCondition, CalCondition, CardCondition, Condition, ErrorResponse},
+    response::{
+        BaseCondition, CalCondition, CardCondition, Condition, ErrorResponse, ResourceNameError,
+    },
     Namespace, Namespaces,
 };
 
+use super::{repair::NAMESPACE, XmlEscape};
+
 impl Display for ErrorResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -20,12 +26,15 @@ impl Display for ErrorResponse {
         )?;
 
         match &self.error {
-            Condition::Base(e) => e.fmt(f)?,
-            Condition::Cal(e) => e.fmt(f)?,
-            Condition::Card(e) => e.fmt(f)?,
+            Some(Condition::Base(e)) => e.fmt(f)?,
+            Some(Condition::Cal(e)) => e.fmt(f)?,
+            Some(Condition::Card(e)) => e.fmt(f)?,
+            None => {}
         }
 
-        write!(f, "</D:error>")
+        write!(f, "<S:code>{}</S:code><S:description>", self.code)?;
+        self.description.write_escaped_to(f)?;
+        write!(f, "</S:description></D:error>")
     }
 }
 
@@ -84,6 +93,15 @@ impl Display for BaseCondition {
             BaseCondition::MissingRequiredPrincipal => write!(f, "<D:missing-required-principal/>"),
             BaseCondition::RecognizedPrincipal => write!(f, "<D:recognized-principal/>"),
             BaseCondition::AllowedPrincipal => write!(f, "<D:allowed-principal/>"),
+            BaseCondition::MaxCollectionMembers(l) => write!(
+                f,
+                "<S:max-collection-members xmlns:S=\"{NAMESPACE}\">{l}</S:max-collection-members>"
+            ),
+            BaseCondition::ValidResourceName(err) => write!(
+                f,
+                "<S:valid-resource-name xmlns:S=\"{NAMESPACE}\">{}</S:valid-resource-name>",
+                err.as_str()
+            ),
         }
     }
 }
@@ -107,6 +125,9 @@ impl Display for CalCondition {
                 write!(f, "<A:initialize-calendar-collection/>")
             }
             CalCondition::SupportedCalendarData => write!(f, "<A:supported-calendar-data/>"),
+            CalCondition::SupportedCalendarComponent => {
+                write!(f, "<A:supported-calendar-component/>")
+            }
             CalCondition::SupportedFilter(_) => write!(f, "<A:supported-filter/>"),
             CalCondition::SupportedCollation(c) => {
                 write!(f, "<A:supported-collation>{c}</A:supported-collation>")
@@ -118,6 +139,11 @@ impl Display for CalCondition {
             }
             CalCondition::MaxInstances => write!(f, "<A:max-instances/>"),
             CalCondition::MaxAttendeesPerInstance => write!(f, "<A:max-attendees-per-instance/>"),
+            CalCondition::ValidManagedIdParameter => {
+                write!(f, "<A:valid-managed-id-parameter/>")
+            }
+            CalCondition::ValidRidParameter => write!(f, "<A:valid-rid-parameter/>"),
+            CalCondition::MaxAttachmentSize => write!(f, "<A:max-attachment-size/>"),
         }
     }
 }
@@ -159,6 +185,18 @@ impl From<CardCondition> for Condition {
     }
 }
 
+impl ResourceNameError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceNameError::TooLong => "too-long",
+            ResourceNameError::InvalidEncoding => "invalid-encoding",
+            ResourceNameError::ForbiddenCharacter => "forbidden-character",
+            ResourceNameError::Reserved => "reserved",
+            ResourceNameError::NotNormalized => "not-normalized",
+        }
+    }
+}
+
 impl From<BaseCondition> for Condition {
     fn from(error: BaseCondition) -> Self {
         Condition::Base(error)
@@ -167,9 +205,81 @@ impl From<BaseCondition> for Condition {
 
 impl ErrorResponse {
     pub fn new(error: impl Into<Condition>) -> Self {
+        let error = error.into();
+        let mut namespaces = Namespaces::default();
+        namespaces.set(Namespace::Stalwart);
+
+        ErrorResponse {
+            namespaces,
+            code: error.display_name(),
+            description: error.description(),
+            error: Some(error),
+        }
+    }
+
+    pub fn from_status(status: StatusCode) -> Self {
+        let (code, description) = match status {
+            StatusCode::BAD_REQUEST => ("BadRequest", "The request could not be parsed"),
+            StatusCode::UNAUTHORIZED => ("Unauthorized", "Authentication is required"),
+            StatusCode::FORBIDDEN => ("Forbidden", "Access to the resource is not allowed"),
+            StatusCode::NOT_FOUND => ("NotFound", "The resource does not exist"),
+            StatusCode::METHOD_NOT_ALLOWED => (
+                "MethodNotAllowed",
+                "The method is not allowed on this resource",
+            ),
+            StatusCode::CONFLICT => (
+                "Conflict",
+                "The request conflicts with the current state of the resource",
+            ),
+            StatusCode::PRECONDITION_FAILED => {
+                ("PreconditionFailed", "A request precondition was not met")
+            }
+            StatusCode::PAYLOAD_TOO_LARGE => (
+                "PayloadTooLarge",
+                "The request body exceeds the maximum size",
+            ),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => (
+                "UnsupportedMediaType",
+                "The request body format is not supported",
+            ),
+            StatusCode::LOCKED => ("Locked", "The resource is locked"),
+            StatusCode::TOO_MANY_REQUESTS => (
+                "TooManyRequests",
+                "Too many requests, please try again later",
+            ),
+            StatusCode::FAILED_DEPENDENCY => (
+                "FailedDependency",
+                "The request failed because a dependent operation failed",
+            ),
+            StatusCode::BAD_GATEWAY => {
+                ("BadGateway", "The destination is not served by this server")
+            }
+            StatusCode::SERVICE_UNAVAILABLE => (
+                "ServiceUnavailable",
+                "The server is busy, please try again later",
+            ),
+            StatusCode::INSUFFICIENT_STORAGE => (
+                "InsufficientStorage",
+                "There is not enough storage to complete the request",
+            ),
+            StatusCode::NOT_IMPLEMENTED => (
+                "NotImplemented",
+                "The request is not supported by this server",
+            ),
+            _ if status.is_client_error() => ("ClientError", "The request could not be processed"),
+            _ => (
+                "ServerError",
+                "An internal error occurred while processing the request",
+            ),
+        };
+        let mut namespaces = Namespaces::default();
+        namespaces.set(Namespace::Stalwart);
+
         ErrorResponse {
-            namespaces: Namespaces::default(),
-            error: error.into(),
+            namespaces,
+            error: None,
+            code,
+            description,
         }
     }
 
@@ -11,11 +11,14 @@ pub mod mkcol;
 pub mod multistatus;
 pub mod property;
 pub mod propstat;
+pub mod push;
+pub mod repair;
+pub mod sharing;
 
 use std::fmt::{Display, Write};
 
 use crate::schema::{
-    property::{Comp, ResourceType, SupportedCollation},
+    property::{Comp, ResourceType, ScheduleCalendarTransp, SupportedCollation},
     request::{DeadProperty, DeadPropertyTag},
     response::{Href, List, Location, ResponseDescription, Status, SyncToken},
     Namespaces,
@@ -84,6 +87,15 @@ impl Display for Namespaces {
         if self.cs {
             f.write_str(" xmlns:C=\"http://calendarserver.org/ns/\"")?;
         }
+        if self.ical {
+            f.write_str(" xmlns:I=\"http://apple.com/ns/ical/\"")?;
+        }
+        if self.push {
+            f.write_str(" xmlns:P=\"https://bitfire.at/webdav-push\"")?;
+        }
+        if self.sw {
+            f.write_str(" xmlns:S=\"urn:stalwart:params:xml:ns:dav\"")?;
+        }
         Ok(())
     }
 }
@@ -140,7 +152,16 @@ impl Display for SyncToken {
 
 impl Display for Comp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "<A:comp name=\"{}\">", self.0.as_str())
+        write!(f, "<A:comp name=\"{}\"/>", self.0.as_str())
+    }
+}
+
+impl Display for ScheduleCalendarTransp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleCalendarTransp::Opaque => write!(f, "<A:opaque/>"),
+            ScheduleCalendarTransp::Transparent => write!(f, "<A:transparent/>"),
+        }
     }
 }
 
@@ -151,6 +172,10 @@ impl Display for ResourceType {
             ResourceType::Principal => write!(f, "<D:principal/>"),
             ResourceType::AddressBook => write!(f, "<B:addressbook/>"),
             ResourceType::Calendar => write!(f, "<A:calendar/>"),
+            ResourceType::ScheduleInbox => write!(f, "<A:schedule-inbox/>"),
+            ResourceType::ScheduleOutbox => write!(f, "<A:schedule-outbox/>"),
+            ResourceType::Directory => write!(f, "<B:directory/>"),
+            ResourceType::Notification => write!(f, "<C:notification/>"),
         }
     }
 }
@@ -207,14 +232,16 @@ mod tests {
         responses::XmlCdataEscape,
         schema::{
             property::{
-                ActiveLock, CalDavProperty, CardDavProperty, DavValue, LockScope, Privilege,
-                ResourceType, Rfc1123DateTime, SupportedLock, WebDavProperty,
+                ActiveLock, CalDavProperty, CardDavProperty, CollectionUsage, DavValue,
+                InviteStatus, LockScope, Privilege, PushTransport, ResourceType, Rfc1123DateTime,
+                Sharee, SupportedLock, UsageItem, WebDavProperty,
             },
             request::{DavPropertyValue, DeadElementTag, DeadProperty, DeadPropertyTag},
             response::{
-                Ace, AclRestrictions, BaseCondition, ErrorResponse, GrantDeny, Href, List,
-                MkColResponse, MultiStatus, Principal, PrincipalSearchProperty,
-                PrincipalSearchPropertySet, PropResponse, PropStat, RequiredPrincipal, Resource,
+                Ace, AclRestrictions, BaseCondition, ErrorResponse, GrantDeny, Href,
+                InviteNotification, List, MkColResponse, MultiStatus, Principal,
+                PrincipalSearchProperty, PrincipalSearchPropertySet, PropResponse, PropStat,
+                PushMessage, Repair, RepairAction, RepairLog, RequiredPrincipal, Resource,
                 Response, SupportedPrivilege,
             },
             Namespace,
@@ -640,6 +667,86 @@ END:VCARD
                 ])],
             )])
             .to_string(),
+            // 020.xml
+            RepairLog::new(vec![
+                Repair::new("DTSTART", 7, RepairAction::Replaced),
+                Repair::new("TRANSP", 9, RepairAction::Added),
+                Repair::new("SEQUENCE", 12, RepairAction::Removed),
+            ])
+            .to_string(),
+            // 021.xml
+            ErrorResponse::new(BaseCondition::MaxCollectionMembers(1000)).to_string(),
+            // 022.xml
+            MultiStatus::new(vec![Response::new_propstat(
+                "/dav/cal/jane/work/",
+                vec![PropStat::new(DavPropertyValue::new(
+                    WebDavProperty::CollectionUsage,
+                    CollectionUsage {
+                        bytes: 20480,
+                        items: 12,
+                        largest: List::new([
+                            UsageItem {
+                                href: Href("/dav/cal/jane/work/offsite.ics".to_string()),
+                                size: 8192,
+                            },
+                            UsageItem {
+                                href: Href("/dav/cal/jane/work/standup.ics".to_string()),
+                                size: 4096,
+                            },
+                        ]),
+                    },
+                ))],
+            )])
+            .with_namespace(Namespace::Stalwart)
+            .to_string(),
+            // 023.xml
+            MultiStatus::new(vec![Response::new_propstat(
+                "/dav/cal/jane/",
+                vec![PropStat::new_list(vec![
+                    DavPropertyValue::new(
+                        WebDavProperty::PushTransports,
+                        vec![PushTransport {
+                            subscription_url: Href("/dav/apns".to_string()),
+                            topic:
+                                "com.apple.calendar.XServer.934668ca-125e-4246-afee-8cf2df37aab8"
+                                    .to_string(),
+                            environment: "PRODUCTION".to_string(),
+                            refresh_interval: 172800,
+                        }],
+                    ),
+                    DavPropertyValue::new(
+                        WebDavProperty::PushKey,
+                        DavValue::String("/dav/cal/12/".to_string()),
+                    ),
+                ])],
+            )])
+            .with_namespace(Namespace::CalendarServer)
+            .to_string(),
+            // 024.xml
+            ErrorResponse::new(BaseCondition::ValidResourceName(
+                ResourceNameError::ForbiddenCharacter,
+            ))
+            .to_string(),
+            // 025.xml
+            InviteNotification::new(
+                "d2683fa5-1d41-4b32-8a4c-1ba0b8e5e48b",
+                1318446077,
+                Sharee {
+                    href: Href("mailto:jane@example.com".to_string()),
+                    common_name: None,
+                    status: InviteStatus::NoResponse,
+                    read_write: true,
+                    summary: Some("Team calendar".to_string()),
+                },
+                "/dav/cal/john/team/",
+                Href("/dav/pal/john/".to_string()),
+            )
+            .with_organizer_name("John Doe")
+            .to_string(),
+            // 026.xml
+            PushMessage::new("cal:3:7")
+                .with_content_update("urn:stalwart:davsync:1f")
+                .to_string(),
         ]
         .into_iter()
         .enumerate()
@@ -4,11 +4,13 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::fmt::Display;
+use std::fmt::{Display, Write};
 
 use hyper::StatusCode;
 
 use crate::schema::{
+    property::DavValue,
+    request::DavPropertyValue,
     response::{
         Condition, Href, List, Location, MultiStatus, PropStat, Response, ResponseDescription,
         ResponseType, Status, SyncToken,
@@ -16,12 +18,16 @@ use crate::schema::{
     Namespace, Namespaces,
 };
 
+// Maximum number of recycled property lists and data buffers kept by a
+// multistatus response
+const MAX_POOL_SIZE: usize = 128;
+
 impl Display for MultiStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><D:multistatus {}>{}",
-            self.namespaces, self.response
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><D:multistatus {}>{}{}",
+            self.namespaces, self.spilled, self.response
         )?;
         if let Some(response_description) = &self.response_description {
             write!(f, "{response_description}")?;
@@ -70,6 +76,9 @@ impl MultiStatus {
         MultiStatus {
             namespaces: Namespaces::default(),
             response: List(response),
+            spilled: String::new(),
+            prop_pool: Vec::new(),
+            data_pool: Vec::new(),
             response_description: None,
             sync_token: None,
         }
@@ -84,6 +93,73 @@ impl MultiStatus {
         self.response.0.push(response);
     }
 
+    /// Serializes the pending responses and releases them, returning
+    /// the total size in bytes of all serialized responses so far.
+    ///
+    /// The serialized XML is kept in memory until the response is sent,
+    /// spilling only replaces the structured responses with their more
+    /// compact text form. Callers are expected to use the returned size
+    /// as a cap and stop adding responses once it exceeds their budget.
+    pub fn spill(&mut self) -> usize {
+        for response in self.response.0.drain(..) {
+            let _ = write!(&mut self.spilled, "{response}");
+
+            // Recycle the property lists and data buffers of serialized responses
+            if let ResponseType::PropStat(propstats) = response.typ {
+                for propstat in propstats.0 {
+                    let mut props = propstat.prop.0 .0;
+                    for prop in props.drain(..) {
+                        if let DavValue::CData(mut data) = prop.value {
+                            if self.data_pool.len() < MAX_POOL_SIZE {
+                                data.clear();
+                                self.data_pool.push(data);
+                            }
+                        }
+                    }
+                    if self.prop_pool.len() < MAX_POOL_SIZE {
+                        self.prop_pool.push(props);
+                    }
+                }
+            }
+        }
+        self.spilled.len()
+    }
+
+    /// Returns an empty property list, reusing the allocation of a
+    /// previously spilled response when available.
+    pub fn take_prop_buffer(&mut self, capacity: usize) -> Vec<DavPropertyValue> {
+        if let Some(mut props) = self.prop_pool.pop() {
+            props.reserve(capacity);
+            props
+        } else {
+            Vec::with_capacity(capacity)
+        }
+    }
+
+    /// Returns an empty buffer for item data such as calendar or address
+    /// data, reusing the allocation of a previously spilled response when
+    /// available.
+    pub fn take_data_buffer(&mut self, capacity: usize) -> String {
+        if let Some(mut data) = self.data_pool.pop() {
+            data.reserve(capacity);
+            data
+        } else {
+            String::with_capacity(capacity)
+        }
+    }
+
+    /// Serializes the multistatus response into a buffer sized upfront
+    /// from the already spilled responses.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::with_capacity(self.spilled.len() + 512);
+        let _ = write!(&mut xml, "{self}");
+        xml
+    }
+
+    pub fn has_responses(&self) -> bool {
+        !self.response.0.is_empty() || !self.spilled.is_empty()
+    }
+
     pub fn with_response_description(mut self, response_description: impl Into<String>) -> Self {
         self.response_description = Some(ResponseDescription(response_description.into()));
         self
@@ -7,9 +7,10 @@
 use super::{XmlCdataEscape, XmlEscape};
 use crate::schema::{
     property::{
-        ActiveLock, CalDavProperty, CardDavProperty, Comp, DavProperty, DavValue, LockDiscovery,
-        LockEntry, PrincipalProperty, Privilege, ReportSet, ResourceType, Rfc1123DateTime,
-        SupportedCollation, SupportedLock, WebDavProperty,
+        ActiveLock, CalDavProperty, CalendarDataType, CardDavProperty, CollectionUsage, Comp,
+        DavProperty, DavValue, InviteStatus, LockDiscovery, LockEntry, PrincipalProperty,
+        Privilege, PushTransport, ReportSet, ResourceType, Rfc1123DateTime, ScheduleCalendarTransp,
+        Sharee, SupportedCollation, SupportedLock, UsageItem, WebDavProperty,
     },
     request::{DavPropertyValue, DeadProperty},
     response::{Ace, AclRestrictions, Href, List, PropResponse, SupportedPrivilege},
@@ -95,6 +96,9 @@ impl Display for DavValue {
             DavValue::Privileges(v) => v.fmt(f),
             DavValue::Acl(v) => v.fmt(f),
             DavValue::AclRestrictions(v) => v.fmt(f),
+            DavValue::CollectionUsage(v) => v.fmt(f),
+            DavValue::PushTransports(v) => v.fmt(f),
+            DavValue::Invite(v) => v.fmt(f),
             DavValue::DeadProperty(v) => v.fmt(f),
             DavValue::SupportedAddressData => {
                 write!(
@@ -112,6 +116,8 @@ impl Display for DavValue {
                     concat!(
                         "<A:calendar-data-type content-type=\"text/calendar\" version=\"2.0\"/>",
                         "<A:calendar-data-type content-type=\"text/calendar\" version=\"1.0\"/>",
+                        "<A:calendar-data-type content-type=\"application/calendar+json\" version=\"2.0\"/>",
+                        "<A:calendar-data-type content-type=\"application/calendar+xml\" version=\"2.0\"/>",
                     )
                 )
             }
@@ -136,6 +142,17 @@ impl Display for DavValue {
                 )
             }
             DavValue::Response(v) => v.fmt(f),
+            DavValue::ScheduleCalendarTransp(v) => v.fmt(f),
+            DavValue::AllowedSharingModes => write!(f, "<C:can-be-shared/>"),
+            DavValue::InviteNotification => write!(f, "<C:invite-notification/>"),
+            DavValue::WebPushTransport => write!(f, "<P:web-push/>"),
+            DavValue::SupportedTriggers => write!(
+                f,
+                concat!(
+                    "<P:content-update><D:depth>1</D:depth></P:content-update>",
+                    "<P:property-update><D:depth>0</D:depth></P:property-update>",
+                )
+            ),
             DavValue::VCard(_) | DavValue::ICalendar(_) | DavValue::Null => Ok(()),
         }
     }
@@ -161,6 +178,7 @@ impl DavProperty {
                     WebDavProperty::QuotaUsedBytes => "D:quota-used-bytes",
                     WebDavProperty::SupportedReportSet => "D:supported-report-set",
                     WebDavProperty::SyncToken => "D:sync-token",
+                    WebDavProperty::AddMember => "D:add-member",
                     WebDavProperty::Owner => "D:owner",
                     WebDavProperty::Group => "D:group",
                     WebDavProperty::SupportedPrivilegeSet => "D:supported-privilege-set",
@@ -169,7 +187,15 @@ impl DavProperty {
                     WebDavProperty::AclRestrictions => "D:acl-restrictions",
                     WebDavProperty::InheritedAclSet => "D:inherited-acl-set",
                     WebDavProperty::PrincipalCollectionSet => "D:principal-collection-set",
+                    WebDavProperty::VersionHistory => "D:version-history",
+                    WebDavProperty::VersionName => "D:version-name",
                     WebDavProperty::GetCTag => "C:getctag",
+                    WebDavProperty::PushTransports => "C:push-transports",
+                    WebDavProperty::PushKey => "C:pushkey",
+                    WebDavProperty::Transports => "P:transports",
+                    WebDavProperty::Topic => "P:topic",
+                    WebDavProperty::SupportedTriggers => "P:supported-triggers",
+                    WebDavProperty::CollectionUsage => "S:collection-usage",
                 },
                 DavProperty::CardDav(prop) => match prop {
                     CardDavProperty::AddressbookDescription => "B:addressbook-description",
@@ -194,6 +220,15 @@ impl DavProperty {
                     CalDavProperty::CalendarData(_) => "A:calendar-data",
                     CalDavProperty::TimezoneServiceSet => "A:timezone-service-set",
                     CalDavProperty::TimezoneId => "A:calendar-timezone-id",
+                    CalDavProperty::ScheduleCalendarTransp => "A:schedule-calendar-transp",
+                    CalDavProperty::CalendarAvailability => "A:calendar-availability",
+                    CalDavProperty::DefaultAlarmVEventDate => "A:default-alarm-vevent-date",
+                    CalDavProperty::DefaultAlarmVEventDateTime => "A:default-alarm-vevent-datetime",
+                    CalDavProperty::CalendarColor => "I:calendar-color",
+                    CalDavProperty::CalendarOrder => "I:calendar-order",
+                    CalDavProperty::Invite => "C:invite",
+                    CalDavProperty::AllowedSharingModes => "C:allowed-sharing-modes",
+                    CalDavProperty::NotificationType => "C:notificationtype",
                 },
                 DavProperty::Principal(prop) => match prop {
                     PrincipalProperty::AlternateURISet => "D:alternate-URI-set",
@@ -203,23 +238,64 @@ impl DavProperty {
                     PrincipalProperty::CalendarHomeSet => "A:calendar-home-set",
                     PrincipalProperty::AddressbookHomeSet => "B:addressbook-home-set",
                     PrincipalProperty::PrincipalAddress => "B:principal-address",
+                    PrincipalProperty::ScheduleInboxURL => "A:schedule-inbox-URL",
+                    PrincipalProperty::ScheduleOutboxURL => "A:schedule-outbox-URL",
+                    PrincipalProperty::CalendarUserAddressSet => "A:calendar-user-address-set",
+                    PrincipalProperty::DirectoryGateway => "B:directory-gateway",
+                    PrincipalProperty::NotificationURL => "C:notification-URL",
                 },
                 DavProperty::DeadProperty(dead) => {
                     return (dead.name.as_str(), dead.attrs.as_deref())
                 }
             },
-            None,
+            match self {
+                DavProperty::CalDav(CalDavProperty::CalendarData(data)) => match data.data_type {
+                    CalendarDataType::ICalendar => None,
+                    CalendarDataType::JCal => {
+                        Some("content-type=\"application/calendar+json\" version=\"2.0\"")
+                    }
+                    CalendarDataType::XCal => {
+                        Some("content-type=\"application/calendar+xml\" version=\"2.0\"")
+                    }
+                },
+                _ => None,
+            },
         )
     }
 
     pub fn namespace(&self) -> Namespace {
         match self {
-            DavProperty::WebDav(WebDavProperty::GetCTag) => Namespace::CalendarServer,
-            DavProperty::CardDav(_)
-            | DavProperty::Principal(PrincipalProperty::AddressbookHomeSet) => Namespace::CardDav,
-            DavProperty::CalDav(_) | DavProperty::Principal(PrincipalProperty::CalendarHomeSet) => {
-                Namespace::CalDav
+            DavProperty::WebDav(
+                WebDavProperty::GetCTag | WebDavProperty::PushTransports | WebDavProperty::PushKey,
+            )
+            | DavProperty::CalDav(
+                CalDavProperty::Invite
+                | CalDavProperty::AllowedSharingModes
+                | CalDavProperty::NotificationType,
+            )
+            | DavProperty::Principal(PrincipalProperty::NotificationURL) => {
+                Namespace::CalendarServer
+            }
+            DavProperty::WebDav(WebDavProperty::CollectionUsage) => Namespace::Stalwart,
+            DavProperty::WebDav(
+                WebDavProperty::Transports
+                | WebDavProperty::Topic
+                | WebDavProperty::SupportedTriggers,
+            ) => Namespace::WebDavPush,
+            DavProperty::CalDav(CalDavProperty::CalendarColor | CalDavProperty::CalendarOrder) => {
+                Namespace::AppleIcal
             }
+            DavProperty::CardDav(_)
+            | DavProperty::Principal(
+                PrincipalProperty::AddressbookHomeSet | PrincipalProperty::DirectoryGateway,
+            ) => Namespace::CardDav,
+            DavProperty::CalDav(_)
+            | DavProperty::Principal(
+                PrincipalProperty::CalendarHomeSet
+                | PrincipalProperty::ScheduleInboxURL
+                | PrincipalProperty::ScheduleOutboxURL
+                | PrincipalProperty::CalendarUserAddressSet,
+            ) => Namespace::CalDav,
             _ => Namespace::Dav,
         }
     }
@@ -245,6 +321,7 @@ impl Display for ReportSet {
             ReportSet::AclPrincipalPropSet => write!(f, "<D:acl-principal-prop-set/>"),
             ReportSet::PrincipalMatch => write!(f, "<D:principal-match/>"),
             ReportSet::PrincipalPropertySearch => write!(f, "<D:principal-property-search/>"),
+            ReportSet::VersionTree => write!(f, "<D:version-tree/>"),
             ReportSet::PrincipalSearchPropertySet => {
                 write!(f, "<D:principal-search-property-set/>")
             }
@@ -253,6 +330,81 @@ impl Display for ReportSet {
     }
 }
 
+impl Display for CollectionUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<S:bytes>{}</S:bytes><S:items>{}</S:items><S:largest>{}</S:largest>",
+            self.bytes, self.items, self.largest
+        )
+    }
+}
+
+impl Display for PushTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<C:transport type=\"APSD\"><C:subscription-url>{}</C:subscription-url><C:apsbundleid>",
+            self.subscription_url
+        )?;
+        self.topic.write_escaped_to(f)?;
+        write!(f, "</C:apsbundleid><C:env>")?;
+        self.environment.write_escaped_to(f)?;
+        write!(
+            f,
+            "</C:env><C:refresh-interval>{}</C:refresh-interval></C:transport>",
+            self.refresh_interval
+        )
+    }
+}
+
+impl Display for Sharee {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<C:user>{}", self.href)?;
+        if let Some(common_name) = &self.common_name {
+            write!(f, "<C:common-name>")?;
+            common_name.write_escaped_to(f)?;
+            write!(f, "</C:common-name>")?;
+        }
+        write!(
+            f,
+            "{}<C:access>{}</C:access>",
+            self.status,
+            if self.read_write {
+                "<C:read-write/>"
+            } else {
+                "<C:read/>"
+            }
+        )?;
+        if let Some(summary) = &self.summary {
+            write!(f, "<C:summary>")?;
+            summary.write_escaped_to(f)?;
+            write!(f, "</C:summary>")?;
+        }
+        write!(f, "</C:user>")
+    }
+}
+
+impl Display for InviteStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InviteStatus::NoResponse => write!(f, "<C:invite-noresponse/>"),
+            InviteStatus::Accepted => write!(f, "<C:invite-accepted/>"),
+            InviteStatus::Declined => write!(f, "<C:invite-declined/>"),
+        }
+    }
+}
+
+impl Display for UsageItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<S:item>{}<S:size>{}</S:size></S:item>",
+            self.href, self.size
+        )
+    }
+}
+
 impl Display for DavProperty {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let (name, attrs) = self.tag_name();
@@ -332,6 +484,12 @@ impl From<Vec<ReportSet>> for DavValue {
     }
 }
 
+impl From<ScheduleCalendarTransp> for DavValue {
+    fn from(v: ScheduleCalendarTransp) -> Self {
+        DavValue::ScheduleCalendarTransp(v)
+    }
+}
+
 impl From<Vec<Comp>> for DavValue {
     fn from(v: Vec<Comp>) -> Self {
         DavValue::Components(List(v))
@@ -398,6 +556,24 @@ impl From<AclRestrictions> for DavValue {
     }
 }
 
+impl From<CollectionUsage> for DavValue {
+    fn from(v: CollectionUsage) -> Self {
+        DavValue::CollectionUsage(v)
+    }
+}
+
+impl From<Vec<PushTransport>> for DavValue {
+    fn from(v: Vec<PushTransport>) -> Self {
+        DavValue::PushTransports(List(v))
+    }
+}
+
+impl From<Vec<Sharee>> for DavValue {
+    fn from(v: Vec<Sharee>) -> Self {
+        DavValue::Invite(List(v))
+    }
+}
+
 impl From<DeadProperty> for DavValue {
     fn from(v: DeadProperty) -> Self {
         DavValue::DeadProperty(v)
@@ -0,0 +1,55 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::fmt::Display;
+
+use super::XmlEscape;
+use crate::schema::{
+    response::{PushMessage, SyncToken},
+    Namespace, Namespaces,
+};
+
+impl Display for PushMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><P:push-message {}><P:topic>",
+            self.namespaces
+        )?;
+        self.topic.write_escaped_to(f)?;
+        write!(f, "</P:topic>")?;
+        if let Some(sync_token) = &self.content_update {
+            write!(f, "<P:content-update>{sync_token}</P:content-update>")?;
+        }
+        if self.property_update {
+            write!(f, "<P:property-update/>")?;
+        }
+        write!(f, "</P:push-message>")
+    }
+}
+
+impl PushMessage {
+    pub fn new(topic: impl Into<String>) -> Self {
+        let mut namespaces = Namespaces::default();
+        namespaces.set(Namespace::WebDavPush);
+        Self {
+            namespaces,
+            topic: topic.into(),
+            content_update: None,
+            property_update: false,
+        }
+    }
+
+    pub fn with_content_update(mut self, sync_token: impl Into<String>) -> Self {
+        self.content_update = Some(SyncToken(sync_token.into()));
+        self
+    }
+
+    pub fn with_property_update(mut self) -> Self {
+        self.property_update = true;
+        self
+    }
+}
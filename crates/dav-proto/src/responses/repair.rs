@@ -0,0 +1,64 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::fmt::Display;
+
+use crate::schema::response::{List, Repair, RepairAction, RepairLog};
+
+use super::XmlEscape;
+
+pub(crate) const NAMESPACE: &str = "urn:stalwart:params:xml:ns:dav";
+
+impl Display for RepairLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><S:repair-log xmlns:S=\"{NAMESPACE}\">{}</S:repair-log>",
+            self.repairs
+        )
+    }
+}
+
+impl Display for Repair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<S:repair><S:property>")?;
+        self.property.write_escaped_to(f)?;
+        write!(
+            f,
+            "</S:property><S:line>{}</S:line><S:action>{}</S:action></S:repair>",
+            self.line,
+            self.action.as_str()
+        )
+    }
+}
+
+impl RepairAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RepairAction::Added => "added",
+            RepairAction::Replaced => "replaced",
+            RepairAction::Removed => "removed",
+        }
+    }
+}
+
+impl RepairLog {
+    pub fn new(repairs: Vec<Repair>) -> Self {
+        RepairLog {
+            repairs: List(repairs),
+        }
+    }
+}
+
+impl Repair {
+    pub fn new(property: impl Into<String>, line: usize, action: RepairAction) -> Self {
+        Repair {
+            property: property.into(),
+            line,
+            action,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::fmt::Display;
+
+use mail_parser::DateTime;
+
+use super::XmlEscape;
+use crate::schema::{
+    property::Sharee,
+    response::{Href, InviteNotification, SharedAsResponse},
+    Namespace, Namespaces,
+};
+
+impl Display for InviteNotification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dt = DateTime::from_timestamp(self.dtstamp);
+        write!(
+            f,
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>",
+                "<C:notification {}><C:dtstamp>{:04}{:02}{:02}T{:02}{:02}{:02}Z</C:dtstamp>",
+                "<C:invite-notification><C:uid>"
+            ),
+            self.namespaces, dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second,
+        )?;
+        self.uid.write_escaped_to(f)?;
+        write!(
+            f,
+            "</C:uid>{}{}<C:access>{}</C:access><C:hosturl>{}</C:hosturl><C:organizer>{}",
+            self.sharee.href,
+            self.sharee.status,
+            if self.sharee.read_write {
+                "<C:read-write/>"
+            } else {
+                "<C:read/>"
+            },
+            self.host_url,
+            self.organizer,
+        )?;
+        if let Some(name) = &self.organizer_name {
+            write!(f, "<C:common-name>")?;
+            name.write_escaped_to(f)?;
+            write!(f, "</C:common-name>")?;
+        }
+        write!(f, "</C:organizer>")?;
+        if let Some(summary) = &self.sharee.summary {
+            write!(f, "<C:summary>")?;
+            summary.write_escaped_to(f)?;
+            write!(f, "</C:summary>")?;
+        }
+        write!(f, "</C:invite-notification></C:notification>")
+    }
+}
+
+impl InviteNotification {
+    pub fn new(
+        uid: impl Into<String>,
+        dtstamp: i64,
+        sharee: Sharee,
+        host_url: impl Into<String>,
+        organizer: Href,
+    ) -> Self {
+        let mut namespaces = Namespaces::default();
+        namespaces.set(Namespace::CalendarServer);
+        Self {
+            namespaces,
+            uid: uid.into(),
+            dtstamp,
+            sharee,
+            host_url: Href(host_url.into()),
+            organizer,
+            organizer_name: None,
+        }
+    }
+
+    pub fn with_organizer_name(mut self, name: impl Into<String>) -> Self {
+        self.organizer_name = Some(name.into());
+        self
+    }
+}
+
+impl Display for SharedAsResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><C:shared-as {}>{}</C:shared-as>",
+            self.namespaces, self.href
+        )
+    }
+}
+
+impl SharedAsResponse {
+    pub fn new(href: impl Into<String>) -> Self {
+        let mut namespaces = Namespaces::default();
+        namespaces.set(Namespace::CalendarServer);
+        Self {
+            namespaces,
+            href: Href(href.into()),
+        }
+    }
+}
@@ -26,6 +26,9 @@ pub enum Namespace {
     CalDav,
     CardDav,
     CalendarServer,
+    AppleIcal,
+    WebDavPush,
+    Stalwart,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -34,6 +37,9 @@ pub struct Namespaces {
     pub(crate) cal: bool,
     pub(crate) card: bool,
     pub(crate) cs: bool,
+    pub(crate) ical: bool,
+    pub(crate) push: bool,
+    pub(crate) sw: bool,
 }
 
 impl Namespaces {
@@ -42,6 +48,9 @@ impl Namespaces {
             Namespace::CalDav => self.cal = true,
             Namespace::CardDav => self.card = true,
             Namespace::CalendarServer => self.cs = true,
+            Namespace::AppleIcal => self.ical = true,
+            Namespace::WebDavPush => self.push = true,
+            Namespace::Stalwart => self.sw = true,
             Namespace::Dav => {}
         }
     }
@@ -54,7 +63,11 @@ impl Namespace {
             "urn:ietf:params:xml:ns:caldav" => Namespace::CalDav,
             "urn:ietf:params:xml:ns:carddav" => Namespace::CardDav,
             "http://calendarserver.org/ns/" => Namespace::CalendarServer,
-            "http://calendarserver.org/ns" => Namespace::CalendarServer
+            "http://calendarserver.org/ns" => Namespace::CalendarServer,
+            "http://apple.com/ns/ical/" => Namespace::AppleIcal,
+            "http://apple.com/ns/ical" => Namespace::AppleIcal,
+            "https://bitfire.at/webdav-push" => Namespace::WebDavPush,
+            "urn:stalwart:params:xml:ns:dav" => Namespace::Stalwart
         )
     }
 
@@ -64,6 +77,9 @@ impl Namespace {
             Namespace::CalDav => "A",
             Namespace::CardDav => "B",
             Namespace::CalendarServer => "C",
+            Namespace::AppleIcal => "I",
+            Namespace::WebDavPush => "P",
+            Namespace::Stalwart => "S",
         }
     }
 
@@ -73,6 +89,9 @@ impl Namespace {
             Namespace::CalDav => "urn:ietf:params:xml:ns:caldav",
             Namespace::CardDav => "urn:ietf:params:xml:ns:carddav",
             Namespace::CalendarServer => "http://calendarserver.org/ns/",
+            Namespace::AppleIcal => "http://apple.com/ns/ical/",
+            Namespace::WebDavPush => "https://bitfire.at/webdav-push",
+            Namespace::Stalwart => "urn:stalwart:params:xml:ns:dav",
         }
     }
 }
@@ -87,6 +106,7 @@ impl AsRef<str> for Namespace {
 #[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
 pub enum Element {
     Abstract,
+    Access,
     Ace,
     Acl,
     AclPrincipalPropSet,
@@ -113,6 +133,7 @@ pub enum Element {
     AllowedAttendeeSchedulingObjectChange,
     AllowedOrganizerSchedulingObjectChange,
     AllowedPrincipal,
+    AllowedSharingModes,
     Allprop,
     AlternateUriSet,
     And,
@@ -120,6 +141,7 @@ pub enum Element {
     ApplyToVersion,
     ApplyToPrincipalCollectionSet,
     Ascending,
+    AuthSecret,
     Authenticated,
     AutoMergeSet,
     AutoUpdate,
@@ -138,15 +160,18 @@ pub enum Element {
     BindingName,
     Calendar,
     CalendarAvailability,
+    CalendarColor,
     CalendarData,
     CalendarDescription,
     CalendarHomeSet,
     CalendarMultiget,
+    CalendarOrder,
     CalendarQuery,
     CalendarTimezone,
     CalendarTimezoneId,
     CalendarUserAddressSet,
     CalendarUserType,
+    CanBeShared,
     Caseless,
     ChangedVersion,
     CheckedIn,
@@ -162,14 +187,18 @@ pub enum Element {
     CheckoutSet,
     CheckoutUnlockedCheckin,
     Collection,
+    CollectionUsage,
     Comment,
     CommonAncestor,
+    CommonName,
     Comp,
     CompFilter,
     CompareBaseline,
     CompareBaselineReport,
     ConflictPreview,
     Contains,
+    ContentEncoding,
+    ContentUpdate,
     Creationdate,
     CreatorDisplayname,
     CurrentActivitySet,
@@ -177,6 +206,8 @@ pub enum Element {
     CurrentUserPrivilegeSet,
     CurrentWorkspaceSet,
     Datatype,
+    DefaultAlarmVeventDate,
+    DefaultAlarmVeventDatetime,
     DefaultCalendarNeeded,
     DeletedVersion,
     Deny,
@@ -184,13 +215,17 @@ pub enum Element {
     Depth,
     Descending,
     Description,
+    Directory,
+    DirectoryGateway,
     Discouraged,
     Displayname,
+    Dtstamp,
     Eq,
     Error,
     Exclusive,
     Expand,
     ExpandProperty,
+    Expires,
     Filter,
     First,
     Forbidden,
@@ -211,13 +246,21 @@ pub enum Element {
     GroupMembership,
     Gt,
     Gte,
+    Hosturl,
     Href,
     IgnorePreview,
+    InReplyTo,
     Include,
     IncludeVersions,
     Inherited,
     InheritedAclSet,
     Invert,
+    Invite,
+    InviteAccepted,
+    InviteDeclined,
+    InviteNoresponse,
+    InviteNotification,
+    InviteReply,
     IsCollection,
     IsDefined,
     IsNotDefined,
@@ -289,6 +332,9 @@ pub enum Element {
     NoUidConflict,
     Not,
     NotSupportedPrivilege,
+    Notification,
+    NotificationUrl,
+    Notificationtype,
     Nresults,
     Opaque,
     Opdesc,
@@ -306,6 +352,7 @@ pub enum Element {
     OrderingType,
     Orderpatch,
     OrderpatchResponse,
+    Organizer,
     Owner,
     ParamFilter,
     Parent,
@@ -329,11 +376,17 @@ pub enum Element {
     Properties,
     Property,
     PropertySearch,
+    PropertyUpdate,
     Propertyupdate,
     Propfind,
     Propname,
     Propstat,
     Protected,
+    PushMessage,
+    PushRegister,
+    PushResource,
+    PushTransports,
+    Pushkey,
     QuerySchema,
     QuerySchemaDiscovery,
     QuotaAvailableBytes,
@@ -342,6 +395,7 @@ pub enum Element {
     ReadAcl,
     ReadCurrentUserPrivilegeSet,
     ReadFreeBusy,
+    ReadWrite,
     Rebind,
     RebindResponse,
     Recipient,
@@ -384,13 +438,18 @@ pub enum Element {
     Selectable,
     Self_,
     Set,
+    Share,
     Shared,
+    SharedAs,
     Sortable,
     Source,
     Status,
     SubactivitySet,
     SubbaselineSet,
+    Subscription,
+    SubscriptionPublicKey,
     SuccessorSet,
+    Summary,
     SupportedAddressData,
     SupportedCalendarComponentSet,
     SupportedCalendarData,
@@ -409,6 +468,7 @@ pub enum Element {
     SupportedReportSet,
     SupportedRscale,
     SupportedRscaleSet,
+    SupportedTriggers,
     Supportedlock,
     SyncCollection,
     SyncLevel,
@@ -421,8 +481,12 @@ pub enum Element {
     Timezone,
     TimezoneId,
     TimezoneServiceSet,
+    Topic,
     Transparent,
+    Transports,
+    Trigger,
     TypedLiteral,
+    Uid,
     Unauthenticated,
     Unbind,
     UnbindResponse,
@@ -436,6 +500,7 @@ pub enum Element {
     Updateredirectref,
     UpdateredirectrefResponse,
     Url,
+    User,
     Username,
     ValidOrganizer,
     ValidScheduleDefaultCalendarUrl,
@@ -452,6 +517,8 @@ pub enum Element {
     VersionName,
     VersionSet,
     VersionTree,
+    WebPush,
+    WebPushSubscription,
     Where,
     Workspace,
     WorkspaceCheckoutSet,
@@ -467,6 +534,7 @@ impl Element {
         hashify::map!(value,
             Element,
             "abstract" => Element::Abstract,
+            "access" => Element::Access,
             "ace" => Element::Ace,
             "acl" => Element::Acl,
             "acl-principal-prop-set" => Element::AclPrincipalPropSet,
@@ -493,6 +561,7 @@ impl Element {
             "allowed-attendee-scheduling-object-change" => Element::AllowedAttendeeSchedulingObjectChange,
             "allowed-organizer-scheduling-object-change" => Element::AllowedOrganizerSchedulingObjectChange,
             "allowed-principal" => Element::AllowedPrincipal,
+            "allowed-sharing-modes" => Element::AllowedSharingModes,
             "allprop" => Element::Allprop,
             "alternate-URI-set" => Element::AlternateUriSet,
             "and" => Element::And,
@@ -500,6 +569,7 @@ impl Element {
             "apply-to-version" => Element::ApplyToVersion,
             "apply-to-principal-collection-set" => Element::ApplyToPrincipalCollectionSet,
             "ascending" => Element::Ascending,
+            "auth-secret" => Element::AuthSecret,
             "authenticated" => Element::Authenticated,
             "auto-merge-set" => Element::AutoMergeSet,
             "auto-update" => Element::AutoUpdate,
@@ -518,15 +588,18 @@ impl Element {
             "binding-name" => Element::BindingName,
             "calendar" => Element::Calendar,
             "calendar-availability" => Element::CalendarAvailability,
+            "calendar-color" => Element::CalendarColor,
             "calendar-data" => Element::CalendarData,
             "calendar-description" => Element::CalendarDescription,
             "calendar-home-set" => Element::CalendarHomeSet,
             "calendar-multiget" => Element::CalendarMultiget,
+            "calendar-order" => Element::CalendarOrder,
             "calendar-query" => Element::CalendarQuery,
             "calendar-timezone" => Element::CalendarTimezone,
             "calendar-timezone-id" => Element::CalendarTimezoneId,
             "calendar-user-address-set" => Element::CalendarUserAddressSet,
             "calendar-user-type" => Element::CalendarUserType,
+            "can-be-shared" => Element::CanBeShared,
             "caseless" => Element::Caseless,
             "changed-version" => Element::ChangedVersion,
             "checked-in" => Element::CheckedIn,
@@ -542,14 +615,18 @@ impl Element {
             "checkout-set" => Element::CheckoutSet,
             "checkout-unlocked-checkin" => Element::CheckoutUnlockedCheckin,
             "collection" => Element::Collection,
+            "collection-usage" => Element::CollectionUsage,
             "comment" => Element::Comment,
             "common-ancestor" => Element::CommonAncestor,
+            "common-name" => Element::CommonName,
             "comp" => Element::Comp,
             "comp-filter" => Element::CompFilter,
             "compare-baseline" => Element::CompareBaseline,
             "compare-baseline-report" => Element::CompareBaselineReport,
             "conflict-preview" => Element::ConflictPreview,
             "contains" => Element::Contains,
+            "content-encoding" => Element::ContentEncoding,
+            "content-update" => Element::ContentUpdate,
             "creationdate" => Element::Creationdate,
             "creator-displayname" => Element::CreatorDisplayname,
             "current-activity-set" => Element::CurrentActivitySet,
@@ -557,6 +634,8 @@ impl Element {
             "current-user-privilege-set" => Element::CurrentUserPrivilegeSet,
             "current-workspace-set" => Element::CurrentWorkspaceSet,
             "datatype" => Element::Datatype,
+            "default-alarm-vevent-date" => Element::DefaultAlarmVeventDate,
+            "default-alarm-vevent-datetime" => Element::DefaultAlarmVeventDatetime,
             "default-calendar-needed" => Element::DefaultCalendarNeeded,
             "deleted-version" => Element::DeletedVersion,
             "deny" => Element::Deny,
@@ -564,13 +643,17 @@ impl Element {
             "depth" => Element::Depth,
             "descending" => Element::Descending,
             "description" => Element::Description,
+            "directory" => Element::Directory,
+            "directory-gateway" => Element::DirectoryGateway,
             "discouraged" => Element::Discouraged,
             "displayname" => Element::Displayname,
+            "dtstamp" => Element::Dtstamp,
             "eq" => Element::Eq,
             "error" => Element::Error,
             "exclusive" => Element::Exclusive,
             "expand" => Element::Expand,
             "expand-property" => Element::ExpandProperty,
+            "expires" => Element::Expires,
             "filter" => Element::Filter,
             "first" => Element::First,
             "forbidden" => Element::Forbidden,
@@ -591,13 +674,21 @@ impl Element {
             "group-membership" => Element::GroupMembership,
             "gt" => Element::Gt,
             "gte" => Element::Gte,
+            "hosturl" => Element::Hosturl,
             "href" => Element::Href,
             "ignore-preview" => Element::IgnorePreview,
+            "in-reply-to" => Element::InReplyTo,
             "include" => Element::Include,
             "include-versions" => Element::IncludeVersions,
             "inherited" => Element::Inherited,
             "inherited-acl-set" => Element::InheritedAclSet,
             "invert" => Element::Invert,
+            "invite" => Element::Invite,
+            "invite-accepted" => Element::InviteAccepted,
+            "invite-declined" => Element::InviteDeclined,
+            "invite-noresponse" => Element::InviteNoresponse,
+            "invite-notification" => Element::InviteNotification,
+            "invite-reply" => Element::InviteReply,
             "is-collection" => Element::IsCollection,
             "is-defined" => Element::IsDefined,
             "is-not-defined" => Element::IsNotDefined,
@@ -669,6 +760,9 @@ impl Element {
             "no-uid-conflict" => Element::NoUidConflict,
             "not" => Element::Not,
             "not-supported-privilege" => Element::NotSupportedPrivilege,
+            "notification" => Element::Notification,
+            "notification-URL" => Element::NotificationUrl,
+            "notificationtype" => Element::Notificationtype,
             "nresults" => Element::Nresults,
             "opaque" => Element::Opaque,
             "opdesc" => Element::Opdesc,
@@ -686,6 +780,7 @@ impl Element {
             "ordering-type" => Element::OrderingType,
             "orderpatch" => Element::Orderpatch,
             "orderpatch-response" => Element::OrderpatchResponse,
+            "organizer" => Element::Organizer,
             "owner" => Element::Owner,
             "param-filter" => Element::ParamFilter,
             "parent" => Element::Parent,
@@ -709,11 +804,17 @@ impl Element {
             "properties" => Element::Properties,
             "property" => Element::Property,
             "property-search" => Element::PropertySearch,
+            "property-update" => Element::PropertyUpdate,
             "propertyupdate" => Element::Propertyupdate,
             "propfind" => Element::Propfind,
             "propname" => Element::Propname,
             "propstat" => Element::Propstat,
             "protected" => Element::Protected,
+            "push-message" => Element::PushMessage,
+            "push-register" => Element::PushRegister,
+            "push-resource" => Element::PushResource,
+            "push-transports" => Element::PushTransports,
+            "pushkey" => Element::Pushkey,
             "query-schema" => Element::QuerySchema,
             "query-schema-discovery" => Element::QuerySchemaDiscovery,
             "quota-available-bytes" => Element::QuotaAvailableBytes,
@@ -722,6 +823,7 @@ impl Element {
             "read-acl" => Element::ReadAcl,
             "read-current-user-privilege-set" => Element::ReadCurrentUserPrivilegeSet,
             "read-free-busy" => Element::ReadFreeBusy,
+            "read-write" => Element::ReadWrite,
             "rebind" => Element::Rebind,
             "rebind-response" => Element::RebindResponse,
             "recipient" => Element::Recipient,
@@ -764,13 +866,18 @@ impl Element {
             "selectable" => Element::Selectable,
             "self" => Element::Self_,
             "set" => Element::Set,
+            "share" => Element::Share,
             "shared" => Element::Shared,
+            "shared-as" => Element::SharedAs,
             "sortable" => Element::Sortable,
             "source" => Element::Source,
             "status" => Element::Status,
             "subactivity-set" => Element::SubactivitySet,
             "subbaseline-set" => Element::SubbaselineSet,
+            "subscription" => Element::Subscription,
+            "subscription-public-key" => Element::SubscriptionPublicKey,
             "successor-set" => Element::SuccessorSet,
+            "summary" => Element::Summary,
             "supported-address-data" => Element::SupportedAddressData,
             "supported-calendar-component-set" => Element::SupportedCalendarComponentSet,
             "supported-calendar-data" => Element::SupportedCalendarData,
@@ -789,6 +896,7 @@ impl Element {
             "supported-report-set" => Element::SupportedReportSet,
             "supported-rscale" => Element::SupportedRscale,
             "supported-rscale-set" => Element::SupportedRscaleSet,
+            "supported-triggers" => Element::SupportedTriggers,
             "supportedlock" => Element::Supportedlock,
             "sync-collection" => Element::SyncCollection,
             "sync-level" => Element::SyncLevel,
@@ -801,8 +909,12 @@ impl Element {
             "timezone" => Element::Timezone,
             "timezone-id" => Element::TimezoneId,
             "timezone-service-set" => Element::TimezoneServiceSet,
+            "topic" => Element::Topic,
             "transparent" => Element::Transparent,
+            "transports" => Element::Transports,
+            "trigger" => Element::Trigger,
             "typed-literal" => Element::TypedLiteral,
+            "uid" => Element::Uid,
             "unauthenticated" => Element::Unauthenticated,
             "unbind" => Element::Unbind,
             "unbind-response" => Element::UnbindResponse,
@@ -816,6 +928,7 @@ impl Element {
             "updateredirectref" => Element::Updateredirectref,
             "updateredirectref-response" => Element::UpdateredirectrefResponse,
             "url" => Element::Url,
+            "user" => Element::User,
             "username" => Element::Username,
             "valid-organizer" => Element::ValidOrganizer,
             "valid-schedule-default-calendar-URL" => Element::ValidScheduleDefaultCalendarUrl,
@@ -832,6 +945,8 @@ impl Element {
             "version-name" => Element::VersionName,
             "version-set" => Element::VersionSet,
             "version-tree" => Element::VersionTree,
+            "web-push" => Element::WebPush,
+            "web-push-subscription" => Element::WebPushSubscription,
             "where" => Element::Where,
             "workspace" => Element::Workspace,
             "workspace-checkout-set" => Element::WorkspaceCheckoutSet,
@@ -848,6 +963,7 @@ impl AsRef<str> for Element {
     fn as_ref(&self) -> &str {
         match self {
             Element::Abstract => "abstract",
+            Element::Access => "access",
             Element::Ace => "ace",
             Element::Acl => "acl",
             Element::AclPrincipalPropSet => "acl-principal-prop-set",
@@ -878,6 +994,7 @@ impl AsRef<str> for Element {
                 "allowed-organizer-scheduling-object-change"
             }
             Element::AllowedPrincipal => "allowed-principal",
+            Element::AllowedSharingModes => "allowed-sharing-modes",
             Element::Allprop => "allprop",
             Element::AlternateUriSet => "alternate-URI-set",
             Element::And => "and",
@@ -885,6 +1002,7 @@ impl AsRef<str> for Element {
             Element::ApplyToVersion => "apply-to-version",
             Element::ApplyToPrincipalCollectionSet => "apply-to-principal-collection-set",
             Element::Ascending => "ascending",
+            Element::AuthSecret => "auth-secret",
             Element::Authenticated => "authenticated",
             Element::AutoMergeSet => "auto-merge-set",
             Element::AutoUpdate => "auto-update",
@@ -903,15 +1021,18 @@ impl AsRef<str> for Element {
             Element::BindingName => "binding-name",
             Element::Calendar => "calendar",
             Element::CalendarAvailability => "calendar-availability",
+            Element::CalendarColor => "calendar-color",
             Element::CalendarData => "calendar-data",
             Element::CalendarDescription => "calendar-description",
             Element::CalendarHomeSet => "calendar-home-set",
             Element::CalendarMultiget => "calendar-multiget",
+            Element::CalendarOrder => "calendar-order",
             Element::CalendarQuery => "calendar-query",
             Element::CalendarTimezone => "calendar-timezone",
             Element::CalendarTimezoneId => "calendar-timezone-id",
             Element::CalendarUserAddressSet => "calendar-user-address-set",
             Element::CalendarUserType => "calendar-user-type",
+            Element::CanBeShared => "can-be-shared",
             Element::Caseless => "caseless",
             Element::ChangedVersion => "changed-version",
             Element::CheckedIn => "checked-in",
@@ -927,14 +1048,18 @@ impl AsRef<str> for Element {
             Element::CheckoutSet => "checkout-set",
             Element::CheckoutUnlockedCheckin => "checkout-unlocked-checkin",
             Element::Collection => "collection",
+            Element::CollectionUsage => "collection-usage",
             Element::Comment => "comment",
             Element::CommonAncestor => "common-ancestor",
+            Element::CommonName => "common-name",
             Element::Comp => "comp",
             Element::CompFilter => "comp-filter",
             Element::CompareBaseline => "compare-baseline",
             Element::CompareBaselineReport => "compare-baseline-report",
             Element::ConflictPreview => "conflict-preview",
             Element::Contains => "contains",
+            Element::ContentEncoding => "content-encoding",
+            Element::ContentUpdate => "content-update",
             Element::Creationdate => "creationdate",
             Element::CreatorDisplayname => "creator-displayname",
             Element::CurrentActivitySet => "current-activity-set",
@@ -942,6 +1067,8 @@ impl AsRef<str> for Element {
             Element::CurrentUserPrivilegeSet => "current-user-privilege-set",
             Element::CurrentWorkspaceSet => "current-workspace-set",
             Element::Datatype => "datatype",
+            Element::DefaultAlarmVeventDate => "default-alarm-vevent-date",
+            Element::DefaultAlarmVeventDatetime => "default-alarm-vevent-datetime",
             Element::DefaultCalendarNeeded => "default-calendar-needed",
             Element::DeletedVersion => "deleted-version",
             Element::Deny => "deny",
@@ -949,13 +1076,17 @@ impl AsRef<str> for Element {
             Element::Depth => "depth",
             Element::Descending => "descending",
             Element::Description => "description",
+            Element::Directory => "directory",
+            Element::DirectoryGateway => "directory-gateway",
             Element::Discouraged => "discouraged",
             Element::Displayname => "displayname",
+            Element::Dtstamp => "dtstamp",
             Element::Eq => "eq",
             Element::Error => "error",
             Element::Exclusive => "exclusive",
             Element::Expand => "expand",
             Element::ExpandProperty => "expand-property",
+            Element::Expires => "expires",
             Element::Filter => "filter",
             Element::First => "first",
             Element::Forbidden => "forbidden",
@@ -976,13 +1107,21 @@ impl AsRef<str> for Element {
             Element::GroupMembership => "group-membership",
             Element::Gt => "gt",
             Element::Gte => "gte",
+            Element::Hosturl => "hosturl",
             Element::Href => "href",
             Element::IgnorePreview => "ignore-preview",
+            Element::InReplyTo => "in-reply-to",
             Element::Include => "include",
             Element::IncludeVersions => "include-versions",
             Element::Inherited => "inherited",
             Element::InheritedAclSet => "inherited-acl-set",
             Element::Invert => "invert",
+            Element::Invite => "invite",
+            Element::InviteAccepted => "invite-accepted",
+            Element::InviteDeclined => "invite-declined",
+            Element::InviteNoresponse => "invite-noresponse",
+            Element::InviteNotification => "invite-notification",
+            Element::InviteReply => "invite-reply",
             Element::IsCollection => "is-collection",
             Element::IsDefined => "is-defined",
             Element::IsNotDefined => "is-not-defined",
@@ -1054,6 +1193,9 @@ impl AsRef<str> for Element {
             Element::NoUidConflict => "no-uid-conflict",
             Element::Not => "not",
             Element::NotSupportedPrivilege => "not-supported-privilege",
+            Element::Notification => "notification",
+            Element::NotificationUrl => "notification-URL",
+            Element::Notificationtype => "notificationtype",
             Element::Nresults => "nresults",
             Element::Opaque => "opaque",
             Element::Opdesc => "opdesc",
@@ -1071,6 +1213,7 @@ impl AsRef<str> for Element {
             Element::OrderingType => "ordering-type",
             Element::Orderpatch => "orderpatch",
             Element::OrderpatchResponse => "orderpatch-response",
+            Element::Organizer => "organizer",
             Element::Owner => "owner",
             Element::ParamFilter => "param-filter",
             Element::Parent => "parent",
@@ -1094,11 +1237,17 @@ impl AsRef<str> for Element {
             Element::Properties => "properties",
             Element::Property => "property",
             Element::PropertySearch => "property-search",
+            Element::PropertyUpdate => "property-update",
             Element::Propertyupdate => "propertyupdate",
             Element::Propfind => "propfind",
             Element::Propname => "propname",
             Element::Propstat => "propstat",
             Element::Protected => "protected",
+            Element::PushMessage => "push-message",
+            Element::PushRegister => "push-register",
+            Element::PushResource => "push-resource",
+            Element::PushTransports => "push-transports",
+            Element::Pushkey => "pushkey",
             Element::QuerySchema => "query-schema",
             Element::QuerySchemaDiscovery => "query-schema-discovery",
             Element::QuotaAvailableBytes => "quota-available-bytes",
@@ -1107,6 +1256,7 @@ impl AsRef<str> for Element {
             Element::ReadAcl => "read-acl",
             Element::ReadCurrentUserPrivilegeSet => "read-current-user-privilege-set",
             Element::ReadFreeBusy => "read-free-busy",
+            Element::ReadWrite => "read-write",
             Element::Rebind => "rebind",
             Element::RebindResponse => "rebind-response",
             Element::Recipient => "recipient",
@@ -1149,13 +1299,18 @@ impl AsRef<str> for Element {
             Element::Selectable => "selectable",
             Element::Self_ => "self",
             Element::Set => "set",
+            Element::Share => "share",
             Element::Shared => "shared",
+            Element::SharedAs => "shared-as",
             Element::Sortable => "sortable",
             Element::Source => "source",
             Element::Status => "status",
             Element::SubactivitySet => "subactivity-set",
             Element::SubbaselineSet => "subbaseline-set",
+            Element::Subscription => "subscription",
+            Element::SubscriptionPublicKey => "subscription-public-key",
             Element::SuccessorSet => "successor-set",
+            Element::Summary => "summary",
             Element::SupportedAddressData => "supported-address-data",
             Element::SupportedCalendarComponentSet => "supported-calendar-component-set",
             Element::SupportedCalendarData => "supported-calendar-data",
@@ -1174,6 +1329,7 @@ impl AsRef<str> for Element {
             Element::SupportedReportSet => "supported-report-set",
             Element::SupportedRscale => "supported-rscale",
             Element::SupportedRscaleSet => "supported-rscale-set",
+            Element::SupportedTriggers => "supported-triggers",
             Element::Supportedlock => "supportedlock",
             Element::SyncCollection => "sync-collection",
             Element::SyncLevel => "sync-level",
@@ -1186,8 +1342,12 @@ impl AsRef<str> for Element {
             Element::Timezone => "timezone",
             Element::TimezoneId => "timezone-id",
             Element::TimezoneServiceSet => "timezone-service-set",
+            Element::Topic => "topic",
             Element::Transparent => "transparent",
+            Element::Transports => "transports",
+            Element::Trigger => "trigger",
             Element::TypedLiteral => "typed-literal",
+            Element::Uid => "uid",
             Element::Unauthenticated => "unauthenticated",
             Element::Unbind => "unbind",
             Element::UnbindResponse => "unbind-response",
@@ -1201,6 +1361,7 @@ impl AsRef<str> for Element {
             Element::Updateredirectref => "updateredirectref",
             Element::UpdateredirectrefResponse => "updateredirectref-response",
             Element::Url => "url",
+            Element::User => "user",
             Element::Username => "username",
             Element::ValidOrganizer => "valid-organizer",
             Element::ValidScheduleDefaultCalendarUrl => "valid-schedule-default-calendar-URL",
@@ -1217,6 +1378,8 @@ impl AsRef<str> for Element {
             Element::VersionName => "version-name",
             Element::VersionSet => "version-set",
             Element::VersionTree => "version-tree",
+            Element::WebPush => "web-push",
+            Element::WebPushSubscription => "web-push-subscription",
             Element::Where => "where",
             Element::Workspace => "workspace",
             Element::WorkspaceCheckoutSet => "workspace-checkout-set",
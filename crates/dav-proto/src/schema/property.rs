@@ -49,6 +49,8 @@ pub enum WebDavProperty {
     QuotaUsedBytes,
     // Sync properties
     SyncToken,
+    // Collection POST properties (RFC 5995)
+    AddMember,
     // ACL properties (all protected)
     Owner,
     Group,
@@ -58,8 +60,19 @@ pub enum WebDavProperty {
     AclRestrictions,
     InheritedAclSet,
     PrincipalCollectionSet,
+    // Versioning properties (all protected)
+    VersionHistory,
+    VersionName,
     // Apple proprietary properties
     GetCTag,
+    PushTransports,
+    PushKey,
+    // WebDAV-Push properties
+    Transports,
+    Topic,
+    SupportedTriggers,
+    // Stalwart proprietary properties
+    CollectionUsage,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -98,6 +111,17 @@ pub enum CalDavProperty {
     CalendarData(CalendarData),
     TimezoneServiceSet,
     TimezoneId,
+    ScheduleCalendarTransp,
+    CalendarAvailability,
+    DefaultAlarmVEventDate,
+    DefaultAlarmVEventDateTime,
+    // Apple proprietary properties
+    CalendarColor,
+    CalendarOrder,
+    // Calendar server sharing properties
+    Invite,
+    AllowedSharingModes,
+    NotificationType,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -111,15 +135,33 @@ pub enum PrincipalProperty {
     CalendarHomeSet,
     AddressbookHomeSet,
     PrincipalAddress,
+    ScheduleInboxURL,
+    ScheduleOutboxURL,
+    CalendarUserAddressSet,
+    DirectoryGateway,
+    NotificationURL,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 #[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
 pub struct CalendarData {
     pub properties: Vec<CalDavPropertyName>,
+    /// Components whose subcomponents are returned in full, either because
+    /// they were selected with `allcomp` or requested without a selection.
+    pub all_components: Vec<ICalendarComponentType>,
     pub expand: Option<TimeRange>,
     pub limit_recurrence: Option<TimeRange>,
     pub limit_freebusy: Option<TimeRange>,
+    pub data_type: CalendarDataType,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub enum CalendarDataType {
+    #[default]
+    ICalendar,
+    JCal,
+    XCal,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -163,11 +205,19 @@ pub enum DavValue {
     Href(List<Href>),
     Acl(List<Ace>),
     AclRestrictions(AclRestrictions),
+    CollectionUsage(CollectionUsage),
+    PushTransports(List<PushTransport>),
+    Invite(List<Sharee>),
     Response(Response),
     DeadProperty(DeadProperty),
     SupportedAddressData,
     SupportedCalendarData,
     SupportedCalendarComponentSet,
+    ScheduleCalendarTransp(ScheduleCalendarTransp),
+    AllowedSharingModes,
+    InviteNotification,
+    WebPushTransport,
+    SupportedTriggers,
     Null,
 }
 
@@ -185,12 +235,62 @@ pub enum ReportSet {
     PrincipalMatch,
     PrincipalPropertySearch,
     PrincipalSearchPropertySet,
+    VersionTree,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
 pub struct Comp(pub ICalendarComponentType);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub enum ScheduleCalendarTransp {
+    Opaque,
+    Transparent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct CollectionUsage {
+    pub bytes: u64,
+    pub items: u64,
+    pub largest: List<UsageItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct UsageItem {
+    pub href: Href,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct PushTransport {
+    pub subscription_url: Href,
+    pub topic: String,
+    pub environment: String,
+    pub refresh_interval: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct Sharee {
+    pub href: Href,
+    pub common_name: Option<String>,
+    pub status: InviteStatus,
+    pub read_write: bool,
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub enum InviteStatus {
+    NoResponse,
+    Accepted,
+    Declined,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
 pub struct SupportedCollation {
@@ -205,6 +305,10 @@ pub enum ResourceType {
     Principal,
     AddressBook,
     Calendar,
+    ScheduleInbox,
+    ScheduleOutbox,
+    Directory,
+    Notification,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -309,6 +413,28 @@ impl From<DavProperty> for DavPropertyValue {
     }
 }
 
+impl CalendarDataType {
+    pub fn try_parse(content_type: &str) -> Option<Self> {
+        let content_type = content_type
+            .split_once(';')
+            .map_or(content_type, |(ct, _)| ct)
+            .trim();
+        hashify::tiny_map_ignore_case!(content_type.as_bytes(),
+            "text/calendar" => CalendarDataType::ICalendar,
+            "application/calendar+json" => CalendarDataType::JCal,
+            "application/calendar+xml" => CalendarDataType::XCal,
+        )
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            CalendarDataType::ICalendar => "text/calendar",
+            CalendarDataType::JCal => "application/calendar+json",
+            CalendarDataType::XCal => "application/calendar+xml",
+        }
+    }
+}
+
 impl Rfc1123DateTime {
     pub fn new(timestamp: i64) -> Self {
         Self(timestamp)
@@ -12,7 +12,7 @@ use calcard::{
 use crate::Depth;
 
 use super::{
-    property::{DavProperty, DavValue, LockScope, LockType, TimeRange},
+    property::{CalDavProperty, DavProperty, DavValue, LockScope, LockType, TimeRange},
     response::Ace,
     Collation, MatchType,
 };
@@ -49,6 +49,66 @@ pub struct MkCol {
     pub props: Vec<DavPropertyValue>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct Bind {
+    pub is_rebind: bool,
+    pub segment: Option<String>,
+    pub href: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct Unbind {
+    pub segment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(test, serde(tag = "type"))]
+pub enum ShareRequest {
+    Share(Share),
+    InviteReply(InviteReply),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct Share {
+    pub set: Vec<ShareSet>,
+    pub remove: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct ShareSet {
+    pub href: String,
+    pub common_name: Option<String>,
+    pub summary: Option<String>,
+    pub read_write: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct InviteReply {
+    pub href: Option<String>,
+    pub accepted: bool,
+    pub host_url: Option<String>,
+    pub in_reply_to: Option<String>,
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct PushRegister {
+    pub push_resource: String,
+    pub content_encoding: Option<String>,
+    pub public_key: Option<String>,
+    pub auth_secret: Option<String>,
+    pub content_update: Option<Depth>,
+    pub property_update: Option<Depth>,
+    pub expires: Option<i64>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
 pub struct LockInfo {
@@ -72,6 +132,7 @@ pub enum Report {
     PrincipalMatch(PrincipalMatch),
     PrincipalPropertySearch(PrincipalPropertySearch),
     PrincipalSearchPropertySet,
+    VersionTree(VersionTree),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -133,6 +194,12 @@ pub struct MultiGet {
     pub hrefs: Vec<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub struct VersionTree {
+    pub properties: PropFind,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
 pub struct SyncCollection {
@@ -361,3 +428,17 @@ impl PropertyUpdate {
         !self.set.is_empty() || !self.remove.is_empty()
     }
 }
+
+impl CalendarQuery {
+    pub fn has_expansion(&self) -> bool {
+        match &self.properties {
+            PropFind::Prop(items) | PropFind::AllProp(items) => items.iter().any(|item| {
+                matches!(
+                    item,
+                    DavProperty::CalDav(CalDavProperty::CalendarData(data)) if data.expand.is_some()
+                )
+            }),
+            PropFind::PropName => false,
+        }
+    }
+}
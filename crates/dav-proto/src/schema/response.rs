@@ -13,7 +13,7 @@ use calcard::{
 use hyper::StatusCode;
 
 use super::{
-    property::{DavProperty, Privilege},
+    property::{DavProperty, Privilege, Sharee},
     request::{DavPropertyValue, Filter},
     Namespaces,
 };
@@ -21,6 +21,9 @@ use super::{
 pub struct MultiStatus {
     pub namespaces: Namespaces,
     pub response: List<Response>,
+    pub spilled: String,
+    pub prop_pool: Vec<Vec<DavPropertyValue>>,
+    pub data_pool: Vec<String>,
     pub response_description: Option<ResponseDescription>,
     pub sync_token: Option<SyncToken>,
 }
@@ -89,6 +92,28 @@ pub struct PropStat {
 #[repr(transparent)]
 pub struct Prop(pub List<DavPropertyValue>);
 
+pub struct InviteNotification {
+    pub namespaces: Namespaces,
+    pub uid: String,
+    pub dtstamp: i64,
+    pub sharee: Sharee,
+    pub host_url: Href,
+    pub organizer: Href,
+    pub organizer_name: Option<String>,
+}
+
+pub struct SharedAsResponse {
+    pub namespaces: Namespaces,
+    pub href: Href,
+}
+
+pub struct PushMessage {
+    pub namespaces: Namespaces,
+    pub topic: String,
+    pub content_update: Option<SyncToken>,
+    pub property_update: bool,
+}
+
 pub struct PropResponse {
     pub namespaces: Namespaces,
     pub properties: List<DavPropertyValue>,
@@ -169,7 +194,27 @@ pub struct PrincipalSearchProperty {
 
 pub struct ErrorResponse {
     pub namespaces: Namespaces,
-    pub error: Condition,
+    pub error: Option<Condition>,
+    pub code: &'static str,
+    pub description: &'static str,
+}
+
+pub struct RepairLog {
+    pub repairs: List<Repair>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub property: String,
+    pub line: usize,
+    pub action: RepairAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairAction {
+    Added,
+    Replaced,
+    Removed,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -208,6 +253,18 @@ pub enum BaseCondition {
     QuotaNotExceeded,
     ValidResourceType,
     ValidSyncToken,
+    MaxCollectionMembers(u32),
+    ValidResourceName(ResourceNameError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(test, derive(serde::Serialize, serde::Deserialize))]
+pub enum ResourceNameError {
+    TooLong,
+    InvalidEncoding,
+    ForbiddenCharacter,
+    Reserved,
+    NotNormalized,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -228,6 +285,7 @@ pub enum CalCondition {
     NoUidConflict(Href),
     InitializeCalendarCollection,
     SupportedCalendarData,
+    SupportedCalendarComponent,
     SupportedFilter(
         Vec<Filter<Vec<ICalendarComponentType>, ICalendarProperty, ICalendarParameterName>>,
     ),
@@ -237,6 +295,9 @@ pub enum CalCondition {
     MaxResourceSize(u32),
     MaxInstances,
     MaxAttendeesPerInstance,
+    ValidManagedIdParameter,
+    ValidRidParameter,
+    MaxAttachmentSize,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -316,6 +377,68 @@ impl BaseCondition {
             BaseCondition::QuotaNotExceeded => "QuotaNotExceeded",
             BaseCondition::ValidResourceType => "ValidResourceType",
             BaseCondition::ValidSyncToken => "ValidSyncToken",
+            BaseCondition::MaxCollectionMembers(_) => "MaxCollectionMembers",
+            BaseCondition::ValidResourceName(_) => "ValidResourceName",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            BaseCondition::NoConflictingLock(_) => "The resource is locked by another lock",
+            BaseCondition::CannotModifyProtectedProperty => {
+                "The request attempted to modify a protected property"
+            }
+            BaseCondition::LockTokenSubmitted(_) => {
+                "A lock token for the locked resource was not submitted"
+            }
+            BaseCondition::LockTokenMatchesRequestUri => {
+                "The lock token does not apply to the requested resource"
+            }
+            BaseCondition::NoExternalEntities => "External XML entities are not allowed",
+            BaseCondition::PreservedLiveProperties => {
+                "Live properties could not be preserved at the destination"
+            }
+            BaseCondition::PropFindFiniteDepth => "PROPFIND with infinite depth is not supported",
+            BaseCondition::ResourceMustBeNull => "A resource already exists at the destination",
+            BaseCondition::NeedPrivileges(_) => {
+                "The current user lacks the privileges required for this operation"
+            }
+            BaseCondition::NoAceConflict => "The access control entries conflict with each other",
+            BaseCondition::NoProtectedAceConflict => {
+                "The access control entries conflict with protected entries"
+            }
+            BaseCondition::NoInheritedAceConflict => {
+                "The access control entries conflict with inherited entries"
+            }
+            BaseCondition::LimitedNumberOfAces => "Too many access control entries",
+            BaseCondition::DenyBeforeGrant => "Deny entries must precede grant entries",
+            BaseCondition::GrantOnly => "Only grant entries are supported",
+            BaseCondition::NoInvert => "Inverted access control entries are not supported",
+            BaseCondition::NoAbstract => "Abstract privileges cannot be granted or denied",
+            BaseCondition::NotSupportedPrivilege => "The privilege is not supported",
+            BaseCondition::MissingRequiredPrincipal => "A required principal is missing",
+            BaseCondition::RecognizedPrincipal => "The principal is not recognized",
+            BaseCondition::AllowedPrincipal => "The principal is not allowed in this entry",
+            BaseCondition::NumberOfMatchesWithinLimit => {
+                "The number of matches exceeds the server limit"
+            }
+            BaseCondition::QuotaNotExceeded => "The storage quota has been exceeded",
+            BaseCondition::ValidResourceType => "The resource type is not valid here",
+            BaseCondition::ValidSyncToken => "The sync token is invalid or has expired",
+            BaseCondition::MaxCollectionMembers(_) => {
+                "The collection has reached its maximum number of members"
+            }
+            BaseCondition::ValidResourceName(err) => match err {
+                ResourceNameError::TooLong => "The resource name exceeds the maximum length",
+                ResourceNameError::InvalidEncoding => "The resource name is not valid UTF-8",
+                ResourceNameError::ForbiddenCharacter => {
+                    "The resource name contains forbidden characters"
+                }
+                ResourceNameError::Reserved => "The resource name is reserved",
+                ResourceNameError::NotNormalized => {
+                    "The resource name has leading or trailing whitespace"
+                }
+            },
         }
     }
 }
@@ -331,6 +454,7 @@ impl CalCondition {
             CalCondition::NoUidConflict(_) => "NoUidConflict",
             CalCondition::InitializeCalendarCollection => "InitializeCalendarCollection",
             CalCondition::SupportedCalendarData => "SupportedCalendarData",
+            CalCondition::SupportedCalendarComponent => "SupportedCalendarComponent",
             CalCondition::SupportedFilter(_) => "SupportedFilter",
             CalCondition::SupportedCollation(_) => "SupportedCollation",
             CalCondition::MinDateTime => "MinDateTime",
@@ -338,6 +462,43 @@ impl CalCondition {
             CalCondition::MaxResourceSize(_) => "MaxResourceSize",
             CalCondition::MaxInstances => "MaxInstances",
             CalCondition::MaxAttendeesPerInstance => "MaxAttendeesPerInstance",
+            CalCondition::ValidManagedIdParameter => "ValidManagedIdParameter",
+            CalCondition::ValidRidParameter => "ValidRidParameter",
+            CalCondition::MaxAttachmentSize => "MaxAttachmentSize",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            CalCondition::CalendarCollectionLocationOk => {
+                "Calendar collections cannot be created at this location"
+            }
+            CalCondition::ValidCalendarData => "The calendar data is not valid iCalendar",
+            CalCondition::ValidFilter => "The calendar query filter is not valid",
+            CalCondition::ValidCalendarObjectResource => {
+                "The calendar object does not meet the resource restrictions"
+            }
+            CalCondition::ValidTimezone => "The time zone is not valid",
+            CalCondition::NoUidConflict(_) => "An event with the same UID already exists",
+            CalCondition::InitializeCalendarCollection => {
+                "The calendar collection could not be initialized"
+            }
+            CalCondition::SupportedCalendarData => "The calendar data format is not supported",
+            CalCondition::SupportedCalendarComponent => {
+                "The calendar component type is not supported by the collection"
+            }
+            CalCondition::SupportedFilter(_) => "The calendar query filter is not supported",
+            CalCondition::SupportedCollation(_) => "The text collation is not supported",
+            CalCondition::MinDateTime => "The date is earlier than the minimum allowed",
+            CalCondition::MaxDateTime => "The date is later than the maximum allowed",
+            CalCondition::MaxResourceSize(_) => "The calendar object exceeds the maximum size",
+            CalCondition::MaxInstances => "The event has too many recurrence instances",
+            CalCondition::MaxAttendeesPerInstance => {
+                "The event has too many attendees per instance"
+            }
+            CalCondition::ValidManagedIdParameter => "The managed attachment does not exist",
+            CalCondition::ValidRidParameter => "The recurrence instance is not supported",
+            CalCondition::MaxAttachmentSize => "The attachment exceeds the maximum size",
         }
     }
 }
@@ -355,6 +516,23 @@ impl CardCondition {
             CardCondition::AddressBookCollectionLocationOk => "AddressBookCollectionLocationOk",
         }
     }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            CardCondition::SupportedAddressData => "The address data format is not supported",
+            CardCondition::SupportedAddressDataConversion => {
+                "The address data cannot be converted to the requested format"
+            }
+            CardCondition::SupportedFilter(_) => "The address book query filter is not supported",
+            CardCondition::SupportedCollation(_) => "The text collation is not supported",
+            CardCondition::ValidAddressData => "The address data is not valid vCard",
+            CardCondition::NoUidConflict(_) => "A contact with the same UID already exists",
+            CardCondition::MaxResourceSize(_) => "The contact exceeds the maximum size",
+            CardCondition::AddressBookCollectionLocationOk => {
+                "Address book collections cannot be created at this location"
+            }
+        }
+    }
 }
 
 impl Condition {
@@ -365,6 +543,14 @@ impl Condition {
             Condition::Card(card) => card.display_name(),
         }
     }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Condition::Base(base) => base.description(),
+            Condition::Cal(cal) => cal.description(),
+            Condition::Card(card) => card.description(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,170 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::{
+    Entry, Parser,
+    icalendar::{ICalendar, ICalendarComponentType},
+};
+use common::Server;
+use groupware::calendar::{ArchivedCalendar, Calendar};
+use jmap_proto::types::collection::Collection;
+use trc::AddContext;
+
+/// Default alarms of a calendar, as VALARM components ready to be inserted
+/// into events.
+#[derive(Debug, Default)]
+pub(crate) struct DefaultAlarms {
+    pub date: Option<String>,
+    pub date_time: Option<String>,
+}
+
+impl DefaultAlarms {
+    pub fn new(calendar: &ArchivedCalendar, account_id: u32) -> Self {
+        DefaultAlarms {
+            date: calendar
+                .default_alert(account_id, false)
+                .map(|alert| default_alarm_text(&alert.alert.to_string())),
+            date_time: calendar
+                .default_alert(account_id, true)
+                .map(|alert| default_alarm_text(&alert.alert.to_string())),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.date.is_none() && self.date_time.is_none()
+    }
+
+    /// Adds the default alarm to the events that do not include an alarm.
+    /// Returns `None` if nothing had to be changed.
+    pub fn inject(&self, ical: &str) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut out = String::with_capacity(ical.len() + 128);
+        let mut has_changes = false;
+        let mut depth = 0;
+        let mut has_alarm = false;
+        let mut is_date = false;
+
+        for line in ical.split_inclusive('\n') {
+            if !line.starts_with([' ', '\t']) {
+                let name = line
+                    .split([';', ':'])
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_ascii_lowercase();
+                let value = line
+                    .split_once(':')
+                    .map(|(_, value)| value.trim_end())
+                    .unwrap_or_default();
+
+                match name.as_str() {
+                    "begin" if depth > 0 || value.eq_ignore_ascii_case("VEVENT") => {
+                        if depth == 0 {
+                            has_alarm = false;
+                            is_date = false;
+                        } else if depth == 1 && value.eq_ignore_ascii_case("VALARM") {
+                            has_alarm = true;
+                        }
+                        depth += 1;
+                    }
+                    "end" if depth > 0 => {
+                        depth -= 1;
+                        if depth == 0 && !has_alarm {
+                            let alarm = if is_date { &self.date } else { &self.date_time };
+                            if let Some(alarm) = alarm {
+                                out.push_str(alarm);
+                                has_changes = true;
+                            }
+                        }
+                    }
+                    "dtstart" if depth == 1 => {
+                        is_date = line.to_ascii_uppercase().contains(";VALUE=DATE:")
+                            || (value.len() == 8 && value.bytes().all(|ch| ch.is_ascii_digit()));
+                    }
+                    _ => {}
+                }
+            }
+
+            out.push_str(line);
+        }
+
+        has_changes.then_some(out)
+    }
+}
+
+pub(crate) trait CalendarDefaultAlarms: Sync + Send {
+    fn fetch_default_alarms(
+        &self,
+        account_id: u32,
+        calendar_id: u32,
+    ) -> impl Future<Output = trc::Result<DefaultAlarms>> + Send;
+}
+
+impl CalendarDefaultAlarms for Server {
+    async fn fetch_default_alarms(
+        &self,
+        account_id: u32,
+        calendar_id: u32,
+    ) -> trc::Result<DefaultAlarms> {
+        if let Some(calendar_) = self
+            .get_archive(account_id, Collection::Calendar, calendar_id)
+            .await
+            .caused_by(trc::location!())?
+        {
+            Ok(DefaultAlarms::new(
+                calendar_
+                    .unarchive::<Calendar>()
+                    .caused_by(trc::location!())?,
+                account_id,
+            ))
+        } else {
+            Ok(DefaultAlarms::default())
+        }
+    }
+}
+
+/// Parses the value of a default alarm property, which contains one or more
+/// VALARM components without an enclosing VCALENDAR.
+pub(crate) fn parse_default_alarm(text: &str) -> Option<ICalendar> {
+    let text = text.trim();
+    let ical = if text
+        .get(..15)
+        .is_some_and(|begin| begin.eq_ignore_ascii_case("BEGIN:VCALENDAR"))
+    {
+        text.to_string()
+    } else {
+        format!("BEGIN:VCALENDAR\r\n{text}\r\nEND:VCALENDAR\r\n")
+    };
+
+    match Parser::new(&ical).entry() {
+        Entry::ICalendar(ical)
+            if ical.components.first().is_some_and(|root| {
+                !root.component_ids.is_empty()
+                    && root.component_ids.iter().all(|id| {
+                        ical.components[*id as usize].component_type
+                            == ICalendarComponentType::VAlarm
+                    })
+            }) =>
+        {
+            Some(ical)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the VALARM components of a stored default alarm.
+pub(crate) fn default_alarm_text(ical: &str) -> String {
+    let upper = ical.to_ascii_uppercase();
+    let start = upper.find("BEGIN:VALARM").unwrap_or_default();
+    let end = upper
+        .rfind("END:VALARM")
+        .and_then(|pos| ical[pos..].find('\n').map(|end| pos + end + 1))
+        .unwrap_or(ical.len());
+    ical[start..end].to_string()
+}
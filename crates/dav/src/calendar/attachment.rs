@@ -0,0 +1,307 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::common::timezone::Tz;
+use common::{Server, auth::AccessToken};
+use dav_proto::{RequestHeaders, schema::response::CalCondition};
+use groupware::{
+    cache::GroupwareCache,
+    calendar::{
+        CalendarAttachment, CalendarEvent, CalendarEventData,
+        attachment::{has_attachments, managed_id},
+    },
+    file::FileProperties,
+    icap::{ContentInspection, ContentKind, InspectionResult},
+};
+use http_proto::HttpResponse;
+use hyper::StatusCode;
+use jmap_proto::types::{
+    acl::Acl,
+    collection::{Collection, SyncCollection},
+};
+use store::write::BatchBuilder;
+use trc::AddContext;
+use utils::url_params::UrlParams;
+
+use crate::{
+    DavError, DavErrorCondition, DavMethod,
+    common::{
+        ETag, ExtractETag,
+        acl::ResourceAcl,
+        limits::CollectionQuotaCheck,
+        lock::{LockRequestHandler, ResourceState},
+        uri::DavUriResource,
+    },
+};
+
+pub(crate) trait CalendarAttachmentRequestHandler: Sync + Send {
+    fn handle_calendar_attachment_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        query: &str,
+        content_disposition: Option<&str>,
+        bytes: Vec<u8>,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+}
+
+enum AttachmentAction<'x> {
+    Add,
+    Update(&'x str),
+    Remove(&'x str),
+}
+
+impl CalendarAttachmentRequestHandler for Server {
+    async fn handle_calendar_attachment_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        query: &str,
+        content_disposition: Option<&str>,
+        bytes: Vec<u8>,
+    ) -> crate::Result<HttpResponse> {
+        // Parse action
+        let params = UrlParams::new(query.into());
+        let action = match (params.get("action"), params.get("managed-id")) {
+            (Some("attachment-add"), _) => AttachmentAction::Add,
+            (Some("attachment-update"), Some(managed_id)) => AttachmentAction::Update(managed_id),
+            (Some("attachment-remove"), Some(managed_id)) => AttachmentAction::Remove(managed_id),
+            (Some("attachment-update" | "attachment-remove"), None) => {
+                return Err(DavError::Condition(DavErrorCondition::new(
+                    StatusCode::BAD_REQUEST,
+                    CalCondition::ValidManagedIdParameter,
+                )));
+            }
+            _ => return Err(DavError::Code(StatusCode::BAD_REQUEST)),
+        };
+        if params.get("rid").is_some() {
+            // Attachments always apply to all instances of the event
+            return Err(DavError::Condition(DavErrorCondition::new(
+                StatusCode::BAD_REQUEST,
+                CalCondition::ValidRidParameter,
+            )));
+        }
+
+        // Validate URI
+        let resource_ = self
+            .validate_uri(access_token, headers.uri)
+            .await?
+            .into_owned_uri()?;
+        let account_id = resource_.account_id;
+        let resource_name = resource_
+            .resource
+            .ok_or(DavError::Code(StatusCode::METHOD_NOT_ALLOWED))?;
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?;
+        let resource = resources
+            .by_path(resource_name)
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        if resource.is_container() {
+            return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+        }
+        let parent_id = resource.parent_id().unwrap();
+        let document_id = resource.document_id();
+        if resources
+            .container_by_id(parent_id)
+            .is_some_and(|container| {
+                container.schedule_collection().is_some() || container.is_birthday_calendar()
+            })
+        {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+
+        // Validate ACL
+        if !access_token.is_member(account_id)
+            && !resources.has_access_to_container(access_token, parent_id, Acl::ModifyItems)
+        {
+            return Err(resources.need_privileges(Some(parent_id), Acl::ModifyItems));
+        }
+
+        // Fetch event
+        let event_ = self
+            .get_archive(account_id, Collection::CalendarEvent, document_id)
+            .await
+            .caused_by(trc::location!())?
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        let event = event_
+            .to_unarchived::<CalendarEvent>()
+            .caused_by(trc::location!())?;
+
+        // Validate headers
+        self.validate_headers(
+            access_token,
+            headers,
+            vec![ResourceState {
+                account_id,
+                collection: Collection::CalendarEvent,
+                document_id: Some(document_id),
+                etag: event.etag().into(),
+                path: resource_name,
+                ..Default::default()
+            }],
+            Default::default(),
+            DavMethod::POST,
+        )
+        .await?;
+
+        let mut new_event = event
+            .deserialize::<CalendarEvent>()
+            .caused_by(trc::location!())?;
+        let previous_id = match action {
+            AttachmentAction::Update(managed_id) | AttachmentAction::Remove(managed_id) => {
+                if !new_event
+                    .attachments
+                    .iter()
+                    .any(|attachment| attachment.managed_id == managed_id)
+                {
+                    return Err(DavError::Condition(DavErrorCondition::new(
+                        StatusCode::BAD_REQUEST,
+                        CalCondition::ValidManagedIdParameter,
+                    )));
+                }
+                Some(managed_id)
+            }
+            AttachmentAction::Add => None,
+        };
+
+        // Store the attachment contents
+        let attachment = if !matches!(action, AttachmentAction::Remove(_)) {
+            if bytes.is_empty() {
+                return Err(DavError::Code(StatusCode::BAD_REQUEST));
+            } else if bytes.len() > self.core.groupware.max_file_size {
+                return Err(DavError::Condition(DavErrorCondition::new(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    CalCondition::MaxAttachmentSize,
+                )));
+            }
+            let file_name = content_disposition.and_then(parse_file_name);
+
+            // Inspect content
+            if self
+                .inspect_content(
+                    access_token,
+                    ContentKind::File,
+                    file_name.as_deref().unwrap_or(resource_name),
+                    headers.content_type,
+                    &bytes,
+                )
+                .await
+                != InspectionResult::Allowed
+            {
+                return Err(DavError::Code(StatusCode::FORBIDDEN));
+            }
+
+            // Validate quota
+            self.has_available_quota(
+                &self.get_resource_token(access_token, account_id).await?,
+                bytes.len() as u64,
+            )
+            .await?;
+            self.assert_collection_quota(
+                &resources,
+                account_id,
+                Collection::Calendar,
+                parent_id.into(),
+                bytes.len() as u64,
+            )
+            .await?;
+
+            let blob_hash = self
+                .put_blob(account_id, &bytes, false)
+                .await
+                .caused_by(trc::location!())?
+                .hash;
+
+            Some(CalendarAttachment {
+                managed_id: format!("{:x}", store::rand::random::<u64>()),
+                file_name,
+                file: FileProperties {
+                    blob_hash,
+                    size: bytes.len() as u32,
+                    media_type: headers.content_type.map(|v| v.to_string()),
+                    executable: false,
+                },
+            })
+        } else {
+            None
+        };
+
+        // Update the ATTACH properties of all instances
+        let href = resources.format_item(resource_name);
+        let mut ical = new_event.data.event.clone();
+        for component in ical
+            .components
+            .iter_mut()
+            .filter(|component| has_attachments(&component.component_type))
+        {
+            if let Some(previous_id) = previous_id {
+                component
+                    .entries
+                    .retain(|entry| managed_id(entry) != Some(previous_id));
+            }
+            if let Some(attachment) = &attachment {
+                component.entries.push(attachment.to_entry(&href));
+            }
+        }
+        if let Some(previous_id) = previous_id {
+            new_event
+                .attachments
+                .retain(|attachment| attachment.managed_id != previous_id);
+        }
+        let managed_id = attachment
+            .as_ref()
+            .map(|attachment| attachment.managed_id.clone());
+        if let Some(attachment) = attachment {
+            new_event.attachments.push(attachment);
+        }
+        new_event.size = ical.to_string().len() as u32;
+        new_event.data =
+            CalendarEventData::new(ical, Tz::Floating, self.core.groupware.max_ical_instances);
+
+        // Prepare write batch
+        let mut batch = BatchBuilder::new();
+        let etag = new_event
+            .update(access_token, event, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?
+            .etag();
+        self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+        trc::event!(
+            Groupware(trc::GroupwareEvent::CalendarUpdated),
+            AccountId = account_id,
+            Collection = Collection::CalendarEvent,
+            DocumentId = document_id,
+            Url = href,
+        );
+
+        let response = if let Some(managed_id) = managed_id {
+            HttpResponse::new(if previous_id.is_none() {
+                StatusCode::CREATED
+            } else {
+                StatusCode::NO_CONTENT
+            })
+            .with_header("Cal-Managed-ID", managed_id)
+        } else {
+            HttpResponse::new(StatusCode::NO_CONTENT)
+        };
+
+        Ok(response.with_etag_opt(etag))
+    }
+}
+
+fn parse_file_name(content_disposition: &str) -> Option<String> {
+    content_disposition
+        .split(';')
+        .find_map(|part| {
+            let (key, value) = part.split_once('=')?;
+            key.trim()
+                .eq_ignore_ascii_case("filename")
+                .then(|| value.trim().trim_matches('"').to_string())
+        })
+        .filter(|file_name| !file_name.is_empty())
+}
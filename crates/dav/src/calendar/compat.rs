@@ -0,0 +1,477 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::{
+    common::PartialDateTime,
+    icalendar::{
+        ICalendar, ICalendarComponent, ICalendarComponentType, ICalendarEntry, ICalendarParameter,
+        ICalendarProperty, ICalendarStatus, ICalendarTransparency, ICalendarValue,
+        ICalendarValueType,
+    },
+};
+use groupware::calendar::timezone::windows_tz;
+
+use dav_proto::schema::response::{Repair, RepairAction};
+
+const BUSY_STATUS: &str = "X-MICROSOFT-CDO-BUSYSTATUS";
+const ALL_DAY_EVENT: &str = "X-MICROSOFT-CDO-ALLDAYEVENT";
+const GOOGLE_COLORS: [&str; 3] = [
+    "X-APPLE-CALENDAR-COLOR",
+    "X-GOOGLE-CALENDAR-COLOR",
+    "X-GOOGLE-COLOR",
+];
+
+/// Line numbers reported in repairs, counted over the logical (unfolded)
+/// content lines of the object as it was received: each component takes a
+/// BEGIN line, one line per property, its subcomponents and an END line.
+pub(crate) struct ContentLines {
+    component_start: Vec<usize>,
+}
+
+impl ContentLines {
+    pub fn new(ical: &ICalendar) -> Self {
+        let mut lines = ContentLines {
+            component_start: vec![0; ical.components.len()],
+        };
+        if !ical.components.is_empty() {
+            lines.number(ical, 0, 1);
+        }
+        lines
+    }
+
+    fn number(&mut self, ical: &ICalendar, component_id: usize, line: usize) -> usize {
+        let component = &ical.components[component_id];
+        self.component_start[component_id] = line;
+        let mut line = line + component.entries.len() + 1;
+        for child_id in &component.component_ids {
+            line = self.number(ical, *child_id as usize, line);
+        }
+        line + 1
+    }
+
+    fn entry(&self, component_id: usize, entry_id: usize) -> usize {
+        self.component_start
+            .get(component_id)
+            .map_or(0, |line| line + entry_id + 1)
+    }
+}
+
+/// Maps Outlook/Exchange specific properties and Windows timezone names to
+/// their iCalendar equivalents. Returns `false` if nothing had to be changed.
+pub(crate) fn import_outlook_compat(
+    ical: &mut ICalendar,
+    lines: &ContentLines,
+    repairs: &mut Vec<Repair>,
+) -> bool {
+    let mut has_changes = false;
+
+    for (component_id, component) in ical.components.iter_mut().enumerate() {
+        if component.component_type == ICalendarComponentType::VEvent {
+            has_changes |= import_event(component, component_id, lines, repairs);
+        }
+
+        for (entry_id, entry) in component.entries.iter_mut().enumerate() {
+            let mut has_windows_tz = false;
+            for param in &mut entry.params {
+                if let ICalendarParameter::Tzid(tz_id) = param {
+                    if let Some(tz) = windows_tz(tz_id) {
+                        *tz_id = tz.to_string();
+                        has_windows_tz = true;
+                    }
+                }
+            }
+            if entry.name == ICalendarProperty::Tzid {
+                for value in &mut entry.values {
+                    if let ICalendarValue::Text(tz_id) = value {
+                        if let Some(tz) = windows_tz(tz_id) {
+                            *tz_id = tz.to_string();
+                            has_windows_tz = true;
+                        }
+                    }
+                }
+            }
+
+            if has_windows_tz {
+                repairs.push(Repair::new(
+                    entry.name.as_str(),
+                    lines.entry(component_id, entry_id),
+                    RepairAction::Replaced,
+                ));
+                has_changes = true;
+            }
+        }
+    }
+
+    has_changes
+}
+
+fn import_event(
+    event: &mut ICalendarComponent,
+    component_id: usize,
+    lines: &ContentLines,
+    repairs: &mut Vec<Repair>,
+) -> bool {
+    let mut busy_status = None;
+    let mut is_all_day = false;
+    let mut has_transp = false;
+    let mut has_status = false;
+    let mut midnight_dates = 0;
+    let mut dates = 0;
+
+    for (entry_id, entry) in event.entries.iter().enumerate() {
+        match &entry.name {
+            ICalendarProperty::Transp => has_transp = true,
+            ICalendarProperty::Status => has_status = true,
+            ICalendarProperty::Dtstart | ICalendarProperty::Dtend => {
+                dates += 1;
+                if midnight_date(entry).is_some() {
+                    midnight_dates += 1;
+                }
+            }
+            name if is_other(name, BUSY_STATUS) => {
+                busy_status = entry_text(entry).map(|status| {
+                    (
+                        lines.entry(component_id, entry_id),
+                        status.to_ascii_uppercase(),
+                    )
+                });
+            }
+            name if is_other(name, ALL_DAY_EVENT) => {
+                is_all_day =
+                    entry_text(entry).is_some_and(|value| value.eq_ignore_ascii_case("TRUE"));
+            }
+            _ => {}
+        }
+    }
+    let mut has_changes = false;
+
+    // All-day events are stored as dates
+    if is_all_day && dates > 0 && dates == midnight_dates {
+        for (entry_id, entry) in event.entries.iter_mut().enumerate().filter(|(_, entry)| {
+            matches!(
+                entry.name,
+                ICalendarProperty::Dtstart | ICalendarProperty::Dtend
+            )
+        }) {
+            if let Some(date) = midnight_date(entry) {
+                entry.params = vec![ICalendarParameter::Value(ICalendarValueType::Date)];
+                entry.values = vec![ICalendarValue::PartialDateTime(Box::new(date))];
+                repairs.push(Repair::new(
+                    entry.name.as_str(),
+                    lines.entry(component_id, entry_id),
+                    RepairAction::Replaced,
+                ));
+                has_changes = true;
+            }
+        }
+    }
+
+    // Add the standard properties derived from the busy status
+    if let Some((line, busy_status)) = busy_status {
+        if !has_transp {
+            event.entries.push(ICalendarEntry {
+                name: ICalendarProperty::Transp,
+                params: vec![],
+                values: vec![ICalendarValue::Transparency(if busy_status == "FREE" {
+                    ICalendarTransparency::Transparent
+                } else {
+                    ICalendarTransparency::Opaque
+                })],
+            });
+            repairs.push(Repair::new("TRANSP", line, RepairAction::Added));
+            has_changes = true;
+        }
+        if !has_status && busy_status == "TENTATIVE" {
+            event.entries.push(ICalendarEntry {
+                name: ICalendarProperty::Status,
+                params: vec![],
+                values: vec![ICalendarValue::Status(ICalendarStatus::Tentative)],
+            });
+            repairs.push(Repair::new("STATUS", line, RepairAction::Added));
+            has_changes = true;
+        }
+    }
+
+    has_changes
+}
+
+/// Adds the Outlook/Exchange busy status and all-day properties to events
+/// that do not include them. Returns `false` if nothing had to be changed.
+pub(crate) fn export_outlook_compat(ical: &mut ICalendar) -> bool {
+    let mut has_changes = false;
+
+    for event in ical
+        .components
+        .iter_mut()
+        .filter(|component| component.component_type == ICalendarComponentType::VEvent)
+    {
+        let mut has_busy_status = false;
+        let mut has_all_day = false;
+        let mut is_transparent = false;
+        let mut is_tentative = false;
+        let mut is_date = false;
+
+        for entry in &event.entries {
+            match &entry.name {
+                ICalendarProperty::Transp => {
+                    is_transparent = matches!(
+                        entry.values.first(),
+                        Some(ICalendarValue::Transparency(
+                            ICalendarTransparency::Transparent
+                        ))
+                    );
+                }
+                ICalendarProperty::Status => {
+                    is_tentative = matches!(
+                        entry.values.first(),
+                        Some(ICalendarValue::Status(ICalendarStatus::Tentative))
+                    );
+                }
+                ICalendarProperty::Dtstart => {
+                    is_date = entry.params.iter().any(|param| {
+                        matches!(param, ICalendarParameter::Value(ICalendarValueType::Date))
+                    }) || matches!(
+                        entry.values.first(),
+                        Some(ICalendarValue::PartialDateTime(dt)) if dt.hour.is_none()
+                    );
+                }
+                name if is_other(name, BUSY_STATUS) => has_busy_status = true,
+                name if is_other(name, ALL_DAY_EVENT) => has_all_day = true,
+                _ => {}
+            }
+        }
+
+        if !has_busy_status {
+            event.entries.push(text_entry(
+                BUSY_STATUS,
+                if is_transparent {
+                    "FREE"
+                } else if is_tentative {
+                    "TENTATIVE"
+                } else {
+                    "BUSY"
+                },
+            ));
+            has_changes = true;
+        }
+        if !has_all_day {
+            event.entries.push(text_entry(
+                ALL_DAY_EVENT,
+                if is_date { "TRUE" } else { "FALSE" },
+            ));
+            has_changes = true;
+        }
+    }
+
+    has_changes
+}
+
+/// Tolerates quirks found in data exported from Google Calendar: timezone
+/// references without a matching VTIMEZONE, nonstandard color properties and
+/// malformed SEQUENCE values. Returns `false` if nothing had to be changed.
+pub(crate) fn import_google_compat(
+    ical: &mut ICalendar,
+    lines: &ContentLines,
+    repairs: &mut Vec<Repair>,
+) -> bool {
+    // Only data produced by Google Calendar is repaired
+    if !ical.components.first().is_some_and(|calendar| {
+        calendar.entries.iter().any(|entry| {
+            entry.name == ICalendarProperty::Prodid
+                && entry_text(entry).is_some_and(|value| value.contains("Google"))
+        })
+    }) {
+        return false;
+    }
+
+    // Timezones defined in the object
+    let defined_tz = ical
+        .components
+        .iter()
+        .filter(|component| component.component_type == ICalendarComponentType::VTimezone)
+        .flat_map(|component| component.entries.iter())
+        .filter(|entry| entry.name == ICalendarProperty::Tzid)
+        .filter_map(entry_text)
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let mut has_changes = false;
+
+    for (component_id, component) in ical.components.iter_mut().enumerate() {
+        // Keep the original color property for clients that rely on it
+        if !component
+            .entries
+            .iter()
+            .any(|entry| entry.name == ICalendarProperty::Color)
+        {
+            if let Some((entry_id, color)) =
+                component
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .find_map(|(entry_id, entry)| {
+                        GOOGLE_COLORS
+                            .iter()
+                            .any(|name| is_other(&entry.name, name))
+                            .then(|| entry_text(entry))
+                            .flatten()
+                            .filter(|color| !color.is_empty())
+                            .map(|color| (entry_id, google_color(color).to_string()))
+                    })
+            {
+                component.entries.push(ICalendarEntry {
+                    name: ICalendarProperty::Color,
+                    params: vec![],
+                    values: vec![ICalendarValue::Text(color)],
+                });
+                repairs.push(Repair::new(
+                    "COLOR",
+                    lines.entry(component_id, entry_id),
+                    RepairAction::Added,
+                ));
+                has_changes = true;
+            }
+        }
+
+        let mut entry_id = 0;
+        component.entries.retain_mut(|entry| {
+            let line = lines.entry(component_id, entry_id);
+            entry_id += 1;
+
+            if entry.name == ICalendarProperty::Sequence {
+                let sequence = match entry.values.first() {
+                    Some(ICalendarValue::Integer(sequence)) => Some(*sequence),
+                    Some(ICalendarValue::Text(sequence)) => sequence.trim().parse::<i64>().ok(),
+                    _ => None,
+                };
+                match sequence.map(|sequence| (sequence, sequence.clamp(0, i32::MAX as i64))) {
+                    Some((sequence, clamped))
+                        if sequence == clamped
+                            && matches!(entry.values.as_slice(), [ICalendarValue::Integer(_)]) => {}
+                    Some((_, clamped)) => {
+                        entry.values = vec![ICalendarValue::Integer(clamped)];
+                        repairs.push(Repair::new("SEQUENCE", line, RepairAction::Replaced));
+                        has_changes = true;
+                    }
+                    None => {
+                        repairs.push(Repair::new("SEQUENCE", line, RepairAction::Removed));
+                        has_changes = true;
+                        return false;
+                    }
+                }
+            } else {
+                let mut has_undefined_tz = false;
+                for param in &mut entry.params {
+                    if let ICalendarParameter::Tzid(tz_id) = param {
+                        if !defined_tz.contains(tz_id) {
+                            if let Some(tz) = google_tz(tz_id) {
+                                *tz_id = tz;
+                                has_undefined_tz = true;
+                            }
+                        }
+                    }
+                }
+                if has_undefined_tz {
+                    repairs.push(Repair::new(
+                        entry.name.as_str(),
+                        line,
+                        RepairAction::Replaced,
+                    ));
+                    has_changes = true;
+                }
+            }
+
+            true
+        });
+    }
+
+    has_changes
+}
+
+fn is_other(name: &ICalendarProperty, other: &str) -> bool {
+    matches!(name, ICalendarProperty::Other(name) if name.eq_ignore_ascii_case(other))
+}
+
+fn entry_text(entry: &ICalendarEntry) -> Option<&str> {
+    match entry.values.first() {
+        Some(ICalendarValue::Text(text)) => Some(text.as_str()),
+        _ => None,
+    }
+}
+
+fn text_entry(name: &str, value: &str) -> ICalendarEntry {
+    ICalendarEntry {
+        name: ICalendarProperty::Other(name.to_string()),
+        params: vec![],
+        values: vec![ICalendarValue::Text(value.to_string())],
+    }
+}
+
+// Returns the date part of a UTC or floating date-time set at midnight
+fn midnight_date(entry: &ICalendarEntry) -> Option<PartialDateTime> {
+    match entry.values.as_slice() {
+        [ICalendarValue::PartialDateTime(dt)]
+            if dt.year.is_some()
+                && dt.month.is_some()
+                && dt.day.is_some()
+                && dt.hour == Some(0)
+                && dt.minute.unwrap_or(0) == 0
+                && dt.second.unwrap_or(0) == 0
+                && dt.tz_hour.unwrap_or(0) == 0
+                && dt.tz_minute.unwrap_or(0) == 0 =>
+        {
+            Some(PartialDateTime {
+                year: dt.year,
+                month: dt.month,
+                day: dt.day,
+                ..Default::default()
+            })
+        }
+        _ => None,
+    }
+}
+
+// Google and Apple colors may include an alpha channel, which COLOR does not allow
+fn google_color(value: &str) -> &str {
+    if value.len() == 9
+        && value.starts_with('#')
+        && value[1..].bytes().all(|ch| ch.is_ascii_hexdigit())
+    {
+        &value[..7]
+    } else {
+        value
+    }
+}
+
+// Resolves timezone names used by Google without a VTIMEZONE definition,
+// such as Windows names and fixed offsets like "GMT+01:00"
+fn google_tz(name: &str) -> Option<String> {
+    if let Some(tz) = windows_tz(name) {
+        return Some(tz.to_string());
+    }
+
+    let offset = name
+        .strip_prefix("GMT")
+        .or_else(|| name.strip_prefix("UTC"))?;
+    if offset.is_empty() {
+        return Some("Etc/UTC".to_string());
+    }
+    let (sign, offset) = match offset.as_bytes()[0] {
+        b'+' => ('-', &offset[1..]),
+        b'-' => ('+', &offset[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = offset.split_once(':').unwrap_or((offset, "00"));
+    let hours = hours.parse::<u8>().ok().filter(|hours| *hours <= 14)?;
+    if minutes != "00" {
+        return None;
+    }
+
+    Some(if hours == 0 {
+        "Etc/UTC".to_string()
+    } else {
+        format!("Etc/GMT{sign}{hours}")
+    })
+}
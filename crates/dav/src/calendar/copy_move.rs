@@ -23,8 +23,11 @@ use trc::AddContext;
 use crate::{
     DavError, DavMethod,
     common::{
+        acl::ResourceAcl,
+        copy_move_events,
+        limits::{CollectionQuotaCheck, ItemLimitCheck},
         lock::{LockRequestHandler, ResourceState},
-        uri::DavUriResource,
+        uri::{DavUriResource, validate_resource_name},
     },
     file::DavFileResource,
 };
@@ -65,19 +68,25 @@ impl CalendarCopyMoveRequestHandler for Server {
             .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
 
         // Validate ACL
+        let from_container_id = if from_resource.is_container() {
+            from_resource.document_id()
+        } else {
+            from_resource.parent_id().unwrap()
+        };
         if !access_token.is_member(from_account_id)
             && !from_resources.has_access_to_container(
                 access_token,
-                if from_resource.is_container() {
-                    from_resource.document_id()
-                } else {
-                    from_resource.parent_id().unwrap()
-                },
+                from_container_id,
                 Acl::ReadItems,
             )
         {
-            return Err(DavError::Code(StatusCode::FORBIDDEN));
+            return Err(from_resources.need_privileges(Some(from_container_id), Acl::ReadItems));
         }
+        let from_acl = if is_move {
+            Acl::RemoveItems
+        } else {
+            Acl::ReadItems
+        };
 
         // Validate destination
         let destination = self
@@ -85,16 +94,16 @@ impl CalendarCopyMoveRequestHandler for Server {
                 access_token,
                 headers
                     .destination
-                    .ok_or(DavError::Code(StatusCode::BAD_GATEWAY))?,
-                StatusCode::BAD_GATEWAY,
+                    .ok_or(DavError::Code(StatusCode::BAD_REQUEST))?,
+                StatusCode::BAD_REQUEST,
             )
             .await?;
         if destination.collection != Collection::Calendar {
-            return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+            return Err(DavError::Code(StatusCode::CONFLICT));
         }
         let to_account_id = destination
             .account_id
-            .ok_or(DavError::Code(StatusCode::BAD_GATEWAY))?;
+            .ok_or(DavError::Code(StatusCode::CONFLICT))?;
         let to_resources = if to_account_id == from_account_id {
             from_resources.clone()
         } else {
@@ -106,8 +115,32 @@ impl CalendarCopyMoveRequestHandler for Server {
         // Validate headers
         let destination_resource_name = destination
             .resource
-            .ok_or(DavError::Code(StatusCode::BAD_GATEWAY))?;
+            .ok_or(DavError::Code(StatusCode::FORBIDDEN))?;
         let to_resource = to_resources.by_path(destination_resource_name);
+
+        // Scheduling collections are managed by the server
+        let to_parent = destination_resource_name
+            .rsplit_once('/')
+            .and_then(|(parent, _)| to_resources.by_path(parent));
+        if from_resource.schedule_collection().is_some()
+            || to_resource.is_some_and(|r| r.schedule_collection().is_some())
+            || to_parent.is_some_and(|r| r.schedule_collection().is_some())
+        {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+
+        // Birthday calendars are generated from the contacts of the account
+        if from_resource.is_birthday_calendar()
+            || (is_move
+                && from_resources
+                    .container_by_id(from_container_id)
+                    .is_some_and(|r| r.is_birthday_calendar()))
+            || to_resource.is_some_and(|r| r.is_birthday_calendar())
+            || to_parent.is_some_and(|r| r.is_birthday_calendar())
+        {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+
         self.validate_headers(
             access_token,
             headers,
@@ -149,10 +182,10 @@ impl CalendarCopyMoveRequestHandler for Server {
         .await?;
 
         // Map destination
-        if let Some(to_resource) = to_resource {
+        let result = if let Some(to_resource) = to_resource {
             if from_resource.path() == to_resource.path() {
                 // Same resource
-                return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+                return Err(DavError::Code(StatusCode::FORBIDDEN));
             }
             let new_name = destination_resource_name
                 .rsplit_once('/')
@@ -172,20 +205,18 @@ impl CalendarCopyMoveRequestHandler for Server {
                         .map(|r| r.document_id())
                         .collect::<Vec<_>>();
 
-                    // Validate ACLs
-                    if !access_token.is_member(to_account_id)
-                        || (!access_token.is_member(from_account_id)
-                            && !from_resources.has_access_to_container(
-                                access_token,
-                                from_resource.document_id(),
-                                if is_move {
-                                    Acl::RemoveItems
-                                } else {
-                                    Acl::ReadItems
-                                },
-                            ))
+                    // Validate ACLs, shared users cannot replace containers
+                    if !access_token.is_member(to_account_id) {
+                        return Err(to_resources.need_privileges(None, Acl::AddItems));
+                    } else if !access_token.is_member(from_account_id)
+                        && !from_resources.has_access_to_container(
+                            access_token,
+                            from_resource.document_id(),
+                            from_acl,
+                        )
                     {
-                        return Err(DavError::Code(StatusCode::FORBIDDEN));
+                        return Err(from_resources
+                            .need_privileges(Some(from_resource.document_id()), from_acl));
                     }
 
                     // Overwrite container
@@ -210,24 +241,26 @@ impl CalendarCopyMoveRequestHandler for Server {
                     let to_calendar_id = to_resource.parent_id().unwrap();
 
                     // Validate ACL
-                    if (!access_token.is_member(from_account_id)
+                    if !access_token.is_member(from_account_id)
                         && !from_resources.has_access_to_container(
                             access_token,
                             from_calendar_id,
-                            if is_move {
-                                Acl::RemoveItems
-                            } else {
-                                Acl::ReadItems
-                            },
-                        ))
-                        || (!access_token.is_member(to_account_id)
-                            && !to_resources.has_access_to_container(
-                                access_token,
-                                to_calendar_id,
-                                Acl::RemoveItems,
-                            ))
+                            from_acl,
+                        )
+                    {
+                        return Err(
+                            from_resources.need_privileges(Some(from_calendar_id), from_acl)
+                        );
+                    } else if !access_token.is_member(to_account_id)
+                        && !to_resources.has_access_to_container(
+                            access_token,
+                            to_calendar_id,
+                            Acl::RemoveItems,
+                        )
                     {
-                        return Err(DavError::Code(StatusCode::FORBIDDEN));
+                        return Err(
+                            to_resources.need_privileges(Some(to_calendar_id), Acl::RemoveItems)
+                        );
                     }
 
                     if is_move {
@@ -258,39 +291,62 @@ impl CalendarCopyMoveRequestHandler for Server {
                         .await
                     }
                 }
-                _ => Err(DavError::Code(StatusCode::BAD_GATEWAY)),
+                _ => Err(DavError::Code(StatusCode::CONFLICT)),
             }
         } else if let Some((parent_resource, new_name)) =
             to_resources.map_parent(destination_resource_name)
         {
+            validate_resource_name(new_name)?;
+
             if let Some(parent_resource) = parent_resource {
                 // Creating items under an event is not allowed
                 // Copying/moving containers under a container is not allowed
                 if !parent_resource.is_container() || from_resource.is_container() {
-                    return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+                    return Err(DavError::Code(StatusCode::CONFLICT));
                 }
 
                 // Validate ACL
                 let from_calendar_id = from_resource.parent_id().unwrap();
                 let to_calendar_id = parent_resource.document_id();
-                if (!access_token.is_member(from_account_id)
+                if !access_token.is_member(from_account_id)
                     && !from_resources.has_access_to_container(
                         access_token,
                         from_calendar_id,
-                        if is_move {
-                            Acl::RemoveItems
-                        } else {
-                            Acl::ReadItems
-                        },
-                    ))
-                    || (!access_token.is_member(to_account_id)
-                        && !to_resources.has_access_to_container(
-                            access_token,
-                            to_calendar_id,
-                            Acl::AddItems,
-                        ))
+                        from_acl,
+                    )
+                {
+                    return Err(from_resources.need_privileges(Some(from_calendar_id), from_acl));
+                } else if !access_token.is_member(to_account_id)
+                    && !to_resources.has_access_to_container(
+                        access_token,
+                        to_calendar_id,
+                        Acl::AddItems,
+                    )
                 {
-                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                    return Err(to_resources.need_privileges(Some(to_calendar_id), Acl::AddItems));
+                }
+
+                // Validate item limits and quotas, renames within the same container are exempt
+                if !is_move
+                    || from_account_id != to_account_id
+                    || to_calendar_id != from_calendar_id
+                {
+                    self.assert_item_limit(
+                        access_token,
+                        &to_resources,
+                        to_account_id,
+                        Collection::CalendarEvent,
+                        to_calendar_id.into(),
+                    )
+                    .await?;
+                    self.assert_collection_quota(
+                        &to_resources,
+                        to_account_id,
+                        Collection::Calendar,
+                        to_calendar_id.into(),
+                        from_resource.size() as u64,
+                    )
+                    .await?;
                 }
 
                 // Copy/move event
@@ -339,12 +395,12 @@ impl CalendarCopyMoveRequestHandler for Server {
             } else {
                 // Copying/moving events to the root is not allowed
                 if !from_resource.is_container() {
-                    return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+                    return Err(DavError::Code(StatusCode::CONFLICT));
                 }
 
                 // Shared users cannot create containers
                 if !access_token.is_member(to_account_id) {
-                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                    return Err(to_resources.need_privileges(None, Acl::AddItems));
                 }
 
                 // Validate ACLs
@@ -352,14 +408,12 @@ impl CalendarCopyMoveRequestHandler for Server {
                     && !from_resources.has_access_to_container(
                         access_token,
                         from_resource.document_id(),
-                        if is_move {
-                            Acl::RemoveItems
-                        } else {
-                            Acl::ReadItems
-                        },
+                        from_acl,
                     )
                 {
-                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                    return Err(
+                        from_resources.need_privileges(Some(from_resource.document_id()), from_acl)
+                    );
                 }
 
                 // Copy/move container
@@ -378,7 +432,8 @@ impl CalendarCopyMoveRequestHandler for Server {
                             if headers.depth != Depth::Zero {
                                 from_children_ids
                             } else {
-                                return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+                                // Collections are always moved with all their members
+                                return Err(DavError::Code(StatusCode::BAD_REQUEST));
                             },
                             from_resources.format_collection(from_resource_name),
                             to_account_id,
@@ -422,7 +477,29 @@ impl CalendarCopyMoveRequestHandler for Server {
             }
         } else {
             Err(DavError::Code(StatusCode::CONFLICT))
+        };
+
+        if let Ok(response) = &result {
+            copy_move_events(
+                response,
+                is_move,
+                if from_resource.is_container() {
+                    Collection::Calendar
+                } else {
+                    Collection::CalendarEvent
+                },
+                from_account_id,
+                from_resources.format_resource(from_resource),
+                to_account_id,
+                if from_resource.is_container() {
+                    to_resources.format_collection(destination_resource_name)
+                } else {
+                    to_resources.format_item(destination_resource_name)
+                },
+            );
         }
+
+        result
     }
 }
 
@@ -459,6 +536,7 @@ async fn copy_event(
         to_account_id,
         to_calendar_id,
         event.inner.data.event.uids().next(),
+        to_document_id,
     )
     .await?;
 
@@ -571,6 +649,7 @@ async fn move_event(
             to_account_id,
             to_calendar_id,
             event.inner.data.event.uids().next(),
+            to_document_id,
         )
         .await?;
     }
@@ -739,32 +818,17 @@ async fn copy_container(
         .await
         .caused_by(trc::location!())?
         .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
-    let old_calendar = calendar_
+    let mut calendar = calendar_
         .to_unarchived::<Calendar>()
-        .caused_by(trc::location!())?;
-    let mut calendar = old_calendar
+        .caused_by(trc::location!())?
         .deserialize::<Calendar>()
         .caused_by(trc::location!())?;
 
-    // Prepare write batch
-    let mut batch = BatchBuilder::new();
-
-    if remove_source {
-        DestroyArchive(old_calendar)
-            .delete(
-                access_token,
-                from_account_id,
-                from_document_id,
-                from_resource_path.into(),
-                &mut batch,
-            )
-            .caused_by(trc::location!())?;
-    }
-
     let preference = calendar.preferences.into_iter().next().unwrap();
     calendar.name = new_name.to_string();
     calendar.default_alerts.clear();
     calendar.acls.clear();
+    calendar.invites.clear();
     calendar.preferences = vec![CalendarPreferences {
         account_id: to_account_id,
         name: preference.name,
@@ -775,6 +839,8 @@ async fn copy_container(
         time_zone: Timezone::Default,
     }];
 
+    // Prepare write batch
+    let mut batch = BatchBuilder::new();
     let is_overwrite = to_document_id.is_some();
     let to_document_id = if let Some(to_document_id) = to_document_id {
         // Overwrite destination
@@ -812,91 +878,176 @@ async fn copy_container(
     calendar
         .insert(access_token, to_account_id, to_document_id, &mut batch)
         .caused_by(trc::location!())?;
+    server
+        .commit_batch(batch)
+        .await
+        .caused_by(trc::location!())?;
 
     // Copy children
+    let mut created_ids = Vec::new();
+    if let Err(err) = copy_events(
+        server,
+        access_token,
+        from_account_id,
+        from_document_id,
+        &from_children_ids,
+        to_account_id,
+        to_document_id,
+        &mut created_ids,
+    )
+    .await
+    {
+        // Undo the batches that were already committed
+        if let Err(rollback_err) = delete_container(
+            server,
+            access_token,
+            to_account_id,
+            to_document_id,
+            created_ids,
+            None,
+        )
+        .await
+        {
+            trc::error!(
+                rollback_err
+                    .account_id(to_account_id)
+                    .details("Failed to rollback partial container copy")
+            );
+        }
+
+        return Err(err);
+    }
+
+    // Unlink the source once all children were copied
+    if remove_source {
+        delete_container(
+            server,
+            access_token,
+            from_account_id,
+            from_document_id,
+            from_children_ids,
+            from_resource_path.into(),
+        )
+        .await?;
+    }
+
+    if !is_overwrite {
+        Ok(HttpResponse::new(StatusCode::CREATED))
+    } else {
+        Ok(HttpResponse::new(StatusCode::NO_CONTENT))
+    }
+}
+
+// Links or copies the children of a calendar into the destination, committing size-bounded
+// batches as it goes. The ids of all committed children are recorded in `created_ids`.
+#[allow(clippy::too_many_arguments)]
+async fn copy_events(
+    server: &Server,
+    access_token: &AccessToken,
+    from_account_id: u32,
+    from_document_id: u32,
+    from_children_ids: &[u32],
+    to_account_id: u32,
+    to_document_id: u32,
+    created_ids: &mut Vec<u32>,
+) -> crate::Result<()> {
+    let mut batch = BatchBuilder::new();
+    let mut pending_ids = Vec::new();
     let mut required_space = 0;
-    for from_child_document_id in from_children_ids {
-        if let Some(event_) = server
+
+    for &from_child_document_id in from_children_ids {
+        let Some(event_) = server
             .get_archive(
                 from_account_id,
                 Collection::CalendarEvent,
                 from_child_document_id,
             )
-            .await?
-        {
-            let event = event_
-                .to_unarchived::<CalendarEvent>()
-                .caused_by(trc::location!())?;
-            let mut new_name = None;
+            .await
+            .caused_by(trc::location!())?
+        else {
+            continue;
+        };
+        let event = event_
+            .to_unarchived::<CalendarEvent>()
+            .caused_by(trc::location!())?;
+        let Some(new_name) = event
+            .inner
+            .names
+            .iter()
+            .find(|name| name.parent_id == from_document_id)
+            .map(|name| DavName {
+                name: name.name.to_string(),
+                parent_id: to_document_id,
+            })
+        else {
+            continue;
+        };
+        let mut new_event = event
+            .deserialize::<CalendarEvent>()
+            .caused_by(trc::location!())?;
 
-            for name in event.inner.names.iter() {
-                if name.parent_id == to_document_id {
-                    continue;
-                } else if name.parent_id == from_document_id {
-                    new_name = Some(name.name.to_string());
-                }
-            }
-            let new_name = if let Some(new_name) = new_name {
-                DavName {
-                    name: new_name,
-                    parent_id: to_document_id,
-                }
-            } else {
-                continue;
-            };
-            let event = event_
-                .to_unarchived::<CalendarEvent>()
+        if from_account_id == to_account_id {
+            new_event.names.push(new_name);
+            new_event
+                .update(
+                    access_token,
+                    event,
+                    from_account_id,
+                    from_child_document_id,
+                    &mut batch,
+                )
                 .caused_by(trc::location!())?;
-            let mut new_event = event
-                .deserialize::<CalendarEvent>()
+            pending_ids.push(from_child_document_id);
+        } else {
+            let to_child_document_id = server
+                .store()
+                .assign_document_ids(to_account_id, Collection::CalendarEvent, 1)
+                .await
                 .caused_by(trc::location!())?;
+            new_event.names = vec![new_name];
+            required_space += new_event.size as u64;
+            new_event
+                .insert(
+                    access_token,
+                    to_account_id,
+                    to_child_document_id,
+                    &mut batch,
+                )
+                .caused_by(trc::location!())?;
+            pending_ids.push(to_child_document_id);
+        }
 
-            if from_account_id == to_account_id {
-                if remove_source {
-                    new_event
-                        .names
-                        .retain(|name| name.parent_id != from_document_id);
-                }
-
-                new_event.names.push(new_name);
-                new_event
-                    .update(
-                        access_token,
-                        event,
-                        from_account_id,
-                        from_child_document_id,
-                        &mut batch,
-                    )
-                    .caused_by(trc::location!())?;
-            } else {
-                if remove_source {
-                    DestroyArchive(event)
-                        .delete(
-                            access_token,
-                            from_account_id,
-                            from_child_document_id,
-                            from_document_id,
-                            None,
-                            &mut batch,
-                        )
-                        .caused_by(trc::location!())?;
-                }
-
-                let to_document_id = server
-                    .store()
-                    .assign_document_ids(to_account_id, Collection::CalendarEvent, 1)
-                    .await
-                    .caused_by(trc::location!())?;
-                new_event.names = vec![new_name];
-                required_space += new_event.size as u64;
-                new_event
-                    .insert(access_token, to_account_id, to_document_id, &mut batch)
-                    .caused_by(trc::location!())?;
-            }
+        // Flush large batches
+        if batch.is_large_batch() {
+            commit_children(
+                server,
+                access_token,
+                to_account_id,
+                std::mem::take(&mut batch),
+                std::mem::take(&mut required_space),
+            )
+            .await?;
+            created_ids.append(&mut pending_ids);
         }
     }
 
-    if from_account_id != to_account_id && required_space > 0 {
+    // Write remaining changes
+    if !batch.is_empty() {
+        commit_children(server, access_token, to_account_id, batch, required_space).await?;
+        created_ids.append(&mut pending_ids);
+    }
+
+    Ok(())
+}
+
+async fn commit_children(
+    server: &Server,
+    access_token: &AccessToken,
+    to_account_id: u32,
+    batch: BatchBuilder,
+    required_space: u64,
+) -> crate::Result<()> {
+    if required_space > 0 {
         server
             .has_available_quota(
                 &server
@@ -912,11 +1063,77 @@ async fn copy_container(
         .await
         .caused_by(trc::location!())?;
 
-    if !is_overwrite {
-        Ok(HttpResponse::new(StatusCode::CREATED))
-    } else {
-        Ok(HttpResponse::new(StatusCode::NO_CONTENT))
+    Ok(())
+}
+
+// Unlinks the children of a calendar and deletes it, committing size-bounded batches as it goes
+async fn delete_container(
+    server: &Server,
+    access_token: &AccessToken,
+    account_id: u32,
+    document_id: u32,
+    children_ids: Vec<u32>,
+    delete_path: Option<String>,
+) -> trc::Result<()> {
+    let mut batch = BatchBuilder::new();
+    for child_document_id in children_ids {
+        if let Some(event_) = server
+            .get_archive(account_id, Collection::CalendarEvent, child_document_id)
+            .await
+            .caused_by(trc::location!())?
+        {
+            DestroyArchive(
+                event_
+                    .to_unarchived::<CalendarEvent>()
+                    .caused_by(trc::location!())?,
+            )
+            .delete(
+                access_token,
+                account_id,
+                child_document_id,
+                document_id,
+                None,
+                &mut batch,
+            )
+            .caused_by(trc::location!())?;
+
+            if batch.is_large_batch() {
+                server
+                    .commit_batch(std::mem::take(&mut batch))
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+        }
     }
+
+    if let Some(calendar_) = server
+        .get_archive(account_id, Collection::Calendar, document_id)
+        .await
+        .caused_by(trc::location!())?
+    {
+        DestroyArchive(
+            calendar_
+                .to_unarchived::<Calendar>()
+                .caused_by(trc::location!())?,
+        )
+        .delete(
+            access_token,
+            account_id,
+            document_id,
+            delete_path,
+            &mut batch,
+        )
+        .caused_by(trc::location!())?;
+    }
+
+    if !batch.is_empty() {
+        server
+            .commit_batch(batch)
+            .await
+            .caused_by(trc::location!())?;
+    }
+
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -6,24 +6,30 @@
 
 use crate::{
     DavError, DavMethod,
+    calendar::split_resource,
     common::{
         ETag,
+        acl::ResourceAcl,
         lock::{LockRequestHandler, ResourceState},
         uri::DavUriResource,
     },
 };
+use calcard::common::timezone::Tz;
 use common::{Server, auth::AccessToken, sharing::EffectiveAcl};
 use dav_proto::RequestHeaders;
 use groupware::{
     DestroyArchive,
     cache::GroupwareCache,
-    calendar::{Calendar, CalendarEvent},
+    calendar::{
+        Calendar, CalendarEvent, CalendarEventData,
+        split::{is_override_resource, remove_override_resource},
+    },
 };
 use http_proto::HttpResponse;
 use hyper::StatusCode;
 use jmap_proto::types::{
     acl::Acl,
-    collection::{Collection, SyncCollection},
+    collection::{Collection, SyncCollection, VanishedCollection},
 };
 use store::write::BatchBuilder;
 use trc::AddContext;
@@ -66,6 +72,13 @@ impl CalendarDeleteRequestHandler for Server {
         // Fetch entry
         let mut batch = BatchBuilder::new();
         if delete_resource.is_container() {
+            // Scheduling and birthday calendars are managed by the server
+            if delete_resource.schedule_collection().is_some()
+                || delete_resource.is_birthday_calendar()
+            {
+                return Err(DavError::Code(StatusCode::FORBIDDEN));
+            }
+
             let calendar_ = self
                 .get_archive(account_id, Collection::Calendar, document_id)
                 .await
@@ -84,7 +97,8 @@ impl CalendarDeleteRequestHandler for Server {
                     .effective_acl(access_token)
                     .contains_all([Acl::Delete, Acl::RemoveItems].into_iter())
             {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(resources
+                    .need_privileges(Some(document_id), vec![Acl::Delete, Acl::RemoveItems]));
             }
 
             // Validate headers
@@ -124,10 +138,16 @@ impl CalendarDeleteRequestHandler for Server {
         } else {
             // Validate ACL
             let calendar_id = delete_resource.parent_id().unwrap();
+            if resources
+                .container_by_id(calendar_id)
+                .is_some_and(|calendar| calendar.is_birthday_calendar())
+            {
+                return Err(DavError::Code(StatusCode::FORBIDDEN));
+            }
             if !access_token.is_member(account_id)
                 && !resources.has_access_to_container(access_token, calendar_id, Acl::RemoveItems)
             {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(resources.need_privileges(Some(calendar_id), Acl::RemoveItems));
             }
 
             let event_ = self
@@ -135,6 +155,14 @@ impl CalendarDeleteRequestHandler for Server {
                 .await
                 .caused_by(trc::location!())?
                 .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+            let event = event_
+                .to_unarchived::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+            let name = delete_path
+                .rsplit_once('/')
+                .map_or(delete_path, |(_, name)| name);
+            let split = split_resource(event.inner, calendar_id, name)?;
+            let is_split = split.is_some();
 
             // Validate headers
             self.validate_headers(
@@ -144,7 +172,7 @@ impl CalendarDeleteRequestHandler for Server {
                     account_id,
                     collection: Collection::CalendarEvent,
                     document_id: document_id.into(),
-                    etag: event_.etag().into(),
+                    etag: split.map_or_else(|| event.etag(), |(_, etag)| etag).into(),
                     path: delete_path,
                     ..Default::default()
                 }],
@@ -153,25 +181,66 @@ impl CalendarDeleteRequestHandler for Server {
             )
             .await?;
 
-            // Delete event
-            DestroyArchive(
-                event_
-                    .to_unarchived::<CalendarEvent>()
-                    .caused_by(trc::location!())?,
-            )
-            .delete(
-                access_token,
-                account_id,
-                document_id,
-                calendar_id,
-                resources.format_resource(delete_resource).into(),
-                &mut batch,
-            )
-            .caused_by(trc::location!())?;
+            let override_event = if is_split {
+                Some(
+                    event
+                        .deserialize::<CalendarEvent>()
+                        .caused_by(trc::location!())?,
+                )
+                .filter(|new_event| is_override_resource(&new_event.data.event, name))
+            } else {
+                None
+            };
+
+            if let Some(mut new_event) = override_event {
+                // Remove the recurrence overrides merged from this resource
+                let mut ical = std::mem::take(&mut new_event.data.event);
+                remove_override_resource(&mut ical, name);
+                new_event
+                    .names
+                    .retain(|other| other.parent_id != calendar_id || other.name != name);
+                new_event.size = ical.to_string().len() as u32;
+                new_event.data = CalendarEventData::new(
+                    ical,
+                    Tz::Floating,
+                    self.core.groupware.max_ical_instances,
+                );
+                batch.with_account_id(account_id).log_vanished_item(
+                    VanishedCollection::Calendar,
+                    resources.format_resource(delete_resource),
+                );
+                new_event
+                    .update(access_token, event, account_id, document_id, &mut batch)
+                    .caused_by(trc::location!())?;
+            } else {
+                // Delete event
+                DestroyArchive(event)
+                    .delete(
+                        access_token,
+                        account_id,
+                        document_id,
+                        calendar_id,
+                        resources.format_resource(delete_resource).into(),
+                        &mut batch,
+                    )
+                    .caused_by(trc::location!())?;
+            }
         }
 
         self.commit_batch(batch).await.caused_by(trc::location!())?;
 
+        trc::event!(
+            Groupware(trc::GroupwareEvent::CalendarDeleted),
+            AccountId = account_id,
+            Collection = if delete_resource.is_container() {
+                Collection::Calendar
+            } else {
+                Collection::CalendarEvent
+            },
+            DocumentId = document_id,
+            Url = resources.format_resource(delete_resource),
+        );
+
         Ok(HttpResponse::new(StatusCode::NO_CONTENT))
     }
 }
@@ -0,0 +1,1424 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use calcard::{
+    Entry, Parser,
+    common::{Data, PartialDateTime, Uri},
+    icalendar::{
+        ICalendar, ICalendarAction, ICalendarClassification, ICalendarComponent,
+        ICalendarComponentType, ICalendarDay, ICalendarDuration, ICalendarEntry,
+        ICalendarFreeBusyType, ICalendarFrequency, ICalendarMethod, ICalendarMonth,
+        ICalendarParameter, ICalendarParticipationStatus, ICalendarPeriod, ICalendarProperty,
+        ICalendarRecurrenceRule, ICalendarScaleType, ICalendarStatus, ICalendarTransparency,
+        ICalendarValue, ICalendarValueType, ICalendarWeekday, Related,
+    },
+    vcard::{
+        VCard, VCardEntry, VCardParameter, VCardProperty, VCardType, VCardValue, VCardValueType,
+    },
+};
+use dav_proto::schema::property::CalendarDataType;
+use serde_json::{Map, Value};
+use std::{borrow::Cow, fmt::Write};
+
+// A property converted from its parsed representation, ready to be written
+// as jCal, jCard or xCal
+struct PropertyData<'x> {
+    name: Cow<'x, str>,
+    group: Option<&'x str>,
+    params: Vec<(Cow<'x, str>, Vec<Cow<'x, str>>)>,
+    value_type: &'static str,
+    values: Vec<PropertyValue<'x>>,
+}
+
+enum PropertyValue<'x> {
+    Text(Cow<'x, str>),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Period(String, String),
+    Recur(Vec<(&'static str, Vec<PropertyValue<'x>>)>),
+    Structured(Vec<PropertyValue<'x>>),
+}
+
+pub(crate) fn write_calendar_data(ical: &str, data_type: CalendarDataType, out: &mut String) {
+    match data_type {
+        CalendarDataType::ICalendar => out.push_str(ical),
+        CalendarDataType::JCal | CalendarDataType::XCal => {
+            if let Entry::ICalendar(ical) = Parser::new(ical).entry() {
+                if data_type == CalendarDataType::JCal {
+                    write_jcal(&ical, out);
+                } else {
+                    write_xcal(&ical, out);
+                }
+            }
+        }
+    }
+}
+
+// Converts an iCalendar object to its jCal (RFC 7265) representation
+pub(crate) fn write_jcal(ical: &ICalendar, out: &mut String) {
+    if let Some(root) = ical.components.first() {
+        write_jcal_component(ical, root, out);
+    }
+}
+
+// Converts an iCalendar object to its xCal (RFC 6321) representation
+pub(crate) fn write_xcal(ical: &ICalendar, out: &mut String) {
+    out.push_str("<icalendar xmlns=\"urn:ietf:params:xml:ns:icalendar-2.0\">");
+    if let Some(root) = ical.components.first() {
+        write_xcal_component(ical, root, out);
+    }
+    out.push_str("</icalendar>");
+}
+
+fn write_jcal_component(ical: &ICalendar, component: &ICalendarComponent, out: &mut String) {
+    out.push('[');
+    write_json_string(out, &component.component_type.as_str().to_ascii_lowercase());
+    out.push_str(",[");
+    for (pos, entry) in component.entries.iter().enumerate() {
+        if pos > 0 {
+            out.push(',');
+        }
+        ical_property(entry).write_json(out);
+    }
+    out.push_str("],[");
+    for (pos, component_id) in component.component_ids.iter().enumerate() {
+        if let Some(component) = ical.components.get(*component_id as usize) {
+            if pos > 0 {
+                out.push(',');
+            }
+            write_jcal_component(ical, component, out);
+        }
+    }
+    out.push_str("]]");
+}
+
+fn write_xcal_component(ical: &ICalendar, component: &ICalendarComponent, out: &mut String) {
+    let name = component.component_type.as_str().to_ascii_lowercase();
+    if !is_xml_name(&name) {
+        return;
+    }
+
+    let _ = write!(out, "<{name}><properties>");
+    for entry in &component.entries {
+        ical_property(entry).write_xml(out);
+    }
+    out.push_str("</properties>");
+    if !component.component_ids.is_empty() {
+        out.push_str("<components>");
+        for component_id in &component.component_ids {
+            if let Some(component) = ical.components.get(*component_id as usize) {
+                write_xcal_component(ical, component, out);
+            }
+        }
+        out.push_str("</components>");
+    }
+    let _ = write!(out, "</{name}>");
+}
+
+fn ical_property(entry: &ICalendarEntry) -> PropertyData<'_> {
+    let name = entry.name.as_str().to_ascii_lowercase();
+    let value_type = entry
+        .params
+        .iter()
+        .find_map(|param| match param {
+            ICalendarParameter::Value(value_type) => Some(value_type_name(value_type.as_str())),
+            _ => None,
+        })
+        .unwrap_or_else(|| match entry.values.first() {
+            Some(ICalendarValue::PartialDateTime(dt)) => {
+                if matches!(name.as_str(), "tzoffsetfrom" | "tzoffsetto") {
+                    "utc-offset"
+                } else {
+                    date_time_type(dt)
+                }
+            }
+            Some(ICalendarValue::Uri(_)) => {
+                if matches!(name.as_str(), "attendee" | "organizer") {
+                    "cal-address"
+                } else {
+                    "uri"
+                }
+            }
+            Some(ICalendarValue::Duration(_)) => "duration",
+            Some(ICalendarValue::Period(_)) => "period",
+            Some(ICalendarValue::RecurrenceRule(_)) => "recur",
+            Some(ICalendarValue::Float(_)) => "float",
+            Some(ICalendarValue::Integer(_)) => "integer",
+            Some(ICalendarValue::Boolean(_)) => "boolean",
+            Some(ICalendarValue::Binary(_)) => "binary",
+            Some(ICalendarValue::Text(_)) | None => ical_default_type(&name),
+            Some(_) => "text",
+        });
+
+    // GEO and REQUEST-STATUS are structured values
+    let mut values = entry
+        .values
+        .iter()
+        .map(|value| ical_value(value, value_type))
+        .collect::<Vec<_>>();
+    if matches!(name.as_str(), "geo" | "request-status") && values.len() > 1 {
+        values = vec![PropertyValue::Structured(values)];
+    }
+
+    PropertyData {
+        params: entry.params.iter().filter_map(ical_param).collect(),
+        name: name.into(),
+        group: None,
+        value_type,
+        values,
+    }
+}
+
+fn ical_value<'x>(value: &'x ICalendarValue, value_type: &str) -> PropertyValue<'x> {
+    match value {
+        ICalendarValue::Binary(bytes) => PropertyValue::Text(STANDARD.encode(bytes).into()),
+        ICalendarValue::Boolean(value) => PropertyValue::Boolean(*value),
+        ICalendarValue::Uri(uri) => PropertyValue::Text(uri_text(uri)),
+        ICalendarValue::PartialDateTime(dt) => {
+            PropertyValue::Text(format_date_time(dt, value_type).into())
+        }
+        ICalendarValue::Duration(duration) => PropertyValue::Text(format_duration(duration).into()),
+        ICalendarValue::RecurrenceRule(rule) => recur_value(rule),
+        ICalendarValue::Period(ICalendarPeriod::Range { start, end }) => {
+            PropertyValue::Period(date_time_text(start), date_time_text(end))
+        }
+        ICalendarValue::Period(ICalendarPeriod::Duration { start, duration }) => {
+            PropertyValue::Period(date_time_text(start), format_duration(duration))
+        }
+        ICalendarValue::Float(value) => PropertyValue::Float(*value),
+        ICalendarValue::Integer(value) => PropertyValue::Integer(*value),
+        ICalendarValue::Text(value) => PropertyValue::Text(value.as_str().into()),
+        ICalendarValue::CalendarScale(value) => PropertyValue::Text(value.as_str().into()),
+        ICalendarValue::Method(value) => PropertyValue::Text(value.as_str().into()),
+        ICalendarValue::Classification(value) => PropertyValue::Text(value.as_str().into()),
+        ICalendarValue::Status(value) => PropertyValue::Text(value.as_str().into()),
+        ICalendarValue::Transparency(value) => PropertyValue::Text(value.as_str().into()),
+        ICalendarValue::Action(value) => PropertyValue::Text(value.as_str().into()),
+        ICalendarValue::BusyType(value) => PropertyValue::Text(value.as_str().into()),
+        ICalendarValue::ParticipantType(value) => PropertyValue::Text(value.as_str().into()),
+        ICalendarValue::ResourceType(value) => PropertyValue::Text(value.as_str().into()),
+        ICalendarValue::Proximity(value) => PropertyValue::Text(value.as_str().into()),
+    }
+}
+
+fn ical_param(param: &ICalendarParameter) -> Option<(Cow<'_, str>, Vec<Cow<'_, str>>)> {
+    let (name, values): (&str, Vec<Cow<'_, str>>) = match param {
+        ICalendarParameter::Value(_) => return None,
+        ICalendarParameter::Altrep(uri) => ("altrep", vec![uri_text(uri)]),
+        ICalendarParameter::Cn(value) => ("cn", vec![value.into()]),
+        ICalendarParameter::Cutype(value) => ("cutype", vec![value.as_str().into()]),
+        ICalendarParameter::DelegatedFrom(uris) => {
+            ("delegated-from", uris.iter().map(uri_text).collect())
+        }
+        ICalendarParameter::DelegatedTo(uris) => {
+            ("delegated-to", uris.iter().map(uri_text).collect())
+        }
+        ICalendarParameter::Dir(uri) => ("dir", vec![uri_text(uri)]),
+        ICalendarParameter::Fmttype(value) => ("fmttype", vec![value.into()]),
+        ICalendarParameter::Fbtype(value) => ("fbtype", vec![value.as_str().into()]),
+        ICalendarParameter::Language(value) => ("language", vec![value.into()]),
+        ICalendarParameter::Member(uris) => ("member", uris.iter().map(uri_text).collect()),
+        ICalendarParameter::Partstat(value) => ("partstat", vec![value.as_str().into()]),
+        ICalendarParameter::Range => ("range", vec!["THISANDFUTURE".into()]),
+        ICalendarParameter::Related(value) => ("related", vec![value.as_str().into()]),
+        ICalendarParameter::Reltype(value) => ("reltype", vec![value.as_str().into()]),
+        ICalendarParameter::Role(value) => ("role", vec![value.as_str().into()]),
+        ICalendarParameter::Rsvp(value) => ("rsvp", vec![boolean_text(*value).into()]),
+        ICalendarParameter::ScheduleAgent(value) => ("schedule-agent", vec![value.as_str().into()]),
+        ICalendarParameter::ScheduleForceSend(value) => {
+            ("schedule-force-send", vec![value.as_str().into()])
+        }
+        ICalendarParameter::ScheduleStatus(value) => ("schedule-status", vec![value.into()]),
+        ICalendarParameter::SentBy(uri) => ("sent-by", vec![uri_text(uri)]),
+        ICalendarParameter::Tzid(value) => ("tzid", vec![value.into()]),
+        ICalendarParameter::Display(values) => (
+            "display",
+            values.iter().map(|value| value.as_str().into()).collect(),
+        ),
+        ICalendarParameter::Email(value) => ("email", vec![value.into()]),
+        ICalendarParameter::Feature(values) => (
+            "feature",
+            values.iter().map(|value| value.as_str().into()).collect(),
+        ),
+        ICalendarParameter::Label(value) => ("label", vec![value.into()]),
+        ICalendarParameter::Size(value) => ("size", vec![value.to_string().into()]),
+        ICalendarParameter::Filename(value) => ("filename", vec![value.into()]),
+        ICalendarParameter::ManagedId(value) => ("managed-id", vec![value.into()]),
+        ICalendarParameter::Order(value) => ("order", vec![value.to_string().into()]),
+        ICalendarParameter::Schema(uri) => ("schema", vec![uri_text(uri)]),
+        ICalendarParameter::Derived(value) => ("derived", vec![boolean_text(*value).into()]),
+        ICalendarParameter::Gap(duration) => ("gap", vec![format_duration(duration).into()]),
+        ICalendarParameter::Linkrel(uri) => ("linkrel", vec![uri_text(uri)]),
+        ICalendarParameter::Other(values) => {
+            let (name, values) = values.split_first()?;
+            return Some((
+                name.to_ascii_lowercase().into(),
+                values.iter().map(|value| value.into()).collect(),
+            ));
+        }
+    };
+
+    Some((name.into(), values))
+}
+
+fn recur_value(rule: &ICalendarRecurrenceRule) -> PropertyValue<'_> {
+    let mut parts = vec![("freq", vec![PropertyValue::Text(rule.freq.as_str().into())])];
+    if let Some(until) = &rule.until {
+        parts.push((
+            "until",
+            vec![PropertyValue::Text(
+                format_date_time(until, date_time_type(until)).into(),
+            )],
+        ));
+    }
+    if let Some(count) = rule.count {
+        parts.push(("count", vec![PropertyValue::Integer(count as i64)]));
+    }
+    if let Some(interval) = rule.interval {
+        parts.push(("interval", vec![PropertyValue::Integer(interval as i64)]));
+    }
+    let numeric: [(&'static str, Vec<i64>); 7] = [
+        (
+            "bysecond",
+            rule.bysecond.iter().map(|v| *v as i64).collect(),
+        ),
+        (
+            "byminute",
+            rule.byminute.iter().map(|v| *v as i64).collect(),
+        ),
+        ("byhour", rule.byhour.iter().map(|v| *v as i64).collect()),
+        (
+            "bymonthday",
+            rule.bymonthday.iter().map(|v| *v as i64).collect(),
+        ),
+        (
+            "byyearday",
+            rule.byyearday.iter().map(|v| *v as i64).collect(),
+        ),
+        (
+            "byweekno",
+            rule.byweekno.iter().map(|v| *v as i64).collect(),
+        ),
+        (
+            "bysetpos",
+            rule.bysetpos.iter().map(|v| *v as i64).collect(),
+        ),
+    ];
+    for (name, values) in numeric {
+        if !values.is_empty() {
+            parts.push((
+                name,
+                values.into_iter().map(PropertyValue::Integer).collect(),
+            ));
+        }
+    }
+    if !rule.byday.is_empty() {
+        parts.push((
+            "byday",
+            rule.byday
+                .iter()
+                .map(|day| {
+                    PropertyValue::Text(
+                        match day.ordwk {
+                            Some(ordwk) => format!("{ordwk}{}", day.weekday.as_str()),
+                            None => day.weekday.as_str().to_string(),
+                        }
+                        .into(),
+                    )
+                })
+                .collect(),
+        ));
+    }
+    if !rule.bymonth.is_empty() {
+        parts.push((
+            "bymonth",
+            rule.bymonth
+                .iter()
+                .map(|month| {
+                    if month.is_leap() {
+                        PropertyValue::Text(format!("{}L", month.month()).into())
+                    } else {
+                        PropertyValue::Integer(month.month() as i64)
+                    }
+                })
+                .collect(),
+        ));
+    }
+    if let Some(wkst) = &rule.wkst {
+        parts.push(("wkst", vec![PropertyValue::Text(wkst.as_str().into())]));
+    }
+
+    PropertyValue::Recur(parts)
+}
+
+// Converts a vCard stream to its jCard (RFC 7095) representation
+pub(crate) fn write_jcard(vcard: &str, out: &mut String) {
+    if let Entry::VCard(vcard) = Parser::new(vcard).entry() {
+        write_jcard_card(&vcard, out);
+    }
+}
+
+fn write_jcard_card(vcard: &VCard, out: &mut String) {
+    out.push_str("[\"vcard\",[");
+    for (pos, entry) in vcard.entries.iter().enumerate() {
+        if pos > 0 {
+            out.push(',');
+        }
+        vcard_property(entry).write_json(out);
+    }
+    out.push_str("]]");
+}
+
+fn vcard_property(entry: &VCardEntry) -> PropertyData<'_> {
+    let name = entry.name.as_str().to_ascii_lowercase();
+    let default_type = vcard_default_type(&name);
+    let value_type = entry
+        .params
+        .iter()
+        .find_map(|param| match param {
+            VCardParameter::Value(value_types) => value_types
+                .first()
+                .map(|value_type| value_type_name(value_type.as_str())),
+            _ => None,
+        })
+        .unwrap_or_else(|| match entry.values.first() {
+            Some(VCardValue::PartialDateTime(dt))
+                if !matches!(default_type, "date-and-or-time" | "timestamp") =>
+            {
+                date_time_type(dt)
+            }
+            Some(VCardValue::Integer(_)) => "integer",
+            Some(VCardValue::Float(_)) => "float",
+            Some(VCardValue::Boolean(_)) => "boolean",
+            Some(VCardValue::Binary(_)) => "uri",
+            _ => default_type,
+        });
+
+    let mut values = entry
+        .values
+        .iter()
+        .map(|value| vcard_value(value, value_type))
+        .collect::<Vec<_>>();
+    match name.as_str() {
+        // jCard always represents vCard 4.0 data
+        "version" => {
+            values = vec![PropertyValue::Text("4.0".into())];
+        }
+        // N and ADR include all of their components, even when empty
+        "n" | "adr" => {
+            let len = if name == "n" { 5 } else { 7 };
+            while values.len() < len {
+                values.push(PropertyValue::Text("".into()));
+            }
+            values = vec![PropertyValue::Structured(values)];
+        }
+        "org" | "gender" | "clientpidmap" if values.len() > 1 => {
+            values = vec![PropertyValue::Structured(values)];
+        }
+        _ => {}
+    }
+
+    PropertyData {
+        params: entry.params.iter().filter_map(vcard_param).collect(),
+        name: name.into(),
+        group: entry.group.as_deref(),
+        value_type,
+        values,
+    }
+}
+
+fn vcard_value<'x>(value: &'x VCardValue, value_type: &str) -> PropertyValue<'x> {
+    match value {
+        VCardValue::Text(value) => PropertyValue::Text(value.as_str().into()),
+        VCardValue::Integer(value) => PropertyValue::Integer(*value),
+        VCardValue::Float(value) => PropertyValue::Float(*value),
+        VCardValue::Boolean(value) => PropertyValue::Boolean(*value),
+        VCardValue::PartialDateTime(dt) => {
+            PropertyValue::Text(format_date_time(dt, value_type).into())
+        }
+        VCardValue::Binary(data) => PropertyValue::Text(data_uri(data).into()),
+        VCardValue::Sex(value) => PropertyValue::Text(value.as_str().into()),
+        VCardValue::GramGender(value) => PropertyValue::Text(value.as_str().into()),
+        VCardValue::Kind(value) => PropertyValue::Text(value.as_str().to_ascii_lowercase().into()),
+        VCardValue::Component(parts) => PropertyValue::Structured(
+            parts
+                .iter()
+                .map(|part| PropertyValue::Text(part.as_str().into()))
+                .collect(),
+        ),
+    }
+}
+
+fn vcard_param(param: &VCardParameter) -> Option<(Cow<'_, str>, Vec<Cow<'_, str>>)> {
+    let (name, values): (&str, Vec<Cow<'_, str>>) = match param {
+        VCardParameter::Value(_) => return None,
+        VCardParameter::Language(value) => ("language", vec![value.into()]),
+        VCardParameter::Pref(value) => ("pref", vec![value.to_string().into()]),
+        VCardParameter::Altid(value) => ("altid", vec![value.into()]),
+        VCardParameter::Pid(values) => ("pid", values.iter().map(|value| value.into()).collect()),
+        VCardParameter::Type(values) => (
+            "type",
+            values
+                .iter()
+                .map(|value| value.as_str().to_ascii_lowercase().into())
+                .collect(),
+        ),
+        VCardParameter::Mediatype(value) => ("mediatype", vec![value.into()]),
+        VCardParameter::Calscale(value) => {
+            ("calscale", vec![value.as_str().to_ascii_lowercase().into()])
+        }
+        VCardParameter::SortAs(value) => ("sort-as", vec![value.into()]),
+        VCardParameter::Geo(value) => ("geo", vec![value.into()]),
+        VCardParameter::Tz(value) => ("tz", vec![value.into()]),
+        VCardParameter::Index(value) => ("index", vec![value.to_string().into()]),
+        VCardParameter::Level(value) => ("level", vec![value.as_str().to_ascii_lowercase().into()]),
+        VCardParameter::Cc(value) => ("cc", vec![value.into()]),
+        VCardParameter::Author(value) => ("author", vec![value.into()]),
+        VCardParameter::AuthorName(value) => ("author-name", vec![value.into()]),
+        VCardParameter::Created(value) => (
+            "created",
+            vec![
+                format_date_time(&PartialDateTime::from_utc_timestamp(*value), "timestamp").into(),
+            ],
+        ),
+        VCardParameter::Derived(value) => ("derived", vec![boolean_text(*value).into()]),
+        VCardParameter::Label(value) => ("label", vec![value.into()]),
+        VCardParameter::Phonetic(value) => {
+            ("phonetic", vec![value.as_str().to_ascii_lowercase().into()])
+        }
+        VCardParameter::PropId(value) => ("prop-id", vec![value.into()]),
+        VCardParameter::Script(value) => ("script", vec![value.into()]),
+        VCardParameter::ServiceType(value) => ("service-type", vec![value.into()]),
+        VCardParameter::Username(value) => ("username", vec![value.into()]),
+        VCardParameter::Jsptr(value) => ("jsptr", vec![value.into()]),
+        VCardParameter::Other(values) => {
+            let (name, values) = values.split_first()?;
+            return Some((
+                name.to_ascii_lowercase().into(),
+                values.iter().map(|value| value.into()).collect(),
+            ));
+        }
+    };
+
+    Some((name.into(), values))
+}
+
+impl PropertyData<'_> {
+    fn write_json(&self, out: &mut String) {
+        out.push('[');
+        write_json_string(out, &self.name);
+        out.push_str(",{");
+
+        // Property groups are carried in the "group" parameter (RFC 7095, section 3.3.1.2)
+        let mut is_first = true;
+        if let Some(group) = self.group {
+            out.push_str("\"group\":");
+            write_json_string(out, &group.to_ascii_lowercase());
+            is_first = false;
+        }
+        for (name, values) in &self.params {
+            if !is_first {
+                out.push(',');
+            }
+            is_first = false;
+            write_json_string(out, name);
+            out.push(':');
+            if let [value] = values.as_slice() {
+                write_json_string(out, value);
+            } else {
+                out.push('[');
+                for (pos, value) in values.iter().enumerate() {
+                    if pos > 0 {
+                        out.push(',');
+                    }
+                    write_json_string(out, value);
+                }
+                out.push(']');
+            }
+        }
+        out.push_str("},");
+        write_json_string(out, self.value_type);
+
+        if self.values.is_empty() {
+            out.push_str(",\"\"");
+        }
+        for value in &self.values {
+            out.push(',');
+            value.write_json(out);
+        }
+
+        out.push(']');
+    }
+
+    fn write_xml(&self, out: &mut String) {
+        if !is_xml_name(&self.name) {
+            return;
+        }
+
+        let _ = write!(out, "<{}>", self.name);
+        if self.params.iter().any(|(name, _)| is_xml_name(name)) {
+            out.push_str("<parameters>");
+            for (name, values) in &self.params {
+                if !is_xml_name(name) {
+                    continue;
+                }
+                let value_type = match name.as_ref() {
+                    "delegated-from" | "delegated-to" | "member" | "sent-by" => "cal-address",
+                    "altrep" | "dir" => "uri",
+                    _ => "text",
+                };
+                let _ = write!(out, "<{name}>");
+                for value in values {
+                    write_xml_element(out, value_type, value);
+                }
+                let _ = write!(out, "</{name}>");
+            }
+            out.push_str("</parameters>");
+        }
+
+        for value in &self.values {
+            match (self.name.as_ref(), value) {
+                ("geo", PropertyValue::Structured(parts)) => {
+                    for (part, element) in parts.iter().zip(["latitude", "longitude"]) {
+                        part.write_xml(out, element);
+                    }
+                }
+                ("request-status", PropertyValue::Structured(parts)) => {
+                    for (part, element) in parts.iter().zip(["code", "description", "data"]) {
+                        part.write_xml(out, element);
+                    }
+                }
+                _ => value.write_xml(out, self.value_type),
+            }
+        }
+        let _ = write!(out, "</{}>", self.name);
+    }
+}
+
+impl PropertyValue<'_> {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            PropertyValue::Text(value) => write_json_string(out, value),
+            PropertyValue::Integer(value) => {
+                let _ = write!(out, "{value}");
+            }
+            PropertyValue::Float(value) => {
+                if value.is_finite() {
+                    let _ = write!(out, "{value}");
+                } else {
+                    write_json_string(out, &value.to_string());
+                }
+            }
+            PropertyValue::Boolean(value) => {
+                out.push_str(if *value { "true" } else { "false" });
+            }
+            PropertyValue::Period(start, end) => {
+                write_json_string(out, &format!("{start}/{end}"));
+            }
+            PropertyValue::Recur(parts) => {
+                out.push('{');
+                for (pos, (name, values)) in parts.iter().enumerate() {
+                    if pos > 0 {
+                        out.push(',');
+                    }
+                    write_json_string(out, name);
+                    out.push(':');
+                    if let [value] = values.as_slice() {
+                        value.write_json(out);
+                    } else {
+                        PropertyValue::write_json_array(values, out);
+                    }
+                }
+                out.push('}');
+            }
+            PropertyValue::Structured(parts) => {
+                PropertyValue::write_json_array(parts, out);
+            }
+        }
+    }
+
+    fn write_json_array(values: &[PropertyValue<'_>], out: &mut String) {
+        out.push('[');
+        for (pos, value) in values.iter().enumerate() {
+            if pos > 0 {
+                out.push(',');
+            }
+            value.write_json(out);
+        }
+        out.push(']');
+    }
+
+    fn write_xml(&self, out: &mut String, element: &str) {
+        match self {
+            PropertyValue::Text(value) => write_xml_element(out, element, value),
+            PropertyValue::Integer(value) => write_xml_element(out, element, &value.to_string()),
+            PropertyValue::Float(value) => write_xml_element(out, element, &value.to_string()),
+            PropertyValue::Boolean(value) => {
+                write_xml_element(out, element, if *value { "true" } else { "false" })
+            }
+            PropertyValue::Period(start, end) => {
+                out.push_str("<period>");
+                write_xml_element(out, "start", start);
+                if end.starts_with(['P', '+', '-']) {
+                    write_xml_element(out, "duration", end);
+                } else {
+                    write_xml_element(out, "end", end);
+                }
+                out.push_str("</period>");
+            }
+            PropertyValue::Recur(parts) => {
+                out.push_str("<recur>");
+                for (name, values) in parts {
+                    for value in values {
+                        value.write_xml(out, name);
+                    }
+                }
+                out.push_str("</recur>");
+            }
+            PropertyValue::Structured(parts) => {
+                for part in parts {
+                    part.write_xml(out, element);
+                }
+            }
+        }
+    }
+}
+
+fn value_type_name(value_type: &str) -> &'static str {
+    match value_type.to_ascii_lowercase().as_str() {
+        "binary" => "binary",
+        "boolean" => "boolean",
+        "cal-address" => "cal-address",
+        "date" => "date",
+        "date-and-or-time" => "date-and-or-time",
+        "date-time" => "date-time",
+        "duration" => "duration",
+        "float" => "float",
+        "integer" => "integer",
+        "language-tag" => "language-tag",
+        "period" => "period",
+        "recur" => "recur",
+        "text" => "text",
+        "time" => "time",
+        "timestamp" => "timestamp",
+        "uri" => "uri",
+        "utc-offset" => "utc-offset",
+        _ => "unknown",
+    }
+}
+
+// Default value types of properties that were not parsed into a typed value
+fn ical_default_type(name: &str) -> &'static str {
+    match name {
+        "dtstart" | "dtend" | "due" | "recurrence-id" | "exdate" | "rdate" | "created"
+        | "dtstamp" | "last-modified" | "completed" | "acknowledged" | "tzuntil" => "date-time",
+        "duration" | "trigger" | "refresh-interval" => "duration",
+        "percent-complete" | "priority" | "repeat" | "sequence" => "integer",
+        "geo" => "float",
+        "tzoffsetfrom" | "tzoffsetto" => "utc-offset",
+        "attach" | "tzurl" | "url" | "source" | "conference" | "image" => "uri",
+        "attendee" | "organizer" => "cal-address",
+        "rrule" | "exrule" => "recur",
+        "freebusy" => "period",
+        "calscale" | "method" | "prodid" | "version" | "categories" | "class" | "comment"
+        | "description" | "location" | "resources" | "status" | "summary" | "transp" | "tzid"
+        | "tzname" | "contact" | "related-to" | "uid" | "action" | "request-status" | "name"
+        | "color" | "busytype" | "tzid-alias-of" => "text",
+        _ => "unknown",
+    }
+}
+
+fn date_time_type(dt: &PartialDateTime) -> &'static str {
+    if dt.hour.is_none() {
+        "date"
+    } else if dt.year.is_none() && dt.month.is_none() && dt.day.is_none() {
+        "time"
+    } else {
+        "date-time"
+    }
+}
+
+fn date_time_text(dt: &PartialDateTime) -> String {
+    format_date_time(dt, "date-time")
+}
+
+// Formats a date and/or time using the extended format of jCal (RFC 7265) and
+// jCard (RFC 7095), including the reduced accuracy forms allowed by vCard
+fn format_date_time(dt: &PartialDateTime, value_type: &str) -> String {
+    let mut out = String::with_capacity(25);
+
+    if value_type == "utc-offset" {
+        let _ = write!(
+            out,
+            "{}{:02}:{:02}",
+            if dt.tz_minus { '-' } else { '+' },
+            dt.tz_hour.unwrap_or_default(),
+            dt.tz_minute.unwrap_or_default()
+        );
+        return out;
+    }
+
+    let has_date = match (dt.year, dt.month, dt.day) {
+        (Some(year), Some(month), Some(day)) => {
+            let _ = write!(out, "{year:04}-{month:02}-{day:02}");
+            true
+        }
+        (Some(year), Some(month), None) => {
+            let _ = write!(out, "{year:04}-{month:02}");
+            true
+        }
+        (Some(year), None, _) => {
+            let _ = write!(out, "{year:04}");
+            true
+        }
+        (None, Some(month), Some(day)) => {
+            let _ = write!(out, "--{month:02}-{day:02}");
+            true
+        }
+        (None, Some(month), None) => {
+            let _ = write!(out, "--{month:02}");
+            true
+        }
+        (None, None, Some(day)) => {
+            let _ = write!(out, "---{day:02}");
+            true
+        }
+        (None, None, None) => false,
+    };
+
+    if dt.hour.is_some() || dt.minute.is_some() || dt.second.is_some() {
+        if has_date || value_type == "date-and-or-time" {
+            out.push('T');
+        }
+        let _ = match (dt.hour, dt.minute, dt.second) {
+            (Some(hour), Some(minute), Some(second)) => {
+                write!(out, "{hour:02}:{minute:02}:{second:02}")
+            }
+            (Some(hour), Some(minute), None) => write!(out, "{hour:02}:{minute:02}"),
+            (Some(hour), None, _) => write!(out, "{hour:02}"),
+            (None, Some(minute), Some(second)) => write!(out, "-{minute:02}:{second:02}"),
+            (None, Some(minute), None) => write!(out, "-{minute:02}"),
+            (None, None, second) => write!(out, "--{:02}", second.unwrap_or_default()),
+        };
+
+        if let Some(tz_hour) = dt.tz_hour {
+            let tz_minute = dt.tz_minute.unwrap_or_default();
+            if tz_hour == 0 && tz_minute == 0 && !dt.tz_minus {
+                out.push('Z');
+            } else {
+                let _ = write!(
+                    out,
+                    "{}{tz_hour:02}:{tz_minute:02}",
+                    if dt.tz_minus { '-' } else { '+' }
+                );
+            }
+        }
+    }
+
+    out
+}
+
+fn format_duration(duration: &ICalendarDuration) -> String {
+    let mut out = String::with_capacity(16);
+    if duration.neg {
+        out.push('-');
+    }
+    out.push('P');
+    if duration.weeks > 0 {
+        let _ = write!(out, "{}W", duration.weeks);
+    }
+    if duration.days > 0 {
+        let _ = write!(out, "{}D", duration.days);
+    }
+    if duration.hours > 0 || duration.minutes > 0 || duration.seconds > 0 {
+        out.push('T');
+        if duration.hours > 0 {
+            let _ = write!(out, "{}H", duration.hours);
+        }
+        if duration.minutes > 0 {
+            let _ = write!(out, "{}M", duration.minutes);
+        }
+        if duration.seconds > 0 {
+            let _ = write!(out, "{}S", duration.seconds);
+        }
+    } else if duration.weeks == 0 && duration.days == 0 {
+        out.push_str("T0S");
+    }
+    out
+}
+
+fn uri_text(uri: &Uri) -> Cow<'_, str> {
+    match uri {
+        Uri::Location(location) => location.into(),
+        Uri::Data(data) => data_uri(data).into(),
+    }
+}
+
+fn data_uri(data: &Data) -> String {
+    format!(
+        "data:{};base64,{}",
+        data.content_type
+            .as_deref()
+            .unwrap_or("application/octet-stream"),
+        STANDARD.encode(&data.data)
+    )
+}
+
+fn boolean_text(value: bool) -> &'static str {
+    if value { "TRUE" } else { "FALSE" }
+}
+
+fn write_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+fn write_xml_element(out: &mut String, name: &str, value: &str) {
+    let _ = write!(out, "<{name}>");
+    for ch in value.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            ch if (ch as u32) < 0x20 && !matches!(ch, '\n' | '\r' | '\t') => {}
+            ch => out.push(ch),
+        }
+    }
+    let _ = write!(out, "</{name}>");
+}
+
+fn is_xml_name(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_ascii_alphabetic())
+        && name
+            .bytes()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == b'-')
+}
+
+// Parses a jCal (RFC 7265) object
+pub(crate) fn parse_jcal(json: &str) -> Option<ICalendar> {
+    let value = serde_json::from_str::<Value>(json).ok()?;
+    let mut ical = ICalendar {
+        components: Vec::new(),
+    };
+    parse_jcal_component(&value, &mut ical)?;
+
+    (ical.components.first()?.component_type == ICalendarComponentType::VCalendar).then_some(ical)
+}
+
+// Parses a jCard (RFC 7095) object
+pub(crate) fn parse_jcard(json: &str) -> Option<VCard> {
+    let value = serde_json::from_str::<Value>(json).ok()?;
+    let [name, properties] = value.as_array()?.as_slice() else {
+        return None;
+    };
+    if !name.as_str()?.eq_ignore_ascii_case("vcard") {
+        return None;
+    }
+
+    Some(VCard {
+        entries: properties
+            .as_array()?
+            .iter()
+            .map(parse_jcard_property)
+            .collect::<Option<Vec<_>>>()?,
+    })
+}
+
+fn parse_jcal_component(value: &Value, ical: &mut ICalendar) -> Option<u16> {
+    let [name, properties, components] = value.as_array()?.as_slice() else {
+        return None;
+    };
+    let name = name.as_str()?.to_ascii_uppercase();
+    let component_id = u16::try_from(ical.components.len()).ok()?;
+    ical.components.push(ICalendarComponent {
+        component_type: ICalendarComponentType::try_from(name.as_bytes())
+            .unwrap_or_else(|_| ICalendarComponentType::Other(name)),
+        entries: properties
+            .as_array()?
+            .iter()
+            .map(parse_jcal_property)
+            .collect::<Option<Vec<_>>>()?,
+        component_ids: Vec::new(),
+    });
+
+    for component in components.as_array()? {
+        let child_id = parse_jcal_component(component, ical)?;
+        ical.components[component_id as usize]
+            .component_ids
+            .push(child_id);
+    }
+
+    Some(component_id)
+}
+
+fn parse_jcal_property(value: &Value) -> Option<ICalendarEntry> {
+    let [name, params, value_type, values @ ..] = value.as_array()?.as_slice() else {
+        return None;
+    };
+    let name = name.as_str()?.to_ascii_uppercase();
+    let value_type = value_type.as_str()?.to_ascii_lowercase();
+    let default_type = ical_default_type(&name.to_ascii_lowercase());
+    let name = ICalendarProperty::try_from(name.as_bytes())
+        .unwrap_or_else(|_| ICalendarProperty::Other(name));
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut entry_params = params
+        .as_object()?
+        .iter()
+        .filter_map(|(name, value)| parse_ical_param(name, value))
+        .collect::<Vec<_>>();
+    if value_type != default_type && value_type != "unknown" {
+        entry_params.push(ICalendarParameter::Value(
+            ICalendarValueType::try_from(value_type.to_ascii_uppercase().as_bytes()).ok()?,
+        ));
+    }
+
+    // GEO and REQUEST-STATUS are structured values
+    let values = match values {
+        [Value::Array(parts)]
+            if matches!(
+                name,
+                ICalendarProperty::Geo | ICalendarProperty::RequestStatus
+            ) =>
+        {
+            parts
+        }
+        values => values,
+    };
+    let values = values
+        .iter()
+        .map(|value| parse_ical_value(&name, &value_type, value))
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(ICalendarEntry {
+        name,
+        params: entry_params,
+        values,
+    })
+}
+
+fn parse_ical_value(
+    name: &ICalendarProperty,
+    value_type: &str,
+    value: &Value,
+) -> Option<ICalendarValue> {
+    match (value_type, value) {
+        ("recur", Value::Object(rule)) => {
+            parse_recur(rule).map(|rule| ICalendarValue::RecurrenceRule(Box::new(rule)))
+        }
+        ("date" | "date-time" | "time" | "utc-offset", Value::String(value)) => {
+            parse_date_time(value).map(|dt| ICalendarValue::PartialDateTime(Box::new(dt)))
+        }
+        ("duration", Value::String(value)) => parse_duration(value).map(ICalendarValue::Duration),
+        ("period", Value::String(value)) => parse_period(value).map(ICalendarValue::Period),
+        ("uri" | "cal-address", Value::String(value)) => {
+            Some(ICalendarValue::Uri(Uri::Location(value.clone())))
+        }
+        ("binary", Value::String(value)) => STANDARD.decode(value).ok().map(ICalendarValue::Binary),
+        ("float", Value::Number(value)) => value.as_f64().map(ICalendarValue::Float),
+        (_, Value::Number(value)) => value
+            .as_i64()
+            .map(ICalendarValue::Integer)
+            .or_else(|| value.as_f64().map(ICalendarValue::Float)),
+        (_, Value::Bool(value)) => Some(ICalendarValue::Boolean(*value)),
+        (_, Value::String(value)) => Some(ical_text_value(name, value)),
+        _ => None,
+    }
+}
+
+// Properties with enumerated values are stored in their typed representation,
+// as the iCalendar parser does
+fn ical_text_value(name: &ICalendarProperty, value: &str) -> ICalendarValue {
+    let keyword = value.to_ascii_uppercase();
+    let keyword = keyword.as_bytes();
+    match name {
+        ICalendarProperty::Status => ICalendarStatus::try_from(keyword)
+            .ok()
+            .map(ICalendarValue::Status),
+        ICalendarProperty::Transp => ICalendarTransparency::try_from(keyword)
+            .ok()
+            .map(ICalendarValue::Transparency),
+        ICalendarProperty::Class => ICalendarClassification::try_from(keyword)
+            .ok()
+            .map(ICalendarValue::Classification),
+        ICalendarProperty::Action => ICalendarAction::try_from(keyword)
+            .ok()
+            .map(ICalendarValue::Action),
+        ICalendarProperty::Method => ICalendarMethod::try_from(keyword)
+            .ok()
+            .map(ICalendarValue::Method),
+        ICalendarProperty::Calscale => ICalendarScaleType::try_from(keyword)
+            .ok()
+            .map(ICalendarValue::CalendarScale),
+        ICalendarProperty::Busytype => ICalendarFreeBusyType::try_from(keyword)
+            .ok()
+            .map(ICalendarValue::BusyType),
+        _ => None,
+    }
+    .unwrap_or_else(|| ICalendarValue::Text(value.to_string()))
+}
+
+fn parse_ical_param(name: &str, value: &Value) -> Option<ICalendarParameter> {
+    let values = json_texts(value);
+    let value = values.first()?.clone();
+    let keyword = value.to_ascii_uppercase();
+    let keyword = keyword.as_bytes();
+    let name = name.to_ascii_lowercase();
+    let param = match name.as_str() {
+        "value" => return None,
+        "cn" => Some(ICalendarParameter::Cn(value)),
+        "email" => Some(ICalendarParameter::Email(value)),
+        "filename" => Some(ICalendarParameter::Filename(value)),
+        "fmttype" => Some(ICalendarParameter::Fmttype(value)),
+        "label" => Some(ICalendarParameter::Label(value)),
+        "language" => Some(ICalendarParameter::Language(value)),
+        "managed-id" => Some(ICalendarParameter::ManagedId(value)),
+        "schedule-status" => Some(ICalendarParameter::ScheduleStatus(value)),
+        "tzid" => Some(ICalendarParameter::Tzid(value)),
+        "size" => value.parse().ok().map(ICalendarParameter::Size),
+        "rsvp" => Some(ICalendarParameter::Rsvp(keyword == b"TRUE")),
+        "range" => Some(ICalendarParameter::Range),
+        "related" => match keyword {
+            b"START" => Some(ICalendarParameter::Related(Related::Start)),
+            b"END" => Some(ICalendarParameter::Related(Related::End)),
+            _ => None,
+        },
+        "fbtype" => ICalendarFreeBusyType::try_from(keyword)
+            .ok()
+            .map(ICalendarParameter::Fbtype),
+        "partstat" => ICalendarParticipationStatus::try_from(keyword)
+            .ok()
+            .map(ICalendarParameter::Partstat),
+        _ => None,
+    };
+
+    Some(param.unwrap_or_else(|| {
+        ICalendarParameter::Other([vec![name.to_ascii_uppercase()], values].concat())
+    }))
+}
+
+fn parse_recur(rule: &Map<String, Value>) -> Option<ICalendarRecurrenceRule> {
+    let freq = rule
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("freq"))
+        .and_then(|(_, value)| value.as_str())?;
+    let mut recur = ICalendarRecurrenceRule {
+        freq: ICalendarFrequency::try_from(freq.to_ascii_uppercase().as_bytes()).ok()?,
+        ..Default::default()
+    };
+
+    for (name, value) in rule {
+        let values = match value {
+            Value::Array(values) => values.iter().collect::<Vec<_>>(),
+            value => vec![value],
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "freq" => {}
+            "until" => recur.until = Some(parse_date_time(value.as_str()?)?),
+            "count" => recur.count = Some(json_number(value)?),
+            "interval" => recur.interval = Some(json_number(value)?),
+            "bysecond" => recur.bysecond = json_numbers(&values)?,
+            "byminute" => recur.byminute = json_numbers(&values)?,
+            "byhour" => recur.byhour = json_numbers(&values)?,
+            "bymonthday" => recur.bymonthday = json_numbers(&values)?,
+            "byyearday" => recur.byyearday = json_numbers(&values)?,
+            "byweekno" => recur.byweekno = json_numbers(&values)?,
+            "bysetpos" => recur.bysetpos = json_numbers(&values)?,
+            "byday" => {
+                recur.byday = values
+                    .into_iter()
+                    .map(|value| {
+                        let value = value.as_str()?.to_ascii_uppercase();
+                        let pos = value.find(|ch: char| ch.is_ascii_alphabetic())?;
+                        Some(ICalendarDay {
+                            ordwk: if pos > 0 {
+                                Some(value[..pos].parse().ok()?)
+                            } else {
+                                None
+                            },
+                            weekday: ICalendarWeekday::try_from(value[pos..].as_bytes()).ok()?,
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()?
+            }
+            "bymonth" => {
+                recur.bymonth = values
+                    .into_iter()
+                    .map(|value| match value {
+                        Value::String(month) => match month.strip_suffix(['L', 'l']) {
+                            Some(month) => Some(ICalendarMonth::new(month.parse().ok()?, true)),
+                            None => Some(ICalendarMonth::new(month.parse().ok()?, false)),
+                        },
+                        month => Some(ICalendarMonth::new(json_number(month)?, false)),
+                    })
+                    .collect::<Option<Vec<_>>>()?
+            }
+            "wkst" => {
+                recur.wkst = Some(
+                    ICalendarWeekday::try_from(value.as_str()?.to_ascii_uppercase().as_bytes())
+                        .ok()?,
+                )
+            }
+            _ => return None,
+        }
+    }
+
+    Some(recur)
+}
+
+fn parse_jcard_property(value: &Value) -> Option<VCardEntry> {
+    let [name, params, value_type, values @ ..] = value.as_array()?.as_slice() else {
+        return None;
+    };
+    let name = name.as_str()?.to_ascii_uppercase();
+    let value_type = value_type.as_str()?.to_ascii_lowercase();
+    let default_type = vcard_default_type(&name.to_ascii_lowercase());
+    let name =
+        VCardProperty::try_from(name.as_bytes()).unwrap_or_else(|_| VCardProperty::Other(name));
+    if values.is_empty() {
+        return None;
+    }
+
+    // Property groups are carried in the "group" parameter
+    let mut group = None;
+    let mut entry_params = Vec::new();
+    for (param, value) in params.as_object()? {
+        if param.eq_ignore_ascii_case("group") {
+            group = json_texts(value).into_iter().next();
+        } else if let Some(param) = parse_vcard_param(param, value) {
+            entry_params.push(param);
+        }
+    }
+    if value_type != default_type && value_type != "unknown" {
+        entry_params.push(VCardParameter::Value(vec![
+            VCardValueType::try_from(value_type.to_ascii_uppercase().as_bytes()).ok()?,
+        ]));
+    }
+
+    let mut entry_values = Vec::with_capacity(values.len());
+    for value in values {
+        if let Value::Array(parts) = value {
+            for part in parts {
+                entry_values.push(if let Value::Array(part) = part {
+                    VCardValue::Component(part.iter().filter_map(json_text).collect())
+                } else {
+                    parse_vcard_value(&value_type, part)?
+                });
+            }
+        } else {
+            entry_values.push(parse_vcard_value(&value_type, value)?);
+        }
+    }
+
+    Some(VCardEntry {
+        group,
+        name,
+        params: entry_params,
+        values: entry_values,
+    })
+}
+
+fn parse_vcard_value(value_type: &str, value: &Value) -> Option<VCardValue> {
+    match (value_type, value) {
+        (
+            "date" | "time" | "date-time" | "date-and-or-time" | "timestamp",
+            Value::String(value),
+        ) => parse_date_time(value).map(VCardValue::PartialDateTime),
+        ("float", Value::Number(value)) => value.as_f64().map(VCardValue::Float),
+        (_, Value::Number(value)) => value
+            .as_i64()
+            .map(VCardValue::Integer)
+            .or_else(|| value.as_f64().map(VCardValue::Float)),
+        (_, Value::Bool(value)) => Some(VCardValue::Boolean(*value)),
+        (_, Value::String(value)) => Some(VCardValue::Text(value.clone())),
+        _ => None,
+    }
+}
+
+fn parse_vcard_param(name: &str, value: &Value) -> Option<VCardParameter> {
+    let values = json_texts(value);
+    let value = values.first()?.clone();
+    let name = name.to_ascii_lowercase();
+    let param = match name.as_str() {
+        "value" => return None,
+        "altid" => Some(VCardParameter::Altid(value)),
+        "cc" => Some(VCardParameter::Cc(value)),
+        "geo" => Some(VCardParameter::Geo(value)),
+        "label" => Some(VCardParameter::Label(value)),
+        "language" => Some(VCardParameter::Language(value)),
+        "mediatype" => Some(VCardParameter::Mediatype(value)),
+        "sort-as" => Some(VCardParameter::SortAs(values.join(","))),
+        "tz" => Some(VCardParameter::Tz(value)),
+        "pid" => Some(VCardParameter::Pid(values.clone())),
+        "pref" => value.parse().ok().map(VCardParameter::Pref),
+        "type" => values
+            .iter()
+            .map(|value| VCardType::try_from(value.to_ascii_uppercase().as_bytes()).ok())
+            .collect::<Option<Vec<_>>>()
+            .map(VCardParameter::Type),
+        _ => None,
+    };
+
+    Some(param.unwrap_or_else(|| {
+        VCardParameter::Other([vec![name.to_ascii_uppercase()], values].concat())
+    }))
+}
+
+// Parses a date and/or time in the extended format, including UTC offsets and
+// the reduced accuracy forms allowed by vCard
+fn parse_date_time(value: &str) -> Option<PartialDateTime> {
+    let mut dt = PartialDateTime::default();
+    let (date, time) = match value.split_once(['T', 't']) {
+        Some((date, time)) => (date, Some(time)),
+        None if value.contains(':') => ("", Some(value)),
+        None => (value, None),
+    };
+
+    if let Some(day) = date.strip_prefix("---") {
+        dt.day = Some(day.parse().ok()?);
+    } else if let Some(month_day) = date.strip_prefix("--") {
+        let mut parts = month_day.splitn(2, '-');
+        dt.month = Some(parts.next()?.parse().ok()?);
+        dt.day = parts.next().map(str::parse).transpose().ok()?;
+    } else if !date.is_empty() {
+        let mut parts = date.splitn(3, '-');
+        dt.year = Some(parts.next()?.parse().ok()?);
+        dt.month = parts.next().map(str::parse).transpose().ok()?;
+        dt.day = parts.next().map(str::parse).transpose().ok()?;
+    }
+
+    if let Some(time) = time {
+        // UTC offsets have a zone but no time component
+        let (time, zone) = time.split_at(time.find(['Z', 'z', '+', '-']).unwrap_or(time.len()));
+
+        let mut parts = time.split(':').filter(|part| !part.is_empty());
+        if let Some(hour) = parts.next() {
+            dt.hour = Some(hour.parse().ok()?);
+            dt.minute = Some(parts.next().map_or(Ok(0), str::parse).ok()?);
+            dt.second = Some(parts.next().map_or(Ok(0), str::parse).ok()?);
+        }
+
+        match zone.as_bytes().first() {
+            Some(b'Z' | b'z') => {
+                dt.tz_hour = Some(0);
+                dt.tz_minute = Some(0);
+            }
+            Some(sign @ (b'+' | b'-')) => {
+                let offset = zone[1..].replace(':', "");
+                dt.tz_minus = *sign == b'-';
+                dt.tz_hour = Some(offset.get(..2)?.parse().ok()?);
+                dt.tz_minute = Some(offset.get(2..4).unwrap_or("0").parse().ok()?);
+            }
+            _ => {}
+        }
+    }
+
+    Some(dt)
+}
+
+fn parse_duration(value: &str) -> Option<ICalendarDuration> {
+    let mut duration = ICalendarDuration::default();
+    let value = match value.strip_prefix('-') {
+        Some(value) => {
+            duration.neg = true;
+            value
+        }
+        None => value.strip_prefix('+').unwrap_or(value),
+    };
+
+    let mut number = 0u32;
+    let mut is_time = false;
+    for ch in value.strip_prefix(['P', 'p'])?.chars() {
+        match ch.to_ascii_uppercase() {
+            '0'..='9' => {
+                number = number
+                    .checked_mul(10)?
+                    .checked_add(ch as u32 - '0' as u32)?;
+            }
+            'T' => is_time = true,
+            'W' if !is_time => duration.weeks = std::mem::take(&mut number),
+            'D' if !is_time => duration.days = std::mem::take(&mut number),
+            'H' if is_time => duration.hours = std::mem::take(&mut number),
+            'M' if is_time => duration.minutes = std::mem::take(&mut number),
+            'S' if is_time => duration.seconds = std::mem::take(&mut number),
+            _ => return None,
+        }
+    }
+
+    Some(duration)
+}
+
+fn parse_period(value: &str) -> Option<ICalendarPeriod> {
+    let (start, end) = value.split_once('/')?;
+    let start = parse_date_time(start)?;
+    if end.starts_with(['P', 'p', '+', '-']) {
+        Some(ICalendarPeriod::Duration {
+            start,
+            duration: parse_duration(end)?,
+        })
+    } else {
+        Some(ICalendarPeriod::Range {
+            start,
+            end: parse_date_time(end)?,
+        })
+    }
+}
+
+fn json_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(value) => Some(boolean_text(*value).to_string()),
+        Value::Null | Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+fn json_texts(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(values) => values.iter().filter_map(json_text).collect(),
+        value => json_text(value).into_iter().collect(),
+    }
+}
+
+fn json_number<T: TryFrom<i64>>(value: &Value) -> Option<T> {
+    value
+        .as_i64()
+        .or_else(|| value.as_str()?.trim().parse().ok())
+        .and_then(|value| T::try_from(value).ok())
+}
+
+fn json_numbers<T: TryFrom<i64>>(values: &[&Value]) -> Option<Vec<T>> {
+    values.iter().map(|value| json_number(value)).collect()
+}
+
+fn vcard_default_type(name: &str) -> &'static str {
+    match name {
+        "bday" | "anniversary" | "deathdate" => "date-and-or-time",
+        "rev" => "timestamp",
+        "source" | "photo" | "logo" | "sound" | "url" | "fburl" | "caladruri" | "caluri"
+        | "member" | "related" | "impp" | "geo" | "key" => "uri",
+        "lang" => "language-tag",
+        _ => "text",
+    }
+}
@@ -4,9 +4,6 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::str::FromStr;
-
-use super::query::CalendarQueryHandler;
 use crate::{DavError, calendar::query::is_resource_in_time_range, common::uri::DavUriResource};
 use calcard::{
     common::{PartialDateTime, timezone::Tz},
@@ -15,15 +12,21 @@ use calcard::{
         ArchivedICalendarProperty, ArchivedICalendarStatus, ArchivedICalendarValue, ICalendar,
         ICalendarComponent, ICalendarComponentType, ICalendarEntry, ICalendarFreeBusyType,
         ICalendarParameter, ICalendarPeriod, ICalendarProperty, ICalendarTransparency,
-        ICalendarValue,
+        ICalendarValue, dates::TimeOrDelta,
     },
 };
-use common::{PROD_ID, Server, auth::AccessToken};
+use common::{PROD_ID, ScheduleCollection, Server, auth::AccessToken};
 use dav_proto::{
     RequestHeaders,
     schema::{property::TimeRange, request::FreeBusyQuery},
 };
-use groupware::{cache::GroupwareCache, calendar::CalendarEvent};
+use groupware::{
+    cache::GroupwareCache,
+    calendar::{
+        ArchivedCalendar, ArchivedCalendarEvent, CALENDAR_TRANSPARENT, Calendar, CalendarEvent,
+        dates::ExpandCalendarEvent, timezone::parse_tz,
+    },
+};
 use http_proto::HttpResponse;
 use hyper::StatusCode;
 use jmap_proto::types::{
@@ -32,9 +35,13 @@ use jmap_proto::types::{
 };
 use store::{
     ahash::AHashMap,
-    write::{now, serialize::rkyv_deserialize},
+    write::{Archive, now, serialize::rkyv_deserialize},
 };
 use trc::AddContext;
+use utils::url_params::UrlParams;
+
+// Window covered by free/busy GET requests without an explicit range
+const FREEBUSY_DEFAULT_WINDOW: i64 = 42 * 86400;
 
 pub(crate) trait CalendarFreebusyRequestHandler: Sync + Send {
     fn handle_calendar_freebusy_request(
@@ -43,6 +50,22 @@ pub(crate) trait CalendarFreebusyRequestHandler: Sync + Send {
         headers: &RequestHeaders<'_>,
         request: FreeBusyQuery,
     ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+
+    fn handle_calendar_freebusy_get_request(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        query: Option<&str>,
+        is_head: bool,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+
+    fn build_freebusy(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        resource: Option<&str>,
+        range: Option<TimeRange>,
+    ) -> impl Future<Output = crate::Result<String>> + Send;
 }
 
 impl CalendarFreebusyRequestHandler for Server {
@@ -57,22 +80,124 @@ impl CalendarFreebusyRequestHandler for Server {
             .validate_uri(access_token, headers.uri)
             .await?
             .into_owned_uri()?;
-        let account_id = resource_.account_id;
+        let ical = self
+            .build_freebusy(
+                access_token,
+                resource_.account_id,
+                resource_.resource,
+                request.range,
+            )
+            .await?;
+
+        Ok(HttpResponse::new(StatusCode::OK)
+            .with_content_type("text/calendar; charset=utf-8")
+            .with_text_body(ical))
+    }
+
+    async fn handle_calendar_freebusy_get_request(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        query: Option<&str>,
+        is_head: bool,
+    ) -> crate::Result<HttpResponse> {
+        // Parse range, defaulting to the upcoming weeks
+        let params = UrlParams::new(query);
+        let mut range = TimeRange {
+            start: now() as i64,
+            end: now() as i64 + FREEBUSY_DEFAULT_WINDOW,
+        };
+        for (param, value) in [("start", &mut range.start), ("end", &mut range.end)] {
+            if let Some(param) = params.get(param) {
+                *value =
+                    parse_utc_timestamp(param).ok_or(DavError::Code(StatusCode::BAD_REQUEST))?;
+            }
+        }
+        if range.end <= range.start {
+            return Err(DavError::Code(StatusCode::BAD_REQUEST));
+        }
+
+        let ical = self
+            .build_freebusy(access_token, account_id, None, Some(range))
+            .await?;
+        let response = HttpResponse::new(StatusCode::OK)
+            .with_content_type("text/calendar; charset=utf-8")
+            .with_no_cache();
+
+        if !is_head {
+            Ok(response.with_text_body(ical))
+        } else {
+            Ok(response.with_content_length(ical.len()))
+        }
+    }
+
+    async fn build_freebusy(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        resource: Option<&str>,
+        range: Option<TimeRange>,
+    ) -> crate::Result<String> {
         let resources = self
             .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
             .await
             .caused_by(trc::location!())?;
-        let resource = resources
-            .by_path(
-                resource_
-                    .resource
-                    .ok_or(DavError::Code(StatusCode::METHOD_NOT_ALLOWED))?,
-            )
-            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
-        if !resource.is_container() {
-            return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+
+        // Requests on the calendar home aggregate all opaque calendars of the account
+        let mut calendars = Vec::new();
+        let mut availability = Vec::new();
+        if let Some(resource) = resource {
+            let resource = resources
+                .by_path(resource)
+                .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+            if !resource.is_container() {
+                return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+            }
+            let default_tz = resource.resource.timezone().unwrap_or(Tz::UTC);
+            if let Some(calendar_) = self
+                .get_archive(account_id, Collection::Calendar, resource.document_id())
+                .await
+                .caused_by(trc::location!())?
+            {
+                let calendar = calendar_
+                    .unarchive::<Calendar>()
+                    .caused_by(trc::location!())?;
+                if let Some(ical) = calendar_availability(calendar)? {
+                    availability.push((ical, default_tz));
+                }
+            }
+            calendars.push((resource.document_id(), default_tz));
+        } else {
+            for resource in resources.resources.iter() {
+                if !resource.is_container()
+                    || resource.schedule_collection() == Some(ScheduleCollection::Outbox)
+                {
+                    continue;
+                }
+                let Some(calendar_) = self
+                    .get_archive(account_id, Collection::Calendar, resource.document_id)
+                    .await
+                    .caused_by(trc::location!())?
+                else {
+                    continue;
+                };
+                let calendar = calendar_
+                    .unarchive::<Calendar>()
+                    .caused_by(trc::location!())?;
+                let default_tz = resource.timezone().unwrap_or(Tz::UTC);
+
+                // The availability stored on the scheduling inbox applies to the whole account
+                if let Some(ical) = calendar_availability(calendar)? {
+                    availability.push((ical, default_tz));
+                }
+                if resource.schedule_collection().is_none()
+                    && calendar.preferences(account_id).flags.to_native() & CALENDAR_TRANSPARENT
+                        == 0
+                {
+                    calendars.push((resource.document_id, default_tz));
+                }
+            }
         }
-        let default_tz = resource.resource.timezone().unwrap_or(Tz::UTC);
 
         // Obtain shared ids
         let shared_ids = if !access_token.is_member(account_id) {
@@ -85,7 +210,7 @@ impl CalendarFreebusyRequestHandler for Server {
 
         // Build FreeBusy component
         let mut entries = Vec::with_capacity(6);
-        if let Some(range) = request.range {
+        if let Some(range) = range {
             entries.push(ICalendarEntry {
                 name: ICalendarProperty::Dtstart,
                 params: vec![],
@@ -108,127 +233,53 @@ impl CalendarFreebusyRequestHandler for Server {
                 ))],
             });
 
-            let document_ids = resources
-                .children(resource.document_id())
-                .filter(|resource| {
-                    shared_ids
-                        .as_ref()
-                        .is_none_or(|ids| ids.contains(resource.document_id()))
-                        && is_resource_in_time_range(resource.resource, &range)
-                })
-                .map(|resource| resource.document_id())
-                .collect::<Vec<_>>();
-
-            let mut fb_entries: AHashMap<ICalendarFreeBusyType, Vec<(i64, i64)>> =
-                AHashMap::with_capacity(document_ids.len());
-
-            for document_id in document_ids {
-                let archive = if let Some(archive) = self
-                    .get_archive(account_id, Collection::CalendarEvent, document_id)
-                    .await
-                    .caused_by(trc::location!())?
-                {
-                    archive
-                } else {
-                    continue;
-                };
-                let event = archive
-                    .unarchive::<CalendarEvent>()
-                    .caused_by(trc::location!())?;
+            let mut fb_entries: AHashMap<ICalendarFreeBusyType, Vec<(i64, i64)>> = AHashMap::new();
 
-                /*
-                   Only VEVENT components without a TRANSP property or with the TRANSP
-                   property set to OPAQUE, and VFREEBUSY components SHOULD be considered
-                   in generating the free busy time information.
-                */
-                let mut components = event
-                    .data
-                    .event
-                    .components
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, comp)| {
-                        (matches!(comp.component_type, ArchivedICalendarComponentType::VEvent)
-                            && comp
-                                .transparency()
-                                .is_none_or(|t| t == &ICalendarTransparency::Opaque))
-                            || matches!(
-                                comp.component_type,
-                                ArchivedICalendarComponentType::VFreebusy
-                            )
+            for (calendar_id, default_tz) in calendars {
+                let document_ids = resources
+                    .children(calendar_id)
+                    .filter(|resource| {
+                        shared_ids
+                            .as_ref()
+                            .is_none_or(|ids| ids.contains(resource.document_id()))
+                            && is_resource_in_time_range(resource.resource, &range)
                     })
-                    .peekable();
-
-                if components.peek().is_none() {
-                    continue;
-                }
-
-                let events =
-                    CalendarQueryHandler::new(event, Some(range), default_tz).into_expanded_times();
+                    .map(|resource| resource.document_id())
+                    .collect::<Vec<_>>();
 
-                if events.is_empty() {
-                    continue;
+                for document_id in document_ids {
+                    let archive = if let Some(archive) = self
+                        .get_archive(account_id, Collection::CalendarEvent, document_id)
+                        .await
+                        .caused_by(trc::location!())?
+                    {
+                        archive
+                    } else {
+                        continue;
+                    };
+                    let event = archive
+                        .to_unarchived::<CalendarEvent>()
+                        .caused_by(trc::location!())?;
+                    collect_freebusy(
+                        self,
+                        account_id,
+                        document_id,
+                        &event,
+                        range,
+                        default_tz,
+                        &mut fb_entries,
+                    );
                 }
+            }
 
-                for (component_id, component) in components {
-                    let component_id = component_id as u16;
-                    match component.component_type {
-                        ArchivedICalendarComponentType::VEvent => {
-                            let fbtype = match component.status() {
-                                Some(ArchivedICalendarStatus::Cancelled) => continue,
-                                Some(ArchivedICalendarStatus::Tentative) => {
-                                    ICalendarFreeBusyType::BusyTentative
-                                }
-                                Some(ArchivedICalendarStatus::Other(v)) => {
-                                    ICalendarFreeBusyType::Other(v.as_str().to_string())
-                                }
-                                _ => ICalendarFreeBusyType::Busy,
-                            };
-
-                            let mut events_in_range = Vec::new();
-                            for event in &events {
-                                if event.comp_id == component_id
-                                    && range.is_in_range(false, event.start, event.end)
-                                {
-                                    events_in_range.push((event.start, event.end));
-                                }
-                            }
-
-                            if !events_in_range.is_empty() {
-                                fb_entries
-                                    .entry(fbtype)
-                                    .or_default()
-                                    .extend(events_in_range);
-                            }
-                        }
-                        ArchivedICalendarComponentType::VFreebusy => {
-                            for entry in component.entries.iter() {
-                                if matches!(entry.name, ArchivedICalendarProperty::Freebusy) {
-                                    let mut fb_in_range =
-                                        freebusy_in_range_utc(entry, &range, default_tz).peekable();
-                                    if fb_in_range.peek().is_some() {
-                                        let fb_type = entry
-                                            .params
-                                            .iter()
-                                            .find_map(|param| {
-                                                if let ArchivedICalendarParameter::Fbtype(param) =
-                                                    param
-                                                {
-                                                    rkyv_deserialize(param).ok()
-                                                } else {
-                                                    None
-                                                }
-                                            })
-                                            .unwrap_or(ICalendarFreeBusyType::Busy);
-
-                                        fb_entries.entry(fb_type).or_default().extend(fb_in_range);
-                                    }
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
+            for (ical, default_tz) in availability {
+                collect_availability(
+                    &ical,
+                    range,
+                    default_tz,
+                    self.core.groupware.max_ical_instances,
+                    &mut fb_entries,
+                );
             }
 
             for (fbtype, events_in_range) in fb_entries {
@@ -241,7 +292,7 @@ impl CalendarFreebusyRequestHandler for Server {
         }
 
         // Build ICalendar
-        let ical = ICalendar {
+        Ok(ICalendar {
             components: vec![
                 ICalendarComponent {
                     component_type: ICalendarComponentType::VCalendar,
@@ -266,14 +317,228 @@ impl CalendarFreebusyRequestHandler for Server {
                 },
             ],
         }
-        .to_string();
+        .to_string())
+    }
+}
 
-        Ok(HttpResponse::new(StatusCode::OK)
-            .with_content_type("text/calendar; charset=utf-8")
-            .with_text_body(ical))
+fn collect_freebusy(
+    server: &Server,
+    account_id: u32,
+    document_id: u32,
+    event: &Archive<&ArchivedCalendarEvent>,
+    range: TimeRange,
+    default_tz: Tz,
+    fb_entries: &mut AHashMap<ICalendarFreeBusyType, Vec<(i64, i64)>>,
+) {
+    /*
+       Only VEVENT components without a TRANSP property or with the TRANSP
+       property set to OPAQUE, and VFREEBUSY components SHOULD be considered
+       in generating the free busy time information.
+    */
+    let mut components = event
+        .inner
+        .data
+        .event
+        .components
+        .iter()
+        .enumerate()
+        .filter(|(_, comp)| {
+            (matches!(comp.component_type, ArchivedICalendarComponentType::VEvent)
+                && comp
+                    .transparency()
+                    .is_none_or(|t| t == &ICalendarTransparency::Opaque))
+                || matches!(
+                    comp.component_type,
+                    ArchivedICalendarComponentType::VFreebusy
+                )
+        })
+        .peekable();
+
+    if components.peek().is_none() {
+        return;
+    }
+
+    let events = server.expand_event(account_id, document_id, event, default_tz, range);
+
+    if events.is_empty() {
+        return;
+    }
+
+    for (component_id, component) in components {
+        let component_id = component_id as u16;
+        match component.component_type {
+            ArchivedICalendarComponentType::VEvent => {
+                let fbtype = match component.status() {
+                    Some(ArchivedICalendarStatus::Cancelled) => continue,
+                    Some(ArchivedICalendarStatus::Tentative) => {
+                        ICalendarFreeBusyType::BusyTentative
+                    }
+                    Some(ArchivedICalendarStatus::Other(v)) => {
+                        ICalendarFreeBusyType::Other(v.as_str().to_string())
+                    }
+                    _ => ICalendarFreeBusyType::Busy,
+                };
+
+                let mut events_in_range = Vec::new();
+                for event in &events {
+                    if event.comp_id == component_id
+                        && range.is_in_range(false, event.start, event.end)
+                    {
+                        events_in_range.push((event.start, event.end));
+                    }
+                }
+
+                if !events_in_range.is_empty() {
+                    fb_entries
+                        .entry(fbtype)
+                        .or_default()
+                        .extend(events_in_range);
+                }
+            }
+            ArchivedICalendarComponentType::VFreebusy => {
+                for entry in component.entries.iter() {
+                    if matches!(entry.name, ArchivedICalendarProperty::Freebusy) {
+                        let mut fb_in_range =
+                            freebusy_in_range_utc(entry, &range, default_tz).peekable();
+                        if fb_in_range.peek().is_some() {
+                            let fb_type = entry
+                                .params
+                                .iter()
+                                .find_map(|param| {
+                                    if let ArchivedICalendarParameter::Fbtype(param) = param {
+                                        rkyv_deserialize(param).ok()
+                                    } else {
+                                        None
+                                    }
+                                })
+                                .unwrap_or(ICalendarFreeBusyType::Busy);
+
+                            fb_entries.entry(fb_type).or_default().extend(fb_in_range);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
     }
 }
 
+fn collect_availability(
+    availability: &ICalendar,
+    range: TimeRange,
+    default_tz: Tz,
+    max_instances: usize,
+    fb_entries: &mut AHashMap<ICalendarFreeBusyType, Vec<(i64, i64)>>,
+) {
+    let mut periods: AHashMap<u16, Vec<(i64, i64)>> = AHashMap::new();
+    for event in availability.expand_dates(default_tz, max_instances).events {
+        let start = event.start.timestamp();
+        let end = match event.end {
+            TimeOrDelta::Time(time) => time.timestamp(),
+            TimeOrDelta::Delta(delta) => start + delta.num_seconds(),
+        };
+        periods.entry(event.comp_id).or_default().push((start, end));
+    }
+
+    /*
+       Time covered by a VAVAILABILITY component is busy, using the type set
+       by its BUSYTYPE property, unless one of its AVAILABLE components marks
+       it as available.
+    */
+    for (comp_id, component) in availability.components.iter().enumerate() {
+        if component.component_type != ICalendarComponentType::VAvailability {
+            continue;
+        }
+
+        // Components without DTSTART or DTEND are unbounded on that side
+        let has_property = |names: &[ICalendarProperty]| {
+            component
+                .entries
+                .iter()
+                .any(|entry| names.contains(&entry.name))
+        };
+        let (mut start, mut end) = (i64::MIN, i64::MAX);
+        if has_property(&[ICalendarProperty::Dtstart]) {
+            let Some(&(period_start, period_end)) = periods
+                .get(&(comp_id as u16))
+                .and_then(|periods| periods.first())
+            else {
+                continue;
+            };
+            start = period_start;
+            if has_property(&[ICalendarProperty::Dtend, ICalendarProperty::Duration]) {
+                end = period_end;
+            }
+        }
+        start = start.max(range.start);
+        end = end.min(range.end);
+        if start >= end {
+            continue;
+        }
+
+        let fbtype = component
+            .entries
+            .iter()
+            .find(|entry| entry.name == ICalendarProperty::Busytype)
+            .and_then(|entry| entry.values.first())
+            .and_then(|value| value.as_text())
+            .map(|value| {
+                if value.eq_ignore_ascii_case("BUSY") {
+                    ICalendarFreeBusyType::Busy
+                } else if value.eq_ignore_ascii_case("BUSY-TENTATIVE") {
+                    ICalendarFreeBusyType::BusyTentative
+                } else {
+                    ICalendarFreeBusyType::BusyUnavailable
+                }
+            })
+            .unwrap_or(ICalendarFreeBusyType::BusyUnavailable);
+
+        let mut available = component
+            .component_ids
+            .iter()
+            .filter(|id| {
+                availability
+                    .components
+                    .get(**id as usize)
+                    .is_some_and(|comp| comp.component_type == ICalendarComponentType::Available)
+            })
+            .filter_map(|id| periods.get(&(*id as u16)))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        available.sort_unstable();
+
+        let mut busy = Vec::new();
+        let mut busy_start = start;
+        for (available_start, available_end) in available {
+            if available_start >= end {
+                break;
+            } else if available_end <= busy_start {
+                continue;
+            }
+            if available_start > busy_start {
+                busy.push((busy_start, available_start));
+            }
+            busy_start = available_end;
+        }
+        if busy_start < end {
+            busy.push((busy_start, end));
+        }
+
+        if !busy.is_empty() {
+            fb_entries.entry(fbtype).or_default().extend(busy);
+        }
+    }
+}
+
+fn calendar_availability(calendar: &ArchivedCalendar) -> trc::Result<Option<ICalendar>> {
+    calendar
+        .availability
+        .as_ref()
+        .map(|ical| rkyv_deserialize(ical).caused_by(trc::location!()))
+        .transpose()
+}
+
 fn merge_intervals(mut intervals: Vec<(i64, i64)>) -> Vec<ICalendarValue> {
     if intervals.len() > 1 {
         intervals.sort_by(|a, b| a.0.cmp(&b.0));
@@ -314,10 +579,7 @@ pub(crate) fn freebusy_in_range(
     range: &TimeRange,
     default_tz: Tz,
 ) -> impl Iterator<Item = ICalendarValue> {
-    let tz = entry
-        .tz_id()
-        .and_then(|tz_id| Tz::from_str(tz_id).ok())
-        .unwrap_or(default_tz);
+    let tz = entry.tz_id().and_then(parse_tz).unwrap_or(default_tz);
 
     entry.values.iter().filter_map(move |value| {
         if let ArchivedICalendarValue::Period(period) = &value {
@@ -341,10 +603,7 @@ fn freebusy_in_range_utc(
     range: &TimeRange,
     default_tz: Tz,
 ) -> impl Iterator<Item = (i64, i64)> {
-    let tz = entry
-        .tz_id()
-        .and_then(|tz_id| Tz::from_str(tz_id).ok())
-        .unwrap_or(default_tz);
+    let tz = entry.tz_id().and_then(parse_tz).unwrap_or(default_tz);
 
     entry.values.iter().filter_map(move |value| {
         if let ArchivedICalendarValue::Period(period) = &value {
@@ -362,3 +621,9 @@ fn freebusy_in_range_utc(
         }
     })
 }
+
+fn parse_utc_timestamp(value: &str) -> Option<i64> {
+    let mut dt = PartialDateTime::default();
+    dt.parse_timestamp(&mut value.as_bytes().iter().peekable(), true);
+    dt.to_timestamp()
+}
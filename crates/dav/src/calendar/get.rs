@@ -4,21 +4,37 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use calcard::{common::timezone::Tz, icalendar::ICalendar};
 use common::{Server, auth::AccessToken};
-use dav_proto::{RequestHeaders, schema::property::Rfc1123DateTime};
-use groupware::{cache::GroupwareCache, calendar::CalendarEvent};
+use dav_proto::{
+    RequestHeaders,
+    schema::property::{CalendarData, CalendarDataType, Rfc1123DateTime},
+};
+use directory::Permission;
+use groupware::{
+    cache::GroupwareCache,
+    calendar::{CalendarEvent, timezone::strip_known_timezones},
+};
 use http_proto::HttpResponse;
 use hyper::StatusCode;
 use jmap_proto::types::{
     acl::Acl,
     collection::{Collection, SyncCollection},
 };
+use std::fmt::Write;
+use store::write::serialize::rkyv_deserialize;
 use trc::AddContext;
+use utils::url_params::UrlParams;
 
 use crate::{
     DavError, DavMethod,
+    calendar::{
+        alarm::CalendarDefaultAlarms, compat::export_outlook_compat, format::write_calendar_data,
+        freebusy::CalendarFreebusyRequestHandler, query::CalendarQueryHandler, split_resource,
+    },
     common::{
         ETag,
+        acl::ResourceAcl,
         lock::{LockRequestHandler, ResourceState},
         uri::DavUriResource,
     },
@@ -29,6 +45,7 @@ pub(crate) trait CalendarGetRequestHandler: Sync + Send {
         &self,
         access_token: &AccessToken,
         headers: &RequestHeaders<'_>,
+        query: Option<&str>,
         is_head: bool,
     ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
 }
@@ -38,6 +55,7 @@ impl CalendarGetRequestHandler for Server {
         &self,
         access_token: &AccessToken,
         headers: &RequestHeaders<'_>,
+        query: Option<&str>,
         is_head: bool,
     ) -> crate::Result<HttpResponse> {
         // Validate URI
@@ -46,16 +64,19 @@ impl CalendarGetRequestHandler for Server {
             .await?
             .into_owned_uri()?;
         let account_id = resource_.account_id;
+        let Some(resource_name) = resource_.resource else {
+            // Calendar homes return the free/busy time aggregated across calendars
+            access_token.assert_has_permission(Permission::DavCalFreeBusyQuery)?;
+            return self
+                .handle_calendar_freebusy_get_request(access_token, account_id, query, is_head)
+                .await;
+        };
         let resources = self
             .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
             .await
             .caused_by(trc::location!())?;
         let resource = resources
-            .by_path(
-                resource_
-                    .resource
-                    .ok_or(DavError::Code(StatusCode::METHOD_NOT_ALLOWED))?,
-            )
+            .by_path(resource_name)
             .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
         if resource.is_container() {
             return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
@@ -69,7 +90,9 @@ impl CalendarGetRequestHandler for Server {
                 Acl::ReadItems,
             )
         {
-            return Err(DavError::Code(StatusCode::FORBIDDEN));
+            return Err(
+                resources.need_privileges(Some(resource.parent_id().unwrap()), Acl::ReadItems)
+            );
         }
 
         // Fetch event
@@ -87,7 +110,16 @@ impl CalendarGetRequestHandler for Server {
             .caused_by(trc::location!())?;
 
         // Validate headers
-        let etag = event_.etag();
+        let split = split_resource(
+            event,
+            resource.parent_id().unwrap(),
+            resource_name
+                .rsplit_once('/')
+                .map_or(resource_name, |(_, name)| name),
+        )?;
+        let etag = split
+            .as_ref()
+            .map_or_else(|| event_.etag(), |(_, etag)| etag.clone());
         self.validate_headers(
             access_token,
             headers,
@@ -96,7 +128,7 @@ impl CalendarGetRequestHandler for Server {
                 collection: Collection::CalendarEvent,
                 document_id: resource.document_id().into(),
                 etag: etag.clone().into(),
-                path: resource_.resource.unwrap(),
+                path: resource_name,
                 ..Default::default()
             }],
             Default::default(),
@@ -104,12 +136,93 @@ impl CalendarGetRequestHandler for Server {
         )
         .await?;
 
+        // Managed attachments are served from the event URL (RFC 8607)
+        if let Some(managed_id) = UrlParams::new(query).get("managed-id") {
+            let attachment = event
+                .attachments
+                .iter()
+                .find(|attachment| attachment.managed_id.as_str() == managed_id)
+                .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+            let mut response = HttpResponse::new(StatusCode::OK)
+                .with_content_type(
+                    attachment
+                        .file
+                        .media_type
+                        .as_ref()
+                        .map_or("application/octet-stream", |v| v.as_str()),
+                )
+                .with_last_modified(Rfc1123DateTime::new(i64::from(event.modified)).to_string());
+            if let Some(file_name) = attachment.file_name.as_ref() {
+                response = response.with_header(
+                    "Content-Disposition",
+                    format!(
+                        "attachment; filename=\"{}\"",
+                        file_name.as_str().replace('"', "")
+                    ),
+                );
+            }
+
+            return if !is_head {
+                Ok(response.with_binary_body(
+                    self.blob_store()
+                        .get_blob(attachment.file.blob_hash.0.as_ref(), 0..usize::MAX)
+                        .await
+                        .caused_by(trc::location!())?
+                        .ok_or(DavError::Code(StatusCode::NOT_FOUND))?,
+                ))
+            } else {
+                Ok(response.with_content_length(attachment.file.size.to_native() as usize))
+            };
+        }
+
+        let data_type = headers.calendar_data_type.unwrap_or_default();
         let response = HttpResponse::new(StatusCode::OK)
-            .with_content_type("text/calendar; charset=utf-8")
+            .with_content_type(format!("{}; charset=utf-8", data_type.content_type()))
             .with_etag(etag)
             .with_last_modified(Rfc1123DateTime::new(i64::from(event.modified)).to_string());
 
-        let ical = event.data.event.to_string();
+        let mut ical = String::with_capacity(event.size.to_native() as usize);
+        // Outlook/Exchange properties are added to the parsed event
+        let event_ical = match split {
+            Some((split, _)) => Some(split),
+            None if self.core.groupware.outlook_compat_export => Some(
+                rkyv_deserialize::<_, ICalendar>(&event.data.event).caused_by(trc::location!())?,
+            ),
+            None => None,
+        };
+        if let Some(mut event_ical) = event_ical {
+            if headers.no_timezones {
+                strip_known_timezones(&mut event_ical);
+            }
+            if self.core.groupware.outlook_compat_export {
+                export_outlook_compat(&mut event_ical);
+            }
+            let _ = write!(&mut ical, "{event_ical}");
+        } else if headers.no_timezones {
+            CalendarQueryHandler::new(vec![], Tz::UTC)
+                .with_no_timezones(true)
+                .serialize_ical(event, &CalendarData::default(), &mut ical);
+        } else {
+            let _ = write!(&mut ical, "{}", event.data.event);
+        }
+        if headers.default_alarms {
+            if let Some(injected) = self
+                .fetch_default_alarms(account_id, resource.parent_id().unwrap())
+                .await
+                .caused_by(trc::location!())?
+                .inject(&ical)
+            {
+                ical = injected;
+            }
+        }
+        if data_type != CalendarDataType::ICalendar {
+            let mut converted = String::with_capacity(ical.len() * 2);
+            if data_type == CalendarDataType::XCal {
+                converted.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+            }
+            write_calendar_data(&ical, data_type, &mut converted);
+            ical = converted;
+        }
 
         if !is_head {
             Ok(response.with_binary_body(ical))
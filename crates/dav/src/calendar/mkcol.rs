@@ -24,7 +24,7 @@ use crate::{
     common::{
         ExtractETag,
         lock::{LockRequestHandler, ResourceState},
-        uri::DavUriResource,
+        uri::{DavUriResource, validate_resource_name},
     },
 };
 
@@ -57,16 +57,15 @@ impl CalendarMkColRequestHandler for Server {
             .ok_or(DavError::Code(StatusCode::FORBIDDEN))?;
         if !access_token.is_member(account_id) {
             return Err(DavError::Code(StatusCode::FORBIDDEN));
-        } else if name.contains('/')
-            || self
-                .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
-                .await
-                .caused_by(trc::location!())?
-                .by_path(name)
-                .is_some()
-        {
+        }
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?;
+        if name.contains('/') || resources.by_path(name).is_some() {
             return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
         }
+        validate_resource_name(name)?;
 
         // Validate headers
         self.validate_headers(
@@ -109,8 +108,9 @@ impl CalendarMkColRequestHandler for Server {
                 &mut prop_stat,
             ) {
                 return Ok(HttpResponse::new(StatusCode::FORBIDDEN).with_xml_body(
-                    MkColResponse::new(prop_stat.build())
+                    MkColResponse::new(prop_stat.with_failed_dependencies().build())
                         .with_namespace(Namespace::CalDav)
+                        .with_namespace(Namespace::AppleIcal)
                         .with_mkcalendar(is_mkcalendar)
                         .to_string(),
                 ));
@@ -133,11 +133,20 @@ impl CalendarMkColRequestHandler for Server {
         let etag = batch.etag();
         self.commit_batch(batch).await.caused_by(trc::location!())?;
 
+        trc::event!(
+            Groupware(trc::GroupwareEvent::CalendarCreated),
+            AccountId = account_id,
+            Collection = Collection::Calendar,
+            DocumentId = document_id,
+            Url = resources.format_collection(name),
+        );
+
         if let Some(prop_stat) = return_prop_stat {
             Ok(HttpResponse::new(StatusCode::CREATED)
                 .with_xml_body(
                     MkColResponse::new(prop_stat.build())
                         .with_namespace(Namespace::CalDav)
+                        .with_namespace(Namespace::AppleIcal)
                         .with_mkcalendar(is_mkcalendar)
                         .to_string(),
                 )
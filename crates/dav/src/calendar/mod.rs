@@ -4,28 +4,38 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+pub mod alarm;
+pub mod attachment;
+pub mod compat;
 pub mod copy_move;
 pub mod delete;
+pub mod format;
 pub mod freebusy;
 pub mod get;
 pub mod mkcol;
 pub mod proppatch;
 pub mod query;
+pub mod sharing;
 pub mod update;
 
 use crate::{DavError, DavErrorCondition};
+use calcard::icalendar::ICalendar;
 use common::IDX_UID;
 use common::{DavResources, Server};
 use dav_proto::schema::{
-    property::{CalDavProperty, CalendarData, DavProperty, WebDavProperty},
+    property::{CalDavProperty, CalendarData, CalendarDataType, DavProperty, WebDavProperty},
     response::CalCondition,
 };
+use groupware::calendar::{
+    ArchivedCalendarEvent,
+    split::{has_override_resources, split_override_resource},
+};
 use hyper::StatusCode;
 use jmap_proto::types::collection::Collection;
-use store::query::Filter;
+use store::{query::Filter, write::serialize::rkyv_deserialize, xxhash_rust::xxh3::xxh3_64};
 use trc::AddContext;
 
-pub(crate) static CALENDAR_CONTAINER_PROPS: [DavProperty; 31] = [
+pub(crate) static CALENDAR_CONTAINER_PROPS: [DavProperty; 37] = [
     DavProperty::WebDav(WebDavProperty::CreationDate),
     DavProperty::WebDav(WebDavProperty::DisplayName),
     DavProperty::WebDav(WebDavProperty::GetETag),
@@ -57,6 +67,12 @@ pub(crate) static CALENDAR_CONTAINER_PROPS: [DavProperty; 31] = [
     DavProperty::CalDav(CalDavProperty::MaxAttendeesPerInstance),
     DavProperty::CalDav(CalDavProperty::TimezoneServiceSet),
     DavProperty::CalDav(CalDavProperty::TimezoneId),
+    DavProperty::CalDav(CalDavProperty::ScheduleCalendarTransp),
+    DavProperty::CalDav(CalDavProperty::CalendarAvailability),
+    DavProperty::CalDav(CalDavProperty::DefaultAlarmVEventDate),
+    DavProperty::CalDav(CalDavProperty::DefaultAlarmVEventDateTime),
+    DavProperty::CalDav(CalDavProperty::CalendarColor),
+    DavProperty::CalDav(CalDavProperty::CalendarOrder),
 ];
 
 pub(crate) static CALENDAR_ITEM_PROPS: [DavProperty; 20] = [
@@ -81,9 +97,11 @@ pub(crate) static CALENDAR_ITEM_PROPS: [DavProperty; 20] = [
     DavProperty::WebDav(WebDavProperty::GetContentType),
     DavProperty::CalDav(CalDavProperty::CalendarData(CalendarData {
         properties: vec![],
+        all_components: vec![],
         expand: None,
         limit_recurrence: None,
         limit_freebusy: None,
+        data_type: CalendarDataType::ICalendar,
     })),
 ];
 
@@ -93,6 +111,7 @@ pub(crate) async fn assert_is_unique_uid(
     account_id: u32,
     calendar_id: u32,
     uid: Option<&str>,
+    replaced_id: Option<u32>,
 ) -> crate::Result<()> {
     if let Some(uid) = uid {
         let hits = server
@@ -107,7 +126,10 @@ pub(crate) async fn assert_is_unique_uid(
 
         if !hits.results.is_empty() {
             for path in resources.children(calendar_id) {
-                if hits.results.contains(path.document_id()) {
+                // The resource being overwritten does not conflict
+                if hits.results.contains(path.document_id())
+                    && replaced_id != Some(path.document_id())
+                {
                     return Err(DavError::Condition(DavErrorCondition::new(
                         StatusCode::PRECONDITION_FAILED,
                         CalCondition::NoUidConflict(resources.format_resource(path).into()),
@@ -119,3 +141,56 @@ pub(crate) async fn assert_is_unique_uid(
 
     Ok(())
 }
+
+pub(crate) async fn uid_document_id(
+    server: &Server,
+    resources: &DavResources,
+    account_id: u32,
+    calendar_id: u32,
+    uid: &str,
+) -> crate::Result<Option<u32>> {
+    let hits = server
+        .store()
+        .filter(
+            account_id,
+            Collection::CalendarEvent,
+            vec![Filter::eq(IDX_UID, uid.as_bytes().to_vec())],
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+    Ok(resources
+        .children(calendar_id)
+        .map(|path| path.document_id())
+        .find(|document_id| hits.results.contains(*document_id)))
+}
+
+// Events holding recurrence overrides uploaded as separate resources are
+// served split back into the original resources, each with its own ETag
+pub(crate) fn split_resource(
+    event: &ArchivedCalendarEvent,
+    calendar_id: u32,
+    name: &str,
+) -> trc::Result<Option<(ICalendar, String)>> {
+    if event
+        .names
+        .iter()
+        .filter(|name| name.parent_id == calendar_id)
+        .count()
+        > 1
+    {
+        let ical =
+            rkyv_deserialize::<_, ICalendar>(&event.data.event).caused_by(trc::location!())?;
+        if has_override_resources(&ical) {
+            let split = split_override_resource(&ical, name);
+            let etag = split_etag(&split);
+            return Ok(Some((split, etag)));
+        }
+    }
+
+    Ok(None)
+}
+
+pub(crate) fn split_etag(ical: &ICalendar) -> String {
+    format!("\"{}\"", xxh3_64(ical.to_string().as_bytes()))
+}
@@ -4,30 +4,35 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::str::FromStr;
-
 use crate::{
     DavError, DavMethod, PropStatBuilder,
+    calendar::alarm::parse_default_alarm,
     common::{
         ETag, ExtractETag,
+        acl::ResourceAcl,
         lock::{LockRequestHandler, ResourceState},
         uri::DavUriResource,
     },
 };
-use calcard::common::timezone::Tz;
+use calcard::icalendar::{ICalendar, ICalendarComponentType};
 use common::{Server, auth::AccessToken};
 use dav_proto::{
     RequestHeaders, Return,
     schema::{
         Namespace,
-        property::{CalDavProperty, DavProperty, DavValue, ResourceType, WebDavProperty},
+        property::{
+            CalDavProperty, DavProperty, DavValue, ResourceType, ScheduleCalendarTransp,
+            WebDavProperty,
+        },
         request::{DavPropertyValue, PropertyUpdate},
         response::{BaseCondition, CalCondition, MultiStatus, Response},
     },
 };
 use groupware::{
     cache::GroupwareCache,
-    calendar::{Calendar, CalendarEvent, Timezone},
+    calendar::{
+        CALENDAR_TRANSPARENT, Calendar, CalendarEvent, Timezone, component_flag, timezone::parse_tz,
+    },
 };
 use http_proto::HttpResponse;
 use hyper::StatusCode;
@@ -106,7 +111,7 @@ impl CalendarPropPatchRequestHandler for Server {
             };
 
             if !resources.has_access_to_container(access_token, document_id, acl) {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(resources.need_privileges(Some(document_id), acl));
             }
         }
 
@@ -225,13 +230,19 @@ impl CalendarPropPatchRequestHandler for Server {
         }
 
         if headers.ret != Return::Minimal || !is_success {
-            Ok(HttpResponse::new(StatusCode::MULTI_STATUS)
+            let response = HttpResponse::new(StatusCode::MULTI_STATUS)
                 .with_xml_body(
                     MultiStatus::new(vec![Response::new_propstat(uri, items.build())])
                         .with_namespace(Namespace::CalDav)
+                        .with_namespace(Namespace::AppleIcal)
                         .to_string(),
                 )
-                .with_etag_opt(etag))
+                .with_etag_opt(etag);
+            if headers.ret == Return::Representation {
+                Ok(response.with_header("Preference-Applied", "return=representation"))
+            } else {
+                Ok(response)
+            }
         } else {
             Ok(HttpResponse::new(StatusCode::NO_CONTENT).with_etag_opt(etag))
         }
@@ -303,8 +314,32 @@ impl CalendarPropPatchRequestHandler for Server {
                         items.insert_ok(property.property);
                     }
                 }
+                (
+                    DavProperty::CalDav(CalDavProperty::CalendarAvailability),
+                    DavValue::ICalendar(ical),
+                ) => {
+                    if ical.size() > self.core.groupware.max_ical_size {
+                        items.insert_error_with_description(
+                            property.property,
+                            StatusCode::INSUFFICIENT_STORAGE,
+                            "Property value is too long",
+                        );
+                        has_errors = true;
+                    } else if !is_availability(&ical) {
+                        items.insert_precondition_failed_with_description(
+                            property.property,
+                            StatusCode::PRECONDITION_FAILED,
+                            CalCondition::ValidCalendarData,
+                            "Invalid calendar availability",
+                        );
+                        has_errors = true;
+                    } else {
+                        calendar.availability = Some(ical);
+                        items.insert_ok(property.property);
+                    }
+                }
                 (DavProperty::CalDav(CalDavProperty::TimezoneId), DavValue::String(tz_id)) => {
-                    if let Ok(tz) = Tz::from_str(&tz_id) {
+                    if let Some(tz) = parse_tz(&tz_id) {
                         calendar.preferences_mut(account_id).time_zone = Timezone::IANA(tz.as_id());
                         items.insert_ok(property.property);
                     } else {
@@ -317,6 +352,81 @@ impl CalendarPropPatchRequestHandler for Server {
                         has_errors = true;
                     }
                 }
+                (
+                    DavProperty::CalDav(CalDavProperty::ScheduleCalendarTransp),
+                    DavValue::ScheduleCalendarTransp(transp),
+                ) => {
+                    let preferences = calendar.preferences_mut(account_id);
+                    if transp == ScheduleCalendarTransp::Transparent {
+                        preferences.flags |= CALENDAR_TRANSPARENT;
+                    } else {
+                        preferences.flags &= !CALENDAR_TRANSPARENT;
+                    }
+                    items.insert_ok(property.property);
+                }
+                (DavProperty::CalDav(CalDavProperty::CalendarColor), DavValue::String(color)) => {
+                    if is_valid_color(&color) {
+                        calendar.preferences_mut(account_id).color = Some(color);
+                        items.insert_ok(property.property);
+                    } else {
+                        items.insert_error_with_description(
+                            property.property,
+                            StatusCode::CONFLICT,
+                            "Invalid calendar color",
+                        );
+                        has_errors = true;
+                    }
+                }
+                (DavProperty::CalDav(CalDavProperty::CalendarOrder), DavValue::Uint64(order)) => {
+                    if let Ok(order) = u32::try_from(order) {
+                        calendar.preferences_mut(account_id).sort_order = order;
+                        items.insert_ok(property.property);
+                    } else {
+                        items.insert_error_with_description(
+                            property.property,
+                            StatusCode::CONFLICT,
+                            "Invalid calendar order",
+                        );
+                        has_errors = true;
+                    }
+                }
+                (
+                    DavProperty::CalDav(
+                        cal_property @ (CalDavProperty::DefaultAlarmVEventDate
+                        | CalDavProperty::DefaultAlarmVEventDateTime),
+                    ),
+                    value @ (DavValue::String(_) | DavValue::Null),
+                ) => {
+                    let with_time =
+                        matches!(cal_property, CalDavProperty::DefaultAlarmVEventDateTime);
+                    let text = match &value {
+                        DavValue::String(text) => text.trim(),
+                        _ => "",
+                    };
+                    if text.is_empty() {
+                        // An empty value removes the default alarm
+                        calendar.set_default_alert(account_id, with_time, None);
+                        items.insert_ok(property.property);
+                    } else if text.len() > self.core.groupware.max_ical_size {
+                        items.insert_error_with_description(
+                            property.property,
+                            StatusCode::INSUFFICIENT_STORAGE,
+                            "Property value is too long",
+                        );
+                        has_errors = true;
+                    } else if let Some(alarm) = parse_default_alarm(text) {
+                        calendar.set_default_alert(account_id, with_time, Some(alarm));
+                        items.insert_ok(property.property);
+                    } else {
+                        items.insert_precondition_failed_with_description(
+                            property.property,
+                            StatusCode::PRECONDITION_FAILED,
+                            CalCondition::ValidCalendarData,
+                            "Invalid default alarm",
+                        );
+                        has_errors = true;
+                    }
+                }
                 (DavProperty::WebDav(WebDavProperty::CreationDate), DavValue::Timestamp(dt)) => {
                     calendar.created = dt;
                     items.insert_ok(property.property);
@@ -362,6 +472,17 @@ impl CalendarPropPatchRequestHandler for Server {
                         has_errors = true;
                     }
                 }
+                (
+                    DavProperty::CalDav(CalDavProperty::SupportedCalendarComponentSet),
+                    DavValue::Components(components),
+                ) => {
+                    // An empty set lifts any restriction on the component types
+                    calendar.supported_components = components
+                        .0
+                        .iter()
+                        .fold(0, |acc, comp| acc | component_flag(&comp.0));
+                    items.insert_ok(property.property);
+                }
                 (_, DavValue::Null | DavValue::Components(_)) => {
                     items.insert_ok(property.property);
                 }
@@ -489,6 +610,34 @@ fn remove_calendar_properties(
                 calendar.preferences_mut(account_id).time_zone = Timezone::Default;
                 items.insert_with_status(property, StatusCode::NO_CONTENT);
             }
+            DavProperty::CalDav(CalDavProperty::ScheduleCalendarTransp) => {
+                calendar.preferences_mut(account_id).flags &= !CALENDAR_TRANSPARENT;
+                items.insert_with_status(property, StatusCode::NO_CONTENT);
+            }
+            DavProperty::CalDav(CalDavProperty::CalendarAvailability) => {
+                calendar.availability = None;
+                items.insert_with_status(property, StatusCode::NO_CONTENT);
+            }
+            DavProperty::CalDav(CalDavProperty::DefaultAlarmVEventDate) => {
+                calendar.set_default_alert(account_id, false, None);
+                items.insert_with_status(property, StatusCode::NO_CONTENT);
+            }
+            DavProperty::CalDav(CalDavProperty::DefaultAlarmVEventDateTime) => {
+                calendar.set_default_alert(account_id, true, None);
+                items.insert_with_status(property, StatusCode::NO_CONTENT);
+            }
+            DavProperty::CalDav(CalDavProperty::SupportedCalendarComponentSet) => {
+                calendar.supported_components = 0;
+                items.insert_with_status(property, StatusCode::NO_CONTENT);
+            }
+            DavProperty::CalDav(CalDavProperty::CalendarColor) => {
+                calendar.preferences_mut(account_id).color = None;
+                items.insert_with_status(property, StatusCode::NO_CONTENT);
+            }
+            DavProperty::CalDav(CalDavProperty::CalendarOrder) => {
+                calendar.preferences_mut(account_id).sort_order = 0;
+                items.insert_with_status(property, StatusCode::NO_CONTENT);
+            }
             DavProperty::DeadProperty(dead) => {
                 calendar.dead_properties.remove_element(dead);
                 items.insert_with_status(property, StatusCode::NO_CONTENT);
@@ -503,3 +652,26 @@ fn remove_calendar_properties(
         }
     }
 }
+
+// Colors are #RRGGBB or #RRGGBBAA hex triplets, as sent by Apple clients
+fn is_valid_color(color: &str) -> bool {
+    color.strip_prefix('#').is_some_and(|hex| {
+        matches!(hex.len(), 6 | 8) && hex.bytes().all(|ch| ch.is_ascii_hexdigit())
+    })
+}
+
+fn is_availability(ical: &ICalendar) -> bool {
+    let mut has_availability = false;
+    for component in &ical.components {
+        match component.component_type {
+            ICalendarComponentType::VAvailability => has_availability = true,
+            ICalendarComponentType::VCalendar
+            | ICalendarComponentType::Available
+            | ICalendarComponentType::VTimezone
+            | ICalendarComponentType::Standard
+            | ICalendarComponentType::Daylight => {}
+            _ => return false,
+        }
+    }
+    has_availability
+}
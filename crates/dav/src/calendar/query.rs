@@ -29,7 +29,10 @@ use dav_proto::{
         request::{CalendarQuery, Filter, FilterOp, PropFind, Timezone},
     },
 };
-use groupware::{cache::GroupwareCache, calendar::ArchivedCalendarEvent};
+use groupware::{
+    cache::GroupwareCache,
+    calendar::{ArchivedCalendarEvent, dates::time_buckets_in_range, timezone::parse_tz},
+};
 use http_proto::HttpResponse;
 use hyper::StatusCode;
 use jmap_proto::types::{acl::Acl, collection::SyncCollection};
@@ -134,6 +137,8 @@ pub(crate) fn is_resource_in_time_range(resource: &DavResource, filter: &TimeRan
 
         ((filter.start < end) || (filter.start <= start))
             && (filter.end > start || filter.end >= end)
+            && (resource.event_time_buckets().is_empty()
+                || time_buckets_in_range(resource.event_time_buckets(), filter))
     } else {
         // If the resource does not have a time range, it is not in the range
         false
@@ -208,7 +213,7 @@ fn extract_data_range(propfind: &PropFind, filter_range: Option<TimeRange>) -> O
 
 pub fn try_parse_tz(tz: &Timezone) -> Option<Tz> {
     match tz {
-        Timezone::Name(value) | Timezone::Id(value) => Tz::from_str(value).ok(),
+        Timezone::Name(value) | Timezone::Id(value) => parse_tz(value),
         Timezone::None => None,
     }
 }
@@ -216,34 +221,24 @@ pub fn try_parse_tz(tz: &Timezone) -> Option<Tz> {
 pub(crate) struct CalendarQueryHandler {
     default_tz: Tz,
     expanded_times: Vec<CalendarEvent<i64, i64>>,
+    no_timezones: bool,
 }
 
 impl CalendarQueryHandler {
-    pub fn new(
-        event: &ArchivedCalendarEvent,
-        max_time_range: Option<TimeRange>,
-        default_tz: Tz,
-    ) -> Self {
+    pub fn new(expanded_times: Vec<CalendarEvent<i64, i64>>, default_tz: Tz) -> Self {
         Self {
             default_tz,
-            expanded_times: max_time_range
-                .map(|max_time_range| {
-                    event
-                        .data
-                        .expand(default_tz, max_time_range)
-                        .unwrap_or_else(|| {
-                            trc::event!(
-                                Calendar(trc::CalendarEvent::RuleExpansionError),
-                                Reason = "chrono error",
-                                Details = event.data.event.to_string(),
-                            );
-                            vec![]
-                        })
-                })
-                .unwrap_or_default(),
+            expanded_times,
+            no_timezones: false,
         }
     }
 
+    // Omit the VTIMEZONE components the client can resolve by TZID (RFC 7809)
+    pub fn with_no_timezones(mut self, no_timezones: bool) -> Self {
+        self.no_timezones = no_timezones;
+        self
+    }
+
     pub fn filter(&mut self, event: &ArchivedCalendarEvent, filters: &CalendarFilter) -> bool {
         let ical = &event.data.event;
         let mut is_all = true;
@@ -287,7 +282,7 @@ impl CalendarQueryHandler {
                                     {
                                         let tz = entry
                                             .tz_id()
-                                            .and_then(|tz_id| Tz::from_str(tz_id).ok())
+                                            .and_then(parse_tz)
                                             .unwrap_or(self.default_tz);
 
                                         if let Some(date) = date
@@ -415,12 +410,19 @@ impl CalendarQueryHandler {
         is_all || matches_one
     }
 
-    pub fn serialize_ical(&mut self, event: &ArchivedCalendarEvent, data: &CalendarData) -> String {
-        let mut out = String::with_capacity(event.size.to_native() as usize);
+    pub fn serialize_ical(
+        &mut self,
+        event: &ArchivedCalendarEvent,
+        data: &CalendarData,
+        out: &mut impl Write,
+    ) {
         let _v = [0.into()];
         let mut component_iter: Iter<'_, rkyv::rend::u16_le> = _v.iter();
-        let mut component_stack: Vec<(&ArchivedICalendarComponent, Iter<'_, rkyv::rend::u16_le>)> =
-            Vec::with_capacity(4);
+        let mut component_stack: Vec<(
+            &ArchivedICalendarComponent,
+            Iter<'_, rkyv::rend::u16_le>,
+            bool,
+        )> = Vec::with_capacity(4);
 
         if data.expand.is_some() {
             self.expanded_times
@@ -437,6 +439,20 @@ impl CalendarQueryHandler {
                     .get(component_id as usize)
                     .unwrap();
 
+                // Skip timezones available in the embedded database
+                if self.no_timezones
+                    && component.component_type == ICalendarComponentType::VTimezone
+                    && component
+                        .entries
+                        .iter()
+                        .find(|entry| matches!(entry.name, ArchivedICalendarProperty::Tzid))
+                        .and_then(|entry| entry.values.first())
+                        .and_then(|value| value.as_text())
+                        .is_some_and(|tz_id| Tz::from_str(tz_id).is_ok())
+                {
+                    continue;
+                }
+
                 // Limit recurrence override
                 if let Some(limit_recurrence) = &data.limit_recurrence {
                     if component.is_recurrence_override()
@@ -465,25 +481,51 @@ impl CalendarQueryHandler {
                     }
                 }
 
+                // Filter components, subcomponents that were not selected are only
+                // returned when their parent was requested in full
+                let (has_all_entries, has_all_components) = if data.properties.is_empty() {
+                    (true, true)
+                } else if component.component_type == ICalendarComponentType::VCalendar {
+                    (
+                        true,
+                        data.all_components
+                            .contains(&ICalendarComponentType::VCalendar),
+                    )
+                } else if data.properties.iter().any(|prop| {
+                    prop.component
+                        .as_ref()
+                        .is_none_or(|comp| comp == &component.component_type)
+                }) {
+                    (
+                        false,
+                        data.all_components
+                            .iter()
+                            .any(|comp| comp == &component.component_type),
+                    )
+                } else if component_stack
+                    .last()
+                    .is_some_and(|(_, _, has_all_components)| *has_all_components)
+                {
+                    (true, true)
+                } else {
+                    continue;
+                };
+
                 // Filter entries
                 let mut entries = component
                     .entries
                     .iter()
                     .filter_map(|entry| {
-                        if data.properties.is_empty()
-                            || component.component_type == ICalendarComponentType::VCalendar
-                        {
+                        if has_all_entries {
                             Some((entry, true))
                         } else {
                             data.properties
                                 .iter()
                                 .find(|prop| {
-                                    prop.component.as_ref().is_none_or(|comp| {
-                                        comp == &component.component_type
-                                            || component_stack.iter().any(|(parent_comp, _)| {
-                                                comp == &parent_comp.component_type
-                                            })
-                                    }) && prop.name.as_ref().is_none_or(|name| name == &entry.name)
+                                    prop.component
+                                        .as_ref()
+                                        .is_none_or(|comp| comp == &component.component_type)
+                                        && prop.name.as_ref().is_none_or(|name| name == &entry.name)
                                 })
                                 .map(|prop| (entry, !prop.no_value))
                         }
@@ -525,7 +567,7 @@ impl CalendarQueryHandler {
                             && (!is_recurrent_or_override
                                 || expand.is_in_range(is_todo, event.start, event.end))
                         {
-                            let _ = write!(&mut out, "BEGIN:{component_name}\r\n");
+                            let _ = write!(out, "BEGIN:{component_name}\r\n");
 
                             // Write DTSTART, DTEND and RECURRENCE-ID
                             let mut entry = ICalendarEntry {
@@ -535,34 +577,34 @@ impl CalendarQueryHandler {
                                     PartialDateTime::from_utc_timestamp(event.start),
                                 ))],
                             };
-                            let _ = entry.write_to(&mut out);
+                            let _ = entry.write_to(out);
                             if is_recurrent_or_override {
                                 entry.name = ICalendarProperty::RecurrenceId;
-                                let _ = entry.write_to(&mut out);
+                                let _ = entry.write_to(out);
                             }
                             if !has_duration {
                                 entry.name = ICalendarProperty::Dtend;
                                 entry.values = vec![ICalendarValue::PartialDateTime(Box::new(
                                     PartialDateTime::from_utc_timestamp(event.end),
                                 ))];
-                                let _ = entry.write_to(&mut out);
+                                let _ = entry.write_to(out);
                             }
 
                             // Write other component entries
                             for (entry, with_value) in &entries {
-                                let _ = entry.write_to(&mut out, *with_value);
+                                let _ = entry.write_to(out, *with_value);
                             }
-                            let _ = write!(&mut out, "END:{component_name}\r\n");
+                            let _ = write!(out, "END:{component_name}\r\n");
                         }
                     }
                 } else if entries.peek().is_some() {
-                    let _ = write!(&mut out, "BEGIN:{component_name}\r\n");
+                    let _ = write!(out, "BEGIN:{component_name}\r\n");
 
                     if data.limit_freebusy.is_none()
                         || component.component_type != ICalendarComponentType::VFreebusy
                     {
                         for (entry, with_value) in entries {
-                            let _ = entry.write_to(&mut out, with_value);
+                            let _ = entry.write_to(out, with_value);
                         }
                     } else {
                         // Filter freebusy
@@ -581,34 +623,28 @@ impl CalendarQueryHandler {
                                             .unwrap_or_default(),
                                         values: fb_in_range.collect(),
                                     }
-                                    .write_to(&mut out);
+                                    .write_to(out);
                                 }
                             } else {
-                                let _ = entry.write_to(&mut out, with_value);
+                                let _ = entry.write_to(out, with_value);
                             }
                         }
                     }
 
                     if !component.component_ids.is_empty() {
-                        component_stack.push((component, component_iter));
+                        component_stack.push((component, component_iter, has_all_components));
                         component_iter = component.component_ids.iter();
                     } else if component.component_ids.is_empty() {
-                        let _ = write!(&mut out, "END:{component_name}\r\n");
+                        let _ = write!(out, "END:{component_name}\r\n");
                     }
                 }
-            } else if let Some((component, iter)) = component_stack.pop() {
-                let _ = write!(&mut out, "END:{}\r\n", component.component_type.as_str());
+            } else if let Some((component, iter, _)) = component_stack.pop() {
+                let _ = write!(out, "END:{}\r\n", component.component_type.as_str());
                 component_iter = iter;
             } else {
                 break;
             }
         }
-
-        out
-    }
-
-    pub fn into_expanded_times(self) -> Vec<CalendarEvent<i64, i64>> {
-        self.expanded_times
     }
 }
 
@@ -0,0 +1,722 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use common::{Server, auth::AccessToken, sharing::EffectiveAcl};
+use dav_proto::{
+    Depth, RequestHeaders, Return,
+    parser::DavParser,
+    schema::{
+        Namespace,
+        property::{
+            CalDavProperty, DavProperty, DavValue, InviteStatus as ShareeStatus, ResourceType,
+            Sharee, WebDavProperty,
+        },
+        request::{DavPropertyValue, InviteReply, PropFind, Share, ShareRequest},
+        response::{BaseCondition, InviteNotification, MultiStatus, Response, SharedAsResponse},
+    },
+};
+use directory::{Permission, QueryBy, Type};
+use groupware::{
+    cache::GroupwareCache,
+    calendar::{
+        ArchivedCalendar, ArchivedInviteStatus, CALENDAR_SUBSCRIBED, Calendar, CalendarInvite,
+        CalendarPreferences, InviteStatus,
+        sharing::{CalendarSharing, NOTIFICATION_COLLECTION_NAME, PendingInvite, invite_acls},
+    },
+};
+use http_proto::{HttpResponse, request::decode_path_element};
+use hyper::StatusCode;
+use jmap_proto::types::{
+    acl::Acl,
+    collection::{Collection, SyncCollection},
+    value::AclGrant,
+};
+use percent_encoding::NON_ALPHANUMERIC;
+use store::write::{BatchBuilder, now};
+use trc::AddContext;
+
+use crate::{
+    DavError, DavErrorCondition, DavMethod, DavResourceName,
+    card::directory::{build_propstat, empty_propstat},
+    common::{acl::ResourceAcl, uri::DavUriResource},
+    principal::{CurrentUserPrincipal, propfind::PrincipalPropFind},
+    request::DavRequestParser,
+};
+
+pub(crate) struct CalendarNotificationTarget {
+    pub uid: Option<String>,
+}
+
+pub(crate) trait CalendarSharingRequestHandler: Sync + Send {
+    fn handle_calendar_share_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        request: ShareRequest,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+
+    fn resolve_calendar_notifications(
+        &self,
+        access_token: &AccessToken,
+        uri: &str,
+    ) -> impl Future<Output = crate::Result<Option<CalendarNotificationTarget>>> + Send;
+
+    fn handle_calendar_notification_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        target: CalendarNotificationTarget,
+        method: DavMethod,
+        body: Vec<u8>,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+
+    fn calendar_sharees(
+        &self,
+        access_token: &AccessToken,
+        calendar: &ArchivedCalendar,
+    ) -> impl Future<Output = crate::Result<Vec<Sharee>>> + Send;
+}
+
+impl CalendarSharingRequestHandler for Server {
+    async fn handle_calendar_share_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        request: ShareRequest,
+    ) -> crate::Result<HttpResponse> {
+        match request {
+            ShareRequest::Share(share) => share_calendar(self, access_token, headers, share).await,
+            ShareRequest::InviteReply(reply) => {
+                reply_to_invite(self, access_token, headers, reply).await
+            }
+        }
+    }
+
+    async fn resolve_calendar_notifications(
+        &self,
+        access_token: &AccessToken,
+        uri: &str,
+    ) -> crate::Result<Option<CalendarNotificationTarget>> {
+        // Notifications are only exposed under the calendar home of the caller
+        let Ok(uri) = self.validate_uri(access_token, uri).await else {
+            return Ok(None);
+        };
+        let (Some(account_id), Some(resource)) = (uri.account_id, uri.resource) else {
+            return Ok(None);
+        };
+        if uri.collection != Collection::Calendar || account_id != access_token.primary_id() {
+            return Ok(None);
+        }
+        let resource = decode_path_element(resource);
+        let uid = if resource == NOTIFICATION_COLLECTION_NAME {
+            None
+        } else if let Some(item) = resource
+            .strip_prefix(NOTIFICATION_COLLECTION_NAME)
+            .and_then(|path| path.strip_prefix('/'))
+        {
+            Some(item.strip_suffix(".xml").unwrap_or(item).to_string())
+        } else {
+            return Ok(None);
+        };
+
+        // Regular calendars take precedence over the notification collection
+        if self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?
+            .by_path(NOTIFICATION_COLLECTION_NAME)
+            .is_some()
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(CalendarNotificationTarget { uid }))
+    }
+
+    async fn handle_calendar_notification_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        target: CalendarNotificationTarget,
+        method: DavMethod,
+        body: Vec<u8>,
+    ) -> crate::Result<HttpResponse> {
+        let base_href = format!(
+            "{}/{}/{}/",
+            DavResourceName::Cal.base_path(),
+            percent_encoding::utf8_percent_encode(&access_token.name, NON_ALPHANUMERIC),
+            NOTIFICATION_COLLECTION_NAME,
+        );
+
+        match method {
+            DavMethod::PROPFIND => {
+                access_token.assert_has_permission(Permission::DavCalPropFind)?;
+                let request = if !body.is_empty() {
+                    PropFind::parse(&mut self.request_tokenizer(&body))?
+                } else {
+                    PropFind::AllProp(vec![])
+                };
+                let is_minimal = headers.ret == Return::Minimal;
+                let invites = self
+                    .pending_invites(access_token)
+                    .await
+                    .caused_by(trc::location!())?;
+                let mut response = MultiStatus::new(Vec::with_capacity(invites.len() + 1));
+                response.set_namespace(Namespace::CalendarServer);
+
+                if let Some(uid) = &target.uid {
+                    let invite = invites
+                        .iter()
+                        .find(|invite| &invite.invite.uid == uid)
+                        .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+                    response.add_response(notification_propstat(
+                        format!("{base_href}{uid}.xml"),
+                        invite,
+                        &request,
+                        is_minimal,
+                    ));
+                } else {
+                    if !headers.depth_no_root {
+                        response.add_response(collection_propstat(
+                            access_token,
+                            base_href.clone(),
+                            &invites,
+                            &request,
+                            is_minimal,
+                        ));
+                    }
+                    if headers.depth != Depth::Zero {
+                        for invite in &invites {
+                            response.add_response(notification_propstat(
+                                format!("{base_href}{}.xml", invite.invite.uid),
+                                invite,
+                                &request,
+                                is_minimal,
+                            ));
+                        }
+                    }
+                }
+
+                Ok(HttpResponse::new(StatusCode::MULTI_STATUS).with_xml_body(response.to_string()))
+            }
+            DavMethod::GET | DavMethod::HEAD => {
+                access_token.assert_has_permission(Permission::DavCalGet)?;
+                let uid = target
+                    .uid
+                    .ok_or(DavError::Code(StatusCode::METHOD_NOT_ALLOWED))?;
+                let invite = self
+                    .pending_invites(access_token)
+                    .await
+                    .caused_by(trc::location!())?
+                    .into_iter()
+                    .find(|invite| invite.invite.uid == uid)
+                    .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+
+                let notification = invite_notification(self, access_token, &invite)
+                    .await?
+                    .to_string();
+                let response = HttpResponse::new(StatusCode::OK)
+                    .with_content_type("application/xml; charset=utf-8")
+                    .with_etag(notification_etag(&invite));
+
+                if matches!(method, DavMethod::GET) {
+                    Ok(response.with_binary_body(notification))
+                } else {
+                    Ok(response.with_content_length(notification.len()))
+                }
+            }
+            DavMethod::PUT
+            | DavMethod::POST
+            | DavMethod::PATCH
+            | DavMethod::DELETE
+            | DavMethod::MKCOL
+            | DavMethod::MKCALENDAR
+            | DavMethod::PROPPATCH
+            | DavMethod::COPY
+            | DavMethod::MOVE => Err(DavError::Code(StatusCode::FORBIDDEN)),
+            _ => Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED)),
+        }
+    }
+
+    async fn calendar_sharees(
+        &self,
+        access_token: &AccessToken,
+        calendar: &ArchivedCalendar,
+    ) -> crate::Result<Vec<Sharee>> {
+        let mut sharees = Vec::with_capacity(calendar.invites.len());
+        for invite in calendar.invites.iter() {
+            sharees.push(Sharee {
+                href: self
+                    .owner_href(access_token, invite.account_id.to_native())
+                    .await
+                    .caused_by(trc::location!())?,
+                common_name: invite.common_name.as_ref().map(|name| name.to_string()),
+                status: match invite.status {
+                    ArchivedInviteStatus::Pending => ShareeStatus::NoResponse,
+                    ArchivedInviteStatus::Accepted => ShareeStatus::Accepted,
+                    ArchivedInviteStatus::Declined => ShareeStatus::Declined,
+                },
+                read_write: invite.read_write,
+                summary: invite.summary.as_ref().map(|summary| summary.to_string()),
+            });
+        }
+        Ok(sharees)
+    }
+}
+
+async fn share_calendar(
+    server: &Server,
+    access_token: &AccessToken,
+    headers: &RequestHeaders<'_>,
+    share: Share,
+) -> crate::Result<HttpResponse> {
+    // Validate URI
+    let resource_ = server
+        .validate_uri(access_token, headers.uri)
+        .await?
+        .into_owned_uri()?;
+    let account_id = resource_.account_id;
+    let resources = server
+        .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+        .await
+        .caused_by(trc::location!())?;
+    let resource = resource_
+        .resource
+        .and_then(|name| resources.by_path(name))
+        .filter(|resource| resource.is_container())
+        .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+
+    // Scheduling collections cannot be shared
+    if resource.schedule_collection().is_some() {
+        return Err(DavError::Code(StatusCode::FORBIDDEN));
+    }
+    let document_id = resource.document_id();
+
+    // Fetch calendar
+    let calendar_ = server
+        .get_archive(account_id, Collection::Calendar, document_id)
+        .await
+        .caused_by(trc::location!())?
+        .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+    let calendar = calendar_
+        .to_unarchived::<Calendar>()
+        .caused_by(trc::location!())?;
+
+    // Validate ACL
+    if !access_token.is_member(account_id)
+        && !calendar
+            .inner
+            .acls
+            .effective_acl(access_token)
+            .contains(Acl::Administer)
+    {
+        return Err(resources.need_privileges(Some(document_id), Acl::Administer));
+    }
+
+    let mut new_calendar = calendar
+        .deserialize::<Calendar>()
+        .caused_by(trc::location!())?;
+    for set in share.set {
+        let sharee_id = resolve_sharee(server, access_token, &set.href)
+            .await?
+            .filter(|sharee_id| *sharee_id != account_id)
+            .ok_or_else(|| {
+                DavError::Condition(DavErrorCondition::new(
+                    StatusCode::FORBIDDEN,
+                    BaseCondition::AllowedPrincipal,
+                ))
+            })?;
+
+        // Previously declined invitations are sent again
+        if let Some(invite) = new_calendar
+            .invites
+            .iter_mut()
+            .find(|invite| invite.account_id == sharee_id)
+        {
+            invite.read_write = set.read_write;
+            invite.common_name = set.common_name;
+            invite.summary = set.summary;
+            if invite.status == InviteStatus::Declined {
+                invite.status = InviteStatus::Pending;
+            }
+        } else {
+            new_calendar.invites.push(CalendarInvite {
+                account_id: sharee_id,
+                uid: format!("{:x}", store::rand::random::<u64>()),
+                status: InviteStatus::Pending,
+                read_write: set.read_write,
+                common_name: set.common_name,
+                summary: set.summary,
+                created: now() as i64,
+            });
+        }
+
+        let grants = invite_acls(set.read_write);
+        if let Some(grant) = new_calendar
+            .acls
+            .iter_mut()
+            .find(|grant| grant.account_id == sharee_id)
+        {
+            grant.grants = grants;
+        } else {
+            new_calendar.acls.push(AclGrant {
+                account_id: sharee_id,
+                grants,
+            });
+        }
+    }
+    for href in share.remove {
+        if let Some(sharee_id) = resolve_sharee(server, access_token, &href).await? {
+            new_calendar
+                .invites
+                .retain(|invite| invite.account_id != sharee_id);
+            new_calendar
+                .acls
+                .retain(|grant| grant.account_id != sharee_id);
+            new_calendar
+                .preferences
+                .retain(|preferences| preferences.account_id != sharee_id);
+        }
+    }
+
+    // Refresh ACLs and update calendar
+    server
+        .refresh_archived_acls(&new_calendar.acls, &calendar.inner.acls)
+        .await;
+    let mut batch = BatchBuilder::new();
+    new_calendar
+        .update(access_token, calendar, account_id, document_id, &mut batch)
+        .caused_by(trc::location!())?;
+    server
+        .commit_batch(batch)
+        .await
+        .caused_by(trc::location!())?;
+
+    Ok(HttpResponse::new(StatusCode::OK))
+}
+
+async fn reply_to_invite(
+    server: &Server,
+    access_token: &AccessToken,
+    headers: &RequestHeaders<'_>,
+    reply: InviteReply,
+) -> crate::Result<HttpResponse> {
+    // Replies are posted to the calendar home of the invitee
+    let sharee_id = access_token.primary_id();
+    let home = server
+        .validate_uri(access_token, headers.uri)
+        .await?
+        .into_owned_uri()?;
+    if home.account_id != sharee_id || home.resource.is_some() {
+        return Err(DavError::Code(StatusCode::FORBIDDEN));
+    }
+
+    // Resolve the shared calendar
+    let host_url = reply
+        .host_url
+        .ok_or(DavError::Code(StatusCode::BAD_REQUEST))?;
+    let resource_ = server
+        .validate_uri(access_token, &host_url)
+        .await?
+        .into_owned_uri()?;
+    if resource_.collection != Collection::Calendar {
+        return Err(DavError::Code(StatusCode::BAD_REQUEST));
+    }
+    let account_id = resource_.account_id;
+    let resources = server
+        .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+        .await
+        .caused_by(trc::location!())?;
+    let resource = resource_
+        .resource
+        .and_then(|name| resources.by_path(name))
+        .filter(|resource| resource.is_container())
+        .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+    let document_id = resource.document_id();
+    let calendar_ = server
+        .get_archive(account_id, Collection::Calendar, document_id)
+        .await
+        .caused_by(trc::location!())?
+        .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+    let calendar = calendar_
+        .to_unarchived::<Calendar>()
+        .caused_by(trc::location!())?;
+    let mut new_calendar = calendar
+        .deserialize::<Calendar>()
+        .caused_by(trc::location!())?;
+    let owner_preferences = new_calendar.preferences(account_id).clone();
+
+    // Only the invitee can reply to an invitation
+    let invite = new_calendar
+        .invites
+        .iter_mut()
+        .find(|invite| {
+            invite.account_id == sharee_id
+                && reply
+                    .in_reply_to
+                    .as_ref()
+                    .is_none_or(|uid| uid == &invite.uid)
+        })
+        .ok_or(DavError::Code(StatusCode::FORBIDDEN))?;
+    let read_write = invite.read_write;
+
+    let response = if reply.accepted {
+        invite.status = InviteStatus::Accepted;
+        if !new_calendar
+            .acls
+            .iter()
+            .any(|grant| grant.account_id == sharee_id)
+        {
+            new_calendar.acls.push(AclGrant {
+                account_id: sharee_id,
+                grants: invite_acls(read_write),
+            });
+        }
+
+        // Subscribe the invitee to the calendar
+        if !new_calendar
+            .preferences
+            .iter()
+            .any(|preferences| preferences.account_id == sharee_id)
+        {
+            new_calendar.preferences.push(CalendarPreferences {
+                account_id: sharee_id,
+                flags: CALENDAR_SUBSCRIBED,
+                ..owner_preferences
+            });
+        }
+
+        HttpResponse::new(StatusCode::OK)
+            .with_xml_body(SharedAsResponse::new(resources.format_resource(resource)).to_string())
+    } else {
+        invite.status = InviteStatus::Declined;
+        new_calendar
+            .acls
+            .retain(|grant| grant.account_id != sharee_id);
+        new_calendar
+            .preferences
+            .retain(|preferences| preferences.account_id != sharee_id);
+
+        HttpResponse::new(StatusCode::NO_CONTENT)
+    };
+
+    // Refresh ACLs and update calendar
+    server
+        .refresh_archived_acls(&new_calendar.acls, &calendar.inner.acls)
+        .await;
+    let mut batch = BatchBuilder::new();
+    new_calendar
+        .update(access_token, calendar, account_id, document_id, &mut batch)
+        .caused_by(trc::location!())?;
+    server
+        .commit_batch(batch)
+        .await
+        .caused_by(trc::location!())?;
+
+    Ok(response)
+}
+
+async fn resolve_sharee(
+    server: &Server,
+    access_token: &AccessToken,
+    href: &str,
+) -> crate::Result<Option<u32>> {
+    let account_id = if let Some(address) = href
+        .strip_prefix("mailto:")
+        .or_else(|| href.strip_prefix("MAILTO:"))
+    {
+        server
+            .core
+            .storage
+            .directory
+            .email_to_id(address.trim())
+            .await
+            .caused_by(trc::location!())?
+    } else {
+        server
+            .validate_uri(access_token, href)
+            .await
+            .ok()
+            .and_then(|uri| uri.account_id)
+    };
+
+    // Calendars can only be shared with individuals and groups
+    if let Some(account_id) = account_id {
+        if let Some(principal) = server
+            .directory()
+            .query(QueryBy::Id(account_id), false)
+            .await
+            .caused_by(trc::location!())?
+        {
+            if matches!(principal.typ(), Type::Individual | Type::Group) {
+                return Ok(Some(account_id));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+async fn invite_notification(
+    server: &Server,
+    access_token: &AccessToken,
+    invite: &PendingInvite,
+) -> crate::Result<InviteNotification> {
+    let organizer = server
+        .owner_href(access_token, invite.account_id)
+        .await
+        .caused_by(trc::location!())?;
+    let notification = InviteNotification::new(
+        &invite.invite.uid,
+        invite.invite.created,
+        Sharee {
+            href: access_token.current_user_principal(),
+            common_name: invite.invite.common_name.clone(),
+            status: ShareeStatus::NoResponse,
+            read_write: invite.invite.read_write,
+            summary: invite.invite.summary.clone(),
+        },
+        &invite.href,
+        organizer,
+    );
+
+    if let Some(name) = server
+        .directory()
+        .query(QueryBy::Id(invite.account_id), false)
+        .await
+        .caused_by(trc::location!())?
+        .and_then(|principal| principal.description().map(|name| name.to_string()))
+    {
+        Ok(notification.with_organizer_name(name))
+    } else {
+        Ok(notification)
+    }
+}
+
+fn notification_etag(invite: &PendingInvite) -> String {
+    let mut hasher = DefaultHasher::new();
+    invite.invite.uid.hash(&mut hasher);
+    invite.invite.read_write.hash(&mut hasher);
+    invite.invite.summary.hash(&mut hasher);
+    invite.href.hash(&mut hasher);
+    format!("\"{:x}\"", hasher.finish())
+}
+
+fn collection_propstat(
+    access_token: &AccessToken,
+    href: String,
+    invites: &[PendingInvite],
+    request: &PropFind,
+    is_minimal: bool,
+) -> Response {
+    static COLLECTION_PROPS: [DavProperty; 4] = [
+        DavProperty::WebDav(WebDavProperty::DisplayName),
+        DavProperty::WebDav(WebDavProperty::ResourceType),
+        DavProperty::WebDav(WebDavProperty::CurrentUserPrincipal),
+        DavProperty::WebDav(WebDavProperty::GetCTag),
+    ];
+
+    let properties = match request {
+        PropFind::PropName => return empty_propstat(href, &COLLECTION_PROPS),
+        PropFind::AllProp(_) => COLLECTION_PROPS.as_slice(),
+        PropFind::Prop(items) => items.as_slice(),
+    };
+
+    let mut fields = Vec::with_capacity(properties.len());
+    let mut fields_not_found = Vec::new();
+    for property in properties {
+        match property {
+            DavProperty::WebDav(WebDavProperty::DisplayName) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::String(NOTIFICATION_COLLECTION_NAME.to_string()),
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::ResourceType) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    vec![ResourceType::Collection, ResourceType::Notification],
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::CurrentUserPrincipal) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    vec![access_token.current_user_principal()],
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::GetCTag) => {
+                let mut hasher = DefaultHasher::new();
+                for invite in invites {
+                    notification_etag(invite).hash(&mut hasher);
+                }
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::String(format!("\"{:x}\"", hasher.finish())),
+                ));
+            }
+            _ => {
+                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+            }
+        }
+    }
+
+    build_propstat(href, request, is_minimal, fields, fields_not_found)
+}
+
+fn notification_propstat(
+    href: String,
+    invite: &PendingInvite,
+    request: &PropFind,
+    is_minimal: bool,
+) -> Response {
+    static NOTIFICATION_PROPS: [DavProperty; 4] = [
+        DavProperty::WebDav(WebDavProperty::GetETag),
+        DavProperty::WebDav(WebDavProperty::GetContentType),
+        DavProperty::WebDav(WebDavProperty::ResourceType),
+        DavProperty::CalDav(CalDavProperty::NotificationType),
+    ];
+
+    let properties = match request {
+        PropFind::PropName => return empty_propstat(href, &NOTIFICATION_PROPS),
+        PropFind::AllProp(_) => NOTIFICATION_PROPS.as_slice(),
+        PropFind::Prop(items) => items.as_slice(),
+    };
+
+    let mut fields = Vec::with_capacity(properties.len());
+    let mut fields_not_found = Vec::new();
+    for property in properties {
+        match property {
+            DavProperty::WebDav(WebDavProperty::GetETag) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::String(notification_etag(invite)),
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::GetContentType) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::String("application/xml".to_string()),
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::ResourceType) => {
+                fields.push(DavPropertyValue::empty(property.clone()));
+            }
+            DavProperty::CalDav(CalDavProperty::NotificationType) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::InviteNotification,
+                ));
+            }
+            _ => {
+                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+            }
+        }
+    }
+
+    build_propstat(href, request, is_minimal, fields, fields_not_found)
+}
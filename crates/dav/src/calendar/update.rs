@@ -11,14 +11,29 @@ use calcard::{
     common::timezone::Tz,
     icalendar::{ICalendar, ICalendarComponentType},
 };
-use common::{DavName, Server, auth::AccessToken};
+use common::{
+    DavName, DavResources, Server,
+    auth::{AccessToken, AsTenantId},
+};
 use dav_proto::{
     RequestHeaders, Return,
-    schema::{property::Rfc1123DateTime, response::CalCondition},
+    schema::{
+        property::{CalendarDataType, Rfc1123DateTime},
+        response::{CalCondition, Repair, RepairLog},
+    },
 };
 use groupware::{
     cache::GroupwareCache,
-    calendar::{CalendarEvent, CalendarEventData},
+    calendar::{
+        Calendar, CalendarEvent, CalendarEventData, component_flag,
+        scheduling::CalendarScheduling,
+        split::{
+            has_override_resources, is_override_only, is_override_resource,
+            merge_override_resource, split_override_resource, strip_resource_tags,
+        },
+        timezone::strip_known_timezones,
+    },
+    icap::{ContentInspection, ContentKind, InspectionResult},
 };
 use http_proto::HttpResponse;
 use hyper::StatusCode;
@@ -31,15 +46,22 @@ use trc::AddContext;
 
 use crate::{
     DavError, DavErrorCondition, DavMethod,
+    calendar::{
+        compat::{ContentLines, import_google_compat, import_outlook_compat},
+        format::parse_jcal,
+    },
     common::{
         ETag, ExtractETag,
+        acl::ResourceAcl,
+        charset::{CharsetError, decode_text_body},
+        limits::{CollectionQuotaCheck, ItemLimitCheck},
         lock::{LockRequestHandler, ResourceState},
-        uri::DavUriResource,
+        uri::{DavUriResource, validate_resource_name},
     },
     file::DavFileResource,
 };
 
-use super::assert_is_unique_uid;
+use super::{assert_is_unique_uid, split_etag, split_resource, uid_document_id};
 
 pub(crate) trait CalendarUpdateRequestHandler: Sync + Send {
     fn handle_calendar_update_request(
@@ -79,21 +101,65 @@ impl CalendarUpdateRequestHandler for Server {
                 CalCondition::MaxResourceSize(self.core.groupware.max_ical_size as u32),
             )));
         }
-        let ical_raw = std::str::from_utf8(&bytes).map_err(|_| {
+
+        // Inspect content
+        if self
+            .inspect_content(
+                access_token,
+                ContentKind::Calendar,
+                resource_name,
+                headers.content_type,
+                &bytes,
+            )
+            .await
+            != InspectionResult::Allowed
+        {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+        let ical_decoded = decode_text_body(&bytes, headers.content_type).map_err(|err| {
             DavError::Condition(DavErrorCondition::new(
                 StatusCode::PRECONDITION_FAILED,
-                CalCondition::SupportedCalendarData,
+                match err {
+                    CharsetError::Unsupported => CalCondition::SupportedCalendarData,
+                    CharsetError::Undecodable => CalCondition::ValidCalendarData,
+                },
             ))
         })?;
+        let ical_raw = ical_decoded.as_ref();
 
-        let ical = match Parser::new(ical_raw).entry() {
-            Entry::ICalendar(ical) => ical,
-            _ => {
-                return Err(DavError::Condition(DavErrorCondition::new(
-                    StatusCode::PRECONDITION_FAILED,
-                    CalCondition::SupportedCalendarData,
-                )));
+        // jCal bodies are parsed into the same iCalendar object as text bodies
+        let mut ical = if headers.content_type.and_then(CalendarDataType::try_parse)
+            == Some(CalendarDataType::JCal)
+        {
+            parse_jcal(ical_raw)
+        } else {
+            match Parser::new(ical_raw).entry() {
+                Entry::ICalendar(ical) => Some(ical),
+                _ => None,
             }
+        }
+        .ok_or(DavError::Condition(DavErrorCondition::new(
+            StatusCode::PRECONDITION_FAILED,
+            CalCondition::SupportedCalendarData,
+        )))?;
+
+        // Map Outlook/Exchange properties and timezone names, and tolerate
+        // Google Calendar quirks when enabled for the tenant
+        let mut repairs = Vec::new();
+        let lines = ContentLines::new(&ical);
+        import_outlook_compat(&mut ical, &lines, &mut repairs);
+        if self.core.groupware.google_compat(access_token.tenant_id()) {
+            import_google_compat(&mut ical, &lines, &mut repairs);
+        }
+
+        strip_resource_tags(&mut ical);
+
+        // Clients using timezones by reference do not need the standard
+        // VTIMEZONE definitions to be stored (RFC 7809)
+        let size = if headers.no_timezones && strip_known_timezones(&mut ical) {
+            ical.to_string().len()
+        } else {
+            bytes.len()
         };
 
         if let Some(resource) = resources.by_path(resource_name) {
@@ -104,10 +170,16 @@ impl CalendarUpdateRequestHandler for Server {
             // Validate ACL
             let parent_id = resource.parent_id().unwrap();
             let document_id = resource.document_id();
+            let name = resource_name
+                .rsplit_once('/')
+                .map_or(resource_name, |(_, name)| name);
+            if is_schedule_collection(&resources, parent_id) {
+                return Err(DavError::Code(StatusCode::FORBIDDEN));
+            }
             if !access_token.is_member(account_id)
                 && !resources.has_access_to_container(access_token, parent_id, Acl::ModifyItems)
             {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(resources.need_privileges(Some(parent_id), Acl::ModifyItems));
             }
 
             // Update
@@ -119,6 +191,10 @@ impl CalendarUpdateRequestHandler for Server {
             let event = event_
                 .to_unarchived::<CalendarEvent>()
                 .caused_by(trc::location!())?;
+            let split = split_resource(event.inner, parent_id, name)?;
+            let etag = split
+                .as_ref()
+                .map_or_else(|| event.etag(), |(_, etag)| etag.clone());
 
             // Validate headers
             match self
@@ -129,7 +205,7 @@ impl CalendarUpdateRequestHandler for Server {
                         account_id,
                         collection: Collection::CalendarEvent,
                         document_id: Some(document_id),
-                        etag: event.etag().into(),
+                        etag: etag.clone().into(),
                         path: resource_name,
                         ..Default::default()
                     }],
@@ -144,19 +220,62 @@ impl CalendarUpdateRequestHandler for Server {
                 {
                     return Ok(HttpResponse::new(StatusCode::PRECONDITION_FAILED)
                         .with_content_type("text/calendar; charset=utf-8")
-                        .with_etag(event.etag())
+                        .with_etag(etag)
                         .with_last_modified(
                             Rfc1123DateTime::new(i64::from(event.inner.modified)).to_string(),
                         )
                         .with_header("Preference-Applied", "return=representation")
-                        .with_binary_body(event.inner.data.event.to_string()));
+                        .with_binary_body(split.map_or_else(
+                            || event.inner.data.event.to_string(),
+                            |(ical, _)| ical.to_string(),
+                        )));
                 }
                 Err(e) => return Err(e),
             }
 
+            // Merge the upload with the recurrence overrides uploaded as
+            // separate resources
+            let itip = self.core.groupware.scheduling_enabled.then(|| ical.clone());
+            let mut new_event = event
+                .deserialize::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+            let (ical, size) = if has_override_resources(&new_event.data.event) {
+                let stored = std::mem::take(&mut new_event.data.event);
+                let ical = if is_override_resource(&stored, name) {
+                    if !is_override_only(&ical) {
+                        return Err(DavError::Condition(DavErrorCondition::new(
+                            StatusCode::PRECONDITION_FAILED,
+                            CalCondition::ValidCalendarObjectResource,
+                        )));
+                    }
+                    let mut stored = stored;
+                    merge_override_resource(&mut stored, ical, name);
+                    stored
+                } else {
+                    let mut ical = ical;
+                    for other in new_event
+                        .names
+                        .iter()
+                        .filter(|other| other.parent_id == parent_id && other.name != name)
+                    {
+                        if is_override_resource(&stored, &other.name) {
+                            merge_override_resource(
+                                &mut ical,
+                                split_override_resource(&stored, &other.name),
+                                &other.name,
+                            );
+                        }
+                    }
+                    ical
+                };
+                let size = ical.to_string().len();
+                (ical, size)
+            } else {
+                (ical, size)
+            };
+
             // Validate quota
-            let extra_bytes =
-                (bytes.len() as u64).saturating_sub(u32::from(event.inner.size) as u64);
+            let extra_bytes = (size as u64).saturating_sub(u32::from(event.inner.size) as u64);
             if extra_bytes > 0 {
                 self.has_available_quota(
                     &self.get_resource_token(access_token, account_id).await?,
@@ -165,6 +284,16 @@ impl CalendarUpdateRequestHandler for Server {
                 .await?;
             }
 
+            // Validate calendar quota
+            self.assert_collection_quota(
+                &resources,
+                account_id,
+                Collection::Calendar,
+                parent_id.into(),
+                extra_bytes,
+            )
+            .await?;
+
             // Validate iCal
             if event.inner.data.event.uids().next().unwrap_or_default() != validate_ical(&ical)? {
                 return Err(DavError::Condition(DavErrorCondition::new(
@@ -172,14 +301,17 @@ impl CalendarUpdateRequestHandler for Server {
                     CalCondition::NoUidConflict(resources.format_resource(resource).into()),
                 )));
             }
+            assert_supported_components(self, account_id, parent_id, &ical).await?;
 
             // Build node
-            let mut new_event = event
-                .deserialize::<CalendarEvent>()
-                .caused_by(trc::location!())?;
-            new_event.size = bytes.len() as u32;
+            new_event.size = size as u32;
             new_event.data =
                 CalendarEventData::new(ical, Tz::Floating, self.core.groupware.max_ical_instances);
+            new_event.retain_referenced_attachments();
+            let split = has_override_resources(&new_event.data.event)
+                .then(|| split_override_resource(&new_event.data.event, name));
+            let representation = (headers.ret == Return::Representation)
+                .then(|| split.as_ref().unwrap_or(&new_event.data.event).to_string());
 
             // Prepare write batch
             let mut batch = BatchBuilder::new();
@@ -187,13 +319,29 @@ impl CalendarUpdateRequestHandler for Server {
                 .update(access_token, event, account_id, document_id, &mut batch)
                 .caused_by(trc::location!())?
                 .etag();
+            let etag = split.as_ref().map(split_etag).or(etag);
             self.commit_batch(batch).await.caused_by(trc::location!())?;
 
-            Ok(HttpResponse::new(StatusCode::NO_CONTENT).with_etag_opt(etag))
+            trc::event!(
+                Groupware(trc::GroupwareEvent::CalendarUpdated),
+                AccountId = account_id,
+                Collection = Collection::CalendarEvent,
+                DocumentId = document_id,
+                Url = resources.format_item(resource_name),
+            );
+
+            if let Some(itip) = itip {
+                send_itip(self, account_id, &itip).await;
+            }
+
+            Ok(put_response(StatusCode::NO_CONTENT, repairs, representation).with_etag_opt(etag))
         } else if let Some((Some(parent), name)) = resources.map_parent(resource_name) {
             if !parent.is_container() {
                 return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+            } else if parent.schedule_collection().is_some() || parent.is_birthday_calendar() {
+                return Err(DavError::Code(StatusCode::FORBIDDEN));
             }
+            validate_resource_name(name)?;
 
             // Validate ACL
             if !access_token.is_member(account_id)
@@ -203,7 +351,7 @@ impl CalendarUpdateRequestHandler for Server {
                     Acl::AddItems,
                 )
             {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(resources.need_privileges(Some(parent.document_id()), Acl::AddItems));
             }
 
             // Validate headers
@@ -222,25 +370,110 @@ impl CalendarUpdateRequestHandler for Server {
             )
             .await?;
 
+            // Validate item limit
+            self.assert_item_limit(
+                access_token,
+                &resources,
+                account_id,
+                Collection::CalendarEvent,
+                parent.document_id().into(),
+            )
+            .await?;
+
             // Validate quota
             if !bytes.is_empty() {
                 self.has_available_quota(
                     &self.get_resource_token(access_token, account_id).await?,
-                    bytes.len() as u64,
+                    size as u64,
                 )
                 .await?;
             }
 
-            // Validate ical object
-            assert_is_unique_uid(
-                self,
+            // Validate calendar quota
+            self.assert_collection_quota(
                 &resources,
                 account_id,
-                parent.document_id(),
-                validate_ical(&ical)?.into(),
+                Collection::Calendar,
+                parent.document_id().into(),
+                size as u64,
             )
             .await?;
 
+            // Validate ical object, recurrence overrides uploaded as a separate
+            // resource are merged into the event they belong to
+            let uid = validate_ical(&ical)?;
+            let merge_id = if is_override_only(&ical) {
+                uid_document_id(self, &resources, account_id, parent.document_id(), uid).await?
+            } else {
+                None
+            };
+            if merge_id.is_none() {
+                assert_is_unique_uid(
+                    self,
+                    &resources,
+                    account_id,
+                    parent.document_id(),
+                    uid.into(),
+                    None,
+                )
+                .await?;
+            }
+            assert_supported_components(self, account_id, parent.document_id(), &ical).await?;
+
+            let itip = self.core.groupware.scheduling_enabled.then(|| ical.clone());
+            if let Some(document_id) = merge_id {
+                let event_ = self
+                    .get_archive(account_id, Collection::CalendarEvent, document_id)
+                    .await
+                    .caused_by(trc::location!())?
+                    .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+                let event = event_
+                    .to_unarchived::<CalendarEvent>()
+                    .caused_by(trc::location!())?;
+
+                // Build node
+                let mut new_event = event
+                    .deserialize::<CalendarEvent>()
+                    .caused_by(trc::location!())?;
+                let mut merged = std::mem::take(&mut new_event.data.event);
+                merge_override_resource(&mut merged, ical, name);
+                new_event.names.push(DavName {
+                    name: name.to_string(),
+                    parent_id: parent.document_id(),
+                });
+                new_event.size = merged.to_string().len() as u32;
+                new_event.data = CalendarEventData::new(
+                    merged,
+                    Tz::Floating,
+                    self.core.groupware.max_ical_instances,
+                );
+                let split = split_override_resource(&new_event.data.event, name);
+                let representation =
+                    (headers.ret == Return::Representation).then(|| split.to_string());
+
+                // Prepare write batch
+                let mut batch = BatchBuilder::new();
+                new_event
+                    .update(access_token, event, account_id, document_id, &mut batch)
+                    .caused_by(trc::location!())?;
+                self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+                trc::event!(
+                    Groupware(trc::GroupwareEvent::CalendarCreated),
+                    AccountId = account_id,
+                    Collection = Collection::CalendarEvent,
+                    DocumentId = document_id,
+                    Url = resources.format_item(resource_name),
+                );
+
+                if let Some(itip) = itip {
+                    send_itip(self, account_id, &itip).await;
+                }
+
+                return Ok(put_response(StatusCode::CREATED, repairs, representation)
+                    .with_etag(split_etag(&split)));
+            }
+
             // Build node
             let event = CalendarEvent {
                 names: vec![DavName {
@@ -252,9 +485,11 @@ impl CalendarUpdateRequestHandler for Server {
                     Tz::Floating,
                     self.core.groupware.max_ical_instances,
                 ),
-                size: bytes.len() as u32,
+                size: size as u32,
                 ..Default::default()
             };
+            let representation =
+                (headers.ret == Return::Representation).then(|| event.data.event.to_string());
 
             // Prepare write batch
             let mut batch = BatchBuilder::new();
@@ -269,13 +504,75 @@ impl CalendarUpdateRequestHandler for Server {
                 .etag();
             self.commit_batch(batch).await.caused_by(trc::location!())?;
 
-            Ok(HttpResponse::new(StatusCode::CREATED).with_etag_opt(etag))
+            trc::event!(
+                Groupware(trc::GroupwareEvent::CalendarCreated),
+                AccountId = account_id,
+                Collection = Collection::CalendarEvent,
+                DocumentId = document_id,
+                Url = resources.format_item(resource_name),
+            );
+
+            if let Some(itip) = itip {
+                send_itip(self, account_id, &itip).await;
+            }
+
+            Ok(put_response(StatusCode::CREATED, repairs, representation).with_etag_opt(etag))
         } else {
             Err(DavError::Code(StatusCode::CONFLICT))?
         }
     }
 }
 
+// Scheduling and birthday calendars are managed by the server
+fn is_schedule_collection(resources: &DavResources, document_id: u32) -> bool {
+    resources
+        .container_by_id(document_id)
+        .is_some_and(|container| {
+            container.schedule_collection().is_some() || container.is_birthday_calendar()
+        })
+}
+
+async fn send_itip(server: &Server, account_id: u32, ical: &ICalendar) {
+    // Delivery failures never fail the request that stored the event
+    if let Err(err) = server.deliver_itip(account_id, ical).await {
+        trc::error!(
+            err.account_id(account_id)
+                .details("Failed to deliver scheduling message")
+                .caused_by(trc::location!())
+        );
+    }
+}
+
+async fn assert_supported_components(
+    server: &Server,
+    account_id: u32,
+    calendar_id: u32,
+    ical: &ICalendar,
+) -> crate::Result<()> {
+    let components = ical
+        .components
+        .iter()
+        .fold(0, |acc, comp| acc | component_flag(&comp.component_type));
+    if let Some(calendar_) = server
+        .get_archive(account_id, Collection::Calendar, calendar_id)
+        .await
+        .caused_by(trc::location!())?
+    {
+        if !calendar_
+            .unarchive::<Calendar>()
+            .caused_by(trc::location!())?
+            .supports_components(components)
+        {
+            return Err(DavError::Condition(DavErrorCondition::new(
+                StatusCode::PRECONDITION_FAILED,
+                CalCondition::SupportedCalendarComponent,
+            )));
+        }
+    }
+
+    Ok(())
+}
+
 fn validate_ical(ical: &ICalendar) -> crate::Result<&str> {
     // Validate UIDs
     let mut uids = HashSet::with_capacity(1);
@@ -308,3 +605,31 @@ fn validate_ical(ical: &ICalendar) -> crate::Result<&str> {
         )))
     }
 }
+
+// Returns the stored event when the client asked for it (RFC 7240), otherwise
+// lists the repairs applied to the uploaded data in the response body, if any
+fn put_response(
+    status: StatusCode,
+    repairs: Vec<Repair>,
+    representation: Option<String>,
+) -> HttpResponse {
+    if let Some(representation) = representation {
+        HttpResponse::new(if status == StatusCode::NO_CONTENT {
+            StatusCode::OK
+        } else {
+            status
+        })
+        .with_content_type("text/calendar; charset=utf-8")
+        .with_header("Preference-Applied", "return=representation")
+        .with_binary_body(representation)
+    } else if repairs.is_empty() {
+        HttpResponse::new(status)
+    } else {
+        HttpResponse::new(if status == StatusCode::NO_CONTENT {
+            StatusCode::OK
+        } else {
+            status
+        })
+        .with_xml_body(RepairLog::new(repairs).to_string())
+    }
+}
@@ -23,8 +23,11 @@ use trc::AddContext;
 use crate::{
     DavError, DavMethod,
     common::{
+        acl::ResourceAcl,
+        copy_move_events,
+        limits::ItemLimitCheck,
         lock::{LockRequestHandler, ResourceState},
-        uri::DavUriResource,
+        uri::{DavUriResource, validate_resource_name},
     },
     file::DavFileResource,
 };
@@ -65,19 +68,25 @@ impl CardCopyMoveRequestHandler for Server {
             .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
 
         // Validate ACL
+        let from_container_id = if from_resource.is_container() {
+            from_resource.document_id()
+        } else {
+            from_resource.parent_id().unwrap()
+        };
         if !access_token.is_member(from_account_id)
             && !from_resources.has_access_to_container(
                 access_token,
-                if from_resource.is_container() {
-                    from_resource.document_id()
-                } else {
-                    from_resource.parent_id().unwrap()
-                },
+                from_container_id,
                 Acl::ReadItems,
             )
         {
-            return Err(DavError::Code(StatusCode::FORBIDDEN));
+            return Err(from_resources.need_privileges(Some(from_container_id), Acl::ReadItems));
         }
+        let from_acl = if is_move {
+            Acl::RemoveItems
+        } else {
+            Acl::ReadItems
+        };
 
         // Validate destination
         let destination = self
@@ -85,16 +94,16 @@ impl CardCopyMoveRequestHandler for Server {
                 access_token,
                 headers
                     .destination
-                    .ok_or(DavError::Code(StatusCode::BAD_GATEWAY))?,
-                StatusCode::BAD_GATEWAY,
+                    .ok_or(DavError::Code(StatusCode::BAD_REQUEST))?,
+                StatusCode::BAD_REQUEST,
             )
             .await?;
         if destination.collection != Collection::AddressBook {
-            return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+            return Err(DavError::Code(StatusCode::CONFLICT));
         }
         let to_account_id = destination
             .account_id
-            .ok_or(DavError::Code(StatusCode::BAD_GATEWAY))?;
+            .ok_or(DavError::Code(StatusCode::CONFLICT))?;
         let to_resources = if to_account_id == from_account_id {
             from_resources.clone()
         } else {
@@ -106,7 +115,7 @@ impl CardCopyMoveRequestHandler for Server {
         // Validate headers
         let destination_resource_name = destination
             .resource
-            .ok_or(DavError::Code(StatusCode::BAD_GATEWAY))?;
+            .ok_or(DavError::Code(StatusCode::FORBIDDEN))?;
         let to_resource = to_resources.by_path(destination_resource_name);
         self.validate_headers(
             access_token,
@@ -149,10 +158,10 @@ impl CardCopyMoveRequestHandler for Server {
         .await?;
 
         // Map destination
-        if let Some(to_resource) = to_resource {
+        let result = if let Some(to_resource) = to_resource {
             if from_resource.path() == to_resource.path() {
                 // Same resource
-                return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+                return Err(DavError::Code(StatusCode::FORBIDDEN));
             }
             let new_name = destination_resource_name
                 .rsplit_once('/')
@@ -172,20 +181,18 @@ impl CardCopyMoveRequestHandler for Server {
                         .map(|r| r.document_id())
                         .collect::<Vec<_>>();
 
-                    // Validate ACLs
-                    if !access_token.is_member(to_account_id)
-                        || (!access_token.is_member(from_account_id)
-                            && !from_resources.has_access_to_container(
-                                access_token,
-                                from_resource.document_id(),
-                                if is_move {
-                                    Acl::RemoveItems
-                                } else {
-                                    Acl::ReadItems
-                                },
-                            ))
+                    // Validate ACLs, shared users cannot replace containers
+                    if !access_token.is_member(to_account_id) {
+                        return Err(to_resources.need_privileges(None, Acl::AddItems));
+                    } else if !access_token.is_member(from_account_id)
+                        && !from_resources.has_access_to_container(
+                            access_token,
+                            from_resource.document_id(),
+                            from_acl,
+                        )
                     {
-                        return Err(DavError::Code(StatusCode::FORBIDDEN));
+                        return Err(from_resources
+                            .need_privileges(Some(from_resource.document_id()), from_acl));
                     }
 
                     // Overwrite container
@@ -210,24 +217,26 @@ impl CardCopyMoveRequestHandler for Server {
                     let to_addressbook_id = to_resource.parent_id().unwrap();
 
                     // Validate ACL
-                    if (!access_token.is_member(from_account_id)
+                    if !access_token.is_member(from_account_id)
                         && !from_resources.has_access_to_container(
                             access_token,
                             from_addressbook_id,
-                            if is_move {
-                                Acl::RemoveItems
-                            } else {
-                                Acl::ReadItems
-                            },
-                        ))
-                        || (!access_token.is_member(to_account_id)
-                            && !to_resources.has_access_to_container(
-                                access_token,
-                                to_addressbook_id,
-                                Acl::RemoveItems,
-                            ))
+                            from_acl,
+                        )
                     {
-                        return Err(DavError::Code(StatusCode::FORBIDDEN));
+                        return Err(
+                            from_resources.need_privileges(Some(from_addressbook_id), from_acl)
+                        );
+                    } else if !access_token.is_member(to_account_id)
+                        && !to_resources.has_access_to_container(
+                            access_token,
+                            to_addressbook_id,
+                            Acl::RemoveItems,
+                        )
+                    {
+                        return Err(
+                            to_resources.need_privileges(Some(to_addressbook_id), Acl::RemoveItems)
+                        );
                     }
 
                     if is_move {
@@ -258,39 +267,56 @@ impl CardCopyMoveRequestHandler for Server {
                         .await
                     }
                 }
-                _ => Err(DavError::Code(StatusCode::BAD_GATEWAY)),
+                _ => Err(DavError::Code(StatusCode::CONFLICT)),
             }
         } else if let Some((parent_resource, new_name)) =
             to_resources.map_parent(destination_resource_name)
         {
+            validate_resource_name(new_name)?;
+
             if let Some(parent_resource) = parent_resource {
                 // Creating items under a card is not allowed
                 // Copying/moving containers under a container is not allowed
                 if !parent_resource.is_container() || from_resource.is_container() {
-                    return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+                    return Err(DavError::Code(StatusCode::CONFLICT));
                 }
 
                 // Validate ACL
                 let from_addressbook_id = from_resource.parent_id().unwrap();
                 let to_addressbook_id = parent_resource.document_id();
-                if (!access_token.is_member(from_account_id)
+                if !access_token.is_member(from_account_id)
                     && !from_resources.has_access_to_container(
                         access_token,
                         from_addressbook_id,
-                        if is_move {
-                            Acl::RemoveItems
-                        } else {
-                            Acl::ReadItems
-                        },
-                    ))
-                    || (!access_token.is_member(to_account_id)
-                        && !to_resources.has_access_to_container(
-                            access_token,
-                            to_addressbook_id,
-                            Acl::AddItems,
-                        ))
+                        from_acl,
+                    )
                 {
-                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                    return Err(from_resources.need_privileges(Some(from_addressbook_id), from_acl));
+                } else if !access_token.is_member(to_account_id)
+                    && !to_resources.has_access_to_container(
+                        access_token,
+                        to_addressbook_id,
+                        Acl::AddItems,
+                    )
+                {
+                    return Err(
+                        to_resources.need_privileges(Some(to_addressbook_id), Acl::AddItems)
+                    );
+                }
+
+                // Validate item limits, renames within the same container are exempt
+                if !is_move
+                    || from_account_id != to_account_id
+                    || to_addressbook_id != from_addressbook_id
+                {
+                    self.assert_item_limit(
+                        access_token,
+                        &to_resources,
+                        to_account_id,
+                        Collection::ContactCard,
+                        to_addressbook_id.into(),
+                    )
+                    .await?;
                 }
 
                 // Copy/move card
@@ -339,12 +365,12 @@ impl CardCopyMoveRequestHandler for Server {
             } else {
                 // Copying/moving cards to the root is not allowed
                 if !from_resource.is_container() {
-                    return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+                    return Err(DavError::Code(StatusCode::CONFLICT));
                 }
 
                 // Shared users cannot create containers
                 if !access_token.is_member(to_account_id) {
-                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                    return Err(to_resources.need_privileges(None, Acl::AddItems));
                 }
 
                 // Validate ACLs
@@ -352,14 +378,12 @@ impl CardCopyMoveRequestHandler for Server {
                     && !from_resources.has_access_to_container(
                         access_token,
                         from_resource.document_id(),
-                        if is_move {
-                            Acl::RemoveItems
-                        } else {
-                            Acl::ReadItems
-                        },
+                        from_acl,
                     )
                 {
-                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                    return Err(
+                        from_resources.need_privileges(Some(from_resource.document_id()), from_acl)
+                    );
                 }
 
                 // Copy/move container
@@ -378,7 +402,8 @@ impl CardCopyMoveRequestHandler for Server {
                             if headers.depth != Depth::Zero {
                                 from_children_ids
                             } else {
-                                return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+                                // Collections are always moved with all their members
+                                return Err(DavError::Code(StatusCode::BAD_REQUEST));
                             },
                             from_resources.format_collection(from_resource_name),
                             to_account_id,
@@ -422,7 +447,29 @@ impl CardCopyMoveRequestHandler for Server {
             }
         } else {
             Err(DavError::Code(StatusCode::CONFLICT))
+        };
+
+        if let Ok(response) = &result {
+            copy_move_events(
+                response,
+                is_move,
+                if from_resource.is_container() {
+                    Collection::AddressBook
+                } else {
+                    Collection::ContactCard
+                },
+                from_account_id,
+                from_resources.format_resource(from_resource),
+                to_account_id,
+                if from_resource.is_container() {
+                    to_resources.format_collection(destination_resource_name)
+                } else {
+                    to_resources.format_item(destination_resource_name)
+                },
+            );
         }
+
+        result
     }
 }
 
@@ -459,6 +506,7 @@ async fn copy_card(
         to_account_id,
         to_addressbook_id,
         card.inner.card.uid(),
+        to_document_id,
     )
     .await?;
 
@@ -571,6 +619,7 @@ async fn move_card(
             to_account_id,
             to_addressbook_id,
             card.inner.card.uid(),
+            to_document_id,
         )
         .await?;
     }
@@ -739,33 +788,19 @@ async fn copy_container(
         .await
         .caused_by(trc::location!())?
         .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
-    let old_book = book_
+    let mut book = book_
         .to_unarchived::<AddressBook>()
-        .caused_by(trc::location!())?;
-    let mut book = old_book
+        .caused_by(trc::location!())?
         .deserialize::<AddressBook>()
         .caused_by(trc::location!())?;
 
-    // Prepare write batch
-    let mut batch = BatchBuilder::new();
-
-    if remove_source {
-        DestroyArchive(old_book)
-            .delete(
-                access_token,
-                from_account_id,
-                from_document_id,
-                from_resource_path.into(),
-                &mut batch,
-            )
-            .caused_by(trc::location!())?;
-    }
-
     book.name = new_name.to_string();
     book.subscribers.clear();
     book.acls.clear();
     book.is_default = false;
 
+    // Prepare write batch
+    let mut batch = BatchBuilder::new();
     let is_overwrite = to_document_id.is_some();
     let to_document_id = if let Some(to_document_id) = to_document_id {
         // Overwrite destination
@@ -802,91 +837,176 @@ async fn copy_container(
     };
     book.insert(access_token, to_account_id, to_document_id, &mut batch)
         .caused_by(trc::location!())?;
+    server
+        .commit_batch(batch)
+        .await
+        .caused_by(trc::location!())?;
 
     // Copy children
+    let mut created_ids = Vec::new();
+    if let Err(err) = copy_cards(
+        server,
+        access_token,
+        from_account_id,
+        from_document_id,
+        &from_children_ids,
+        to_account_id,
+        to_document_id,
+        &mut created_ids,
+    )
+    .await
+    {
+        // Undo the batches that were already committed
+        if let Err(rollback_err) = delete_container(
+            server,
+            access_token,
+            to_account_id,
+            to_document_id,
+            created_ids,
+            None,
+        )
+        .await
+        {
+            trc::error!(
+                rollback_err
+                    .account_id(to_account_id)
+                    .details("Failed to rollback partial container copy")
+            );
+        }
+
+        return Err(err);
+    }
+
+    // Unlink the source once all children were copied
+    if remove_source {
+        delete_container(
+            server,
+            access_token,
+            from_account_id,
+            from_document_id,
+            from_children_ids,
+            from_resource_path.into(),
+        )
+        .await?;
+    }
+
+    if !is_overwrite {
+        Ok(HttpResponse::new(StatusCode::CREATED))
+    } else {
+        Ok(HttpResponse::new(StatusCode::NO_CONTENT))
+    }
+}
+
+// Links or copies the children of a book into the destination, committing size-bounded
+// batches as it goes. The ids of all committed children are recorded in `created_ids`.
+#[allow(clippy::too_many_arguments)]
+async fn copy_cards(
+    server: &Server,
+    access_token: &AccessToken,
+    from_account_id: u32,
+    from_document_id: u32,
+    from_children_ids: &[u32],
+    to_account_id: u32,
+    to_document_id: u32,
+    created_ids: &mut Vec<u32>,
+) -> crate::Result<()> {
+    let mut batch = BatchBuilder::new();
+    let mut pending_ids = Vec::new();
     let mut required_space = 0;
-    for from_child_document_id in from_children_ids {
-        if let Some(card_) = server
+
+    for &from_child_document_id in from_children_ids {
+        let Some(card_) = server
             .get_archive(
                 from_account_id,
                 Collection::ContactCard,
                 from_child_document_id,
             )
-            .await?
-        {
-            let card = card_
-                .to_unarchived::<ContactCard>()
-                .caused_by(trc::location!())?;
-            let mut new_name = None;
+            .await
+            .caused_by(trc::location!())?
+        else {
+            continue;
+        };
+        let card = card_
+            .to_unarchived::<ContactCard>()
+            .caused_by(trc::location!())?;
+        let Some(new_name) = card
+            .inner
+            .names
+            .iter()
+            .find(|name| name.parent_id == from_document_id)
+            .map(|name| DavName {
+                name: name.name.to_string(),
+                parent_id: to_document_id,
+            })
+        else {
+            continue;
+        };
+        let mut new_card = card
+            .deserialize::<ContactCard>()
+            .caused_by(trc::location!())?;
 
-            for name in card.inner.names.iter() {
-                if name.parent_id == to_document_id {
-                    continue;
-                } else if name.parent_id == from_document_id {
-                    new_name = Some(name.name.to_string());
-                }
-            }
-            let new_name = if let Some(new_name) = new_name {
-                DavName {
-                    name: new_name,
-                    parent_id: to_document_id,
-                }
-            } else {
-                continue;
-            };
-            let card = card_
-                .to_unarchived::<ContactCard>()
+        if from_account_id == to_account_id {
+            new_card.names.push(new_name);
+            new_card
+                .update(
+                    access_token,
+                    card,
+                    from_account_id,
+                    from_child_document_id,
+                    &mut batch,
+                )
                 .caused_by(trc::location!())?;
-            let mut new_card = card
-                .deserialize::<ContactCard>()
+            pending_ids.push(from_child_document_id);
+        } else {
+            let to_child_document_id = server
+                .store()
+                .assign_document_ids(to_account_id, Collection::ContactCard, 1)
+                .await
                 .caused_by(trc::location!())?;
+            new_card.names = vec![new_name];
+            required_space += new_card.size as u64;
+            new_card
+                .insert(
+                    access_token,
+                    to_account_id,
+                    to_child_document_id,
+                    &mut batch,
+                )
+                .caused_by(trc::location!())?;
+            pending_ids.push(to_child_document_id);
+        }
 
-            if from_account_id == to_account_id {
-                if remove_source {
-                    new_card
-                        .names
-                        .retain(|name| name.parent_id != from_document_id);
-                }
-
-                new_card.names.push(new_name);
-                new_card
-                    .update(
-                        access_token,
-                        card,
-                        from_account_id,
-                        from_child_document_id,
-                        &mut batch,
-                    )
-                    .caused_by(trc::location!())?;
-            } else {
-                if remove_source {
-                    DestroyArchive(card)
-                        .delete(
-                            access_token,
-                            from_account_id,
-                            from_child_document_id,
-                            from_document_id,
-                            None,
-                            &mut batch,
-                        )
-                        .caused_by(trc::location!())?;
-                }
-
-                let to_document_id = server
-                    .store()
-                    .assign_document_ids(to_account_id, Collection::ContactCard, 1)
-                    .await
-                    .caused_by(trc::location!())?;
-                new_card.names = vec![new_name];
-                required_space += new_card.size as u64;
-                new_card
-                    .insert(access_token, to_account_id, to_document_id, &mut batch)
-                    .caused_by(trc::location!())?;
-            }
+        // Flush large batches
+        if batch.is_large_batch() {
+            commit_children(
+                server,
+                access_token,
+                to_account_id,
+                std::mem::take(&mut batch),
+                std::mem::take(&mut required_space),
+            )
+            .await?;
+            created_ids.append(&mut pending_ids);
         }
     }
 
-    if from_account_id != to_account_id && required_space > 0 {
+    // Write remaining changes
+    if !batch.is_empty() {
+        commit_children(server, access_token, to_account_id, batch, required_space).await?;
+        created_ids.append(&mut pending_ids);
+    }
+
+    Ok(())
+}
+
+async fn commit_children(
+    server: &Server,
+    access_token: &AccessToken,
+    to_account_id: u32,
+    batch: BatchBuilder,
+    required_space: u64,
+) -> crate::Result<()> {
+    if required_space > 0 {
         server
             .has_available_quota(
                 &server
@@ -902,11 +1022,77 @@ async fn copy_container(
         .await
         .caused_by(trc::location!())?;
 
-    if !is_overwrite {
-        Ok(HttpResponse::new(StatusCode::CREATED))
-    } else {
-        Ok(HttpResponse::new(StatusCode::NO_CONTENT))
+    Ok(())
+}
+
+// Unlinks the children of a book and deletes it, committing size-bounded batches as it goes
+async fn delete_container(
+    server: &Server,
+    access_token: &AccessToken,
+    account_id: u32,
+    document_id: u32,
+    children_ids: Vec<u32>,
+    delete_path: Option<String>,
+) -> trc::Result<()> {
+    let mut batch = BatchBuilder::new();
+    for child_document_id in children_ids {
+        if let Some(card_) = server
+            .get_archive(account_id, Collection::ContactCard, child_document_id)
+            .await
+            .caused_by(trc::location!())?
+        {
+            DestroyArchive(
+                card_
+                    .to_unarchived::<ContactCard>()
+                    .caused_by(trc::location!())?,
+            )
+            .delete(
+                access_token,
+                account_id,
+                child_document_id,
+                document_id,
+                None,
+                &mut batch,
+            )
+            .caused_by(trc::location!())?;
+
+            if batch.is_large_batch() {
+                server
+                    .commit_batch(std::mem::take(&mut batch))
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+        }
+    }
+
+    if let Some(book_) = server
+        .get_archive(account_id, Collection::AddressBook, document_id)
+        .await
+        .caused_by(trc::location!())?
+    {
+        DestroyArchive(
+            book_
+                .to_unarchived::<AddressBook>()
+                .caused_by(trc::location!())?,
+        )
+        .delete(
+            access_token,
+            account_id,
+            document_id,
+            delete_path,
+            &mut batch,
+        )
+        .caused_by(trc::location!())?;
     }
+
+    if !batch.is_empty() {
+        server
+            .commit_batch(batch)
+            .await
+            .caused_by(trc::location!())?;
+    }
+
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -24,6 +24,7 @@ use crate::{
     DavError, DavMethod,
     common::{
         ETag,
+        acl::ResourceAcl,
         lock::{LockRequestHandler, ResourceState},
         uri::DavUriResource,
     },
@@ -85,7 +86,8 @@ impl CardDeleteRequestHandler for Server {
                     .effective_acl(access_token)
                     .contains_all([Acl::Delete, Acl::RemoveItems].into_iter())
             {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(resources
+                    .need_privileges(Some(document_id), vec![Acl::Delete, Acl::RemoveItems]));
             }
 
             // Validate headers
@@ -132,7 +134,7 @@ impl CardDeleteRequestHandler for Server {
                     Acl::RemoveItems,
                 )
             {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(resources.need_privileges(Some(addressbook_id), Acl::RemoveItems));
             }
 
             let card_ = self
@@ -177,6 +179,18 @@ impl CardDeleteRequestHandler for Server {
 
         self.commit_batch(batch).await.caused_by(trc::location!())?;
 
+        trc::event!(
+            Groupware(trc::GroupwareEvent::ContactDeleted),
+            AccountId = account_id,
+            Collection = if delete_resource.is_container() {
+                Collection::AddressBook
+            } else {
+                Collection::ContactCard
+            },
+            DocumentId = document_id,
+            Url = resources.format_resource(delete_resource),
+        );
+
         Ok(HttpResponse::new(StatusCode::NO_CONTENT))
     }
 }
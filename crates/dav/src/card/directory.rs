@@ -0,0 +1,561 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use calcard::vcard::{ArchivedVCard, VCardVersion};
+use common::{
+    Server,
+    auth::{AccessToken, AsTenantId},
+};
+use dav_proto::{
+    RequestHeaders, Return,
+    parser::DavParser,
+    schema::{
+        Namespace,
+        property::{
+            CardDavProperty, DavProperty, DavValue, Privilege, ReportSet, ResourceType,
+            WebDavProperty,
+        },
+        request::{DavPropertyValue, PropFind, Report},
+        response::{BaseCondition, MultiStatus, PropStat, Response},
+    },
+};
+use directory::{Permission, Principal, Type, backend::internal::manage::ManageDirectory};
+use groupware::{cache::GroupwareCache, contact::sync::principal_to_vcard};
+use http_proto::{HttpResponse, request::decode_path_element};
+use hyper::StatusCode;
+use jmap_proto::types::collection::{Collection, SyncCollection};
+use percent_encoding::NON_ALPHANUMERIC;
+use rkyv::util::AlignedVec;
+use trc::AddContext;
+
+use crate::{
+    DavError, DavMethod, DavResourceName, PropStatBuilder,
+    calendar::format::write_jcard,
+    card::query::{serialize_vcard_with_props, vcard_query},
+    common::uri::DavUriResource,
+    principal::CurrentUserPrincipal,
+    request::DavRequestParser,
+};
+
+pub(crate) struct DirectoryGatewayTarget<'x> {
+    pub name: &'x str,
+    pub principal_id: Option<u32>,
+}
+
+pub(crate) trait CardDirectoryRequestHandler: Sync + Send {
+    fn resolve_directory_gateway(
+        &self,
+        access_token: &AccessToken,
+        uri: &str,
+    ) -> impl Future<Output = crate::Result<Option<DirectoryGatewayTarget<'_>>>> + Send;
+
+    fn handle_directory_gateway_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        target: DirectoryGatewayTarget<'_>,
+        method: DavMethod,
+        body: Vec<u8>,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+}
+
+struct DirectoryCard {
+    id: u32,
+    name: String,
+    card: AlignedVec,
+}
+
+const DIRECTORY_TYPES: [Type; 5] = [
+    Type::Individual,
+    Type::Group,
+    Type::List,
+    Type::Resource,
+    Type::Location,
+];
+
+impl CardDirectoryRequestHandler for Server {
+    async fn resolve_directory_gateway(
+        &self,
+        access_token: &AccessToken,
+        uri: &str,
+    ) -> crate::Result<Option<DirectoryGatewayTarget<'_>>> {
+        let Some(gateway) = &self.core.groupware.directory_gateway else {
+            return Ok(None);
+        };
+
+        // The gateway is only exposed under the address book home of the caller
+        let Ok(uri) = self.validate_uri(access_token, uri).await else {
+            return Ok(None);
+        };
+        let (Some(account_id), Some(resource)) = (uri.account_id, uri.resource) else {
+            return Ok(None);
+        };
+        if uri.collection != Collection::AddressBook || account_id != access_token.primary_id() {
+            return Ok(None);
+        }
+        let resource = decode_path_element(resource);
+        let item = if resource == gateway.as_str() {
+            None
+        } else if let Some(item) = resource
+            .strip_prefix(gateway.as_str())
+            .and_then(|path| path.strip_prefix('/'))
+        {
+            Some(item)
+        } else {
+            return Ok(None);
+        };
+
+        // Regular address books take precedence over the gateway
+        if self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::AddressBook)
+            .await
+            .caused_by(trc::location!())?
+            .by_path(gateway)
+            .is_some()
+        {
+            return Ok(None);
+        }
+
+        let principal_id = if let Some(item) = item {
+            Some(
+                item.strip_suffix(".vcf")
+                    .and_then(|id| id.parse::<u32>().ok())
+                    .ok_or(DavError::Code(StatusCode::NOT_FOUND))?,
+            )
+        } else {
+            None
+        };
+
+        Ok(Some(DirectoryGatewayTarget {
+            name: gateway,
+            principal_id,
+        }))
+    }
+
+    async fn handle_directory_gateway_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        target: DirectoryGatewayTarget<'_>,
+        method: DavMethod,
+        body: Vec<u8>,
+    ) -> crate::Result<HttpResponse> {
+        let base_href = format!(
+            "{}/{}/{}/",
+            DavResourceName::Card.base_path(),
+            percent_encoding::utf8_percent_encode(&access_token.name, NON_ALPHANUMERIC),
+            percent_encoding::utf8_percent_encode(target.name, NON_ALPHANUMERIC),
+        );
+
+        match method {
+            DavMethod::PROPFIND => {
+                access_token.assert_has_permission(Permission::DavCardPropFind)?;
+                let request = if !body.is_empty() {
+                    PropFind::parse(&mut self.request_tokenizer(&body))?
+                } else {
+                    PropFind::AllProp(vec![])
+                };
+                let mut response = MultiStatus::new(Vec::with_capacity(1));
+                response.set_namespace(Namespace::CardDav);
+
+                // Directory gateways are not enumerable, cards are only returned by reports
+                if let Some(principal_id) = target.principal_id {
+                    let card = directory_card(self, access_token, principal_id)
+                        .await?
+                        .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+                    response.add_response(card.propstat(
+                        format!("{base_href}{principal_id}.vcf"),
+                        &request,
+                        headers,
+                    ));
+                } else if !headers.depth_no_root {
+                    response.add_response(gateway_propstat(
+                        access_token,
+                        base_href,
+                        target.name,
+                        &request,
+                        headers.ret == Return::Minimal,
+                    ));
+                }
+
+                Ok(HttpResponse::new(StatusCode::MULTI_STATUS).with_xml_body(response.to_string()))
+            }
+            DavMethod::GET | DavMethod::HEAD => {
+                access_token.assert_has_permission(Permission::DavCardGet)?;
+                let principal_id = target
+                    .principal_id
+                    .ok_or(DavError::Code(StatusCode::METHOD_NOT_ALLOWED))?;
+                let card = directory_card(self, access_token, principal_id)
+                    .await?
+                    .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+
+                let mut vcard = String::new();
+                let _ = card.card().write_to(
+                    &mut vcard,
+                    if headers.jcard {
+                        VCardVersion::V4_0
+                    } else {
+                        headers
+                            .max_vcard_version
+                            .or_else(|| card.card().version())
+                            .unwrap_or_default()
+                    },
+                );
+                if headers.jcard {
+                    let mut jcard = String::with_capacity(vcard.len() * 2);
+                    write_jcard(&vcard, &mut jcard);
+                    vcard = jcard;
+                }
+                let response = HttpResponse::new(StatusCode::OK)
+                    .with_content_type(if headers.jcard {
+                        "application/vcard+json; charset=utf-8"
+                    } else {
+                        "text/vcard; charset=utf-8"
+                    })
+                    .with_etag(card.etag());
+
+                if matches!(method, DavMethod::GET) {
+                    Ok(response.with_binary_body(vcard))
+                } else {
+                    Ok(response.with_content_length(vcard.len()))
+                }
+            }
+            DavMethod::REPORT => {
+                if target.principal_id.is_some() {
+                    return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+                }
+                let mut response = MultiStatus::new(Vec::with_capacity(16));
+                response.set_namespace(Namespace::CardDav);
+
+                match Report::parse(&mut self.request_tokenizer(&body))? {
+                    Report::AddressbookQuery(report) => {
+                        access_token.assert_has_permission(Permission::DavCardQuery)?;
+                        let principals = self
+                            .store()
+                            .list_principals(
+                                None,
+                                access_token.tenant_id(),
+                                &DIRECTORY_TYPES,
+                                true,
+                                0,
+                                0,
+                            )
+                            .await
+                            .caused_by(trc::location!())?;
+                        let max_results = report
+                            .limit
+                            .map(|limit| limit as usize)
+                            .unwrap_or(usize::MAX)
+                            .min(self.core.groupware.max_results);
+
+                        let mut matches = 0;
+                        for card in principals.items.iter().filter_map(DirectoryCard::new) {
+                            if !vcard_query(card.card(), &report.filters) {
+                                continue;
+                            }
+                            if matches == max_results {
+                                response.add_response(
+                                    Response::new_status(
+                                        [base_href.as_str()],
+                                        StatusCode::INSUFFICIENT_STORAGE,
+                                    )
+                                    .with_error(BaseCondition::NumberOfMatchesWithinLimit)
+                                    .with_response_description(format!(
+                                        "The number of matches exceeds the limit of {max_results}"
+                                    )),
+                                );
+                                break;
+                            }
+                            response.add_response(card.propstat(
+                                format!("{base_href}{}.vcf", card.id),
+                                &report.properties,
+                                headers,
+                            ));
+                            matches += 1;
+                        }
+                    }
+                    Report::AddressbookMultiGet(report) => {
+                        access_token.assert_has_permission(Permission::DavCardMultiGet)?;
+                        for href in report.hrefs {
+                            let card = if let Some(principal_id) = href
+                                .split_once(base_href.as_str())
+                                .and_then(|(_, item)| item.strip_suffix(".vcf"))
+                                .and_then(|id| id.parse::<u32>().ok())
+                            {
+                                directory_card(self, access_token, principal_id).await?
+                            } else {
+                                None
+                            };
+
+                            if let Some(card) = card {
+                                response.add_response(card.propstat(
+                                    href,
+                                    &report.properties,
+                                    headers,
+                                ));
+                            } else {
+                                response.add_response(Response::new_status(
+                                    [href],
+                                    StatusCode::NOT_FOUND,
+                                ));
+                            }
+                        }
+                    }
+                    _ => return Err(DavError::Code(StatusCode::FORBIDDEN)),
+                }
+
+                Ok(HttpResponse::new(StatusCode::MULTI_STATUS).with_xml_body(response.to_string()))
+            }
+            DavMethod::PUT
+            | DavMethod::POST
+            | DavMethod::PATCH
+            | DavMethod::DELETE
+            | DavMethod::MKCOL
+            | DavMethod::PROPPATCH
+            | DavMethod::COPY
+            | DavMethod::MOVE => Err(DavError::Code(StatusCode::FORBIDDEN)),
+            _ => Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED)),
+        }
+    }
+}
+
+impl DirectoryCard {
+    fn new(principal: &Principal) -> Option<Self> {
+        let (_, vcard) = principal_to_vcard(principal)?;
+        Some(DirectoryCard {
+            id: principal.id,
+            name: principal
+                .description
+                .clone()
+                .unwrap_or_else(|| principal.name.clone()),
+            card: rkyv::to_bytes::<rkyv::rancor::Error>(&vcard).ok()?,
+        })
+    }
+
+    fn card(&self) -> &ArchivedVCard {
+        // SAFETY: The bytes were produced by rkyv from a VCard
+        unsafe { rkyv::access_unchecked::<ArchivedVCard>(&self.card) }
+    }
+
+    fn etag(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.card.as_slice().hash(&mut hasher);
+        format!("\"{:x}\"", hasher.finish())
+    }
+
+    fn propstat(&self, href: String, request: &PropFind, headers: &RequestHeaders<'_>) -> Response {
+        static CARD_PROPS: [DavProperty; 6] = [
+            DavProperty::WebDav(WebDavProperty::DisplayName),
+            DavProperty::WebDav(WebDavProperty::GetETag),
+            DavProperty::WebDav(WebDavProperty::ResourceType),
+            DavProperty::WebDav(WebDavProperty::GetContentLength),
+            DavProperty::WebDav(WebDavProperty::GetContentType),
+            DavProperty::CardDav(CardDavProperty::AddressData(vec![])),
+        ];
+
+        let properties = match request {
+            PropFind::PropName => return empty_propstat(href, &CARD_PROPS),
+            PropFind::AllProp(_) => CARD_PROPS.as_slice(),
+            PropFind::Prop(items) => items.as_slice(),
+        };
+        let version = headers
+            .max_vcard_version
+            .or_else(|| self.card().version())
+            .unwrap_or_default();
+
+        let mut fields = Vec::with_capacity(properties.len());
+        let mut fields_not_found = Vec::new();
+        for property in properties {
+            match property {
+                DavProperty::WebDav(WebDavProperty::DisplayName) => {
+                    fields.push(DavPropertyValue::new(
+                        property.clone(),
+                        DavValue::String(self.name.clone()),
+                    ));
+                }
+                DavProperty::WebDav(WebDavProperty::GetETag) => {
+                    fields.push(DavPropertyValue::new(
+                        property.clone(),
+                        DavValue::String(self.etag()),
+                    ));
+                }
+                DavProperty::WebDav(WebDavProperty::ResourceType) => {
+                    fields.push(DavPropertyValue::empty(property.clone()));
+                }
+                DavProperty::WebDav(WebDavProperty::GetContentLength) => {
+                    let mut vcard = String::new();
+                    let _ = self.card().write_to(&mut vcard, version);
+                    fields.push(DavPropertyValue::new(
+                        property.clone(),
+                        DavValue::Uint64(vcard.len() as u64),
+                    ));
+                }
+                DavProperty::WebDav(WebDavProperty::GetContentType) => {
+                    fields.push(DavPropertyValue::new(
+                        property.clone(),
+                        DavValue::String("text/vcard".to_string()),
+                    ));
+                }
+                DavProperty::CardDav(CardDavProperty::AddressData(items)) => {
+                    let mut vcard = String::new();
+                    if headers.jcard {
+                        serialize_vcard_with_props(
+                            self.card(),
+                            items,
+                            VCardVersion::V4_0.into(),
+                            &mut vcard,
+                        );
+                        let mut jcard = String::with_capacity(vcard.len() * 2);
+                        write_jcard(&vcard, &mut jcard);
+                        vcard = jcard;
+                    } else {
+                        serialize_vcard_with_props(self.card(), items, version.into(), &mut vcard);
+                    }
+                    fields.push(DavPropertyValue::new(
+                        property.clone(),
+                        DavValue::CData(vcard.as_str().into()),
+                    ));
+                }
+                _ => {
+                    fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                }
+            }
+        }
+
+        build_propstat(
+            href,
+            request,
+            headers.ret == Return::Minimal,
+            fields,
+            fields_not_found,
+        )
+    }
+}
+
+async fn directory_card(
+    server: &Server,
+    access_token: &AccessToken,
+    principal_id: u32,
+) -> crate::Result<Option<DirectoryCard>> {
+    Ok(server
+        .store()
+        .get_principal(principal_id)
+        .await
+        .caused_by(trc::location!())?
+        .filter(|principal| principal.tenant() == access_token.tenant_id())
+        .as_ref()
+        .and_then(DirectoryCard::new))
+}
+
+fn gateway_propstat(
+    access_token: &AccessToken,
+    href: String,
+    name: &str,
+    request: &PropFind,
+    is_minimal: bool,
+) -> Response {
+    static GATEWAY_PROPS: [DavProperty; 6] = [
+        DavProperty::WebDav(WebDavProperty::DisplayName),
+        DavProperty::WebDav(WebDavProperty::ResourceType),
+        DavProperty::WebDav(WebDavProperty::CurrentUserPrincipal),
+        DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet),
+        DavProperty::WebDav(WebDavProperty::SupportedReportSet),
+        DavProperty::CardDav(CardDavProperty::SupportedAddressData),
+    ];
+
+    let properties = match request {
+        PropFind::PropName => return empty_propstat(href, &GATEWAY_PROPS),
+        PropFind::AllProp(_) => GATEWAY_PROPS.as_slice(),
+        PropFind::Prop(items) => items.as_slice(),
+    };
+
+    let mut fields = Vec::with_capacity(properties.len());
+    let mut fields_not_found = Vec::new();
+    for property in properties {
+        match property {
+            DavProperty::WebDav(WebDavProperty::DisplayName) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::String(name.to_string()),
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::ResourceType) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    vec![
+                        ResourceType::Collection,
+                        ResourceType::AddressBook,
+                        ResourceType::Directory,
+                    ],
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::CurrentUserPrincipal) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    vec![access_token.current_user_principal()],
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    vec![Privilege::Read],
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::SupportedReportSet) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    vec![ReportSet::AddressbookQuery, ReportSet::AddressbookMultiGet],
+                ));
+            }
+            DavProperty::CardDav(CardDavProperty::SupportedAddressData) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::SupportedAddressData,
+                ));
+            }
+            _ => {
+                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+            }
+        }
+    }
+
+    build_propstat(href, request, is_minimal, fields, fields_not_found)
+}
+
+pub(crate) fn empty_propstat(href: String, properties: &[DavProperty]) -> Response {
+    Response::new_propstat(
+        href,
+        vec![PropStat::new_list(
+            properties
+                .iter()
+                .cloned()
+                .map(DavPropertyValue::empty)
+                .collect(),
+        )],
+    )
+}
+
+pub(crate) fn build_propstat(
+    href: String,
+    request: &PropFind,
+    is_minimal: bool,
+    fields: Vec<DavPropertyValue>,
+    fields_not_found: Vec<DavPropertyValue>,
+) -> Response {
+    let mut prop_stat = PropStatBuilder::default()
+        .with_minimal(is_minimal || matches!(request, PropFind::AllProp(_)));
+    prop_stat
+        .insert_list_with_status(fields, StatusCode::OK)
+        .insert_list_with_status(fields_not_found, StatusCode::NOT_FOUND);
+    let mut prop_stat = prop_stat.build();
+    if prop_stat.is_empty() {
+        prop_stat.push(PropStat::new_list(vec![]));
+    }
+
+    Response::new_propstat(href, prop_stat)
+}
@@ -4,6 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+use calcard::vcard::VCardVersion;
 use common::{Server, auth::AccessToken};
 use dav_proto::{RequestHeaders, schema::property::Rfc1123DateTime};
 use groupware::{cache::GroupwareCache, contact::ContactCard};
@@ -17,8 +18,10 @@ use trc::AddContext;
 
 use crate::{
     DavError, DavMethod,
+    calendar::format::write_jcard,
     common::{
         ETag,
+        acl::ResourceAcl,
         lock::{LockRequestHandler, ResourceState},
         uri::DavUriResource,
     },
@@ -69,7 +72,9 @@ impl CardGetRequestHandler for Server {
                 Acl::ReadItems,
             )
         {
-            return Err(DavError::Code(StatusCode::FORBIDDEN));
+            return Err(
+                resources.need_privileges(Some(resource.parent_id().unwrap()), Acl::ReadItems)
+            );
         }
 
         // Fetch card
@@ -101,18 +106,32 @@ impl CardGetRequestHandler for Server {
         .await?;
 
         let response = HttpResponse::new(StatusCode::OK)
-            .with_content_type("text/vcard; charset=utf-8")
+            .with_content_type(if headers.jcard {
+                "application/vcard+json; charset=utf-8"
+            } else {
+                "text/vcard; charset=utf-8"
+            })
             .with_etag(etag)
             .with_last_modified(Rfc1123DateTime::new(i64::from(card.modified)).to_string());
 
-        let mut vcard = String::with_capacity(128);
+        // jCard represents vCard 4.0 data (RFC 7095)
+        let mut vcard = String::with_capacity(card.size.to_native() as usize);
         let _ = card.card.write_to(
             &mut vcard,
-            headers
-                .max_vcard_version
-                .or_else(|| card.card.version())
-                .unwrap_or_default(),
+            if headers.jcard {
+                VCardVersion::V4_0
+            } else {
+                headers
+                    .max_vcard_version
+                    .or_else(|| card.card.version())
+                    .unwrap_or_default()
+            },
         );
+        if headers.jcard {
+            let mut jcard = String::with_capacity(vcard.len() * 2);
+            write_jcard(&vcard, &mut jcard);
+            vcard = jcard;
+        }
 
         if !is_head {
             Ok(response.with_binary_body(vcard))
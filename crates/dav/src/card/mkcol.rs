@@ -10,7 +10,7 @@ use crate::{
     common::{
         ExtractETag,
         lock::{LockRequestHandler, ResourceState},
-        uri::DavUriResource,
+        uri::{DavUriResource, validate_resource_name},
     },
 };
 use common::{Server, auth::AccessToken};
@@ -52,16 +52,15 @@ impl CardMkColRequestHandler for Server {
             .ok_or(DavError::Code(StatusCode::FORBIDDEN))?;
         if !access_token.is_member(account_id) {
             return Err(DavError::Code(StatusCode::FORBIDDEN));
-        } else if name.contains('/')
-            || self
-                .fetch_dav_resources(access_token, account_id, SyncCollection::AddressBook)
-                .await
-                .caused_by(trc::location!())?
-                .by_path(name)
-                .is_some()
-        {
+        }
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::AddressBook)
+            .await
+            .caused_by(trc::location!())?;
+        if name.contains('/') || resources.by_path(name).is_some() {
             return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
         }
+        validate_resource_name(name)?;
 
         // Validate headers
         self.validate_headers(
@@ -91,7 +90,7 @@ impl CardMkColRequestHandler for Server {
             let mut prop_stat = PropStatBuilder::default();
             if !self.apply_addressbook_properties(&mut book, false, mkcol.props, &mut prop_stat) {
                 return Ok(HttpResponse::new(StatusCode::FORBIDDEN).with_xml_body(
-                    MkColResponse::new(prop_stat.build())
+                    MkColResponse::new(prop_stat.with_failed_dependencies().build())
                         .with_namespace(Namespace::CardDav)
                         .to_string(),
                 ));
@@ -113,6 +112,14 @@ impl CardMkColRequestHandler for Server {
         let etag = batch.etag();
         self.commit_batch(batch).await.caused_by(trc::location!())?;
 
+        trc::event!(
+            Groupware(trc::GroupwareEvent::ContactCreated),
+            AccountId = account_id,
+            Collection = Collection::AddressBook,
+            DocumentId = document_id,
+            Url = resources.format_collection(name),
+        );
+
         if let Some(prop_stat) = return_prop_stat {
             Ok(HttpResponse::new(StatusCode::CREATED)
                 .with_xml_body(
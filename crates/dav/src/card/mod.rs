@@ -19,6 +19,7 @@ use crate::{DavError, DavErrorCondition};
 
 pub mod copy_move;
 pub mod delete;
+pub mod directory;
 pub mod get;
 pub mod mkcol;
 pub mod proppatch;
@@ -80,6 +81,7 @@ pub(crate) async fn assert_is_unique_uid(
     account_id: u32,
     addressbook_id: u32,
     uid: Option<&str>,
+    replaced_id: Option<u32>,
 ) -> crate::Result<()> {
     if let Some(uid) = uid {
         let hits = server
@@ -93,7 +95,10 @@ pub(crate) async fn assert_is_unique_uid(
             .caused_by(trc::location!())?;
         if !hits.results.is_empty() {
             for path in resources.children(addressbook_id) {
-                if hits.results.contains(path.document_id()) {
+                // The resource being overwritten does not conflict
+                if hits.results.contains(path.document_id())
+                    && replaced_id != Some(path.document_id())
+                {
                     return Err(DavError::Condition(DavErrorCondition::new(
                         StatusCode::PRECONDITION_FAILED,
                         CardCondition::NoUidConflict(resources.format_resource(path).into()),
@@ -31,6 +31,7 @@ use crate::{
     DavError, DavMethod, PropStatBuilder,
     common::{
         ETag, ExtractETag,
+        acl::ResourceAcl,
         lock::{LockRequestHandler, ResourceState},
         uri::DavUriResource,
     },
@@ -103,7 +104,7 @@ impl CardPropPatchRequestHandler for Server {
             };
 
             if !resources.has_access_to_container(access_token, document_id, acl) {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(resources.need_privileges(Some(document_id), acl));
             }
         }
 
@@ -211,13 +212,18 @@ impl CardPropPatchRequestHandler for Server {
         }
 
         if headers.ret != Return::Minimal || !is_success {
-            Ok(HttpResponse::new(StatusCode::MULTI_STATUS)
+            let response = HttpResponse::new(StatusCode::MULTI_STATUS)
                 .with_xml_body(
                     MultiStatus::new(vec![Response::new_propstat(uri, items.build())])
                         .with_namespace(Namespace::CardDav)
                         .to_string(),
                 )
-                .with_etag_opt(etag))
+                .with_etag_opt(etag);
+            if headers.ret == Return::Representation {
+                Ok(response.with_header("Preference-Applied", "return=representation"))
+            } else {
+                Ok(response)
+            }
         } else {
             Ok(HttpResponse::new(StatusCode::NO_CONTENT).with_etag_opt(etag))
         }
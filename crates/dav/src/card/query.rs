@@ -9,14 +9,17 @@ use crate::{
     common::{
         AddressbookFilter, DavQuery,
         propfind::{PropFindItem, PropFindRequestHandler},
-        uri::DavUriResource,
+        uri::{DavUriResource, Urn},
     },
 };
 use calcard::vcard::{
     ArchivedVCard, ArchivedVCardEntry, ArchivedVCardParameter, VCardParameterName, VCardProperty,
     VCardVersion,
 };
-use common::{Server, auth::AccessToken};
+use common::{
+    IDX_CARD_EMAIL, IDX_CARD_FN, IDX_CARD_ORG, IDX_CARD_TEL, IDX_CARD_TEXT, Server,
+    auth::AccessToken,
+};
 use dav_proto::{
     RequestHeaders,
     schema::{
@@ -24,11 +27,15 @@ use dav_proto::{
         request::{AddressbookQuery, Filter, FilterOp, VCardPropertyWithGroup},
     },
 };
-use groupware::cache::GroupwareCache;
+use groupware::{cache::GroupwareCache, contact::index::CARD_TEXT_INDEX_VERSION};
 use http_proto::HttpResponse;
 use hyper::StatusCode;
-use jmap_proto::types::{acl::Acl, collection::SyncCollection};
+use jmap_proto::types::{
+    acl::Acl,
+    collection::{Collection, SyncCollection},
+};
 use std::fmt::Write;
+use store::{query, roaring::RoaringBitmap};
 use trc::AddContext;
 
 pub(crate) trait CardQueryRequestHandler: Sync + Send {
@@ -37,6 +44,7 @@ pub(crate) trait CardQueryRequestHandler: Sync + Send {
         access_token: &AccessToken,
         headers: &RequestHeaders<'_>,
         request: AddressbookQuery,
+        query: Option<&str>,
     ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
 }
 
@@ -46,7 +54,23 @@ impl CardQueryRequestHandler for Server {
         access_token: &AccessToken,
         headers: &RequestHeaders<'_>,
         request: AddressbookQuery,
+        query: Option<&str>,
     ) -> crate::Result<HttpResponse> {
+        // Continuation token of a previous truncated response
+        let after_id = if let Some(token) = query
+            .unwrap_or_default()
+            .split('&')
+            .find_map(|param| param.strip_prefix("page="))
+        {
+            Some(
+                Urn::parse(token)
+                    .and_then(|urn| urn.try_unwrap_query_page())
+                    .ok_or(DavError::Code(StatusCode::BAD_REQUEST))?,
+            )
+        } else {
+            None
+        };
+
         // Validate URI
         let resource_ = self
             .validate_uri(access_token, headers.uri)
@@ -77,12 +101,22 @@ impl CardQueryRequestHandler for Server {
             None
         };
 
+        // Narrow down the cards to scan using the text index
+        let candidate_ids = self
+            .card_query_candidates(account_id, &request.filters)
+            .await
+            .caused_by(trc::location!())?;
+
         // Obtain document ids in folder
         let mut items = Vec::with_capacity(16);
         for resource in resources.children(resource.document_id()) {
             if shared_ids
                 .as_ref()
                 .is_none_or(|ids| ids.contains(resource.document_id()))
+                && candidate_ids
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(resource.document_id()))
+                && after_id.is_none_or(|after_id| resource.document_id() > after_id)
             {
                 items.push(PropFindItem::new(
                     resources.format_resource(resource),
@@ -92,6 +126,9 @@ impl CardQueryRequestHandler for Server {
             }
         }
 
+        // Results are returned in a stable order so they can be paged
+        items.sort_unstable_by_key(|item| item.document_id);
+
         self.handle_dav_query(
             access_token,
             DavQuery::addressbook_query(request, items, headers),
@@ -100,6 +137,83 @@ impl CardQueryRequestHandler for Server {
     }
 }
 
+trait CardQueryCandidates: Sync + Send {
+    fn card_query_candidates(
+        &self,
+        account_id: u32,
+        filters: &AddressbookFilter,
+    ) -> impl Future<Output = trc::Result<Option<RoaringBitmap>>> + Send;
+}
+
+impl CardQueryCandidates for Server {
+    // Returns the cards that may match the text-match filters on indexed
+    // properties, cards that were not indexed are always included.
+    async fn card_query_candidates(
+        &self,
+        account_id: u32,
+        filters: &AddressbookFilter,
+    ) -> trc::Result<Option<RoaringBitmap>> {
+        let mut is_all = true;
+        let mut has_unindexed = false;
+        let mut conditions = Vec::new();
+
+        for filter in filters {
+            match filter {
+                Filter::AnyOf => {
+                    is_all = false;
+                }
+                Filter::AllOf => {
+                    is_all = true;
+                }
+                Filter::Property {
+                    prop,
+                    op: FilterOp::TextMatch(text_match),
+                    ..
+                } if !text_match.negate => {
+                    let field = match prop.name {
+                        VCardProperty::Fn => IDX_CARD_FN,
+                        VCardProperty::Email => IDX_CARD_EMAIL,
+                        VCardProperty::Tel => IDX_CARD_TEL,
+                        VCardProperty::Org => IDX_CARD_ORG,
+                        _ => {
+                            has_unindexed = true;
+                            continue;
+                        }
+                    };
+                    conditions.push(query::Filter::contains(field, &text_match.value));
+                }
+                _ => {
+                    has_unindexed = true;
+                }
+            }
+        }
+
+        if conditions.is_empty() || (!is_all && has_unindexed) {
+            return Ok(None);
+        }
+
+        let mut store_filters = Vec::with_capacity(conditions.len() + 6);
+        store_filters.extend([
+            query::Filter::Or,
+            query::Filter::Not,
+            query::Filter::eq(IDX_CARD_TEXT, vec![CARD_TEXT_INDEX_VERSION]),
+            query::Filter::End,
+            if is_all {
+                query::Filter::And
+            } else {
+                query::Filter::Or
+            },
+        ]);
+        store_filters.extend(conditions);
+        store_filters.extend([query::Filter::End, query::Filter::End]);
+
+        self.store()
+            .filter(account_id, Collection::ContactCard, store_filters)
+            .await
+            .map(|result_set| Some(result_set.results))
+    }
+}
+
 pub(crate) fn vcard_query(card: &ArchivedVCard, filters: &AddressbookFilter) -> bool {
     let mut is_all = true;
     let mut matches_one = false;
@@ -202,29 +316,27 @@ pub(crate) fn serialize_vcard_with_props(
     card: &ArchivedVCard,
     props: &[CardDavPropertyName],
     version: Option<VCardVersion>,
-) -> String {
-    let mut vcard = String::with_capacity(128);
+    out: &mut impl Write,
+) {
     let version = version.or_else(|| card.version()).unwrap_or_default();
     if !props.is_empty() {
-        let _ = write!(&mut vcard, "BEGIN:VCARD\r\n");
+        let _ = write!(out, "BEGIN:VCARD\r\n");
         let is_v4 = matches!(version, VCardVersion::V4_0);
 
         for entry in card.entries.iter() {
             for item in props {
                 if entry.name == item.name && entry.group == item.group {
                     if item.name != VCardProperty::Version {
-                        let _ = entry.write_to(&mut vcard, !item.no_value, is_v4);
+                        let _ = entry.write_to(out, !item.no_value, is_v4);
                     } else {
-                        let _ = write!(&mut vcard, "VERSION:{version}\r\n");
+                        let _ = write!(out, "VERSION:{version}\r\n");
                     }
                     break;
                 }
             }
         }
-        let _ = write!(&mut vcard, "END:VCARD\r\n");
+        let _ = write!(out, "END:VCARD\r\n");
     } else {
-        let _ = card.write_to(&mut vcard, version);
+        let _ = card.write_to(out, version);
     }
-
-    vcard
 }
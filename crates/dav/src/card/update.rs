@@ -10,7 +10,11 @@ use dav_proto::{
     RequestHeaders, Return,
     schema::{property::Rfc1123DateTime, response::CardCondition},
 };
-use groupware::{cache::GroupwareCache, contact::ContactCard};
+use groupware::{
+    cache::GroupwareCache,
+    contact::ContactCard,
+    icap::{ContentInspection, ContentKind, InspectionResult},
+};
 use http_proto::HttpResponse;
 use hyper::StatusCode;
 use jmap_proto::types::{
@@ -22,10 +26,14 @@ use trc::AddContext;
 
 use crate::{
     DavError, DavErrorCondition, DavMethod,
+    calendar::format::parse_jcard,
     common::{
         ETag, ExtractETag,
+        acl::ResourceAcl,
+        charset::{CharsetError, decode_text_body},
+        limits::ItemLimitCheck,
         lock::{LockRequestHandler, ResourceState},
-        uri::DavUriResource,
+        uri::{DavUriResource, validate_resource_name},
     },
     file::DavFileResource,
 };
@@ -70,22 +78,51 @@ impl CardUpdateRequestHandler for Server {
                 CardCondition::MaxResourceSize(self.core.groupware.max_vcard_size as u32),
             )));
         }
-        let vcard_raw = std::str::from_utf8(&bytes).map_err(|_| {
+
+        // Inspect content
+        if self
+            .inspect_content(
+                access_token,
+                ContentKind::Contact,
+                resource_name,
+                headers.content_type,
+                &bytes,
+            )
+            .await
+            != InspectionResult::Allowed
+        {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+        let vcard_decoded = decode_text_body(&bytes, headers.content_type).map_err(|err| {
             DavError::Condition(DavErrorCondition::new(
                 StatusCode::PRECONDITION_FAILED,
-                CardCondition::SupportedAddressData,
+                match err {
+                    CharsetError::Unsupported => CardCondition::SupportedAddressData,
+                    CharsetError::Undecodable => CardCondition::ValidAddressData,
+                },
             ))
         })?;
+        let vcard_raw = vcard_decoded.as_ref();
 
-        let vcard = match Parser::new(vcard_raw).strict().entry() {
-            Entry::VCard(vcard) => vcard,
-            _ => {
-                return Err(DavError::Condition(DavErrorCondition::new(
-                    StatusCode::PRECONDITION_FAILED,
-                    CardCondition::SupportedAddressData,
-                )));
+        // jCard bodies are parsed into the same vCard object as text bodies
+        let vcard = if headers.content_type.is_some_and(|content_type| {
+            content_type
+                .split_once(';')
+                .map_or(content_type, |(ct, _)| ct)
+                .trim()
+                .eq_ignore_ascii_case("application/vcard+json")
+        }) {
+            parse_jcard(vcard_raw)
+        } else {
+            match Parser::new(vcard_raw).strict().entry() {
+                Entry::VCard(vcard) => Some(vcard),
+                _ => None,
             }
-        };
+        }
+        .ok_or(DavError::Condition(DavErrorCondition::new(
+            StatusCode::PRECONDITION_FAILED,
+            CardCondition::SupportedAddressData,
+        )))?;
 
         if let Some(resource) = resources.by_path(resource_name) {
             if resource.is_container() {
@@ -98,7 +135,7 @@ impl CardUpdateRequestHandler for Server {
             if !access_token.is_member(account_id)
                 && !resources.has_access_to_container(access_token, parent_id, Acl::ModifyItems)
             {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(resources.need_privileges(Some(parent_id), Acl::ModifyItems));
             }
 
             // Update
@@ -159,7 +196,19 @@ impl CardUpdateRequestHandler for Server {
             // Validate UID
             match (card.inner.card.uid(), vcard.uid()) {
                 (Some(old_uid), Some(new_uid)) if old_uid == new_uid => {}
-                (None, None) | (None, Some(_)) => {}
+                (None, None) => {}
+                (None, Some(new_uid)) => {
+                    // Cards gaining a UID must not clash with the rest of the address book
+                    assert_is_unique_uid(
+                        self,
+                        &resources,
+                        account_id,
+                        parent_id,
+                        Some(new_uid),
+                        Some(document_id),
+                    )
+                    .await?;
+                }
                 _ => {
                     return Err(DavError::Condition(DavErrorCondition::new(
                         StatusCode::PRECONDITION_FAILED,
@@ -174,6 +223,8 @@ impl CardUpdateRequestHandler for Server {
                 .caused_by(trc::location!())?;
             new_card.size = bytes.len() as u32;
             new_card.card = vcard;
+            let representation =
+                (headers.ret == Return::Representation).then(|| new_card.card.to_string());
 
             // Prepare write batch
             let mut batch = BatchBuilder::new();
@@ -183,11 +234,20 @@ impl CardUpdateRequestHandler for Server {
                 .etag();
             self.commit_batch(batch).await.caused_by(trc::location!())?;
 
-            Ok(HttpResponse::new(StatusCode::NO_CONTENT).with_etag_opt(etag))
+            trc::event!(
+                Groupware(trc::GroupwareEvent::ContactUpdated),
+                AccountId = account_id,
+                Collection = Collection::ContactCard,
+                DocumentId = document_id,
+                Url = resources.format_item(resource_name),
+            );
+
+            Ok(put_response(StatusCode::NO_CONTENT, representation).with_etag_opt(etag))
         } else if let Some((Some(parent), name)) = resources.map_parent(resource_name) {
             if !parent.is_container() {
                 return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
             }
+            validate_resource_name(name)?;
 
             // Validate ACL
             if !access_token.is_member(account_id)
@@ -197,7 +257,7 @@ impl CardUpdateRequestHandler for Server {
                     Acl::AddItems,
                 )
             {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(resources.need_privileges(Some(parent.document_id()), Acl::AddItems));
             }
 
             // Validate headers
@@ -216,6 +276,16 @@ impl CardUpdateRequestHandler for Server {
             )
             .await?;
 
+            // Validate item limit
+            self.assert_item_limit(
+                access_token,
+                &resources,
+                account_id,
+                Collection::ContactCard,
+                parent.document_id().into(),
+            )
+            .await?;
+
             // Validate quota
             if !bytes.is_empty() {
                 self.has_available_quota(
@@ -232,6 +302,7 @@ impl CardUpdateRequestHandler for Server {
                 account_id,
                 parent.document_id(),
                 vcard.uid(),
+                None,
             )
             .await?;
 
@@ -245,6 +316,8 @@ impl CardUpdateRequestHandler for Server {
                 size: bytes.len() as u32,
                 ..Default::default()
             };
+            let representation =
+                (headers.ret == Return::Representation).then(|| card.card.to_string());
 
             // Prepare write batch
             let mut batch = BatchBuilder::new();
@@ -259,9 +332,33 @@ impl CardUpdateRequestHandler for Server {
                 .etag();
             self.commit_batch(batch).await.caused_by(trc::location!())?;
 
-            Ok(HttpResponse::new(StatusCode::CREATED).with_etag_opt(etag))
+            trc::event!(
+                Groupware(trc::GroupwareEvent::ContactCreated),
+                AccountId = account_id,
+                Collection = Collection::ContactCard,
+                DocumentId = document_id,
+                Url = resources.format_item(resource_name),
+            );
+
+            Ok(put_response(StatusCode::CREATED, representation).with_etag_opt(etag))
         } else {
             Err(DavError::Code(StatusCode::CONFLICT))?
         }
     }
 }
+
+// Returns the stored card when the client asked for it (RFC 7240)
+fn put_response(status: StatusCode, representation: Option<String>) -> HttpResponse {
+    if let Some(representation) = representation {
+        HttpResponse::new(if status == StatusCode::NO_CONTENT {
+            StatusCode::OK
+        } else {
+            status
+        })
+        .with_content_type("text/vcard; charset=utf-8")
+        .with_header("Preference-Applied", "return=representation")
+        .with_binary_body(representation)
+    } else {
+        HttpResponse::new(status)
+    }
+}
@@ -8,13 +8,13 @@ use crate::{
     DavError, DavErrorCondition, DavResourceName, common::uri::DavUriResource,
     principal::propfind::PrincipalPropFind,
 };
-use common::{DavResources, Server, auth::AccessToken, sharing::EffectiveAcl};
+use common::{DavResourcePath, DavResources, Server, auth::AccessToken, sharing::EffectiveAcl};
 use dav_proto::{
-    RequestHeaders,
+    Depth, RequestHeaders,
     schema::{
         property::{DavProperty, Privilege, WebDavProperty},
         request::{AclPrincipalPropSet, PropFind},
-        response::{Ace, BaseCondition, GrantDeny, Href, MultiStatus, Principal},
+        response::{Ace, BaseCondition, GrantDeny, Href, List, MultiStatus, Principal, Resource},
     },
 };
 use directory::{QueryBy, Type, backend::internal::manage::ManageDirectory};
@@ -28,7 +28,7 @@ use jmap_proto::types::{
 };
 use percent_encoding::NON_ALPHANUMERIC;
 use rkyv::vec::ArchivedVec;
-use store::{ahash::AHashSet, roaring::RoaringBitmap, write::BatchBuilder};
+use store::{roaring::RoaringBitmap, write::BatchBuilder};
 use trc::AddContext;
 use utils::map::bitmap::Bitmap;
 
@@ -73,6 +73,12 @@ pub(crate) trait ResourceAcl {
         parent_id: Option<u32>,
         check_acls: impl Into<Bitmap<Acl>> + Send,
     ) -> crate::Result<u32>;
+
+    fn need_privileges(
+        &self,
+        document_id: Option<u32>,
+        check_acls: impl Into<Bitmap<Acl>> + Send,
+    ) -> DavError;
 }
 
 impl DavAclHandler for Server {
@@ -123,7 +129,10 @@ impl DavAclHandler for Server {
         if !access_token.is_member(account_id)
             && !acls.effective_acl(access_token).contains(Acl::Administer)
         {
-            return Err(DavError::Code(StatusCode::FORBIDDEN));
+            return Err(need_privileges(
+                resources.format_resource(resource),
+                Acl::Administer,
+            ));
         }
 
         // Validate ACEs
@@ -196,6 +205,11 @@ impl DavAclHandler for Server {
         headers: &RequestHeaders<'_>,
         mut request: AclPrincipalPropSet,
     ) -> crate::Result<HttpResponse> {
+        // The report is only defined for a depth of zero (RFC 3744)
+        if matches!(headers.depth, Depth::One | Depth::Infinity) {
+            return Err(DavError::Code(StatusCode::BAD_REQUEST));
+        }
+
         let uri = self
             .validate_uri(access_token, headers.uri)
             .await
@@ -241,11 +255,11 @@ impl DavAclHandler for Server {
             _ => unreachable!(),
         };
 
-        // Validate ACLs
+        // Listing the principals in the ACL requires the read-acl privilege
         if !access_token.is_member(uri.account_id)
-            && !acls.effective_acl(access_token).contains(Acl::Read)
+            && !acls.effective_acl(access_token).contains(Acl::Administer)
         {
-            return Err(DavError::Code(StatusCode::FORBIDDEN));
+            return Err(need_privileges(headers.uri.to_string(), Acl::Administer));
         }
 
         // Validate
@@ -315,6 +329,7 @@ impl DavAclHandler for Server {
                     Privilege::Write => {
                         acls.insert(Acl::Modify);
                         acls.insert(Acl::Delete);
+                        acls.insert(Acl::AddItems);
                         acls.insert(Acl::ModifyItems);
                         acls.insert(Acl::RemoveItems);
                     }
@@ -325,10 +340,13 @@ impl DavAclHandler for Server {
                     Privilege::WriteProperties => {
                         acls.insert(Acl::Modify);
                     }
-                    Privilege::ReadCurrentUserPrivilegeSet
-                    | Privilege::Unlock
-                    | Privilege::Bind
-                    | Privilege::Unbind => {}
+                    Privilege::Bind => {
+                        acls.insert(Acl::AddItems);
+                    }
+                    Privilege::Unbind => {
+                        acls.insert(Acl::RemoveItems);
+                    }
+                    Privilege::ReadCurrentUserPrivilegeSet | Privilege::Unlock => {}
                     Privilege::All => {
                         return Err(DavError::Condition(DavErrorCondition::new(
                             StatusCode::FORBIDDEN,
@@ -392,10 +410,18 @@ impl DavAclHandler for Server {
                 )));
             }
 
-            grants.push(AclGrant {
-                account_id: principal_id,
-                grants: acls,
-            });
+            // Multiple ACEs for the same principal are combined
+            if let Some(grant) = grants
+                .iter_mut()
+                .find(|grant| grant.account_id == principal_id)
+            {
+                grant.grants.union(&acls);
+            } else {
+                grants.push(AclGrant {
+                    account_id: principal_id,
+                    grants: acls,
+                });
+            }
         }
 
         Ok(grants)
@@ -444,9 +470,10 @@ impl DavAclHandler for Server {
 
                 aces.push(Ace::new(
                     principal,
-                    GrantDeny::grant(current_user_privilege_set(Bitmap::<Acl>::from(
-                        &grant.grants,
-                    ))),
+                    GrantDeny::grant(current_user_privilege_set(
+                        Bitmap::<Acl>::from(&grant.grants),
+                        true,
+                    )),
                 ));
             }
         }
@@ -465,21 +492,88 @@ impl ResourceAcl for DavResources {
     ) -> crate::Result<u32> {
         match parent_id {
             Some(parent_id) => {
+                let check_acls: Bitmap<Acl> = check_acls.into();
                 if is_member || self.has_access_to_container(access_token, parent_id, check_acls) {
                     Ok(parent_id + 1)
                 } else {
-                    Err(DavError::Code(StatusCode::FORBIDDEN))
+                    Err(self.need_privileges(Some(parent_id), check_acls))
                 }
             }
             None => {
                 if is_member {
                     Ok(0)
                 } else {
-                    Err(DavError::Code(StatusCode::FORBIDDEN))
+                    Err(self.need_privileges(None, check_acls))
                 }
             }
         }
     }
+
+    fn need_privileges(
+        &self,
+        document_id: Option<u32>,
+        check_acls: impl Into<Bitmap<Acl>> + Send,
+    ) -> DavError {
+        let href = document_id
+            .and_then(|document_id| {
+                self.container_by_id(document_id).or_else(|| {
+                    self.paths.iter().find_map(|path| {
+                        let resource = &self.resources[path.resource_idx];
+                        (resource.document_id == document_id)
+                            .then_some(DavResourcePath { path, resource })
+                    })
+                })
+            })
+            .map(|resource| self.format_resource(resource))
+            .unwrap_or_else(|| self.format_item(""));
+
+        need_privileges(href, check_acls)
+    }
+}
+
+/// DAV:need-privileges error (RFC 3744, section 7.1.1) listing the
+/// privileges that map to the missing ACLs.
+pub(crate) fn need_privileges(href: String, check_acls: impl Into<Bitmap<Acl>>) -> DavError {
+    DavErrorCondition::new(
+        StatusCode::FORBIDDEN,
+        need_privileges_condition(href, check_acls),
+    )
+    .into()
+}
+
+pub(crate) fn need_privileges_condition(
+    href: String,
+    check_acls: impl Into<Bitmap<Acl>>,
+) -> BaseCondition {
+    let mut privileges = Vec::with_capacity(4);
+    for acl in check_acls.into() {
+        let privilege = match acl {
+            Acl::Read | Acl::ReadItems => Privilege::Read,
+            Acl::Modify => Privilege::WriteProperties,
+            Acl::ModifyItems => Privilege::WriteContent,
+            Acl::AddItems => Privilege::Bind,
+            Acl::Delete | Acl::RemoveItems => Privilege::Unbind,
+            Acl::Administer => Privilege::WriteAcl,
+            Acl::ReadFreeBusy => Privilege::ReadFreeBusy,
+            _ => continue,
+        };
+        if !privileges.contains(&privilege) {
+            privileges.push(privilege);
+        }
+    }
+    if privileges.is_empty() {
+        privileges.push(Privilege::All);
+    }
+
+    BaseCondition::NeedPrivileges(List(
+        privileges
+            .into_iter()
+            .map(|privilege| Resource {
+                href: Href(href.clone()),
+                privilege,
+            })
+            .collect(),
+    ))
 }
 
 pub(crate) trait Privileges {
@@ -488,6 +582,7 @@ pub(crate) trait Privileges {
         account_id: u32,
         grants: &ArchivedVec<ArchivedAclGrant>,
         is_calendar: bool,
+        is_container: bool,
     ) -> Vec<Privilege>;
 }
 
@@ -497,41 +592,95 @@ impl Privileges for AccessToken {
         account_id: u32,
         grants: &ArchivedVec<ArchivedAclGrant>,
         is_calendar: bool,
+        is_container: bool,
     ) -> Vec<Privilege> {
-        if self.is_member(account_id) {
+        if !self.is_member(account_id) {
+            current_user_privilege_set(grants.effective_acl(self), is_container)
+        } else if is_container {
             Privilege::all(is_calendar)
         } else {
-            current_user_privilege_set(grants.effective_acl(self))
+            current_user_privilege_set(Bitmap::all(), false)
         }
     }
 }
 
-pub(crate) fn current_user_privilege_set(acl_bitmap: Bitmap<Acl>) -> Vec<Privilege> {
-    let mut acls = AHashSet::with_capacity(16);
-    for grant in acl_bitmap {
-        match grant {
-            Acl::Read | Acl::ReadItems => {
-                acls.insert(Privilege::Read);
-                acls.insert(Privilege::ReadCurrentUserPrivilegeSet);
-            }
-            Acl::Modify => {
-                acls.insert(Privilege::WriteProperties);
-            }
-            Acl::ModifyItems => {
-                acls.insert(Privilege::WriteContent);
-            }
-            Acl::Delete | Acl::RemoveItems => {
-                acls.insert(Privilege::Write);
-            }
-            Acl::Administer => {
-                acls.insert(Privilege::ReadAcl);
-                acls.insert(Privilege::WriteAcl);
-            }
-            Acl::ReadFreeBusy => {
-                acls.insert(Privilege::ReadFreeBusy);
-            }
-            _ => {}
+pub(crate) fn current_user_privilege_set(
+    acl_bitmap: Bitmap<Acl>,
+    is_container: bool,
+) -> Vec<Privilege> {
+    let mut privileges = Vec::with_capacity(12);
+    if acl_bitmap.contains(Acl::Read) || acl_bitmap.contains(Acl::ReadItems) {
+        privileges.push(Privilege::Read);
+        privileges.push(Privilege::ReadCurrentUserPrivilegeSet);
+    }
+    if is_container {
+        // DAV:write aggregates the privileges to modify the collection and its members
+        if acl_bitmap.contains_all(
+            [
+                Acl::Modify,
+                Acl::AddItems,
+                Acl::ModifyItems,
+                Acl::RemoveItems,
+            ]
+            .into_iter(),
+        ) {
+            privileges.push(Privilege::Write);
+        }
+        if acl_bitmap.contains(Acl::Modify) {
+            privileges.push(Privilege::WriteProperties);
         }
+        if acl_bitmap.contains(Acl::ModifyItems) {
+            privileges.push(Privilege::WriteContent);
+        }
+        if acl_bitmap.contains(Acl::AddItems) {
+            privileges.push(Privilege::Bind);
+        }
+        if acl_bitmap.contains(Acl::RemoveItems) {
+            privileges.push(Privilege::Unbind);
+        }
+        if acl_bitmap.contains(Acl::ReadFreeBusy) {
+            privileges.push(Privilege::ReadFreeBusy);
+        }
+    } else if acl_bitmap.contains(Acl::Modify) {
+        // Files are written with the permission to modify the node itself
+        privileges.push(Privilege::Write);
+        privileges.push(Privilege::WriteProperties);
+        privileges.push(Privilege::WriteContent);
+        privileges.push(Privilege::Unlock);
+    }
+    if acl_bitmap.contains(Acl::Administer) {
+        privileges.push(Privilege::ReadAcl);
+        privileges.push(Privilege::WriteAcl);
     }
-    acls.into_iter().collect()
+    privileges
+}
+
+// Members of a calendar or address book do not have an ACL of their own, their
+// privileges derive from the item permissions granted on the container.
+pub(crate) fn current_user_item_privilege_set(container_acl: Bitmap<Acl>) -> Vec<Privilege> {
+    let mut privileges = Vec::with_capacity(6);
+    if container_acl.contains(Acl::ReadItems) {
+        privileges.push(Privilege::Read);
+        privileges.push(Privilege::ReadCurrentUserPrivilegeSet);
+    }
+    if container_acl.contains(Acl::ModifyItems) {
+        privileges.push(Privilege::Write);
+        privileges.push(Privilege::WriteProperties);
+        privileges.push(Privilege::WriteContent);
+        privileges.push(Privilege::Unlock);
+    }
+    privileges
+}
+
+// Removes the privileges to modify members of collections managed by the server
+pub(crate) fn read_only_privilege_set(privileges: &mut Vec<Privilege>, allow_unbind: bool) {
+    privileges.retain(|privilege| match privilege {
+        Privilege::All
+        | Privilege::Write
+        | Privilege::WriteContent
+        | Privilege::Unlock
+        | Privilege::Bind => false,
+        Privilege::Unbind => allow_unbind,
+        _ => true,
+    });
 }
@@ -0,0 +1,82 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{Server, auth::AccessToken};
+use dav_proto::RequestHeaders;
+use groupware::{DavResourceName, cache::GroupwareCache, member_name};
+use hyper::StatusCode;
+use jmap_proto::types::collection::SyncCollection;
+use trc::AddContext;
+
+use crate::{DavError, common::uri::DavUriResource};
+
+pub(crate) trait AddMemberRequestHandler: Sync + Send {
+    /// Returns the URI of a new member of the collection addressed by the
+    /// request, with a name chosen by the server (RFC 5995).
+    fn add_member_uri(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        resource: DavResourceName,
+    ) -> impl Future<Output = crate::Result<String>> + Send;
+}
+
+impl AddMemberRequestHandler for Server {
+    async fn add_member_uri(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        resource: DavResourceName,
+    ) -> crate::Result<String> {
+        // Validate URI
+        let resource_ = self
+            .validate_uri(access_token, headers.uri)
+            .await?
+            .into_owned_uri()?;
+        let account_id = resource_.account_id;
+        let resources = self
+            .fetch_dav_resources(
+                access_token,
+                account_id,
+                SyncCollection::from(resource_.collection),
+            )
+            .await
+            .caused_by(trc::location!())?;
+        let extension = match resource {
+            DavResourceName::Cal => ".ics",
+            DavResourceName::Card => ".vcf",
+            DavResourceName::File => "",
+            DavResourceName::Principal => {
+                return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+            }
+        };
+
+        // Members can be added to any collection except calendar and address book
+        // homes and scheduling collections
+        let parent_path = if let Some(name) = resource_.resource {
+            let parent = resources
+                .by_path(name)
+                .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+            if !parent.is_container()
+                || parent.schedule_collection().is_some()
+                || parent.is_birthday_calendar()
+            {
+                return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+            }
+            parent.path()
+        } else if resource == DavResourceName::File {
+            ""
+        } else {
+            return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+        };
+
+        Ok(format!(
+            "{}/{}",
+            headers.uri.trim_end_matches('/'),
+            member_name(&resources, parent_path, extension)
+        ))
+    }
+}
@@ -0,0 +1,107 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use common::{Server, auth::AccessToken, config::groupware::ApnsEnvironment};
+use dav_proto::schema::{property::PushTransport, response::Href};
+use groupware::{
+    DavResourceName,
+    apns::{ApnsPush, apns_push_key},
+};
+use http_proto::{HttpRequest, HttpResponse, HttpSessionData, request::fetch_body};
+use hyper::StatusCode;
+
+pub const APNS_SUBSCRIPTION_PATH: &str = "/dav/apns";
+
+pub trait DavApnsHandler: Sync + Send {
+    fn handle_apns_subscription(
+        &self,
+        req: HttpRequest,
+        access_token: Arc<AccessToken>,
+        session: &HttpSessionData,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl DavApnsHandler for Server {
+    async fn handle_apns_subscription(
+        &self,
+        mut req: HttpRequest,
+        access_token: Arc<AccessToken>,
+        session: &HttpSessionData,
+    ) -> trc::Result<HttpResponse> {
+        if self.core.groupware.apns.is_none() {
+            return Ok(HttpResponse::new(StatusCode::NOT_FOUND));
+        }
+
+        // Clients post the device token along with the push key of the home
+        let body = fetch_body(&mut req, 4096, session.session_id)
+            .await
+            .ok_or_else(|| trc::LimitEvent::SizeRequest.into_err())?;
+        let mut token = None;
+        let mut key = None;
+        for (name, value) in http_proto::form_urlencoded::parse(&body) {
+            match name.as_ref() {
+                "token" => token = Some(value.into_owned()),
+                "key" => key = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+        let (Some(token), Some(key)) = (
+            token.filter(|token| {
+                !token.is_empty()
+                    && token.len() <= 200
+                    && token.chars().all(|ch| ch.is_ascii_hexdigit())
+            }),
+            key,
+        ) else {
+            return Err(trc::ResourceEvent::BadParameters.into_err());
+        };
+
+        if self
+            .register_apns_device(&access_token, &key, &token)
+            .await?
+        {
+            Ok(HttpResponse::new(StatusCode::OK))
+        } else {
+            Ok(HttpResponse::new(StatusCode::FORBIDDEN))
+        }
+    }
+}
+
+/// Returns the push transports advertised on calendar and address book
+/// homes, or None when APNs is not configured for the service.
+pub(crate) fn apns_push_transports(
+    server: &Server,
+    service: DavResourceName,
+) -> Option<Vec<PushTransport>> {
+    let apns = server.core.groupware.apns.as_ref()?;
+    let topic = match service {
+        DavResourceName::Cal => apns.calendar_topic.as_ref()?,
+        DavResourceName::Card => apns.contacts_topic.as_ref()?,
+        _ => return None,
+    };
+
+    Some(vec![PushTransport {
+        subscription_url: Href(APNS_SUBSCRIPTION_PATH.to_string()),
+        topic: topic.clone(),
+        environment: match apns.environment {
+            ApnsEnvironment::Production => "PRODUCTION",
+            ApnsEnvironment::Sandbox => "SANDBOX",
+        }
+        .to_string(),
+        refresh_interval: apns.refresh_interval.as_secs(),
+    }])
+}
+
+/// Returns the push key of the home an account's collections belong to.
+pub(crate) fn apns_push_key_for(
+    server: &Server,
+    service: DavResourceName,
+    account_id: u32,
+) -> Option<String> {
+    apns_push_transports(server, service).map(|_| apns_push_key(service, account_id))
+}
@@ -0,0 +1,124 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Windows1252,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharsetError {
+    Unsupported,
+    Undecodable,
+}
+
+// Windows-1252 code points for bytes 0x80-0x9F, zero marks undefined bytes
+static WINDOWS_1252: [u16; 32] = [
+    0x20AC, 0x0000, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160, 0x2039,
+    0x0152, 0x0000, 0x017D, 0x0000, 0x0000, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014,
+    0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x0000, 0x017E, 0x0178,
+];
+
+impl Charset {
+    pub fn parse(value: &str) -> Option<Self> {
+        hashify::tiny_map_ignore_case!(value.trim().trim_matches('"').as_bytes(),
+            "utf-8" => Charset::Utf8,
+            "utf8" => Charset::Utf8,
+            "us-ascii" => Charset::Utf8,
+            "ascii" => Charset::Utf8,
+            "utf-16" => Charset::Utf16Be,
+            "utf-16be" => Charset::Utf16Be,
+            "utf-16le" => Charset::Utf16Le,
+            "iso-8859-1" => Charset::Latin1,
+            "iso8859-1" => Charset::Latin1,
+            "iso_8859-1" => Charset::Latin1,
+            "latin1" => Charset::Latin1,
+            "l1" => Charset::Latin1,
+            "windows-1252" => Charset::Windows1252,
+            "cp1252" => Charset::Windows1252,
+        )
+    }
+
+    pub fn from_content_type(content_type: &str) -> Result<Option<Self>, CharsetError> {
+        for param in content_type.split(';').skip(1) {
+            if let Some((name, value)) = param.split_once('=') {
+                if name.trim().eq_ignore_ascii_case("charset") {
+                    return Charset::parse(value)
+                        .map(Some)
+                        .ok_or(CharsetError::Unsupported);
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Decodes a text request body to UTF-8. A byte order mark takes precedence
+/// over the charset parameter, and bodies without either must be UTF-8.
+pub fn decode_text_body<'x>(
+    bytes: &'x [u8],
+    content_type: Option<&str>,
+) -> Result<Cow<'x, str>, CharsetError> {
+    let (charset, bytes) = if let Some(bytes) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        (Charset::Utf8, bytes)
+    } else if let Some(bytes) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        (Charset::Utf16Le, bytes)
+    } else if let Some(bytes) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        (Charset::Utf16Be, bytes)
+    } else {
+        (
+            content_type
+                .map(Charset::from_content_type)
+                .transpose()?
+                .flatten()
+                .unwrap_or(Charset::Utf8),
+            bytes,
+        )
+    };
+
+    match charset {
+        Charset::Utf8 => std::str::from_utf8(bytes)
+            .map(Cow::Borrowed)
+            .map_err(|_| CharsetError::Undecodable),
+        Charset::Utf16Le | Charset::Utf16Be => {
+            let units = bytes.chunks_exact(2);
+            if !units.remainder().is_empty() {
+                return Err(CharsetError::Undecodable);
+            }
+            let units = units.map(|unit| {
+                if charset == Charset::Utf16Le {
+                    u16::from_le_bytes([unit[0], unit[1]])
+                } else {
+                    u16::from_be_bytes([unit[0], unit[1]])
+                }
+            });
+            char::decode_utf16(units)
+                .collect::<Result<String, _>>()
+                .map(Cow::Owned)
+                .map_err(|_| CharsetError::Undecodable)
+        }
+        Charset::Latin1 => Ok(Cow::Owned(bytes.iter().map(|&ch| ch as char).collect())),
+        Charset::Windows1252 => bytes
+            .iter()
+            .map(|&ch| match ch {
+                0x80..=0x9F => match WINDOWS_1252[(ch - 0x80) as usize] {
+                    0 => None,
+                    ch => char::from_u32(ch as u32),
+                },
+                ch => Some(ch as char),
+            })
+            .collect::<Option<String>>()
+            .map(Cow::Owned)
+            .ok_or(CharsetError::Undecodable),
+    }
+}
@@ -0,0 +1,285 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use http_proto::{HttpResponse, HttpResponseBody};
+use hyper::{StatusCode, header};
+use std::io::{Read, Write};
+
+// Variants are ordered by preference when clients assign equal q-values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ContentEncoding {
+    Deflate,
+    Gzip,
+    Brotli,
+}
+
+impl ContentEncoding {
+    pub fn parse(value: &str) -> Option<Self> {
+        hashify::tiny_map_ignore_case!(value.trim().as_bytes(),
+            "gzip" => ContentEncoding::Gzip,
+            "x-gzip" => ContentEncoding::Gzip,
+            "deflate" => ContentEncoding::Deflate,
+            "br" => ContentEncoding::Brotli,
+        )
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Deflate => "deflate",
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Brotli => "br",
+        }
+    }
+
+    /// Picks the preferred encoding from an Accept-Encoding header value,
+    /// honouring q-values and favouring brotli over gzip over deflate on ties.
+    /// The `*` wildcard only applies to encodings not listed explicitly.
+    pub fn negotiate(accept_encoding: &str) -> Option<Self> {
+        let mut best: Option<(ContentEncoding, u32)> = None;
+        let mut wildcard_q = None;
+        let mut listed = [false; 3];
+
+        for item in accept_encoding.split(',') {
+            let mut parts = item.split(';');
+            let name = parts.next().unwrap_or_default().trim();
+            let q = parts
+                .find_map(|param| {
+                    param
+                        .trim()
+                        .strip_prefix("q=")
+                        .or_else(|| param.trim().strip_prefix("Q="))
+                })
+                .map(parse_qvalue)
+                .unwrap_or(1000);
+
+            if name == "*" {
+                wildcard_q = Some(q);
+            } else if let Some(encoding) = ContentEncoding::parse(name) {
+                listed[encoding as usize] = true;
+                if q > 0 && best.is_none_or(|(prev, prev_q)| (q, encoding) > (prev_q, prev)) {
+                    best = Some((encoding, q));
+                }
+            }
+        }
+
+        if let Some(q) = wildcard_q.filter(|q| *q > 0) {
+            for encoding in [
+                ContentEncoding::Deflate,
+                ContentEncoding::Gzip,
+                ContentEncoding::Brotli,
+            ] {
+                if !listed[encoding as usize]
+                    && best.is_none_or(|(prev, prev_q)| (q, encoding) > (prev_q, prev))
+                {
+                    best = Some((encoding, q));
+                }
+            }
+        }
+
+        best.map(|(encoding, _)| encoding)
+    }
+
+    pub fn encode(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len() / 4);
+        match self {
+            ContentEncoding::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(&mut out, flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()?;
+            }
+            ContentEncoding::Deflate => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(&mut out, flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()?;
+            }
+            ContentEncoding::Brotli => {
+                let mut encoder = brotli::CompressorWriter::new(&mut out, 4096, 5, 22);
+                encoder.write_all(data)?;
+                // Finalizes the brotli stream
+                encoder.into_inner();
+            }
+        }
+        Ok(out)
+    }
+
+    /// Decodes a compressed request body. Returns `Ok(None)` if the decoded
+    /// body exceeds `max_size` (a value of zero disables the limit).
+    pub fn decode(&self, data: &[u8], max_size: usize) -> std::io::Result<Option<Vec<u8>>> {
+        let limit = if max_size > 0 {
+            max_size as u64 + 1
+        } else {
+            u64::MAX
+        };
+        // Decoding stops as soon as the output exceeds the limit
+        let capacity = data.len().saturating_mul(4);
+        let mut out = Vec::with_capacity(if max_size > 0 {
+            capacity.min(max_size)
+        } else {
+            capacity
+        });
+        match self {
+            ContentEncoding::Gzip => {
+                flate2::read::MultiGzDecoder::new(data)
+                    .take(limit)
+                    .read_to_end(&mut out)?;
+            }
+            ContentEncoding::Deflate => {
+                flate2::read::ZlibDecoder::new(data)
+                    .take(limit)
+                    .read_to_end(&mut out)?;
+            }
+            ContentEncoding::Brotli => {
+                brotli::Decompressor::new(data, 4096)
+                    .take(limit)
+                    .read_to_end(&mut out)?;
+            }
+        }
+
+        Ok(if max_size == 0 || out.len() <= max_size {
+            Some(out)
+        } else {
+            None
+        })
+    }
+}
+
+// Bodies at least this large are compressed on the blocking thread pool
+const BLOCKING_COMPRESS_SIZE: usize = 128 * 1024;
+
+pub(crate) trait CompressResponse {
+    fn compress(
+        self,
+        encoding: Option<ContentEncoding>,
+        min_size: usize,
+    ) -> impl Future<Output = Self> + Send;
+}
+
+impl CompressResponse for HttpResponse {
+    async fn compress(self, encoding: Option<ContentEncoding>, min_size: usize) -> Self {
+        // Byte ranges refer to the unencoded representation
+        if self.size() < min_size.max(1)
+            || self.status() == StatusCode::PARTIAL_CONTENT
+            || !self
+                .headers()
+                .and_then(|headers| headers.get(header::CONTENT_TYPE))
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(is_compressible)
+            || self
+                .headers()
+                .is_some_and(|headers| headers.contains_key(header::CONTENT_ENCODING))
+        {
+            return self;
+        }
+
+        // From here on the representation depends on Accept-Encoding, so
+        // caches are told even when the body is sent uncompressed
+        let Some(encoding) = encoding else {
+            return self.with_header(header::VARY, "Accept-Encoding");
+        };
+
+        if self.size() < BLOCKING_COMPRESS_SIZE {
+            encode_response(self, encoding)
+        } else {
+            tokio::task::spawn_blocking(move || encode_response(self, encoding))
+                .await
+                .unwrap_or_else(|_| HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+fn encode_response(response: HttpResponse, encoding: ContentEncoding) -> HttpResponse {
+    let encoded = match response.body() {
+        HttpResponseBody::Text(body) => encoding.encode(body.as_bytes()),
+        HttpResponseBody::Binary(body) => encoding.encode(body),
+        _ => return response.with_header(header::VARY, "Accept-Encoding"),
+    };
+
+    match encoded {
+        Ok(body) if body.len() < response.size() => {
+            response.with_encoded_body(encoding.as_str(), body)
+        }
+        _ => response.with_header(header::VARY, "Accept-Encoding"),
+    }
+}
+
+fn is_compressible(content_type: &str) -> bool {
+    let content_type = content_type
+        .split_once(';')
+        .map_or(content_type, |(ct, _)| ct)
+        .trim();
+
+    content_type.ends_with("/xml")
+        || content_type.ends_with("+xml")
+        || content_type.eq_ignore_ascii_case("text/calendar")
+        || content_type.eq_ignore_ascii_case("application/calendar+json")
+        || content_type.eq_ignore_ascii_case("text/vcard")
+        || content_type.eq_ignore_ascii_case("text/x-vcard")
+}
+
+fn parse_qvalue(value: &str) -> u32 {
+    let value = value.trim();
+    let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+    let int = match int {
+        "1" => 1000,
+        "0" | "" => 0,
+        _ => return 0,
+    };
+    let frac = frac
+        .bytes()
+        .take(3)
+        .chain(std::iter::repeat(b'0'))
+        .take(3)
+        .try_fold(0u32, |acc, ch| {
+            ch.is_ascii_digit().then(|| acc * 10 + (ch - b'0') as u32)
+        })
+        .unwrap_or(0);
+
+    (int + frac).min(1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ContentEncoding;
+
+    #[test]
+    fn negotiate_content_encoding() {
+        for (accept_encoding, expected) in [
+            // q-values
+            ("gzip", Some(ContentEncoding::Gzip)),
+            ("gzip;q=0.5, deflate", Some(ContentEncoding::Deflate)),
+            ("br;q=0.2, gzip;q=0.8", Some(ContentEncoding::Gzip)),
+            ("br;Q=0.9, gzip;q=0.899", Some(ContentEncoding::Brotli)),
+            ("gzip;q=0, deflate;q=0", None),
+            ("compress, identity", None),
+            ("", None),
+            // Ties favour brotli over gzip over deflate
+            ("deflate, gzip", Some(ContentEncoding::Gzip)),
+            ("deflate, gzip, br", Some(ContentEncoding::Brotli)),
+            ("x-gzip;q=0.5, deflate;q=0.5", Some(ContentEncoding::Gzip)),
+            // identity;q=0 does not prevent compression
+            ("identity;q=0, gzip", Some(ContentEncoding::Gzip)),
+            ("identity;q=0, *", Some(ContentEncoding::Brotli)),
+            ("identity;q=0, *;q=0", None),
+            // The wildcard only applies to encodings not listed explicitly
+            ("*", Some(ContentEncoding::Brotli)),
+            ("br;q=0, *", Some(ContentEncoding::Gzip)),
+            ("br;q=0, gzip;q=0, *", Some(ContentEncoding::Deflate)),
+            ("br;q=0, gzip;q=0, deflate;q=0, *", None),
+            ("gzip;q=0.5, *", Some(ContentEncoding::Brotli)),
+            ("br;q=0.5, *;q=0.4", Some(ContentEncoding::Brotli)),
+            ("br;q=0.3, *;q=0.4", Some(ContentEncoding::Gzip)),
+            ("gzip, *;q=0", Some(ContentEncoding::Gzip)),
+        ] {
+            assert_eq!(
+                ContentEncoding::negotiate(accept_encoding),
+                expected,
+                "{accept_encoding:?}"
+            );
+        }
+    }
+}
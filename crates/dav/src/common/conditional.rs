@@ -0,0 +1,119 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{
+    propfind::ShardedSyncChanges,
+    uri::{DavUriResource, Urn},
+};
+use common::{Server, auth::AccessToken};
+use dav_proto::RequestHeaders;
+use groupware::cache::GroupwareCache;
+use http_proto::HttpResponse;
+use hyper::StatusCode;
+use jmap_proto::types::{
+    acl::Acl,
+    collection::{Collection, SyncCollection},
+};
+use trc::AddContext;
+
+pub(crate) trait ReportNotModifiedHandler: Sync + Send {
+    fn handle_report_not_modified(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        collection: Collection,
+    ) -> impl Future<Output = crate::Result<Option<HttpResponse>>> + Send;
+}
+
+impl ReportNotModifiedHandler for Server {
+    async fn handle_report_not_modified(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        collection: Collection,
+    ) -> crate::Result<Option<HttpResponse>> {
+        // Clients may send their last collection ETag (getctag) or sync token
+        // in If-None-Match, in which case the query is only evaluated when the
+        // collection changed since. Entity tags listed in the If header are
+        // state preconditions rather than validators and are not considered.
+        let mut tags = headers
+            .if_none_match
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty() && *tag != "*")
+            .peekable();
+        if tags.peek().is_none() {
+            return Ok(None);
+        }
+
+        // Resolve the target collection
+        let uri = self.validate_uri(access_token, headers.uri).await?;
+        let (Some(account_id), Some(path)) = (uri.account_id, uri.resource) else {
+            return Ok(None);
+        };
+        let sync_collection = SyncCollection::from(collection);
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, sync_collection)
+            .await
+            .caused_by(trc::location!())?;
+        let Some(container_id) = resources
+            .by_path(path)
+            .filter(|resource| resource.is_container())
+            .map(|resource| resource.document_id())
+        else {
+            return Ok(None);
+        };
+
+        // Only readers learn whether the collection changed, everyone else
+        // gets the response of the report handler
+        if !access_token.is_member(account_id)
+            && !resources.has_access_to_container(access_token, container_id, Acl::ReadItems)
+        {
+            return Ok(None);
+        }
+
+        // Both the ctag and the sync token encode the change id they were
+        // issued at, the collection is unchanged if its own log has no
+        // entries since then
+        let Some(change_id) = tags
+            .filter_map(|tag| {
+                let tag = tag.strip_prefix("W/").unwrap_or(tag).trim_matches('"');
+                match Urn::parse(tag) {
+                    Some(Urn::Sync { id, epoch }) if epoch == resources.sync_epoch => Some(id),
+                    Some(_) => None,
+                    None => tag.parse::<u64>().ok(),
+                }
+            })
+            .filter(|change_id| *change_id <= resources.highest_change_id)
+            .max()
+        else {
+            return Ok(None);
+        };
+        if change_id < resources.highest_change_id
+            && !self
+                .sync_changes(
+                    account_id,
+                    sync_collection,
+                    Some(container_id),
+                    change_id,
+                    resources.highest_change_id,
+                )
+                .await
+                .caused_by(trc::location!())?
+                .changes
+                .is_empty()
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            HttpResponse::new(StatusCode::NOT_MODIFIED)
+                .with_etag(format!("\"{}\"", resources.highest_change_id))
+                .with_no_cache(),
+        ))
+    }
+}
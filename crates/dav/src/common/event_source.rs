@@ -0,0 +1,243 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use common::{LONG_1D_SLUMBER, Server, auth::AccessToken};
+use groupware::{DavResourceName, cache::GroupwareCache};
+use http_body_util::{StreamBody, combinators::BoxBody};
+use http_proto::{HttpRequest, HttpResponse};
+use hyper::{
+    StatusCode,
+    body::{Bytes, Frame},
+};
+use jmap_proto::types::{collection::SyncCollection, type_state::DataType};
+use percent_encoding::NON_ALPHANUMERIC;
+use store::ahash::AHashMap;
+use utils::map::{bitmap::Bitmap, vec_map::VecMap};
+
+use super::uri::Urn;
+
+pub trait DavEventSourceHandler: Sync + Send {
+    fn handle_dav_event_source(
+        &self,
+        req: HttpRequest,
+        access_token: Arc<AccessToken>,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl DavEventSourceHandler for Server {
+    async fn handle_dav_event_source(
+        &self,
+        req: HttpRequest,
+        access_token: Arc<AccessToken>,
+    ) -> trc::Result<HttpResponse> {
+        // Parse query
+        let mut ping = 0;
+        let mut services = Vec::new();
+        for (key, value) in
+            http_proto::form_urlencoded::parse(req.uri().query().unwrap_or_default().as_bytes())
+        {
+            match key.as_ref() {
+                "types" => {
+                    for service in value.split(',') {
+                        if service == "*" {
+                            services = vec![
+                                DavResourceName::Cal,
+                                DavResourceName::Card,
+                                DavResourceName::File,
+                            ];
+                            break;
+                        } else if let Some(service) = DavResourceName::parse(service)
+                            .filter(|service| *service != DavResourceName::Principal)
+                        {
+                            services.push(service);
+                        } else {
+                            return Err(trc::ResourceEvent::BadParameters.into_err());
+                        }
+                    }
+                }
+                "ping" => match value.parse::<u32>() {
+                    Ok(value) => {
+                        ping = value;
+                    }
+                    Err(_) => return Err(trc::ResourceEvent::BadParameters.into_err()),
+                },
+                _ => {}
+            }
+        }
+        if services.is_empty() {
+            services = vec![
+                DavResourceName::Cal,
+                DavResourceName::Card,
+                DavResourceName::File,
+            ];
+        }
+
+        // Only subscribe to the services enabled for this account
+        let mut types = Bitmap::default();
+        for service in services {
+            if service.is_enabled(&access_token) {
+                for data_type in service_data_types(service) {
+                    types.insert(*data_type);
+                }
+            }
+        }
+        if types.is_empty() {
+            return Err(trc::SecurityEvent::Unauthorized
+                .into_err()
+                .details("No DAV services are enabled for this account"));
+        }
+
+        let ping = if ping > 0 {
+            #[cfg(not(feature = "test_mode"))]
+            let interval = std::cmp::max(ping, 30) * 1000;
+            #[cfg(feature = "test_mode")]
+            let interval = ping * 1000;
+
+            Some((
+                Duration::from_millis(interval as u64),
+                Bytes::from(format!(
+                    "event: ping\ndata: {{\"interval\": {}}}\n\n",
+                    interval
+                )),
+            ))
+        } else {
+            None
+        };
+        let throttle = self.core.groupware.event_source_throttle;
+
+        // Register with state manager
+        let mut change_rx = self
+            .subscribe_state_manager(access_token.primary_id(), types)
+            .await?;
+        let server = self.clone();
+
+        Ok(HttpResponse::new(StatusCode::OK)
+            .with_content_type("text/event-stream")
+            .with_cache_control("no-store")
+            .with_stream_body(BoxBody::new(StreamBody::new(async_stream::stream! {
+                let mut names: AHashMap<u32, String> = AHashMap::new();
+                let mut pending: VecMap<String, (u64, u64)> = VecMap::new();
+                let mut last_message = Instant::now() - throttle;
+                let mut last_ping = Instant::now();
+                let mut timeout =
+                    ping.as_ref().map(|(interval, _)| *interval).unwrap_or(LONG_1D_SLUMBER);
+
+                loop {
+                    match tokio::time::timeout(timeout, change_rx.recv()).await {
+                        Ok(Some(state_change)) => {
+                            // Resolve the home collection of the account that changed
+                            let account_id = state_change.account_id;
+                            if !names.contains_key(&account_id) {
+                                let name = server
+                                    .store()
+                                    .get_principal_name(account_id)
+                                    .await
+                                    .ok()
+                                    .flatten()
+                                    .unwrap_or_else(|| format!("_{account_id}"));
+                                names.insert(account_id, name);
+                            }
+                            let name = &names[&account_id];
+
+                            for service in [
+                                DavResourceName::Cal,
+                                DavResourceName::Card,
+                                DavResourceName::File,
+                            ] {
+                                if service_data_types(service)
+                                    .iter()
+                                    .any(|data_type| state_change.types.contains(*data_type))
+                                {
+                                    let sync_collection = match service {
+                                        DavResourceName::Cal => SyncCollection::Calendar,
+                                        DavResourceName::Card => SyncCollection::AddressBook,
+                                        _ => SyncCollection::FileNode,
+                                    };
+                                    let epoch = match server
+                                        .fetch_dav_resources(
+                                            &access_token,
+                                            account_id,
+                                            sync_collection,
+                                        )
+                                        .await
+                                    {
+                                        Ok(resources) => resources.sync_epoch,
+                                        Err(err) => {
+                                            trc::error!(
+                                                err.ctx(trc::Key::AccountId, account_id)
+                                                    .ctx(trc::Key::CausedBy, trc::location!())
+                                            );
+                                            continue;
+                                        }
+                                    };
+                                    pending.set(
+                                        format!(
+                                            "{}/{}/",
+                                            service.base_path(),
+                                            percent_encoding::utf8_percent_encode(
+                                                name,
+                                                NON_ALPHANUMERIC
+                                            ),
+                                        ),
+                                        (state_change.change_id, epoch),
+                                    );
+                                }
+                            }
+                        }
+                        Ok(None) => {
+                            break;
+                        }
+                        Err(_) => (),
+                    }
+
+                    timeout = if !pending.is_empty() {
+                        let elapsed = last_message.elapsed();
+                        if elapsed >= throttle {
+                            last_message = Instant::now();
+                            for (href, (change_id, epoch)) in pending.iter() {
+                                yield Ok(Frame::data(Bytes::from(format!(
+                                    "event: sync\ndata: {}\n\n",
+                                    serde_json::json!({
+                                        "href": href,
+                                        "syncToken": Urn::Sync { id: *change_id, epoch: *epoch }.to_string(),
+                                    })
+                                ))));
+                            }
+                            pending.clear();
+                            ping.as_ref().map(|(interval, _)| *interval).unwrap_or(LONG_1D_SLUMBER)
+                        } else {
+                            throttle - elapsed
+                        }
+                    } else if let Some((interval, payload)) = &ping {
+                        let elapsed = last_ping.elapsed();
+                        if elapsed >= *interval {
+                            last_ping = Instant::now();
+                            yield Ok(Frame::data(payload.clone()));
+                            *interval
+                        } else {
+                            *interval - elapsed
+                        }
+                    } else {
+                        LONG_1D_SLUMBER
+                    };
+                }
+            }))))
+    }
+}
+
+fn service_data_types(service: DavResourceName) -> &'static [DataType] {
+    match service {
+        DavResourceName::Cal => &[DataType::Calendar, DataType::CalendarEvent],
+        DavResourceName::Card => &[DataType::AddressBook, DataType::ContactCard],
+        DavResourceName::File => &[DataType::FileNode],
+        DavResourceName::Principal => &[],
+    }
+}
@@ -0,0 +1,145 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{DavResourcePath, DavResources, Server, auth::AccessToken};
+use dav_proto::schema::response::BaseCondition;
+use hyper::StatusCode;
+use jmap_proto::types::{collection::Collection, property::Property};
+use store::{ValueKey, write::ValueClass};
+use trc::AddContext;
+
+use crate::{DavError, DavErrorCondition};
+
+pub(crate) trait ItemLimitCheck: Sync + Send {
+    fn assert_item_limit(
+        &self,
+        access_token: &AccessToken,
+        resources: &DavResources,
+        account_id: u32,
+        collection: Collection,
+        parent_id: Option<u32>,
+    ) -> impl Future<Output = crate::Result<()>> + Send;
+}
+
+pub(crate) trait CollectionQuotaCheck: Sync + Send {
+    fn collection_quota(
+        &self,
+        account_id: u32,
+        collection: Collection,
+        document_id: u32,
+    ) -> impl Future<Output = trc::Result<Option<u64>>> + Send;
+
+    fn assert_collection_quota(
+        &self,
+        resources: &DavResources,
+        account_id: u32,
+        collection: Collection,
+        container_id: Option<u32>,
+        extra_bytes: u64,
+    ) -> impl Future<Output = crate::Result<()>> + Send;
+}
+
+impl ItemLimitCheck for Server {
+    async fn assert_item_limit(
+        &self,
+        access_token: &AccessToken,
+        resources: &DavResources,
+        account_id: u32,
+        collection: Collection,
+        parent_id: Option<u32>,
+    ) -> crate::Result<()> {
+        // Limits apply to the owner of the collection, not to the requester
+        let tenant_id = if !self.core.groupware.item_limits_tenants.is_empty() {
+            self.get_resource_token(access_token, account_id)
+                .await?
+                .tenant
+                .map(|tenant| tenant.id)
+        } else {
+            None
+        };
+        let limits = self.core.groupware.item_limits(account_id, tenant_id);
+        let limit = match collection {
+            Collection::CalendarEvent => limits.events_per_calendar,
+            Collection::ContactCard => limits.cards_per_addressbook,
+            _ => limits.children_per_folder,
+        };
+
+        if let Some(limit) = limit {
+            let members = resources
+                .paths
+                .iter()
+                .filter(|path| path.parent_id == parent_id)
+                .count();
+            if members >= limit {
+                return Err(DavError::Condition(DavErrorCondition::new(
+                    StatusCode::PRECONDITION_FAILED,
+                    BaseCondition::MaxCollectionMembers(limit as u32),
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CollectionQuotaCheck for Server {
+    async fn collection_quota(
+        &self,
+        account_id: u32,
+        collection: Collection,
+        document_id: u32,
+    ) -> trc::Result<Option<u64>> {
+        self.store()
+            .get_value::<u64>(ValueKey {
+                account_id,
+                collection: collection.into(),
+                document_id,
+                class: ValueClass::Property(Property::Quota.into()),
+            })
+            .await
+            .caused_by(trc::location!())
+    }
+
+    async fn assert_collection_quota(
+        &self,
+        resources: &DavResources,
+        account_id: u32,
+        collection: Collection,
+        container_id: Option<u32>,
+        extra_bytes: u64,
+    ) -> crate::Result<()> {
+        if extra_bytes == 0 {
+            return Ok(());
+        }
+
+        // Folder quotas also cover the contents of their subfolders
+        let mut container_id = container_id;
+        while let Some(container) = container_id.and_then(|id| resources.container_by_id(id)) {
+            let document_id = container.document_id();
+            if let Some(quota) = self
+                .collection_quota(account_id, collection, document_id)
+                .await?
+            {
+                if collection_usage(resources, container) + extra_bytes > quota {
+                    return Err(DavError::Condition(DavErrorCondition::new(
+                        StatusCode::PRECONDITION_FAILED,
+                        BaseCondition::QuotaNotExceeded,
+                    )));
+                }
+            }
+            container_id = container.parent_id();
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn collection_usage(resources: &DavResources, container: DavResourcePath<'_>) -> u64 {
+    resources
+        .subtree(container.path())
+        .map(|resource| resource.size() as u64)
+        .sum()
+}
@@ -6,6 +6,7 @@
 
 use common::KV_LOCK_DAV;
 use common::{Server, auth::AccessToken};
+use dav_proto::parser::header::etag_matches;
 use dav_proto::schema::property::{ActiveLock, LockScope, WebDavProperty};
 use dav_proto::schema::request::{DavPropertyValue, DeadProperty};
 use dav_proto::schema::response::{BaseCondition, List, PropResponse};
@@ -15,7 +16,7 @@ use dav_proto::{RequestHeaders, schema::request::LockInfo};
 use groupware::cache::GroupwareCache;
 use http_proto::HttpResponse;
 use hyper::StatusCode;
-use jmap_proto::types::collection::Collection;
+use jmap_proto::types::{acl::Acl, collection::Collection};
 use std::collections::HashMap;
 use store::dispatch::lookup::KeyValue;
 use store::write::serialize::rkyv_deserialize;
@@ -24,6 +25,8 @@ use store::{Serialize, U32_LEN};
 use trc::AddContext;
 
 use super::ETag;
+use super::acl::need_privileges;
+use super::propfind::SyncTokenUrn;
 use super::uri::{DavUriResource, OwnedUri, UriResource, Urn};
 use crate::{DavError, DavErrorCondition, DavMethod};
 
@@ -114,7 +117,7 @@ impl LockRequestHandler for Server {
             .ok_or(DavError::Code(StatusCode::CONFLICT))?;
         let account_id = resource.account_id;
         if !access_token.is_member(account_id) {
-            return Err(DavError::Code(StatusCode::FORBIDDEN));
+            return Err(need_privileges(headers.uri.to_string(), Acl::ModifyItems));
         }
 
         let resources = vec![ResourceState {
@@ -125,19 +128,35 @@ impl LockRequestHandler for Server {
         }];
 
         let mut base_path = None;
+        let mut lock_root = None;
         let is_lock_request = !matches!(lock_info, LockRequest::Unlock);
-        let if_lock_token = headers
-            .if_
-            .iter()
-            .flat_map(|if_| if_.list.iter())
-            .find_map(|cond| {
-                if let Condition::StateToken { token, .. } = cond {
-                    Urn::parse(token).and_then(|u| u.try_unwrap_lock())
-                } else {
-                    None
+        let lock_id = match &lock_info {
+            LockRequest::Lock(_) => {
+                // Locks cover either the resource alone (Depth: 0) or its whole
+                // subtree, which is the default when no Depth header is sent
+                if matches!(headers.depth, Depth::One) {
+                    return Err(DavError::Code(StatusCode::BAD_REQUEST));
                 }
-            })
-            .unwrap_or_default();
+                0
+            }
+            LockRequest::Refresh => headers
+                .if_
+                .iter()
+                .flat_map(|if_| if_.list.iter())
+                .find_map(|cond| {
+                    if let Condition::StateToken { token, .. } = cond {
+                        Urn::parse(token).and_then(|u| u.try_unwrap_lock())
+                    } else {
+                        None
+                    }
+                })
+                .ok_or(DavError::Code(StatusCode::BAD_REQUEST))?,
+            LockRequest::Unlock => headers
+                .lock_token
+                .and_then(Urn::parse)
+                .and_then(|urn| urn.try_unwrap_lock())
+                .ok_or(DavError::Code(StatusCode::BAD_REQUEST))?,
+        };
         let mut lock_data = if let Some(lock_data) = self
             .in_memory_store()
             .key_get::<Archive<AlignedBytes>>(resource_hash.as_slice())
@@ -164,11 +183,12 @@ impl LockRequestHandler for Server {
             if let LockRequest::Lock(lock_info) = &lock_info {
                 let mut failed_locks = Vec::new();
                 let is_exclusive = matches!(lock_info.lock_scope, LockScope::Exclusive);
-                let is_infinity = matches!(headers.depth, Depth::Infinity);
+                let is_infinity = !matches!(headers.depth, Depth::Zero);
 
+                // Submitting the token of an existing lock does not allow
+                // creating a conflicting one
                 for (lock_path, lock_item) in lock_data.find_locks(resource_path, true) {
-                    if if_lock_token != lock_item.lock_id
-                        && (lock_item.exclusive || is_exclusive)
+                    if (lock_item.exclusive || is_exclusive)
                         && (lock_path.len() == resource_path.len()
                             || lock_item.depth_infinity && resource_path.len() > lock_path.len()
                             || is_infinity && lock_path.len() > resource_path.len())
@@ -209,9 +229,36 @@ impl LockRequestHandler for Server {
                 {
                     return Err(DavError::Code(StatusCode::TOO_MANY_REQUESTS));
                 }
+            } else {
+                // Locks are refreshed or removed by their owner through any
+                // resource within their scope
+                match lock_data
+                    .find_locks(resource_path, false)
+                    .into_iter()
+                    .find(|(_, lock_item)| lock_item.lock_id == lock_id)
+                {
+                    Some((lock_path, lock_item)) => {
+                        if lock_item.owner != access_token.primary_id {
+                            return Err(DavError::Code(StatusCode::FORBIDDEN));
+                        }
+                        lock_root = Some(lock_path.to_string());
+                    }
+                    None if is_lock_request => {
+                        return Err(DavError::Code(StatusCode::PRECONDITION_FAILED));
+                    }
+                    None => {
+                        return Err(DavErrorCondition::new(
+                            StatusCode::CONFLICT,
+                            BaseCondition::LockTokenMatchesRequestUri,
+                        )
+                        .into());
+                    }
+                }
             }
 
             rkyv_deserialize(lock_data).caused_by(trc::location!())?
+        } else if matches!(lock_info, LockRequest::Refresh) {
+            return Err(DavError::Code(StatusCode::PRECONDITION_FAILED));
         } else if is_lock_request {
             self.validate_headers(
                 access_token,
@@ -240,12 +287,11 @@ impl LockRequestHandler for Server {
             };
             let expires = now + timeout;
 
-            let lock_item = if if_lock_token > 0 {
+            let lock_item = if let Some(lock_root) = &lock_root {
                 if let Some(lock_item) = lock_data
                     .locks
-                    .values_mut()
-                    .flat_map(|locks| locks.0.iter_mut())
-                    .find(|lock| lock.lock_id == if_lock_token)
+                    .get_mut(lock_root)
+                    .and_then(|locks| locks.0.iter_mut().find(|lock| lock.lock_id == lock_id))
                 {
                     lock_item
                 } else {
@@ -271,15 +317,18 @@ impl LockRequestHandler for Server {
 
                 lock_item.lock_id = store::rand::random::<u64>() ^ expires;
                 lock_item.owner = access_token.primary_id;
-                lock_item.depth_infinity = matches!(headers.depth, Depth::Infinity);
+                lock_item.depth_infinity = !matches!(headers.depth, Depth::Zero);
                 lock_item.owner_dav = lock_info.owner;
                 lock_item.exclusive = matches!(lock_info.lock_scope, LockScope::Exclusive);
             }
 
             let base_path = base_path.get_or_insert_with(|| headers.base_uri().unwrap_or_default());
-            let active_lock = lock_item.to_active_lock(format!("{base_path}/{resource_path}"));
+            let active_lock = lock_item.to_active_lock(format!(
+                "{base_path}/{}",
+                lock_root.as_deref().unwrap_or(resource_path)
+            ));
 
-            HttpResponse::new(if if_lock_token == 0 {
+            HttpResponse::new(if lock_root.is_none() {
                 StatusCode::CREATED
             } else {
                 StatusCode::OK
@@ -293,12 +342,6 @@ impl LockRequestHandler for Server {
                 .to_string(),
             )
         } else {
-            let lock_id = headers
-                .lock_token
-                .and_then(Urn::parse)
-                .and_then(|urn| urn.try_unwrap_lock())
-                .ok_or(DavError::Code(StatusCode::BAD_REQUEST))?;
-
             if lock_data.remove_lock(lock_id) {
                 HttpResponse::new(StatusCode::NO_CONTENT)
             } else {
@@ -345,6 +388,8 @@ impl LockRequestHandler for Server {
         method: DavMethod,
     ) -> crate::Result<()> {
         let no_if_headers = headers.if_.is_empty();
+        // State-changing requests require strong entity tag comparison
+        let strong_compare = !matches!(method, DavMethod::GET | DavMethod::HEAD);
         match method {
             DavMethod::GET | DavMethod::HEAD => {
                 // Return early for GET/HEAD requests without If headers
@@ -356,7 +401,9 @@ impl LockRequestHandler for Server {
             | DavMethod::MOVE
             | DavMethod::POST
             | DavMethod::PUT
-            | DavMethod::PATCH => {
+            | DavMethod::PATCH
+            | DavMethod::BIND
+            | DavMethod::REBIND => {
                 if headers.overwrite_fail
                     && resources.last().is_some_and(|r| {
                         r.etag.is_some() || r.document_id.is_some_and(|id| id != u32::MAX)
@@ -386,6 +433,10 @@ impl LockRequestHandler for Server {
         ) {
             let mut base_path = None;
 
+            // Every resource affected by the request (such as both the source
+            // and destination of a MOVE) needs one of its lock tokens submitted,
+            // either in an untagged list or in a list tagged with the resource
+            // or the root of the lock
             'outer: for (pos, resource) in resources.iter().enumerate() {
                 if pos == 0 && matches!(method, DavMethod::COPY) {
                     continue;
@@ -397,12 +448,14 @@ impl LockRequestHandler for Server {
                     for (lock_path, lock_item) in locks.find_locks_by_pos(idx, resource, true)? {
                         let lock_token = lock_item.urn().to_string();
                         if headers.if_.iter().any(|if_| {
-                            if_.resource
-                                .is_none_or(|r| {
-                                    r.trim_end_matches('/').ends_with(lock_path)})
-                                && if_.list.iter().any(|cond| matches!(cond, Condition::StateToken { token, .. } if token == &lock_token))
+                            if_.resource.is_none_or(|r| {
+                                let r = r.trim_end_matches('/');
+                                r.ends_with(lock_path) || r.ends_with(resource.path)
+                            }) && if_.list.iter().any(|cond| {
+                                matches!(cond, Condition::StateToken { token, .. } if token == &lock_token)
+                            })
                         }) {
-                            break 'outer;
+                            continue 'outer;
                         } else {
                             let base_path = base_path.get_or_insert_with(|| {
                                 headers.base_uri()
@@ -555,16 +608,15 @@ impl LockRequestHandler for Server {
 
                 // Fetch sync token
                 if needs_sync_token && resource_state.sync_token.is_none() {
-                    let id = self
+                    let resources = self
                         .fetch_dav_resources(
                             access_token,
                             resource_state.account_id,
                             resource_state.collection.into(),
                         )
                         .await
-                        .caused_by(trc::location!())?
-                        .highest_change_id;
-                    resource_state.sync_token = Some(Urn::Sync { id, seq: 0 }.to_string());
+                        .caused_by(trc::location!())?;
+                    resource_state.sync_token = Some(resources.sync_token());
                 }
             }
 
@@ -588,8 +640,10 @@ impl LockRequestHandler for Server {
                         }
                     }
                     Condition::ETag { is_not, tag } => {
-                        if !((resource_state.etag.as_ref().is_some_and(|etag| etag == tag))
-                            ^ is_not)
+                        // Negated tags (If-None-Match) always use weak comparison
+                        if !((resource_state.etag.as_ref().is_some_and(|etag| {
+                            etag_matches(etag, tag, strong_compare && !is_not)
+                        })) ^ is_not)
                         {
                             continue 'outer;
                         }
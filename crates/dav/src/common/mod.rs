@@ -20,19 +20,35 @@ use dav_proto::{
     },
 };
 use groupware::{
-    calendar::{ArchivedCalendar, ArchivedCalendarEvent, Calendar, CalendarEvent},
+    calendar::{
+        ArchivedCalendar, ArchivedCalendarEvent, CALENDAR_SCHEDULE_INBOX, CALENDAR_SCHEDULE_OUTBOX,
+        Calendar, CalendarEvent,
+    },
     contact::{AddressBook, ArchivedAddressBook, ArchivedContactCard, ContactCard},
     file::{ArchivedFileNode, FileNode},
 };
+use http_proto::HttpResponse;
+use hyper::StatusCode;
 use jmap_proto::types::{collection::Collection, property::Property, value::ArchivedAclGrant};
 use propfind::PropFindItem;
 use rkyv::vec::ArchivedVec;
 use store::write::{AlignedBytes, Archive, BatchBuilder, Operation, ValueClass, ValueOp};
+use trc::GroupwareEvent;
 use uri::{OwnedUri, Urn};
 
 pub mod acl;
+pub mod add_member;
+pub mod apns;
+pub mod charset;
+pub mod compression;
+pub mod conditional;
+pub mod event_source;
+pub mod limits;
 pub mod lock;
+pub mod options;
 pub mod propfind;
+pub mod push;
+pub mod slow;
 pub mod uri;
 
 #[derive(Debug)]
@@ -44,6 +60,9 @@ pub(crate) struct DavQuery<'x> {
     pub depth: usize,
     pub limit: Option<u32>,
     pub max_vcard_version: Option<VCardVersion>,
+    pub jcard: bool,
+    pub no_timezones: bool,
+    pub default_alarms: bool,
     pub ret: Return,
     pub depth_no_root: bool,
     pub expand: bool,
@@ -53,10 +72,16 @@ pub(crate) struct DavQuery<'x> {
 pub(crate) enum SyncType {
     #[default]
     None,
-    Initial,
+    Initial {
+        to_id: Option<u64>,
+        offset: u32,
+        epoch: Option<u64>,
+    },
     From {
         id: u64,
-        seq: u32,
+        to_id: Option<u64>,
+        offset: u32,
+        epoch: u64,
     },
 }
 
@@ -143,6 +168,57 @@ impl DavCollection for Collection {
     }
 }
 
+// Reports the resources created, replaced or removed by a COPY or MOVE request
+pub(crate) fn copy_move_events(
+    response: &HttpResponse,
+    is_move: bool,
+    collection: Collection,
+    from_account_id: u32,
+    from_href: String,
+    to_account_id: u32,
+    to_href: String,
+) {
+    let (created, updated, deleted) = match collection {
+        Collection::Calendar | Collection::CalendarEvent => (
+            GroupwareEvent::CalendarCreated,
+            GroupwareEvent::CalendarUpdated,
+            GroupwareEvent::CalendarDeleted,
+        ),
+        Collection::AddressBook | Collection::ContactCard => (
+            GroupwareEvent::ContactCreated,
+            GroupwareEvent::ContactUpdated,
+            GroupwareEvent::ContactDeleted,
+        ),
+        _ => (
+            GroupwareEvent::FileCreated,
+            GroupwareEvent::FileUpdated,
+            GroupwareEvent::FileDeleted,
+        ),
+    };
+
+    let event = match response.status() {
+        StatusCode::CREATED => created,
+        StatusCode::NO_CONTENT => updated,
+        _ => return,
+    };
+
+    trc::event!(
+        Groupware(event),
+        AccountId = to_account_id,
+        Collection = collection,
+        Url = to_href,
+    );
+
+    if is_move {
+        trc::event!(
+            Groupware(deleted),
+            AccountId = from_account_id,
+            Collection = collection,
+            Url = from_href,
+        );
+    }
+}
+
 impl<'x> DavQuery<'x> {
     pub fn propfind(
         resource: OwnedUri<'x>,
@@ -154,12 +230,16 @@ impl<'x> DavQuery<'x> {
             propfind,
             depth: match headers.depth {
                 Depth::Zero => 0,
+                Depth::Infinity => usize::MAX,
                 _ => 1,
             },
             ret: headers.ret,
             depth_no_root: headers.depth_no_root,
             uri: headers.uri,
             max_vcard_version: headers.max_vcard_version,
+            jcard: headers.jcard,
+            no_timezones: headers.no_timezones,
+            default_alarms: headers.default_alarms,
             sync_type: Default::default(),
             limit: Default::default(),
             expand: Default::default(),
@@ -181,6 +261,9 @@ impl<'x> DavQuery<'x> {
             depth_no_root: headers.depth_no_root,
             uri: headers.uri,
             max_vcard_version: headers.max_vcard_version,
+            jcard: headers.jcard,
+            no_timezones: headers.no_timezones,
+            default_alarms: headers.default_alarms,
             sync_type: Default::default(),
             depth: Default::default(),
             limit: Default::default(),
@@ -205,6 +288,9 @@ impl<'x> DavQuery<'x> {
             depth_no_root: headers.depth_no_root,
             uri: headers.uri,
             max_vcard_version: headers.max_vcard_version,
+            jcard: headers.jcard,
+            no_timezones: headers.no_timezones,
+            default_alarms: headers.default_alarms,
             sync_type: Default::default(),
             depth: Default::default(),
             expand: Default::default(),
@@ -235,6 +321,9 @@ impl<'x> DavQuery<'x> {
             depth: Default::default(),
             limit: Default::default(),
             max_vcard_version: Default::default(),
+            jcard: Default::default(),
+            no_timezones: headers.no_timezones,
+            default_alarms: headers.default_alarms,
             expand: Default::default(),
         }
     }
@@ -252,8 +341,11 @@ impl<'x> DavQuery<'x> {
                 .as_deref()
                 .and_then(Urn::parse)
                 .and_then(|urn| urn.try_unwrap_sync())
-                .map(|(id, seq)| SyncType::From { id, seq })
-                .unwrap_or(SyncType::Initial),
+                .unwrap_or(SyncType::Initial {
+                    to_id: None,
+                    offset: 0,
+                    epoch: None,
+                }),
             depth: match changes.depth {
                 Depth::One => 1,
                 Depth::Infinity => usize::MAX,
@@ -265,6 +357,9 @@ impl<'x> DavQuery<'x> {
             expand: false,
             uri: headers.uri,
             max_vcard_version: headers.max_vcard_version,
+            jcard: headers.jcard,
+            no_timezones: headers.no_timezones,
+            default_alarms: headers.default_alarms,
         }
     }
 
@@ -296,6 +391,9 @@ impl<'x> DavQuery<'x> {
             sync_type: Default::default(),
             limit: Default::default(),
             max_vcard_version: headers.max_vcard_version,
+            jcard: headers.jcard,
+            no_timezones: headers.no_timezones,
+            default_alarms: headers.default_alarms,
         }
     }
 
@@ -441,14 +539,26 @@ impl<'x> ArchivedResource<'x> {
                 ReportSet::PrincipalMatch,
             ]
             .into(),
+            ArchivedResource::FileNode(_) => vec![ReportSet::VersionTree].into(),
             _ => None,
         }
     }
 
     pub fn resource_type(&self) -> Option<Vec<ResourceType>> {
         match self {
-            ArchivedResource::Calendar(_) => {
-                vec![ResourceType::Collection, ResourceType::Calendar].into()
+            ArchivedResource::Calendar(archive) => {
+                let flags = archive
+                    .inner
+                    .preferences
+                    .first()
+                    .map_or(0, |pref| pref.flags.to_native());
+                if flags & CALENDAR_SCHEDULE_INBOX != 0 {
+                    vec![ResourceType::Collection, ResourceType::ScheduleInbox].into()
+                } else if flags & CALENDAR_SCHEDULE_OUTBOX != 0 {
+                    vec![ResourceType::Collection, ResourceType::ScheduleOutbox].into()
+                } else {
+                    vec![ResourceType::Collection, ResourceType::Calendar].into()
+                }
             }
             ArchivedResource::AddressBook(_) => {
                 vec![ResourceType::Collection, ResourceType::AddressBook].into()
@@ -467,6 +577,14 @@ impl SyncType {
     }
 
     pub fn is_none_or_initial(&self) -> bool {
-        matches!(self, SyncType::None | SyncType::Initial)
+        matches!(self, SyncType::None | SyncType::Initial { .. })
+    }
+
+    pub fn epoch(&self) -> Option<u64> {
+        match self {
+            SyncType::None => None,
+            SyncType::Initial { epoch, .. } => *epoch,
+            SyncType::From { epoch, .. } => Some(*epoch),
+        }
     }
 }
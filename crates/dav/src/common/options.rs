@@ -0,0 +1,187 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{DavError, DavResourceName, common::uri::DavUriResource};
+use common::{Server, auth::AccessToken};
+use groupware::cache::GroupwareCache;
+use http_proto::HttpResponse;
+use hyper::StatusCode;
+use jmap_proto::types::acl::Acl;
+use trc::AddContext;
+use utils::map::bitmap::Bitmap;
+
+pub trait DavOptionsHandler: Sync + Send {
+    fn handle_dav_options(
+        &self,
+        access_token: &AccessToken,
+        uri: &str,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl DavOptionsHandler for Server {
+    async fn handle_dav_options(
+        &self,
+        access_token: &AccessToken,
+        uri: &str,
+    ) -> trc::Result<HttpResponse> {
+        let uri = match self.validate_uri(access_token, uri).await {
+            Ok(uri) => uri,
+            Err(DavError::Internal(err)) => return Err(err),
+            Err(DavError::Code(code)) => return Ok(HttpResponse::new(code)),
+            Err(_) => return Ok(HttpResponse::new(StatusCode::NOT_FOUND)),
+        };
+        let service = DavResourceName::from(uri.collection);
+        let mut allow = vec!["OPTIONS"];
+
+        match (service, uri.account_id, uri.resource) {
+            (DavResourceName::Principal, _, _) | (_, None, _) => {
+                allow.extend(["PROPFIND", "REPORT"]);
+            }
+            (_, Some(account_id), None) => {
+                // Home collection, new collections require ownership
+                allow.extend(["PROPFIND", "REPORT"]);
+                if service == DavResourceName::Cal {
+                    // Aggregated free/busy time
+                    allow.extend(["GET", "HEAD"]);
+                }
+                if access_token.is_member(account_id) {
+                    match service {
+                        DavResourceName::Cal => allow.extend(["MKCALENDAR", "MKCOL"]),
+                        DavResourceName::File => {
+                            allow.extend(["MKCOL", "PUT", "BIND", "UNBIND", "REBIND"])
+                        }
+                        _ => allow.push("MKCOL"),
+                    }
+                }
+            }
+            (_, Some(account_id), Some(path)) => {
+                let is_member = access_token.is_member(account_id);
+                let resources = self
+                    .fetch_dav_resources(access_token, account_id, uri.collection.into())
+                    .await
+                    .caused_by(trc::location!())?;
+                let acls = |document_id: u32| {
+                    if is_member {
+                        Bitmap::all()
+                    } else {
+                        resources.container_acl(access_token, document_id)
+                    }
+                };
+
+                if let Some(resource) = resources.by_path(path) {
+                    if resource.is_container() {
+                        let acls = acls(resource.document_id());
+                        allow.extend(["PROPFIND", "REPORT"]);
+                        if acls.contains(Acl::Read) {
+                            allow.push("COPY");
+                        }
+                        if acls.contains(Acl::Modify) {
+                            allow.extend(["PROPPATCH", "LOCK", "UNLOCK"]);
+                        }
+                        if acls.contains(Acl::Delete) {
+                            allow.extend(["DELETE", "MOVE"]);
+                        }
+                        if acls.contains(Acl::Administer) {
+                            allow.push("ACL");
+                        }
+                        if service == DavResourceName::File {
+                            if acls.contains(Acl::AddItems) {
+                                allow.extend(["BIND", "REBIND"]);
+                            }
+                            if acls.contains(Acl::RemoveItems) {
+                                allow.push("UNBIND");
+                            }
+                        }
+                    } else if service == DavResourceName::File {
+                        // Files carry their own ACLs
+                        let acls = acls(resource.document_id());
+                        if acls.contains(Acl::Read) {
+                            allow.extend(["GET", "HEAD", "PROPFIND", "COPY"]);
+                        }
+                        if acls.contains(Acl::Modify) {
+                            allow.extend(["PUT", "PATCH", "PROPPATCH", "LOCK", "UNLOCK"]);
+                        }
+                        if acls.contains(Acl::Delete) {
+                            allow.extend(["DELETE", "MOVE"]);
+                        }
+                        if acls.contains(Acl::Administer) {
+                            allow.push("ACL");
+                        }
+                    } else {
+                        // Calendar and contact items inherit the ACLs of their collection
+                        let acls = resource.parent_id().map(acls).unwrap_or_default();
+                        if acls.contains(Acl::ReadItems) {
+                            allow.extend(["GET", "HEAD", "PROPFIND", "COPY"]);
+                        }
+                        if acls.contains(Acl::ModifyItems) {
+                            allow.extend(["PUT", "PATCH", "PROPPATCH", "LOCK", "UNLOCK"]);
+                        }
+                        if acls.contains(Acl::RemoveItems) {
+                            allow.extend(["DELETE", "MOVE"]);
+                        }
+                    }
+                } else if let Some((parent, _)) = path.rsplit_once('/') {
+                    // Unmapped resource below an existing collection
+                    if let Some(parent) = resources.by_path(parent).filter(|p| p.is_container()) {
+                        if acls(parent.document_id()).contains(Acl::AddItems) {
+                            allow.extend(["PUT", "LOCK"]);
+                            if service == DavResourceName::File {
+                                allow.push("MKCOL");
+                            }
+                        }
+                    }
+                } else if is_member {
+                    // Unmapped resource directly under the home
+                    match service {
+                        DavResourceName::Cal => allow.extend(["MKCALENDAR", "MKCOL"]),
+                        DavResourceName::File => allow.extend(["MKCOL", "PUT", "LOCK"]),
+                        _ => allow.push("MKCOL"),
+                    }
+                }
+            }
+        }
+
+        Ok(HttpResponse::new(StatusCode::OK)
+            .with_header("DAV", service_compliance(self, access_token, service))
+            .with_header("Allow", allow.join(", ")))
+    }
+}
+
+fn service_compliance(
+    server: &Server,
+    access_token: &AccessToken,
+    service: DavResourceName,
+) -> String {
+    match service {
+        DavResourceName::Cal => {
+            let mut compliance = concat!(
+                "1, 2, 3, access-control, extended-mkcol, ",
+                "calendar-access, calendar-no-timezone, calendar-managed-attachments"
+            )
+            .to_string();
+            if server.core.groupware.scheduling_enabled {
+                compliance.push_str(", calendar-auto-schedule");
+            }
+            compliance
+        }
+        DavResourceName::Card => "1, 2, 3, access-control, extended-mkcol, addressbook".to_string(),
+        DavResourceName::File => "1, 2, 3, access-control, extended-mkcol, bind".to_string(),
+        DavResourceName::Principal => dav_compliance(Some(access_token)),
+    }
+}
+
+/// Compliance classes of all the services enabled for the caller, used for
+/// principals and anonymous requests.
+pub fn dav_compliance(access_token: Option<&AccessToken>) -> String {
+    let mut compliance = "1, 2, 3, access-control, extended-mkcol, bind".to_string();
+    if access_token.is_none_or(|access_token| DavResourceName::Cal.is_enabled(access_token)) {
+        compliance.push_str(", calendar-access, calendar-no-timezone");
+    }
+    if access_token.is_none_or(|access_token| DavResourceName::Card.is_enabled(access_token)) {
+        compliance.push_str(", addressbook");
+    }
+    compliance
+}
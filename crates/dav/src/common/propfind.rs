@@ -7,36 +7,49 @@
 use super::{
     ArchivedResource, DavCollection, DavQuery, DavQueryFilter, ETag, SyncType,
     acl::{DavAclHandler, Privileges},
+    limits::{CollectionQuotaCheck, collection_usage},
     lock::{LockData, build_lock_key},
     uri::{UriResource, Urn},
 };
 use crate::{
-    DavError, DavErrorCondition,
+    DavError, DavErrorCondition, PropStatBuilder,
     calendar::{
         CALENDAR_CONTAINER_PROPS, CALENDAR_ITEM_PROPS,
+        alarm::{CalendarDefaultAlarms, DefaultAlarms, default_alarm_text},
+        compat::export_outlook_compat,
+        format::{write_calendar_data, write_jcard},
         query::{CalendarQueryHandler, try_parse_tz},
+        sharing::CalendarSharingRequestHandler,
+        split_resource,
     },
     card::{
         CARD_CONTAINER_PROPS, CARD_ITEM_PROPS,
         query::{serialize_vcard_with_props, vcard_query},
     },
-    common::{DavQueryResource, acl::current_user_privilege_set, uri::DavUriResource},
+    common::{
+        DavQueryResource,
+        acl::{current_user_item_privilege_set, read_only_privilege_set},
+        apns::{apns_push_key_for, apns_push_transports},
+        uri::DavUriResource,
+    },
     file::{FILE_CONTAINER_PROPS, FILE_ITEM_PROPS},
     principal::{CurrentUserPrincipal, propfind::PrincipalPropFind},
 };
-use calcard::common::timezone::Tz;
+use calcard::{common::timezone::Tz, icalendar::ICalendar, vcard::VCardVersion};
 use common::{
-    DavResourcePath, DavResources, Server,
+    DavResourcePath, DavResources, ScheduleCollection, Server,
     auth::{AccessToken, AsTenantId},
+    sharing::EffectiveAcl,
 };
 use dav_proto::{
-    Depth, RequestHeaders,
+    Depth, RequestHeaders, Return,
     parser::header::dav_base_uri,
     schema::{
         Collation, Namespace,
         property::{
-            ActiveLock, CalDavProperty, CardDavProperty, DavProperty, DavValue, PrincipalProperty,
-            Privilege, ReportSet, ResourceType, Rfc1123DateTime, SupportedCollation, SupportedLock,
+            ActiveLock, CalDavProperty, CalendarDataType, CardDavProperty, CollectionUsage, Comp,
+            DavProperty, DavValue, PrincipalProperty, Privilege, ReportSet, ResourceType,
+            Rfc1123DateTime, ScheduleCalendarTransp, SupportedCollation, SupportedLock, UsageItem,
             WebDavProperty,
         },
         request::{DavPropertyValue, PropFind},
@@ -48,7 +61,13 @@ use dav_proto::{
 };
 use directory::{Permission, Type, backend::internal::manage::ManageDirectory};
 use groupware::{
-    DavCalendarResource, DavResourceName, cache::GroupwareCache, calendar::ArchivedTimezone,
+    DavCalendarResource, DavResourceName,
+    cache::GroupwareCache,
+    calendar::{
+        ArchivedTimezone, CALENDAR_COMPONENTS, CALENDAR_TRANSPARENT, component_flag,
+        dates::ExpandCalendarEvent, timezone::strip_known_timezones,
+    },
+    push::dav_push_topic,
 };
 use http_proto::HttpResponse;
 use hyper::StatusCode;
@@ -57,14 +76,21 @@ use jmap_proto::types::{
     collection::{Collection, SyncCollection},
 };
 use percent_encoding::NON_ALPHANUMERIC;
-use std::sync::Arc;
+use std::{fmt::Write, sync::Arc};
 use store::{
     ahash::AHashMap,
-    query::log::{Change, Query},
+    query::log::{Change, Changes, Query},
     roaring::RoaringBitmap,
-    write::{AlignedBytes, Archive},
+    write::{AlignedBytes, Archive, serialize::rkyv_deserialize},
 };
 use trc::AddContext;
+use utils::map::bitmap::Bitmap;
+
+// Number of responses kept unserialized before spilling them to XML
+const RESPONSE_SPILL_BATCH: usize = 64;
+
+// Number of items listed in the collection usage breakdown
+const USAGE_LARGEST_ITEMS: usize = 10;
 
 pub(crate) trait PropFindRequestHandler: Sync + Send {
     fn handle_propfind_request(
@@ -125,15 +151,17 @@ impl PropFindRequestHandler for Server {
         // Validate URI
         let resource = self.validate_uri(access_token, headers.uri).await?;
 
-        // Reject Infinity depth for certain queries
+        // Reject Infinity depth for certain queries, file hierarchies can only
+        // be listed in full when an upper bound on the results is configured
         let return_children = match headers.depth {
             Depth::One | Depth::None => true,
             Depth::Zero => false,
             Depth::Infinity => {
-                if resource.account_id.is_none()
-                    || resource.resource.is_none()
-                    || matches!(resource.collection, Collection::FileNode)
-                {
+                let is_allowed = match resource.collection {
+                    Collection::FileNode => self.core.groupware.max_infinity_results.is_some(),
+                    _ => resource.resource.is_some(),
+                };
+                if resource.account_id.is_none() || !is_allowed {
                     return Err(DavErrorCondition::new(
                         StatusCode::FORBIDDEN,
                         BaseCondition::PropFindFiniteDepth,
@@ -160,19 +188,37 @@ impl PropFindRequestHandler for Server {
                         _ => unreachable!(),
                     })?;
 
-                    self.handle_dav_query(
-                        access_token,
-                        DavQuery::propfind(
-                            UriResource::new_owned(
-                                resource.collection,
-                                account_id,
-                                resource.resource,
-                            ),
-                            request,
+                    // Serve Depth:0 requests of common collection properties from the cache
+                    if let Some(response) = self
+                        .cached_container_propfind(
+                            access_token,
                             headers,
-                        ),
-                    )
-                    .await
+                            resource.collection,
+                            account_id,
+                            resource.resource,
+                            &request,
+                        )
+                        .await?
+                    {
+                        return Ok(response);
+                    }
+
+                    let mut query = DavQuery::propfind(
+                        UriResource::new_owned(resource.collection, account_id, resource.resource),
+                        request,
+                        headers,
+                    );
+                    if headers.depth == Depth::Infinity
+                        && resource.collection == Collection::FileNode
+                    {
+                        query.limit = self
+                            .core
+                            .groupware
+                            .max_infinity_results
+                            .map(|limit| limit as u32);
+                    }
+
+                    self.handle_dav_query(access_token, query).await
                 }
                 Collection::Principal => {
                     let mut response = MultiStatus::new(Vec::with_capacity(16));
@@ -304,21 +350,15 @@ impl PropFindRequestHandler for Server {
                         }
                     }
 
-                    let mut prop_stat = Vec::with_capacity(2);
-
-                    if !fields.is_empty() {
-                        prop_stat.push(PropStat::new_list(fields));
-                    }
-
-                    if !fields_not_found.is_empty() {
-                        prop_stat.push(
-                            PropStat::new_list(fields_not_found).with_status(StatusCode::NOT_FOUND),
-                        );
-                    }
+                    let mut prop_stat =
+                        PropStatBuilder::default().with_minimal(headers.ret == Return::Minimal);
+                    prop_stat
+                        .insert_list_with_status(fields, StatusCode::OK)
+                        .insert_list_with_status(fields_not_found, StatusCode::NOT_FOUND);
 
                     response.add_response(Response::new_propstat(
                         resource.collection_path(),
-                        prop_stat,
+                        prop_stat.build(),
                     ));
                 }
             }
@@ -390,6 +430,10 @@ impl PropFindRequestHandler for Server {
             self.core.groupware.max_results,
         );
         let mut is_sync_limited = false;
+        let mut is_size_limited = false;
+        let mut is_multiget = false;
+        let mut is_paged = false;
+        let mut next_page = None;
 
         //let c = println!("handling DAV query {query:#?}");
 
@@ -437,12 +481,37 @@ impl PropFindRequestHandler for Server {
                         }))
                     });
 
+                // Tokens issued before the sync epoch was reset can no longer be trusted
+                if query
+                    .sync_type
+                    .epoch()
+                    .is_some_and(|epoch| epoch != resources.sync_epoch)
+                {
+                    return Err(DavErrorCondition::new(
+                        StatusCode::FORBIDDEN,
+                        BaseCondition::ValidSyncToken,
+                    )
+                    .into());
+                }
+
                 // Filter by changelog
                 match query.sync_type {
-                    SyncType::From { id, seq } => {
+                    SyncType::From {
+                        id, to_id, offset, ..
+                    } => {
+                        // Pages of a truncated report keep listing the changes up to the
+                        // change id the first page was generated at
+                        let to_id = to_id.unwrap_or(resources.highest_change_id);
+
+                        // Use the per-container log when syncing a single container
+                        let shard_id = resource
+                            .resource
+                            .filter(|_| container_has_children)
+                            .and_then(|name| resources.by_path(name))
+                            .filter(|resource| resource.is_container())
+                            .map(|resource| resource.document_id());
                         let changes = self
-                            .store()
-                            .changes(account_id, sync_collection, Query::Since(id))
+                            .sync_changes(account_id, sync_collection, shard_id, id, to_id)
                             .await
                             .caused_by(trc::location!())?;
                         let mut vanished: Vec<String> = Vec::new();
@@ -521,7 +590,7 @@ impl PropFindRequestHandler for Server {
                                 .vanished(
                                     account_id,
                                     sync_collection.vanished_collection().unwrap(),
-                                    Query::Since(id),
+                                    Query::RangeInclusive(id + 1, to_id),
                                 )
                                 .await
                                 .caused_by(trc::location!())?;
@@ -529,8 +598,9 @@ impl PropFindRequestHandler for Server {
                         }
 
                         // Truncate changes
-                        if total_changes > limit {
-                            let mut offset = limit * seq as usize;
+                        if total_changes > limit || offset > 0 {
+                            let next_offset = offset as usize + limit;
+                            let mut offset = offset as usize;
                             let mut total_changes = 0;
 
                             // Add vanished items to response
@@ -568,7 +638,15 @@ impl PropFindRequestHandler for Server {
                             }
 
                             if is_sync_limited {
-                                response.set_sync_token(Urn::Sync { id, seq: seq + 1 }.to_string());
+                                response.set_sync_token(
+                                    Urn::SyncPage {
+                                        id: Some(id),
+                                        to_id,
+                                        offset: next_offset as u32,
+                                        epoch: resources.sync_epoch,
+                                    }
+                                    .to_string(),
+                                );
                             }
                         } else {
                             // Add vanished items to response
@@ -581,11 +659,23 @@ impl PropFindRequestHandler for Server {
                         }
 
                         if !is_sync_limited {
-                            response.set_sync_token(resources.sync_token());
+                            response.set_sync_token(
+                                Urn::Sync {
+                                    id: to_id,
+                                    epoch: resources.sync_epoch,
+                                }
+                                .to_string(),
+                            );
                         }
                     }
-                    SyncType::Initial => {
-                        response.set_sync_token(resources.sync_token());
+                    SyncType::Initial { to_id, .. } => {
+                        response.set_sync_token(
+                            Urn::Sync {
+                                id: to_id.unwrap_or(resources.highest_change_id),
+                                epoch: resources.sync_epoch,
+                            }
+                            .to_string(),
+                        );
                     }
                     SyncType::None => (),
                 }
@@ -652,6 +742,25 @@ impl PropFindRequestHandler for Server {
                         .collect::<Vec<_>>()
                 };
 
+                // Page the initial listing of a sync report
+                if let SyncType::Initial { to_id, offset, .. } = query.sync_type {
+                    let offset = std::cmp::min(offset as usize, paths.len());
+                    paths.drain(..offset);
+                    if paths.len() > limit {
+                        paths.truncate(limit);
+                        is_sync_limited = true;
+                        response.set_sync_token(
+                            Urn::SyncPage {
+                                id: None,
+                                to_id: to_id.unwrap_or(resources.highest_change_id),
+                                offset: (offset + limit) as u32,
+                                epoch: resources.sync_epoch,
+                            }
+                            .to_string(),
+                        );
+                    }
+                }
+
                 if paths.is_empty() && query.sync_type.is_none() {
                     response.add_response(
                         Response::new_status([query.uri], StatusCode::NOT_FOUND)
@@ -667,6 +776,7 @@ impl PropFindRequestHandler for Server {
                 parent_collection,
             } => {
                 paths = Vec::with_capacity(hrefs.len());
+                is_multiget = true;
                 let mut shared_folders_by_account: AHashMap<u32, Arc<RoaringBitmap>> =
                     AHashMap::with_capacity(3);
                 collection_container = parent_collection;
@@ -674,17 +784,35 @@ impl PropFindRequestHandler for Server {
                 sync_collection = SyncCollection::from(collection_container);
                 response.set_namespace(collection_container.namespace());
 
+                // Stale or foreign hrefs are reported individually so that the
+                // remaining hrefs are still returned
                 for item in hrefs {
                     let resource = match self
                         .validate_uri(access_token, &item)
                         .await
                         .and_then(|r| r.into_owned_uri())
                     {
-                        Ok(resource) => resource,
+                        Ok(resource) if resource.collection == collection_container => resource,
+                        Ok(_) => {
+                            response.add_response(
+                                Response::new_status([item], StatusCode::NOT_FOUND)
+                                    .with_response_description(
+                                        "Resource does not belong to this collection type",
+                                    ),
+                            );
+                            continue;
+                        }
                         Err(DavError::Code(code)) => {
                             response.add_response(Response::new_status([item], code));
                             continue;
                         }
+                        Err(DavError::Condition(condition)) => {
+                            response.add_response(
+                                Response::new_status([item], condition.code)
+                                    .with_error(condition.condition),
+                            );
+                            continue;
+                        }
                         Err(err) => {
                             return Err(err);
                         }
@@ -745,6 +873,11 @@ impl PropFindRequestHandler for Server {
                                     ),
                             );
                         }
+                    } else if resource.resource.is_none() {
+                        response.add_response(
+                            Response::new_status([item], StatusCode::FORBIDDEN)
+                                .with_response_description("Multiget not allowed for collections"),
+                        );
                     } else {
                         response.add_response(Response::new_status([item], StatusCode::NOT_FOUND));
                     }
@@ -756,6 +889,8 @@ impl PropFindRequestHandler for Server {
                 items,
             } => {
                 paths = items;
+                // Address book query results are ordered by document id
+                is_paged = matches!(filter, DavQueryFilter::Addressbook(_));
                 query_filter = Some(filter);
                 collection_container = parent_collection;
                 collection_children = collection_container.child_collection().unwrap();
@@ -817,6 +952,42 @@ impl PropFindRequestHandler for Server {
             PropFind::Prop(items) => items.clone(),
         };
 
+        // Dead properties are only looked up for allprop or when explicitly requested
+        let needs_dead_properties = skip_not_found
+            || properties
+                .iter()
+                .any(|property| matches!(property, DavProperty::DeadProperty(_)));
+
+        // Fetch all multiget archives upfront rather than one read per href
+        let mut prefetched_archives = AHashMap::new();
+        if is_multiget && !paths.is_empty() {
+            let mut document_ids: AHashMap<u32, RoaringBitmap> = AHashMap::with_capacity(1);
+            for item in &paths {
+                document_ids
+                    .entry(item.account_id)
+                    .or_default()
+                    .insert(item.document_id);
+            }
+
+            prefetched_archives.reserve(paths.len());
+            for (account_id, document_ids) in document_ids {
+                self.get_archives(
+                    account_id,
+                    collection_children,
+                    &document_ids,
+                    |document_id, archive| {
+                        prefetched_archives.insert((account_id, document_id), archive);
+                        Ok(true)
+                    },
+                )
+                .await
+                .caused_by(trc::location!())?;
+            }
+        }
+
+        // Scratch buffer for item data, copied into a recycled response buffer once filled
+        let mut serialize_buf = String::with_capacity(1024);
+        let mut default_alarms: AHashMap<(u32, u32), DefaultAlarms> = AHashMap::new();
         let view_as_id = access_token.primary_id();
         for item in paths {
             let account_id = item.account_id;
@@ -826,11 +997,14 @@ impl PropFindRequestHandler for Server {
             } else {
                 collection_children
             };
-            let archive_ = if let Some(archive_) = self
-                .get_archive(account_id, collection, document_id)
-                .await
-                .caused_by(trc::location!())?
-            {
+            let archive_ = if is_multiget {
+                prefetched_archives.remove(&(account_id, document_id))
+            } else {
+                self.get_archive(account_id, collection, document_id)
+                    .await
+                    .caused_by(trc::location!())?
+            };
+            let archive_ = if let Some(archive_) = archive_ {
                 archive_
             } else {
                 response.add_response(Response::new_status([item.name], StatusCode::NOT_FOUND));
@@ -839,6 +1013,20 @@ impl PropFindRequestHandler for Server {
             let archive = ArchivedResource::from_archive(&archive_, collection)
                 .caused_by(trc::location!())?;
 
+            // Events holding recurrence overrides uploaded as separate
+            // resources are served split back into those resources
+            let split = match (&archive, item.parent_id) {
+                (ArchivedResource::CalendarEvent(event), Some(calendar_id)) => split_resource(
+                    event.inner,
+                    calendar_id,
+                    item.name
+                        .trim_end_matches('/')
+                        .rsplit_once('/')
+                        .map_or(item.name.as_str(), |(_, name)| name),
+                )?,
+                _ => None,
+            };
+
             // Filter
             let mut calendar_filter = None;
             if let Some(query_filter) = &query_filter {
@@ -867,8 +1055,20 @@ impl PropFindRequestHandler for Server {
                         } else {
                             Tz::UTC
                         };
+                        let expanded_times = max_time_range
+                            .map(|max_time_range| {
+                                self.expand_event(
+                                    account_id,
+                                    document_id,
+                                    event,
+                                    default_tz,
+                                    max_time_range,
+                                )
+                            })
+                            .unwrap_or_default();
                         let mut query_handler =
-                            CalendarQueryHandler::new(event.inner, *max_time_range, default_tz);
+                            CalendarQueryHandler::new(expanded_times, default_tz)
+                                .with_no_timezones(query.no_timezones);
                         if !query_handler.filter(event.inner, filter) {
                             continue;
                         }
@@ -879,8 +1079,10 @@ impl PropFindRequestHandler for Server {
             }
 
             // Fill properties
-            let dead_properties = archive.dead_properties();
-            let mut fields = Vec::with_capacity(properties.len());
+            let dead_properties = needs_dead_properties
+                .then(|| archive.dead_properties())
+                .filter(|dead_properties| !dead_properties.0.is_empty());
+            let mut fields = response.take_prop_buffer(properties.len());
             let mut fields_not_found = Vec::new();
             for property in &properties {
                 match property {
@@ -929,7 +1131,11 @@ impl PropFindRequestHandler for Server {
                         WebDavProperty::GetETag => {
                             fields.push(DavPropertyValue::new(
                                 property.clone(),
-                                DavValue::String(archive_.etag()),
+                                DavValue::String(
+                                    split
+                                        .as_ref()
+                                        .map_or_else(|| archive_.etag(), |(_, etag)| etag.clone()),
+                                ),
                             ));
                         }
                         WebDavProperty::GetCTag => {
@@ -1019,10 +1225,16 @@ impl PropFindRequestHandler for Server {
                             if item.is_container {
                                 fields.push(DavPropertyValue::new(
                                     property.clone(),
-                                    data.quota(self, access_token, account_id)
-                                        .await
-                                        .caused_by(trc::location!())?
-                                        .available,
+                                    data.container_quota(
+                                        self,
+                                        access_token,
+                                        account_id,
+                                        collection_container,
+                                        &item,
+                                    )
+                                    .await
+                                    .caused_by(trc::location!())?
+                                    .available,
                                 ));
                             } else if !skip_not_found {
                                 fields_not_found.push(DavPropertyValue::empty(property.clone()));
@@ -1032,15 +1244,40 @@ impl PropFindRequestHandler for Server {
                             if item.is_container {
                                 fields.push(DavPropertyValue::new(
                                     property.clone(),
-                                    data.quota(self, access_token, account_id)
-                                        .await
-                                        .caused_by(trc::location!())?
-                                        .used,
+                                    data.container_quota(
+                                        self,
+                                        access_token,
+                                        account_id,
+                                        collection_container,
+                                        &item,
+                                    )
+                                    .await
+                                    .caused_by(trc::location!())?
+                                    .used,
                                 ));
                             } else if !skip_not_found {
                                 fields_not_found.push(DavPropertyValue::empty(property.clone()));
                             }
                         }
+                        WebDavProperty::CollectionUsage => {
+                            if item.is_container {
+                                fields.push(DavPropertyValue::new(
+                                    property.clone(),
+                                    data.container_usage(
+                                        self,
+                                        access_token,
+                                        account_id,
+                                        collection_container,
+                                        &item,
+                                    )
+                                    .await
+                                    .caused_by(trc::location!())?,
+                                ));
+                                response.set_namespace(Namespace::Stalwart);
+                            } else if !skip_not_found {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                        }
                         WebDavProperty::Owner => {
                             if !query.expand {
                                 fields.push(DavPropertyValue::new(
@@ -1132,28 +1369,43 @@ impl PropFindRequestHandler for Server {
                             ));
                         }
                         WebDavProperty::CurrentUserPrivilegeSet => {
-                            let privileges = if access_token.is_member(account_id) {
-                                Privilege::all(matches!(
-                                    collection,
-                                    Collection::Calendar | Collection::CalendarEvent
-                                ))
-                            } else if let Some(acls) = archive.acls() {
+                            let resources = data
+                                .resources(self, access_token, account_id, sync_collection)
+                                .await
+                                .caused_by(trc::location!())?;
+                            let mut privileges = if let Some(acls) = archive.acls() {
                                 access_token.current_privilege_set(
                                     account_id,
                                     acls,
                                     collection_container == Collection::Calendar,
+                                    item.is_container,
                                 )
+                            } else if access_token.is_member(account_id) {
+                                current_user_item_privilege_set(Bitmap::all())
                             } else if let Some(parent_id) = item.parent_id {
-                                current_user_privilege_set(
-                                    data.resources(self, access_token, account_id, sync_collection)
-                                        .await
-                                        .caused_by(trc::location!())?
-                                        .container_acl(access_token, parent_id),
+                                current_user_item_privilege_set(
+                                    resources.container_acl(access_token, parent_id),
                                 )
                             } else {
                                 vec![]
                             };
 
+                            // Scheduling and birthday calendars are managed by the server
+                            if let Some(calendar) = if item.is_container {
+                                Some(item.document_id)
+                            } else {
+                                item.parent_id
+                            }
+                            .filter(|_| collection_container == Collection::Calendar)
+                            .and_then(|calendar_id| resources.container_resource_by_id(calendar_id))
+                            {
+                                if calendar.is_birthday_calendar() {
+                                    read_only_privilege_set(&mut privileges, false);
+                                } else if calendar.schedule_collection().is_some() {
+                                    read_only_privilege_set(&mut privileges, true);
+                                }
+                            }
+
                             if !privileges.is_empty() {
                                 fields.push(DavPropertyValue::new(property.clone(), privileges));
                             } else if !skip_not_found {
@@ -1195,9 +1447,118 @@ impl PropFindRequestHandler for Server {
                                 )],
                             ));
                         }
+                        WebDavProperty::AddMember => {
+                            let is_schedule_collection =
+                                matches!(&archive, ArchivedResource::Calendar(_))
+                                    && data
+                                        .resources(self, access_token, account_id, sync_collection)
+                                        .await
+                                        .caused_by(trc::location!())?
+                                        .container_resource_by_id(item.document_id)
+                                        .is_some_and(|resource| {
+                                            resource.schedule_collection().is_some()
+                                        });
+
+                            if item.is_container && !is_schedule_collection {
+                                fields.push(DavPropertyValue::new(
+                                    property.clone(),
+                                    vec![Href(format!(
+                                        "{}/?add-member",
+                                        item.name.trim_end_matches('/')
+                                    ))],
+                                ));
+                            } else if !skip_not_found {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                        }
+                        WebDavProperty::VersionHistory | WebDavProperty::VersionName => {
+                            let version = match &archive {
+                                ArchivedResource::FileNode(node)
+                                    if node.inner.file.is_some()
+                                        && self.core.groupware.max_file_versions > 0 =>
+                                {
+                                    Some(node.inner.current_version())
+                                }
+                                _ => None,
+                            };
+
+                            if let Some(version) = version {
+                                if matches!(dav_property, WebDavProperty::VersionHistory) {
+                                    fields.push(DavPropertyValue::new(
+                                        property.clone(),
+                                        vec![Href(item.name.clone())],
+                                    ));
+                                } else {
+                                    fields.push(DavPropertyValue::new(
+                                        property.clone(),
+                                        DavValue::String(version.to_string()),
+                                    ));
+                                }
+                            } else if !skip_not_found {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                        }
+                        WebDavProperty::PushTransports | WebDavProperty::PushKey => {
+                            let service = match collection_container {
+                                Collection::Calendar if item.is_container => {
+                                    Some(DavResourceName::Cal)
+                                }
+                                Collection::AddressBook if item.is_container => {
+                                    Some(DavResourceName::Card)
+                                }
+                                _ => None,
+                            };
+                            let value = service.and_then(|service| {
+                                if matches!(dav_property, WebDavProperty::PushTransports) {
+                                    apns_push_transports(self, service).map(DavValue::from)
+                                } else {
+                                    apns_push_key_for(self, service, account_id)
+                                        .map(DavValue::String)
+                                }
+                            });
+
+                            if let Some(value) = value {
+                                fields.push(DavPropertyValue::new(property.clone(), value));
+                                response.set_namespace(Namespace::CalendarServer);
+                            } else if !skip_not_found {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                        }
+                        WebDavProperty::Transports
+                        | WebDavProperty::Topic
+                        | WebDavProperty::SupportedTriggers => {
+                            let service = match collection_container {
+                                Collection::Calendar if item.is_container => {
+                                    Some(DavResourceName::Cal)
+                                }
+                                Collection::AddressBook if item.is_container => {
+                                    Some(DavResourceName::Card)
+                                }
+                                _ => None,
+                            }
+                            .filter(|_| self.core.groupware.web_push.is_some());
+                            let value = service.map(|service| match dav_property {
+                                WebDavProperty::Transports => DavValue::WebPushTransport,
+                                WebDavProperty::Topic => DavValue::String(dav_push_topic(
+                                    service,
+                                    account_id,
+                                    item.document_id,
+                                )),
+                                _ => DavValue::SupportedTriggers,
+                            });
+
+                            if let Some(value) = value {
+                                fields.push(DavPropertyValue::new(property.clone(), value));
+                                response.set_namespace(Namespace::WebDavPush);
+                            } else if !skip_not_found {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                        }
                     },
                     DavProperty::DeadProperty(tag) => {
-                        if let Some(value) = dead_properties.find_tag(&tag.name) {
+                        if let Some(value) =
+                            dead_properties.and_then(|props| props.find_tag(&tag.name))
+                        {
                             fields.push(DavPropertyValue::new(property.clone(), value));
                         } else {
                             fields_not_found.push(DavPropertyValue::empty(property.clone()));
@@ -1250,15 +1611,33 @@ impl PropFindRequestHandler for Server {
                             CardDavProperty::AddressData(items),
                             ArchivedResource::ContactCard(card),
                         ) => {
-                            fields.push(DavPropertyValue::new(
-                                property.clone(),
-                                DavValue::CData(serialize_vcard_with_props(
-                                    &card.inner.card,
-                                    items,
+                            serialize_buf.clear();
+                            serialize_vcard_with_props(
+                                &card.inner.card,
+                                items,
+                                if query.jcard {
+                                    Some(VCardVersion::V4_0)
+                                } else {
                                     query
                                         .max_vcard_version
-                                        .or_else(|| card.inner.card.version()),
-                                )),
+                                        .or_else(|| card.inner.card.version())
+                                },
+                                &mut serialize_buf,
+                            );
+                            let address_data = if query.jcard {
+                                let mut converted =
+                                    response.take_data_buffer(serialize_buf.len() * 2);
+                                write_jcard(&serialize_buf, &mut converted);
+                                converted
+                            } else {
+                                let mut address_data =
+                                    response.take_data_buffer(serialize_buf.len());
+                                address_data.push_str(&serialize_buf);
+                                address_data
+                            };
+                            fields.push(DavPropertyValue::new(
+                                property.clone(),
+                                DavValue::CData(address_data),
                             ));
                         }
                         _ => {
@@ -1313,13 +1692,118 @@ impl PropFindRequestHandler for Server {
                                 fields_not_found.push(DavPropertyValue::empty(property.clone()));
                             }
                         }
+                        (
+                            CalDavProperty::ScheduleCalendarTransp,
+                            ArchivedResource::Calendar(calendar),
+                        ) => {
+                            let flags = calendar.inner.preferences(account_id).flags.to_native();
+                            fields.push(DavPropertyValue::new(
+                                property.clone(),
+                                if flags & CALENDAR_TRANSPARENT != 0 {
+                                    ScheduleCalendarTransp::Transparent
+                                } else {
+                                    ScheduleCalendarTransp::Opaque
+                                },
+                            ));
+                        }
+                        (
+                            CalDavProperty::CalendarAvailability,
+                            ArchivedResource::Calendar(calendar),
+                        ) => {
+                            if let Some(availability) = calendar.inner.availability.as_ref() {
+                                fields.push(DavPropertyValue::new(
+                                    property.clone(),
+                                    DavValue::CData(availability.to_string()),
+                                ));
+                            } else {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                        }
+                        (
+                            CalDavProperty::DefaultAlarmVEventDate
+                            | CalDavProperty::DefaultAlarmVEventDateTime,
+                            ArchivedResource::Calendar(calendar),
+                        ) => {
+                            if let Some(alert) = calendar.inner.default_alert(
+                                account_id,
+                                matches!(cal_property, CalDavProperty::DefaultAlarmVEventDateTime),
+                            ) {
+                                fields.push(DavPropertyValue::new(
+                                    property.clone(),
+                                    DavValue::CData(default_alarm_text(&alert.alert.to_string())),
+                                ));
+                            } else {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                        }
+                        (CalDavProperty::CalendarColor, ArchivedResource::Calendar(calendar)) => {
+                            response.set_namespace(Namespace::AppleIcal);
+                            if let Some(color) =
+                                calendar.inner.preferences(account_id).color.as_deref()
+                            {
+                                fields.push(DavPropertyValue::new(
+                                    property.clone(),
+                                    color.to_string(),
+                                ));
+                            } else {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                        }
+                        (CalDavProperty::CalendarOrder, ArchivedResource::Calendar(calendar)) => {
+                            response.set_namespace(Namespace::AppleIcal);
+                            fields.push(DavPropertyValue::new(
+                                property.clone(),
+                                calendar
+                                    .inner
+                                    .preferences(account_id)
+                                    .sort_order
+                                    .to_native() as u64,
+                            ));
+                        }
+                        (CalDavProperty::Invite, ArchivedResource::Calendar(calendar)) => {
+                            response.set_namespace(Namespace::CalendarServer);
+                            if !calendar.inner.invites.is_empty()
+                                && (access_token.is_member(account_id)
+                                    || calendar
+                                        .inner
+                                        .acls
+                                        .effective_acl(access_token)
+                                        .contains(Acl::Administer))
+                            {
+                                fields.push(DavPropertyValue::new(
+                                    property.clone(),
+                                    self.calendar_sharees(access_token, calendar.inner).await?,
+                                ));
+                            } else {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                        }
+                        (CalDavProperty::AllowedSharingModes, ArchivedResource::Calendar(_)) => {
+                            response.set_namespace(Namespace::CalendarServer);
+                            fields.push(DavPropertyValue::new(
+                                property.clone(),
+                                DavValue::AllowedSharingModes,
+                            ));
+                        }
                         (
                             CalDavProperty::SupportedCalendarComponentSet,
-                            ArchivedResource::Calendar(_),
+                            ArchivedResource::Calendar(calendar),
                         ) => {
+                            let supported = calendar.inner.supported_components.to_native();
                             fields.push(DavPropertyValue::new(
                                 property.clone(),
-                                DavValue::SupportedCalendarComponentSet,
+                                if supported == 0 {
+                                    DavValue::SupportedCalendarComponentSet
+                                } else {
+                                    DavValue::Components(List(
+                                        CALENDAR_COMPONENTS
+                                            .iter()
+                                            .filter(|typ| supported & component_flag(typ) != 0)
+                                            .cloned()
+                                            .map(Comp)
+                                            .collect(),
+                                    ))
+                                },
                             ));
                         }
                         (CalDavProperty::SupportedCalendarData, ArchivedResource::Calendar(_)) => {
@@ -1380,19 +1864,73 @@ impl PropFindRequestHandler for Server {
                             CalDavProperty::CalendarData(data),
                             ArchivedResource::CalendarEvent(event),
                         ) => {
-                            let ical = if calendar_filter.is_some() || !data.properties.is_empty() {
+                            serialize_buf.clear();
+                            if let Some((split, _)) = split
+                                .as_ref()
+                                .filter(|_| data.properties.is_empty() && data.expand.is_none())
+                            {
+                                // Partial and expanded calendar data is built from
+                                // the stored event, which includes all the overrides
+                                if query.no_timezones {
+                                    let mut split = split.clone();
+                                    strip_known_timezones(&mut split);
+                                    let _ = write!(&mut serialize_buf, "{split}");
+                                } else {
+                                    let _ = write!(&mut serialize_buf, "{split}");
+                                }
+                            } else if calendar_filter.is_some()
+                                || !data.properties.is_empty()
+                                || query.no_timezones
+                            {
                                 calendar_filter
                                     .get_or_insert_with(|| {
-                                        CalendarQueryHandler::new(event.inner, None, Tz::UTC)
+                                        CalendarQueryHandler::new(vec![], Tz::UTC)
+                                            .with_no_timezones(query.no_timezones)
                                     })
-                                    .serialize_ical(event.inner, data)
+                                    .serialize_ical(event.inner, data, &mut serialize_buf);
+                            } else if self.core.groupware.outlook_compat_export {
+                                let mut ical =
+                                    rkyv_deserialize::<_, ICalendar>(&event.inner.data.event)
+                                        .caused_by(trc::location!())?;
+                                export_outlook_compat(&mut ical);
+                                let _ = write!(&mut serialize_buf, "{ical}");
                             } else {
-                                event.inner.data.event.to_string()
-                            };
+                                let _ = write!(&mut serialize_buf, "{}", event.inner.data.event);
+                            }
+
+                            // Events without alarms include the calendar's default
+                            // alarm when the client prefers so
+                            if let Some(calendar_id) = item
+                                .parent_id
+                                .filter(|_| query.default_alarms && data.properties.is_empty())
+                            {
+                                if !default_alarms.contains_key(&(account_id, calendar_id)) {
+                                    default_alarms.insert(
+                                        (account_id, calendar_id),
+                                        self.fetch_default_alarms(account_id, calendar_id)
+                                            .await
+                                            .caused_by(trc::location!())?,
+                                    );
+                                }
+                                if let Some(injected) = default_alarms[&(account_id, calendar_id)]
+                                    .inject(&serialize_buf)
+                                {
+                                    serialize_buf = injected;
+                                }
+                            }
+
+                            let mut calendar_data = response.take_data_buffer(
+                                if data.data_type == CalendarDataType::ICalendar {
+                                    serialize_buf.len()
+                                } else {
+                                    serialize_buf.len() + (serialize_buf.len() / 2)
+                                },
+                            );
+                            write_calendar_data(&serialize_buf, data.data_type, &mut calendar_data);
 
                             fields.push(DavPropertyValue::new(
                                 property.clone(),
-                                DavValue::CData(ical),
+                                DavValue::CData(calendar_data),
                             ));
                         }
 
@@ -1414,31 +1952,59 @@ impl PropFindRequestHandler for Server {
             }
 
             // Add dead properties
-            if skip_not_found && !dead_properties.0.is_empty() {
+            if let Some(dead_properties) = dead_properties.filter(|_| skip_not_found) {
                 dead_properties.to_dav_values(&mut fields);
             }
 
             // Add response
-            let mut prop_stat = Vec::with_capacity(2);
-            if !fields.is_empty() {
-                prop_stat.push(PropStat::new_list(fields));
-            }
-            if !fields_not_found.is_empty() && !query.is_minimal() {
-                prop_stat
-                    .push(PropStat::new_list(fields_not_found).with_status(StatusCode::NOT_FOUND));
-            }
+            let mut prop_stat = PropStatBuilder::default().with_minimal(query.is_minimal());
+            prop_stat
+                .insert_list_with_status(fields, StatusCode::OK)
+                .insert_list_with_status(fields_not_found, StatusCode::NOT_FOUND);
+            let mut prop_stat = prop_stat.build();
             if prop_stat.is_empty() {
                 prop_stat.push(PropStat::new_list(vec![]));
             }
             response.add_response(Response::new_propstat(item.name, prop_stat));
 
+            // Serialize pending responses and stop once the buffered output exceeds the size budget
+            if response.response.0.len() >= RESPONSE_SPILL_BATCH
+                && response.spill() > self.core.groupware.max_response_size
+            {
+                is_size_limited = true;
+                break;
+            }
+
             limit -= 1;
             if limit == 0 {
+                if is_paged {
+                    next_page = Some(Urn::QueryPage {
+                        after_id: document_id,
+                    });
+                }
                 break;
             }
         }
 
-        if limit == 0 || is_sync_limited {
+        if is_size_limited {
+            // Truncating a sync report would advance the client past unseen changes
+            if !query.sync_type.is_none() {
+                return Err(DavErrorCondition::new(
+                    StatusCode::INSUFFICIENT_STORAGE,
+                    BaseCondition::NumberOfMatchesWithinLimit,
+                )
+                .into());
+            }
+
+            response.add_response(
+                Response::new_status([query.uri], StatusCode::INSUFFICIENT_STORAGE)
+                    .with_error(BaseCondition::NumberOfMatchesWithinLimit)
+                    .with_response_description(format!(
+                        "The response exceeds the maximum size of {} bytes",
+                        self.core.groupware.max_response_size
+                    )),
+            );
+        } else if is_sync_limited || (limit == 0 && query.sync_type.is_none()) {
             response.add_response(
                 Response::new_status([query.uri], StatusCode::INSUFFICIENT_STORAGE)
                     .with_error(BaseCondition::NumberOfMatchesWithinLimit)
@@ -1449,14 +2015,25 @@ impl PropFindRequestHandler for Server {
                             .unwrap_or(self.core.groupware.max_results as u32)
                     )),
             );
-        } else if response.response.0.is_empty() && query.sync_type.is_none() {
+
+            // Clients resume the query from the next page by sending the token
+            // in the page parameter of the request URI
+            if let Some(next_page) = next_page {
+                return Ok(HttpResponse::new(StatusCode::MULTI_STATUS)
+                    .with_header(
+                        "Link",
+                        format!("<{}?page={next_page}>; rel=\"next\"", query.uri),
+                    )
+                    .with_xml_body(response.to_xml()));
+            }
+        } else if !response.has_responses() && query.sync_type.is_none() {
             response.add_response(
                 Response::new_status([query.uri], StatusCode::NOT_FOUND)
                     .with_response_description("No resources found"),
             );
         }
 
-        Ok(HttpResponse::new(StatusCode::MULTI_STATUS).with_xml_body(response.to_string()))
+        Ok(HttpResponse::new(StatusCode::MULTI_STATUS).with_xml_body(response.to_xml()))
     }
 
     async fn dav_quota(
@@ -1468,22 +2045,27 @@ impl PropFindRequestHandler for Server {
             .get_resource_token(access_token, account_id)
             .await
             .caused_by(trc::location!())?;
-        let quota = if resource_token.quota > 0 {
-            resource_token.quota
-        } else if let Some(tenant) = resource_token.tenant.filter(|t| t.quota > 0) {
-            tenant.quota
-        } else {
-            u32::MAX as u64
-        };
         let used = self
             .get_used_quota(account_id)
             .await
             .caused_by(trc::location!())? as u64;
+        let mut available = if resource_token.quota > 0 {
+            resource_token.quota
+        } else {
+            u32::MAX as u64
+        }
+        .saturating_sub(used);
 
-        Ok(PropFindAccountQuota {
-            used,
-            available: quota.saturating_sub(used),
-        })
+        // Space left to the tenant also bounds what the account can store
+        if let Some(tenant) = resource_token.tenant.filter(|t| t.quota > 0) {
+            let tenant_used = self
+                .get_used_quota(tenant.id)
+                .await
+                .caused_by(trc::location!())? as u64;
+            available = available.min(tenant.quota.saturating_sub(tenant_used));
+        }
+
+        Ok(PropFindAccountQuota { used, available })
     }
 }
 
@@ -1521,6 +2103,88 @@ impl PropFindData {
         Ok(data.quota.clone().unwrap())
     }
 
+    pub async fn container_quota(
+        &mut self,
+        server: &Server,
+        access_token: &AccessToken,
+        account_id: u32,
+        collection_container: Collection,
+        item: &PropFindItem,
+    ) -> trc::Result<PropFindAccountQuota> {
+        // Calendars and folders with their own quota report it instead of the account's
+        if matches!(
+            collection_container,
+            Collection::Calendar | Collection::FileNode
+        ) {
+            if let Some(quota) = server
+                .collection_quota(account_id, collection_container, item.document_id)
+                .await?
+            {
+                let resources = self
+                    .resources(
+                        server,
+                        access_token,
+                        account_id,
+                        SyncCollection::from(collection_container),
+                    )
+                    .await?;
+                let used = resources
+                    .container_by_id(item.document_id)
+                    .map(|container| collection_usage(&resources, container))
+                    .unwrap_or_default();
+                let account_quota = self.quota(server, access_token, account_id).await?;
+
+                return Ok(PropFindAccountQuota {
+                    used,
+                    available: quota.saturating_sub(used).min(account_quota.available),
+                });
+            }
+        }
+
+        self.quota(server, access_token, account_id).await
+    }
+
+    pub async fn container_usage(
+        &mut self,
+        server: &Server,
+        access_token: &AccessToken,
+        account_id: u32,
+        collection_container: Collection,
+        item: &PropFindItem,
+    ) -> trc::Result<CollectionUsage> {
+        let resources = self
+            .resources(
+                server,
+                access_token,
+                account_id,
+                SyncCollection::from(collection_container),
+            )
+            .await?;
+        let Some(container) = resources.container_by_id(item.document_id) else {
+            return Ok(CollectionUsage {
+                bytes: 0,
+                items: 0,
+                largest: List(vec![]),
+            });
+        };
+        let usage = resources.usage(container, USAGE_LARGEST_ITEMS);
+
+        Ok(CollectionUsage {
+            bytes: usage.bytes,
+            items: usage.items,
+            largest: List(
+                usage
+                    .largest
+                    .into_iter()
+                    .map(|resource| UsageItem {
+                        href: Href(resources.format_resource(resource)),
+                        size: resource.size() as u64,
+                    })
+                    .collect(),
+            ),
+        })
+    }
+
     pub async fn owner(
         &mut self,
         server: &Server,
@@ -1598,6 +2262,185 @@ impl PropFindData {
     }
 }
 
+trait CachedContainerPropFind: Sync + Send {
+    fn cached_container_propfind(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        collection: Collection,
+        account_id: u32,
+        resource: Option<&str>,
+        request: &PropFind,
+    ) -> impl Future<Output = crate::Result<Option<HttpResponse>>> + Send;
+}
+
+impl CachedContainerPropFind for Server {
+    async fn cached_container_propfind(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        collection: Collection,
+        account_id: u32,
+        resource: Option<&str>,
+        request: &PropFind,
+    ) -> crate::Result<Option<HttpResponse>> {
+        let is_calendar = match collection {
+            Collection::Calendar => true,
+            Collection::AddressBook => false,
+            _ => return Ok(None),
+        };
+        let (PropFind::Prop(properties), Some(resource)) = (request, resource) else {
+            return Ok(None);
+        };
+        if !matches!(headers.depth, Depth::Zero)
+            || headers.depth_no_root
+            || access_token.primary_id() != account_id
+            || properties.is_empty()
+            || !properties.iter().all(|property| {
+                matches!(
+                    property,
+                    DavProperty::WebDav(
+                        WebDavProperty::ResourceType
+                            | WebDavProperty::DisplayName
+                            | WebDavProperty::GetCTag
+                            | WebDavProperty::SyncToken
+                            | WebDavProperty::CurrentUserPrivilegeSet
+                    )
+                )
+            })
+        {
+            return Ok(None);
+        }
+
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::from(collection))
+            .await
+            .caused_by(trc::location!())?;
+        let Some(container) = resources
+            .by_path(resource)
+            .filter(|resource| resource.is_container())
+        else {
+            return Ok(None);
+        };
+
+        let mut response = MultiStatus::new(Vec::with_capacity(1));
+        response.set_namespace(collection.namespace());
+        let mut fields = Vec::with_capacity(properties.len());
+        let mut fields_not_found = Vec::new();
+        for property in properties {
+            match property {
+                DavProperty::WebDav(WebDavProperty::ResourceType) => {
+                    fields.push(DavPropertyValue::new(
+                        property.clone(),
+                        vec![
+                            ResourceType::Collection,
+                            match container.schedule_collection() {
+                                Some(ScheduleCollection::Inbox) => ResourceType::ScheduleInbox,
+                                Some(ScheduleCollection::Outbox) => ResourceType::ScheduleOutbox,
+                                None if is_calendar => ResourceType::Calendar,
+                                None => ResourceType::AddressBook,
+                            },
+                        ],
+                    ));
+                }
+                DavProperty::WebDav(WebDavProperty::DisplayName) => {
+                    if let Some(name) = container.resource.display_name() {
+                        fields.push(DavPropertyValue::new(
+                            property.clone(),
+                            DavValue::String(name.to_string()),
+                        ));
+                    } else {
+                        fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                    }
+                }
+                DavProperty::WebDav(WebDavProperty::GetCTag) => {
+                    fields.push(DavPropertyValue::new(
+                        property.clone(),
+                        DavValue::String(format!("\"{}\"", resources.highest_change_id)),
+                    ));
+                    response.set_namespace(Namespace::CalendarServer);
+                }
+                DavProperty::WebDav(WebDavProperty::SyncToken) => {
+                    fields.push(DavPropertyValue::new(
+                        property.clone(),
+                        resources.sync_token(),
+                    ));
+                }
+                _ => {
+                    // Scheduling and birthday calendars are managed by the server
+                    let mut privileges = Privilege::all(is_calendar);
+                    if container.is_birthday_calendar() {
+                        read_only_privilege_set(&mut privileges, false);
+                    } else if container.schedule_collection().is_some() {
+                        read_only_privilege_set(&mut privileges, true);
+                    }
+                    fields.push(DavPropertyValue::new(property.clone(), privileges));
+                }
+            }
+        }
+
+        let mut prop_stat = PropStatBuilder::default().with_minimal(headers.ret == Return::Minimal);
+        prop_stat
+            .insert_list_with_status(fields, StatusCode::OK)
+            .insert_list_with_status(fields_not_found, StatusCode::NOT_FOUND);
+        response.add_response(Response::new_propstat(
+            resources.format_resource(container),
+            prop_stat.build(),
+        ));
+
+        Ok(Some(
+            HttpResponse::new(StatusCode::MULTI_STATUS).with_xml_body(response.to_string()),
+        ))
+    }
+}
+
+pub(crate) trait ShardedSyncChanges: Sync + Send {
+    fn sync_changes(
+        &self,
+        account_id: u32,
+        sync_collection: SyncCollection,
+        shard_id: Option<u32>,
+        since_change_id: u64,
+        until_change_id: u64,
+    ) -> impl Future<Output = trc::Result<Changes>> + Send;
+}
+
+impl ShardedSyncChanges for Server {
+    async fn sync_changes(
+        &self,
+        account_id: u32,
+        sync_collection: SyncCollection,
+        shard_id: Option<u32>,
+        since_change_id: u64,
+        until_change_id: u64,
+    ) -> trc::Result<Changes> {
+        if until_change_id <= since_change_id {
+            return Ok(Changes::default());
+        }
+        let query = Query::RangeInclusive(since_change_id + 1, until_change_id);
+        if let Some(shard_id) = shard_id {
+            if self
+                .store()
+                .get_shard_epoch(account_id, sync_collection)
+                .await
+                .caused_by(trc::location!())?
+                .is_some_and(|epoch| epoch <= since_change_id)
+            {
+                return self
+                    .store()
+                    .shard_changes(account_id, sync_collection, shard_id, query)
+                    .await
+                    .caused_by(trc::location!());
+            }
+        }
+
+        self.store()
+            .changes(account_id, sync_collection, query)
+            .await
+            .caused_by(trc::location!())
+    }
+}
+
 pub(crate) trait SyncTokenUrn {
     fn sync_token(&self) -> String;
 }
@@ -1606,7 +2449,7 @@ impl SyncTokenUrn for DavResources {
     fn sync_token(&self) -> String {
         Urn::Sync {
             id: self.highest_change_id,
-            seq: 0,
+            epoch: self.sync_epoch,
         }
         .to_string()
     }
@@ -0,0 +1,233 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use common::{Server, auth::AccessToken};
+use dav_proto::{
+    RequestHeaders,
+    parser::tokenizer::Tokenizer,
+    schema::{Element, NamedElement, Namespace, property::Rfc1123DateTime, request::PushRegister},
+};
+use groupware::{
+    DavResourceName,
+    cache::GroupwareCache,
+    push::{DavPush, DavPushKeys, DavPushRegistration, DavPushSubscription},
+};
+use http_proto::{HttpRequest, HttpResponse};
+use hyper::{StatusCode, header};
+use jmap_proto::types::{acl::Acl, collection::SyncCollection};
+use trc::AddContext;
+
+use crate::{
+    DavError,
+    common::{acl::ResourceAcl, uri::DavUriResource},
+};
+
+pub const PUSH_SUBSCRIPTION_PATH: &str = "/dav/push";
+
+pub(crate) trait DavPushRequestHandler: Sync + Send {
+    fn handle_push_register_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        service: DavResourceName,
+        request: PushRegister,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+}
+
+pub trait DavPushUnregisterHandler: Sync + Send {
+    fn handle_push_unregister(
+        &self,
+        req: &HttpRequest,
+        access_token: Arc<AccessToken>,
+    ) -> impl Future<Output = trc::Result<HttpResponse>> + Send;
+}
+
+impl DavPushRequestHandler for Server {
+    async fn handle_push_register_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        service: DavResourceName,
+        request: PushRegister,
+    ) -> crate::Result<HttpResponse> {
+        if self.core.groupware.web_push.is_none() {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+
+        // Only Web Push subscriptions using secure push resources are accepted
+        if request.push_resource.len() > 512 || !request.push_resource.starts_with("https://") {
+            return Err(DavError::Code(StatusCode::BAD_REQUEST));
+        }
+        let keys = match (&request.public_key, &request.auth_secret) {
+            (Some(public_key), Some(auth_secret)) => {
+                if request
+                    .content_encoding
+                    .as_deref()
+                    .is_some_and(|encoding| !encoding.eq_ignore_ascii_case("aes128gcm"))
+                {
+                    return Err(DavError::Code(StatusCode::BAD_REQUEST));
+                }
+                match (decode_key(public_key), decode_key(auth_secret)) {
+                    (Some(p256dh), Some(auth)) => Some(DavPushKeys { p256dh, auth }),
+                    _ => return Err(DavError::Code(StatusCode::BAD_REQUEST)),
+                }
+            }
+            (None, None) => None,
+            _ => return Err(DavError::Code(StatusCode::BAD_REQUEST)),
+        };
+
+        // Validate URI
+        let resource_ = self
+            .validate_uri(access_token, headers.uri)
+            .await?
+            .into_owned_uri()?;
+        let account_id = resource_.account_id;
+        let resources = self
+            .fetch_dav_resources(
+                access_token,
+                account_id,
+                if service == DavResourceName::Cal {
+                    SyncCollection::Calendar
+                } else {
+                    SyncCollection::AddressBook
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+        let document_id = resource_
+            .resource
+            .and_then(|name| resources.by_path(name))
+            .filter(|resource| resource.is_container())
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?
+            .document_id();
+
+        // Validate ACL
+        if !access_token.is_member(account_id)
+            && !resources.has_access_to_container(access_token, document_id, Acl::Read)
+        {
+            return Err(resources.need_privileges(Some(document_id), Acl::Read));
+        }
+
+        // Content updates are sent when no trigger was requested
+        let property_update = request.property_update.is_some();
+        let content_update = request.content_update.is_some() || !property_update;
+
+        match self
+            .register_dav_push(
+                access_token,
+                service,
+                account_id,
+                DavPushSubscription {
+                    document_id,
+                    url: request.push_resource,
+                    keys,
+                    content_update,
+                    property_update,
+                    expires: request.expires.unwrap_or_default().max(0) as u64,
+                    ..Default::default()
+                },
+            )
+            .await
+            .caused_by(trc::location!())?
+        {
+            DavPushRegistration::Created { id, expires } => {
+                Ok(HttpResponse::new(StatusCode::CREATED)
+                    .with_header(
+                        header::LOCATION,
+                        push_registration_url(service, account_id, id),
+                    )
+                    .with_header(
+                        header::EXPIRES,
+                        Rfc1123DateTime::new(expires as i64).to_string(),
+                    ))
+            }
+            DavPushRegistration::Updated { id, expires } => {
+                Ok(HttpResponse::new(StatusCode::NO_CONTENT)
+                    .with_header(
+                        header::LOCATION,
+                        push_registration_url(service, account_id, id),
+                    )
+                    .with_header(
+                        header::EXPIRES,
+                        Rfc1123DateTime::new(expires as i64).to_string(),
+                    ))
+            }
+            DavPushRegistration::TooManySubscriptions | DavPushRegistration::Disabled => {
+                Err(DavError::Code(StatusCode::FORBIDDEN))
+            }
+        }
+    }
+}
+
+impl DavPushUnregisterHandler for Server {
+    async fn handle_push_unregister(
+        &self,
+        req: &HttpRequest,
+        access_token: Arc<AccessToken>,
+    ) -> trc::Result<HttpResponse> {
+        // Registration URLs have the form /dav/push/{service}/{account_id}/{id}
+        let Some((service, account_id, id)) = req
+            .uri()
+            .path()
+            .strip_prefix(PUSH_SUBSCRIPTION_PATH)
+            .and_then(|path| path.strip_prefix('/'))
+            .and_then(parse_push_registration)
+        else {
+            return Ok(HttpResponse::new(StatusCode::NOT_FOUND));
+        };
+
+        if self
+            .unregister_dav_push(&access_token, service, account_id, id)
+            .await?
+        {
+            Ok(HttpResponse::new(StatusCode::NO_CONTENT))
+        } else {
+            Ok(HttpResponse::new(StatusCode::NOT_FOUND))
+        }
+    }
+}
+
+/// Returns true when an XML request body is a WebDAV-Push registration.
+pub(crate) fn is_push_register(body: &[u8]) -> bool {
+    matches!(
+        Tokenizer::new(body).unwrap_named_element(),
+        Ok(NamedElement {
+            ns: Namespace::WebDavPush,
+            element: Element::PushRegister,
+        })
+    )
+}
+
+fn push_registration_url(service: DavResourceName, account_id: u32, id: u64) -> String {
+    format!(
+        "{PUSH_SUBSCRIPTION_PATH}/{}/{account_id}/{id:x}",
+        service.base_path().trim_start_matches("/dav/")
+    )
+}
+
+fn parse_push_registration(path: &str) -> Option<(DavResourceName, u32, u64)> {
+    let mut parts = path.trim_end_matches('/').split('/');
+    let service = DavResourceName::parse(parts.next()?)
+        .filter(|service| matches!(service, DavResourceName::Cal | DavResourceName::Card))?;
+    let account_id = parts.next()?.parse().ok()?;
+    let id = u64::from_str_radix(parts.next()?, 16).ok()?;
+
+    if parts.next().is_none() {
+        Some((service, account_id, id))
+    } else {
+        None
+    }
+}
+
+fn decode_key(key: &str) -> Option<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(key.trim().trim_end_matches('='))
+        .ok()
+        .filter(|key| !key.is_empty())
+}
@@ -0,0 +1,255 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    fmt::{Debug, Write},
+    time::Duration,
+};
+
+use common::{Server, auth::AccessToken};
+use dav_proto::{
+    Depth, RequestHeaders,
+    parser::{DavParser, tokenizer::Tokenizer},
+    schema::{
+        property::{CalDavProperty, DavProperty, TimeRange},
+        request::{Filter, FilterOp, PropFind, Report},
+    },
+};
+use groupware::{DavResourceName, cache::GroupwareCache};
+use jmap_proto::types::collection::SyncCollection;
+
+use crate::{DavMethod, common::uri::DavUriResource};
+
+pub(crate) trait SlowRequestLog: Sync + Send {
+    #[allow(clippy::too_many_arguments)]
+    fn log_slow_request(
+        &self,
+        access_token: &AccessToken,
+        session_id: u64,
+        headers: &RequestHeaders<'_>,
+        resource: DavResourceName,
+        method: DavMethod,
+        body: &[u8],
+        elapsed: Duration,
+    ) -> impl Future<Output = ()> + Send;
+}
+
+impl SlowRequestLog for Server {
+    async fn log_slow_request(
+        &self,
+        access_token: &AccessToken,
+        session_id: u64,
+        headers: &RequestHeaders<'_>,
+        resource: DavResourceName,
+        method: DavMethod,
+        body: &[u8],
+        elapsed: Duration,
+    ) {
+        // Describe the shape of the request without including any values
+        let mut details = String::new();
+        let mut range = None;
+        match method {
+            DavMethod::REPORT => {
+                if let Ok(report) = Report::parse(&mut Tokenizer::new(body)) {
+                    report_shape(&report, &mut details, &mut range);
+                }
+            }
+            DavMethod::PROPFIND => {
+                if let Ok(propfind) = PropFind::parse(&mut Tokenizer::new(body)) {
+                    propfind_shape(&propfind, &mut details);
+                }
+            }
+            _ => {
+                if let Some(destination) = headers.destination {
+                    let _ = write!(&mut details, "destination={destination}");
+                }
+            }
+        }
+        let _ = write!(
+            &mut details,
+            " depth={}",
+            match headers.depth {
+                Depth::Zero => "0",
+                Depth::One => "1",
+                Depth::Infinity => "infinity",
+                Depth::None => "none",
+            }
+        );
+
+        // Number of resources under the requested path
+        let mut collection_size = None;
+        if resource != DavResourceName::Principal {
+            if let Ok(uri) = self.validate_uri(access_token, headers.uri).await {
+                if let Some(account_id) = uri.account_id {
+                    if let Ok(resources) = self
+                        .fetch_dav_resources(
+                            access_token,
+                            account_id,
+                            SyncCollection::from(uri.collection),
+                        )
+                        .await
+                    {
+                        collection_size = Some(match uri.resource {
+                            Some(path) => resources.subtree(path).count(),
+                            None => resources.paths.len(),
+                        });
+                    }
+                }
+            }
+        }
+
+        trc::event!(
+            WebDav(trc::WebDavEvent::SlowRequest),
+            SpanId = session_id,
+            AccountId = access_token.primary_id(),
+            Url = headers.uri.to_string(),
+            Type = resource.name(),
+            Id = trc::WebDavEvent::from(method).name(),
+            Details = details.trim_start().to_string(),
+            Total = collection_size,
+            RangeFrom = range
+                .filter(|range: &TimeRange| range.start != i64::MIN)
+                .map(|range| trc::Value::Timestamp(range.start as u64)),
+            RangeTo = range
+                .filter(|range: &TimeRange| range.end != i64::MAX)
+                .map(|range| trc::Value::Timestamp(range.end as u64)),
+            Elapsed = elapsed,
+        );
+    }
+}
+
+fn report_shape(report: &Report, out: &mut String, range: &mut Option<TimeRange>) {
+    match report {
+        Report::CalendarQuery(query) => {
+            out.push_str("calendar-query");
+            filter_shape(&query.filters, out, range);
+            propfind_shape(&query.properties, out);
+            if let PropFind::Prop(items) | PropFind::AllProp(items) = &query.properties {
+                for item in items {
+                    if let DavProperty::CalDav(CalDavProperty::CalendarData(data)) = item {
+                        if let Some(expand) = data.expand.or(data.limit_recurrence) {
+                            out.push_str(" expand");
+                            *range = Some(expand);
+                        }
+                    }
+                }
+            }
+        }
+        Report::AddressbookQuery(query) => {
+            out.push_str("addressbook-query");
+            filter_shape(&query.filters, out, range);
+            propfind_shape(&query.properties, out);
+            if let Some(limit) = query.limit {
+                let _ = write!(out, " limit={limit}");
+            }
+        }
+        Report::CalendarMultiGet(multiget) | Report::AddressbookMultiGet(multiget) => {
+            let _ = write!(
+                out,
+                "{} hrefs={}",
+                if matches!(report, Report::CalendarMultiGet(_)) {
+                    "calendar-multiget"
+                } else {
+                    "addressbook-multiget"
+                },
+                multiget.hrefs.len()
+            );
+            propfind_shape(&multiget.properties, out);
+        }
+        Report::SyncCollection(sync) => {
+            let _ = write!(
+                out,
+                "sync-collection {}",
+                if sync.sync_token.is_some() {
+                    "incremental"
+                } else {
+                    "initial"
+                }
+            );
+            propfind_shape(&sync.properties, out);
+            if let Some(limit) = sync.limit {
+                let _ = write!(out, " limit={limit}");
+            }
+        }
+        Report::FreeBusyQuery(query) => {
+            out.push_str("free-busy-query");
+            *range = query.range;
+        }
+        Report::ExpandProperty(_) => out.push_str("expand-property"),
+        Report::AclPrincipalPropSet(_) => out.push_str("acl-principal-prop-set"),
+        Report::PrincipalMatch(_) => out.push_str("principal-match"),
+        Report::PrincipalPropertySearch(_) => out.push_str("principal-property-search"),
+        Report::PrincipalSearchPropertySet => out.push_str("principal-search-property-set"),
+        Report::VersionTree(report) => {
+            out.push_str("version-tree");
+            propfind_shape(&report.properties, out);
+        }
+    }
+}
+
+fn propfind_shape(propfind: &PropFind, out: &mut String) {
+    match propfind {
+        PropFind::PropName => out.push_str(" propname"),
+        PropFind::AllProp(items) => {
+            let _ = write!(out, " allprop include={}", items.len());
+        }
+        PropFind::Prop(items) => {
+            let _ = write!(out, " props={}", items.len());
+        }
+    }
+}
+
+fn filter_shape<A: Debug, B: Debug, C: Debug>(
+    filters: &[Filter<A, B, C>],
+    out: &mut String,
+    range: &mut Option<TimeRange>,
+) {
+    out.push_str(" filter=[");
+    for (pos, filter) in filters.iter().enumerate() {
+        if pos > 0 {
+            out.push_str(", ");
+        }
+        let op = match filter {
+            Filter::AnyOf => {
+                out.push_str("anyof");
+                continue;
+            }
+            Filter::AllOf => {
+                out.push_str("allof");
+                continue;
+            }
+            Filter::Component { comp, op } => {
+                let _ = write!(out, "comp:{comp:?}");
+                op
+            }
+            Filter::Property { comp, prop, op } => {
+                let _ = write!(out, "prop:{comp:?}/{prop:?}");
+                op
+            }
+            Filter::Parameter {
+                comp,
+                prop,
+                param,
+                op,
+            } => {
+                let _ = write!(out, "param:{comp:?}/{prop:?}/{param:?}");
+                op
+            }
+        };
+        match op {
+            FilterOp::Exists => {}
+            FilterOp::Undefined => out.push_str(" undefined"),
+            FilterOp::TimeRange(time_range) => {
+                out.push_str(" time-range");
+                range.get_or_insert(*time_range);
+            }
+            FilterOp::TextMatch(text_match) => {
+                let _ = write!(out, " text-match:{:?}", text_match.match_type);
+            }
+        }
+    }
+    out.push(']');
+}
@@ -8,15 +8,19 @@ use std::fmt::Display;
 
 use common::{Server, auth::AccessToken};
 
+use dav_proto::schema::response::{BaseCondition, ResourceNameError};
 use directory::backend::internal::manage::ManageDirectory;
 
 use groupware::cache::GroupwareCache;
 use http_proto::request::decode_path_element;
 use hyper::StatusCode;
 use jmap_proto::types::collection::Collection;
+use percent_encoding::percent_decode_str;
 use trc::AddContext;
 
-use crate::{DavError, DavResourceName};
+use crate::{DavError, DavErrorCondition, DavResourceName};
+
+use super::SyncType;
 
 #[derive(Debug)]
 pub(crate) struct UriResource<A, R> {
@@ -27,7 +31,25 @@ pub(crate) struct UriResource<A, R> {
 
 pub(crate) enum Urn {
     Lock(u64),
-    Sync { id: u64, seq: u32 },
+    /// Sync token issued at change `id`, only valid while the sync epoch of
+    /// the collection remains `epoch`.
+    Sync {
+        id: u64,
+        epoch: u64,
+    },
+    /// Intermediate token of a truncated sync report, resuming the changes
+    /// after `id` (or the initial listing) up to `to_id` from `offset`.
+    SyncPage {
+        id: Option<u64>,
+        to_id: u64,
+        offset: u32,
+        epoch: u64,
+    },
+    /// Continuation token of a truncated query report, resuming after the
+    /// resource with the given document id.
+    QueryPage {
+        after_id: u32,
+    },
 }
 
 pub(crate) type UnresolvedUri<'x> = UriResource<Option<u32>, Option<&'x str>>;
@@ -181,6 +203,44 @@ impl OwnedUri<'_> {
     }
 }
 
+/// Maximum length in bytes of a single resource name.
+pub(crate) const MAX_RESOURCE_NAME_LEN: usize = 255;
+
+/// Validates the last segment of a path before it is used to create or rename
+/// a resource, so that only names that can be safely turned back into hrefs
+/// make it into the hierarchy.
+pub(crate) fn validate_resource_name(name: &str) -> crate::Result<()> {
+    let error = match percent_decode_str(name).decode_utf8() {
+        Ok(decoded) => {
+            if name.len() > MAX_RESOURCE_NAME_LEN || decoded.len() > MAX_RESOURCE_NAME_LEN {
+                ResourceNameError::TooLong
+            } else if decoded.is_empty() || decoded == "." || decoded == ".." {
+                ResourceNameError::Reserved
+            } else if decoded
+                .chars()
+                .any(|ch| ch.is_control() || matches!(ch, '/' | '\\'))
+            {
+                ResourceNameError::ForbiddenCharacter
+            } else if decoded.trim() != decoded {
+                ResourceNameError::NotNormalized
+            } else {
+                return Ok(());
+            }
+        }
+        Err(_) => ResourceNameError::InvalidEncoding,
+    };
+
+    Err(DavErrorCondition::new(
+        if error == ResourceNameError::Reserved {
+            StatusCode::CONFLICT
+        } else {
+            StatusCode::UNPROCESSABLE_ENTITY
+        },
+        BaseCondition::ValidResourceName(error),
+    )
+    .into())
+}
+
 impl<A, R> UriResource<A, R> {
     pub fn collection_path(&self) -> &'static str {
         DavResourceName::from(self.collection).collection_path()
@@ -200,16 +260,33 @@ impl Urn {
         match kind {
             "davlock" => u64::from_str_radix(id, 16).ok().map(Urn::Lock),
             "davsync" => {
-                if let Some((id, seq)) = id.split_once(':') {
-                    let id = u64::from_str_radix(id, 16).ok()?;
-                    let seq = u32::from_str_radix(seq, 16).ok()?;
-                    Some(Urn::Sync { id, seq })
-                } else {
-                    u64::from_str_radix(id, 16)
+                // Tokens issued before the first epoch reset have no epoch suffix
+                let (id, epoch) = match id.split_once('@') {
+                    Some((id, epoch)) => (id, u64::from_str_radix(epoch, 16).ok()?),
+                    None => (id, 0),
+                };
+                let mut parts = id.split(':');
+                let id = parts.next()?;
+                match (parts.next(), parts.next()) {
+                    (Some(to_id), Some(offset)) => Some(Urn::SyncPage {
+                        id: if !id.is_empty() {
+                            Some(u64::from_str_radix(id, 16).ok()?)
+                        } else {
+                            None
+                        },
+                        to_id: u64::from_str_radix(to_id, 16).ok()?,
+                        offset: u32::from_str_radix(offset, 16).ok()?,
+                        epoch,
+                    }),
+                    // Page tokens issued by earlier versions restart the paging
+                    _ => u64::from_str_radix(id, 16)
                         .ok()
-                        .map(|id| Urn::Sync { id, seq: 0 })
+                        .map(|id| Urn::Sync { id, epoch }),
                 }
             }
+            "davquery" => u32::from_str_radix(id, 16)
+                .ok()
+                .map(|after_id| Urn::QueryPage { after_id }),
             _ => None,
         }
     }
@@ -221,9 +298,42 @@ impl Urn {
         }
     }
 
-    pub fn try_unwrap_sync(&self) -> Option<(u64, u32)> {
+    pub fn try_unwrap_query_page(&self) -> Option<u32> {
         match self {
-            Urn::Sync { id, seq } => Some((*id, *seq)),
+            Urn::QueryPage { after_id } => Some(*after_id),
+            _ => None,
+        }
+    }
+
+    pub fn try_unwrap_sync(&self) -> Option<SyncType> {
+        match self {
+            Urn::Sync { id, epoch } => Some(SyncType::From {
+                id: *id,
+                to_id: None,
+                offset: 0,
+                epoch: *epoch,
+            }),
+            Urn::SyncPage {
+                id: Some(id),
+                to_id,
+                offset,
+                epoch,
+            } => Some(SyncType::From {
+                id: *id,
+                to_id: Some(*to_id),
+                offset: *offset,
+                epoch: *epoch,
+            }),
+            Urn::SyncPage {
+                id: None,
+                to_id,
+                offset,
+                epoch,
+            } => Some(SyncType::Initial {
+                to_id: Some(*to_id),
+                offset: *offset,
+                epoch: Some(*epoch),
+            }),
             _ => None,
         }
     }
@@ -233,13 +343,37 @@ impl Display for Urn {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Urn::Lock(id) => write!(f, "urn:stalwart:davlock:{id:x}",),
-            Urn::Sync { id, seq } => {
-                if *seq == 0 {
-                    write!(f, "urn:stalwart:davsync:{id:x}")
-                } else {
-                    write!(f, "urn:stalwart:davsync:{id:x}:{seq:x}")
-                }
+            Urn::Sync { id, epoch } => {
+                write!(f, "urn:stalwart:davsync:{id:x}")?;
+                write_epoch(f, *epoch)
             }
+            Urn::SyncPage {
+                id: Some(id),
+                to_id,
+                offset,
+                epoch,
+            } => {
+                write!(f, "urn:stalwart:davsync:{id:x}:{to_id:x}:{offset:x}")?;
+                write_epoch(f, *epoch)
+            }
+            Urn::SyncPage {
+                id: None,
+                to_id,
+                offset,
+                epoch,
+            } => {
+                write!(f, "urn:stalwart:davsync::{to_id:x}:{offset:x}")?;
+                write_epoch(f, *epoch)
+            }
+            Urn::QueryPage { after_id } => write!(f, "urn:stalwart:davquery:{after_id:x}"),
         }
     }
 }
+
+fn write_epoch(f: &mut std::fmt::Formatter<'_>, epoch: u64) -> std::fmt::Result {
+    if epoch > 0 {
+        write!(f, "@{epoch:x}")
+    } else {
+        Ok(())
+    }
+}
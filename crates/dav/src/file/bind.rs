@@ -0,0 +1,346 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{
+    DavError, DavMethod,
+    common::{
+        ExtractETag,
+        acl::ResourceAcl,
+        lock::{LockRequestHandler, ResourceState},
+        uri::DavUriResource,
+    },
+};
+use common::{DavName, Server, auth::AccessToken};
+use dav_proto::{
+    RequestHeaders,
+    schema::request::{Bind, Unbind},
+};
+use groupware::{DestroyArchive, cache::GroupwareCache, file::FileNode};
+use http_proto::HttpResponse;
+use hyper::StatusCode;
+use jmap_proto::types::{
+    acl::Acl,
+    collection::{Collection, SyncCollection, VanishedCollection},
+};
+use store::write::BatchBuilder;
+use trc::AddContext;
+
+pub(crate) trait FileBindRequestHandler: Sync + Send {
+    fn handle_file_bind_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        request: Bind,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+
+    fn handle_file_unbind_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        request: Unbind,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+}
+
+impl FileBindRequestHandler for Server {
+    async fn handle_file_bind_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        request: Bind,
+    ) -> crate::Result<HttpResponse> {
+        let (Some(segment), Some(href)) = (
+            request
+                .segment
+                .filter(|segment| !segment.is_empty() && !segment.contains('/')),
+            request.href,
+        ) else {
+            return Err(DavError::Code(StatusCode::BAD_REQUEST));
+        };
+
+        // Validate the collection the binding is added to
+        let collection_ = self
+            .validate_uri(access_token, headers.uri)
+            .await?
+            .into_owned_uri()?;
+        let account_id = collection_.account_id;
+        let is_member = access_token.is_member(account_id);
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::FileNode)
+            .await
+            .caused_by(trc::location!())?;
+        let collection_path = collection_.resource.filter(|path| !path.is_empty());
+        let collection_id = if let Some(collection_path) = collection_path {
+            let collection = resources
+                .by_path(collection_path)
+                .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+            if !collection.is_container() {
+                return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+            }
+            Some(collection.document_id())
+        } else {
+            None
+        };
+        let parent_id = resources.validate_and_map_parent_acl(
+            access_token,
+            is_member,
+            collection_id,
+            Acl::AddItems,
+        )?;
+        let binding_path = if let Some(collection_path) = collection_path {
+            format!("{collection_path}/{segment}")
+        } else {
+            segment.clone()
+        };
+
+        // Validate the bound resource, bindings cannot cross accounts
+        let source = self
+            .validate_uri_with_status(access_token, &href, StatusCode::CONFLICT)
+            .await?;
+        if source.collection != Collection::FileNode || source.account_id != Some(account_id) {
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        }
+        let source_path = source
+            .resource
+            .ok_or(DavError::Code(StatusCode::CONFLICT))?;
+        let source = resources
+            .by_path(source_path)
+            .ok_or(DavError::Code(StatusCode::CONFLICT))?;
+        let document_id = source.document_id();
+        let source_acls = if request.is_rebind {
+            vec![Acl::Read, Acl::Delete]
+        } else {
+            vec![Acl::Read]
+        };
+        if source.is_container() {
+            // Only files can have multiple bindings
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        } else if !is_member
+            && !resources.has_access_to_container(access_token, document_id, &source_acls)
+        {
+            return Err(resources.need_privileges(Some(document_id), source_acls));
+        }
+        let source_parent_id = source.parent_id().map(|id| id + 1).unwrap_or(0);
+        let source_name = source_path
+            .rsplit_once('/')
+            .map_or(source_path, |(_, name)| name)
+            .to_string();
+
+        // Bindings to other resources are replaced
+        let existing = resources
+            .by_path(&binding_path)
+            .filter(|existing| existing.document_id() != document_id);
+        if let Some(existing) = &existing {
+            if existing.is_container() {
+                return Err(DavError::Code(StatusCode::FORBIDDEN));
+            } else if !is_member
+                && !resources.has_access_to_container(
+                    access_token,
+                    existing.document_id(),
+                    Acl::Delete,
+                )
+            {
+                return Err(resources.need_privileges(Some(existing.document_id()), Acl::Delete));
+            }
+        }
+
+        // Validate headers
+        self.validate_headers(
+            access_token,
+            headers,
+            vec![
+                ResourceState {
+                    account_id,
+                    collection: Collection::FileNode,
+                    document_id: Some(document_id),
+                    path: source_path,
+                    ..Default::default()
+                },
+                ResourceState {
+                    account_id,
+                    collection: Collection::FileNode,
+                    document_id: Some(
+                        existing
+                            .as_ref()
+                            .map(|existing| existing.document_id())
+                            .unwrap_or(u32::MAX),
+                    ),
+                    path: &binding_path,
+                    ..Default::default()
+                },
+            ],
+            Default::default(),
+            if request.is_rebind {
+                DavMethod::REBIND
+            } else {
+                DavMethod::BIND
+            },
+        )
+        .await?;
+
+        let mut batch = BatchBuilder::new();
+        if let Some(existing) = &existing {
+            unbind_or_destroy(
+                self,
+                access_token,
+                account_id,
+                existing.document_id(),
+                parent_id,
+                &segment,
+                resources.format_item(&binding_path),
+                &mut batch,
+            )
+            .await?;
+        }
+
+        let node_ = self
+            .get_archive(account_id, Collection::FileNode, document_id)
+            .await
+            .caused_by(trc::location!())?
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        let node = node_
+            .to_unarchived::<FileNode>()
+            .caused_by(trc::location!())?;
+        let mut new_node = node.deserialize::<FileNode>().caused_by(trc::location!())?;
+        if request.is_rebind {
+            if source_parent_id == parent_id && source_name == segment {
+                return Ok(HttpResponse::new(StatusCode::OK));
+            }
+            new_node.rebind(source_parent_id, &source_name, parent_id, segment);
+        } else if !new_node.is_bound_to(parent_id, &segment) {
+            new_node.bindings.push(DavName::new(segment, parent_id));
+        } else {
+            return Ok(HttpResponse::new(StatusCode::OK));
+        }
+
+        let etag = new_node
+            .update(access_token, node, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?
+            .etag();
+        if request.is_rebind {
+            batch.log_vanished_item(
+                VanishedCollection::FileNode,
+                resources.format_item(source_path),
+            );
+        }
+        self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+        Ok(HttpResponse::new(if existing.is_some() {
+            StatusCode::OK
+        } else {
+            StatusCode::CREATED
+        })
+        .with_etag_opt(etag))
+    }
+
+    async fn handle_file_unbind_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        request: Unbind,
+    ) -> crate::Result<HttpResponse> {
+        let segment = request
+            .segment
+            .filter(|segment| !segment.is_empty() && !segment.contains('/'))
+            .ok_or(DavError::Code(StatusCode::BAD_REQUEST))?;
+
+        // Validate URI
+        let collection_ = self
+            .validate_uri(access_token, headers.uri)
+            .await?
+            .into_owned_uri()?;
+        let account_id = collection_.account_id;
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::FileNode)
+            .await
+            .caused_by(trc::location!())?;
+        let binding_path =
+            if let Some(collection_path) = collection_.resource.filter(|path| !path.is_empty()) {
+                format!("{collection_path}/{segment}")
+            } else {
+                segment.clone()
+            };
+        let resource = resources
+            .by_path(&binding_path)
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        let document_id = resource.document_id();
+        if resource.is_container() {
+            // Collections are removed with DELETE
+            return Err(DavError::Code(StatusCode::FORBIDDEN));
+        } else if !access_token.is_member(account_id)
+            && !resources.has_access_to_container(access_token, document_id, Acl::Delete)
+        {
+            return Err(resources.need_privileges(Some(document_id), Acl::Delete));
+        }
+
+        // Validate headers
+        self.validate_headers(
+            access_token,
+            headers,
+            vec![ResourceState {
+                account_id,
+                collection: Collection::FileNode,
+                document_id: Some(document_id),
+                path: &binding_path,
+                ..Default::default()
+            }],
+            Default::default(),
+            DavMethod::UNBIND,
+        )
+        .await?;
+
+        let mut batch = BatchBuilder::new();
+        unbind_or_destroy(
+            self,
+            access_token,
+            account_id,
+            document_id,
+            resource.parent_id().map(|id| id + 1).unwrap_or(0),
+            &segment,
+            resources.format_item(&binding_path),
+            &mut batch,
+        )
+        .await?;
+        self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+        Ok(HttpResponse::new(StatusCode::OK))
+    }
+}
+
+// Removes a single binding, the node is destroyed along with its last binding
+#[allow(clippy::too_many_arguments)]
+async fn unbind_or_destroy(
+    server: &Server,
+    access_token: &AccessToken,
+    account_id: u32,
+    document_id: u32,
+    parent_id: u32,
+    name: &str,
+    href: String,
+    batch: &mut BatchBuilder,
+) -> crate::Result<()> {
+    let node_ = server
+        .get_archive(account_id, Collection::FileNode, document_id)
+        .await
+        .caused_by(trc::location!())?
+        .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+    let node = node_
+        .to_unarchived::<FileNode>()
+        .caused_by(trc::location!())?;
+    let mut new_node = node.deserialize::<FileNode>().caused_by(trc::location!())?;
+
+    if new_node.unbind(parent_id, name) {
+        new_node
+            .update(access_token, node, account_id, document_id, batch)
+            .caused_by(trc::location!())?
+            .log_vanished_item(VanishedCollection::FileNode, href);
+    } else {
+        DestroyArchive(node)
+            .delete(access_token, account_id, document_id, batch, href)
+            .caused_by(trc::location!())?;
+    }
+
+    Ok(())
+}
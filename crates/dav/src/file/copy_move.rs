@@ -9,15 +9,22 @@ use crate::{
     DavError, DavMethod,
     common::{
         ExtractETag,
+        acl::{ResourceAcl, need_privileges_condition},
+        copy_move_events,
+        limits::{CollectionQuotaCheck, ItemLimitCheck},
         lock::{LockRequestHandler, ResourceState},
-        uri::{DavUriResource, UriResource},
+        uri::{DavUriResource, UriResource, validate_resource_name},
     },
     file::{DavFileResource, FileItemId},
 };
 use common::{
-    DavResourcePath, DavResources, Server, auth::AccessToken, storage::index::ObjectIndexBuilder,
+    DavName, DavResourcePath, DavResources, Server, auth::AccessToken,
+    storage::index::ObjectIndexBuilder,
+};
+use dav_proto::{
+    Depth, RequestHeaders,
+    schema::response::{MultiStatus, Response},
 };
-use dav_proto::{Depth, RequestHeaders};
 use groupware::{DestroyArchive, cache::GroupwareCache, file::FileNode};
 use http_proto::HttpResponse;
 use hyper::StatusCode;
@@ -28,10 +35,14 @@ use jmap_proto::types::{
 use std::sync::Arc;
 use store::{
     ahash::AHashMap,
-    write::{BatchBuilder, now},
+    write::{Archive, BatchBuilder, now},
 };
 use trc::AddContext;
 
+#[cfg(feature = "test_mode")]
+pub static FAIL_MOVE_DELETE: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
 pub(crate) trait FileCopyMoveRequestHandler: Sync + Send {
     fn handle_file_copy_move_request(
         &self,
@@ -63,19 +74,18 @@ impl FileCopyMoveRequestHandler for Server {
 
         // Validate source ACLs
         if !access_token.is_member(from_account_id) {
-            let shared = from_resources.shared_containers(
-                access_token,
-                if is_move {
-                    [Acl::Read, Acl::Delete].as_slice().iter().copied()
-                } else {
-                    [Acl::Read].as_slice().iter().copied()
-                },
-                false,
-            );
+            let source_acls = if is_move {
+                [Acl::Read, Acl::Delete].as_slice()
+            } else {
+                [Acl::Read].as_slice()
+            };
+            let shared =
+                from_resources.shared_containers(access_token, source_acls.iter().copied(), false);
 
             for resource in from_resources.subtree(from_resource_.resource.unwrap()) {
                 if !shared.contains(resource.document_id()) {
-                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                    return Err(from_resources
+                        .need_privileges(Some(resource.document_id()), source_acls.to_vec()));
                 }
             }
         }
@@ -86,16 +96,16 @@ impl FileCopyMoveRequestHandler for Server {
                 access_token,
                 headers
                     .destination
-                    .ok_or(DavError::Code(StatusCode::BAD_GATEWAY))?,
-                StatusCode::BAD_GATEWAY,
+                    .ok_or(DavError::Code(StatusCode::BAD_REQUEST))?,
+                StatusCode::BAD_REQUEST,
             )
             .await?;
         if destination.collection != Collection::FileNode {
-            return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+            return Err(DavError::Code(StatusCode::CONFLICT));
         }
         let to_account_id = destination
             .account_id
-            .ok_or(DavError::Code(StatusCode::BAD_GATEWAY))?;
+            .ok_or(DavError::Code(StatusCode::CONFLICT))?;
         let to_resources = if to_account_id == from_account_id {
             from_resources.clone()
         } else {
@@ -107,14 +117,22 @@ impl FileCopyMoveRequestHandler for Server {
         // Map file item
         let destination_resource_name = destination
             .resource
-            .ok_or(DavError::Code(StatusCode::BAD_GATEWAY))?;
-        if from_account_id == to_account_id
-            && (from_resource_name == destination_resource_name
+            .ok_or(DavError::Code(StatusCode::FORBIDDEN))?;
+        if from_account_id == to_account_id {
+            if from_resource_name == destination_resource_name {
+                // Source and destination are the same resource (RFC 4918, section 9.8.5)
+                return Err(DavError::Code(StatusCode::FORBIDDEN));
+            } else if destination_resource_name
+                .strip_prefix(from_resource_name)
+                .is_some_and(|v| v.starts_with('/'))
                 || from_resource_name
                     .strip_prefix(destination_resource_name)
-                    .is_some_and(|v| v.is_empty() || v.starts_with('/')))
-        {
-            return Ok(HttpResponse::new(StatusCode::BAD_GATEWAY));
+                    .is_some_and(|v| v.starts_with('/'))
+            {
+                // A collection cannot be copied or moved into its own subtree, nor
+                // replace one of its ancestors
+                return Err(DavError::Code(StatusCode::CONFLICT));
+            }
         }
 
         // Check if the resource exists
@@ -130,8 +148,10 @@ impl FileCopyMoveRequestHandler for Server {
                     existing_destination.account_id = to_account_id;
                     delete_destination = Some(existing_destination);
                 } else {
-                    return Ok(HttpResponse::new(StatusCode::PRECONDITION_FAILED));
+                    return Err(DavError::Code(StatusCode::PRECONDITION_FAILED));
                 }
+            } else {
+                validate_resource_name(new_name)?;
             }
 
             let mut destination = destination
@@ -147,24 +167,21 @@ impl FileCopyMoveRequestHandler for Server {
         // Validate destination ACLs
         if let Some(document_id) = destination.document_id {
             if let Some(delete_destination) = &delete_destination {
+                let delete_id = delete_destination.document_id.unwrap();
                 if !access_token.is_member(to_account_id)
-                    && !from_resources.has_access_to_container(
-                        access_token,
-                        delete_destination.document_id.unwrap(),
-                        Acl::Delete,
-                    )
+                    && !to_resources.has_access_to_container(access_token, delete_id, Acl::Delete)
                 {
-                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                    return Err(to_resources.need_privileges(Some(delete_id), Acl::Delete));
                 }
             }
 
             if !access_token.is_member(to_account_id)
-                && !from_resources.has_access_to_container(access_token, document_id, Acl::Modify)
+                && !to_resources.has_access_to_container(access_token, document_id, Acl::Modify)
             {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(to_resources.need_privileges(Some(document_id), Acl::Modify));
             }
         } else if !access_token.is_member(to_account_id) {
-            return Err(DavError::Code(StatusCode::FORBIDDEN));
+            return Err(to_resources.need_privileges(None, Acl::AddItems));
         }
 
         // Validate headers
@@ -201,6 +218,18 @@ impl FileCopyMoveRequestHandler for Server {
         )
         .await?;
 
+        let (from_href, to_href) = if from_resource.resource.is_container {
+            (
+                from_resources.format_collection(from_resource_name),
+                to_resources.format_collection(destination_resource_name),
+            )
+        } else {
+            (
+                from_resources.format_item(from_resource_name),
+                to_resources.format_item(destination_resource_name),
+            )
+        };
+
         if delete_destination.is_none()
             && from_account_id == destination.account_id
             && from_resource.resource.parent_id == destination.document_id
@@ -208,19 +237,53 @@ impl FileCopyMoveRequestHandler for Server {
             && is_move
         {
             // Rename
-            let from_resource_path = if from_resource.resource.is_container {
-                from_resources.format_collection(from_resource_name)
-            } else {
-                from_resources.format_item(from_resource_name)
-            };
-            return rename_item(
+            let result = rename_item(
                 self,
                 access_token,
                 from_resource,
-                from_resource_path,
+                from_href.clone(),
                 destination,
             )
             .await;
+            if let Ok(response) = &result {
+                copy_move_events(
+                    response,
+                    is_move,
+                    Collection::FileNode,
+                    from_account_id,
+                    from_href,
+                    to_account_id,
+                    to_href,
+                );
+            }
+            return result;
+        }
+
+        // Validate item limit and folder quotas
+        if delete_destination.is_none()
+            && (!is_move
+                || from_account_id != to_account_id
+                || from_resource.resource.parent_id != destination.document_id)
+        {
+            self.assert_item_limit(
+                access_token,
+                &to_resources,
+                to_account_id,
+                Collection::FileNode,
+                destination.document_id,
+            )
+            .await?;
+            self.assert_collection_quota(
+                &to_resources,
+                to_account_id,
+                Collection::FileNode,
+                destination.document_id,
+                from_resources
+                    .subtree(from_resource_name)
+                    .map(|a| a.size() as u64)
+                    .sum::<u64>(),
+            )
+            .await?;
         }
 
         // Validate quota
@@ -247,6 +310,26 @@ impl FileCopyMoveRequestHandler for Server {
                 .subtree(destination_resource_name)
                 .collect::<Vec<_>>();
             if !ids.is_empty() {
+                // Members that cannot be deleted are reported individually and the
+                // destination is left untouched (RFC 4918, sections 9.6.1 and 9.8.8)
+                if !access_token.is_member(to_account_id) {
+                    let permissions =
+                        to_resources.shared_containers(access_token, [Acl::Delete], false);
+                    let failed = ids
+                        .iter()
+                        .filter(|item| !permissions.contains(item.document_id()))
+                        .map(|item| {
+                            let href = to_resources.format_resource(*item);
+                            Response::new_status([href.clone()], StatusCode::FORBIDDEN)
+                                .with_error(need_privileges_condition(href, Acl::Delete))
+                        })
+                        .collect::<Vec<_>>();
+                    if !failed.is_empty() {
+                        return Ok(HttpResponse::new(StatusCode::MULTI_STATUS)
+                            .with_xml_body(MultiStatus::new(failed).to_string()));
+                    }
+                }
+
                 ids.sort_unstable_by_key(|b| std::cmp::Reverse(b.hierarchy_seq()));
                 let mut sorted_ids = Vec::with_capacity(ids.len());
                 sorted_ids.extend(ids.into_iter().map(|a| a.document_id()));
@@ -257,7 +340,7 @@ impl FileCopyMoveRequestHandler for Server {
             }
         }
 
-        match (from_resource.resource.is_container, is_move) {
+        let result = match (from_resource.resource.is_container, is_move) {
             (true, true) => {
                 move_container(
                     self,
@@ -319,7 +402,21 @@ impl FileCopyMoveRequestHandler for Server {
             } else {
                 r
             }
-        })
+        });
+
+        if let Ok(response) = &result {
+            copy_move_events(
+                response,
+                is_move,
+                Collection::FileNode,
+                from_account_id,
+                from_href,
+                to_account_id,
+                to_href,
+            );
+        }
+
+        result
     }
 }
 
@@ -428,7 +525,6 @@ async fn copy_container(
 
     let from_account_id = from_resource.account_id;
     let to_account_id = destination.account_id;
-    let parent_id = destination.document_id.map(|id| id + 1).unwrap_or(0);
 
     // Obtain files to copy
     let mut copy_files = if infinity_copy {
@@ -444,20 +540,84 @@ async fn copy_container(
     };
 
     // Top-down copy
-    let mut batch = BatchBuilder::new();
-    let mut id_map = AHashMap::with_capacity(copy_files.len());
+    copy_files.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+    let mut created_ids = Vec::with_capacity(copy_files.len());
     let mut delete_files = if delete_source {
         Vec::with_capacity(copy_files.len())
     } else {
         Vec::new()
     };
-    copy_files.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+    if let Err(err) = copy_nodes(
+        server,
+        access_token,
+        from_account_id,
+        &mut destination,
+        copy_files,
+        &mut created_ids,
+        delete_source.then_some(&mut delete_files),
+    )
+    .await
+    {
+        rollback_copy(server, access_token, to_account_id, created_ids).await;
+        return Err(err);
+    }
+
+    // Delete nodes, restoring the source and undoing the copy if any batch fails
+    // so a failed MOVE does not leave the tree at both locations
+    if !delete_files.is_empty() {
+        let mut deleted_nodes = Vec::with_capacity(delete_files.len());
+        if let Err(err) = delete_nodes(
+            server,
+            access_token,
+            from_account_id,
+            delete_files,
+            from_resources.format_collection(from_resource_name),
+            &mut deleted_nodes,
+        )
+        .await
+        {
+            if let Err(restore_err) =
+                restore_nodes(server, access_token, from_account_id, deleted_nodes).await
+            {
+                trc::error!(
+                    restore_err
+                        .account_id(from_account_id)
+                        .details("Failed to restore source nodes of a failed move")
+                );
+            } else {
+                rollback_copy(server, access_token, to_account_id, created_ids).await;
+            }
+
+            return Err(err);
+        }
+    }
+
+    Ok(HttpResponse::new(StatusCode::CREATED))
+}
+
+// Copies nodes top-down into the destination, committing size-bounded batches as it goes.
+// The ids of all committed nodes are recorded in `created_ids` so the caller can undo them.
+async fn copy_nodes(
+    server: &Server,
+    access_token: &AccessToken,
+    from_account_id: u32,
+    destination: &mut Destination,
+    copy_files: Vec<(u32, u32)>,
+    created_ids: &mut Vec<u32>,
+    mut delete_files: Option<&mut Vec<(u32, Archive<FileNode>)>>,
+) -> crate::Result<()> {
+    let to_account_id = destination.account_id;
+    let parent_id = destination.document_id.map(|id| id + 1).unwrap_or(0);
+    let mut batch = BatchBuilder::new();
+    let mut pending_ids = Vec::new();
+    let mut id_map = AHashMap::with_capacity(copy_files.len());
     let now = now() as i64;
     let mut next_document_id = server
         .store()
         .assign_document_ids(to_account_id, Collection::FileNode, copy_files.len() as u64)
         .await
         .caused_by(trc::location!())?;
+
     for (document_id, _) in copy_files.into_iter() {
         let node_ = server
             .get_archive(from_account_id, Collection::FileNode, document_id)
@@ -468,12 +628,12 @@ async fn copy_container(
             .caused_by(trc::location!())?;
 
         // Build node
-        let mut node = if !delete_source {
-            node_.inner
-        } else {
+        let mut node = if let Some(delete_files) = delete_files.as_deref_mut() {
             let node = node_.inner.clone();
             delete_files.push((document_id, node_));
             node
+        } else {
+            node_.inner
         };
         node.modified = now;
         node.created = now;
@@ -485,6 +645,7 @@ async fn copy_container(
         } else {
             parent_id
         };
+        node.bindings.clear();
 
         // Prepare write batch
         let new_document_id = next_document_id;
@@ -500,32 +661,133 @@ async fn copy_container(
             )
             .caused_by(trc::location!())?
             .commit_point();
+        pending_ids.push(new_document_id);
         id_map.insert(document_id + 1, new_document_id + 1);
+
+        // Flush large batches
+        if batch.is_large_batch() {
+            server
+                .commit_batch(std::mem::take(&mut batch))
+                .await
+                .caused_by(trc::location!())?;
+            created_ids.append(&mut pending_ids);
+        }
     }
 
-    // Delete nodes
-    if !delete_files.is_empty() {
-        for (document_id, node) in delete_files.into_iter().rev() {
-            // Delete record
-            batch
-                .with_account_id(from_account_id)
-                .with_collection(Collection::FileNode)
-                .delete_document(document_id)
-                .custom(
-                    ObjectIndexBuilder::<_, ()>::new()
-                        .with_tenant_id(access_token)
-                        .with_current(node),
-                )
-                .caused_by(trc::location!())?
-                .commit_point();
+    // Write remaining changes
+    if !batch.is_empty() {
+        server
+            .commit_batch(batch)
+            .await
+            .caused_by(trc::location!())?;
+        created_ids.append(&mut pending_ids);
+    }
+
+    Ok(())
+}
+
+// Undoes the batches committed by `copy_nodes`, deepest nodes first
+async fn rollback_copy(
+    server: &Server,
+    access_token: &AccessToken,
+    account_id: u32,
+    mut created_ids: Vec<u32>,
+) {
+    if !created_ids.is_empty() {
+        created_ids.reverse();
+        if let Err(rollback_err) = DestroyArchive(created_ids)
+            .delete(server, access_token, account_id, None)
+            .await
+        {
+            trc::error!(
+                rollback_err
+                    .account_id(account_id)
+                    .details("Failed to rollback partial container copy")
+            );
+        }
+    }
+}
+
+// Deletes the source nodes of a move bottom-up, committing size-bounded batches as it goes.
+// The nodes of all committed batches are recorded in `deleted_nodes` so the caller can restore them.
+async fn delete_nodes(
+    server: &Server,
+    access_token: &AccessToken,
+    account_id: u32,
+    delete_files: Vec<(u32, Archive<FileNode>)>,
+    vanished_path: String,
+    deleted_nodes: &mut Vec<(u32, FileNode)>,
+) -> crate::Result<()> {
+    let mut batch = BatchBuilder::new();
+    let mut pending_nodes = Vec::new();
+
+    for (document_id, node) in delete_files.into_iter().rev() {
+        pending_nodes.push((document_id, node.inner.clone()));
+
+        // Delete record
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::FileNode)
+            .delete_document(document_id)
+            .custom(
+                ObjectIndexBuilder::<_, ()>::new()
+                    .with_tenant_id(access_token)
+                    .with_current(node),
+            )
+            .caused_by(trc::location!())?
+            .commit_point();
+
+        if batch.is_large_batch() {
+            server
+                .commit_batch(std::mem::take(&mut batch))
+                .await
+                .caused_by(trc::location!())?;
+            deleted_nodes.append(&mut pending_nodes);
+            fail_move_delete()?;
+        }
+    }
+
+    batch
+        .with_account_id(account_id)
+        .log_vanished_item(VanishedCollection::FileNode, vanished_path);
+    server
+        .commit_batch(batch)
+        .await
+        .caused_by(trc::location!())?;
+    deleted_nodes.append(&mut pending_nodes);
+    fail_move_delete()
+}
+
+// Recreates the source nodes deleted by a failed move, parents first
+async fn restore_nodes(
+    server: &Server,
+    access_token: &AccessToken,
+    account_id: u32,
+    deleted_nodes: Vec<(u32, FileNode)>,
+) -> trc::Result<()> {
+    let mut batch = BatchBuilder::new();
+
+    for (document_id, node) in deleted_nodes.into_iter().rev() {
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::FileNode)
+            .create_document(document_id)
+            .custom(
+                ObjectIndexBuilder::<(), _>::new()
+                    .with_changes(node)
+                    .with_tenant_id(access_token),
+            )
+            .caused_by(trc::location!())?
+            .commit_point();
+
+        if batch.is_large_batch() {
+            server
+                .commit_batch(std::mem::take(&mut batch))
+                .await
+                .caused_by(trc::location!())?;
         }
-        batch.with_account_id(from_account_id).log_vanished_item(
-            VanishedCollection::FileNode,
-            from_resources.format_collection(from_resource_name),
-        );
     }
 
-    // Write changes
     if !batch.is_empty() {
         server
             .commit_batch(batch)
@@ -533,7 +795,22 @@ async fn copy_container(
             .caused_by(trc::location!())?;
     }
 
-    Ok(HttpResponse::new(StatusCode::CREATED))
+    Ok(())
+}
+
+// Simulates a store failure after a batch of source nodes was deleted
+#[inline(always)]
+fn fail_move_delete() -> crate::Result<()> {
+    #[cfg(feature = "test_mode")]
+    if FAIL_MOVE_DELETE.load(std::sync::atomic::Ordering::Relaxed) {
+        return Err(DavError::Internal(
+            trc::StoreEvent::UnexpectedError
+                .into_err()
+                .details("Simulated move failure"),
+        ));
+    }
+
+    Ok(())
 }
 
 // Overwrites the contents of one file with another, then deletes the original
@@ -578,6 +855,12 @@ async fn overwrite_and_delete_item(
         dest_node.inner.name.to_string()
     };
     source_node.parent_id = dest_node.inner.parent_id.into();
+    source_node.bindings = dest_node
+        .inner
+        .bindings
+        .iter()
+        .map(|binding| DavName::new(binding.name.to_string(), binding.parent_id.into()))
+        .collect();
 
     let mut batch = BatchBuilder::new();
     let etag = source_node
@@ -644,6 +927,12 @@ async fn overwrite_item(
         dest_node.inner.name.to_string()
     };
     source_node.parent_id = dest_node.inner.parent_id.into();
+    source_node.bindings = dest_node
+        .inner
+        .bindings
+        .iter()
+        .map(|binding| DavName::new(binding.name.to_string(), binding.parent_id.into()))
+        .collect();
     let mut batch = BatchBuilder::new();
     let etag = source_node
         .update(
@@ -685,9 +974,30 @@ async fn move_item(
         .to_unarchived::<FileNode>()
         .caused_by(trc::location!())?;
     let mut new_node = node.deserialize::<FileNode>().caused_by(trc::location!())?;
-    new_node.parent_id = parent_id;
-    if let Some(new_name) = destination.new_name {
-        new_node.name = new_name;
+    if from_account_id == to_account_id && !new_node.bindings.is_empty() {
+        // Only the binding the item was addressed by is moved
+        let from_name = from_resource_path.rsplit('/').next().unwrap_or_default();
+        let new_name = destination
+            .new_name
+            .unwrap_or_else(|| from_name.to_string());
+        new_node.rebind(
+            from_resource
+                .resource
+                .parent_id
+                .map(|id| id + 1)
+                .unwrap_or(0),
+            from_name,
+            parent_id,
+            new_name,
+        );
+    } else {
+        new_node.parent_id = parent_id;
+        if let Some(new_name) = destination.new_name {
+            new_node.name = new_name;
+        }
+        if from_account_id != to_account_id {
+            new_node.bindings.clear();
+        }
     }
 
     let mut batch = BatchBuilder::new();
@@ -754,6 +1064,7 @@ async fn copy_item(
         .deserialize::<FileNode>()
         .caused_by(trc::location!())?;
     node.parent_id = parent_id;
+    node.bindings.clear();
     if let Some(new_name) = destination.new_name {
         node.name = new_name;
     }
@@ -796,7 +1107,18 @@ async fn rename_item(
         .caused_by(trc::location!())?;
     let mut new_node = node.deserialize::<FileNode>().caused_by(trc::location!())?;
     if let Some(new_name) = destination.new_name {
-        new_node.name = new_name;
+        if new_node.bindings.is_empty() {
+            new_node.name = new_name;
+        } else {
+            // Only the binding the item was addressed by is renamed
+            let parent_id = from_resource
+                .resource
+                .parent_id
+                .map(|id| id + 1)
+                .unwrap_or(0);
+            let from_name = from_resource_path.rsplit('/').next().unwrap_or_default();
+            new_node.rebind(parent_id, from_name, parent_id, new_name);
+        }
     }
     let mut batch = BatchBuilder::new();
     let etag = new_node
@@ -7,16 +7,28 @@
 use crate::{
     DavError, DavMethod,
     common::{
+        acl::ResourceAcl,
         lock::{LockRequestHandler, ResourceState},
         uri::DavUriResource,
     },
 };
-use common::{Server, auth::AccessToken};
+use common::{DavResourceMetadata, Server, auth::AccessToken};
 use dav_proto::RequestHeaders;
-use groupware::{DestroyArchive, cache::GroupwareCache};
+use groupware::{
+    DestroyArchive,
+    cache::GroupwareCache,
+    file::{
+        FileNode,
+        trash::{FileTrash, is_trash_path},
+    },
+};
 use http_proto::HttpResponse;
 use hyper::StatusCode;
-use jmap_proto::types::{acl::Acl, collection::SyncCollection};
+use jmap_proto::types::{
+    acl::Acl,
+    collection::{Collection, SyncCollection, VanishedCollection},
+};
+use store::{ahash::AHashMap, write::BatchBuilder};
 use trc::AddContext;
 
 pub(crate) trait FileDeleteRequestHandler: Sync + Send {
@@ -60,16 +72,51 @@ impl FileDeleteRequestHandler for Server {
             .last()
             .map(|a| (a.document_id(), resources.format_resource(*a)))
             .unwrap();
+
+        // Files still bound outside the deleted tree only lose their bindings
+        let mut bound_paths: AHashMap<u32, Vec<_>> = AHashMap::new();
+        for item in &ids {
+            if let DavResourceMetadata::File { bindings, .. } = &item.resource.data {
+                if !bindings.is_empty() {
+                    bound_paths
+                        .entry(item.document_id())
+                        .or_default()
+                        .push(*item);
+                }
+            }
+        }
+        let mut unbind_ids = AHashMap::new();
+        for (document_id, paths) in bound_paths {
+            if let DavResourceMetadata::File { bindings, .. } = &paths[0].resource.data {
+                if paths.len() <= bindings.len() {
+                    unbind_ids.insert(document_id, paths);
+                }
+            }
+        }
+        ids.retain(|item| !unbind_ids.contains_key(&item.document_id()));
         let mut sorted_ids = Vec::with_capacity(ids.len());
-        sorted_ids.extend(ids.into_iter().map(|a| a.document_id()));
+        for id in ids.into_iter().map(|a| a.document_id()) {
+            if !sorted_ids.contains(&id) {
+                sorted_ids.push(id);
+            }
+        }
 
         // Validate ACLs
         if !access_token.is_member(account_id) {
             let permissions = resources.shared_containers(access_token, [Acl::Delete], false);
-            if permissions.len() != sorted_ids.len() as u64
-                || !sorted_ids.iter().all(|id| permissions.contains(*id))
+            if permissions.len() != (sorted_ids.len() + unbind_ids.len()) as u64
+                || !sorted_ids
+                    .iter()
+                    .chain(unbind_ids.keys())
+                    .all(|id| permissions.contains(*id))
             {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                let denied_id = sorted_ids
+                    .iter()
+                    .chain(unbind_ids.keys())
+                    .find(|id| !permissions.contains(**id))
+                    .copied()
+                    .unwrap_or(document_id);
+                return Err(resources.need_privileges(Some(denied_id), Acl::Delete));
             }
         }
 
@@ -89,9 +136,69 @@ impl FileDeleteRequestHandler for Server {
         )
         .await?;
 
-        DestroyArchive(sorted_ids)
-            .delete(self, access_token, account_id, full_delete_path.into())
+        if !unbind_ids.is_empty() {
+            let mut batch = BatchBuilder::new();
+            for (document_id, paths) in unbind_ids {
+                let Some(node_) = self
+                    .get_archive(account_id, Collection::FileNode, document_id)
+                    .await
+                    .caused_by(trc::location!())?
+                else {
+                    continue;
+                };
+                let node = node_
+                    .to_unarchived::<FileNode>()
+                    .caused_by(trc::location!())?;
+                let mut new_node = node.deserialize::<FileNode>().caused_by(trc::location!())?;
+                for path in paths {
+                    new_node.unbind(
+                        path.parent_id().map(|id| id + 1).unwrap_or(0),
+                        path.path()
+                            .rsplit_once('/')
+                            .map_or(path.path(), |(_, name)| name),
+                    );
+                }
+                new_node
+                    .update(access_token, node, account_id, document_id, &mut batch)
+                    .caused_by(trc::location!())?;
+            }
+            if sorted_ids.is_empty() {
+                batch.log_vanished_item(VanishedCollection::FileNode, full_delete_path.clone());
+            }
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+        }
+
+        if self.core.groupware.file_trash
+            && !is_trash_path(delete_path)
+            && sorted_ids.contains(&document_id)
+        {
+            // Descendants follow their parent into the trash
+            self.move_to_trash(
+                access_token,
+                &resources,
+                account_id,
+                document_id,
+                delete_path,
+            )
             .await?;
+        } else {
+            DestroyArchive(sorted_ids)
+                .delete(
+                    self,
+                    access_token,
+                    account_id,
+                    full_delete_path.clone().into(),
+                )
+                .await?;
+        }
+
+        trc::event!(
+            Groupware(trc::GroupwareEvent::FileDeleted),
+            AccountId = account_id,
+            Collection = Collection::FileNode,
+            DocumentId = document_id,
+            Url = full_delete_path,
+        );
 
         Ok(HttpResponse::new(StatusCode::NO_CONTENT))
     }
@@ -5,20 +5,22 @@
  */
 
 use common::{Server, auth::AccessToken, sharing::EffectiveAcl};
-use dav_proto::{RequestHeaders, schema::property::Rfc1123DateTime};
+use dav_proto::{RequestHeaders, parser::header::etag_matches, schema::property::Rfc1123DateTime};
 use groupware::{cache::GroupwareCache, file::FileNode};
 use http_proto::HttpResponse;
-use hyper::StatusCode;
+use hyper::{StatusCode, header};
 use jmap_proto::types::{
     acl::Acl,
     collection::{Collection, SyncCollection},
 };
 use trc::AddContext;
+use utils::url_params::UrlParams;
 
 use crate::{
     DavError, DavMethod,
     common::{
         ETag,
+        acl::ResourceAcl,
         lock::{LockRequestHandler, ResourceState},
         uri::DavUriResource,
     },
@@ -30,6 +32,7 @@ pub(crate) trait FileGetRequestHandler: Sync + Send {
         &self,
         access_token: &AccessToken,
         headers: &RequestHeaders<'_>,
+        query: Option<&str>,
         is_head: bool,
     ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
 }
@@ -39,6 +42,7 @@ impl FileGetRequestHandler for Server {
         &self,
         access_token: &AccessToken,
         headers: &RequestHeaders<'_>,
+        query: Option<&str>,
         is_head: bool,
     ) -> crate::Result<HttpResponse> {
         // Validate URI
@@ -65,7 +69,7 @@ impl FileGetRequestHandler for Server {
         if !access_token.is_member(account_id)
             && !node.acls.effective_acl(access_token).contains(Acl::Read)
         {
-            return Err(DavError::Code(StatusCode::FORBIDDEN));
+            return Err(files.need_privileges(Some(resource.resource), Acl::Read));
         }
 
         let (hash, size, content_type) = if let Some(file) = node.file.as_ref() {
@@ -78,6 +82,25 @@ impl FileGetRequestHandler for Server {
             return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
         };
 
+        // Previous versions are served from the file URL
+        let version = if let Some(version) = UrlParams::new(query).get("version") {
+            let version = version
+                .parse::<u32>()
+                .map_err(|_| DavError::Code(StatusCode::BAD_REQUEST))?;
+            if version != node.current_version() {
+                Some(
+                    node.versions
+                        .iter()
+                        .find(|v| v.version.to_native() == version)
+                        .ok_or(DavError::Code(StatusCode::NOT_FOUND))?,
+                )
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
         // Validate headers
         let etag = node_.etag();
         self.validate_headers(
@@ -96,12 +119,72 @@ impl FileGetRequestHandler for Server {
         )
         .await?;
 
-        let response = HttpResponse::new(StatusCode::OK)
-            .with_content_type(content_type.unwrap_or("application/octet-stream"))
-            .with_etag(etag)
-            .with_last_modified(Rfc1123DateTime::new(i64::from(node.modified)).to_string());
+        let (hash, size, last_modified, response) = if let Some(version) = version {
+            let last_modified = Rfc1123DateTime::new(i64::from(version.modified)).to_string();
+            (
+                version.file.blob_hash.0.as_ref(),
+                u32::from(version.file.size) as usize,
+                last_modified.clone(),
+                HttpResponse::new(StatusCode::OK)
+                    .with_content_type(
+                        version
+                            .file
+                            .media_type
+                            .as_ref()
+                            .map_or("application/octet-stream", |s| s.as_str()),
+                    )
+                    .with_last_modified(last_modified),
+            )
+        } else {
+            let last_modified = Rfc1123DateTime::new(i64::from(node.modified)).to_string();
+            (
+                hash,
+                size,
+                last_modified.clone(),
+                HttpResponse::new(StatusCode::OK)
+                    .with_content_type(content_type.unwrap_or("application/octet-stream"))
+                    .with_etag(etag.clone())
+                    .with_last_modified(last_modified),
+            )
+        };
+        let response = response.with_header(header::ACCEPT_RANGES, "bytes");
+
+        if is_head {
+            return Ok(response.with_content_length(size));
+        }
+
+        // Byte ranges are only served when the If-Range validator, if any,
+        // still matches the selected representation (RFC 9110, section 13.1.5)
+        let range = headers.range.filter(|_| {
+            headers.if_range.is_none_or(|if_range| {
+                if if_range.starts_with('"') || if_range.starts_with("W/") {
+                    version.is_none() && etag_matches(&etag, if_range, true)
+                } else {
+                    if_range == last_modified
+                }
+            })
+        });
 
-        if !is_head {
+        if let Some(range) = range {
+            let Some(range) = range.resolve(size as u64) else {
+                return Ok(HttpResponse::new(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .with_header(header::CONTENT_RANGE, format!("bytes */{size}")));
+            };
+
+            Ok(response
+                .with_status_code(StatusCode::PARTIAL_CONTENT)
+                .with_header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{size}", range.start, range.end - 1),
+                )
+                .with_binary_body(
+                    self.blob_store()
+                        .get_blob(hash, range.start as usize..range.end as usize)
+                        .await
+                        .caused_by(trc::location!())?
+                        .ok_or(DavError::Code(StatusCode::NOT_FOUND))?,
+                ))
+        } else {
             Ok(response.with_binary_body(
                 self.blob_store()
                     .get_blob(hash, 0..usize::MAX)
@@ -109,8 +192,6 @@ impl FileGetRequestHandler for Server {
                     .caused_by(trc::location!())?
                     .ok_or(DavError::Code(StatusCode::NOT_FOUND))?,
             ))
-        } else {
-            Ok(response.with_content_length(size))
         }
     }
 }
@@ -10,8 +10,9 @@ use crate::{
     common::{
         ExtractETag,
         acl::ResourceAcl,
+        limits::ItemLimitCheck,
         lock::{LockRequestHandler, ResourceState},
-        uri::DavUriResource,
+        uri::{DavUriResource, validate_resource_name},
     },
     file::DavFileResource,
 };
@@ -57,6 +58,7 @@ impl FileMkColRequestHandler for Server {
             .await
             .caused_by(trc::location!())?;
         let resource = resources.map_parent_resource(&resource_)?;
+        validate_resource_name(resource.resource.1)?;
 
         // Validate and map parent ACL
         let parent_id = resources.validate_and_map_parent_acl(
@@ -82,6 +84,16 @@ impl FileMkColRequestHandler for Server {
         )
         .await?;
 
+        // Validate item limit
+        self.assert_item_limit(
+            access_token,
+            &resources,
+            account_id,
+            Collection::FileNode,
+            resource.resource.0,
+        )
+        .await?;
+
         // Build file container
         let now = now();
         let mut node = FileNode {
@@ -93,6 +105,9 @@ impl FileMkColRequestHandler for Server {
             modified: now as i64,
             dead_properties: Default::default(),
             acls: Default::default(),
+            bindings: Default::default(),
+            versions: Default::default(),
+            trash: None,
         };
 
         // Apply MKCOL properties
@@ -101,7 +116,7 @@ impl FileMkColRequestHandler for Server {
             let mut prop_stat = PropStatBuilder::default();
             if !self.apply_file_properties(&mut node, false, mkcol.props, &mut prop_stat) {
                 return Ok(HttpResponse::new(StatusCode::FORBIDDEN).with_xml_body(
-                    MkColResponse::new(prop_stat.build())
+                    MkColResponse::new(prop_stat.with_failed_dependencies().build())
                         .with_namespace(Namespace::Dav)
                         .to_string(),
                 ));
@@ -127,6 +142,14 @@ impl FileMkColRequestHandler for Server {
         let etag = batch.etag();
         self.commit_batch(batch).await.caused_by(trc::location!())?;
 
+        trc::event!(
+            Groupware(trc::GroupwareEvent::FileCreated),
+            AccountId = account_id,
+            Collection = Collection::FileNode,
+            DocumentId = document_id,
+            Url = resources.format_collection(resource_.resource.unwrap()),
+        );
+
         if let Some(prop_stat) = return_prop_stat {
             Ok(HttpResponse::new(StatusCode::CREATED)
                 .with_xml_body(
@@ -12,12 +12,16 @@ use common::{DavResourcePath, DavResources};
 use dav_proto::schema::property::{DavProperty, WebDavProperty};
 use hyper::StatusCode;
 
+pub mod bind;
 pub mod copy_move;
 pub mod delete;
 pub mod get;
 pub mod mkcol;
+pub mod mount;
 pub mod proppatch;
+pub mod trash;
 pub mod update;
+pub mod version;
 
 pub(crate) static FILE_CONTAINER_PROPS: [DavProperty; 19] = [
     DavProperty::WebDav(WebDavProperty::CreationDate),
@@ -41,7 +45,7 @@ pub(crate) static FILE_CONTAINER_PROPS: [DavProperty; 19] = [
     DavProperty::WebDav(WebDavProperty::QuotaUsedBytes),
 ];
 
-pub(crate) static FILE_ITEM_PROPS: [DavProperty; 19] = [
+pub(crate) static FILE_ITEM_PROPS: [DavProperty; 21] = [
     DavProperty::WebDav(WebDavProperty::CreationDate),
     DavProperty::WebDav(WebDavProperty::DisplayName),
     DavProperty::WebDav(WebDavProperty::GetETag),
@@ -61,6 +65,8 @@ pub(crate) static FILE_ITEM_PROPS: [DavProperty; 19] = [
     DavProperty::WebDav(WebDavProperty::GetContentLanguage),
     DavProperty::WebDav(WebDavProperty::GetContentLength),
     DavProperty::WebDav(WebDavProperty::GetContentType),
+    DavProperty::WebDav(WebDavProperty::VersionHistory),
+    DavProperty::WebDav(WebDavProperty::VersionName),
 ];
 
 pub(crate) trait FromDavResource {
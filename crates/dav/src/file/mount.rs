@@ -0,0 +1,627 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    fs::Metadata,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use common::{Server, auth::AccessToken, config::groupware::FileMount, sharing::EffectiveAcl};
+use dav_proto::{
+    Depth, RequestHeaders, Return,
+    parser::DavParser,
+    schema::{
+        property::{DavProperty, DavValue, ResourceType, Rfc1123DateTime, WebDavProperty},
+        request::{DavPropertyValue, PropFind},
+        response::{MultiStatus, PropStat, Response},
+    },
+};
+use directory::{Permission, backend::internal::manage::ManageDirectory};
+use groupware::{
+    cache::GroupwareCache,
+    file::FileNode,
+    icap::{ContentInspection, ContentKind, InspectionResult},
+};
+use http_body_util::{StreamBody, combinators::BoxBody};
+use http_proto::{HttpResponse, request::decode_path_element};
+use hyper::{
+    StatusCode,
+    body::{Bytes, Frame},
+};
+use jmap_proto::types::{
+    acl::Acl,
+    collection::{Collection, SyncCollection},
+};
+use percent_encoding::NON_ALPHANUMERIC;
+use tokio::io::AsyncReadExt;
+use trc::AddContext;
+
+use crate::{
+    DavError, DavMethod, PropStatBuilder,
+    common::{acl::ResourceAcl, uri::DavUriResource},
+    file::update::FileUpload,
+    request::DavRequestParser,
+};
+
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+pub(crate) struct MountTarget<'x> {
+    pub mount: &'x FileMount,
+    pub path: PathBuf,
+    pub is_root: bool,
+    pub can_write: bool,
+}
+
+pub(crate) trait FileMountRequestHandler: Sync + Send {
+    fn resolve_file_mount(
+        &self,
+        access_token: &AccessToken,
+        uri: &str,
+    ) -> impl Future<Output = crate::Result<Option<MountTarget<'_>>>> + Send;
+
+    fn handle_file_mount_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        target: MountTarget<'_>,
+        method: DavMethod,
+        body: Vec<u8>,
+        upload: FileUpload<'_>,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+}
+
+impl FileMountRequestHandler for Server {
+    async fn resolve_file_mount(
+        &self,
+        access_token: &AccessToken,
+        uri: &str,
+    ) -> crate::Result<Option<MountTarget<'_>>> {
+        if self.core.groupware.file_mounts.is_empty() {
+            return Ok(None);
+        }
+
+        // Invalid URIs are rejected by the regular file handlers
+        let Ok(uri) = self.validate_uri(access_token, uri).await else {
+            return Ok(None);
+        };
+        let (Some(account_id), Some(resource)) = (uri.account_id, uri.resource) else {
+            return Ok(None);
+        };
+        if uri.collection != Collection::FileNode {
+            return Ok(None);
+        }
+        let resource = decode_path_element(resource);
+
+        for mount in &self.core.groupware.file_mounts {
+            let sub_path = if resource == mount.path.as_str() {
+                ""
+            } else if let Some(sub_path) = resource
+                .strip_prefix(mount.path.as_str())
+                .and_then(|path| path.strip_prefix('/'))
+            {
+                sub_path
+            } else {
+                continue;
+            };
+            let mount_account_id = match mount.account_id.get() {
+                Some(mount_account_id) => *mount_account_id,
+                None => {
+                    let Some(mount_account_id) = self
+                        .store()
+                        .get_principal_id(&mount.account)
+                        .await
+                        .caused_by(trc::location!())?
+                    else {
+                        continue;
+                    };
+                    *mount.account_id.get_or_init(|| mount_account_id)
+                }
+            };
+            if mount_account_id != account_id {
+                continue;
+            }
+
+            // The mount point is an existing folder whose ACLs apply to the mounted tree
+            let resources = self
+                .fetch_dav_resources(access_token, account_id, SyncCollection::FileNode)
+                .await
+                .caused_by(trc::location!())?;
+            let Some(document_id) = resources
+                .by_path(&mount.path)
+                .filter(|resource| resource.is_container())
+                .map(|resource| resource.document_id())
+            else {
+                return Ok(None);
+            };
+            let node_ = self
+                .get_archive(account_id, Collection::FileNode, document_id)
+                .await
+                .caused_by(trc::location!())?
+                .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+            let node = node_.unarchive::<FileNode>().caused_by(trc::location!())?;
+            let (can_read, can_write) = if access_token.is_member(account_id) {
+                (true, true)
+            } else {
+                let acl = node.acls.effective_acl(access_token);
+                (acl.contains(Acl::Read), acl.contains(Acl::Modify))
+            };
+            if !can_read {
+                return Err(resources.need_privileges(Some(document_id), Acl::Read));
+            }
+
+            let mut path = mount.root.clone();
+            for part in sub_path.split('/').filter(|part| !part.is_empty()) {
+                if part == "." || part == ".." || part.contains('\\') {
+                    return Err(DavError::Code(StatusCode::BAD_REQUEST));
+                }
+                path.push(part);
+            }
+
+            return Ok(Some(MountTarget {
+                mount,
+                path: confine_path(&mount.root, path).await?,
+                is_root: sub_path.trim_matches('/').is_empty(),
+                can_write: can_write && !mount.read_only,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    async fn handle_file_mount_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        target: MountTarget<'_>,
+        method: DavMethod,
+        body: Vec<u8>,
+        upload: FileUpload<'_>,
+    ) -> crate::Result<HttpResponse> {
+        let metadata = tokio::fs::metadata(&target.path).await.ok();
+
+        match method {
+            DavMethod::PROPFIND => {
+                access_token.assert_has_permission(Permission::DavFilePropFind)?;
+                let metadata = metadata.ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+                let request = if !body.is_empty() {
+                    PropFind::parse(&mut self.request_tokenizer(&body))?
+                } else {
+                    PropFind::AllProp(vec![])
+                };
+                let base_uri = headers.uri.trim_end_matches('/');
+                let mut response = MultiStatus::new(Vec::with_capacity(16));
+
+                if !headers.depth_no_root || metadata.is_file() {
+                    response.add_response(mount_propstat(
+                        if metadata.is_dir() {
+                            format!("{base_uri}/")
+                        } else {
+                            base_uri.to_string()
+                        },
+                        if target.is_root {
+                            target.mount.path.clone()
+                        } else {
+                            file_name(&target.path)
+                        },
+                        &metadata,
+                        &request,
+                        headers.ret == Return::Minimal,
+                    ));
+                }
+
+                // Nested folders are never expanded beyond the first level
+                if metadata.is_dir() && headers.depth != Depth::Zero {
+                    let mut entries = tokio::fs::read_dir(&target.path)
+                        .await
+                        .map_err(into_dav_error)?;
+                    while let Some(entry) = entries.next_entry().await.map_err(into_dav_error)? {
+                        let Ok(metadata) = entry.metadata().await else {
+                            continue;
+                        };
+                        let Some(name) = entry.file_name().to_str().map(|name| name.to_string())
+                        else {
+                            continue;
+                        };
+                        // Entry metadata does not follow symbolic links, which are not listed
+                        if !metadata.is_dir() && !metadata.is_file() {
+                            continue;
+                        }
+                        let href = format!(
+                            "{base_uri}/{}{}",
+                            percent_encoding::utf8_percent_encode(&name, NON_ALPHANUMERIC),
+                            if metadata.is_dir() { "/" } else { "" }
+                        );
+                        response.add_response(mount_propstat(
+                            href,
+                            name,
+                            &metadata,
+                            &request,
+                            headers.ret == Return::Minimal,
+                        ));
+                    }
+                }
+
+                Ok(HttpResponse::new(StatusCode::MULTI_STATUS).with_xml_body(response.to_string()))
+            }
+            DavMethod::GET | DavMethod::HEAD => {
+                access_token.assert_has_permission(Permission::DavFileGet)?;
+                let metadata = metadata.ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+                if !metadata.is_file() {
+                    return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+                }
+
+                let response = HttpResponse::new(StatusCode::OK)
+                    .with_content_type(content_type(&target.path))
+                    .with_etag(etag(&metadata))
+                    .with_last_modified(
+                        Rfc1123DateTime::new(timestamp(metadata.modified())).to_string(),
+                    )
+                    .with_content_length(metadata.len() as usize);
+                if metadata.len() > self.core.groupware.max_file_size as u64 {
+                    return Err(DavError::Code(StatusCode::PAYLOAD_TOO_LARGE));
+                } else if matches!(method, DavMethod::HEAD) {
+                    return Ok(response);
+                }
+
+                let mut file = tokio::fs::File::open(&target.path)
+                    .await
+                    .map_err(into_dav_error)?
+                    .take(metadata.len());
+                Ok(response.with_stream_body(BoxBody::new(StreamBody::new(
+                    async_stream::stream! {
+                        let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+                        loop {
+                            match file.read(&mut buf).await {
+                                Ok(0) => break,
+                                Ok(len) => {
+                                    yield Ok(Frame::data(Bytes::copy_from_slice(&buf[..len])));
+                                }
+                                Err(err) => {
+                                    trc::error!(
+                                        trc::StoreEvent::UnexpectedError
+                                            .reason(err)
+                                            .details("External storage operation failed")
+                                    );
+                                    break;
+                                }
+                            }
+                        }
+                    },
+                ))))
+            }
+            DavMethod::PUT | DavMethod::POST | DavMethod::PATCH => {
+                access_token.assert_has_permission(Permission::DavFilePut)?;
+                if !target.can_write {
+                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                } else if target.is_root || metadata.as_ref().is_some_and(|m| m.is_dir()) {
+                    return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+                } else if !parent_exists(&target.path).await {
+                    return Err(DavError::Code(StatusCode::CONFLICT));
+                }
+
+                let upload = upload.read(self, self.core.groupware.max_file_size).await?;
+                let load = self.is_inspected(access_token, ContentKind::File, upload.size);
+                let upload = upload.load_if(load).await?;
+                if let Some(bytes) = upload.bytes() {
+                    if self
+                        .inspect_content(
+                            access_token,
+                            ContentKind::File,
+                            headers.uri,
+                            headers.content_type,
+                            bytes,
+                        )
+                        .await
+                        != InspectionResult::Allowed
+                    {
+                        return Err(DavError::Code(StatusCode::FORBIDDEN));
+                    }
+                }
+                upload
+                    .write_to(&target.path)
+                    .await
+                    .map_err(into_dav_error)?;
+                let new_metadata = tokio::fs::metadata(&target.path)
+                    .await
+                    .map_err(into_dav_error)?;
+
+                Ok(HttpResponse::new(if metadata.is_some() {
+                    StatusCode::NO_CONTENT
+                } else {
+                    StatusCode::CREATED
+                })
+                .with_etag(etag(&new_metadata)))
+            }
+            DavMethod::MKCOL => {
+                access_token.assert_has_permission(Permission::DavFileMkCol)?;
+                if !target.can_write {
+                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                } else if metadata.is_some() {
+                    return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+                } else if !body.is_empty() {
+                    return Err(DavError::Code(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+                } else if !parent_exists(&target.path).await {
+                    return Err(DavError::Code(StatusCode::CONFLICT));
+                }
+
+                tokio::fs::create_dir(&target.path)
+                    .await
+                    .map_err(into_dav_error)?;
+
+                Ok(HttpResponse::new(StatusCode::CREATED))
+            }
+            DavMethod::DELETE => {
+                access_token.assert_has_permission(Permission::DavFileDelete)?;
+                let metadata = metadata.ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+                if !target.can_write || target.is_root {
+                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                }
+
+                if metadata.is_dir() {
+                    tokio::fs::remove_dir_all(&target.path).await
+                } else {
+                    tokio::fs::remove_file(&target.path).await
+                }
+                .map_err(into_dav_error)?;
+
+                Ok(HttpResponse::new(StatusCode::NO_CONTENT))
+            }
+            DavMethod::COPY | DavMethod::MOVE => {
+                let is_move = matches!(method, DavMethod::MOVE);
+                access_token.assert_has_permission(if is_move {
+                    Permission::DavFileMove
+                } else {
+                    Permission::DavFileCopy
+                })?;
+                let metadata = metadata.ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+
+                // Transfers are only supported within the same mount
+                let destination = self
+                    .resolve_file_mount(
+                        access_token,
+                        headers
+                            .destination
+                            .ok_or(DavError::Code(StatusCode::BAD_GATEWAY))?,
+                    )
+                    .await?
+                    .filter(|destination| destination.mount.id == target.mount.id)
+                    .ok_or(DavError::Code(StatusCode::BAD_GATEWAY))?;
+                if !destination.can_write || (is_move && (!target.can_write || target.is_root)) {
+                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                } else if destination.is_root || destination.path.starts_with(&target.path) {
+                    return Err(DavError::Code(StatusCode::BAD_GATEWAY));
+                } else if !is_move && metadata.is_dir() {
+                    return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+                } else if !parent_exists(&destination.path).await {
+                    return Err(DavError::Code(StatusCode::CONFLICT));
+                }
+
+                let exists = tokio::fs::metadata(&destination.path).await.is_ok();
+                if exists && headers.overwrite_fail {
+                    return Err(DavError::Code(StatusCode::PRECONDITION_FAILED));
+                }
+                if is_move {
+                    tokio::fs::rename(&target.path, &destination.path).await
+                } else {
+                    tokio::fs::copy(&target.path, &destination.path)
+                        .await
+                        .map(|_| ())
+                }
+                .map_err(into_dav_error)?;
+
+                Ok(HttpResponse::new(if exists {
+                    StatusCode::NO_CONTENT
+                } else {
+                    StatusCode::CREATED
+                }))
+            }
+            _ => Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED)),
+        }
+    }
+}
+
+fn mount_propstat(
+    href: String,
+    name: String,
+    metadata: &Metadata,
+    request: &PropFind,
+    is_minimal: bool,
+) -> Response {
+    static MOUNT_PROPS: [DavProperty; 7] = [
+        DavProperty::WebDav(WebDavProperty::CreationDate),
+        DavProperty::WebDav(WebDavProperty::DisplayName),
+        DavProperty::WebDav(WebDavProperty::GetETag),
+        DavProperty::WebDav(WebDavProperty::GetLastModified),
+        DavProperty::WebDav(WebDavProperty::ResourceType),
+        DavProperty::WebDav(WebDavProperty::GetContentLength),
+        DavProperty::WebDav(WebDavProperty::GetContentType),
+    ];
+
+    let properties = match request {
+        PropFind::PropName => {
+            return Response::new_propstat(
+                href,
+                vec![PropStat::new_list(
+                    MOUNT_PROPS
+                        .iter()
+                        .cloned()
+                        .map(DavPropertyValue::empty)
+                        .collect(),
+                )],
+            );
+        }
+        PropFind::AllProp(_) => MOUNT_PROPS.as_slice(),
+        PropFind::Prop(items) => items.as_slice(),
+    };
+
+    let mut fields = Vec::with_capacity(properties.len());
+    let mut fields_not_found = Vec::new();
+    for property in properties {
+        match property {
+            DavProperty::WebDav(WebDavProperty::CreationDate) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::Timestamp(timestamp(metadata.created().or(metadata.modified()))),
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::DisplayName) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::String(name.clone()),
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::GetETag) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::String(etag(metadata)),
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::GetLastModified) => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::Rfc1123Date(Rfc1123DateTime::new(timestamp(metadata.modified()))),
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::ResourceType) => {
+                if metadata.is_dir() {
+                    fields.push(DavPropertyValue::new(
+                        property.clone(),
+                        vec![ResourceType::Collection],
+                    ));
+                } else {
+                    fields.push(DavPropertyValue::empty(property.clone()));
+                }
+            }
+            DavProperty::WebDav(WebDavProperty::GetContentLength) if metadata.is_file() => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::Uint64(metadata.len()),
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::GetContentType) if metadata.is_file() => {
+                fields.push(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::String(content_type(Path::new(&name)).to_string()),
+                ));
+            }
+            _ => {
+                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+            }
+        }
+    }
+
+    let mut prop_stat = PropStatBuilder::default()
+        .with_minimal(is_minimal || matches!(request, PropFind::AllProp(_)));
+    prop_stat
+        .insert_list_with_status(fields, StatusCode::OK)
+        .insert_list_with_status(fields_not_found, StatusCode::NOT_FOUND);
+
+    Response::new_propstat(href, prop_stat.build())
+}
+
+/// Resolves symbolic links in `path` and makes sure the result stays under
+/// `root`, paths that do not exist yet are checked through their parent.
+async fn confine_path(root: &Path, path: PathBuf) -> crate::Result<PathBuf> {
+    let resolved = if tokio::fs::symlink_metadata(&path).await.is_ok() {
+        tokio::fs::canonicalize(&path).await.ok()
+    } else if let (Some(parent), Some(name)) = (path.parent(), path.file_name()) {
+        match tokio::fs::canonicalize(parent).await {
+            Ok(parent) => Some(parent.join(name)),
+            // Missing parents are reported by the method handlers
+            Err(_) => return Ok(path),
+        }
+    } else {
+        None
+    };
+
+    match resolved {
+        Some(resolved) if resolved.starts_with(root) => Ok(resolved),
+        _ => Err(DavError::Code(StatusCode::FORBIDDEN)),
+    }
+}
+
+async fn parent_exists(path: &Path) -> bool {
+    match path.parent() {
+        Some(parent) => tokio::fs::metadata(parent)
+            .await
+            .is_ok_and(|metadata| metadata.is_dir()),
+        None => false,
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.to_string())
+        .unwrap_or_default()
+}
+
+fn etag(metadata: &Metadata) -> String {
+    format!(
+        "\"{:x}-{:x}\"",
+        timestamp(metadata.modified()),
+        metadata.len()
+    )
+}
+
+fn timestamp(time: std::io::Result<SystemTime>) -> i64 {
+    time.ok()
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn content_type(path: &Path) -> &'static str {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .and_then(|ext| {
+            hashify::tiny_map!(ext.as_bytes(),
+                "txt" => "text/plain",
+                "html" => "text/html",
+                "htm" => "text/html",
+                "css" => "text/css",
+                "csv" => "text/csv",
+                "ics" => "text/calendar",
+                "vcf" => "text/vcard",
+                "xml" => "application/xml",
+                "json" => "application/json",
+                "pdf" => "application/pdf",
+                "zip" => "application/zip",
+                "doc" => "application/msword",
+                "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+                "xls" => "application/vnd.ms-excel",
+                "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+                "ppt" => "application/vnd.ms-powerpoint",
+                "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+                "odt" => "application/vnd.oasis.opendocument.text",
+                "ods" => "application/vnd.oasis.opendocument.spreadsheet",
+                "png" => "image/png",
+                "jpg" => "image/jpeg",
+                "jpeg" => "image/jpeg",
+                "gif" => "image/gif",
+                "svg" => "image/svg+xml",
+                "webp" => "image/webp",
+                "mp3" => "audio/mpeg",
+                "mp4" => "video/mp4",
+            )
+        })
+        .unwrap_or("application/octet-stream")
+}
+
+fn into_dav_error(err: std::io::Error) -> DavError {
+    match err.kind() {
+        std::io::ErrorKind::NotFound => DavError::Code(StatusCode::NOT_FOUND),
+        std::io::ErrorKind::PermissionDenied => DavError::Code(StatusCode::FORBIDDEN),
+        std::io::ErrorKind::AlreadyExists => DavError::Code(StatusCode::METHOD_NOT_ALLOWED),
+        _ => DavError::Internal(
+            trc::StoreEvent::UnexpectedError
+                .reason(err)
+                .details("External storage operation failed"),
+        ),
+    }
+}
@@ -27,6 +27,7 @@ use crate::{
     DavError, DavMethod, PropStatBuilder,
     common::{
         ETag, ExtractETag,
+        acl::ResourceAcl,
         lock::{LockRequestHandler, ResourceState},
         uri::DavUriResource,
     },
@@ -92,7 +93,7 @@ impl FilePropPatchRequestHandler for Server {
                 .effective_acl(access_token)
                 .contains(Acl::Modify)
         {
-            return Err(DavError::Code(StatusCode::FORBIDDEN));
+            return Err(files.need_privileges(Some(resource.resource), Acl::Modify));
         }
 
         // Validate headers
@@ -152,11 +153,16 @@ impl FilePropPatchRequestHandler for Server {
         };
 
         if headers.ret != Return::Minimal || !is_success {
-            Ok(HttpResponse::new(StatusCode::MULTI_STATUS)
+            let response = HttpResponse::new(StatusCode::MULTI_STATUS)
                 .with_xml_body(
                     MultiStatus::new(vec![Response::new_propstat(uri, items.build())]).to_string(),
                 )
-                .with_etag_opt(etag))
+                .with_etag_opt(etag);
+            if headers.ret == Return::Representation {
+                Ok(response.with_header("Preference-Applied", "return=representation"))
+            } else {
+                Ok(response)
+            }
         } else {
             Ok(HttpResponse::new(StatusCode::NO_CONTENT).with_etag_opt(etag))
         }
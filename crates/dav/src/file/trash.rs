@@ -0,0 +1,164 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{Server, auth::AccessToken, sharing::EffectiveAcl};
+use dav_proto::RequestHeaders;
+use groupware::{
+    cache::GroupwareCache,
+    file::{
+        FileNode,
+        trash::{TRASH_FOLDER_NAME, is_trash_path},
+    },
+};
+use http_proto::HttpResponse;
+use hyper::StatusCode;
+use jmap_proto::types::{
+    acl::Acl,
+    collection::{Collection, SyncCollection},
+};
+use store::write::BatchBuilder;
+use trc::AddContext;
+
+use crate::{
+    DavError, DavMethod,
+    common::{
+        ETag, ExtractETag,
+        acl::ResourceAcl,
+        lock::{LockRequestHandler, ResourceState},
+        uri::DavUriResource,
+    },
+    file::DavFileResource,
+};
+
+pub(crate) trait FileTrashRequestHandler: Sync + Send {
+    fn handle_file_trash_restore_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+}
+
+impl FileTrashRequestHandler for Server {
+    async fn handle_file_trash_restore_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+    ) -> crate::Result<HttpResponse> {
+        // Validate URI
+        let resource_ = self
+            .validate_uri(access_token, headers.uri)
+            .await?
+            .into_owned_uri()?;
+        let account_id = resource_.account_id;
+        let resource_name = resource_
+            .resource
+            .ok_or(DavError::Code(StatusCode::METHOD_NOT_ALLOWED))?;
+
+        // Only items directly under the trash folder can be restored
+        if resource_name
+            .strip_prefix(TRASH_FOLDER_NAME)
+            .and_then(|name| name.strip_prefix('/'))
+            .is_none_or(|name| name.is_empty() || name.contains('/'))
+        {
+            return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+        }
+
+        let files = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::FileNode)
+            .await
+            .caused_by(trc::location!())?;
+        let document_id = files
+            .by_path(resource_name)
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?
+            .document_id();
+
+        // Fetch node
+        let node_ = self
+            .get_archive(account_id, Collection::FileNode, document_id)
+            .await
+            .caused_by(trc::location!())?
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        let node = node_
+            .to_unarchived::<FileNode>()
+            .caused_by(trc::location!())?;
+        let original_path = node
+            .inner
+            .trash
+            .as_ref()
+            .map(|trash| trash.path.as_str())
+            .ok_or(DavError::Code(StatusCode::CONFLICT))?;
+
+        // Validate ACL
+        if !access_token.is_member(account_id)
+            && !node
+                .inner
+                .acls
+                .effective_acl(access_token)
+                .contains(Acl::Modify)
+        {
+            return Err(files.need_privileges(Some(document_id), Acl::Modify));
+        }
+
+        // The original location must still be available
+        if files.by_path(original_path).is_some() {
+            return Err(DavError::Code(StatusCode::PRECONDITION_FAILED));
+        }
+        let (parent, name) = files
+            .map_parent(original_path)
+            .filter(|(parent, _)| {
+                parent
+                    .as_ref()
+                    .is_none_or(|parent| parent.is_container() && !is_trash_path(parent.path()))
+            })
+            .ok_or(DavError::Code(StatusCode::CONFLICT))?;
+        let parent_id = files.validate_and_map_parent_acl(
+            access_token,
+            access_token.is_member(account_id),
+            parent.map(|parent| parent.document_id()),
+            Acl::AddItems,
+        )?;
+
+        // Validate headers
+        self.validate_headers(
+            access_token,
+            headers,
+            vec![ResourceState {
+                account_id,
+                collection: Collection::FileNode,
+                document_id: Some(document_id),
+                etag: node.etag().into(),
+                path: resource_name,
+                ..Default::default()
+            }],
+            Default::default(),
+            DavMethod::POST,
+        )
+        .await?;
+
+        // Move the item back to its original location
+        let url = files.format_item(original_path);
+        let mut new_node = node.deserialize::<FileNode>().caused_by(trc::location!())?;
+        new_node.parent_id = parent_id;
+        new_node.name = name.to_string();
+        new_node.trash = None;
+        let mut batch = BatchBuilder::new();
+        let etag = new_node
+            .update(access_token, node, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?
+            .etag();
+        self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+        trc::event!(
+            Groupware(trc::GroupwareEvent::FileUpdated),
+            AccountId = account_id,
+            Collection = Collection::FileNode,
+            DocumentId = document_id,
+            Url = url,
+        );
+
+        Ok(HttpResponse::new(StatusCode::NO_CONTENT).with_etag_opt(etag))
+    }
+}
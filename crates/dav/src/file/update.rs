@@ -9,45 +9,58 @@ use crate::{
     common::{
         ETag, ExtractETag,
         acl::ResourceAcl,
+        limits::{CollectionQuotaCheck, ItemLimitCheck},
         lock::{LockRequestHandler, ResourceState},
-        uri::DavUriResource,
+        uri::{DavUriResource, validate_resource_name},
     },
     file::DavFileResource,
 };
 use common::{
     Server, auth::AccessToken, sharing::EffectiveAcl, storage::index::ObjectIndexBuilder,
 };
-use dav_proto::{RequestHeaders, Return, schema::property::Rfc1123DateTime};
+use dav_proto::{ContentRange, RequestHeaders, Return, schema::property::Rfc1123DateTime};
 use groupware::{
     cache::GroupwareCache,
     file::{FileNode, FileProperties},
+    icap::{ContentInspection, ContentKind, InspectionResult},
 };
+use http_body_util::BodyExt;
 use http_proto::HttpResponse;
-use hyper::StatusCode;
+use hyper::{StatusCode, body::Incoming};
 use jmap_proto::types::{
     acl::Acl,
     collection::{Collection, SyncCollection},
 };
+use std::path::{Path, PathBuf};
 use store::write::{BatchBuilder, now};
+use tokio::io::AsyncWriteExt;
 use trc::AddContext;
-use utils::BlobHash;
+use utils::{BlobHash, BlobHasher};
 
 pub(crate) trait FileUpdateRequestHandler: Sync + Send {
     fn handle_file_update_request(
         &self,
         access_token: &AccessToken,
         headers: &RequestHeaders<'_>,
-        bytes: Vec<u8>,
+        upload: FileUpload<'_>,
         is_patch: bool,
     ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
 }
 
+pub(crate) enum FileUpload<'x> {
+    Buffered(Vec<u8>),
+    Streamed {
+        body: &'x mut Incoming,
+        content_length: Option<usize>,
+    },
+}
+
 impl FileUpdateRequestHandler for Server {
     async fn handle_file_update_request(
         &self,
         access_token: &AccessToken,
         headers: &RequestHeaders<'_>,
-        bytes: Vec<u8>,
+        upload: FileUpload<'_>,
         _is_patch: bool,
     ) -> crate::Result<HttpResponse> {
         // Validate URI
@@ -64,10 +77,17 @@ impl FileUpdateRequestHandler for Server {
             .resource
             .ok_or(DavError::Code(StatusCode::CONFLICT))?;
 
-        if bytes.len() > self.core.groupware.max_file_size {
+        // Reject oversized uploads before reading the body
+        let max_file_size = self.core.groupware.max_file_size;
+        let size_hint = upload.size_hint();
+        if size_hint.is_some_and(|size| size > max_file_size) {
             return Err(DavError::Code(StatusCode::PAYLOAD_TOO_LARGE));
         }
 
+        // The final size of partial uploads is only known once they are
+        // merged with the current contents
+        let size_hint = size_hint.filter(|_| headers.content_range.is_none());
+
         if let Some(document_id) = resources.by_path(resource_name).map(|r| r.document_id()) {
             // Update
             let node_ = self
@@ -87,7 +107,7 @@ impl FileUpdateRequestHandler for Server {
                     .effective_acl(access_token)
                     .contains(Acl::Modify)
             {
-                return Err(DavError::Code(StatusCode::FORBIDDEN));
+                return Err(resources.need_privileges(Some(document_id), Acl::Modify));
             }
 
             // Validate headers
@@ -137,43 +157,111 @@ impl FileUpdateRequestHandler for Server {
                 Err(e) => return Err(e),
             }
 
-            // Verify that the node is a file
-            if let Some(file) = node.inner.file.as_ref() {
-                if BlobHash::generate(&bytes).as_slice() == file.blob_hash.0.as_slice() {
-                    return Ok(HttpResponse::new(StatusCode::NO_CONTENT));
+            // Verify that the node is a file, the current contents are only
+            // released when no previous versions are kept
+            let max_versions = self.core.groupware.max_file_versions;
+            let current_size = if let Some(file) = node.inner.file.as_ref() {
+                if max_versions == 0 {
+                    u32::from(file.size) as u64
+                } else {
+                    0
                 }
             } else {
                 return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+            };
+
+            // Validate quota before reading the body when the size is known
+            let resource_token = self.get_resource_token(access_token, account_id).await?;
+            if let Some(size) = size_hint {
+                let extra_bytes = (size as u64).saturating_sub(current_size);
+                if extra_bytes > 0 {
+                    self.has_available_quota(&resource_token, extra_bytes)
+                        .await?;
+                }
             }
 
-            // Validate quota
-            let extra_bytes = (bytes.len() as u64)
-                .saturating_sub(u32::from(node.inner.file.as_ref().unwrap().size) as u64);
-            if extra_bytes > 0 {
-                self.has_available_quota(
-                    &self.get_resource_token(access_token, account_id).await?,
-                    extra_bytes,
-                )
-                .await?;
+            // Read body
+            let upload = upload.read(self, max_file_size).await?;
+            let file = node.inner.file.as_ref().unwrap();
+            let upload = if let Some(range) = headers.content_range {
+                let contents = self
+                    .blob_store()
+                    .get_blob(file.blob_hash.0.as_slice(), 0..usize::MAX)
+                    .await
+                    .caused_by(trc::location!())?
+                    .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+                UploadedFile::from_bytes(apply_content_range(
+                    contents,
+                    range,
+                    upload.into_bytes().await?,
+                    max_file_size,
+                )?)
+            } else {
+                let load = headers.ret == Return::Representation
+                    || self.is_inspected(access_token, ContentKind::File, upload.size);
+                upload.load_if(load).await?
+            };
+            if upload.hash.as_slice() == file.blob_hash.0.as_slice() {
+                return Ok(put_response(
+                    headers,
+                    StatusCode::NO_CONTENT,
+                    file.media_type.as_ref().map(|v| v.as_str()),
+                    upload,
+                ));
+            }
+
+            // Inspect content
+            if let Some(bytes) = upload.bytes() {
+                if self
+                    .inspect_content(
+                        access_token,
+                        ContentKind::File,
+                        resource_name,
+                        headers.content_type,
+                        bytes,
+                    )
+                    .await
+                    != InspectionResult::Allowed
+                {
+                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                }
+            }
+            if size_hint.is_none() {
+                let extra_bytes = (upload.size as u64).saturating_sub(current_size);
+                if extra_bytes > 0 {
+                    self.has_available_quota(&resource_token, extra_bytes)
+                        .await?;
+                }
             }
 
+            // Validate folder quota
+            self.assert_collection_quota(
+                &resources,
+                account_id,
+                Collection::FileNode,
+                resources.by_path(resource_name).and_then(|r| r.parent_id()),
+                (upload.size as u64).saturating_sub(current_size),
+            )
+            .await?;
+
             // Write blob
-            let blob_hash = self
-                .put_blob(account_id, &bytes, false)
+            let blob_hash = upload
+                .store(self, account_id)
                 .await
-                .caused_by(trc::location!())?
-                .hash;
+                .caused_by(trc::location!())?;
 
             // Build node
             let mut new_node = node.deserialize::<FileNode>().caused_by(trc::location!())?;
+            new_node.push_version(max_versions);
             let new_file = new_node.file.as_mut().unwrap();
             new_file.blob_hash = blob_hash;
             new_file.media_type = headers
                 .content_type
                 .filter(|ct| !ct.is_empty() && *ct != "application/octet-stream")
                 .map(|v| v.to_string());
-            new_file.size = bytes.len() as u32;
+            new_file.size = upload.size as u32;
             new_node.modified = now() as i64;
+            let media_type = new_file.media_type.clone();
 
             // Prepare write batch
             let mut batch = BatchBuilder::new();
@@ -191,7 +279,21 @@ impl FileUpdateRequestHandler for Server {
             let etag = batch.etag();
             self.commit_batch(batch).await.caused_by(trc::location!())?;
 
-            Ok(HttpResponse::new(StatusCode::NO_CONTENT).with_etag_opt(etag))
+            trc::event!(
+                Groupware(trc::GroupwareEvent::FileUpdated),
+                AccountId = account_id,
+                Collection = Collection::FileNode,
+                DocumentId = document_id,
+                Url = resources.format_item(resource_name),
+            );
+
+            Ok(put_response(
+                headers,
+                StatusCode::NO_CONTENT,
+                media_type.as_deref(),
+                upload,
+            )
+            .with_etag_opt(etag))
         } else {
             // Insert
             let orig_resource_name = resource_name;
@@ -211,6 +313,7 @@ impl FileUpdateRequestHandler for Server {
             if parent.as_ref().is_some_and(|r| !r.is_container()) {
                 return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
             }
+            validate_resource_name(resource_name)?;
 
             // Validate headers
             self.validate_headers(
@@ -228,21 +331,74 @@ impl FileUpdateRequestHandler for Server {
             )
             .await?;
 
-            // Validate quota
-            if !bytes.is_empty() {
-                self.has_available_quota(
-                    &self.get_resource_token(access_token, account_id).await?,
-                    bytes.len() as u64,
-                )
-                .await?;
+            // Validate item limit
+            self.assert_item_limit(
+                access_token,
+                &resources,
+                account_id,
+                Collection::FileNode,
+                parent.map(|r| r.document_id()),
+            )
+            .await?;
+
+            // Validate quota before reading the body when the size is known
+            let resource_token = self.get_resource_token(access_token, account_id).await?;
+            if let Some(size) = size_hint.filter(|size| *size > 0) {
+                self.has_available_quota(&resource_token, size as u64)
+                    .await?;
+            }
+
+            // Read body, partial uploads of new files have to start at offset zero
+            let upload = upload.read(self, max_file_size).await?;
+            let upload = if let Some(range) = headers.content_range {
+                UploadedFile::from_bytes(apply_content_range(
+                    Vec::new(),
+                    range,
+                    upload.into_bytes().await?,
+                    max_file_size,
+                )?)
+            } else {
+                let load = headers.ret == Return::Representation
+                    || self.is_inspected(access_token, ContentKind::File, upload.size);
+                upload.load_if(load).await?
+            };
+            if size_hint.is_none() && upload.size > 0 {
+                self.has_available_quota(&resource_token, upload.size as u64)
+                    .await?;
+            }
+
+            // Inspect content
+            if let Some(bytes) = upload.bytes() {
+                if self
+                    .inspect_content(
+                        access_token,
+                        ContentKind::File,
+                        resource_name,
+                        headers.content_type,
+                        bytes,
+                    )
+                    .await
+                    != InspectionResult::Allowed
+                {
+                    return Err(DavError::Code(StatusCode::FORBIDDEN));
+                }
             }
 
+            // Validate folder quota
+            self.assert_collection_quota(
+                &resources,
+                account_id,
+                Collection::FileNode,
+                parent.map(|r| r.document_id()),
+                upload.size as u64,
+            )
+            .await?;
+
             // Write blob
-            let blob_hash = self
-                .put_blob(account_id, &bytes, false)
+            let blob_hash = upload
+                .store(self, account_id)
                 .await
-                .caused_by(trc::location!())?
-                .hash;
+                .caused_by(trc::location!())?;
 
             // Build node
             let now = now();
@@ -252,7 +408,7 @@ impl FileUpdateRequestHandler for Server {
                 display_name: None,
                 file: Some(FileProperties {
                     blob_hash,
-                    size: bytes.len() as u32,
+                    size: upload.size as u32,
                     media_type: headers.content_type.map(|v| v.to_string()),
                     executable: false,
                 }),
@@ -260,6 +416,9 @@ impl FileUpdateRequestHandler for Server {
                 modified: now as i64,
                 dead_properties: Default::default(),
                 acls: Default::default(),
+                bindings: Default::default(),
+                versions: Default::default(),
+                trash: None,
             };
 
             // Prepare write batch
@@ -282,7 +441,223 @@ impl FileUpdateRequestHandler for Server {
             let etag = batch.etag();
             self.commit_batch(batch).await.caused_by(trc::location!())?;
 
-            Ok(HttpResponse::new(StatusCode::CREATED).with_etag_opt(etag))
+            trc::event!(
+                Groupware(trc::GroupwareEvent::FileCreated),
+                AccountId = account_id,
+                Collection = Collection::FileNode,
+                DocumentId = document_id,
+                Url = resources.format_item(orig_resource_name),
+            );
+
+            Ok(
+                put_response(headers, StatusCode::CREATED, headers.content_type, upload)
+                    .with_etag_opt(etag),
+            )
+        }
+    }
+}
+
+impl FileUpload<'_> {
+    fn size_hint(&self) -> Option<usize> {
+        match self {
+            FileUpload::Buffered(bytes) => Some(bytes.len()),
+            FileUpload::Streamed { content_length, .. } => *content_length,
+        }
+    }
+
+    pub(crate) async fn read(
+        self,
+        server: &Server,
+        max_size: usize,
+    ) -> crate::Result<UploadedFile> {
+        match self {
+            FileUpload::Buffered(bytes) => {
+                if bytes.len() <= max_size {
+                    Ok(UploadedFile::from_bytes(bytes))
+                } else {
+                    Err(DavError::Code(StatusCode::PAYLOAD_TOO_LARGE))
+                }
+            }
+            FileUpload::Streamed { body, .. } => {
+                // Spool the payload to a temporary file, hashing and sizing it as it arrives
+                let spool = SpooledFile {
+                    path: std::env::temp_dir()
+                        .join(format!("dav-upload-{}", server.generate_snowflake_id())),
+                };
+                let mut file = tokio::fs::File::create(&spool.path)
+                    .await
+                    .map_err(spool_error)?;
+                let mut hasher = BlobHasher::new();
+                let mut size = 0;
+                while let Some(frame) = body.frame().await {
+                    let frame = frame.map_err(|_| DavError::Code(StatusCode::BAD_REQUEST))?;
+                    if let Some(data) = frame.data_ref() {
+                        size += data.len();
+                        if size > max_size {
+                            return Err(DavError::Code(StatusCode::PAYLOAD_TOO_LARGE));
+                        }
+                        hasher.update(data);
+                        file.write_all(data).await.map_err(spool_error)?;
+                    }
+                }
+                file.flush().await.map_err(spool_error)?;
+
+                Ok(UploadedFile {
+                    contents: UploadContents::Spooled(spool),
+                    hash: hasher.finalize(),
+                    size,
+                })
+            }
+        }
+    }
+}
+
+pub(crate) struct UploadedFile {
+    contents: UploadContents,
+    pub hash: BlobHash,
+    pub size: usize,
+}
+
+enum UploadContents {
+    Memory(Vec<u8>),
+    Spooled(SpooledFile),
+}
+
+// Temporary file holding a streamed upload, removed once the upload is stored
+struct SpooledFile {
+    path: PathBuf,
+}
+
+impl UploadedFile {
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        UploadedFile {
+            hash: BlobHash::generate(&bytes),
+            size: bytes.len(),
+            contents: UploadContents::Memory(bytes),
+        }
+    }
+
+    pub(crate) fn bytes(&self) -> Option<&[u8]> {
+        match &self.contents {
+            UploadContents::Memory(bytes) => Some(bytes),
+            UploadContents::Spooled(_) => None,
+        }
+    }
+
+    // Spooled uploads are only loaded when their contents have to be
+    // inspected, merged or returned to the client
+    pub(crate) async fn load_if(self, condition: bool) -> crate::Result<Self> {
+        match self.contents {
+            UploadContents::Spooled(spool) if condition => {
+                let bytes = tokio::fs::read(&spool.path).await.map_err(spool_error)?;
+                Ok(UploadedFile {
+                    contents: UploadContents::Memory(bytes),
+                    hash: self.hash,
+                    size: self.size,
+                })
+            }
+            contents => Ok(UploadedFile { contents, ..self }),
+        }
+    }
+
+    async fn into_bytes(self) -> crate::Result<Vec<u8>> {
+        match self.load_if(true).await?.contents {
+            UploadContents::Memory(bytes) => Ok(bytes),
+            UploadContents::Spooled(_) => unreachable!(),
+        }
+    }
+
+    async fn store(&self, server: &Server, account_id: u32) -> trc::Result<BlobHash> {
+        match &self.contents {
+            UploadContents::Memory(bytes) => {
+                server
+                    .put_blob_with_hash(account_id, bytes, self.hash.clone(), false)
+                    .await
+            }
+            UploadContents::Spooled(spool) => {
+                server
+                    .put_blob_file_with_hash(
+                        account_id,
+                        &spool.path,
+                        self.size,
+                        self.hash.clone(),
+                        false,
+                    )
+                    .await
+            }
         }
+        .map(|blob_id| blob_id.hash)
+    }
+
+    pub(crate) async fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        match &self.contents {
+            UploadContents::Memory(bytes) => tokio::fs::write(path, bytes).await,
+            UploadContents::Spooled(spool) => tokio::fs::copy(&spool.path, path).await.map(|_| ()),
+        }
+    }
+}
+
+impl Drop for SpooledFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn spool_error(err: std::io::Error) -> DavError {
+    DavError::Internal(
+        trc::StoreEvent::FilesystemError
+            .reason(err)
+            .details("Failed to spool upload"),
+    )
+}
+
+// Writes a partial upload over the current contents of a file, which allows
+// interrupted uploads to be resumed (RFC 9110, section 14.5)
+fn apply_content_range(
+    mut contents: Vec<u8>,
+    range: ContentRange,
+    bytes: Vec<u8>,
+    max_size: usize,
+) -> crate::Result<Vec<u8>> {
+    if bytes.len() as u64 != range.len() {
+        return Err(DavError::Code(StatusCode::BAD_REQUEST));
+    } else if range.first > contents.len() as u64 {
+        return Err(DavError::Code(StatusCode::RANGE_NOT_SATISFIABLE));
+    } else if range.last >= max_size as u64 {
+        return Err(DavError::Code(StatusCode::PAYLOAD_TOO_LARGE));
+    }
+
+    let first = range.first as usize;
+    let last = first + bytes.len();
+    if contents.len() < last {
+        contents.resize(last, 0);
+    }
+    contents[first..last].copy_from_slice(&bytes);
+    if let Some(complete_length) = range.complete_length {
+        contents.truncate(complete_length as usize);
+    }
+
+    Ok(contents)
+}
+
+// Returns the stored contents when the client asked for them (RFC 7240)
+fn put_response(
+    headers: &RequestHeaders<'_>,
+    status: StatusCode,
+    media_type: Option<&str>,
+    upload: UploadedFile,
+) -> HttpResponse {
+    if let (Return::Representation, UploadContents::Memory(bytes)) = (headers.ret, upload.contents)
+    {
+        HttpResponse::new(if status == StatusCode::NO_CONTENT {
+            StatusCode::OK
+        } else {
+            status
+        })
+        .with_content_type(media_type.unwrap_or("application/octet-stream"))
+        .with_header("Preference-Applied", "return=representation")
+        .with_binary_body(bytes)
+    } else {
+        HttpResponse::new(status)
     }
 }
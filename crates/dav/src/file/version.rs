@@ -0,0 +1,338 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{Server, auth::AccessToken, sharing::EffectiveAcl};
+use dav_proto::{
+    RequestHeaders, Return,
+    schema::{
+        property::{DavProperty, DavValue, Rfc1123DateTime, WebDavProperty},
+        request::{DavPropertyValue, PropFind, VersionTree},
+        response::{MultiStatus, PropStat, Response},
+    },
+};
+use groupware::{
+    cache::GroupwareCache,
+    file::{ArchivedFileProperties, FileNode},
+};
+use http_proto::HttpResponse;
+use hyper::StatusCode;
+use jmap_proto::types::{
+    acl::Acl,
+    collection::{Collection, SyncCollection},
+};
+use store::write::{BatchBuilder, now};
+use trc::AddContext;
+use utils::url_params::UrlParams;
+
+use crate::{
+    DavError, DavMethod, PropStatBuilder,
+    common::{
+        ETag, ExtractETag,
+        acl::ResourceAcl,
+        limits::CollectionQuotaCheck,
+        lock::{LockRequestHandler, ResourceState},
+        uri::DavUriResource,
+    },
+    file::DavFileResource,
+};
+
+pub(crate) static FILE_VERSION_PROPS: [DavProperty; 4] = [
+    DavProperty::WebDav(WebDavProperty::VersionName),
+    DavProperty::WebDav(WebDavProperty::GetContentLength),
+    DavProperty::WebDav(WebDavProperty::GetContentType),
+    DavProperty::WebDav(WebDavProperty::GetLastModified),
+];
+
+pub(crate) trait FileVersionRequestHandler: Sync + Send {
+    fn handle_file_version_tree_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        request: VersionTree,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+
+    fn handle_file_version_restore_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        query: &str,
+    ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
+}
+
+impl FileVersionRequestHandler for Server {
+    async fn handle_file_version_tree_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        request: VersionTree,
+    ) -> crate::Result<HttpResponse> {
+        // Validate URI
+        let resource_ = self
+            .validate_uri(access_token, headers.uri)
+            .await?
+            .into_owned_uri()?;
+        let account_id = resource_.account_id;
+        let files = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::FileNode)
+            .await
+            .caused_by(trc::location!())?;
+        let resource = files.map_resource::<u32>(&resource_)?;
+
+        // Fetch node
+        let node_ = self
+            .get_archive(account_id, Collection::FileNode, resource.resource)
+            .await
+            .caused_by(trc::location!())?
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        let node = node_.unarchive::<FileNode>().caused_by(trc::location!())?;
+
+        // Validate ACL
+        if !access_token.is_member(account_id)
+            && !node.acls.effective_acl(access_token).contains(Acl::Read)
+        {
+            return Err(files.need_privileges(Some(resource.resource), Acl::Read));
+        }
+
+        // Only files are versioned
+        let Some(file) = node.file.as_ref() else {
+            return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+        };
+
+        let (properties, is_propname) = match &request.properties {
+            PropFind::PropName => (FILE_VERSION_PROPS.as_slice(), true),
+            PropFind::AllProp(_) => (FILE_VERSION_PROPS.as_slice(), false),
+            PropFind::Prop(properties) => (properties.as_slice(), false),
+        };
+        let is_minimal = headers.ret == Return::Minimal;
+
+        // List the current version followed by the previous ones, newest first
+        let mut responses = Vec::with_capacity(node.versions.len() + 1);
+        responses.push(Response::new_propstat(
+            headers.uri,
+            version_propstat(
+                properties,
+                is_propname,
+                is_minimal,
+                node.current_version(),
+                file,
+                i64::from(node.modified),
+            ),
+        ));
+        for version in node.versions.iter().rev() {
+            responses.push(Response::new_propstat(
+                format!("{}?version={}", headers.uri, version.version.to_native()),
+                version_propstat(
+                    properties,
+                    is_propname,
+                    is_minimal,
+                    version.version.to_native(),
+                    &version.file,
+                    i64::from(version.modified),
+                ),
+            ));
+        }
+
+        Ok(HttpResponse::new(StatusCode::MULTI_STATUS)
+            .with_xml_body(MultiStatus::new(responses).to_string()))
+    }
+
+    async fn handle_file_version_restore_request(
+        &self,
+        access_token: &AccessToken,
+        headers: &RequestHeaders<'_>,
+        query: &str,
+    ) -> crate::Result<HttpResponse> {
+        // Parse action
+        let params = UrlParams::new(query.into());
+        let version = match (params.get("action"), params.get("version")) {
+            (Some("restore-version"), Some(version)) => version
+                .parse::<u32>()
+                .map_err(|_| DavError::Code(StatusCode::BAD_REQUEST))?,
+            _ => return Err(DavError::Code(StatusCode::BAD_REQUEST)),
+        };
+
+        // Validate URI
+        let resource_ = self
+            .validate_uri(access_token, headers.uri)
+            .await?
+            .into_owned_uri()?;
+        let account_id = resource_.account_id;
+        let resource_name = resource_
+            .resource
+            .ok_or(DavError::Code(StatusCode::METHOD_NOT_ALLOWED))?;
+        let files = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::FileNode)
+            .await
+            .caused_by(trc::location!())?;
+        let resource = files
+            .by_path(resource_name)
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        let document_id = resource.document_id();
+        let parent_id = resource.parent_id();
+
+        // Fetch node
+        let node_ = self
+            .get_archive(account_id, Collection::FileNode, document_id)
+            .await
+            .caused_by(trc::location!())?
+            .ok_or(DavError::Code(StatusCode::NOT_FOUND))?;
+        let node = node_
+            .to_unarchived::<FileNode>()
+            .caused_by(trc::location!())?;
+
+        // Validate ACL
+        if !access_token.is_member(account_id)
+            && !node
+                .inner
+                .acls
+                .effective_acl(access_token)
+                .contains(Acl::Modify)
+        {
+            return Err(files.need_privileges(Some(document_id), Acl::Modify));
+        }
+
+        // Validate headers
+        self.validate_headers(
+            access_token,
+            headers,
+            vec![ResourceState {
+                account_id,
+                collection: Collection::FileNode,
+                document_id: Some(document_id),
+                etag: node.etag().into(),
+                path: resource_name,
+                ..Default::default()
+            }],
+            Default::default(),
+            DavMethod::POST,
+        )
+        .await?;
+
+        // Obtain the version to restore
+        let Some(current_file) = node.inner.file.as_ref() else {
+            return Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED));
+        };
+        let restored = node
+            .inner
+            .versions
+            .iter()
+            .find(|v| v.version.to_native() == version)
+            .ok_or(DavError::Code(StatusCode::CONFLICT))?;
+
+        // Validate quota, the current contents are only released when no
+        // previous versions are kept
+        let max_versions = self.core.groupware.max_file_versions;
+        let extra_bytes = if max_versions == 0 {
+            u32::from(restored.file.size).saturating_sub(u32::from(current_file.size)) as u64
+        } else {
+            u32::from(restored.file.size) as u64
+        };
+        if extra_bytes > 0 {
+            self.has_available_quota(
+                &self.get_resource_token(access_token, account_id).await?,
+                extra_bytes,
+            )
+            .await?;
+            self.assert_collection_quota(
+                &files,
+                account_id,
+                Collection::FileNode,
+                parent_id,
+                extra_bytes,
+            )
+            .await?;
+        }
+
+        // Build node
+        let mut new_node = node.deserialize::<FileNode>().caused_by(trc::location!())?;
+        let restored_file = new_node
+            .versions
+            .iter()
+            .find(|v| v.version == version)
+            .map(|v| v.file.clone());
+        new_node.push_version(max_versions);
+        new_node.file = restored_file;
+        new_node.modified = now() as i64;
+
+        // Prepare write batch
+        let mut batch = BatchBuilder::new();
+        let etag = new_node
+            .update(access_token, node, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?
+            .etag();
+        self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+        trc::event!(
+            Groupware(trc::GroupwareEvent::FileUpdated),
+            AccountId = account_id,
+            Collection = Collection::FileNode,
+            DocumentId = document_id,
+            Url = files.format_item(resource_name),
+        );
+
+        Ok(HttpResponse::new(StatusCode::NO_CONTENT).with_etag_opt(etag))
+    }
+}
+
+fn version_propstat(
+    properties: &[DavProperty],
+    is_propname: bool,
+    is_minimal: bool,
+    version: u32,
+    file: &ArchivedFileProperties,
+    modified: i64,
+) -> Vec<PropStat> {
+    let mut prop_stat = PropStatBuilder::default().with_minimal(is_minimal);
+
+    for property in properties {
+        if is_propname {
+            prop_stat.insert_ok(DavPropertyValue::empty(property.clone()));
+            continue;
+        }
+
+        match property {
+            DavProperty::WebDav(WebDavProperty::VersionName) => {
+                prop_stat.insert_ok(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::String(version.to_string()),
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::GetContentLength) => {
+                prop_stat.insert_ok(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::Uint64(u32::from(file.size) as u64),
+                ));
+            }
+            DavProperty::WebDav(WebDavProperty::GetContentType) => {
+                if let Some(media_type) = file.media_type.as_ref() {
+                    prop_stat.insert_ok(DavPropertyValue::new(
+                        property.clone(),
+                        DavValue::String(media_type.to_string()),
+                    ));
+                } else {
+                    prop_stat.insert_with_status(
+                        DavPropertyValue::empty(property.clone()),
+                        StatusCode::NOT_FOUND,
+                    );
+                }
+            }
+            DavProperty::WebDav(WebDavProperty::GetLastModified) => {
+                prop_stat.insert_ok(DavPropertyValue::new(
+                    property.clone(),
+                    DavValue::Rfc1123Date(Rfc1123DateTime::new(modified)),
+                ));
+            }
+            _ => {
+                prop_stat.insert_with_status(
+                    DavPropertyValue::empty(property.clone()),
+                    StatusCode::NOT_FOUND,
+                );
+            }
+        }
+    }
+
+    prop_stat.build()
+}
@@ -17,7 +17,6 @@ use dav_proto::schema::{
 };
 use groupware::DavResourceName;
 use hyper::{Method, StatusCode};
-use store::ahash::AHashMap;
 
 pub(crate) type Result<T> = std::result::Result<T, DavError>;
 
@@ -40,6 +39,9 @@ pub enum DavMethod {
     UNLOCK,
     OPTIONS,
     ACL,
+    BIND,
+    UNBIND,
+    REBIND,
 }
 
 impl From<DavMethod> for trc::WebDavEvent {
@@ -62,6 +64,9 @@ impl From<DavMethod> for trc::WebDavEvent {
             DavMethod::UNLOCK => trc::WebDavEvent::Unlock,
             DavMethod::OPTIONS => trc::WebDavEvent::Options,
             DavMethod::ACL => trc::WebDavEvent::Acl,
+            DavMethod::BIND => trc::WebDavEvent::Bind,
+            DavMethod::UNBIND => trc::WebDavEvent::Unbind,
+            DavMethod::REBIND => trc::WebDavEvent::Rebind,
         }
     }
 }
@@ -123,7 +128,10 @@ impl DavMethod {
                     "MOVE" => DavMethod::MOVE,
                     "LOCK" => DavMethod::LOCK,
                     "UNLOCK" => DavMethod::UNLOCK,
-                    "ACL" => DavMethod::ACL
+                    "ACL" => DavMethod::ACL,
+                    "BIND" => DavMethod::BIND,
+                    "UNBIND" => DavMethod::UNBIND,
+                    "REBIND" => DavMethod::REBIND
                 )
             }
         }
@@ -142,21 +150,48 @@ impl DavMethod {
                 | DavMethod::LOCK
                 | DavMethod::ACL
                 | DavMethod::MKCALENDAR
+                | DavMethod::BIND
+                | DavMethod::UNBIND
+                | DavMethod::REBIND
+        )
+    }
+
+    #[inline]
+    pub fn has_xml_body(self) -> bool {
+        matches!(
+            self,
+            DavMethod::PROPPATCH
+                | DavMethod::PROPFIND
+                | DavMethod::REPORT
+                | DavMethod::LOCK
+                | DavMethod::ACL
+                | DavMethod::MKCOL
+                | DavMethod::MKCALENDAR
+                | DavMethod::BIND
+                | DavMethod::UNBIND
+                | DavMethod::REBIND
         )
     }
 }
 
+type PropStatKey = (StatusCode, Option<Condition>, Option<String>);
+
+// Groups are kept in insertion order so identical requests produce identical responses
 #[derive(Debug, Default)]
 pub struct PropStatBuilder {
-    propstats: AHashMap<(StatusCode, Option<Condition>, Option<String>), Vec<DavPropertyValue>>,
+    propstats: Vec<(PropStatKey, Vec<DavPropertyValue>)>,
+    is_minimal: bool,
 }
 
 impl PropStatBuilder {
+    // Minimal builders omit the properties that were not found (RFC 8144)
+    pub fn with_minimal(mut self, is_minimal: bool) -> Self {
+        self.is_minimal = is_minimal;
+        self
+    }
+
     pub fn insert_ok(&mut self, prop: impl Into<DavPropertyValue>) -> &mut Self {
-        self.propstats
-            .entry((StatusCode::OK, None, None))
-            .or_default()
-            .push(prop.into());
+        self.entry((StatusCode::OK, None, None)).push(prop.into());
         self
     }
 
@@ -165,10 +200,18 @@ impl PropStatBuilder {
         prop: impl Into<DavPropertyValue>,
         status: StatusCode,
     ) -> &mut Self {
-        self.propstats
-            .entry((status, None, None))
-            .or_default()
-            .push(prop.into());
+        self.entry((status, None, None)).push(prop.into());
+        self
+    }
+
+    pub fn insert_list_with_status(
+        &mut self,
+        props: Vec<DavPropertyValue>,
+        status: StatusCode,
+    ) -> &mut Self {
+        if !props.is_empty() {
+            self.entry((status, None, None)).extend(props);
+        }
         self
     }
 
@@ -178,9 +221,7 @@ impl PropStatBuilder {
         status: StatusCode,
         description: impl Into<String>,
     ) -> &mut Self {
-        self.propstats
-            .entry((status, None, Some(description.into())))
-            .or_default()
+        self.entry((status, None, Some(description.into())))
             .push(prop.into());
         self
     }
@@ -191,9 +232,7 @@ impl PropStatBuilder {
         status: StatusCode,
         condition: impl Into<Condition>,
     ) -> &mut Self {
-        self.propstats
-            .entry((status, Some(condition.into()), None))
-            .or_default()
+        self.entry((status, Some(condition.into()), None))
             .push(prop.into());
         self
     }
@@ -205,22 +244,57 @@ impl PropStatBuilder {
         condition: impl Into<Condition>,
         description: impl Into<String>,
     ) -> &mut Self {
-        self.propstats
-            .entry((status, Some(condition.into()), Some(description.into())))
-            .or_default()
+        self.entry((status, Some(condition.into()), Some(description.into())))
             .push(prop.into());
         self
     }
 
+    // Properties that would have been set are reported as failed
+    // dependencies when the request is rejected as a whole (RFC 4918)
+    pub fn with_failed_dependencies(mut self) -> Self {
+        if let Some(idx) =
+            self.propstats
+                .iter()
+                .position(|((status, condition, description), _)| {
+                    *status == StatusCode::OK && condition.is_none() && description.is_none()
+                })
+        {
+            let (_, props) = self.propstats.remove(idx);
+            self.entry((StatusCode::FAILED_DEPENDENCY, None, None))
+                .extend(props);
+        }
+        self
+    }
+
+    fn entry(&mut self, key: PropStatKey) -> &mut Vec<DavPropertyValue> {
+        let idx = if let Some(idx) = self.propstats.iter().position(|(k, _)| k == &key) {
+            idx
+        } else {
+            self.propstats.push((key, Vec::new()));
+            self.propstats.len() - 1
+        };
+        &mut self.propstats[idx].1
+    }
+
     pub fn build(self) -> Vec<PropStat> {
-        self.propstats
+        let is_minimal = self.is_minimal;
+        let mut propstats = self
+            .propstats
             .into_iter()
+            .filter(|((status, _, _), _)| !is_minimal || *status != StatusCode::NOT_FOUND)
             .map(|((status, condition, description), props)| PropStat {
                 prop: Prop(List(props)),
                 status: Status(status),
                 error: condition,
                 response_description: description.map(ResponseDescription),
             })
-            .collect()
+            .collect::<Vec<_>>();
+
+        // Responses must contain at least one propstat
+        if is_minimal && propstats.is_empty() {
+            propstats.push(PropStat::new_list(vec![]));
+        }
+
+        propstats
     }
 }
@@ -9,7 +9,7 @@ use dav_proto::{
     RequestHeaders,
     schema::{
         property::{DavProperty, WebDavProperty},
-        request::{PrincipalMatch, PropFind},
+        request::{PrincipalMatch, PrincipalMatchProperties, PropFind},
         response::MultiStatus,
     },
 };
@@ -55,6 +55,20 @@ impl PrincipalMatching for Server {
                         .push(DavProperty::WebDav(WebDavProperty::Owner));
                 }
                 if let Some(account_id) = resource.account_id {
+                    // Members are matched through the principal that owns them,
+                    // as they are not principals themselves
+                    let is_match = match &request.principal_properties {
+                        PrincipalMatchProperties::Properties(properties) => {
+                            properties.contains(&DavProperty::WebDav(WebDavProperty::Owner))
+                                && access_token.is_member(account_id)
+                        }
+                        PrincipalMatchProperties::Self_ => false,
+                    };
+                    if !is_match {
+                        return Ok(HttpResponse::new(StatusCode::MULTI_STATUS)
+                            .with_xml_body(MultiStatus::new(vec![]).to_string()));
+                    }
+
                     return self
                         .handle_dav_query(
                             access_token,
@@ -72,6 +86,9 @@ impl PrincipalMatching for Server {
                                 sync_type: Default::default(),
                                 limit: Default::default(),
                                 max_vcard_version: Default::default(),
+                                jcard: Default::default(),
+                                no_timezones: Default::default(),
+                                default_alarms: Default::default(),
                                 expand: Default::default(),
                             },
                         )
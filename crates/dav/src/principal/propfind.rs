@@ -6,7 +6,7 @@
 
 use std::borrow::Cow;
 
-use common::{Server, auth::AccessToken};
+use common::{ScheduleCollection, Server, auth::AccessToken};
 use dav_proto::schema::{
     Namespace,
     property::{
@@ -16,7 +16,10 @@ use dav_proto::schema::{
     response::{Href, MultiStatus, PropStat, Response},
 };
 use directory::{QueryBy, backend::internal::manage::ManageDirectory};
-use groupware::cache::GroupwareCache;
+use groupware::{
+    cache::GroupwareCache,
+    calendar::{scheduling::CalendarScheduling, sharing::NOTIFICATION_COLLECTION_NAME},
+};
 use hyper::StatusCode;
 use jmap_proto::types::collection::Collection;
 use percent_encoding::NON_ALPHANUMERIC;
@@ -24,7 +27,10 @@ use trc::AddContext;
 
 use crate::{
     DavResourceName,
-    common::propfind::{PropFindRequestHandler, SyncTokenUrn},
+    common::{
+        apns::{apns_push_key_for, apns_push_transports},
+        propfind::{PropFindRequestHandler, SyncTokenUrn},
+    },
 };
 
 use super::CurrentUserPrincipal;
@@ -227,6 +233,25 @@ impl PrincipalPropFind for Server {
                                 )],
                             ));
                         }
+                        WebDavProperty::PushTransports if !is_principal => {
+                            if let Some(transports) = apns_push_transports(self, collection.into())
+                            {
+                                fields.push(DavPropertyValue::new(property.clone(), transports));
+                            } else {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                            response.set_namespace(Namespace::CalendarServer);
+                        }
+                        WebDavProperty::PushKey if !is_principal => {
+                            if let Some(push_key) =
+                                apns_push_key_for(self, collection.into(), account_id)
+                            {
+                                fields.push(DavPropertyValue::new(property.clone(), push_key));
+                            } else {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                            response.set_namespace(Namespace::CalendarServer);
+                        }
                         _ => {
                             response.set_namespace(property.namespace());
                             fields_not_found.push(DavPropertyValue::empty(property.clone()));
@@ -274,6 +299,102 @@ impl PrincipalPropFind for Server {
                             fields_not_found.push(DavPropertyValue::empty(property.clone()));
                             response.set_namespace(Namespace::CardDav);
                         }
+                        PrincipalProperty::DirectoryGateway => {
+                            if let Some(gateway) = self
+                                .core
+                                .groupware
+                                .directory_gateway
+                                .as_ref()
+                                .filter(|_| access_token.primary_id() == account_id)
+                            {
+                                fields.push(DavPropertyValue::new(
+                                    property.clone(),
+                                    vec![Href(format!(
+                                        "{}/{}/{}/",
+                                        DavResourceName::Card.base_path(),
+                                        percent_encoding::utf8_percent_encode(
+                                            &name,
+                                            NON_ALPHANUMERIC
+                                        ),
+                                        percent_encoding::utf8_percent_encode(
+                                            gateway,
+                                            NON_ALPHANUMERIC
+                                        ),
+                                    ))],
+                                ));
+                            } else {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                            response.set_namespace(Namespace::CardDav);
+                        }
+                        PrincipalProperty::NotificationURL => {
+                            if access_token.primary_id() == account_id {
+                                fields.push(DavPropertyValue::new(
+                                    property.clone(),
+                                    vec![Href(format!(
+                                        "{}/{}/{}/",
+                                        DavResourceName::Cal.base_path(),
+                                        percent_encoding::utf8_percent_encode(
+                                            &name,
+                                            NON_ALPHANUMERIC
+                                        ),
+                                        NOTIFICATION_COLLECTION_NAME,
+                                    ))],
+                                ));
+                            } else {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                            response.set_namespace(Namespace::CalendarServer);
+                        }
+                        PrincipalProperty::ScheduleInboxURL
+                        | PrincipalProperty::ScheduleOutboxURL => {
+                            let schedule = if self.core.groupware.scheduling_enabled
+                                && access_token.is_member(account_id)
+                            {
+                                let kind = if matches!(
+                                    principal_property,
+                                    PrincipalProperty::ScheduleInboxURL
+                                ) {
+                                    ScheduleCollection::Inbox
+                                } else {
+                                    ScheduleCollection::Outbox
+                                };
+                                let resources = self
+                                    .schedule_resources(access_token, account_id)
+                                    .await
+                                    .caused_by(trc::location!())?;
+                                resources
+                                    .schedule_collection(kind)
+                                    .map(|collection| Href(resources.format_resource(collection)))
+                            } else {
+                                None
+                            };
+                            if let Some(href) = schedule {
+                                fields.push(DavPropertyValue::new(property.clone(), vec![href]));
+                            } else {
+                                fields_not_found.push(DavPropertyValue::empty(property.clone()));
+                            }
+                            response.set_namespace(Namespace::CalDav);
+                        }
+                        PrincipalProperty::CalendarUserAddressSet => {
+                            let emails = if access_token.primary_id() == account_id {
+                                access_token.emails.clone()
+                            } else {
+                                self.get_access_token(account_id)
+                                    .await
+                                    .caused_by(trc::location!())?
+                                    .emails
+                                    .clone()
+                            };
+                            fields.push(DavPropertyValue::new(
+                                property.clone(),
+                                emails
+                                    .into_iter()
+                                    .map(|email| Href(format!("mailto:{email}")))
+                                    .collect::<Vec<_>>(),
+                            ));
+                            response.set_namespace(Namespace::CalDav);
+                        }
                     },
                     _ => {
                         response.set_namespace(property.namespace());
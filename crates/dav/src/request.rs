@@ -7,51 +7,76 @@
 use crate::{
     DavError, DavMethod, DavResourceName,
     calendar::{
-        copy_move::CalendarCopyMoveRequestHandler, delete::CalendarDeleteRequestHandler,
-        freebusy::CalendarFreebusyRequestHandler, get::CalendarGetRequestHandler,
-        mkcol::CalendarMkColRequestHandler, proppatch::CalendarPropPatchRequestHandler,
-        query::CalendarQueryRequestHandler, update::CalendarUpdateRequestHandler,
+        attachment::CalendarAttachmentRequestHandler, copy_move::CalendarCopyMoveRequestHandler,
+        delete::CalendarDeleteRequestHandler, freebusy::CalendarFreebusyRequestHandler,
+        get::CalendarGetRequestHandler, mkcol::CalendarMkColRequestHandler,
+        proppatch::CalendarPropPatchRequestHandler, query::CalendarQueryRequestHandler,
+        sharing::CalendarSharingRequestHandler, update::CalendarUpdateRequestHandler,
     },
     card::{
         copy_move::CardCopyMoveRequestHandler, delete::CardDeleteRequestHandler,
-        get::CardGetRequestHandler, mkcol::CardMkColRequestHandler,
-        proppatch::CardPropPatchRequestHandler, query::CardQueryRequestHandler,
-        update::CardUpdateRequestHandler,
+        directory::CardDirectoryRequestHandler, get::CardGetRequestHandler,
+        mkcol::CardMkColRequestHandler, proppatch::CardPropPatchRequestHandler,
+        query::CardQueryRequestHandler, update::CardUpdateRequestHandler,
     },
     common::{
         DavQuery,
         acl::DavAclHandler,
+        add_member::AddMemberRequestHandler,
+        compression::{CompressResponse, ContentEncoding},
+        conditional::ReportNotModifiedHandler,
         lock::{LockRequest, LockRequestHandler},
         propfind::PropFindRequestHandler,
+        push::{DavPushRequestHandler, is_push_register},
+        slow::SlowRequestLog,
         uri::DavUriResource,
     },
     file::{
-        copy_move::FileCopyMoveRequestHandler, delete::FileDeleteRequestHandler,
-        get::FileGetRequestHandler, mkcol::FileMkColRequestHandler,
-        proppatch::FilePropPatchRequestHandler, update::FileUpdateRequestHandler,
+        bind::FileBindRequestHandler,
+        copy_move::FileCopyMoveRequestHandler,
+        delete::FileDeleteRequestHandler,
+        get::FileGetRequestHandler,
+        mkcol::FileMkColRequestHandler,
+        mount::FileMountRequestHandler,
+        proppatch::FilePropPatchRequestHandler,
+        trash::FileTrashRequestHandler,
+        update::{FileUpdateRequestHandler, FileUpload},
+        version::FileVersionRequestHandler,
     },
     principal::{matching::PrincipalMatching, propsearch::PrincipalPropSearch},
 };
-use common::{Server, auth::AccessToken};
+use common::{Server, auth::AccessToken, telemetry::capture::CapturedRequest};
 use compact_str::{CompactString, ToCompactString};
 use dav_proto::{
-    RequestHeaders,
-    parser::{DavParser, tokenizer::Tokenizer},
+    Depth, RequestHeaders, Return,
+    parser::{
+        DavParser,
+        tokenizer::{ElementScanner, Tokenizer},
+    },
     schema::{
         Namespace,
         property::WebDavProperty,
-        request::{Acl, LockInfo, MkCol, PropFind, PropertyUpdate, Report},
+        request::{
+            Acl, Bind, LockInfo, MkCol, PropFind, PropertyUpdate, PushRegister, Report,
+            ShareRequest, Unbind,
+        },
         response::{
             BaseCondition, ErrorResponse, PrincipalSearchProperty, PrincipalSearchPropertySet,
         },
     },
 };
 use directory::Permission;
-use http_proto::{HttpRequest, HttpResponse, HttpSessionData, request::fetch_body};
-use hyper::{StatusCode, header};
+use http_body_util::BodyExt;
+use http_proto::{HttpRequest, HttpResponse, HttpResponseBody, HttpSessionData};
+use hyper::{StatusCode, body::Incoming, header, http::request::Parts};
 use jmap_proto::types::collection::Collection;
 use std::{sync::Arc, time::Instant};
+use store::write::now;
 use trc::{EventType, LimitEvent, StoreEvent, WebDavEvent};
+use utils::url_params::UrlParams;
+
+// Suggested delay before retrying a throttled request
+const RETRY_AFTER_SECS: &str = "5";
 
 pub trait DavRequestHandler: Sync + Send {
     fn handle_dav_request(
@@ -64,32 +89,125 @@ pub trait DavRequestHandler: Sync + Send {
     ) -> impl Future<Output = HttpResponse> + Send;
 }
 
+pub(crate) trait DavRequestParser: Sync + Send {
+    fn request_tokenizer<'x>(&self, body: &'x [u8]) -> Tokenizer<'x>;
+}
+
 pub(crate) trait DavRequestDispatcher: Sync + Send {
     fn dispatch_dav_request(
         &self,
-        request: &HttpRequest,
+        request: &Parts,
         headers: &RequestHeaders<'_>,
         access_token: Arc<AccessToken>,
         resource: DavResourceName,
         method: DavMethod,
-        body: Vec<u8>,
+        body: Arc<Vec<u8>>,
+        upload: Option<&mut Incoming>,
     ) -> impl Future<Output = crate::Result<HttpResponse>> + Send;
 }
 
 impl DavRequestDispatcher for Server {
     async fn dispatch_dav_request(
         &self,
-        request: &HttpRequest,
+        request: &Parts,
         headers: &RequestHeaders<'_>,
         access_token: Arc<AccessToken>,
         resource: DavResourceName,
         method: DavMethod,
-        body: Vec<u8>,
+        body: Arc<Vec<u8>>,
+        upload: Option<&mut Incoming>,
     ) -> crate::Result<HttpResponse> {
+        // Proxy requests addressed to externally mounted storage
+        if resource == DavResourceName::File {
+            if let Some(target) = self.resolve_file_mount(&access_token, headers.uri).await? {
+                // Buffered bodies are either parsed as a request or written as
+                // the file contents, never both
+                let body = Arc::unwrap_or_clone(body);
+                let (body, upload) = if let Some(upload) = upload {
+                    let content_length = request
+                        .headers
+                        .get(header::CONTENT_LENGTH)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<usize>().ok());
+                    (
+                        body,
+                        FileUpload::Streamed {
+                            body: upload,
+                            content_length,
+                        },
+                    )
+                } else if matches!(method, DavMethod::PUT | DavMethod::POST | DavMethod::PATCH) {
+                    (Vec::new(), FileUpload::Buffered(body))
+                } else {
+                    (body, FileUpload::Buffered(Vec::new()))
+                };
+
+                return self
+                    .handle_file_mount_request(&access_token, headers, target, method, body, upload)
+                    .await;
+            }
+        }
+
+        // Serve the read-only directory gateway from the directory principals
+        if resource == DavResourceName::Card {
+            if let Some(target) = self
+                .resolve_directory_gateway(&access_token, headers.uri)
+                .await?
+            {
+                return self
+                    .handle_directory_gateway_request(
+                        &access_token,
+                        headers,
+                        target,
+                        method,
+                        Arc::unwrap_or_clone(body),
+                    )
+                    .await;
+            }
+        }
+
+        // Serve the calendar sharing notifications of the caller
+        if resource == DavResourceName::Cal {
+            if let Some(target) = self
+                .resolve_calendar_notifications(&access_token, headers.uri)
+                .await?
+            {
+                return self
+                    .handle_calendar_notification_request(
+                        &access_token,
+                        headers,
+                        target,
+                        method,
+                        Arc::unwrap_or_clone(body),
+                    )
+                    .await;
+            }
+        }
+
+        // WebDAV-Push subscriptions to calendar and address book collections
+        if matches!(method, DavMethod::POST)
+            && matches!(resource, DavResourceName::Cal | DavResourceName::Card)
+            && headers.content_type.is_some_and(|h| h.contains("xml"))
+            && is_push_register(&body)
+        {
+            let request = PushRegister::parse(&mut self.request_tokenizer(&body))?;
+
+            return self
+                .handle_push_register_request(&access_token, headers, resource, request)
+                .await;
+        }
+
         // Dispatch
         match method {
             DavMethod::PROPFIND => {
-                let request = PropFind::parse(&mut Tokenizer::new(&body))?;
+                let request = PropFind::parse(&mut self.request_tokenizer(&body))?;
+
+                // Limit concurrent expensive requests
+                let _in_flight = if headers.depth == Depth::Infinity {
+                    self.is_dav_expensive_request_allowed(&access_token)?
+                } else {
+                    None
+                };
 
                 self.handle_propfind_request(&access_token, headers, request)
                     .await
@@ -113,6 +231,7 @@ impl DavRequestDispatcher for Server {
                     self.handle_calendar_get_request(
                         &access_token,
                         headers,
+                        request.uri.query(),
                         matches!(method, DavMethod::HEAD),
                     )
                     .await
@@ -127,8 +246,9 @@ impl DavRequestDispatcher for Server {
                         self.handle_file_get_request(
                             &access_token,
                             headers,
+                            request.uri.query(),
                             matches!(method, DavMethod::HEAD)
-                                && !request.headers().contains_key("x-litmus"),
+                                && !request.headers.contains_key("x-litmus"),
                         )
                         .await
                     }
@@ -138,6 +258,7 @@ impl DavRequestDispatcher for Server {
                         self.handle_file_get_request(
                             &access_token,
                             headers,
+                            request.uri.query(),
                             matches!(method, DavMethod::HEAD),
                         )
                         .await
@@ -145,11 +266,18 @@ impl DavRequestDispatcher for Server {
                 }
                 DavResourceName::Principal => Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED)),
             },
-            DavMethod::REPORT => match Report::parse(&mut Tokenizer::new(&body))? {
+            DavMethod::REPORT => match Report::parse(&mut self.request_tokenizer(&body))? {
                 Report::SyncCollection(sync_collection) => {
                     // Validate permissions
                     access_token.assert_has_permission(Permission::DavSyncCollection)?;
 
+                    // Limit concurrent expensive requests
+                    let _in_flight = if sync_collection.depth == Depth::Infinity {
+                        self.is_dav_expensive_request_allowed(&access_token)?
+                    } else {
+                        None
+                    };
+
                     let uri = self
                         .validate_uri(&access_token, headers.uri)
                         .await
@@ -213,13 +341,34 @@ impl DavRequestDispatcher for Server {
                     // Validate permissions
                     access_token.assert_has_permission(Permission::DavCardQuery)?;
 
-                    self.handle_card_query_request(&access_token, headers, report)
-                        .await
+                    // Skip query evaluation if the collection did not change
+                    if let Some(response) = self
+                        .handle_report_not_modified(&access_token, headers, Collection::AddressBook)
+                        .await?
+                    {
+                        return Ok(response);
+                    }
+
+                    self.handle_card_query_request(
+                        &access_token,
+                        headers,
+                        report,
+                        request.uri.query(),
+                    )
+                    .await
                 }
                 Report::AddressbookMultiGet(report) => {
                     // Validate permissions
                     access_token.assert_has_permission(Permission::DavCardMultiGet)?;
 
+                    // Skip query evaluation if the collection did not change
+                    if let Some(response) = self
+                        .handle_report_not_modified(&access_token, headers, Collection::AddressBook)
+                        .await?
+                    {
+                        return Ok(response);
+                    }
+
                     self.handle_dav_query(
                         &access_token,
                         DavQuery::multiget(report, Collection::AddressBook, headers),
@@ -230,6 +379,21 @@ impl DavRequestDispatcher for Server {
                     // Validate permissions
                     access_token.assert_has_permission(Permission::DavCalQuery)?;
 
+                    // Limit concurrent expensive requests
+                    let _in_flight = if report.has_expansion() {
+                        self.is_dav_expensive_request_allowed(&access_token)?
+                    } else {
+                        None
+                    };
+
+                    // Skip query evaluation if the collection did not change
+                    if let Some(response) = self
+                        .handle_report_not_modified(&access_token, headers, Collection::Calendar)
+                        .await?
+                    {
+                        return Ok(response);
+                    }
+
                     self.handle_calendar_query_request(&access_token, headers, report)
                         .await
                 }
@@ -237,6 +401,14 @@ impl DavRequestDispatcher for Server {
                     // Validate permissions
                     access_token.assert_has_permission(Permission::DavCalMultiGet)?;
 
+                    // Skip query evaluation if the collection did not change
+                    if let Some(response) = self
+                        .handle_report_not_modified(&access_token, headers, Collection::Calendar)
+                        .await?
+                    {
+                        return Ok(response);
+                    }
+
                     self.handle_dav_query(
                         &access_token,
                         DavQuery::multiget(report, Collection::Calendar, headers),
@@ -272,9 +444,20 @@ impl DavRequestDispatcher for Server {
                         }
                     }
                 }
+                Report::VersionTree(report) => {
+                    if resource == DavResourceName::File {
+                        // Validate permissions
+                        access_token.assert_has_permission(Permission::DavFilePropFind)?;
+
+                        self.handle_file_version_tree_request(&access_token, headers, report)
+                            .await
+                    } else {
+                        Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED))
+                    }
+                }
             },
             DavMethod::PROPPATCH => {
-                let request = PropertyUpdate::parse(&mut Tokenizer::new(&body))?;
+                let request = PropertyUpdate::parse(&mut self.request_tokenizer(&body))?;
                 match resource {
                     DavResourceName::Card => {
                         // Validate permissions
@@ -304,7 +487,7 @@ impl DavRequestDispatcher for Server {
             }
             DavMethod::MKCOL => {
                 let request = if !body.is_empty() {
-                    Some(MkCol::parse(&mut Tokenizer::new(&body))?)
+                    Some(MkCol::parse(&mut self.request_tokenizer(&body))?)
                 } else {
                     None
                 };
@@ -360,47 +543,149 @@ impl DavRequestDispatcher for Server {
                 }
                 DavResourceName::Principal => Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED)),
             },
-            DavMethod::PUT | DavMethod::POST | DavMethod::PATCH => match resource {
-                DavResourceName::Card => {
-                    // Validate permissions
-                    access_token.assert_has_permission(Permission::DavCardPut)?;
+            DavMethod::PUT | DavMethod::POST | DavMethod::PATCH => {
+                // Create members with a server assigned name (RFC 5995)
+                let add_member =
+                    matches!(method, DavMethod::POST) && request.uri.query() == Some("add-member");
+                let member_uri;
+                let member_headers;
+                let headers = if add_member {
+                    member_uri = self
+                        .add_member_uri(&access_token, headers, resource)
+                        .await?;
+                    member_headers = RequestHeaders {
+                        uri: &member_uri,
+                        ..headers.clone()
+                    };
+                    &member_headers
+                } else {
+                    headers
+                };
 
-                    self.handle_card_update_request(
-                        &access_token,
-                        headers,
-                        body,
-                        matches!(method, DavMethod::PATCH),
-                    )
-                    .await
-                }
-                DavResourceName::Cal => {
-                    // Validate permissions
-                    access_token.assert_has_permission(Permission::DavCalPut)?;
+                let response = match resource {
+                    DavResourceName::Card => {
+                        // Validate permissions
+                        access_token.assert_has_permission(Permission::DavCardPut)?;
 
-                    self.handle_calendar_update_request(
-                        &access_token,
-                        headers,
-                        body,
-                        matches!(method, DavMethod::PATCH),
-                    )
-                    .await
-                }
-                DavResourceName::File => {
-                    // Validate permissions
-                    access_token.assert_has_permission(Permission::DavFilePut)?;
+                        self.handle_card_update_request(
+                            &access_token,
+                            headers,
+                            Arc::unwrap_or_clone(body),
+                            matches!(method, DavMethod::PATCH),
+                        )
+                        .await
+                    }
+                    DavResourceName::Cal => {
+                        // Validate permissions
+                        access_token.assert_has_permission(Permission::DavCalPut)?;
 
-                    self.handle_file_update_request(
-                        &access_token,
-                        headers,
-                        body,
-                        matches!(method, DavMethod::PATCH),
-                    )
-                    .await
+                        // Managed attachments (RFC 8607)
+                        if matches!(method, DavMethod::POST) && !add_member {
+                            if let Some(query) = request.uri.query() {
+                                return self
+                                    .handle_calendar_attachment_request(
+                                        &access_token,
+                                        headers,
+                                        query,
+                                        request
+                                            .headers
+                                            .get(header::CONTENT_DISPOSITION)
+                                            .and_then(|v| v.to_str().ok()),
+                                        Arc::unwrap_or_clone(body),
+                                    )
+                                    .await;
+                            }
+
+                            // Calendar sharing (calendarserver.org sharing extension)
+                            if headers.content_type.is_some_and(|h| h.contains("xml")) {
+                                access_token.assert_has_permission(Permission::DavCalAcl)?;
+                                let request =
+                                    ShareRequest::parse(&mut self.request_tokenizer(&body))?;
+
+                                return self
+                                    .handle_calendar_share_request(&access_token, headers, request)
+                                    .await;
+                            }
+                        }
+
+                        self.handle_calendar_update_request(
+                            &access_token,
+                            headers,
+                            Arc::unwrap_or_clone(body),
+                            matches!(method, DavMethod::PATCH),
+                        )
+                        .await
+                    }
+                    DavResourceName::File => {
+                        // Validate permissions
+                        access_token.assert_has_permission(Permission::DavFilePut)?;
+
+                        // Restore a previous version of a file or an item from the trash
+                        if matches!(method, DavMethod::POST) && !add_member {
+                            if let Some(query) = request.uri.query() {
+                                return if UrlParams::new(query.into()).get("action")
+                                    == Some("restore")
+                                {
+                                    self.handle_file_trash_restore_request(&access_token, headers)
+                                        .await
+                                } else {
+                                    self.handle_file_version_restore_request(
+                                        &access_token,
+                                        headers,
+                                        query,
+                                    )
+                                    .await
+                                };
+                            }
+                        }
+
+                        let upload = if let Some(body) = upload {
+                            FileUpload::Streamed {
+                                body,
+                                content_length: request
+                                    .headers
+                                    .get(header::CONTENT_LENGTH)
+                                    .and_then(|v| v.to_str().ok())
+                                    .and_then(|v| v.parse::<usize>().ok()),
+                            }
+                        } else {
+                            FileUpload::Buffered(Arc::unwrap_or_clone(body))
+                        };
+
+                        self.handle_file_update_request(
+                            &access_token,
+                            headers,
+                            upload,
+                            matches!(method, DavMethod::PATCH),
+                        )
+                        .await
+                    }
+                    DavResourceName::Principal => {
+                        Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED))
+                    }
+                };
+
+                if add_member {
+                    response.map(|response| {
+                        if response.status() == StatusCode::CREATED {
+                            response.with_header(header::LOCATION, headers.uri)
+                        } else {
+                            response
+                        }
+                    })
+                } else {
+                    response
                 }
-                DavResourceName::Principal => Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED)),
-            },
+            }
             DavMethod::COPY | DavMethod::MOVE => {
                 let is_move = matches!(method, DavMethod::MOVE);
+
+                // Limit concurrent expensive requests
+                let _in_flight = if is_move || headers.depth != Depth::Zero {
+                    self.is_dav_expensive_request_allowed(&access_token)?
+                } else {
+                    None
+                };
                 match resource {
                     DavResourceName::Card => {
                         // Validate permissions
@@ -447,7 +732,7 @@ impl DavRequestDispatcher for Server {
                     self.handle_calendar_mkcol_request(
                         &access_token,
                         headers,
-                        Some(MkCol::parse(&mut Tokenizer::new(&body))?),
+                        Some(MkCol::parse(&mut self.request_tokenizer(&body))?),
                     )
                     .await
                 }
@@ -466,7 +751,7 @@ impl DavRequestDispatcher for Server {
                     &access_token,
                     headers,
                     if !body.is_empty() {
-                        LockRequest::Lock(LockInfo::parse(&mut Tokenizer::new(&body))?)
+                        LockRequest::Lock(LockInfo::parse(&mut self.request_tokenizer(&body))?)
                     } else {
                         LockRequest::Refresh
                     },
@@ -497,10 +782,42 @@ impl DavRequestDispatcher for Server {
                 self.handle_acl_request(
                     &access_token,
                     headers,
-                    Acl::parse(&mut Tokenizer::new(&body))?,
+                    Acl::parse(&mut self.request_tokenizer(&body))?,
                 )
                 .await
             }
+            DavMethod::BIND | DavMethod::REBIND => match resource {
+                DavResourceName::File => {
+                    // Validate permissions
+                    access_token.assert_has_permission(if matches!(method, DavMethod::REBIND) {
+                        Permission::DavFileMove
+                    } else {
+                        Permission::DavFilePut
+                    })?;
+
+                    self.handle_file_bind_request(
+                        &access_token,
+                        headers,
+                        Bind::parse(&mut self.request_tokenizer(&body))?,
+                    )
+                    .await
+                }
+                _ => Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED)),
+            },
+            DavMethod::UNBIND => match resource {
+                DavResourceName::File => {
+                    // Validate permissions
+                    access_token.assert_has_permission(Permission::DavFileDelete)?;
+
+                    self.handle_file_unbind_request(
+                        &access_token,
+                        headers,
+                        Unbind::parse(&mut self.request_tokenizer(&body))?,
+                    )
+                    .await
+                }
+                _ => Err(DavError::Code(StatusCode::METHOD_NOT_ALLOWED)),
+            },
             DavMethod::OPTIONS => unreachable!(),
         }
     }
@@ -509,40 +826,106 @@ impl DavRequestDispatcher for Server {
 impl DavRequestHandler for Server {
     async fn handle_dav_request(
         &self,
-        mut request: HttpRequest,
+        request: HttpRequest,
         access_token: Arc<AccessToken>,
         session: &HttpSessionData,
         resource: DavResourceName,
         method: DavMethod,
     ) -> HttpResponse {
-        let body = if method.has_body()
+        let (request, mut body_stream) = request.into_parts();
+
+        // File uploads are streamed by the update handler
+        let is_streamed_upload = resource == DavResourceName::File
+            && matches!(method, DavMethod::PUT | DavMethod::POST | DavMethod::PATCH)
+            && !request.headers.contains_key(header::CONTENT_ENCODING);
+
+        let body = if is_streamed_upload {
+            Vec::new()
+        } else if method.has_body()
             || request
-                .headers()
+                .headers
                 .get(header::CONTENT_LENGTH)
                 .and_then(|v| v.to_str().ok())
                 .and_then(|v| v.parse::<u64>().ok())
                 .is_some_and(|len| len > 0)
         {
-            if let Some(body) = fetch_body(
-                &mut request,
-                if !access_token.has_permission(Permission::UnlimitedUploads) {
-                    self.core.groupware.max_request_size
-                } else {
-                    0
-                },
-                session.session_id,
-            )
-            .await
-            {
-                body
+            let max_size = if !access_token.has_permission(Permission::UnlimitedUploads) {
+                self.core.groupware.max_request_size
             } else {
-                trc::event!(
-                    Limit(trc::LimitEvent::SizeRequest),
-                    SpanId = session.session_id,
-                    Contents = "Request body too large",
-                );
+                0
+            };
 
-                return HttpResponse::new(StatusCode::PAYLOAD_TOO_LARGE);
+            // Uncompressed XML bodies are scanned as they are read
+            let scanner = (!request.headers.contains_key(header::CONTENT_ENCODING)
+                && (method.has_xml_body()
+                    || request
+                        .headers
+                        .get(header::CONTENT_TYPE)
+                        .and_then(|v| v.to_str().ok())
+                        .is_some_and(|v| v.contains("xml"))))
+            .then(|| {
+                ElementScanner::new(
+                    self.core.groupware.max_request_depth,
+                    self.core.groupware.max_request_elements,
+                )
+            });
+            let body = match fetch_dav_body(&mut body_stream, max_size, scanner, session.session_id)
+                .await
+            {
+                Ok(Some(body)) => body,
+                Ok(None) => {
+                    trc::event!(
+                        Limit(trc::LimitEvent::SizeRequest),
+                        SpanId = session.session_id,
+                        Contents = "Request body too large",
+                    );
+
+                    return error_response(StatusCode::PAYLOAD_TOO_LARGE);
+                }
+                Err(err) => {
+                    trc::event!(
+                        WebDav(WebDavEvent::Error),
+                        SpanId = session.session_id,
+                        Url = request.uri.path().to_compact_string(),
+                        Type = resource.name(),
+                        Result = StatusCode::BAD_REQUEST.as_u16(),
+                        Reason = err.to_compact_string(),
+                    );
+
+                    return error_response(StatusCode::BAD_REQUEST);
+                }
+            };
+
+            // Decompress request body
+            match request
+                .headers
+                .get(header::CONTENT_ENCODING)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty() && !v.eq_ignore_ascii_case("identity"))
+            {
+                Some(encoding) if !body.is_empty() => {
+                    let Some(encoding) = ContentEncoding::parse(encoding) else {
+                        return error_response(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+                    };
+
+                    match encoding.decode(&body, max_size) {
+                        Ok(Some(body)) => body,
+                        Ok(None) => {
+                            trc::event!(
+                                Limit(trc::LimitEvent::SizeRequest),
+                                SpanId = session.session_id,
+                                Contents = "Decompressed request body too large",
+                            );
+
+                            return error_response(StatusCode::PAYLOAD_TOO_LARGE);
+                        }
+                        Err(_) => {
+                            return error_response(StatusCode::BAD_REQUEST);
+                        }
+                    }
+                }
+                _ => body,
             }
         } else {
             Vec::new()
@@ -552,15 +935,94 @@ impl DavRequestHandler for Server {
         //let std_body = std::str::from_utf8(&body).unwrap_or("[binary]").to_string();
 
         // Parse headers
-        let mut headers = RequestHeaders::new(request.uri().path());
-        for (key, value) in request.headers() {
+        let mut headers = RequestHeaders::new(request.uri.path());
+        for (key, value) in request.headers.iter() {
             headers.parse(key.as_str(), value.to_str().unwrap_or_default());
         }
 
+        // Apply client workarounds
+        let client_policy = request
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|user_agent| self.core.groupware.client_policy(user_agent));
+        if let Some(policy) = client_policy {
+            if let Some(vcard_version) = policy.vcard_version {
+                headers.max_vcard_version = Some(vcard_version);
+            }
+            if !policy.alternate_formats {
+                headers.calendar_data_type = None;
+            }
+            if !policy.prefer {
+                headers.ret = Return::Default;
+                headers.depth_no_root = false;
+            }
+        }
+
+        // Negotiate response encoding
+        let response_compression = self
+            .core
+            .groupware
+            .compression_min_size
+            .filter(|_| client_policy.is_none_or(|policy| policy.compression))
+            .map(|min_size| {
+                (
+                    request
+                        .headers
+                        .get(header::ACCEPT_ENCODING)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(ContentEncoding::negotiate),
+                    min_size,
+                )
+            });
+
+        // Record the request when a capture is active for the account
+        let account_id = access_token.primary_id();
+        let capture = self.dav_capture_body_size(account_id).map(|max_body_size| {
+            (
+                CapturedRequest {
+                    timestamp: now(),
+                    method: request.method.to_string(),
+                    uri: request.uri.to_string(),
+                    request_headers: CapturedRequest::capture_headers(&request.headers),
+                    request_body: CapturedRequest::capture_body(&body, max_body_size),
+                    ..Default::default()
+                },
+                max_body_size,
+            )
+        });
+
+        // Keep what is needed to describe the request if it turns out to be slow,
+        // sharing the body with the handler rather than copying it
+        let body = Arc::new(body);
+        let slow_request = self
+            .core
+            .groupware
+            .slow_request_threshold
+            .filter(|_| {
+                matches!(
+                    method,
+                    DavMethod::REPORT | DavMethod::PROPFIND | DavMethod::COPY
+                )
+            })
+            .map(|threshold| (threshold, access_token.clone(), body.clone()));
+
+        // Store reads and batch commits performed by the handler are
+        // reported under the request's span
         let start_time = Instant::now();
-        match self
-            .dispatch_dav_request(&request, &headers, access_token, resource, method, body)
-            .await
+        let response = match trc::in_span(
+            session.session_id,
+            self.dispatch_dav_request(
+                &request,
+                &headers,
+                access_token,
+                resource,
+                method,
+                body,
+                is_streamed_upload.then_some(&mut body_stream),
+            ),
+        )
+        .await
         {
             Ok(response) => {
                 let event = WebDavEvent::from(method);
@@ -568,6 +1030,7 @@ impl DavRequestHandler for Server {
                 trc::event!(
                     WebDav(event),
                     SpanId = session.session_id,
+                    AccountId = account_id,
                     Url = headers.uri.to_compact_string(),
                     Type = resource.name(),
                     Details = &headers,
@@ -582,6 +1045,7 @@ impl DavRequestHandler for Server {
 
                 trc::error!(
                     err.span_id(session.session_id)
+                        .ctx(trc::Key::AccountId, account_id)
                         .ctx(trc::Key::Url, headers.uri.to_compact_string())
                         .ctx(trc::Key::Type, resource.name())
                         .ctx(trc::Key::Elapsed, start_time.elapsed())
@@ -604,22 +1068,30 @@ impl DavRequestHandler for Server {
                             .with_no_cache()
                     }
                     EventType::Store(StoreEvent::AssertValueFailed) => {
-                        HttpResponse::new(StatusCode::CONFLICT)
+                        error_response(StatusCode::CONFLICT)
                     }
-                    EventType::Security(_) => HttpResponse::new(StatusCode::FORBIDDEN),
-                    _ => HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR),
+                    EventType::Security(_) => error_response(StatusCode::FORBIDDEN),
+                    EventType::Limit(
+                        LimitEvent::ConcurrentRequest | LimitEvent::TooManyRequests,
+                    ) => error_response(StatusCode::SERVICE_UNAVAILABLE)
+                        .with_header(header::RETRY_AFTER, RETRY_AFTER_SECS),
+                    _ => error_response(StatusCode::INTERNAL_SERVER_ERROR),
                 }
             }
             Err(DavError::Parse(err)) => {
-                let result = if headers.content_type.is_some_and(|h| h.contains("/xml")) {
-                    StatusCode::BAD_REQUEST
-                } else {
-                    StatusCode::UNSUPPORTED_MEDIA_TYPE
+                let result = match &err {
+                    dav_proto::parser::Error::MaxDepthExceeded(_)
+                    | dav_proto::parser::Error::MaxElementsExceeded(_) => StatusCode::BAD_REQUEST,
+                    _ if headers.content_type.is_some_and(|h| h.contains("/xml")) => {
+                        StatusCode::BAD_REQUEST
+                    }
+                    _ => StatusCode::UNSUPPORTED_MEDIA_TYPE,
                 };
 
                 trc::event!(
                     WebDav(WebDavEvent::Error),
                     SpanId = session.session_id,
+                    AccountId = account_id,
                     Url = headers.uri.to_compact_string(),
                     Type = resource.name(),
                     Details = &headers,
@@ -628,7 +1100,7 @@ impl DavRequestHandler for Server {
                     Elapsed = start_time.elapsed(),
                 );
 
-                HttpResponse::new(result)
+                error_response(result)
             }
             Err(DavError::Condition(condition)) => {
                 let event = WebDavEvent::from(method);
@@ -636,6 +1108,7 @@ impl DavRequestHandler for Server {
                 trc::event!(
                     WebDav(event),
                     SpanId = session.session_id,
+                    AccountId = account_id,
                     Url = headers.uri.to_compact_string(),
                     Type = resource.name(),
                     Details = &headers,
@@ -664,6 +1137,7 @@ impl DavRequestHandler for Server {
                 trc::event!(
                     WebDav(event),
                     SpanId = session.session_id,
+                    AccountId = account_id,
                     Url = headers.uri.to_compact_string(),
                     Type = resource.name(),
                     Details = &headers,
@@ -671,10 +1145,51 @@ impl DavRequestHandler for Server {
                     Elapsed = start_time.elapsed(),
                 );
 
-                HttpResponse::new(code)
+                error_response(code)
+            }
+        };
+
+        if let Some((mut captured, max_body_size)) = capture {
+            captured.status = response.status().as_u16();
+            captured.response_headers = response
+                .headers()
+                .map(CapturedRequest::capture_headers)
+                .unwrap_or_default();
+            captured.response_body = match response.body() {
+                HttpResponseBody::Text(text) => {
+                    CapturedRequest::capture_body(text.as_bytes(), max_body_size)
+                }
+                HttpResponseBody::Binary(bytes) => {
+                    CapturedRequest::capture_body(bytes, max_body_size)
+                }
+                HttpResponseBody::Stream(_) => Some("[stream]".to_string()),
+                HttpResponseBody::WebsocketUpgrade(_) | HttpResponseBody::Empty => None,
+            };
+            self.record_dav_capture(account_id, captured);
+        }
+
+        if let Some((threshold, access_token, body)) = slow_request {
+            let elapsed = start_time.elapsed();
+            if elapsed > threshold {
+                self.log_slow_request(
+                    &access_token,
+                    session.session_id,
+                    &headers,
+                    resource,
+                    method,
+                    &body,
+                    elapsed,
+                )
+                .await;
             }
         }
 
+        if let Some((encoding, min_size)) = response_compression {
+            response.compress(encoding, min_size).await
+        } else {
+            response
+        }
+
         /*let c = println!(
             "{:?} {} -> {:?}\nHeaders: {:?}\nBody: {}\nResponse headers: {:?}\nResponse: {}",
             method,
@@ -694,6 +1209,66 @@ impl DavRequestHandler for Server {
     }
 }
 
+impl DavRequestParser for Server {
+    // Request bodies are scanned for the size and element limits while they are
+    // read, the tokenizer enforces the same limits on bodies that were decoded.
+    fn request_tokenizer<'x>(&self, body: &'x [u8]) -> Tokenizer<'x> {
+        Tokenizer::new(body).with_limits(
+            self.core.groupware.max_request_depth,
+            self.core.groupware.max_request_elements,
+        )
+    }
+}
+
+// Reads a request body while enforcing the size limit, XML documents are also
+// checked against the element limits as each frame arrives
+async fn fetch_dav_body(
+    body: &mut Incoming,
+    max_size: usize,
+    mut scanner: Option<ElementScanner>,
+    session_id: u64,
+) -> dav_proto::parser::Result<Option<Vec<u8>>> {
+    let mut bytes = Vec::with_capacity(1024);
+    while let Some(Ok(frame)) = body.frame().await {
+        if let Some(data) = frame.data_ref() {
+            if max_size > 0 && bytes.len() + data.len() > max_size {
+                trc::event!(
+                    Http(trc::HttpEvent::RequestBody),
+                    SpanId = session_id,
+                    Contents = std::str::from_utf8(&bytes)
+                        .unwrap_or("[binary data]")
+                        .to_string(),
+                    Size = bytes.len(),
+                    Limit = max_size,
+                );
+
+                return Ok(None);
+            }
+            if let Some(scanner) = &mut scanner {
+                scanner.scan(data)?;
+            }
+            bytes.extend_from_slice(data);
+        }
+    }
+
+    trc::event!(
+        Http(trc::HttpEvent::RequestBody),
+        SpanId = session_id,
+        Contents = std::str::from_utf8(&bytes)
+            .unwrap_or("[binary data]")
+            .to_string(),
+        Size = bytes.len(),
+    );
+
+    Ok(Some(bytes))
+}
+
+fn error_response(status: StatusCode) -> HttpResponse {
+    HttpResponse::new(status)
+        .with_xml_body(ErrorResponse::from_status(status).to_string())
+        .with_no_cache()
+}
+
 impl From<dav_proto::parser::Error> for DavError {
     fn from(err: dav_proto::parser::Error) -> Self {
         DavError::Parse(err)
@@ -246,6 +246,39 @@ impl Permission {
             Permission::DavCalQuery => "Search for calendar entries matching criteria",
             Permission::DavCalMultiGet => "Retrieve multiple calendar entries in a single request",
             Permission::DavCalFreeBusyQuery => "Query free/busy time information for scheduling",
+            Permission::GroupwareExport => "Export an account's calendars, contacts and files",
+            Permission::GroupwareImport => "Import calendars, contacts and files into an account",
+            Permission::GroupwareRebuild => "Rebuild the groupware hierarchy of an account",
+            Permission::GroupwareQuota => "Set storage quotas on calendars and folders",
+            Permission::GroupwareCheck => "Check and repair the groupware data of an account",
+            Permission::GroupwareUsage => "View per-collection groupware storage usage",
+            Permission::DavCalAccess => "Access the CalDAV calendar service",
+            Permission::DavCardAccess => "Access the CardDAV contacts service",
+            Permission::DavFileAccess => "Access the WebDAV file storage service",
+            Permission::JmapCalendarGet => "Retrieve calendars via JMAP",
+            Permission::JmapCalendarSet => "Create, update or delete calendars via JMAP",
+            Permission::JmapCalendarChanges => "Track calendar changes via JMAP",
+            Permission::JmapCalendarEventGet => "Retrieve calendar events via JMAP",
+            Permission::JmapCalendarEventSet => "Create, update or delete calendar events via JMAP",
+            Permission::JmapCalendarEventChanges => "Track calendar event changes via JMAP",
+            Permission::JmapCalendarEventQuery => "Perform calendar event queries via JMAP",
+            Permission::JmapCalendarEventQueryChanges => {
+                "Track calendar event query changes via JMAP"
+            }
+            Permission::JmapAddressBookGet => "Retrieve address books via JMAP",
+            Permission::JmapAddressBookSet => "Create, update or delete address books via JMAP",
+            Permission::JmapAddressBookChanges => "Track address book changes via JMAP",
+            Permission::JmapContactCardGet => "Retrieve contact cards via JMAP",
+            Permission::JmapContactCardSet => "Create, update or delete contact cards via JMAP",
+            Permission::JmapContactCardChanges => "Track contact card changes via JMAP",
+            Permission::JmapContactCardQuery => "Perform contact card queries via JMAP",
+            Permission::JmapContactCardQueryChanges => "Track contact card query changes via JMAP",
+            Permission::JmapFileNodeGet => "Retrieve files and folders via JMAP",
+            Permission::JmapFileNodeSet => "Create, update or delete files and folders via JMAP",
+            Permission::JmapFileNodeChanges => "Track file and folder changes via JMAP",
+            Permission::JmapFileNodeQuery => "Perform file and folder queries via JMAP",
+            Permission::JmapFileNodeQueryChanges => "Track file and folder query changes via JMAP",
+            Permission::DavEventSource => "Subscribe to DAV collection change notifications",
         }
     }
 }
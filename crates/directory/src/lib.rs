@@ -373,6 +373,37 @@ pub enum Permission {
     DavCalQuery,
     DavCalMultiGet,
     DavCalFreeBusyQuery,
+    GroupwareExport,
+    GroupwareImport,
+    GroupwareRebuild,
+    GroupwareQuota,
+    GroupwareCheck,
+    GroupwareUsage,
+    DavCalAccess,
+    DavCardAccess,
+    DavFileAccess,
+    JmapCalendarGet,
+    JmapCalendarSet,
+    JmapCalendarChanges,
+    JmapCalendarEventGet,
+    JmapCalendarEventSet,
+    JmapCalendarEventChanges,
+    JmapCalendarEventQuery,
+    JmapCalendarEventQueryChanges,
+    JmapAddressBookGet,
+    JmapAddressBookSet,
+    JmapAddressBookChanges,
+    JmapContactCardGet,
+    JmapContactCardSet,
+    JmapContactCardChanges,
+    JmapContactCardQuery,
+    JmapContactCardQueryChanges,
+    JmapFileNodeGet,
+    JmapFileNodeSet,
+    JmapFileNodeChanges,
+    JmapFileNodeQuery,
+    JmapFileNodeQueryChanges,
+    DavEventSource,
     // WARNING: add new ids at the end (TODO: use static ids)
 }
 
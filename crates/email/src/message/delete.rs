@@ -7,6 +7,7 @@
 use super::metadata::MessageData;
 use crate::{cache::MessageCacheFetch, mailbox::*, message::metadata::MessageMetadata};
 use common::{KV_LOCK_PURGE_ACCOUNT, Server, storage::index::ObjectIndexBuilder};
+use groupware::file::trash::FileTrash;
 use jmap_proto::types::collection::VanishedCollection;
 use jmap_proto::types::{collection::Collection, property::Property};
 use std::future::Future;
@@ -153,6 +154,21 @@ impl EmailDeletion for Server {
             );
         }
 
+        // Purge expired items from the file trash
+        if let Some(period) = self
+            .core
+            .groupware
+            .file_trash_retention
+            .filter(|_| self.core.groupware.file_trash)
+        {
+            if let Err(err) = self.purge_file_trash(account_id, period).await {
+                trc::error!(
+                    err.details("Failed to purge file trash.")
+                        .account_id(account_id)
+                );
+            }
+        }
+
         // Purge changelogs
         if let Some(history) = self.core.jmap.changes_max_history {
             if let Err(err) = self.delete_changes(account_id, history).await {
@@ -7,8 +7,9 @@
 use common::Server;
 
 use directory::Permission;
+use groupware::{IngestResult, calendar::ingest::CalendarIngest};
 use jmap_proto::types::{state::StateChange, type_state::DataType};
-use mail_parser::MessageParser;
+use mail_parser::{Message, MessageParser, PartType};
 use std::{borrow::Cow, future::Future};
 use store::ahash::AHashMap;
 use utils::BlobHash;
@@ -124,6 +125,17 @@ impl MailDelivery for Server {
             }
         };
 
+        // Calendar invitations sent as plain attachments
+        let message_parsed = if self.core.groupware.invites_from_attachments {
+            MessageParser::new().parse(&raw_message)
+        } else {
+            None
+        };
+        let invites = message_parsed
+            .as_ref()
+            .map(calendar_invites)
+            .unwrap_or_default();
+
         // Obtain the UIDs for each recipient
         let mut uids: AHashMap<u32, usize> = AHashMap::with_capacity(message.recipients.len());
         let mut result = LocalDeliveryResult {
@@ -171,7 +183,7 @@ impl MailDelivery for Server {
             }) {
                 Ok(access_token) => {
                     // Check if there is an active sieve script
-                    match self.sieve_script_get_active(uid).await {
+                    let status = match self.sieve_script_get_active(uid).await {
                         Ok(None) => {
                             // Ingest message
                             self.email_ingest(IngestEmail {
@@ -202,7 +214,34 @@ impl MailDelivery for Server {
                             .await
                         }
                         Err(err) => Err(err),
+                    };
+
+                    // Surface calendar invitations in the recipient's calendar
+                    if status.is_ok() {
+                        for invite in &invites {
+                            match self
+                                .ingest_calendar_data(&access_token, uid, None, invite)
+                                .await
+                            {
+                                Ok(IngestResult::Skipped(reason)) => {
+                                    trc::event!(
+                                        Calendar(trc::CalendarEvent::InviteSkipped),
+                                        SpanId = message.session_id,
+                                        AccountId = uid,
+                                        Reason = reason,
+                                    );
+                                }
+                                Ok(_) => {}
+                                Err(err) => {
+                                    trc::error!(
+                                        err.span_id(message.session_id).caused_by(trc::location!())
+                                    );
+                                }
+                            }
+                        }
                     }
+
+                    status
                 }
 
                 Err(err) => Err(err),
@@ -281,3 +320,70 @@ impl MailDelivery for Server {
         result
     }
 }
+
+// Returns the iCalendar attachments of a message that carry new or updated
+// invitations. Replies and cancellations are left to the calendar client.
+pub(crate) fn calendar_invites<'x>(message: &'x Message<'x>) -> Vec<&'x str> {
+    message
+        .parts
+        .iter()
+        .filter(|part| {
+            part.is_content_type("text", "calendar")
+                || part.is_content_type("application", "ics")
+                || part
+                    .attachment_name()
+                    .and_then(|name| name.rsplit_once('.'))
+                    .is_some_and(|(_, ext)| ext.eq_ignore_ascii_case("ics"))
+        })
+        .filter_map(|part| {
+            let text = match &part.body {
+                PartType::Text(text) => text.as_ref(),
+                PartType::Binary(data) | PartType::InlineBinary(data) => {
+                    std::str::from_utf8(data).ok()?
+                }
+                _ => return None,
+            };
+
+            // iMIP parts declare the method in the content type, plain attachments in the object
+            let method = part
+                .content_type()
+                .and_then(|ct| ct.attribute("method"))
+                .or_else(|| {
+                    text.lines().find_map(|line| {
+                        line.get(..7)
+                            .filter(|name| name.eq_ignore_ascii_case("METHOD:"))
+                            .map(|_| line[7..].trim())
+                    })
+                });
+            if method.is_none_or(|method| {
+                method.eq_ignore_ascii_case("REQUEST") || method.eq_ignore_ascii_case("PUBLISH")
+            }) {
+                Some(text)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn vcard_attachments<'x>(message: &'x Message<'x>) -> Vec<&'x str> {
+    message
+        .parts
+        .iter()
+        .filter(|part| {
+            part.is_content_type("text", "vcard")
+                || part.is_content_type("text", "x-vcard")
+                || part
+                    .attachment_name()
+                    .and_then(|name| name.rsplit_once('.'))
+                    .is_some_and(|(_, ext)| {
+                        ext.eq_ignore_ascii_case("vcf") || ext.eq_ignore_ascii_case("vcard")
+                    })
+        })
+        .filter_map(|part| match &part.body {
+            PartType::Text(text) => Some(text.as_ref()),
+            PartType::Binary(data) | PartType::InlineBinary(data) => std::str::from_utf8(data).ok(),
+            _ => None,
+        })
+        .collect()
+}
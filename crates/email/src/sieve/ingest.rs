@@ -9,14 +9,23 @@ use crate::{
     cache::{MessageCacheFetch, mailbox::MailboxCacheAccess},
     mailbox::{INBOX_ID, TRASH_ID, manage::MailboxFnc},
     message::{
-        delivery::AutogeneratedMessage,
+        delivery::{AutogeneratedMessage, calendar_invites, vcard_attachments},
         ingest::{EmailIngest, IngestEmail, IngestSource, IngestedEmail},
     },
 };
 use common::{
-    Server, auth::AccessToken, config::jmap::settings::SpecialUse, scripts::plugins::PluginContext,
+    Server,
+    auth::AccessToken,
+    config::jmap::settings::SpecialUse,
+    scripts::plugins::{
+        PluginContext,
+        groupware::{PLUGIN_FILE_INTO_ADDRESSBOOK, PLUGIN_FILE_INTO_CALENDAR},
+    },
 };
 use directory::{Permission, QueryBy};
+use groupware::{
+    DavResourceName, IngestResult, calendar::ingest::CalendarIngest, contact::ingest::ContactIngest,
+};
 use jmap_proto::types::{collection::Collection, id::Id, keyword::Keyword, property::Property};
 use mail_parser::MessageParser;
 use sieve::{Envelope, Event, Input, Mailbox, Recipient, Sieve};
@@ -430,6 +439,50 @@ impl SieveScriptIngest for Server {
                         // Not allowed
                         input = false.into();
                     }
+                    Event::Function {
+                        id: id @ (PLUGIN_FILE_INTO_CALENDAR | PLUGIN_FILE_INTO_ADDRESSBOOK),
+                        arguments,
+                    } => {
+                        let name = arguments
+                            .first()
+                            .map(|name| name.to_string())
+                            .filter(|name| !name.is_empty());
+                        let mut stored = false;
+
+                        if id == PLUGIN_FILE_INTO_CALENDAR {
+                            if DavResourceName::Cal.is_enabled(access_token) {
+                                for ical in calendar_invites(instance.message()) {
+                                    stored |= groupware_filed(
+                                        self.ingest_calendar_data(
+                                            access_token,
+                                            account_id,
+                                            name.as_deref(),
+                                            ical,
+                                        )
+                                        .await,
+                                        account_id,
+                                        session_id,
+                                    );
+                                }
+                            }
+                        } else if DavResourceName::Card.is_enabled(access_token) {
+                            for vcard in vcard_attachments(instance.message()) {
+                                stored |= groupware_filed(
+                                    self.ingest_contact_data(
+                                        access_token,
+                                        account_id,
+                                        name.as_deref(),
+                                        vcard,
+                                    )
+                                    .await,
+                                    account_id,
+                                    session_id,
+                                );
+                            }
+                        }
+
+                        input = stored.into();
+                    }
                     Event::Function { id, arguments } => {
                         input = self
                             .core
@@ -717,6 +770,25 @@ impl SieveScriptIngest for Server {
     }
 }
 
+fn groupware_filed(result: trc::Result<IngestResult>, account_id: u32, session_id: u64) -> bool {
+    match result {
+        Ok(IngestResult::Created(_) | IngestResult::Updated(_)) => true,
+        Ok(IngestResult::Skipped(reason)) => {
+            trc::event!(
+                Sieve(SieveEvent::RuntimeError),
+                AccountId = account_id,
+                Reason = reason,
+                SpanId = session_id
+            );
+            false
+        }
+        Err(err) => {
+            trc::error!(err.span_id(session_id).caused_by(trc::location!()));
+            false
+        }
+    }
+}
+
 pub struct CompiledScript {
     pub script: Sieve,
     pub name: String,
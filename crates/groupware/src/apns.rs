@@ -0,0 +1,282 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{KV_APNS_DEVICES, Server, auth::AccessToken};
+use jmap_proto::types::{state::StateChange, type_state::DataType};
+use reqwest::{Client, Identity, StatusCode};
+use store::{
+    Serialize,
+    dispatch::lookup::KeyValue,
+    write::{AlignedBytes, Archive, Archiver, now},
+};
+use trc::{AddContext, PushSubscriptionEvent};
+
+use crate::DavResourceName;
+
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+#[rkyv(derive(Debug))]
+pub struct ApnsDevices {
+    pub devices: Vec<ApnsDevice>,
+}
+
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+#[rkyv(derive(Debug))]
+pub struct ApnsDevice {
+    pub token: String,
+    pub account_id: u32,
+    pub expires: u64,
+}
+
+pub trait ApnsPush: Sync + Send {
+    /// Registers an Apple device token for the changes published under a
+    /// push key. Returns false when the key does not refer to a home the
+    /// principal can access.
+    fn register_apns_device(
+        &self,
+        access_token: &AccessToken,
+        push_key: &str,
+        token: &str,
+    ) -> impl Future<Output = trc::Result<bool>> + Send;
+
+    /// Notifies the devices registered for the calendar and address book
+    /// homes affected by a state change.
+    fn notify_apns(
+        &self,
+        state_change: StateChange,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl ApnsPush for Server {
+    async fn register_apns_device(
+        &self,
+        access_token: &AccessToken,
+        push_key: &str,
+        token: &str,
+    ) -> trc::Result<bool> {
+        let Some(apns) = &self.core.groupware.apns else {
+            return Ok(false);
+        };
+        let Some((service, account_id)) = parse_push_key(push_key) else {
+            return Ok(false);
+        };
+        if !access_token.is_member(account_id) && !access_token.has_access(account_id, service) {
+            return Ok(false);
+        }
+
+        let key = KeyValue::<()>::build_key(KV_APNS_DEVICES, push_key.as_bytes());
+        let now = now();
+        let token = token.to_ascii_lowercase();
+        let mut devices = self
+            .apns_devices(key.clone())
+            .await
+            .caused_by(trc::location!())?;
+        devices
+            .devices
+            .retain(|device| device.expires > now && device.token != token);
+        devices.devices.push(ApnsDevice {
+            token,
+            account_id: access_token.primary_id(),
+            expires: now + apns.refresh_interval.as_secs(),
+        });
+
+        self.in_memory_store()
+            .key_set(
+                KeyValue::new(
+                    key,
+                    Archiver::new(devices)
+                        .serialize()
+                        .caused_by(trc::location!())?,
+                )
+                .expires(apns.refresh_interval.as_secs()),
+            )
+            .await
+            .caused_by(trc::location!())?;
+
+        Ok(true)
+    }
+
+    async fn notify_apns(&self, state_change: StateChange) -> trc::Result<()> {
+        let Some(apns) = &self.core.groupware.apns else {
+            return Ok(());
+        };
+        let mut client = None;
+
+        for (service, topic, data_types) in [
+            (
+                DavResourceName::Cal,
+                &apns.calendar_topic,
+                [DataType::Calendar, DataType::CalendarEvent],
+            ),
+            (
+                DavResourceName::Card,
+                &apns.contacts_topic,
+                [DataType::AddressBook, DataType::ContactCard],
+            ),
+        ] {
+            let Some(topic) = topic else {
+                continue;
+            };
+            if !data_types
+                .iter()
+                .any(|data_type| state_change.types.contains(*data_type))
+            {
+                continue;
+            }
+
+            let push_key = apns_push_key(service, state_change.account_id);
+            let key = KeyValue::<()>::build_key(KV_APNS_DEVICES, push_key.as_bytes());
+            let now = now();
+            let mut devices = self
+                .apns_devices(key.clone())
+                .await
+                .caused_by(trc::location!())?;
+            let num_devices = devices.devices.len();
+            devices.devices.retain(|device| device.expires > now);
+            if devices.devices.is_empty() {
+                continue;
+            }
+
+            if client.is_none() {
+                client = Some(
+                    Identity::from_pem(&apns.identity)
+                        .and_then(|identity| {
+                            Client::builder()
+                                .identity(identity)
+                                .timeout(apns.timeout)
+                                .build()
+                        })
+                        .map_err(|err| {
+                            trc::EventType::PushSubscription(PushSubscriptionEvent::Error)
+                                .into_err()
+                                .reason(err)
+                                .details("Failed to create APNs client")
+                        })?,
+                );
+            }
+            let client = client.as_ref().unwrap();
+            let payload = format!(
+                "{{\"key\":\"{push_key}\",\"dataChangedTimestamp\":{now},\"pushRequestSubmittedTimestamp\":{now}}}"
+            );
+
+            let mut unregistered = Vec::new();
+            for device in &devices.devices {
+                let url = format!("{}/3/device/{}", apns.url, device.token);
+                match client
+                    .post(&url)
+                    .header("apns-topic", topic.as_str())
+                    .header("apns-priority", "5")
+                    .body(payload.clone())
+                    .send()
+                    .await
+                {
+                    Ok(response) if response.status().is_success() => {
+                        trc::event!(
+                            PushSubscription(PushSubscriptionEvent::Success),
+                            AccountId = device.account_id,
+                            Url = url,
+                        );
+                    }
+                    Ok(response)
+                        if matches!(
+                            response.status(),
+                            StatusCode::GONE | StatusCode::BAD_REQUEST
+                        ) =>
+                    {
+                        // The token is no longer valid for this topic
+                        trc::event!(
+                            PushSubscription(PushSubscriptionEvent::NotFound),
+                            AccountId = device.account_id,
+                            Url = url,
+                            Code = response.status().as_u16(),
+                        );
+                        unregistered.push(device.token.clone());
+                    }
+                    Ok(response) => {
+                        trc::event!(
+                            PushSubscription(PushSubscriptionEvent::Error),
+                            Details = "APNs request failed",
+                            AccountId = device.account_id,
+                            Url = url,
+                            Code = response.status().as_u16(),
+                        );
+                    }
+                    Err(err) => {
+                        trc::event!(
+                            PushSubscription(PushSubscriptionEvent::Error),
+                            Details = "APNs request failed",
+                            AccountId = device.account_id,
+                            Url = url,
+                            Reason = err.to_string(),
+                        );
+                    }
+                }
+            }
+
+            if !unregistered.is_empty() || devices.devices.len() != num_devices {
+                devices
+                    .devices
+                    .retain(|device| !unregistered.contains(&device.token));
+                if devices.devices.is_empty() {
+                    self.in_memory_store()
+                        .key_delete(key)
+                        .await
+                        .caused_by(trc::location!())?;
+                } else {
+                    self.in_memory_store()
+                        .key_set(
+                            KeyValue::new(
+                                key,
+                                Archiver::new(devices)
+                                    .serialize()
+                                    .caused_by(trc::location!())?,
+                            )
+                            .expires(apns.refresh_interval.as_secs()),
+                        )
+                        .await
+                        .caused_by(trc::location!())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+trait ApnsDeviceStore {
+    fn apns_devices(&self, key: Vec<u8>) -> impl Future<Output = trc::Result<ApnsDevices>> + Send;
+}
+
+impl ApnsDeviceStore for Server {
+    async fn apns_devices(&self, key: Vec<u8>) -> trc::Result<ApnsDevices> {
+        self.in_memory_store()
+            .key_get::<Archive<AlignedBytes>>(key)
+            .await
+            .caused_by(trc::location!())?
+            .map(|devices| devices.deserialize::<ApnsDevices>())
+            .transpose()
+            .map(|devices| devices.unwrap_or_default())
+    }
+}
+
+/// Push keys identify the calendar or address book home of an account.
+pub fn apns_push_key(service: DavResourceName, account_id: u32) -> String {
+    format!("{}/{account_id}/", service.base_path())
+}
+
+fn parse_push_key(push_key: &str) -> Option<(DavResourceName, u32)> {
+    let (service, account_id) = push_key
+        .strip_prefix("/dav/")?
+        .strip_suffix('/')?
+        .split_once('/')?;
+    let service = DavResourceName::parse(service)
+        .filter(|service| matches!(service, DavResourceName::Cal | DavResourceName::Card))?;
+
+    Some((service, account_id.parse().ok()?))
+}
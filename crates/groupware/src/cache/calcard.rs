@@ -7,12 +7,16 @@
 use super::GroupwareCache;
 use crate::{
     DavResourceName,
-    calendar::{ArchivedCalendar, ArchivedCalendarEvent, Calendar, CalendarEvent},
+    calendar::{
+        ArchivedCalendar, ArchivedCalendarEvent, CALENDAR_BIRTHDAYS, CALENDAR_SCHEDULE_INBOX,
+        CALENDAR_SCHEDULE_OUTBOX, Calendar, CalendarEvent,
+    },
     contact::{AddressBook, ArchivedAddressBook, ArchivedContactCard, ContactCard},
 };
 use calcard::common::timezone::Tz;
 use common::{
-    DavName, DavPath, DavResource, DavResourceMetadata, DavResources, Server, auth::AccessToken,
+    DavName, DavPath, DavResource, DavResourceMetadata, DavResources, ScheduleCollection, Server,
+    auth::AccessToken,
 };
 use directory::backend::internal::manage::ManageDirectory;
 use jmap_proto::types::{
@@ -88,6 +92,11 @@ pub(super) async fn build_calcard_resources(
         .await
         .caused_by(trc::location!())?
         .unwrap_or_else(|| format!("_{account_id}"));
+    let sync_epoch = server
+        .store()
+        .get_sync_epoch(account_id, sync_collection)
+        .await
+        .caused_by(trc::location!())?;
 
     let mut cache = DavResources {
         base_path: format!(
@@ -105,8 +114,10 @@ pub(super) async fn build_calcard_resources(
         item_change_id: last_change_id,
         container_change_id: last_change_id,
         highest_change_id: last_change_id,
+        sync_epoch,
         size: std::mem::size_of::<DavResources>() as u64,
         update_lock,
+        acl_cache: Default::default(),
     };
 
     for document_id in container_ids {
@@ -200,7 +211,7 @@ pub(super) fn build_simple_hierarchy(cache: &mut DavResources) {
                 cache.paths.insert(path);
             }
             DavResourceMetadata::CalendarEvent { names, .. }
-            | DavResourceMetadata::ContactCard { names } => {
+            | DavResourceMetadata::ContactCard { names, .. } => {
                 for name in names {
                     if let Some(parent_name) = name_idx.get(&name.parent_id) {
                         let path = DavPath {
@@ -218,7 +229,44 @@ pub(super) fn build_simple_hierarchy(cache: &mut DavResources) {
             }
             _ => unreachable!(),
         }
-        cache.size += std::mem::size_of::<DavResource>() as u64;
+        cache.size += (std::mem::size_of::<DavResource>()
+            + (resource.event_time_buckets().len() * std::mem::size_of::<i32>()))
+            as u64;
+    }
+}
+
+pub(super) fn update_simple_hierarchy(cache: &mut DavResources, resource_idxs: &[usize]) {
+    if resource_idxs.is_empty() {
+        return;
+    }
+
+    let name_idx = cache
+        .resources
+        .iter()
+        .filter_map(|resource| {
+            resource
+                .container_name()
+                .map(|name| (resource.document_id, name))
+        })
+        .collect::<AHashMap<_, _>>();
+
+    for &resource_idx in resource_idxs {
+        for name in cache.resources[resource_idx]
+            .child_names()
+            .unwrap_or_default()
+        {
+            if let Some(parent_name) = name_idx.get(&name.parent_id) {
+                let path = DavPath {
+                    path: format!("{parent_name}/{}", name.name),
+                    parent_id: Some(name.parent_id),
+                    hierarchy_seq: 1,
+                    resource_idx,
+                };
+                cache.size +=
+                    (std::mem::size_of::<DavPath>() + name.name.len() + path.path.len()) as u64;
+                cache.paths.insert(path);
+            }
+        }
     }
 }
 
@@ -227,6 +275,10 @@ pub(super) fn resource_from_calendar(calendar: &ArchivedCalendar, document_id: u
         document_id,
         data: DavResourceMetadata::Calendar {
             name: calendar.name.to_string(),
+            display_name: calendar
+                .preferences
+                .first()
+                .map(|pref| pref.name.to_string()),
             acls: calendar
                 .acls
                 .iter()
@@ -240,6 +292,20 @@ pub(super) fn resource_from_calendar(calendar: &ArchivedCalendar, document_id: u
                 .first()
                 .and_then(|pref| pref.time_zone.tz())
                 .unwrap_or(Tz::UTC),
+            schedule: calendar.preferences.first().and_then(|pref| {
+                let flags = pref.flags.to_native();
+                if flags & CALENDAR_SCHEDULE_INBOX != 0 {
+                    Some(ScheduleCollection::Inbox)
+                } else if flags & CALENDAR_SCHEDULE_OUTBOX != 0 {
+                    Some(ScheduleCollection::Outbox)
+                } else {
+                    None
+                }
+            }),
+            is_birthdays: calendar
+                .preferences
+                .first()
+                .is_some_and(|pref| pref.flags.to_native() & CALENDAR_BIRTHDAYS != 0),
         },
     }
 }
@@ -259,6 +325,8 @@ pub(super) fn resource_from_event(event: &ArchivedCalendarEvent, document_id: u3
                 .collect(),
             start,
             duration,
+            size: event.size.to_native(),
+            time_buckets: event.data.time_buckets(),
         },
     }
 }
@@ -271,6 +339,7 @@ pub(super) fn resource_from_addressbook(
         document_id,
         data: DavResourceMetadata::AddressBook {
             name: book.name.to_string(),
+            display_name: book.display_name.as_deref().map(|name| name.to_string()),
             acls: book
                 .acls
                 .iter()
@@ -295,6 +364,7 @@ pub(super) fn resource_from_card(card: &ArchivedContactCard, document_id: u32) -
                     parent_id: name.parent_id.to_native(),
                 })
                 .collect(),
+            size: card.size.to_native(),
         },
     }
 }
@@ -8,7 +8,7 @@ use crate::{
     DavResourceName,
     file::{ArchivedFileNode, FileNode},
 };
-use common::{DavPath, DavResource, DavResourceMetadata, DavResources, Server};
+use common::{DavName, DavPath, DavResource, DavResourceMetadata, DavResources, Server};
 use directory::backend::internal::manage::ManageDirectory;
 use jmap_proto::types::{
     collection::{Collection, SyncCollection},
@@ -45,6 +45,11 @@ pub(super) async fn build_file_resources(
         .await
         .caused_by(trc::location!())?
         .unwrap_or_else(|| format!("_{account_id}"));
+    let sync_epoch = server
+        .store()
+        .get_sync_epoch(account_id, SyncCollection::FileNode)
+        .await
+        .caused_by(trc::location!())?;
     let resources = fetch_files(server, account_id).await?;
     let mut files = DavResources {
         base_path: format!(
@@ -58,7 +63,9 @@ pub(super) async fn build_file_resources(
         item_change_id: last_change_id,
         container_change_id: last_change_id,
         highest_change_id: last_change_id,
+        sync_epoch,
         update_lock,
+        acl_cache: Default::default(),
     };
 
     build_nested_hierarchy(&mut files);
@@ -108,8 +115,36 @@ pub(super) fn build_nested_hierarchy(resources: &mut DavResources) {
         }
     }
 
+    // Additional bindings place a file under further folders
+    let mut bindings = Vec::new();
+    for (resource_idx, resource) in resources.resources.iter().enumerate() {
+        if let DavResourceMetadata::File {
+            bindings: resource_bindings,
+            ..
+        } = &resource.data
+        {
+            for binding in resource_bindings {
+                let parent_id = binding.parent_id.checked_sub(1);
+                let (path, hierarchy_seq) = match parent_id.and_then(|id| names.get(&id)) {
+                    Some(parent) => (
+                        format!("{}/{}", parent.path, binding.name),
+                        parent.hierarchy_seq + 1,
+                    ),
+                    None => (binding.name.clone(), 1),
+                };
+                bindings.push(DavPath {
+                    path,
+                    parent_id,
+                    hierarchy_seq,
+                    resource_idx,
+                });
+            }
+        }
+    }
+
     resources.paths = names
         .into_values()
+        .chain(bindings)
         .inspect(|v| {
             resources.size += (std::mem::size_of::<DavPath>()
                 + std::mem::size_of::<u32>()
@@ -120,6 +155,81 @@ pub(super) fn build_nested_hierarchy(resources: &mut DavResources) {
         .collect();
 }
 
+pub(super) fn update_nested_hierarchy(resources: &mut DavResources, resource_idxs: &[usize]) {
+    if resource_idxs.is_empty() {
+        return;
+    }
+
+    // Only the folders of the updated files need to be resolved
+    let parent_ids = resource_idxs
+        .iter()
+        .flat_map(
+            |&resource_idx| match &resources.resources[resource_idx].data {
+                DavResourceMetadata::File {
+                    parent_id,
+                    bindings,
+                    ..
+                } => parent_id
+                    .into_iter()
+                    .chain(
+                        bindings
+                            .iter()
+                            .filter_map(|binding| binding.parent_id.checked_sub(1)),
+                    )
+                    .collect::<Vec<_>>(),
+                _ => vec![],
+            },
+        )
+        .collect::<AHashSet<_>>();
+    let parents = resources
+        .paths
+        .iter()
+        .filter_map(|path| {
+            let resource = &resources.resources[path.resource_idx];
+            (resource.is_container() && parent_ids.contains(&resource.document_id)).then(|| {
+                (
+                    resource.document_id,
+                    (path.path.clone(), path.hierarchy_seq),
+                )
+            })
+        })
+        .collect::<AHashMap<_, _>>();
+
+    for &resource_idx in resource_idxs {
+        if let DavResourceMetadata::File {
+            name,
+            parent_id,
+            bindings,
+            ..
+        } = &resources.resources[resource_idx].data
+        {
+            for (name, parent_id) in [(name.as_str(), *parent_id)].into_iter().chain(
+                bindings
+                    .iter()
+                    .map(|binding| (binding.name.as_str(), binding.parent_id.checked_sub(1))),
+            ) {
+                let (path, hierarchy_seq) = match parent_id.and_then(|id| parents.get(&id)) {
+                    Some((parent_path, parent_seq)) => {
+                        (format!("{parent_path}/{name}"), parent_seq + 1)
+                    }
+                    None => (name.to_string(), 1),
+                };
+                resources.size += (std::mem::size_of::<DavPath>()
+                    + std::mem::size_of::<u32>()
+                    + std::mem::size_of::<usize>()
+                    + std::mem::size_of::<DavResource>()
+                    + path.len()) as u64;
+                resources.paths.insert(DavPath {
+                    path,
+                    parent_id,
+                    hierarchy_seq,
+                    resource_idx,
+                });
+            }
+        }
+    }
+}
+
 async fn fetch_files(server: &Server, account_id: u32) -> trc::Result<Vec<DavResource>> {
     let mut files = Vec::with_capacity(16);
 
@@ -177,6 +287,14 @@ pub(super) fn resource_from_file(node: &ArchivedFileNode, document_id: u32) -> D
                     grants: Bitmap::from(&acl.grants),
                 })
                 .collect(),
+            bindings: node
+                .bindings
+                .iter()
+                .map(|binding| DavName {
+                    name: binding.name.as_str().to_string(),
+                    parent_id: binding.parent_id.to_native(),
+                })
+                .collect(),
         },
     }
 }
@@ -11,16 +11,19 @@ use crate::{
 };
 use calcard::{
     build_calcard_resources, build_simple_hierarchy, resource_from_addressbook,
-    resource_from_calendar, resource_from_card, resource_from_event,
+    resource_from_calendar, resource_from_card, resource_from_event, update_simple_hierarchy,
+};
+use common::{CacheSwap, DavPath, DavResource, DavResources, Server, auth::AccessToken};
+use file::{
+    build_file_resources, build_nested_hierarchy, resource_from_file, update_nested_hierarchy,
 };
-use common::{CacheSwap, DavResource, DavResources, Server, auth::AccessToken};
-use file::{build_file_resources, build_nested_hierarchy, resource_from_file};
 use jmap_proto::types::collection::{Collection, SyncCollection};
 use std::{sync::Arc, time::Instant};
 use store::{
-    ahash::AHashMap,
+    Key, LogShardKey, SUBSPACE_LOGS, SerializeInfallible,
+    ahash::{AHashMap, AHashSet},
     query::log::{Change, Query},
-    write::{AlignedBytes, Archive, BatchBuilder},
+    write::{AlignedBytes, AnyClass, Archive, BatchBuilder, ValueClass},
 };
 use tokio::sync::Semaphore;
 use trc::{AddContext, StoreEvent};
@@ -53,6 +56,31 @@ pub trait GroupwareCache: Sync + Send {
         account_id: u32,
         collection: SyncCollection,
     ) -> Option<Arc<DavResources>>;
+
+    fn rebuild_dav_resources(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        collection: SyncCollection,
+    ) -> impl Future<Output = trc::Result<DavResourcesRepair>> + Send;
+
+    fn reset_sync_epoch(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        collection: SyncCollection,
+    ) -> impl Future<Output = trc::Result<u64>> + Send;
+}
+
+/// Differences found between the cached hierarchy of an account and the one
+/// derived from primary storage.
+#[derive(Debug, Default)]
+pub struct DavResourcesRepair {
+    pub missing: Vec<String>,
+    pub stale: Vec<String>,
+    pub changed: Vec<String>,
+    pub previous_change_id: Option<u64>,
+    pub change_id: u64,
 }
 
 impl GroupwareCache for Server {
@@ -87,6 +115,7 @@ impl GroupwareCache for Server {
 
                 trc::event!(
                     Store(StoreEvent::CacheMiss),
+                    SpanId = trc::current_span(),
                     AccountId = account_id,
                     Collection = collection.as_str(),
                     Total = cache.resources.len(),
@@ -127,6 +156,7 @@ impl GroupwareCache for Server {
 
             trc::event!(
                 Store(StoreEvent::CacheStale),
+                SpanId = trc::current_span(),
                 AccountId = account_id,
                 Collection = collection.as_str(),
                 ChangeId = cache.highest_change_id,
@@ -141,6 +171,7 @@ impl GroupwareCache for Server {
         if changes.changes.is_empty() {
             trc::event!(
                 Store(StoreEvent::CacheHit),
+                SpanId = trc::current_span(),
                 AccountId = account_id,
                 Collection = collection.as_str(),
                 ChangeId = cache.highest_change_id,
@@ -156,6 +187,7 @@ impl GroupwareCache for Server {
         if cache.highest_change_id >= changes.to_change_id {
             trc::event!(
                 Store(StoreEvent::CacheHit),
+                SpanId = trc::current_span(),
                 AccountId = account_id,
                 Collection = collection.as_str(),
                 ChangeId = cache.highest_change_id,
@@ -168,6 +200,7 @@ impl GroupwareCache for Server {
         let mut updated_resources = AHashMap::with_capacity(8);
         let has_no_children = collection == SyncCollection::FileNode;
         let num_changes = changes.changes.len();
+        let mut has_epoch_change = false;
 
         for change in changes.changes {
             match change {
@@ -217,12 +250,28 @@ impl GroupwareCache for Server {
                 Change::DeleteContainer(id) => {
                     updated_resources.insert((true, id as u32), None);
                 }
-                Change::UpdateContainerProperty(_) => (),
+                Change::UpdateContainerProperty(_) => {
+                    has_epoch_change = true;
+                }
             }
         }
 
+        // Container property changes are only logged when the sync epoch is reset
+        let sync_epoch = if has_epoch_change {
+            self.store()
+                .get_sync_epoch(account_id, collection)
+                .await
+                .caused_by(trc::location!())?
+        } else {
+            cache.sync_epoch
+        };
+
+        // Container changes affect the paths of their children and require a full
+        // rebuild, item changes are patched into the existing hierarchy.
         let mut rebuild_hierarchy = false;
         let mut resources = Vec::with_capacity(cache.resources.len());
+        let mut resource_idx_map = Vec::with_capacity(cache.resources.len());
+        let mut dirty_idxs = Vec::new();
 
         for resource in &cache.resources {
             let is_container = has_no_children || resource.is_container();
@@ -230,26 +279,34 @@ impl GroupwareCache for Server {
                 updated_resources.remove(&(is_container, resource.document_id))
             {
                 if let Some(updated_resource) = updated_resource {
-                    rebuild_hierarchy =
-                        rebuild_hierarchy || updated_resource.has_hierarchy_changes(resource);
+                    if updated_resource.has_hierarchy_changes(resource) {
+                        rebuild_hierarchy = rebuild_hierarchy || resource.is_container();
+                        resource_idx_map.push(None);
+                        dirty_idxs.push(resources.len());
+                    } else {
+                        resource_idx_map.push(Some(resources.len()));
+                    }
                     resources.push(updated_resource);
                 } else {
                     // Deleted resource
-                    rebuild_hierarchy = true;
+                    rebuild_hierarchy = rebuild_hierarchy || resource.is_container();
+                    resource_idx_map.push(None);
                 }
             } else {
+                resource_idx_map.push(Some(resources.len()));
                 resources.push(resource.clone());
             }
         }
 
         // Add new resources
         for resource in updated_resources.into_values().flatten() {
+            rebuild_hierarchy = rebuild_hierarchy || resource.is_container();
+            dirty_idxs.push(resources.len());
             resources.push(resource);
-            rebuild_hierarchy = true;
         }
 
-        let cache = if rebuild_hierarchy {
-            let mut cache = DavResources {
+        let mut cache = if rebuild_hierarchy {
+            DavResources {
                 base_path: cache.base_path.clone(),
                 paths: Default::default(),
                 resources,
@@ -258,36 +315,57 @@ impl GroupwareCache for Server {
                     .container_change_id
                     .unwrap_or(cache.container_change_id),
                 highest_change_id: changes.to_change_id,
+                sync_epoch,
                 size: std::mem::size_of::<DavResources>() as u64,
                 update_lock: cache.update_lock.clone(),
-            };
-
-            if matches!(collection, SyncCollection::FileNode) {
-                build_nested_hierarchy(&mut cache);
-            } else {
-                build_simple_hierarchy(&mut cache);
+                acl_cache: Default::default(),
             }
-            cache
         } else {
+            let mut size = cache.size;
+            let mut paths = AHashSet::with_capacity(cache.paths.len() + dirty_idxs.len());
+            for path in &cache.paths {
+                if let Some(resource_idx) = resource_idx_map[path.resource_idx] {
+                    paths.insert(DavPath {
+                        path: path.path.clone(),
+                        parent_id: path.parent_id,
+                        hierarchy_seq: path.hierarchy_seq,
+                        resource_idx,
+                    });
+                } else {
+                    size = size
+                        .saturating_sub((std::mem::size_of::<DavPath>() + path.path.len()) as u64);
+                }
+            }
+
             DavResources {
                 base_path: cache.base_path.clone(),
-                paths: cache.paths.clone(),
+                paths,
                 resources,
                 item_change_id: changes.item_change_id.unwrap_or(cache.item_change_id),
                 container_change_id: changes
                     .container_change_id
                     .unwrap_or(cache.container_change_id),
                 highest_change_id: changes.to_change_id,
-                size: cache.size,
+                sync_epoch,
+                size,
                 update_lock: cache.update_lock.clone(),
+                acl_cache: Default::default(),
             }
         };
 
+        match (collection, rebuild_hierarchy) {
+            (SyncCollection::FileNode, true) => build_nested_hierarchy(&mut cache),
+            (SyncCollection::FileNode, false) => update_nested_hierarchy(&mut cache, &dirty_idxs),
+            (_, true) => build_simple_hierarchy(&mut cache),
+            (_, false) => update_simple_hierarchy(&mut cache, &dirty_idxs),
+        }
+
         let cache = Arc::new(cache);
         cache_.update(cache.clone());
 
         trc::event!(
             Store(StoreEvent::CacheUpdate),
+            SpanId = trc::current_span(),
             AccountId = account_id,
             Collection = collection.as_str(),
             ChangeId = cache.highest_change_id,
@@ -304,15 +382,31 @@ impl GroupwareCache for Server {
         access_token: &AccessToken,
         account_id: u32,
     ) -> trc::Result<()> {
-        if let Some(name) = &self.core.groupware.default_addressbook_name {
+        let template = self
+            .core
+            .groupware
+            .provisioning_template(access_token.tenant.map(|tenant| tenant.id));
+        if let Some(name) = &template.addressbook_name {
+            let last_change_id = self
+                .store()
+                .get_last_change_id(account_id, SyncCollection::AddressBook)
+                .await
+                .caused_by(trc::location!())?
+                .unwrap_or_default();
             let mut batch = BatchBuilder::new();
+            set_shard_epoch(
+                &mut batch,
+                account_id,
+                SyncCollection::AddressBook,
+                last_change_id,
+            );
             let document_id = self
                 .store()
                 .assign_document_ids(account_id, Collection::AddressBook, 1)
                 .await?;
             AddressBook {
                 name: name.clone(),
-                display_name: self.core.groupware.default_addressbook_display_name.clone(),
+                display_name: template.addressbook_display_name.clone(),
                 is_default: true,
                 ..Default::default()
             }
@@ -328,8 +422,24 @@ impl GroupwareCache for Server {
         access_token: &AccessToken,
         account_id: u32,
     ) -> trc::Result<()> {
-        if let Some(name) = &self.core.groupware.default_calendar_name {
+        let template = self
+            .core
+            .groupware
+            .provisioning_template(access_token.tenant.map(|tenant| tenant.id));
+        if let Some(name) = &template.calendar_name {
+            let last_change_id = self
+                .store()
+                .get_last_change_id(account_id, SyncCollection::Calendar)
+                .await
+                .caused_by(trc::location!())?
+                .unwrap_or_default();
             let mut batch = BatchBuilder::new();
+            set_shard_epoch(
+                &mut batch,
+                account_id,
+                SyncCollection::Calendar,
+                last_change_id,
+            );
             let document_id = self
                 .store()
                 .assign_document_ids(account_id, Collection::Calendar, 3)
@@ -338,10 +448,8 @@ impl GroupwareCache for Server {
                 name: name.clone(),
                 preferences: vec![CalendarPreferences {
                     account_id,
-                    name: self
-                        .core
-                        .groupware
-                        .default_calendar_display_name
+                    name: template
+                        .calendar_display_name
                         .clone()
                         .unwrap_or_else(|| name.clone()),
                     ..Default::default()
@@ -369,6 +477,156 @@ impl GroupwareCache for Server {
         .get(&account_id)
         .map(|cache| cache.load_full())
     }
+
+    async fn rebuild_dav_resources(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        collection: SyncCollection,
+    ) -> trc::Result<DavResourcesRepair> {
+        let cache_store = match collection {
+            SyncCollection::Calendar => &self.inner.cache.events,
+            SyncCollection::AddressBook => &self.inner.cache.contacts,
+            SyncCollection::FileNode => &self.inner.cache.files,
+            _ => unreachable!(),
+        };
+        let previous = cache_store.get(&account_id).map(|cache| cache.load_full());
+        let update_lock = previous
+            .as_ref()
+            .map(|cache| cache.update_lock.clone())
+            .unwrap_or_else(|| Arc::new(Semaphore::new(1)));
+
+        // Block incremental updates while the hierarchy is rebuilt
+        let _permit = update_lock.acquire().await;
+        let start_time = Instant::now();
+        let cache = full_cache_build(
+            self,
+            account_id,
+            collection,
+            update_lock.clone(),
+            access_token,
+        )
+        .await?;
+        cache_store.insert(account_id, CacheSwap::new(cache.clone()));
+
+        let mut repair = DavResourcesRepair {
+            previous_change_id: previous.as_ref().map(|cache| cache.highest_change_id),
+            change_id: cache.highest_change_id,
+            ..Default::default()
+        };
+        if let Some(previous) = &previous {
+            let previous_paths = resource_paths(previous);
+            let paths = resource_paths(&cache);
+
+            for (path, id) in &paths {
+                match previous_paths.get(path) {
+                    Some(previous_id) if previous_id != id => repair.changed.push(path.to_string()),
+                    Some(_) => {}
+                    None => repair.missing.push(path.to_string()),
+                }
+            }
+            repair.stale = previous_paths
+                .keys()
+                .filter(|path| !paths.contains_key(*path))
+                .map(|path| path.to_string())
+                .collect();
+            repair.missing.sort_unstable();
+            repair.stale.sort_unstable();
+            repair.changed.sort_unstable();
+        }
+
+        trc::event!(
+            Store(StoreEvent::CacheUpdate),
+            SpanId = trc::current_span(),
+            AccountId = account_id,
+            Collection = collection.as_str(),
+            ChangeId = cache.highest_change_id,
+            Details = "Rebuilt hierarchy",
+            Total = cache.resources.len(),
+            Elapsed = start_time.elapsed(),
+        );
+
+        Ok(repair)
+    }
+
+    async fn reset_sync_epoch(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        collection: SyncCollection,
+    ) -> trc::Result<u64> {
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, collection)
+            .await
+            .caused_by(trc::location!())?;
+        let sync_epoch = resources.sync_epoch + 1;
+
+        // Log a property change on every container so that the hierarchies
+        // cached by other nodes pick up the new epoch
+        let mut batch = BatchBuilder::new();
+        batch.with_account_id(account_id).set(
+            ValueClass::Any(AnyClass {
+                subspace: SUBSPACE_LOGS,
+                key: LogShardKey::sync_epoch(account_id, u8::from(collection)).serialize(0),
+            }),
+            sync_epoch.serialize(),
+        );
+        set_shard_epoch(
+            &mut batch,
+            account_id,
+            collection,
+            resources.highest_change_id,
+        );
+        for resource in resources.resources.iter().filter(|r| r.is_container()) {
+            batch.log_container_property_change(collection, resource.document_id);
+        }
+        self.commit_batch(batch).await.caused_by(trc::location!())?;
+        match collection {
+            SyncCollection::Calendar => &self.inner.cache.events,
+            SyncCollection::AddressBook => &self.inner.cache.contacts,
+            SyncCollection::FileNode => &self.inner.cache.files,
+            _ => unreachable!(),
+        }
+        .remove(&account_id);
+
+        Ok(sync_epoch)
+    }
+}
+
+/// Per-container changes are only complete from the point the collection is
+/// created or its sync epoch is reset, older tokens are served from the full log.
+fn set_shard_epoch(
+    batch: &mut BatchBuilder,
+    account_id: u32,
+    collection: SyncCollection,
+    change_id: u64,
+) {
+    if matches!(
+        collection,
+        SyncCollection::Calendar | SyncCollection::AddressBook
+    ) {
+        batch.with_account_id(account_id).set(
+            ValueClass::Any(AnyClass {
+                subspace: SUBSPACE_LOGS,
+                key: LogShardKey::epoch(account_id, u8::from(collection)).serialize(0),
+            }),
+            change_id.serialize(),
+        );
+    }
+}
+
+fn resource_paths(cache: &DavResources) -> AHashMap<&str, (bool, u32)> {
+    cache
+        .paths
+        .iter()
+        .map(|path| {
+            let resource = &cache.resources[path.resource_idx];
+            (
+                path.path.as_str(),
+                (resource.is_container(), resource.document_id),
+            )
+        })
+        .collect()
 }
 
 async fn full_cache_build(
@@ -0,0 +1,80 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::icalendar::{
+    ICalendar, ICalendarComponentType, ICalendarEntry, ICalendarParameter, ICalendarProperty,
+    ICalendarValue,
+};
+
+use super::{CalendarAttachment, CalendarEvent};
+
+impl CalendarEvent {
+    /// Drops the managed attachments that are no longer referenced by an
+    /// ATTACH property of the event, which releases their blobs.
+    pub fn retain_referenced_attachments(&mut self) {
+        let event = &self.data.event;
+        self.attachments
+            .retain(|attachment| is_referenced(event, &attachment.managed_id));
+    }
+}
+
+impl CalendarAttachment {
+    /// Builds the ATTACH property referencing this attachment (RFC 8607).
+    pub fn to_entry(&self, href: &str) -> ICalendarEntry {
+        let mut params = vec![
+            ICalendarParameter::ManagedId(self.managed_id.clone()),
+            ICalendarParameter::Size(self.file.size as u64),
+        ];
+        if let Some(media_type) = &self.file.media_type {
+            params.push(ICalendarParameter::Fmttype(media_type.clone()));
+        }
+        if let Some(file_name) = &self.file_name {
+            params.push(ICalendarParameter::Filename(file_name.clone()));
+        }
+
+        ICalendarEntry {
+            name: ICalendarProperty::Attach,
+            params,
+            values: vec![ICalendarValue::Text(format!(
+                "{href}?managed-id={}",
+                self.managed_id
+            ))],
+        }
+    }
+}
+
+/// Returns the MANAGED-ID parameter of an ATTACH property.
+pub fn managed_id(entry: &ICalendarEntry) -> Option<&str> {
+    if entry.name == ICalendarProperty::Attach {
+        entry.params.iter().find_map(|param| {
+            if let ICalendarParameter::ManagedId(id) = param {
+                Some(id.as_str())
+            } else {
+                None
+            }
+        })
+    } else {
+        None
+    }
+}
+
+/// Returns whether a component can carry attachments.
+pub fn has_attachments(component_type: &ICalendarComponentType) -> bool {
+    matches!(
+        component_type,
+        ICalendarComponentType::VEvent
+            | ICalendarComponentType::VTodo
+            | ICalendarComponentType::VJournal
+    )
+}
+
+fn is_referenced(ical: &ICalendar, id: &str) -> bool {
+    ical.components
+        .iter()
+        .filter(|component| has_attachments(&component.component_type))
+        .flat_map(|component| component.entries.iter())
+        .any(|entry| managed_id(entry) == Some(id))
+}
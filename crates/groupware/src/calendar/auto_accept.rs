@@ -0,0 +1,330 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::{
+    common::timezone::Tz,
+    icalendar::{
+        ArchivedICalendarComponentType, ArchivedICalendarStatus, ICalendar, ICalendarComponentType,
+        ICalendarParameter, ICalendarParticipationStatus, ICalendarProperty, ICalendarTransparency,
+        dates::TimeOrDelta,
+    },
+};
+use common::{IDX_UID, Server, auth::AccessToken, config::groupware::ResourceAutoAccept};
+use dav_proto::schema::property::TimeRange;
+use directory::{QueryBy, Type};
+use jmap_proto::types::collection::{Collection, SyncCollection};
+use store::{query::Filter, write::BatchBuilder};
+use trc::AddContext;
+
+use crate::{IngestResult, cache::GroupwareCache};
+
+use super::{
+    CALENDAR_TRANSPARENT, Calendar, CalendarEvent, CalendarEventData, dates::ExpandCalendarEvent,
+    ingest::CalendarIngest, scheduling::calendar_user_address,
+};
+
+/// Processes a scheduling request delivered to a room or resource principal
+/// on its behalf. The request is accepted when the policy allows it and the
+/// resource is free at the requested times, otherwise it is declined. The
+/// participation status of the resource is then updated in the event of the
+/// organizer. Accepted events are stored in the default calendar of the
+/// resource so that they count towards its free/busy time.
+pub(super) async fn auto_process_request(
+    server: &Server,
+    organizer_id: u32,
+    resource_id: u32,
+    address: &str,
+    request: &ICalendar,
+) -> trc::Result<()> {
+    let policy = server.core.groupware.resource_auto_accept;
+    if policy == ResourceAutoAccept::Disabled {
+        return Ok(());
+    }
+    match server
+        .core
+        .storage
+        .directory
+        .query(QueryBy::Id(resource_id), false)
+        .await
+        .caused_by(trc::location!())?
+    {
+        Some(principal) if matches!(principal.typ(), Type::Resource | Type::Location) => {}
+        _ => return Ok(()),
+    }
+    let Some(uid) = request
+        .components
+        .iter()
+        .find_map(|comp| comp.uid())
+        .map(|uid| uid.to_string())
+    else {
+        return Ok(());
+    };
+
+    let access_token = server
+        .get_access_token(resource_id)
+        .await
+        .caused_by(trc::location!())?;
+    let is_free = policy == ResourceAutoAccept::Always
+        || !has_conflicts(server, &access_token, resource_id, &uid, request)
+            .await
+            .caused_by(trc::location!())?;
+    let partstat = if is_free {
+        ICalendarParticipationStatus::Accepted
+    } else {
+        ICalendarParticipationStatus::Declined
+    };
+
+    // Store the accepted event in the calendar of the resource
+    if is_free {
+        let mut event = request.clone();
+        for comp in &mut event.components {
+            comp.entries
+                .retain(|entry| entry.name != ICalendarProperty::Method);
+        }
+        set_partstat(&mut event, address, &partstat);
+        if let IngestResult::Skipped(reason) = server
+            .ingest_calendar_data(&access_token, resource_id, None, &event.to_string())
+            .await
+            .caused_by(trc::location!())?
+        {
+            trc::event!(
+                Calendar(trc::CalendarEvent::InviteSkipped),
+                AccountId = resource_id,
+                Reason = reason,
+            );
+        }
+    }
+
+    update_organizer_partstat(server, organizer_id, &uid, address, &partstat)
+        .await
+        .caused_by(trc::location!())
+}
+
+// Returns whether any instance of the request overlaps with a busy instance
+// of an event of another UID in an opaque calendar of the resource
+async fn has_conflicts(
+    server: &Server,
+    access_token: &AccessToken,
+    account_id: u32,
+    uid: &str,
+    request: &ICalendar,
+) -> trc::Result<bool> {
+    // Expand the requested event instances
+    let mut requested = Vec::new();
+    let mut range = TimeRange {
+        start: i64::MAX,
+        end: i64::MIN,
+    };
+    for event in request
+        .expand_dates(Tz::UTC, server.core.groupware.max_ical_instances)
+        .events
+    {
+        if request
+            .components
+            .get(event.comp_id as usize)
+            .is_none_or(|comp| comp.component_type != ICalendarComponentType::VEvent)
+        {
+            continue;
+        }
+        let start = event.start.timestamp();
+        let end = match event.end {
+            TimeOrDelta::Time(time) => time.timestamp(),
+            TimeOrDelta::Delta(delta) => start + delta.num_seconds(),
+        };
+        range.start = range.start.min(start);
+        range.end = range.end.max(end);
+        requested.push((start, end));
+    }
+    if requested.is_empty() {
+        return Ok(false);
+    }
+
+    let resources = server
+        .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+        .await
+        .caused_by(trc::location!())?;
+    for calendar in resources
+        .resources
+        .iter()
+        .filter(|resource| resource.is_container() && resource.schedule_collection().is_none())
+    {
+        let Some(calendar_) = server
+            .get_archive(account_id, Collection::Calendar, calendar.document_id)
+            .await
+            .caused_by(trc::location!())?
+        else {
+            continue;
+        };
+        if calendar_
+            .unarchive::<Calendar>()
+            .caused_by(trc::location!())?
+            .preferences(account_id)
+            .flags
+            .to_native()
+            & CALENDAR_TRANSPARENT
+            != 0
+        {
+            continue;
+        }
+        let default_tz = calendar.timezone().unwrap_or(Tz::UTC);
+
+        for resource in resources.children(calendar.document_id).filter(|resource| {
+            resource
+                .event_time_range()
+                .is_some_and(|(start, end)| start < range.end && end > range.start)
+        }) {
+            let document_id = resource.document_id();
+            let Some(event_) = server
+                .get_archive(account_id, Collection::CalendarEvent, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let event = event_
+                .to_unarchived::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+            let ical = &event.inner.data.event;
+            if ical.uids().next() == Some(uid) {
+                continue;
+            }
+
+            for instance in server.expand_event(account_id, document_id, &event, default_tz, range)
+            {
+                let is_busy = ical
+                    .components
+                    .get(instance.comp_id as usize)
+                    .is_some_and(|comp| {
+                        matches!(comp.component_type, ArchivedICalendarComponentType::VEvent)
+                            && comp
+                                .transparency()
+                                .is_none_or(|t| t == &ICalendarTransparency::Opaque)
+                            && !matches!(comp.status(), Some(ArchivedICalendarStatus::Cancelled))
+                    });
+                if is_busy
+                    && requested
+                        .iter()
+                        .any(|(start, end)| instance.start < *end && instance.end > *start)
+                {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+async fn update_organizer_partstat(
+    server: &Server,
+    account_id: u32,
+    uid: &str,
+    address: &str,
+    partstat: &ICalendarParticipationStatus,
+) -> trc::Result<()> {
+    let access_token = server
+        .get_access_token(account_id)
+        .await
+        .caused_by(trc::location!())?;
+    let resources = server
+        .fetch_dav_resources(&access_token, account_id, SyncCollection::Calendar)
+        .await
+        .caused_by(trc::location!())?;
+    let uid_hits = server
+        .store()
+        .filter(
+            account_id,
+            Collection::CalendarEvent,
+            vec![Filter::eq(IDX_UID, uid.as_bytes().to_vec())],
+        )
+        .await
+        .caused_by(trc::location!())?
+        .results;
+
+    let mut batch = BatchBuilder::new();
+    for document_id in uid_hits {
+        // Messages in the scheduling collections are left untouched
+        let Some(resource) = resources.by_id(document_id) else {
+            continue;
+        };
+        if resource.parent_id().is_some_and(|parent_id| {
+            resources
+                .container_by_id(parent_id)
+                .is_some_and(|calendar| calendar.schedule_collection().is_some())
+        }) {
+            continue;
+        }
+
+        let Some(event_) = server
+            .get_archive(account_id, Collection::CalendarEvent, document_id)
+            .await
+            .caused_by(trc::location!())?
+        else {
+            continue;
+        };
+        let event = event_
+            .to_unarchived::<CalendarEvent>()
+            .caused_by(trc::location!())?;
+        let mut new_event = event
+            .deserialize::<CalendarEvent>()
+            .caused_by(trc::location!())?;
+        let mut ical = new_event.data.event;
+        if !set_partstat(&mut ical, address, partstat) {
+            continue;
+        }
+        new_event.size = ical.to_string().len() as u32;
+        new_event.data =
+            CalendarEventData::new(ical, Tz::Floating, server.core.groupware.max_ical_instances);
+        new_event
+            .update(&access_token, event, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?;
+    }
+
+    if !batch.is_empty() {
+        server
+            .commit_batch(batch)
+            .await
+            .caused_by(trc::location!())?;
+    }
+
+    Ok(())
+}
+
+// Sets the participation status of an attendee, returns whether the
+// attendee was found
+fn set_partstat(
+    ical: &mut ICalendar,
+    address: &str,
+    partstat: &ICalendarParticipationStatus,
+) -> bool {
+    let mut found = false;
+    for entry in ical
+        .components
+        .iter_mut()
+        .flat_map(|comp| comp.entries.iter_mut())
+        .filter(|entry| entry.name == ICalendarProperty::Attendee)
+    {
+        if entry
+            .values
+            .first()
+            .and_then(|value| value.as_text())
+            .and_then(calendar_user_address)
+            .is_some_and(|attendee| attendee == address)
+        {
+            entry.params.retain(|param| {
+                !matches!(
+                    param,
+                    ICalendarParameter::Partstat(_) | ICalendarParameter::Rsvp(_)
+                )
+            });
+            entry
+                .params
+                .push(ICalendarParameter::Partstat(partstat.clone()));
+            found = true;
+        }
+    }
+    found
+}
@@ -0,0 +1,307 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::fmt::Write;
+
+use calcard::{
+    Entry, Parser,
+    common::timezone::Tz,
+    icalendar::ICalendar,
+    vcard::{VCard, VCardProperty, VCardValue},
+};
+use common::{DavName, PROD_ID, Server};
+use jmap_proto::types::collection::{Collection, SyncCollection};
+use store::{ahash::AHashMap, write::BatchBuilder};
+use trc::AddContext;
+
+use crate::{DestroyArchive, cache::GroupwareCache, contact::ContactCard};
+
+use super::{
+    CALENDAR_BIRTHDAYS, CALENDAR_TRANSPARENT, Calendar, CalendarEvent, CalendarEventData,
+    CalendarPreferences,
+};
+
+pub const BIRTHDAYS_DISPLAY_NAME: &str = "Birthdays";
+
+pub trait CalendarBirthdays: Sync + Send {
+    /// Regenerates the birthday calendar of an account from the birthdays and
+    /// anniversaries of its contacts. The calendar is created once a contact
+    /// has a date and removed when no contact has one left.
+    fn sync_birthday_calendar(
+        &self,
+        account_id: u32,
+        href_name: &str,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl CalendarBirthdays for Server {
+    async fn sync_birthday_calendar(&self, account_id: u32, href_name: &str) -> trc::Result<()> {
+        let access_token = self
+            .get_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?;
+
+        // Build the events from the dates of the contacts
+        let contacts = self
+            .fetch_dav_resources(&access_token, account_id, SyncCollection::AddressBook)
+            .await
+            .caused_by(trc::location!())?;
+        let mut events = AHashMap::new();
+        for resource in contacts
+            .resources
+            .iter()
+            .filter(|resource| !resource.is_container())
+        {
+            let Some(card_) = self
+                .get_archive(account_id, Collection::ContactCard, resource.document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let card = card_
+                .deserialize::<ContactCard>()
+                .caused_by(trc::location!())?;
+            for (kind, property) in [
+                ("birthday", VCardProperty::Bday),
+                ("anniversary", VCardProperty::Anniversary),
+            ] {
+                if let Some(ical) = date_event(&card.card, &property, kind, resource.document_id) {
+                    events.insert(format!("{kind}-{}.ics", resource.document_id), ical);
+                }
+            }
+        }
+
+        let resources = self
+            .fetch_dav_resources(&access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?;
+        let mut batch = BatchBuilder::new();
+        let calendar_id = match resources.birthday_calendar() {
+            Some(calendar) if events.is_empty() => {
+                // Remove the calendar once no contact has a date left
+                let calendar_id = calendar.document_id();
+                if let Some(calendar_) = self
+                    .get_archive(account_id, Collection::Calendar, calendar_id)
+                    .await
+                    .caused_by(trc::location!())?
+                {
+                    DestroyArchive(
+                        calendar_
+                            .to_unarchived::<Calendar>()
+                            .caused_by(trc::location!())?,
+                    )
+                    .delete_with_events(
+                        self,
+                        &access_token,
+                        account_id,
+                        calendar_id,
+                        resources
+                            .children(calendar_id)
+                            .map(|resource| resource.document_id())
+                            .collect(),
+                        resources.format_resource(calendar).into(),
+                        &mut batch,
+                    )
+                    .await
+                    .caused_by(trc::location!())?;
+                    self.commit_batch(batch).await.caused_by(trc::location!())?;
+                }
+                return Ok(());
+            }
+            Some(calendar) => calendar.document_id(),
+            None if events.is_empty() => {
+                return Ok(());
+            }
+            None => {
+                // Avoid clashing with a regular calendar using the same name
+                let mut name = href_name.to_string();
+                let mut suffix = 0;
+                while resources.by_path(&name).is_some() {
+                    suffix += 1;
+                    name = format!("{href_name}-{suffix}");
+                }
+
+                let document_id = self
+                    .store()
+                    .assign_document_ids(account_id, Collection::Calendar, 1)
+                    .await
+                    .caused_by(trc::location!())?;
+                Calendar {
+                    name,
+                    preferences: vec![CalendarPreferences {
+                        account_id,
+                        name: BIRTHDAYS_DISPLAY_NAME.to_string(),
+                        flags: CALENDAR_BIRTHDAYS | CALENDAR_TRANSPARENT,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }
+                .insert(&access_token, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+                document_id
+            }
+        };
+
+        // Update the existing events and remove those without a date
+        for resource in resources.children(calendar_id) {
+            let document_id = resource.document_id();
+            let name = resource
+                .path()
+                .rsplit_once('/')
+                .map_or(resource.path(), |(_, name)| name);
+            let Some(event_) = self
+                .get_archive(account_id, Collection::CalendarEvent, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let event = event_
+                .to_unarchived::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+
+            if let Some(ical) = events.remove(name) {
+                let ical_text = ical.to_string();
+                if event.inner.data.event.to_string() != ical_text {
+                    let mut new_event = event
+                        .deserialize::<CalendarEvent>()
+                        .caused_by(trc::location!())?;
+                    new_event.size = ical_text.len() as u32;
+                    new_event.data = CalendarEventData::new(
+                        ical,
+                        Tz::Floating,
+                        self.core.groupware.max_ical_instances,
+                    );
+                    new_event
+                        .update(&access_token, event, account_id, document_id, &mut batch)
+                        .caused_by(trc::location!())?;
+                }
+            } else {
+                DestroyArchive(event)
+                    .delete(
+                        &access_token,
+                        account_id,
+                        document_id,
+                        calendar_id,
+                        resources.format_resource(resource).into(),
+                        &mut batch,
+                    )
+                    .caused_by(trc::location!())?;
+            }
+        }
+
+        // Add the events of new dates
+        for (name, ical) in events {
+            let document_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::CalendarEvent, 1)
+                .await
+                .caused_by(trc::location!())?;
+            CalendarEvent {
+                names: vec![DavName {
+                    name,
+                    parent_id: calendar_id,
+                }],
+                size: ical.to_string().len() as u32,
+                data: CalendarEventData::new(
+                    ical,
+                    Tz::Floating,
+                    self.core.groupware.max_ical_instances,
+                ),
+                ..Default::default()
+            }
+            .insert(&access_token, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?;
+        }
+
+        if !batch.is_empty() {
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+}
+
+// Builds a yearly all-day event for the first date of a vCard property
+fn date_event(
+    card: &VCard,
+    property: &VCardProperty,
+    kind: &str,
+    document_id: u32,
+) -> Option<ICalendar> {
+    let (year, month, day) = card
+        .properties(property)
+        .flat_map(|entry| entry.values.iter())
+        .find_map(|value| match value {
+            VCardValue::PartialDateTime(dt) => Some((dt.year, dt.month?, dt.day?)),
+            _ => None,
+        })?;
+    let name = card
+        .properties(&VCardProperty::Fn)
+        .flat_map(|entry| entry.values.iter())
+        .find_map(|value| value.as_text())
+        .unwrap_or_default();
+    let summary = if kind == "birthday" {
+        "Birthday"
+    } else {
+        "Anniversary"
+    };
+
+    // Dates without a year start in 1970, the timestamp is derived from the
+    // date so that regenerating the event does not modify it
+    let date = format!("{:04}{month:02}{day:02}", year.unwrap_or(1970));
+    let mut ical = String::with_capacity(256);
+    let _ = write!(
+        &mut ical,
+        concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "VERSION:2.0\r\n",
+            "PRODID:{}\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:{}-{}\r\n",
+            "DTSTAMP:{}T000000Z\r\n",
+            "DTSTART;VALUE=DATE:{}\r\n",
+            "DURATION:P1D\r\n",
+            "RRULE:FREQ=YEARLY\r\n",
+            "SUMMARY:{}\r\n",
+            "TRANSP:TRANSPARENT\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n"
+        ),
+        PROD_ID,
+        kind,
+        document_id,
+        date,
+        date,
+        escape_text(&if name.is_empty() {
+            summary.to_string()
+        } else {
+            format!("{summary}: {name}")
+        }),
+    );
+
+    match Parser::new(&ical).entry() {
+        Entry::ICalendar(ical) => Some(ical),
+        _ => None,
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
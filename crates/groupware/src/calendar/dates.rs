@@ -5,10 +5,10 @@
  */
 
 use super::{
-    Alarm, AlarmDelta, ArchivedAlarmDelta, ArchivedCalendarEventData, ArchivedTimezone,
-    CalendarEventData, Timezone,
+    Alarm, AlarmDelta, ArchivedAlarmDelta, ArchivedCalendarEvent, ArchivedCalendarEventData,
+    ArchivedTimezone, CalendarEventData, Timezone,
 };
-use crate::calendar::ComponentTimeRange;
+use crate::calendar::{ComponentTimeRange, timezone::parse_tz};
 use calcard::{
     common::timezone::Tz,
     icalendar::{
@@ -18,12 +18,13 @@ use calcard::{
     },
 };
 use chrono::{DateTime, TimeZone};
+use common::{EventExpansion, EventExpansionKey, Server};
 use compact_str::ToCompactString;
 use dav_proto::schema::property::TimeRange;
-use std::str::FromStr;
+use std::sync::Arc;
 use store::{
     ahash::AHashMap,
-    write::{bitpack::BitpackIterator, key::KeySerializer},
+    write::{Archive, bitpack::BitpackIterator, key::KeySerializer},
 };
 use utils::codec::leb128::Leb128Reader;
 
@@ -252,6 +253,162 @@ impl ArchivedCalendarEventData {
     }
 }
 
+// Width of the intervals used to index the instances of recurring events
+pub const EVENT_TIME_BUCKET: i64 = 7 * 86400;
+const MAX_EVENT_TIME_BUCKETS: usize = 4096;
+
+impl ArchivedCalendarEventData {
+    /// Returns the sorted time buckets covered by the instances and alarms of
+    /// a recurring event, or an empty list when the event does not recur or
+    /// its instances are spread over too many buckets.
+    pub fn time_buckets(&self) -> Box<[i32]> {
+        let Some(events) = self.expand(
+            Tz::UTC,
+            TimeRange {
+                start: i64::MIN,
+                end: i64::MAX,
+            },
+        ) else {
+            return Box::default();
+        };
+        if events.len() < 2 {
+            return Box::default();
+        }
+
+        let mut buckets = Vec::with_capacity(events.len());
+        for event in events {
+            let mut min = std::cmp::min(event.start, event.end);
+            let mut max = std::cmp::max(event.start, event.end);
+            for alarm in self
+                .alarms
+                .iter()
+                .filter(|alarm| alarm.comp_id.to_native() == event.comp_id)
+            {
+                for alarm_delta in alarm.alarms.iter() {
+                    if let Some(alarm_time) =
+                        alarm_delta.to_timestamp(event.start, event.end, Tz::UTC)
+                    {
+                        min = std::cmp::min(min, alarm_time);
+                        max = std::cmp::max(max, alarm_time);
+                    }
+                }
+            }
+
+            for bucket in time_bucket(min)..=time_bucket(max) {
+                buckets.push(bucket);
+                if buckets.len() > MAX_EVENT_TIME_BUCKETS * 2 {
+                    return Box::default();
+                }
+            }
+        }
+
+        buckets.sort_unstable();
+        buckets.dedup();
+        if buckets.len() <= MAX_EVENT_TIME_BUCKETS {
+            buckets.into_boxed_slice()
+        } else {
+            Box::default()
+        }
+    }
+}
+
+/// Returns true when any of the time buckets of an event overlaps a range.
+/// Times are bucketed as UTC, so the range is widened by a day to account
+/// for floating times.
+pub fn time_buckets_in_range(buckets: &[i32], range: &TimeRange) -> bool {
+    let first = time_bucket(range.start.saturating_sub(86400));
+    let last = time_bucket(range.end.saturating_add(86400));
+    let idx = buckets.partition_point(|bucket| *bucket < first);
+
+    buckets.get(idx).is_some_and(|bucket| *bucket <= last)
+}
+
+fn time_bucket(timestamp: i64) -> i32 {
+    timestamp
+        .div_euclid(EVENT_TIME_BUCKET)
+        .clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+pub trait ExpandCalendarEvent: Sync + Send {
+    /// Returns the instances of an event that fall within a time range. The
+    /// full expansion of each event version is cached, so repeated queries
+    /// on long recurrences only need to filter it.
+    fn expand_event(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        event: &Archive<&ArchivedCalendarEvent>,
+        default_tz: Tz,
+        limit: TimeRange,
+    ) -> Vec<CalendarEvent<i64, i64>>;
+}
+
+impl ExpandCalendarEvent for Server {
+    fn expand_event(
+        &self,
+        account_id: u32,
+        document_id: u32,
+        event: &Archive<&ArchivedCalendarEvent>,
+        default_tz: Tz,
+        limit: TimeRange,
+    ) -> Vec<CalendarEvent<i64, i64>> {
+        let key = event.version.hash().map(|hash| EventExpansionKey {
+            account_id,
+            document_id,
+            hash,
+            default_tz: default_tz.as_id(),
+        });
+        let expansion = if let Some(expansion) = key
+            .as_ref()
+            .and_then(|key| self.inner.cache.event_expansions.get(key))
+        {
+            expansion
+        } else {
+            let expansion = Arc::new(EventExpansion {
+                events: event
+                    .inner
+                    .data
+                    .expand(
+                        default_tz,
+                        TimeRange {
+                            start: i64::MIN,
+                            end: i64::MAX,
+                        },
+                    )
+                    .unwrap_or_else(|| {
+                        trc::event!(
+                            Calendar(trc::CalendarEvent::RuleExpansionError),
+                            Reason = "chrono error",
+                            Details = event.inner.data.event.to_string(),
+                        );
+                        vec![]
+                    }),
+            });
+            if let Some(key) = key {
+                self.inner
+                    .cache
+                    .event_expansions
+                    .insert(key, expansion.clone());
+            }
+            expansion
+        };
+
+        expansion
+            .events
+            .iter()
+            .filter(|event| {
+                ((event.start < limit.end) || (event.start <= limit.start))
+                    && (event.end > limit.start || event.end >= limit.end)
+            })
+            .map(|event| CalendarEvent {
+                comp_id: event.comp_id,
+                start: event.start,
+                end: event.end,
+            })
+            .collect()
+    }
+}
+
 #[derive(Default, Debug)]
 struct TimeRanges {
     max_time_utc: i64,
@@ -336,7 +493,7 @@ impl ExpandAlarm for ICalendarComponent {
                             trigger_start = matches!(related, Related::Start);
                         }
                         ICalendarParameter::Tzid(tz_id) => {
-                            tz = Tz::from_str(tz_id).ok();
+                            tz = parse_tz(tz_id);
                         }
                         _ => {}
                     }
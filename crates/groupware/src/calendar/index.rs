@@ -22,11 +22,15 @@ impl IndexableObject for Calendar {
                 used: self.dead_properties.size() as u32
                     + self.preferences.iter().map(|p| p.size()).sum::<usize>() as u32
                     + self.default_alerts.iter().map(|a| a.size()).sum::<usize>() as u32
+                    + self.availability.as_ref().map_or(0, |a| a.size()) as u32
                     + self.name.len() as u32,
             },
             IndexValue::LogContainer {
                 sync_collection: SyncCollection::Calendar.into(),
             },
+            IndexValue::LogContainerShard {
+                sync_collection: SyncCollection::Calendar.into(),
+            },
         ]
         .into_iter()
     }
@@ -47,11 +51,15 @@ impl IndexableObject for &ArchivedCalendar {
                 used: self.dead_properties.size() as u32
                     + self.preferences.iter().map(|p| p.size()).sum::<usize>() as u32
                     + self.default_alerts.iter().map(|a| a.size()).sum::<usize>() as u32
+                    + self.availability.as_ref().map_or(0, |a| a.size()) as u32
                     + self.name.len() as u32,
             },
             IndexValue::LogContainer {
                 sync_collection: SyncCollection::Calendar.into(),
             },
+            IndexValue::LogContainerShard {
+                sync_collection: SyncCollection::Calendar.into(),
+            },
         ]
         .into_iter()
     }
@@ -65,7 +73,9 @@ impl IndexableAndSerializableObject for Calendar {
 
 impl IndexableObject for CalendarEvent {
     fn index_values(&self) -> impl Iterator<Item = IndexValue<'_>> {
-        [
+        let mut values = Vec::with_capacity(4 + self.attachments.len());
+
+        values.extend([
             IndexValue::Index {
                 field: IDX_UID,
                 value: self.data.event.uids().next().into(),
@@ -74,20 +84,32 @@ impl IndexableObject for CalendarEvent {
                 used: self.dead_properties.size() as u32
                     + self.display_name.as_ref().map_or(0, |n| n.len() as u32)
                     + self.names.iter().map(|n| n.name.len() as u32).sum::<u32>()
+                    + self.attachments.iter().map(|a| a.file.size).sum::<u32>()
                     + self.size,
             },
             IndexValue::LogItem {
                 sync_collection: SyncCollection::Calendar.into(),
                 prefix: None,
             },
-        ]
-        .into_iter()
+            IndexValue::LogItemShard {
+                sync_collection: SyncCollection::Calendar.into(),
+                ids: self.names.iter().map(|name| name.parent_id).collect(),
+            },
+        ]);
+
+        values.extend(self.attachments.iter().map(|attachment| IndexValue::Blob {
+            value: attachment.file.blob_hash.clone(),
+        }));
+
+        values.into_iter()
     }
 }
 
 impl IndexableObject for &ArchivedCalendarEvent {
     fn index_values(&self) -> impl Iterator<Item = IndexValue<'_>> {
-        [
+        let mut values = Vec::with_capacity(4 + self.attachments.len());
+
+        values.extend([
             IndexValue::Index {
                 field: IDX_UID,
                 value: self.data.event.uids().next().into(),
@@ -96,14 +118,32 @@ impl IndexableObject for &ArchivedCalendarEvent {
                 used: self.dead_properties.size() as u32
                     + self.display_name.as_ref().map_or(0, |n| n.len() as u32)
                     + self.names.iter().map(|n| n.name.len() as u32).sum::<u32>()
+                    + self
+                        .attachments
+                        .iter()
+                        .map(|a| a.file.size.to_native())
+                        .sum::<u32>()
                     + self.size,
             },
             IndexValue::LogItem {
                 sync_collection: SyncCollection::Calendar.into(),
                 prefix: None,
             },
-        ]
-        .into_iter()
+            IndexValue::LogItemShard {
+                sync_collection: SyncCollection::Calendar.into(),
+                ids: self
+                    .names
+                    .iter()
+                    .map(|name| name.parent_id.to_native())
+                    .collect(),
+            },
+        ]);
+
+        values.extend(self.attachments.iter().map(|attachment| IndexValue::Blob {
+            value: (&attachment.file.blob_hash).into(),
+        }));
+
+        values.into_iter()
     }
 }
 
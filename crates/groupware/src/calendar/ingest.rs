@@ -0,0 +1,233 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::collections::HashSet;
+
+use calcard::{
+    Entry, Parser,
+    common::timezone::Tz,
+    icalendar::{ICalendar, ICalendarComponentType},
+};
+use common::{DavName, DavResources, IDX_UID, Server, auth::AccessToken};
+use jmap_proto::types::collection::{Collection, SyncCollection};
+use store::{query::Filter, write::BatchBuilder};
+use trc::AddContext;
+
+use crate::{IngestResult, cache::GroupwareCache, member_name};
+
+use super::{CalendarEvent, CalendarEventData};
+
+pub trait CalendarIngest: Sync + Send {
+    /// Stores an iCalendar object received outside of CalDAV, such as an email
+    /// attachment, in the named calendar or the account's default calendar.
+    /// Objects sharing the UID of an existing event replace it.
+    fn ingest_calendar_data(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        calendar_name: Option<&str>,
+        calendar_data: &str,
+    ) -> impl Future<Output = trc::Result<IngestResult>> + Send;
+}
+
+impl CalendarIngest for Server {
+    async fn ingest_calendar_data(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        calendar_name: Option<&str>,
+        calendar_data: &str,
+    ) -> trc::Result<IngestResult> {
+        if calendar_data.len() > self.core.groupware.max_ical_size {
+            return Ok(IngestResult::Skipped("iCalendar object too large"));
+        }
+        let ical = match Parser::new(calendar_data).entry() {
+            Entry::ICalendar(ical) => ical,
+            _ => return Ok(IngestResult::Skipped("invalid iCalendar object")),
+        };
+        let Some(uid) = validate_ical(&ical).map(|uid| uid.to_string()) else {
+            return Ok(IngestResult::Skipped("iCalendar object has no single UID"));
+        };
+
+        // Locate the target calendar
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?;
+        let calendar_id = match calendar_name {
+            Some(name) => resources
+                .by_path(name)
+                .filter(|calendar| calendar.is_container())
+                .map(|calendar| calendar.document_id()),
+            None => default_calendar(
+                &resources,
+                self.core.groupware.default_calendar_name.as_deref(),
+            ),
+        };
+        let Some(calendar_id) = calendar_id else {
+            return Ok(IngestResult::Skipped("calendar not found"));
+        };
+
+        // Replace any event with the same UID in the calendar
+        let uid_hits = self
+            .store()
+            .filter(
+                account_id,
+                Collection::CalendarEvent,
+                vec![Filter::eq(IDX_UID, uid.as_bytes().to_vec())],
+            )
+            .await
+            .caused_by(trc::location!())?
+            .results;
+        let existing = resources
+            .children(calendar_id)
+            .find(|child| uid_hits.contains(child.document_id()));
+        let resource_token = self.get_resource_token(access_token, account_id).await?;
+        let mut batch = BatchBuilder::new();
+
+        if let Some(existing) = existing {
+            let document_id = existing.document_id();
+            let Some(event_) = self
+                .get_archive(account_id, Collection::CalendarEvent, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                return Ok(IngestResult::Skipped("event not found"));
+            };
+            let event = event_
+                .to_unarchived::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+            let mut new_event = event
+                .deserialize::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+            let extra_bytes = (calendar_data.len() as u64).saturating_sub(new_event.size as u64);
+            if extra_bytes > 0 {
+                self.has_available_quota(&resource_token, extra_bytes)
+                    .await?;
+            }
+            new_event.size = calendar_data.len() as u32;
+            new_event.data =
+                CalendarEventData::new(ical, Tz::Floating, self.core.groupware.max_ical_instances);
+            new_event
+                .update(access_token, event, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+            trc::event!(
+                Groupware(trc::GroupwareEvent::CalendarUpdated),
+                AccountId = account_id,
+                Collection = Collection::CalendarEvent,
+                DocumentId = document_id,
+                Url = resources.format_resource(existing),
+            );
+
+            Ok(IngestResult::Updated(document_id))
+        } else {
+            // Validate item limit and quota
+            if let Some(max_events) = self
+                .core
+                .groupware
+                .item_limits(
+                    account_id,
+                    resource_token.tenant.as_ref().map(|tenant| tenant.id),
+                )
+                .events_per_calendar
+            {
+                if resources.children(calendar_id).count() >= max_events {
+                    return Ok(IngestResult::Skipped("calendar is full"));
+                }
+            }
+            self.has_available_quota(&resource_token, calendar_data.len() as u64)
+                .await?;
+
+            let document_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::CalendarEvent, 1)
+                .await
+                .caused_by(trc::location!())?;
+            let calendar_path = resources
+                .container_by_id(calendar_id)
+                .map(|calendar| calendar.path().to_string())
+                .unwrap_or_default();
+            let name = event_name(&resources, &calendar_path);
+            let href = resources.format_item(&format!("{calendar_path}/{name}"));
+            CalendarEvent {
+                names: vec![DavName::new(name, calendar_id)],
+                data: CalendarEventData::new(
+                    ical,
+                    Tz::Floating,
+                    self.core.groupware.max_ical_instances,
+                ),
+                size: calendar_data.len() as u32,
+                ..Default::default()
+            }
+            .insert(access_token, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?;
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+            trc::event!(
+                Groupware(trc::GroupwareEvent::CalendarCreated),
+                AccountId = account_id,
+                Collection = Collection::CalendarEvent,
+                DocumentId = document_id,
+                Url = href,
+            );
+
+            Ok(IngestResult::Created(document_id))
+        }
+    }
+}
+
+fn default_calendar(resources: &DavResources, name: Option<&str>) -> Option<u32> {
+    name.and_then(|name| resources.by_path(name))
+        .filter(|calendar| calendar.is_container() && calendar.schedule_collection().is_none())
+        .map(|calendar| calendar.document_id())
+        .or_else(|| {
+            // Fall back to the oldest calendar when the default one was renamed or removed
+            resources
+                .resources
+                .iter()
+                .filter(|resource| {
+                    resource.is_container() && resource.schedule_collection().is_none()
+                })
+                .map(|resource| resource.document_id)
+                .min()
+        })
+}
+
+pub(super) fn event_name(resources: &DavResources, calendar_path: &str) -> String {
+    member_name(resources, calendar_path, ".ics")
+}
+
+fn validate_ical(ical: &ICalendar) -> Option<&str> {
+    // Validate UIDs
+    let mut uids = HashSet::with_capacity(1);
+
+    // Validate component types
+    let mut types: [u8; 5] = [0; 5];
+    for comp in &ical.components {
+        *(match comp.component_type {
+            ICalendarComponentType::VEvent => &mut types[0],
+            ICalendarComponentType::VTodo => &mut types[1],
+            ICalendarComponentType::VJournal => &mut types[2],
+            ICalendarComponentType::VFreebusy => &mut types[3],
+            ICalendarComponentType::VAvailability => &mut types[4],
+            _ => {
+                continue;
+            }
+        }) += 1;
+
+        if let Some(uid) = comp.uid() {
+            uids.insert(uid);
+        }
+    }
+
+    if uids.len() == 1 && types.iter().filter(|&&v| v == 0).count() == 4 {
+        uids.iter().next().copied()
+    } else {
+        None
+    }
+}
@@ -0,0 +1,323 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{
+    KV_CALENDAR_MIRROR, Server,
+    config::groupware::{CalendarMirror, MirrorConflictPolicy},
+};
+use directory::backend::internal::manage::ManageDirectory;
+use jmap_proto::types::collection::{Collection, SyncCollection};
+use reqwest::{
+    Client, Method, StatusCode,
+    header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH},
+};
+use store::{
+    Serialize,
+    ahash::AHashMap,
+    dispatch::lookup::KeyValue,
+    write::{AlignedBytes, Archive, Archiver},
+};
+use trc::AddContext;
+
+use crate::cache::GroupwareCache;
+
+use super::CalendarEvent;
+
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+#[rkyv(derive(Debug))]
+pub struct MirrorState {
+    pub items: Vec<MirrorItem>,
+}
+
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+#[rkyv(derive(Debug))]
+pub struct MirrorItem {
+    pub name: String,
+    pub local_hash: u32,
+    pub remote_etag: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MirrorStats {
+    pub uploaded: usize,
+    pub deleted: usize,
+    pub conflicts: usize,
+    pub errors: usize,
+}
+
+enum MirrorOutcome {
+    Stored(Option<String>),
+    Conflict,
+    Failed(String),
+}
+
+pub trait CalendarMirrorSync: Sync + Send {
+    /// Pushes the changes made to a local calendar since the previous run to
+    /// the external CalDAV collection. Mirroring is one-way, remote changes are
+    /// never imported.
+    fn mirror_calendar(
+        &self,
+        mirror: &CalendarMirror,
+    ) -> impl Future<Output = trc::Result<MirrorStats>> + Send;
+}
+
+impl CalendarMirrorSync for Server {
+    async fn mirror_calendar(&self, mirror: &CalendarMirror) -> trc::Result<MirrorStats> {
+        let Some(account_id) = self
+            .store()
+            .get_principal_id(&mirror.account)
+            .await
+            .caused_by(trc::location!())?
+        else {
+            return Err(trc::ResourceEvent::NotFound
+                .into_err()
+                .details("Calendar owner not found")
+                .id(mirror.account.clone()));
+        };
+        let access_token = self
+            .get_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let resources = self
+            .fetch_dav_resources(&access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?;
+        let Some(calendar_id) = resources
+            .by_path(&mirror.calendar)
+            .filter(|calendar| calendar.is_container())
+            .map(|calendar| calendar.document_id())
+        else {
+            return Err(trc::ResourceEvent::NotFound
+                .into_err()
+                .details("Calendar not found")
+                .id(mirror.calendar.clone()));
+        };
+
+        // Obtain the state of the previous run
+        let state_key = KeyValue::<()>::build_key(KV_CALENDAR_MIRROR, mirror.id.as_bytes());
+        let mut previous: AHashMap<String, MirrorItem> = self
+            .in_memory_store()
+            .key_get::<Archive<AlignedBytes>>(state_key.clone())
+            .await
+            .caused_by(trc::location!())?
+            .map(|state| state.deserialize::<MirrorState>())
+            .transpose()
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+            .items
+            .into_iter()
+            .map(|item| (item.name.clone(), item))
+            .collect();
+
+        let client = Client::builder()
+            .timeout(mirror.timeout)
+            .danger_accept_invalid_certs(mirror.tls_allow_invalid_certs)
+            .build()
+            .map_err(|err| {
+                trc::EventType::Calendar(trc::CalendarEvent::MirrorError)
+                    .into_err()
+                    .reason(err)
+                    .details("Failed to create HTTP client")
+            })?;
+        let mut stats = MirrorStats::default();
+        let mut items = Vec::with_capacity(previous.len());
+
+        // Upload new and modified events
+        for resource in resources.children(calendar_id) {
+            let Some(name) = resource.path().rsplit_once('/').map(|(_, name)| name) else {
+                continue;
+            };
+            let Some(event_) = self
+                .get_archive(
+                    account_id,
+                    Collection::CalendarEvent,
+                    resource.document_id(),
+                )
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let local_hash = event_.version.hash().unwrap_or_default();
+            let previous_item = previous.remove(name);
+            if previous_item
+                .as_ref()
+                .is_some_and(|item| item.local_hash == local_hash)
+            {
+                items.extend(previous_item);
+                continue;
+            }
+            let event = event_
+                .unarchive::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+
+            let previous_hash = previous_item.as_ref().map(|item| item.local_hash);
+            let remote_etag = previous_item.and_then(|item| item.remote_etag);
+            let condition = match (mirror.conflict_policy, &remote_etag) {
+                (MirrorConflictPolicy::LocalWins, _) => None,
+                (MirrorConflictPolicy::RemoteWins, Some(etag)) => Some((IF_MATCH, etag.as_str())),
+                (MirrorConflictPolicy::RemoteWins, None) => Some((IF_NONE_MATCH, "*")),
+            };
+            match send(
+                &client,
+                mirror,
+                Method::PUT,
+                name,
+                condition,
+                Some(event.data.event.to_string()),
+            )
+            .await
+            {
+                MirrorOutcome::Stored(remote_etag) => {
+                    stats.uploaded += 1;
+                    items.push(MirrorItem {
+                        name: name.to_string(),
+                        local_hash,
+                        remote_etag,
+                    });
+                }
+                MirrorOutcome::Conflict => {
+                    // Keep the remote version until the event changes locally again
+                    stats.conflicts += 1;
+                    items.push(MirrorItem {
+                        name: name.to_string(),
+                        local_hash,
+                        remote_etag,
+                    });
+                    trc::event!(
+                        Calendar(trc::CalendarEvent::MirrorConflict),
+                        Id = mirror.id.clone(),
+                        AccountId = account_id,
+                        Url = format!("{}{name}", mirror.url),
+                    );
+                }
+                MirrorOutcome::Failed(reason) => {
+                    // Retry on the next run
+                    stats.errors += 1;
+                    if let Some(local_hash) = previous_hash {
+                        items.push(MirrorItem {
+                            name: name.to_string(),
+                            local_hash,
+                            remote_etag,
+                        });
+                    }
+                    trc::event!(
+                        Calendar(trc::CalendarEvent::MirrorError),
+                        Id = mirror.id.clone(),
+                        AccountId = account_id,
+                        Url = format!("{}{name}", mirror.url),
+                        Reason = reason,
+                    );
+                }
+            }
+        }
+
+        // Remove events deleted locally
+        for (name, item) in previous {
+            let condition = match (mirror.conflict_policy, &item.remote_etag) {
+                (MirrorConflictPolicy::RemoteWins, Some(etag)) => Some((IF_MATCH, etag.as_str())),
+                _ => None,
+            };
+            match send(&client, mirror, Method::DELETE, &name, condition, None).await {
+                MirrorOutcome::Stored(_) => {
+                    stats.deleted += 1;
+                }
+                MirrorOutcome::Conflict => {
+                    stats.conflicts += 1;
+                    trc::event!(
+                        Calendar(trc::CalendarEvent::MirrorConflict),
+                        Id = mirror.id.clone(),
+                        AccountId = account_id,
+                        Url = format!("{}{name}", mirror.url),
+                    );
+                }
+                MirrorOutcome::Failed(reason) => {
+                    stats.errors += 1;
+                    trc::event!(
+                        Calendar(trc::CalendarEvent::MirrorError),
+                        Id = mirror.id.clone(),
+                        AccountId = account_id,
+                        Url = format!("{}{name}", mirror.url),
+                        Reason = reason,
+                    );
+                    items.push(item);
+                }
+            }
+        }
+
+        // Store state
+        self.in_memory_store()
+            .key_set(KeyValue::new(
+                state_key,
+                Archiver::new(MirrorState { items })
+                    .serialize()
+                    .caused_by(trc::location!())?,
+            ))
+            .await
+            .caused_by(trc::location!())?;
+
+        trc::event!(
+            Calendar(trc::CalendarEvent::MirrorCompleted),
+            Id = mirror.id.clone(),
+            AccountId = account_id,
+            Url = mirror.url.clone(),
+            TotalSuccesses = stats.uploaded + stats.deleted,
+            TotalFailures = stats.conflicts + stats.errors,
+        );
+
+        Ok(stats)
+    }
+}
+
+async fn send(
+    client: &Client,
+    mirror: &CalendarMirror,
+    method: Method,
+    name: &str,
+    condition: Option<(reqwest::header::HeaderName, &str)>,
+    body: Option<String>,
+) -> MirrorOutcome {
+    let url = format!(
+        "{}{}",
+        mirror.url,
+        percent_encoding::utf8_percent_encode(name, percent_encoding::NON_ALPHANUMERIC)
+    );
+    let mut request = client
+        .request(method.clone(), &url)
+        .headers(mirror.headers.clone());
+    if let Some((header, value)) = condition {
+        request = request.header(header, value);
+    }
+    if let Some(body) = body {
+        request = request
+            .header(CONTENT_TYPE, "text/calendar; charset=utf-8")
+            .body(body);
+    }
+
+    match request.send().await {
+        Ok(response) => match response.status() {
+            status if status.is_success() => MirrorOutcome::Stored(
+                response
+                    .headers()
+                    .get(ETAG)
+                    .and_then(|etag| etag.to_str().ok())
+                    .map(|etag| etag.to_string()),
+            ),
+            StatusCode::NOT_FOUND if method == Method::DELETE => MirrorOutcome::Stored(None),
+            StatusCode::PRECONDITION_FAILED => MirrorOutcome::Conflict,
+            status => MirrorOutcome::Failed(format!(
+                "Request failed with code {}: {}",
+                status.as_u16(),
+                status.canonical_reason().unwrap_or("Unknown")
+            )),
+        },
+        Err(err) => MirrorOutcome::Failed(format!("Request to {url} failed: {err}")),
+    }
+}
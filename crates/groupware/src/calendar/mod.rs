@@ -4,11 +4,21 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+pub mod attachment;
+pub mod auto_accept;
+pub mod birthdays;
 pub mod dates;
 pub mod index;
+pub mod ingest;
+pub mod mirror;
+pub mod scheduling;
+pub mod sharing;
+pub mod split;
 pub mod storage;
+pub mod timezone;
 
-use calcard::icalendar::ICalendar;
+use crate::file::FileProperties;
+use calcard::icalendar::{ICalendar, ICalendarComponentType};
 use common::DavName;
 use dav_proto::schema::request::DeadProperty;
 use jmap_proto::types::{acl::Acl, value::AclGrant};
@@ -20,7 +30,10 @@ pub struct Calendar {
     pub name: String,
     pub preferences: Vec<CalendarPreferences>,
     pub default_alerts: Vec<DefaultAlert>,
+    pub availability: Option<ICalendar>,
+    pub supported_components: u16,
     pub acls: Vec<AclGrant>,
+    pub invites: Vec<CalendarInvite>,
     pub dead_properties: DeadProperty,
     pub created: i64,
     pub modified: i64,
@@ -31,6 +44,19 @@ pub const CALENDAR_DEFAULT: u16 = 1 << 1;
 pub const CALENDAR_VISIBLE: u16 = 1 << 2;
 pub const CALENDAR_AVAILABILITY_ALL: u16 = 1 << 3;
 pub const CALENDAR_AVAILABILITY_ATTENDING: u16 = 1 << 4;
+pub const CALENDAR_SCHEDULE_INBOX: u16 = 1 << 5;
+pub const CALENDAR_SCHEDULE_OUTBOX: u16 = 1 << 6;
+pub const CALENDAR_TRANSPARENT: u16 = 1 << 7;
+pub const CALENDAR_BIRTHDAYS: u16 = 1 << 8;
+
+// Top-level component types a calendar can be restricted to (RFC 4791)
+pub const CALENDAR_COMPONENTS: [ICalendarComponentType; 5] = [
+    ICalendarComponentType::VEvent,
+    ICalendarComponentType::VTodo,
+    ICalendarComponentType::VJournal,
+    ICalendarComponentType::VFreebusy,
+    ICalendarComponentType::VAvailability,
+];
 
 #[derive(
     rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
@@ -45,6 +71,30 @@ pub struct CalendarPreferences {
     pub time_zone: Timezone,
 }
 
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+pub struct CalendarInvite {
+    pub account_id: u32,
+    pub uid: String,
+    pub status: InviteStatus,
+    pub read_write: bool,
+    pub common_name: Option<String>,
+    pub summary: Option<String>,
+    pub created: i64,
+}
+
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, Copy, PartialEq, Eq,
+)]
+#[rkyv(compare(PartialEq), derive(Debug))]
+pub enum InviteStatus {
+    #[default]
+    Pending,
+    Accepted,
+    Declined,
+}
+
 #[derive(
     rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
 )]
@@ -71,11 +121,21 @@ pub struct CalendarEvent {
     pub user_properties: Vec<UserProperties>,
     pub flags: u16,
     pub dead_properties: DeadProperty,
+    pub attachments: Vec<CalendarAttachment>,
     pub size: u32,
     pub created: i64,
     pub modified: i64,
 }
 
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+pub struct CalendarAttachment {
+    pub managed_id: String,
+    pub file_name: Option<String>,
+    pub file: FileProperties,
+}
+
 #[derive(
     rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
 )]
@@ -204,9 +264,45 @@ impl Calendar {
             &mut self.preferences[idx]
         }
     }
+
+    /// Replaces the default alert of an account for events with or without
+    /// a time, removing it when `alert` is `None`.
+    pub fn set_default_alert(
+        &mut self,
+        account_id: u32,
+        with_time: bool,
+        alert: Option<ICalendar>,
+    ) {
+        self.default_alerts
+            .retain(|a| a.account_id != account_id || a.with_time != with_time);
+        if let Some(alert) = alert {
+            self.default_alerts.push(DefaultAlert {
+                account_id,
+                id: if with_time { "datetime" } else { "date" }.to_string(),
+                alert,
+                with_time,
+            });
+        }
+    }
+}
+
+/// Returns the bit of a top-level component type in a calendar's supported
+/// component set, or zero for types that calendars cannot be restricted to.
+pub fn component_flag(component_type: &ICalendarComponentType) -> u16 {
+    CALENDAR_COMPONENTS
+        .iter()
+        .position(|typ| typ == component_type)
+        .map_or(0, |pos| 1 << pos)
 }
 
 impl ArchivedCalendar {
+    /// Returns whether the calendar accepts all the given component types,
+    /// calendars without a supported component set accept any of them.
+    pub fn supports_components(&self, components: u16) -> bool {
+        let supported = self.supported_components.to_native();
+        supported == 0 || components & !supported == 0
+    }
+
     pub fn preferences(&self, account_id: u32) -> &ArchivedCalendarPreferences {
         if self.preferences.len() == 1 {
             &self.preferences[0]
@@ -218,4 +314,10 @@ impl ArchivedCalendar {
                 .unwrap()
         }
     }
+
+    pub fn default_alert(&self, account_id: u32, with_time: bool) -> Option<&ArchivedDefaultAlert> {
+        self.default_alerts
+            .iter()
+            .find(|a| a.account_id == account_id && a.with_time == with_time)
+    }
 }
@@ -0,0 +1,313 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use calcard::{
+    common::timezone::Tz,
+    icalendar::{
+        ICalendar, ICalendarComponentType, ICalendarEntry, ICalendarProperty, ICalendarValue,
+    },
+};
+use common::{DavName, DavResources, ScheduleCollection, Server, auth::AccessToken};
+use jmap_proto::types::collection::{Collection, SyncCollection};
+use store::{ahash::AHashSet, write::BatchBuilder};
+use trc::AddContext;
+
+use crate::cache::GroupwareCache;
+
+use super::{
+    CALENDAR_SCHEDULE_INBOX, CALENDAR_SCHEDULE_OUTBOX, Calendar, CalendarEvent, CalendarEventData,
+    CalendarPreferences, auto_accept::auto_process_request, ingest::event_name,
+};
+
+pub const SCHEDULE_INBOX_NAME: &str = "inbox";
+pub const SCHEDULE_OUTBOX_NAME: &str = "outbox";
+
+pub trait CalendarScheduling: Sync + Send {
+    /// Returns the calendar resources of an account, creating its scheduling
+    /// inbox and outbox first if they do not exist yet.
+    fn schedule_resources(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<Arc<DavResources>>> + Send;
+
+    /// Delivers the scheduling message implied by an event stored by the
+    /// account to the inboxes of local calendar users. Organizers send a
+    /// REQUEST to all attendees, attendees send a REPLY to the organizer.
+    /// Returns the number of inboxes the message was delivered to.
+    fn deliver_itip(
+        &self,
+        account_id: u32,
+        ical: &ICalendar,
+    ) -> impl Future<Output = trc::Result<usize>> + Send;
+}
+
+impl CalendarScheduling for Server {
+    async fn schedule_resources(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+    ) -> trc::Result<Arc<DavResources>> {
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?;
+
+        let mut batch = BatchBuilder::new();
+        for (collection, name, flags) in [
+            (
+                ScheduleCollection::Inbox,
+                SCHEDULE_INBOX_NAME,
+                CALENDAR_SCHEDULE_INBOX,
+            ),
+            (
+                ScheduleCollection::Outbox,
+                SCHEDULE_OUTBOX_NAME,
+                CALENDAR_SCHEDULE_OUTBOX,
+            ),
+        ] {
+            if resources.schedule_collection(collection).is_some() {
+                continue;
+            }
+
+            // Avoid clashing with a regular calendar using the same name
+            let mut href_name = name.to_string();
+            let mut suffix = 0;
+            while resources.by_path(&href_name).is_some() {
+                suffix += 1;
+                href_name = format!("{name}-{suffix}");
+            }
+
+            let document_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::Calendar, 1)
+                .await
+                .caused_by(trc::location!())?;
+            Calendar {
+                name: href_name,
+                preferences: vec![CalendarPreferences {
+                    account_id,
+                    name: name.to_string(),
+                    flags,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }
+            .insert(access_token, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?;
+        }
+
+        if batch.is_empty() {
+            Ok(resources)
+        } else {
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+            self.fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+                .await
+                .caused_by(trc::location!())
+        }
+    }
+
+    async fn deliver_itip(&self, account_id: u32, ical: &ICalendar) -> trc::Result<usize> {
+        if !self.core.groupware.scheduling_enabled {
+            return Ok(0);
+        }
+
+        // Obtain the organizer and attendees of the scheduled components
+        let mut organizer = None;
+        let mut attendees = Vec::new();
+        for comp in &ical.components {
+            if !matches!(
+                comp.component_type,
+                ICalendarComponentType::VEvent | ICalendarComponentType::VTodo
+            ) {
+                continue;
+            }
+            for entry in &comp.entries {
+                let address = entry
+                    .values
+                    .first()
+                    .and_then(|value| value.as_text())
+                    .and_then(calendar_user_address);
+                match (&entry.name, address) {
+                    (ICalendarProperty::Organizer, Some(address)) => {
+                        organizer = Some(address);
+                    }
+                    (ICalendarProperty::Attendee, Some(address)) => {
+                        if !attendees.contains(&address) {
+                            attendees.push(address);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let Some(organizer) = organizer else {
+            return Ok(0);
+        };
+
+        // Build the scheduling message
+        let access_token = self
+            .get_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let is_sender = |address: &str| {
+            access_token
+                .emails
+                .iter()
+                .any(|email| email.eq_ignore_ascii_case(address))
+        };
+        let mut message = ical.clone();
+        let (method, recipients) = if is_sender(&organizer) {
+            ("REQUEST", attendees)
+        } else if attendees.iter().any(|address| is_sender(address)) {
+            // Replies only carry the participation status of the sender
+            for comp in &mut message.components {
+                comp.entries.retain(|entry| {
+                    entry.name != ICalendarProperty::Attendee
+                        || entry
+                            .values
+                            .first()
+                            .and_then(|value| value.as_text())
+                            .and_then(calendar_user_address)
+                            .is_some_and(|address| is_sender(&address))
+                });
+            }
+            ("REPLY", vec![organizer])
+        } else {
+            return Ok(0);
+        };
+        if let Some(calendar) = message
+            .components
+            .iter_mut()
+            .find(|comp| comp.component_type == ICalendarComponentType::VCalendar)
+        {
+            calendar
+                .entries
+                .retain(|entry| entry.name != ICalendarProperty::Method);
+            calendar.entries.push(ICalendarEntry {
+                name: ICalendarProperty::Method,
+                params: vec![],
+                values: vec![ICalendarValue::Text(method.to_string())],
+            });
+        }
+        let size = message.to_string().len();
+
+        // Deliver to the inbox of each local recipient
+        let mut delivered_to = AHashSet::new();
+        for address in recipients {
+            if is_sender(&address) {
+                continue;
+            }
+            let Some(recipient_id) = self
+                .core
+                .storage
+                .directory
+                .email_to_id(&address)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            if recipient_id == account_id || !delivered_to.insert(recipient_id) {
+                continue;
+            }
+
+            if let Err(err) = deliver_to_inbox(self, recipient_id, &message, size).await {
+                trc::error!(
+                    err.account_id(recipient_id)
+                        .details("Failed to deliver scheduling message")
+                        .caused_by(trc::location!())
+                );
+                delivered_to.remove(&recipient_id);
+            } else if method == "REQUEST" {
+                // Rooms and resources reply on their own
+                if let Err(err) =
+                    auto_process_request(self, account_id, recipient_id, &address, &message).await
+                {
+                    trc::error!(
+                        err.account_id(recipient_id)
+                            .details("Failed to process scheduling request")
+                            .caused_by(trc::location!())
+                    );
+                }
+            }
+        }
+
+        Ok(delivered_to.len())
+    }
+}
+
+async fn deliver_to_inbox(
+    server: &Server,
+    account_id: u32,
+    message: &ICalendar,
+    size: usize,
+) -> trc::Result<()> {
+    let access_token = server
+        .get_access_token(account_id)
+        .await
+        .caused_by(trc::location!())?;
+    let resources = server
+        .schedule_resources(&access_token, account_id)
+        .await
+        .caused_by(trc::location!())?;
+    let Some(inbox) = resources.schedule_collection(ScheduleCollection::Inbox) else {
+        return Ok(());
+    };
+    let resource_token = server
+        .get_resource_token(&access_token, account_id)
+        .await
+        .caused_by(trc::location!())?;
+    server
+        .has_available_quota(&resource_token, size as u64)
+        .await?;
+
+    let inbox_id = inbox.document_id();
+    let name = event_name(&resources, inbox.path());
+    let href = resources.format_item(&format!("{}/{name}", inbox.path()));
+    let document_id = server
+        .store()
+        .assign_document_ids(account_id, Collection::CalendarEvent, 1)
+        .await
+        .caused_by(trc::location!())?;
+    let mut batch = BatchBuilder::new();
+    CalendarEvent {
+        names: vec![DavName::new(name, inbox_id)],
+        data: CalendarEventData::new(
+            message.clone(),
+            Tz::Floating,
+            server.core.groupware.max_ical_instances,
+        ),
+        size: size as u32,
+        ..Default::default()
+    }
+    .insert(&access_token, account_id, document_id, &mut batch)
+    .caused_by(trc::location!())?;
+    server
+        .commit_batch(batch)
+        .await
+        .caused_by(trc::location!())?;
+
+    trc::event!(
+        Groupware(trc::GroupwareEvent::CalendarCreated),
+        AccountId = account_id,
+        Collection = Collection::CalendarEvent,
+        DocumentId = document_id,
+        Url = href,
+    );
+
+    Ok(())
+}
+
+pub(super) fn calendar_user_address(value: &str) -> Option<String> {
+    value
+        .strip_prefix("mailto:")
+        .or_else(|| value.strip_prefix("MAILTO:"))
+        .map(|address| address.trim().to_lowercase())
+        .filter(|address| address.contains('@'))
+}
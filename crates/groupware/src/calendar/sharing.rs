@@ -0,0 +1,108 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{Server, auth::AccessToken};
+use jmap_proto::types::{
+    acl::Acl,
+    collection::{Collection, SyncCollection},
+};
+use trc::AddContext;
+use utils::map::bitmap::Bitmap;
+
+use crate::cache::GroupwareCache;
+
+use super::{Calendar, CalendarInvite, InviteStatus};
+
+pub const NOTIFICATION_COLLECTION_NAME: &str = "notifications";
+
+pub struct PendingInvite {
+    pub account_id: u32,
+    pub document_id: u32,
+    pub href: String,
+    pub name: String,
+    pub invite: CalendarInvite,
+}
+
+pub trait CalendarSharing: Sync + Send {
+    /// Returns the invitations to calendars of other accounts that were
+    /// shared with the account and have not been accepted or declined yet.
+    fn pending_invites(
+        &self,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<Vec<PendingInvite>>> + Send;
+}
+
+/// Returns the privileges granted to the invitee of a shared calendar.
+pub fn invite_acls(read_write: bool) -> Bitmap<Acl> {
+    let mut acls = Bitmap::from_iter([Acl::Read, Acl::ReadItems]);
+    if read_write {
+        acls.insert(Acl::AddItems);
+        acls.insert(Acl::ModifyItems);
+        acls.insert(Acl::RemoveItems);
+    }
+    acls
+}
+
+impl CalendarSharing for Server {
+    async fn pending_invites(&self, access_token: &AccessToken) -> trc::Result<Vec<PendingInvite>> {
+        let account_id = access_token.primary_id();
+        let mut invites = Vec::new();
+
+        for owner_id in access_token
+            .shared_accounts(Collection::Calendar)
+            .copied()
+            .collect::<Vec<_>>()
+        {
+            let resources = self
+                .fetch_dav_resources(access_token, owner_id, SyncCollection::Calendar)
+                .await
+                .caused_by(trc::location!())?;
+            for document_id in resources.shared_containers(access_token, [Acl::Read], false) {
+                let Some(resource) = resources.container_by_id(document_id) else {
+                    continue;
+                };
+                let Some(calendar_) = self
+                    .get_archive(owner_id, Collection::Calendar, document_id)
+                    .await
+                    .caused_by(trc::location!())?
+                else {
+                    continue;
+                };
+                if !calendar_
+                    .unarchive::<Calendar>()
+                    .caused_by(trc::location!())?
+                    .invites
+                    .iter()
+                    .any(|invite| {
+                        invite.account_id.to_native() == account_id
+                            && invite.status == InviteStatus::Pending
+                    })
+                {
+                    continue;
+                }
+                let mut calendar = calendar_
+                    .deserialize::<Calendar>()
+                    .caused_by(trc::location!())?;
+                let name = calendar.preferences(owner_id).name.clone();
+                if let Some(invite) = calendar
+                    .invites
+                    .drain(..)
+                    .find(|invite| invite.account_id == account_id)
+                {
+                    invites.push(PendingInvite {
+                        account_id: owner_id,
+                        document_id,
+                        href: resources.format_resource(resource),
+                        name,
+                        invite,
+                    });
+                }
+            }
+        }
+
+        Ok(invites)
+    }
+}
@@ -0,0 +1,262 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::icalendar::{
+    ICalendar, ICalendarComponent, ICalendarComponentType, ICalendarEntry, ICalendarProperty,
+    ICalendarValue,
+};
+use store::ahash::AHashSet;
+
+// Some clients store recurrence overrides as separate resources sharing the
+// UID of the recurring event. These are merged into the event they belong to,
+// with each override component tagged with the name of the resource it was
+// uploaded as, so the original resources can be served back on request.
+pub const SPLIT_RESOURCE_PROPERTY: &str = "X-STALWART-RESOURCE";
+
+/// Returns whether all the events, tasks or journal entries in the calendar
+/// object are recurrence overrides.
+pub fn is_override_only(ical: &ICalendar) -> bool {
+    let mut has_components = false;
+    for component in &ical.components {
+        if is_schedulable(component) {
+            if recurrence_id(component).is_none() {
+                return false;
+            }
+            has_components = true;
+        }
+    }
+    has_components
+}
+
+/// Returns whether the calendar object contains components merged from
+/// other resources.
+pub fn has_override_resources(ical: &ICalendar) -> bool {
+    ical.components
+        .iter()
+        .any(|component| resource_tag(component).is_some())
+}
+
+/// Returns whether the calendar object contains components merged from a
+/// resource.
+pub fn is_override_resource(ical: &ICalendar, name: &str) -> bool {
+    ical.components
+        .iter()
+        .any(|component| resource_tag(component) == Some(name))
+}
+
+/// Removes any resource tags, which are never accepted from clients.
+pub fn strip_resource_tags(ical: &mut ICalendar) {
+    for component in &mut ical.components {
+        component.entries.retain(|entry| !is_resource_tag(entry));
+    }
+}
+
+/// Merges the recurrence overrides uploaded as a separate resource into the
+/// calendar object, replacing any overrides previously stored under the same
+/// resource name or for the same instances.
+pub fn merge_override_resource(ical: &mut ICalendar, overrides: ICalendar, name: &str) {
+    remove_override_resource(ical, name);
+
+    // Remove the instances being overridden
+    let new_instances = overrides
+        .components
+        .iter()
+        .filter(|component| is_schedulable(component))
+        .filter_map(recurrence_id)
+        .collect::<Vec<_>>();
+    let tz_ids = ical
+        .components
+        .iter()
+        .filter_map(tz_id)
+        .map(|tz_id| tz_id.to_string())
+        .collect::<AHashSet<_>>();
+    let mut removed = vec![false; ical.components.len()];
+    let mut has_removed = false;
+    for (comp_id, component) in ical.components.iter().enumerate() {
+        if is_schedulable(component)
+            && recurrence_id(component).is_some_and(|rid| new_instances.contains(&rid))
+        {
+            mark_subtree(ical, comp_id, &mut removed);
+            has_removed = true;
+        }
+    }
+    if has_removed {
+        remove_components(ical, &removed);
+    }
+
+    // Append the overrides and any timezones missing from the calendar object
+    if let Some(root) = overrides.components.first() {
+        for &comp_id in &root.component_ids {
+            let component = &overrides.components[comp_id as usize];
+            if tz_id(component).is_some_and(|tz_id| tz_ids.contains(tz_id)) {
+                continue;
+            }
+            let new_id = copy_subtree(&mut ical.components, &overrides.components, comp_id);
+            if is_schedulable(component) {
+                ical.components[new_id as usize]
+                    .entries
+                    .push(ICalendarEntry {
+                        name: ICalendarProperty::Other(SPLIT_RESOURCE_PROPERTY.to_string()),
+                        params: vec![],
+                        values: vec![ICalendarValue::Text(name.to_string())],
+                    });
+            }
+            ical.components[0].component_ids.push(new_id);
+        }
+    }
+}
+
+/// Removes the components merged from a resource, returns whether any
+/// component was removed.
+pub fn remove_override_resource(ical: &mut ICalendar, name: &str) -> bool {
+    let mut removed = vec![false; ical.components.len()];
+    let mut has_removed = false;
+    for (comp_id, component) in ical.components.iter().enumerate() {
+        if resource_tag(component) == Some(name) {
+            mark_subtree(ical, comp_id, &mut removed);
+            has_removed = true;
+        }
+    }
+    if has_removed {
+        remove_components(ical, &removed);
+    }
+    has_removed
+}
+
+/// Builds the calendar object served for a resource, which contains either
+/// the components merged from it or, for the resource holding the recurring
+/// event, all the components not merged from other resources.
+pub fn split_override_resource(ical: &ICalendar, name: &str) -> ICalendar {
+    let Some(root) = ical.components.first() else {
+        return ical.clone();
+    };
+    let is_override = is_override_resource(ical, name);
+    let mut split = ICalendar {
+        components: vec![ICalendarComponent {
+            component_type: root.component_type.clone(),
+            entries: root.entries.clone(),
+            component_ids: vec![],
+        }],
+    };
+
+    for &comp_id in &root.component_ids {
+        let component = &ical.components[comp_id as usize];
+        let is_included = if is_schedulable(component) {
+            match resource_tag(component) {
+                Some(tag) => tag == name,
+                None => !is_override,
+            }
+        } else {
+            component.component_type == ICalendarComponentType::VTimezone || !is_override
+        };
+
+        if is_included {
+            let new_id = copy_subtree(&mut split.components, &ical.components, comp_id);
+            split.components[new_id as usize]
+                .entries
+                .retain(|entry| !is_resource_tag(entry));
+            split.components[0].component_ids.push(new_id);
+        }
+    }
+
+    split
+}
+
+/// Removes the components flagged in `removed` and renumbers the remaining
+/// ones. Children of removed components must be flagged as well.
+pub(crate) fn remove_components(ical: &mut ICalendar, removed: &[bool]) {
+    let mut new_ids = Vec::with_capacity(removed.len());
+    let mut next_id = 0;
+    for is_removed in removed {
+        new_ids.push(next_id);
+        if !is_removed {
+            next_id += 1;
+        }
+    }
+    ical.components = std::mem::take(&mut ical.components)
+        .into_iter()
+        .zip(removed.iter())
+        .filter(|(_, is_removed)| !**is_removed)
+        .map(|(mut component, _)| {
+            component.component_ids = component
+                .component_ids
+                .into_iter()
+                .filter(|id| !removed[*id as usize])
+                .map(|id| new_ids[id as usize])
+                .collect();
+            component
+        })
+        .collect();
+}
+
+fn mark_subtree(ical: &ICalendar, comp_id: usize, removed: &mut [bool]) {
+    removed[comp_id] = true;
+    for child_id in &ical.components[comp_id].component_ids {
+        mark_subtree(ical, *child_id as usize, removed);
+    }
+}
+
+fn copy_subtree(
+    dest: &mut Vec<ICalendarComponent>,
+    source: &[ICalendarComponent],
+    comp_id: u32,
+) -> u32 {
+    let component = &source[comp_id as usize];
+    let new_id = dest.len() as u32;
+    dest.push(ICalendarComponent {
+        component_type: component.component_type.clone(),
+        entries: component.entries.clone(),
+        component_ids: vec![],
+    });
+    for &child_id in &component.component_ids {
+        let new_child_id = copy_subtree(dest, source, child_id);
+        dest[new_id as usize].component_ids.push(new_child_id);
+    }
+    new_id
+}
+
+fn is_schedulable(component: &ICalendarComponent) -> bool {
+    matches!(
+        component.component_type,
+        ICalendarComponentType::VEvent
+            | ICalendarComponentType::VTodo
+            | ICalendarComponentType::VJournal
+    )
+}
+
+fn is_resource_tag(entry: &ICalendarEntry) -> bool {
+    matches!(&entry.name, ICalendarProperty::Other(name) if name.eq_ignore_ascii_case(SPLIT_RESOURCE_PROPERTY))
+}
+
+fn resource_tag(component: &ICalendarComponent) -> Option<&str> {
+    component
+        .entries
+        .iter()
+        .find(|entry| is_resource_tag(entry))
+        .and_then(|entry| entry.values.first())
+        .and_then(|value| value.as_text())
+}
+
+fn recurrence_id(component: &ICalendarComponent) -> Option<&[ICalendarValue]> {
+    component
+        .entries
+        .iter()
+        .find(|entry| entry.name == ICalendarProperty::RecurrenceId)
+        .map(|entry| entry.values.as_slice())
+}
+
+fn tz_id(component: &ICalendarComponent) -> Option<&str> {
+    if component.component_type == ICalendarComponentType::VTimezone {
+        component
+            .entries
+            .iter()
+            .find(|entry| entry.name == ICalendarProperty::Tzid)
+            .and_then(|entry| entry.values.first())
+            .and_then(|value| value.as_text())
+    } else {
+        None
+    }
+}
@@ -6,7 +6,10 @@
 
 use crate::DestroyArchive;
 use common::{Server, auth::AccessToken, storage::index::ObjectIndexBuilder};
-use jmap_proto::types::collection::{Collection, VanishedCollection};
+use jmap_proto::types::{
+    collection::{Collection, VanishedCollection},
+    property::Property,
+};
 use store::write::{Archive, BatchBuilder, now};
 use trc::AddContext;
 
@@ -188,7 +191,8 @@ impl DestroyArchive<Archive<&ArchivedCalendar>> {
                     .with_tenant_id(access_token)
                     .with_current(calendar),
             )
-            .caused_by(trc::location!())?;
+            .caused_by(trc::location!())?
+            .clear(Property::Quota);
         if let Some(delete_path) = delete_path {
             batch.log_vanished_item(VanishedCollection::Calendar, delete_path);
         }
@@ -209,22 +213,25 @@ impl DestroyArchive<Archive<&ArchivedCalendarEvent>> {
         batch: &mut BatchBuilder,
     ) -> trc::Result<()> {
         let event = self.0;
-        if let Some(delete_idx) = event
+        // Events holding recurrence overrides uploaded as separate resources
+        // have more than one name in the same calendar
+        let num_names = event
             .inner
             .names
             .iter()
-            .position(|name| name.parent_id == calendar_id)
-        {
+            .filter(|name| name.parent_id == calendar_id)
+            .count();
+        if num_names > 0 {
             batch
                 .with_account_id(account_id)
                 .with_collection(Collection::CalendarEvent);
 
-            if event.inner.names.len() > 1 {
+            if event.inner.names.len() > num_names {
                 // Unlink calendar id from event
                 let mut new_event = event
                     .deserialize::<CalendarEvent>()
                     .caused_by(trc::location!())?;
-                new_event.names.swap_remove(delete_idx);
+                new_event.names.retain(|name| name.parent_id != calendar_id);
                 batch
                     .update_document(document_id)
                     .custom(
@@ -0,0 +1,427 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::{
+    cache::{DavResourcesRepair, GroupwareCache},
+    calendar::{Calendar, CalendarEvent},
+    contact::{AddressBook, ContactCard},
+    file::FileNode,
+};
+use common::{Server, storage::index::ObjectIndexBuilder};
+use directory::backend::internal::manage::ManageDirectory;
+use jmap_proto::types::collection::{Collection, SyncCollection};
+use store::{
+    ahash::{AHashMap, AHashSet},
+    roaring::RoaringBitmap,
+    write::BatchBuilder,
+};
+use trc::AddContext;
+
+/// Inconsistencies found in the groupware data of an account.
+#[derive(Debug, Default)]
+pub struct ConsistencyReport {
+    pub orphaned_events: Vec<u32>,
+    pub orphaned_cards: Vec<u32>,
+    pub orphaned_files: Vec<u32>,
+    pub dangling_acls: Vec<DanglingAcl>,
+    pub hierarchy: Vec<(SyncCollection, DavResourcesRepair)>,
+}
+
+#[derive(Debug)]
+pub struct DanglingAcl {
+    pub collection: Collection,
+    pub document_id: u32,
+    pub principal_id: u32,
+}
+
+pub trait GroupwareConsistency: Sync + Send {
+    fn check_groupware(
+        &self,
+        account_id: u32,
+        repair: bool,
+    ) -> impl Future<Output = trc::Result<ConsistencyReport>> + Send;
+}
+
+impl GroupwareConsistency for Server {
+    async fn check_groupware(
+        &self,
+        account_id: u32,
+        repair: bool,
+    ) -> trc::Result<ConsistencyReport> {
+        let access_token = self
+            .get_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let mut report = ConsistencyReport::default();
+        let mut principals = PrincipalLookup::default();
+        let mut batch = BatchBuilder::new();
+
+        // Remove grants to deleted principals from calendars
+        let calendar_ids = document_ids(self, account_id, Collection::Calendar).await?;
+        for document_id in &calendar_ids {
+            let Some(archive) = self
+                .get_archive(account_id, Collection::Calendar, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let calendar = archive
+                .to_unarchived::<Calendar>()
+                .caused_by(trc::location!())?;
+            let dangling = principals
+                .missing(
+                    self,
+                    calendar
+                        .inner
+                        .acls
+                        .iter()
+                        .map(|acl| acl.account_id.to_native())
+                        .collect(),
+                )
+                .await?;
+            if dangling.is_empty() {
+                continue;
+            }
+
+            if repair {
+                let mut new_calendar = calendar
+                    .deserialize::<Calendar>()
+                    .caused_by(trc::location!())?;
+                new_calendar
+                    .acls
+                    .retain(|acl| !dangling.contains(&acl.account_id));
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::Calendar)
+                    .update_document(document_id)
+                    .custom(
+                        ObjectIndexBuilder::new()
+                            .with_current(calendar)
+                            .with_changes(new_calendar)
+                            .with_tenant_id(&access_token),
+                    )
+                    .caused_by(trc::location!())?
+                    .commit_point();
+            }
+            report
+                .dangling_acls
+                .extend(dangling.into_iter().map(|principal_id| DanglingAcl {
+                    collection: Collection::Calendar,
+                    document_id,
+                    principal_id,
+                }));
+        }
+
+        // Unlink events from deleted calendars
+        for document_id in &document_ids(self, account_id, Collection::CalendarEvent).await? {
+            let Some(archive) = self
+                .get_archive(account_id, Collection::CalendarEvent, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let event = archive
+                .to_unarchived::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+            if !event.inner.names.is_empty()
+                && event
+                    .inner
+                    .names
+                    .iter()
+                    .all(|name| calendar_ids.contains(name.parent_id.to_native()))
+            {
+                continue;
+            }
+
+            report.orphaned_events.push(document_id);
+            if repair {
+                let mut new_event = event
+                    .deserialize::<CalendarEvent>()
+                    .caused_by(trc::location!())?;
+                new_event
+                    .names
+                    .retain(|name| calendar_ids.contains(name.parent_id));
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::CalendarEvent);
+                if !new_event.names.is_empty() {
+                    batch
+                        .update_document(document_id)
+                        .custom(
+                            ObjectIndexBuilder::new()
+                                .with_current(event)
+                                .with_changes(new_event)
+                                .with_tenant_id(&access_token),
+                        )
+                        .caused_by(trc::location!())?;
+                } else {
+                    batch
+                        .delete_document(document_id)
+                        .custom(
+                            ObjectIndexBuilder::<_, ()>::new()
+                                .with_current(event)
+                                .with_tenant_id(&access_token),
+                        )
+                        .caused_by(trc::location!())?;
+                }
+                batch.commit_point();
+            }
+        }
+
+        // Remove grants to deleted principals from address books
+        let addressbook_ids = document_ids(self, account_id, Collection::AddressBook).await?;
+        for document_id in &addressbook_ids {
+            let Some(archive) = self
+                .get_archive(account_id, Collection::AddressBook, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let book = archive
+                .to_unarchived::<AddressBook>()
+                .caused_by(trc::location!())?;
+            let dangling = principals
+                .missing(
+                    self,
+                    book.inner
+                        .acls
+                        .iter()
+                        .map(|acl| acl.account_id.to_native())
+                        .collect(),
+                )
+                .await?;
+            if dangling.is_empty() {
+                continue;
+            }
+
+            if repair {
+                let mut new_book = book
+                    .deserialize::<AddressBook>()
+                    .caused_by(trc::location!())?;
+                new_book
+                    .acls
+                    .retain(|acl| !dangling.contains(&acl.account_id));
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::AddressBook)
+                    .update_document(document_id)
+                    .custom(
+                        ObjectIndexBuilder::new()
+                            .with_current(book)
+                            .with_changes(new_book)
+                            .with_tenant_id(&access_token),
+                    )
+                    .caused_by(trc::location!())?
+                    .commit_point();
+            }
+            report
+                .dangling_acls
+                .extend(dangling.into_iter().map(|principal_id| DanglingAcl {
+                    collection: Collection::AddressBook,
+                    document_id,
+                    principal_id,
+                }));
+        }
+
+        // Unlink cards from deleted address books
+        for document_id in &document_ids(self, account_id, Collection::ContactCard).await? {
+            let Some(archive) = self
+                .get_archive(account_id, Collection::ContactCard, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let card = archive
+                .to_unarchived::<ContactCard>()
+                .caused_by(trc::location!())?;
+            if !card.inner.names.is_empty()
+                && card
+                    .inner
+                    .names
+                    .iter()
+                    .all(|name| addressbook_ids.contains(name.parent_id.to_native()))
+            {
+                continue;
+            }
+
+            report.orphaned_cards.push(document_id);
+            if repair {
+                let mut new_card = card
+                    .deserialize::<ContactCard>()
+                    .caused_by(trc::location!())?;
+                new_card
+                    .names
+                    .retain(|name| addressbook_ids.contains(name.parent_id));
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::ContactCard);
+                if !new_card.names.is_empty() {
+                    batch
+                        .update_document(document_id)
+                        .custom(
+                            ObjectIndexBuilder::new()
+                                .with_current(card)
+                                .with_changes(new_card)
+                                .with_tenant_id(&access_token),
+                        )
+                        .caused_by(trc::location!())?;
+                } else {
+                    batch
+                        .delete_document(document_id)
+                        .custom(
+                            ObjectIndexBuilder::<_, ()>::new()
+                                .with_current(card)
+                                .with_tenant_id(&access_token),
+                        )
+                        .caused_by(trc::location!())?;
+                }
+                batch.commit_point();
+            }
+        }
+
+        // Files whose parent is missing or is not a folder are moved to the root
+        let mut nodes = Vec::new();
+        let mut folder_ids = RoaringBitmap::new();
+        let mut root_names = AHashSet::new();
+        for document_id in &document_ids(self, account_id, Collection::FileNode).await? {
+            if let Some(archive) = self
+                .get_archive(account_id, Collection::FileNode, document_id)
+                .await
+                .caused_by(trc::location!())?
+            {
+                let node = archive
+                    .unarchive::<FileNode>()
+                    .caused_by(trc::location!())?;
+                if node.file.is_none() {
+                    folder_ids.insert(document_id);
+                }
+                if node.parent_id == 0 {
+                    root_names.insert(node.name.to_string());
+                }
+                nodes.push((document_id, archive));
+            }
+        }
+        for (document_id, archive) in nodes {
+            let node = archive
+                .to_unarchived::<FileNode>()
+                .caused_by(trc::location!())?;
+            let parent_id = node.inner.parent_id.to_native();
+            let is_orphan = parent_id > 0
+                && (parent_id - 1 == document_id || !folder_ids.contains(parent_id - 1));
+            let dangling = principals
+                .missing(
+                    self,
+                    node.inner
+                        .acls
+                        .iter()
+                        .map(|acl| acl.account_id.to_native())
+                        .collect(),
+                )
+                .await?;
+            if !is_orphan && dangling.is_empty() {
+                continue;
+            }
+
+            if repair {
+                let mut new_node = node.deserialize::<FileNode>().caused_by(trc::location!())?;
+                new_node
+                    .acls
+                    .retain(|acl| !dangling.contains(&acl.account_id));
+                if is_orphan {
+                    new_node.parent_id = 0;
+                    if !root_names.insert(new_node.name.clone()) {
+                        new_node.name = format!("{}-{document_id}", new_node.name);
+                        root_names.insert(new_node.name.clone());
+                    }
+                }
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(Collection::FileNode)
+                    .update_document(document_id)
+                    .custom(
+                        ObjectIndexBuilder::new()
+                            .with_current(node)
+                            .with_changes(new_node)
+                            .with_tenant_id(&access_token),
+                    )
+                    .caused_by(trc::location!())?
+                    .commit_point();
+            }
+            if is_orphan {
+                report.orphaned_files.push(document_id);
+            }
+            report
+                .dangling_acls
+                .extend(dangling.into_iter().map(|principal_id| DanglingAcl {
+                    collection: Collection::FileNode,
+                    document_id,
+                    principal_id,
+                }));
+        }
+
+        if !batch.is_empty() {
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+        }
+
+        // Compare the cached hierarchies against primary storage
+        for collection in [
+            SyncCollection::Calendar,
+            SyncCollection::AddressBook,
+            SyncCollection::FileNode,
+        ] {
+            report.hierarchy.push((
+                collection,
+                self.rebuild_dav_resources(&access_token, account_id, collection)
+                    .await?,
+            ));
+        }
+
+        Ok(report)
+    }
+}
+
+#[derive(Default)]
+struct PrincipalLookup(AHashMap<u32, bool>);
+
+impl PrincipalLookup {
+    async fn missing(&mut self, server: &Server, ids: Vec<u32>) -> trc::Result<Vec<u32>> {
+        let mut missing = Vec::new();
+        for id in ids {
+            let exists = if let Some(exists) = self.0.get(&id) {
+                *exists
+            } else {
+                let exists = server
+                    .store()
+                    .get_principal_name(id)
+                    .await
+                    .caused_by(trc::location!())?
+                    .is_some();
+                self.0.insert(id, exists);
+                exists
+            };
+            if !exists {
+                missing.push(id);
+            }
+        }
+        Ok(missing)
+    }
+}
+
+async fn document_ids(
+    server: &Server,
+    account_id: u32,
+    collection: Collection,
+) -> trc::Result<RoaringBitmap> {
+    server
+        .get_document_ids(account_id, collection)
+        .await
+        .caused_by(trc::location!())
+        .map(|ids| ids.unwrap_or_default())
+}
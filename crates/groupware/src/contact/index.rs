@@ -9,7 +9,9 @@ use calcard::vcard::VCardProperty;
 use common::storage::index::{
     IndexItem, IndexValue, IndexableAndSerializableObject, IndexableObject,
 };
-use common::{IDX_EMAIL, IDX_UID};
+use common::{
+    IDX_CARD_EMAIL, IDX_CARD_FN, IDX_CARD_ORG, IDX_CARD_TEL, IDX_CARD_TEXT, IDX_EMAIL, IDX_UID,
+};
 use jmap_proto::types::{collection::SyncCollection, value::AclGrant};
 use std::collections::HashSet;
 use utils::sanitize_email;
@@ -29,6 +31,9 @@ impl IndexableObject for AddressBook {
             IndexValue::LogContainer {
                 sync_collection: SyncCollection::AddressBook.into(),
             },
+            IndexValue::LogContainerShard {
+                sync_collection: SyncCollection::AddressBook.into(),
+            },
         ]
         .into_iter()
     }
@@ -54,6 +59,9 @@ impl IndexableObject for &ArchivedAddressBook {
             IndexValue::LogContainer {
                 sync_collection: SyncCollection::AddressBook.into(),
             },
+            IndexValue::LogContainerShard {
+                sync_collection: SyncCollection::AddressBook.into(),
+            },
         ]
         .into_iter()
     }
@@ -91,8 +99,13 @@ impl IndexableObject for ContactCard {
                 sync_collection: SyncCollection::AddressBook.into(),
                 prefix: None,
             },
+            IndexValue::LogItemShard {
+                sync_collection: SyncCollection::AddressBook.into(),
+                ids: self.names.iter().map(|name| name.parent_id).collect(),
+            },
         ]
         .into_iter()
+        .chain(text_index_values(self.text_values()))
     }
 }
 
@@ -122,8 +135,17 @@ impl IndexableObject for &ArchivedContactCard {
                 sync_collection: SyncCollection::AddressBook.into(),
                 prefix: None,
             },
+            IndexValue::LogItemShard {
+                sync_collection: SyncCollection::AddressBook.into(),
+                ids: self
+                    .names
+                    .iter()
+                    .map(|name| name.parent_id.to_native())
+                    .collect(),
+            },
         ]
         .into_iter()
+        .chain(text_index_values(self.text_values()))
     }
 }
 
@@ -141,6 +163,19 @@ impl ContactCard {
                 .filter_map(|v| v.as_text().and_then(sanitize_email))
         })
     }
+
+    fn text_values(&self) -> [(u8, HashSet<String>); 4] {
+        TEXT_INDEX_PROPERTIES.map(|(field, property)| {
+            (
+                field,
+                self.card
+                    .properties(&property)
+                    .flat_map(|e| e.values.iter().filter_map(|v| v.as_text()))
+                    .map(|text| text.to_lowercase())
+                    .collect(),
+            )
+        })
+    }
 }
 
 impl ArchivedContactCard {
@@ -151,4 +186,63 @@ impl ArchivedContactCard {
                 .filter_map(|v| v.as_text().and_then(sanitize_email))
         })
     }
+
+    fn text_values(&self) -> [(u8, HashSet<String>); 4] {
+        TEXT_INDEX_PROPERTIES.map(|(field, property)| {
+            (
+                field,
+                self.card
+                    .properties(&property)
+                    .flat_map(|e| e.values.iter().filter_map(|v| v.as_text()))
+                    .map(|text| text.to_lowercase())
+                    .collect(),
+            )
+        })
+    }
+}
+
+// Properties whose lowercased values are indexed for addressbook-query
+// text-match filters
+const TEXT_INDEX_PROPERTIES: [(u8, VCardProperty); 4] = [
+    (IDX_CARD_FN, VCardProperty::Fn),
+    (IDX_CARD_EMAIL, VCardProperty::Email),
+    (IDX_CARD_TEL, VCardProperty::Tel),
+    (IDX_CARD_ORG, VCardProperty::Org),
+];
+const MAX_TEXT_INDEX_LEN: usize = 255;
+pub const CARD_TEXT_INDEX_VERSION: u8 = 1;
+
+// Cards holding values too long to be indexed are not marked as indexed,
+// so text-match filters fall back to scanning them.
+fn text_index_values<'x>(
+    values: [(u8, HashSet<String>); 4],
+) -> impl Iterator<Item = IndexValue<'x>> {
+    let is_indexed = values
+        .iter()
+        .all(|(_, values)| values.iter().all(|value| value.len() <= MAX_TEXT_INDEX_LEN));
+
+    [IndexValue::Index {
+        field: IDX_CARD_TEXT,
+        value: if is_indexed {
+            IndexItem::Vec(vec![CARD_TEXT_INDEX_VERSION])
+        } else {
+            IndexItem::None
+        },
+    }]
+    .into_iter()
+    .chain(
+        values
+            .into_iter()
+            .map(move |(field, values)| IndexValue::IndexList {
+                field,
+                value: if is_indexed {
+                    values
+                        .into_iter()
+                        .map(|value| IndexItem::Vec(value.into_bytes()))
+                        .collect()
+                } else {
+                    vec![]
+                },
+            }),
+    )
 }
@@ -0,0 +1,191 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::{Entry, Parser};
+use common::{DavName, DavResources, IDX_UID, Server, auth::AccessToken};
+use jmap_proto::types::collection::{Collection, SyncCollection};
+use store::{query::Filter, write::BatchBuilder};
+use trc::AddContext;
+
+use crate::{IngestResult, cache::GroupwareCache, member_name};
+
+use super::ContactCard;
+
+pub trait ContactIngest: Sync + Send {
+    /// Stores a vCard received outside of CardDAV, such as an email attachment,
+    /// in the named address book or the account's default address book.
+    /// Cards sharing the UID of an existing card replace it.
+    fn ingest_contact_data(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        addressbook_name: Option<&str>,
+        vcard_data: &str,
+    ) -> impl Future<Output = trc::Result<IngestResult>> + Send;
+}
+
+impl ContactIngest for Server {
+    async fn ingest_contact_data(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        addressbook_name: Option<&str>,
+        vcard_data: &str,
+    ) -> trc::Result<IngestResult> {
+        if vcard_data.len() > self.core.groupware.max_vcard_size {
+            return Ok(IngestResult::Skipped("vCard too large"));
+        }
+        let vcard = match Parser::new(vcard_data).entry() {
+            Entry::VCard(vcard) => vcard,
+            _ => return Ok(IngestResult::Skipped("invalid vCard")),
+        };
+
+        // Locate the target address book
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::AddressBook)
+            .await
+            .caused_by(trc::location!())?;
+        let addressbook_id = match addressbook_name {
+            Some(name) => resources
+                .by_path(name)
+                .filter(|book| book.is_container())
+                .map(|book| book.document_id()),
+            None => default_addressbook(
+                &resources,
+                self.core.groupware.default_addressbook_name.as_deref(),
+            ),
+        };
+        let Some(addressbook_id) = addressbook_id else {
+            return Ok(IngestResult::Skipped("address book not found"));
+        };
+
+        // Replace any card with the same UID in the address book
+        let existing = if let Some(uid) = vcard.uid() {
+            let uid_hits = self
+                .store()
+                .filter(
+                    account_id,
+                    Collection::ContactCard,
+                    vec![Filter::eq(IDX_UID, uid.as_bytes().to_vec())],
+                )
+                .await
+                .caused_by(trc::location!())?
+                .results;
+            resources
+                .children(addressbook_id)
+                .find(|child| uid_hits.contains(child.document_id()))
+        } else {
+            None
+        };
+        let resource_token = self.get_resource_token(access_token, account_id).await?;
+        let mut batch = BatchBuilder::new();
+
+        if let Some(existing) = existing {
+            let document_id = existing.document_id();
+            let Some(card_) = self
+                .get_archive(account_id, Collection::ContactCard, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                return Ok(IngestResult::Skipped("card not found"));
+            };
+            let card = card_
+                .to_unarchived::<ContactCard>()
+                .caused_by(trc::location!())?;
+            let mut new_card = card
+                .deserialize::<ContactCard>()
+                .caused_by(trc::location!())?;
+            let extra_bytes = (vcard_data.len() as u64).saturating_sub(new_card.size as u64);
+            if extra_bytes > 0 {
+                self.has_available_quota(&resource_token, extra_bytes)
+                    .await?;
+            }
+            new_card.size = vcard_data.len() as u32;
+            new_card.card = vcard;
+            new_card
+                .update(access_token, card, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+            trc::event!(
+                Groupware(trc::GroupwareEvent::ContactUpdated),
+                AccountId = account_id,
+                Collection = Collection::ContactCard,
+                DocumentId = document_id,
+                Url = resources.format_resource(existing),
+            );
+
+            Ok(IngestResult::Updated(document_id))
+        } else {
+            // Validate item limit and quota
+            if let Some(max_cards) = self
+                .core
+                .groupware
+                .item_limits(
+                    account_id,
+                    resource_token.tenant.as_ref().map(|tenant| tenant.id),
+                )
+                .cards_per_addressbook
+            {
+                if resources.children(addressbook_id).count() >= max_cards {
+                    return Ok(IngestResult::Skipped("address book is full"));
+                }
+            }
+            self.has_available_quota(&resource_token, vcard_data.len() as u64)
+                .await?;
+
+            let document_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::ContactCard, 1)
+                .await
+                .caused_by(trc::location!())?;
+            let addressbook_path = resources
+                .container_by_id(addressbook_id)
+                .map(|book| book.path().to_string())
+                .unwrap_or_default();
+            let name = card_name(&resources, &addressbook_path);
+            let href = resources.format_item(&format!("{addressbook_path}/{name}"));
+            ContactCard {
+                names: vec![DavName::new(name, addressbook_id)],
+                card: vcard,
+                size: vcard_data.len() as u32,
+                ..Default::default()
+            }
+            .insert(access_token, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?;
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+            trc::event!(
+                Groupware(trc::GroupwareEvent::ContactCreated),
+                AccountId = account_id,
+                Collection = Collection::ContactCard,
+                DocumentId = document_id,
+                Url = href,
+            );
+
+            Ok(IngestResult::Created(document_id))
+        }
+    }
+}
+
+fn default_addressbook(resources: &DavResources, name: Option<&str>) -> Option<u32> {
+    name.and_then(|name| resources.by_path(name))
+        .filter(|book| book.is_container())
+        .map(|book| book.document_id())
+        .or_else(|| {
+            // Fall back to the oldest address book when the default one was renamed or removed
+            resources
+                .resources
+                .iter()
+                .filter(|resource| resource.is_container())
+                .map(|resource| resource.document_id)
+                .min()
+        })
+}
+
+fn card_name(resources: &DavResources, addressbook_path: &str) -> String {
+    member_name(resources, addressbook_path, ".vcf")
+}
@@ -5,7 +5,9 @@
  */
 
 pub mod index;
+pub mod ingest;
 pub mod storage;
+pub mod sync;
 
 use calcard::vcard::VCard;
 use common::DavName;
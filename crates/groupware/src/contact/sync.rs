@@ -0,0 +1,290 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::{Entry, Parser, vcard::VCard};
+use common::{DavName, Server, config::groupware::DirectoryAddressBook};
+use directory::{Principal, QueryBy, Type, backend::internal::manage::ManageDirectory};
+use jmap_proto::types::{
+    acl::Acl,
+    collection::{Collection, SyncCollection},
+    value::AclGrant,
+};
+use store::{Rows, Value, ahash::AHashMap, write::BatchBuilder};
+use trc::AddContext;
+use utils::map::bitmap::Bitmap;
+
+use crate::{DestroyArchive, cache::GroupwareCache};
+
+use super::{AddressBook, ContactCard};
+
+pub trait DirectoryAddressBookSync: Sync + Send {
+    /// Rebuilds a directory-synchronized address book from the principals
+    /// currently listed in the directory. Cards that were edited locally are
+    /// overwritten and cards of principals no longer listed are removed.
+    fn sync_directory_addressbook(
+        &self,
+        book: &DirectoryAddressBook,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl DirectoryAddressBookSync for Server {
+    async fn sync_directory_addressbook(&self, book: &DirectoryAddressBook) -> trc::Result<()> {
+        let Some(account_id) = self
+            .store()
+            .get_principal_id(&book.account)
+            .await
+            .caused_by(trc::location!())?
+        else {
+            return Err(trc::ResourceEvent::NotFound
+                .into_err()
+                .details("Address book owner not found")
+                .id(book.account.clone()));
+        };
+        let access_token = self
+            .get_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?;
+
+        // Obtain the listed principals
+        let mut members = book.members.clone();
+        if let Some(query) = &book.members_query {
+            let rows = self
+                .get_data_store(&query.store, 0)
+                .sql_query::<Rows>(&query.query, vec![])
+                .await
+                .caused_by(trc::location!())?;
+            members.extend(rows.rows.into_iter().filter_map(|row| {
+                match row.values.into_iter().next()? {
+                    Value::Text(name) => Some(name.into_owned()),
+                    _ => None,
+                }
+            }));
+        }
+        let directory = self.get_directory_or_default(&book.directory, 0);
+        let mut cards: AHashMap<String, VCard> = AHashMap::with_capacity(members.len());
+        for member in &members {
+            let principal = if member.contains('@') {
+                match directory
+                    .email_to_id(member)
+                    .await
+                    .caused_by(trc::location!())?
+                {
+                    Some(id) => directory
+                        .query(QueryBy::Id(id), false)
+                        .await
+                        .caused_by(trc::location!())?,
+                    None => None,
+                }
+            } else {
+                directory
+                    .query(QueryBy::Name(member), false)
+                    .await
+                    .caused_by(trc::location!())?
+            };
+
+            if let Some((uid, card)) = principal.as_ref().and_then(principal_to_vcard) {
+                cards.insert(uid, card);
+            } else {
+                trc::event!(
+                    Resource(trc::ResourceEvent::NotFound),
+                    AccountId = account_id,
+                    Id = member.clone(),
+                    Details = "Directory principal not found",
+                );
+            }
+        }
+
+        // Resolve the accounts the address book is shared with
+        let mut acls = Vec::with_capacity(book.share_with.len());
+        for name in &book.share_with {
+            if let Some(grantee_id) = self
+                .store()
+                .get_principal_id(name)
+                .await
+                .caused_by(trc::location!())?
+            {
+                acls.push(AclGrant {
+                    account_id: grantee_id,
+                    grants: Bitmap::from_iter([Acl::Read, Acl::ReadItems]),
+                });
+            }
+        }
+
+        // Create or update the address book
+        let resources = self
+            .fetch_dav_resources(&access_token, account_id, SyncCollection::AddressBook)
+            .await
+            .caused_by(trc::location!())?;
+        let mut batch = BatchBuilder::new();
+        let addressbook_id = if let Some(resource) = resources
+            .by_path(&book.name)
+            .filter(|resource| resource.is_container())
+        {
+            let addressbook_id = resource.document_id();
+            let book_ = self
+                .get_archive(account_id, Collection::AddressBook, addressbook_id)
+                .await
+                .caused_by(trc::location!())?
+                .ok_or_else(|| trc::StoreEvent::NotFound.caused_by(trc::location!()))?;
+            let current_book = book_
+                .to_unarchived::<AddressBook>()
+                .caused_by(trc::location!())?;
+            let mut new_book = current_book
+                .deserialize::<AddressBook>()
+                .caused_by(trc::location!())?;
+            if new_book.acls != acls || new_book.display_name != book.display_name {
+                self.refresh_acls(&acls, Some(&new_book.acls)).await;
+                new_book.acls = acls;
+                new_book.display_name = book.display_name.clone();
+                new_book
+                    .update(
+                        &access_token,
+                        current_book,
+                        account_id,
+                        addressbook_id,
+                        &mut batch,
+                    )
+                    .caused_by(trc::location!())?;
+            }
+            addressbook_id
+        } else {
+            let addressbook_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::AddressBook, 1)
+                .await
+                .caused_by(trc::location!())?;
+            self.refresh_acls(&acls, None).await;
+            AddressBook {
+                name: book.name.clone(),
+                display_name: book.display_name.clone(),
+                acls,
+                ..Default::default()
+            }
+            .insert(&access_token, account_id, addressbook_id, &mut batch)
+            .caused_by(trc::location!())?;
+            addressbook_id
+        };
+
+        // Update or remove the existing cards
+        for resource in resources.children(addressbook_id) {
+            let document_id = resource.document_id();
+            let Some(card_) = self
+                .get_archive(account_id, Collection::ContactCard, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let card = card_
+                .to_unarchived::<ContactCard>()
+                .caused_by(trc::location!())?;
+            let new_vcard = card.inner.card.uid().and_then(|uid| cards.remove(uid));
+
+            if let Some(new_vcard) = new_vcard {
+                let mut new_card = card
+                    .deserialize::<ContactCard>()
+                    .caused_by(trc::location!())?;
+                if new_card.card != new_vcard {
+                    new_card.size = new_vcard.to_string().len() as u32;
+                    new_card.card = new_vcard;
+                    new_card
+                        .update(&access_token, card, account_id, document_id, &mut batch)
+                        .caused_by(trc::location!())?;
+                }
+            } else {
+                DestroyArchive(card)
+                    .delete(
+                        &access_token,
+                        account_id,
+                        document_id,
+                        addressbook_id,
+                        resources.format_resource(resource).into(),
+                        &mut batch,
+                    )
+                    .caused_by(trc::location!())?;
+            }
+        }
+
+        // Add cards for new principals
+        if !cards.is_empty() {
+            let mut document_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::ContactCard, cards.len() as u64)
+                .await
+                .caused_by(trc::location!())?;
+            for (uid, card) in cards {
+                let name = format!(
+                    "{}.vcf",
+                    uid.rsplit_once(':').map_or(uid.as_str(), |(_, name)| name)
+                );
+                ContactCard {
+                    names: vec![DavName::new(name, addressbook_id)],
+                    size: card.to_string().len() as u32,
+                    card,
+                    ..Default::default()
+                }
+                .insert(&access_token, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+                document_id = document_id.saturating_sub(1);
+            }
+        }
+
+        if !batch.is_empty() {
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds the vCard describing a directory principal, returning it along with
+/// its UID. Principals that are not people, groups or resources are skipped.
+pub fn principal_to_vcard(principal: &Principal) -> Option<(String, VCard)> {
+    let kind = match principal.typ {
+        Type::Individual => "individual",
+        Type::Group | Type::List => "group",
+        Type::Resource => "device",
+        Type::Location => "location",
+        _ => return None,
+    };
+    let uid = format!("urn:stalwart:directory:{}", principal.id);
+    let mut vcard = format!(
+        "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{uid}\r\nKIND:{kind}\r\nFN:{}\r\nNICKNAME:{}\r\n",
+        escape_text(principal.description.as_deref().unwrap_or(&principal.name)),
+        escape_text(&principal.name)
+    );
+    for (pos, email) in principal.emails.iter().enumerate() {
+        if pos == 0 {
+            vcard.push_str("EMAIL;PREF=1:");
+        } else {
+            vcard.push_str("EMAIL:");
+        }
+        vcard.push_str(&escape_text(email));
+        vcard.push_str("\r\n");
+    }
+    vcard.push_str("END:VCARD\r\n");
+
+    match Parser::new(&vcard).entry() {
+        Entry::VCard(vcard) => Some((uid, vcard)),
+        _ => None,
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' | ',' | ';' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
@@ -29,6 +29,10 @@ impl IndexableObject for FileNode {
             }]);
         }
 
+        values.extend(self.versions.iter().map(|version| IndexValue::Blob {
+            value: version.file.blob_hash.clone(),
+        }));
+
         values.into_iter()
     }
 }
@@ -59,6 +63,10 @@ impl IndexableObject for &ArchivedFileNode {
             }]);
         }
 
+        values.extend(self.versions.iter().map(|version| IndexValue::Blob {
+            value: (&version.file.blob_hash).into(),
+        }));
+
         values.into_iter()
     }
 }
@@ -79,6 +87,11 @@ impl NodeSize for ArchivedFileNode {
             + self.display_name.as_ref().map_or(0, |n| n.len() as u32)
             + self.name.len() as u32
             + self.file.as_ref().map_or(0, |f| u32::from(f.size))
+            + self
+                .versions
+                .iter()
+                .map(|v| u32::from(v.file.size))
+                .sum::<u32>()
     }
 }
 
@@ -88,5 +101,6 @@ impl NodeSize for FileNode {
             + self.display_name.as_ref().map_or(0, |n| n.len() as u32)
             + self.name.len() as u32
             + self.file.as_ref().map_or(0, |f| f.size)
+            + self.versions.iter().map(|v| v.file.size).sum::<u32>()
     }
 }
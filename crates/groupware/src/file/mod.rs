@@ -6,7 +6,9 @@
 
 pub mod index;
 pub mod storage;
+pub mod trash;
 
+use common::DavName;
 use dav_proto::schema::request::DeadProperty;
 use jmap_proto::types::value::AclGrant;
 use utils::BlobHash;
@@ -24,6 +26,9 @@ pub struct FileNode {
     pub modified: i64,
     pub dead_properties: DeadProperty,
     pub acls: Vec<AclGrant>,
+    pub bindings: Vec<DavName>,
+    pub versions: Vec<FileVersion>,
+    pub trash: Option<FileTrashInfo>,
 }
 
 #[derive(
@@ -36,3 +41,93 @@ pub struct FileProperties {
     pub media_type: Option<String>,
     pub executable: bool,
 }
+
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+#[rkyv(derive(Debug))]
+pub struct FileVersion {
+    pub version: u32,
+    pub file: FileProperties,
+    pub modified: i64,
+}
+
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+#[rkyv(derive(Debug))]
+pub struct FileTrashInfo {
+    pub path: String,
+    pub deleted: i64,
+}
+
+impl FileNode {
+    /// Returns whether the node is bound under the given name and parent,
+    /// either through its primary binding or an additional one.
+    pub fn is_bound_to(&self, parent_id: u32, name: &str) -> bool {
+        (self.parent_id == parent_id && self.name == name)
+            || self
+                .bindings
+                .iter()
+                .any(|binding| binding.parent_id == parent_id && binding.name == name)
+    }
+
+    /// Removes a binding from the node, promoting an additional binding when
+    /// the primary one is removed. Returns false if the node has no bindings
+    /// left and should be destroyed instead.
+    pub fn unbind(&mut self, parent_id: u32, name: &str) -> bool {
+        if self.parent_id == parent_id && self.name == name {
+            if self.bindings.is_empty() {
+                return false;
+            }
+            let binding = self.bindings.remove(0);
+            self.parent_id = binding.parent_id;
+            self.name = binding.name;
+        } else {
+            self.bindings
+                .retain(|binding| binding.parent_id != parent_id || binding.name != name);
+        }
+        true
+    }
+
+    /// Moves one of the node's bindings to a new name and parent.
+    pub fn rebind(&mut self, parent_id: u32, name: &str, new_parent_id: u32, new_name: String) {
+        if self.unbind(parent_id, name) {
+            self.bindings.push(DavName::new(new_name, new_parent_id));
+        } else {
+            self.parent_id = new_parent_id;
+            self.name = new_name;
+        }
+    }
+
+    /// Returns the version number of the current contents of the file.
+    pub fn current_version(&self) -> u32 {
+        self.versions
+            .last()
+            .map_or(1, |version| version.version + 1)
+    }
+
+    /// Keeps the current contents as the most recent prior version, dropping
+    /// the oldest versions beyond the given limit.
+    pub fn push_version(&mut self, max_versions: usize) {
+        if let Some(file) = self.file.clone().filter(|_| max_versions > 0) {
+            self.versions.push(FileVersion {
+                version: self.current_version(),
+                file,
+                modified: self.modified,
+            });
+        }
+        if self.versions.len() > max_versions {
+            self.versions.drain(..self.versions.len() - max_versions);
+        }
+    }
+}
+
+impl ArchivedFileNode {
+    /// Returns the version number of the current contents of the file.
+    pub fn current_version(&self) -> u32 {
+        self.versions
+            .last()
+            .map_or(1, |version| version.version.to_native() + 1)
+    }
+}
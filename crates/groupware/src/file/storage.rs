@@ -5,7 +5,10 @@
  */
 
 use common::{Server, auth::AccessToken, storage::index::ObjectIndexBuilder};
-use jmap_proto::types::collection::{Collection, VanishedCollection};
+use jmap_proto::types::{
+    collection::{Collection, VanishedCollection},
+    property::Property,
+};
 use store::write::{Archive, BatchBuilder, now};
 use trc::AddContext;
 
@@ -83,6 +86,7 @@ impl DestroyArchive<Archive<&ArchivedFileNode>> {
                     .with_current(self.0)
                     .with_tenant_id(access_token),
             )?
+            .clear(Property::Quota)
             .log_vanished_item(VanishedCollection::FileNode, path)
             .commit_point();
         Ok(())
@@ -119,6 +123,7 @@ impl DestroyArchive<Vec<u32>> {
                             ),
                     )
                     .caused_by(trc::location!())?
+                    .clear(Property::Quota)
                     .commit_point();
             }
         }
@@ -0,0 +1,177 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::time::Duration;
+
+use common::{DavResources, Server, auth::AccessToken};
+use jmap_proto::types::collection::{Collection, SyncCollection, VanishedCollection};
+use store::write::{BatchBuilder, now};
+use trc::AddContext;
+
+use crate::{DestroyArchive, cache::GroupwareCache};
+
+use super::{FileNode, FileTrashInfo};
+
+pub const TRASH_FOLDER_NAME: &str = ".trash";
+
+pub trait FileTrash: Sync + Send {
+    /// Moves a file or folder, including its descendants, to the trash
+    /// folder of the account, creating the folder first if it does not
+    /// exist yet. The original path is kept so the item can be restored.
+    fn move_to_trash(
+        &self,
+        access_token: &AccessToken,
+        resources: &DavResources,
+        account_id: u32,
+        document_id: u32,
+        path: &str,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+
+    /// Permanently deletes the items that were moved to the trash folder
+    /// longer than the given period ago.
+    fn purge_file_trash(
+        &self,
+        account_id: u32,
+        period: Duration,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+/// Returns whether a path is the trash folder or one of its descendants.
+pub fn is_trash_path(path: &str) -> bool {
+    path.strip_prefix(TRASH_FOLDER_NAME)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+impl FileTrash for Server {
+    async fn move_to_trash(
+        &self,
+        access_token: &AccessToken,
+        resources: &DavResources,
+        account_id: u32,
+        document_id: u32,
+        path: &str,
+    ) -> trc::Result<()> {
+        let Some(node_) = self
+            .get_archive(account_id, Collection::FileNode, document_id)
+            .await
+            .caused_by(trc::location!())?
+        else {
+            return Ok(());
+        };
+        let node = node_
+            .to_unarchived::<FileNode>()
+            .caused_by(trc::location!())?;
+
+        let mut batch = BatchBuilder::new();
+        let trash_id = if let Some(trash) = resources
+            .by_path(TRASH_FOLDER_NAME)
+            .filter(|trash| trash.is_container())
+        {
+            trash.document_id()
+        } else {
+            let trash_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::FileNode, 1)
+                .await
+                .caused_by(trc::location!())?;
+            FileNode {
+                name: TRASH_FOLDER_NAME.to_string(),
+                ..Default::default()
+            }
+            .insert(access_token, account_id, trash_id, &mut batch)
+            .caused_by(trc::location!())?;
+            trash_id
+        };
+
+        // Avoid clashing with previously deleted items using the same name
+        let name = path.rsplit_once('/').map_or(path, |(_, name)| name);
+        let mut trash_name = name.to_string();
+        let mut suffix = 0;
+        while resources
+            .by_path(&format!("{TRASH_FOLDER_NAME}/{trash_name}"))
+            .is_some()
+        {
+            suffix += 1;
+            trash_name = format!("{name}-{suffix}");
+        }
+
+        let vanished_path = resources
+            .by_path(path)
+            .map(|resource| resources.format_resource(resource))
+            .unwrap_or_else(|| resources.format_item(path));
+        let mut new_node = node.deserialize::<FileNode>().caused_by(trc::location!())?;
+        new_node.parent_id = trash_id + 1;
+        new_node.name = trash_name;
+        new_node.trash = Some(FileTrashInfo {
+            path: path.to_string(),
+            deleted: now() as i64,
+        });
+        new_node
+            .update(access_token, node, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?;
+        batch
+            .with_account_id(account_id)
+            .log_vanished_item(VanishedCollection::FileNode, vanished_path);
+        self.commit_batch(batch).await.caused_by(trc::location!())?;
+
+        Ok(())
+    }
+
+    async fn purge_file_trash(&self, account_id: u32, period: Duration) -> trc::Result<()> {
+        let access_token = self
+            .get_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let resources = self
+            .fetch_dav_resources(&access_token, account_id, SyncCollection::FileNode)
+            .await
+            .caused_by(trc::location!())?;
+        let Some(trash) = resources.by_path(TRASH_FOLDER_NAME) else {
+            return Ok(());
+        };
+
+        let cutoff = now() as i64 - period.as_secs() as i64;
+        for item in resources.children(trash.document_id()) {
+            let Some(node_) = self
+                .get_archive(account_id, Collection::FileNode, item.document_id())
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let node = node_.unarchive::<FileNode>().caused_by(trc::location!())?;
+            if !node
+                .trash
+                .as_ref()
+                .is_some_and(|trash| i64::from(trash.deleted) <= cutoff)
+            {
+                continue;
+            }
+
+            // Delete from the deepest item to the root
+            let mut ids = resources.subtree(item.path()).collect::<Vec<_>>();
+            ids.sort_unstable_by_key(|b| std::cmp::Reverse(b.hierarchy_seq()));
+            let mut sorted_ids = Vec::with_capacity(ids.len());
+            for id in ids.into_iter().map(|a| a.document_id()) {
+                if !sorted_ids.contains(&id) {
+                    sorted_ids.push(id);
+                }
+            }
+
+            DestroyArchive(sorted_ids)
+                .delete(
+                    self,
+                    &access_token,
+                    account_id,
+                    resources.format_resource(item).into(),
+                )
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{
+    Server,
+    auth::AccessToken,
+    config::groupware::{IcapFailurePolicy, IcapServer},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+// Largest ICAP response header accepted
+const MAX_HEADER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    File,
+    Calendar,
+    Contact,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InspectionResult {
+    Allowed,
+    Rejected(String),
+}
+
+pub trait ContentInspection: Sync + Send {
+    /// Submits an uploaded resource to the tenant's ICAP server, if any, and
+    /// returns whether it may be stored. Unreachable servers are handled
+    /// according to the configured failure policy.
+    fn inspect_content(
+        &self,
+        access_token: &AccessToken,
+        kind: ContentKind,
+        name: &str,
+        content_type: Option<&str>,
+        data: &[u8],
+    ) -> impl Future<Output = InspectionResult> + Send;
+
+    /// Returns whether contents of the given size would be submitted for
+    /// inspection, which allows skipping loading them otherwise.
+    fn is_inspected(&self, access_token: &AccessToken, kind: ContentKind, size: usize) -> bool;
+}
+
+impl ContentInspection for Server {
+    async fn inspect_content(
+        &self,
+        access_token: &AccessToken,
+        kind: ContentKind,
+        name: &str,
+        content_type: Option<&str>,
+        data: &[u8],
+    ) -> InspectionResult {
+        let Some(server) = inspection_server(self, access_token, kind, data.len()) else {
+            return InspectionResult::Allowed;
+        };
+
+        let content_type = content_type.unwrap_or(match kind {
+            ContentKind::File => "application/octet-stream",
+            ContentKind::Calendar => "text/calendar",
+            ContentKind::Contact => "text/vcard",
+        });
+        let result =
+            match tokio::time::timeout(server.timeout, respmod(server, name, content_type, data))
+                .await
+            {
+                Ok(Ok(result)) => result,
+                Ok(Err(reason)) => {
+                    return inspection_failed(server, access_token, name, reason);
+                }
+                Err(_) => {
+                    return inspection_failed(
+                        server,
+                        access_token,
+                        name,
+                        "Request timed out".to_string(),
+                    );
+                }
+            };
+
+        if let InspectionResult::Rejected(reason) = &result {
+            trc::event!(
+                Groupware(trc::GroupwareEvent::ContentRejected),
+                AccountId = access_token.primary_id,
+                Id = server.id.clone(),
+                Details = name.to_string(),
+                Reason = reason.clone(),
+            );
+        }
+
+        result
+    }
+
+    fn is_inspected(&self, access_token: &AccessToken, kind: ContentKind, size: usize) -> bool {
+        inspection_server(self, access_token, kind, size).is_some()
+    }
+}
+
+fn inspection_server<'x>(
+    server: &'x Server,
+    access_token: &AccessToken,
+    kind: ContentKind,
+    size: usize,
+) -> Option<&'x IcapServer> {
+    server
+        .core
+        .groupware
+        .icap_server(access_token.tenant.map(|tenant| tenant.id))
+        .filter(|server| match kind {
+            ContentKind::File => server.scan_files,
+            ContentKind::Calendar => server.scan_calendars,
+            ContentKind::Contact => server.scan_contacts,
+        })
+        .filter(|server| size > 0 && size <= server.max_size)
+}
+
+fn inspection_failed(
+    server: &IcapServer,
+    access_token: &AccessToken,
+    name: &str,
+    reason: String,
+) -> InspectionResult {
+    trc::event!(
+        Groupware(trc::GroupwareEvent::ContentInspectionError),
+        AccountId = access_token.primary_id,
+        Id = server.id.clone(),
+        Details = name.to_string(),
+        Reason = reason,
+    );
+
+    match server.failure_policy {
+        IcapFailurePolicy::Allow => InspectionResult::Allowed,
+        IcapFailurePolicy::Reject => {
+            InspectionResult::Rejected("Content inspection unavailable".to_string())
+        }
+    }
+}
+
+async fn respmod(
+    server: &IcapServer,
+    name: &str,
+    content_type: &str,
+    data: &[u8],
+) -> Result<InspectionResult, String> {
+    // The upload is encapsulated as the body of an HTTP response (RFC 3507)
+    let file_name = name
+        .rsplit('/')
+        .next()
+        .unwrap_or(name)
+        .replace(['"', '\r', '\n'], "");
+    let http_header = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nContent-Disposition: attachment; filename=\"{file_name}\"\r\n\r\n",
+        data.len()
+    );
+    let mut request = format!(
+        "RESPMOD icap://{}:{}/{} ICAP/1.0\r\nHost: {}\r\nAllow: 204\r\nEncapsulated: res-hdr=0, res-body={}\r\n\r\n{http_header}{:x}\r\n",
+        server.host,
+        server.port,
+        server.service,
+        server.host,
+        http_header.len(),
+        data.len()
+    )
+    .into_bytes();
+    request.extend_from_slice(data);
+    request.extend_from_slice(b"\r\n0\r\n\r\n");
+
+    let mut stream = TcpStream::connect((server.host.as_str(), server.port))
+        .await
+        .map_err(|err| format!("Failed to connect to ICAP server: {err}"))?;
+    stream
+        .write_all(&request)
+        .await
+        .map_err(|err| format!("Failed to send ICAP request: {err}"))?;
+
+    // Only the ICAP response header is needed to reach a verdict
+    let mut response = Vec::with_capacity(1024);
+    let mut buf = [0u8; 4096];
+    let header_end = loop {
+        let bytes_read = stream
+            .read(&mut buf)
+            .await
+            .map_err(|err| format!("Failed to read ICAP response: {err}"))?;
+        if bytes_read == 0 {
+            return Err("ICAP server closed the connection".to_string());
+        }
+        response.extend_from_slice(&buf[..bytes_read]);
+        if let Some(pos) = response.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        } else if response.len() > MAX_HEADER_SIZE {
+            return Err("ICAP response header too large".to_string());
+        }
+    };
+    let header = String::from_utf8_lossy(&response[..header_end]);
+    let mut lines = header.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.strip_prefix("ICAP/1.0 "))
+        .and_then(|line| line.split(' ').next())
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| "Invalid ICAP response".to_string())?;
+
+    match status {
+        204 => Ok(InspectionResult::Allowed),
+        200 => {
+            // Servers report findings in vendor headers, a modified body also means rejection
+            let reason = lines
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| {
+                    ["X-Infection-Found", "X-Violations-Found", "X-Virus-ID"]
+                        .iter()
+                        .any(|header| name.trim().eq_ignore_ascii_case(header))
+                })
+                .map(|(_, value)| value.trim().to_string())
+                .unwrap_or_else(|| "Content modified by inspection service".to_string());
+            Ok(InspectionResult::Rejected(reason))
+        }
+        status => Err(format!("ICAP server returned status {status}")),
+    }
+}
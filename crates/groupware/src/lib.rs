@@ -5,13 +5,20 @@
  */
 
 use calcard::common::timezone::Tz;
-use common::DavResources;
+use common::{DavResources, auth::AccessToken};
+use directory::Permission;
 use jmap_proto::types::collection::Collection;
+use store::rand::{Rng, distr::Alphanumeric, rng};
 
+pub mod apns;
 pub mod cache;
 pub mod calendar;
+pub mod check;
 pub mod contact;
 pub mod file;
+pub mod icap;
+pub mod provision;
+pub mod push;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DavResourceName {
@@ -23,6 +30,13 @@ pub enum DavResourceName {
 
 pub struct DestroyArchive<T>(pub T);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestResult {
+    Created(u32),
+    Updated(u32),
+    Skipped(&'static str),
+}
+
 impl DavResourceName {
     pub fn parse(service: &str) -> Option<Self> {
         hashify::tiny_map!(service.as_bytes(),
@@ -59,6 +73,21 @@ impl DavResourceName {
             DavResourceName::Principal => "Principal",
         }
     }
+
+    /// Permission that enables the service, principals are always available.
+    pub fn service_permission(&self) -> Option<Permission> {
+        match self {
+            DavResourceName::Card => Some(Permission::DavCardAccess),
+            DavResourceName::Cal => Some(Permission::DavCalAccess),
+            DavResourceName::File => Some(Permission::DavFileAccess),
+            DavResourceName::Principal => None,
+        }
+    }
+
+    pub fn is_enabled(&self, access_token: &AccessToken) -> bool {
+        self.service_permission()
+            .is_none_or(|permission| access_token.has_permission(permission))
+    }
 }
 
 impl From<DavResourceName> for Collection {
@@ -84,6 +113,29 @@ impl From<Collection> for DavResourceName {
     }
 }
 
+/// Generates a random name for a new member of a collection that does not
+/// clash with any of the existing members.
+pub fn member_name(resources: &DavResources, parent_path: &str, extension: &str) -> String {
+    loop {
+        let name = format!(
+            "{}{extension}",
+            rng()
+                .sample_iter(Alphanumeric)
+                .take(15)
+                .map(|ch| char::from(ch.to_ascii_lowercase()))
+                .collect::<String>()
+        );
+        let path = if !parent_path.is_empty() {
+            format!("{parent_path}/{name}")
+        } else {
+            name.clone()
+        };
+        if resources.by_path(&path).is_none() {
+            return name;
+        }
+    }
+}
+
 pub trait DavCalendarResource {
     fn calendar_default_tz(&self, calendar_id: u32) -> Option<Tz>;
 }
@@ -0,0 +1,219 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use jmap_proto::types::collection::Collection;
+use store::{query::acl::AclQuery, write::BatchBuilder};
+use trc::AddContext;
+
+use crate::{
+    calendar::{Calendar, CalendarPreferences},
+    contact::AddressBook,
+    file::FileNode,
+};
+
+pub trait GroupwareProvisioning: Sync + Send {
+    /// Creates the calendar, address book and folders listed in the
+    /// provisioning template of the principal's tenant. Collections that
+    /// already exist are left untouched.
+    fn provision_groupware(&self, account_id: u32) -> impl Future<Output = trc::Result<()>> + Send;
+
+    /// Removes the grants a deleted principal held on collections shared by
+    /// other accounts and evicts its cached DAV resources.
+    fn deprovision_groupware(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl GroupwareProvisioning for Server {
+    async fn provision_groupware(&self, account_id: u32) -> trc::Result<()> {
+        let access_token = self
+            .get_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let template = self
+            .core
+            .groupware
+            .provisioning_template(access_token.tenant.map(|tenant| tenant.id));
+        let mut batch = BatchBuilder::new();
+
+        if let Some(name) = &template.calendar_name {
+            if self
+                .get_document_ids(account_id, Collection::Calendar)
+                .await
+                .caused_by(trc::location!())?
+                .is_none_or(|ids| ids.is_empty())
+            {
+                let document_id = self
+                    .store()
+                    .assign_document_ids(account_id, Collection::Calendar, 1)
+                    .await
+                    .caused_by(trc::location!())?;
+                Calendar {
+                    name: name.clone(),
+                    preferences: vec![CalendarPreferences {
+                        account_id,
+                        name: template
+                            .calendar_display_name
+                            .clone()
+                            .unwrap_or_else(|| name.clone()),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }
+                .insert(&access_token, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+            }
+        }
+
+        if let Some(name) = &template.addressbook_name {
+            if self
+                .get_document_ids(account_id, Collection::AddressBook)
+                .await
+                .caused_by(trc::location!())?
+                .is_none_or(|ids| ids.is_empty())
+            {
+                let document_id = self
+                    .store()
+                    .assign_document_ids(account_id, Collection::AddressBook, 1)
+                    .await
+                    .caused_by(trc::location!())?;
+                AddressBook {
+                    name: name.clone(),
+                    display_name: template.addressbook_display_name.clone(),
+                    is_default: true,
+                    ..Default::default()
+                }
+                .insert(&access_token, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+            }
+        }
+
+        if !template.folders.is_empty() {
+            let mut existing = Vec::new();
+            for document_id in self
+                .get_document_ids(account_id, Collection::FileNode)
+                .await
+                .caused_by(trc::location!())?
+                .unwrap_or_default()
+            {
+                if let Some(node) = self
+                    .get_archive(account_id, Collection::FileNode, document_id)
+                    .await
+                    .caused_by(trc::location!())?
+                {
+                    let node = node.unarchive::<FileNode>().caused_by(trc::location!())?;
+                    if node.parent_id == 0 {
+                        existing.push(node.name.to_string());
+                    }
+                }
+            }
+
+            for name in &template.folders {
+                if !existing.iter().any(|existing| existing == name) {
+                    let document_id = self
+                        .store()
+                        .assign_document_ids(account_id, Collection::FileNode, 1)
+                        .await
+                        .caused_by(trc::location!())?;
+                    FileNode {
+                        parent_id: 0,
+                        name: name.clone(),
+                        ..Default::default()
+                    }
+                    .insert(&access_token, account_id, document_id, &mut batch)
+                    .caused_by(trc::location!())?;
+                }
+            }
+        }
+
+        if !batch.is_empty() {
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+
+    async fn deprovision_groupware(&self, account_id: u32) -> trc::Result<()> {
+        for acl_item in self
+            .store()
+            .acl_query(AclQuery::HasAccess {
+                grant_account_id: account_id,
+            })
+            .await
+            .caused_by(trc::location!())?
+        {
+            let owner_id = acl_item.to_account_id;
+            let document_id = acl_item.to_document_id;
+            let collection = Collection::from(acl_item.to_collection);
+            if !matches!(
+                collection,
+                Collection::Calendar | Collection::AddressBook | Collection::FileNode
+            ) {
+                continue;
+            }
+            let Some(archive) = self
+                .get_archive(owner_id, collection, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let owner_token = self
+                .get_access_token(owner_id)
+                .await
+                .caused_by(trc::location!())?;
+            let mut batch = BatchBuilder::new();
+
+            match collection {
+                Collection::Calendar => {
+                    let calendar = archive
+                        .to_unarchived::<Calendar>()
+                        .caused_by(trc::location!())?;
+                    let mut new_calendar = calendar
+                        .deserialize::<Calendar>()
+                        .caused_by(trc::location!())?;
+                    new_calendar.acls.retain(|acl| acl.account_id != account_id);
+                    new_calendar
+                        .update(&owner_token, calendar, owner_id, document_id, &mut batch)
+                        .caused_by(trc::location!())?;
+                }
+                Collection::AddressBook => {
+                    let book = archive
+                        .to_unarchived::<AddressBook>()
+                        .caused_by(trc::location!())?;
+                    let mut new_book = book
+                        .deserialize::<AddressBook>()
+                        .caused_by(trc::location!())?;
+                    new_book.acls.retain(|acl| acl.account_id != account_id);
+                    new_book
+                        .update(&owner_token, book, owner_id, document_id, &mut batch)
+                        .caused_by(trc::location!())?;
+                }
+                _ => {
+                    let node = archive
+                        .to_unarchived::<FileNode>()
+                        .caused_by(trc::location!())?;
+                    let mut new_node =
+                        node.deserialize::<FileNode>().caused_by(trc::location!())?;
+                    new_node.acls.retain(|acl| acl.account_id != account_id);
+                    new_node
+                        .update(&owner_token, node, owner_id, document_id, &mut batch)
+                        .caused_by(trc::location!())?;
+                }
+            }
+
+            self.commit_batch(batch).await.caused_by(trc::location!())?;
+        }
+
+        self.inner.cache.events.remove(&account_id);
+        self.inner.cache.contacts.remove(&account_id);
+        self.inner.cache.files.remove(&account_id);
+
+        Ok(())
+    }
+}
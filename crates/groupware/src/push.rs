@@ -0,0 +1,310 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{KV_DAV_PUSH, Server, auth::AccessToken};
+use dav_proto::schema::response::PushMessage;
+use store::{
+    Serialize,
+    dispatch::lookup::KeyValue,
+    rand,
+    write::{AlignedBytes, Archive, Archiver, now},
+};
+use trc::AddContext;
+
+use crate::DavResourceName;
+
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+#[rkyv(derive(Debug))]
+pub struct DavPushSubscriptions {
+    pub subscriptions: Vec<DavPushSubscription>,
+}
+
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+#[rkyv(derive(Debug))]
+pub struct DavPushSubscription {
+    pub id: u64,
+    pub account_id: u32,
+    pub document_id: u32,
+    pub url: String,
+    pub keys: Option<DavPushKeys>,
+    pub content_update: bool,
+    pub property_update: bool,
+    pub expires: u64,
+}
+
+#[derive(
+    rkyv::Archive, rkyv::Deserialize, rkyv::Serialize, Debug, Default, Clone, PartialEq, Eq,
+)]
+#[rkyv(derive(Debug))]
+pub struct DavPushKeys {
+    pub p256dh: Vec<u8>,
+    pub auth: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DavPushRegistration {
+    Created { id: u64, expires: u64 },
+    Updated { id: u64, expires: u64 },
+    TooManySubscriptions,
+    Disabled,
+}
+
+pub trait DavPush: Sync + Send {
+    /// Registers or refreshes a Web Push subscription for a calendar or
+    /// address book collection. Subscriptions are matched by their push
+    /// resource, so registering the same URL twice updates it.
+    fn register_dav_push(
+        &self,
+        access_token: &AccessToken,
+        service: DavResourceName,
+        account_id: u32,
+        subscription: DavPushSubscription,
+    ) -> impl Future<Output = trc::Result<DavPushRegistration>> + Send;
+
+    /// Removes a subscription, only the subscriber or a member of the
+    /// account owning the collection may do so.
+    fn unregister_dav_push(
+        &self,
+        access_token: &AccessToken,
+        service: DavResourceName,
+        account_id: u32,
+        id: u64,
+    ) -> impl Future<Output = trc::Result<bool>> + Send;
+
+    /// Returns the unexpired subscriptions to the collections of a home.
+    fn dav_push_subscriptions(
+        &self,
+        service: DavResourceName,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<Vec<DavPushSubscription>>> + Send;
+
+    /// Removes the subscriptions that were rejected by their push service.
+    fn remove_dav_push_subscriptions(
+        &self,
+        service: DavResourceName,
+        account_id: u32,
+        ids: &[u64],
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl DavPush for Server {
+    async fn register_dav_push(
+        &self,
+        access_token: &AccessToken,
+        service: DavResourceName,
+        account_id: u32,
+        mut subscription: DavPushSubscription,
+    ) -> trc::Result<DavPushRegistration> {
+        let Some(web_push) = &self.core.groupware.web_push else {
+            return Ok(DavPushRegistration::Disabled);
+        };
+
+        let key = dav_push_key(service, account_id);
+        let now = now();
+        let max_expires = now + web_push.max_expiry.as_secs();
+        let mut subscriptions = self
+            .dav_push_store(key.clone())
+            .await
+            .caused_by(trc::location!())?;
+        subscriptions
+            .subscriptions
+            .retain(|subscription| subscription.expires > now);
+
+        subscription.account_id = access_token.primary_id();
+        subscription.expires = if subscription.expires > now {
+            subscription.expires.min(max_expires)
+        } else {
+            max_expires
+        };
+        let expires = subscription.expires;
+
+        let result = if let Some(existing) =
+            subscriptions.subscriptions.iter_mut().find(|existing| {
+                existing.url == subscription.url && existing.document_id == subscription.document_id
+            }) {
+            subscription.id = existing.id;
+            *existing = subscription;
+            DavPushRegistration::Updated {
+                id: existing.id,
+                expires,
+            }
+        } else if subscriptions
+            .subscriptions
+            .iter()
+            .filter(|existing| existing.account_id == subscription.account_id)
+            .count()
+            >= web_push.max_subscriptions
+        {
+            return Ok(DavPushRegistration::TooManySubscriptions);
+        } else {
+            let id = rand::random::<u64>();
+            subscription.id = id;
+            subscriptions.subscriptions.push(subscription);
+            DavPushRegistration::Created { id, expires }
+        };
+
+        self.write_dav_push_store(key, subscriptions)
+            .await
+            .caused_by(trc::location!())
+            .map(|_| result)
+    }
+
+    async fn unregister_dav_push(
+        &self,
+        access_token: &AccessToken,
+        service: DavResourceName,
+        account_id: u32,
+        id: u64,
+    ) -> trc::Result<bool> {
+        let key = dav_push_key(service, account_id);
+        let mut subscriptions = self
+            .dav_push_store(key.clone())
+            .await
+            .caused_by(trc::location!())?;
+        let num_subscriptions = subscriptions.subscriptions.len();
+        subscriptions.subscriptions.retain(|subscription| {
+            subscription.id != id
+                || (subscription.account_id != access_token.primary_id()
+                    && !access_token.is_member(account_id))
+        });
+
+        if subscriptions.subscriptions.len() != num_subscriptions {
+            self.write_dav_push_store(key, subscriptions)
+                .await
+                .caused_by(trc::location!())
+                .map(|_| true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    async fn dav_push_subscriptions(
+        &self,
+        service: DavResourceName,
+        account_id: u32,
+    ) -> trc::Result<Vec<DavPushSubscription>> {
+        let now = now();
+        self.dav_push_store(dav_push_key(service, account_id))
+            .await
+            .map(|subscriptions| {
+                subscriptions
+                    .subscriptions
+                    .into_iter()
+                    .filter(|subscription| subscription.expires > now)
+                    .collect()
+            })
+    }
+
+    async fn remove_dav_push_subscriptions(
+        &self,
+        service: DavResourceName,
+        account_id: u32,
+        ids: &[u64],
+    ) -> trc::Result<()> {
+        let key = dav_push_key(service, account_id);
+        let now = now();
+        let mut subscriptions = self
+            .dav_push_store(key.clone())
+            .await
+            .caused_by(trc::location!())?;
+        subscriptions
+            .subscriptions
+            .retain(|subscription| subscription.expires > now && !ids.contains(&subscription.id));
+
+        self.write_dav_push_store(key, subscriptions)
+            .await
+            .caused_by(trc::location!())
+    }
+}
+
+trait DavPushStore {
+    fn dav_push_store(
+        &self,
+        key: Vec<u8>,
+    ) -> impl Future<Output = trc::Result<DavPushSubscriptions>> + Send;
+
+    fn write_dav_push_store(
+        &self,
+        key: Vec<u8>,
+        subscriptions: DavPushSubscriptions,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl DavPushStore for Server {
+    async fn dav_push_store(&self, key: Vec<u8>) -> trc::Result<DavPushSubscriptions> {
+        self.in_memory_store()
+            .key_get::<Archive<AlignedBytes>>(key)
+            .await
+            .caused_by(trc::location!())?
+            .map(|subscriptions| subscriptions.deserialize::<DavPushSubscriptions>())
+            .transpose()
+            .map(|subscriptions| subscriptions.unwrap_or_default())
+    }
+
+    async fn write_dav_push_store(
+        &self,
+        key: Vec<u8>,
+        subscriptions: DavPushSubscriptions,
+    ) -> trc::Result<()> {
+        // The entry lives as long as its longest lasting subscription
+        let now = now();
+        let expires = subscriptions
+            .subscriptions
+            .iter()
+            .map(|subscription| subscription.expires)
+            .max()
+            .unwrap_or_default();
+
+        if expires > now {
+            self.in_memory_store()
+                .key_set(
+                    KeyValue::new(
+                        key,
+                        Archiver::new(subscriptions)
+                            .serialize()
+                            .caused_by(trc::location!())?,
+                    )
+                    .expires(expires - now),
+                )
+                .await
+        } else {
+            self.in_memory_store().key_delete(key).await
+        }
+    }
+}
+
+fn dav_push_key(service: DavResourceName, account_id: u32) -> Vec<u8> {
+    KeyValue::<()>::build_key(
+        KV_DAV_PUSH,
+        format!("{}/{account_id}/", service.base_path()).as_bytes(),
+    )
+}
+
+/// Topics identify a single calendar or address book collection.
+pub fn dav_push_topic(service: DavResourceName, account_id: u32, document_id: u32) -> String {
+    let service = match service {
+        DavResourceName::Cal => "cal",
+        DavResourceName::Card => "card",
+        DavResourceName::File => "file",
+        DavResourceName::Principal => "pal",
+    };
+    format!("{service}-{account_id:x}-{document_id:x}")
+}
+
+/// Builds the push message sent when a subscribed collection changes.
+pub fn dav_push_message(topic: String, change_id: Option<u64>) -> String {
+    let message = PushMessage::new(topic);
+    if let Some(change_id) = change_id {
+        message.with_content_update(format!("urn:stalwart:davsync:{change_id:x}"))
+    } else {
+        message.with_property_update()
+    }
+    .to_string()
+}
@@ -7,8 +7,7 @@
 use std::borrow::Cow;
 
 use http_body_util::BodyExt;
-
-use crate::HttpRequest;
+use hyper::body::{Body, Bytes};
 
 #[inline]
 pub fn decode_path_element(item: &str) -> Cow<'_, str> {
@@ -17,11 +16,10 @@ pub fn decode_path_element(item: &str) -> Cow<'_, str> {
         .unwrap_or_else(|_| item.into())
 }
 
-pub async fn fetch_body(
-    req: &mut HttpRequest,
-    max_size: usize,
-    session_id: u64,
-) -> Option<Vec<u8>> {
+pub async fn fetch_body<B>(req: &mut B, max_size: usize, session_id: u64) -> Option<Vec<u8>>
+where
+    B: Body<Data = Bytes> + Unpin,
+{
     let mut bytes = Vec::with_capacity(1024);
     while let Some(Ok(frame)) = req.frame().await {
         if let Some(data) = frame.data_ref() {
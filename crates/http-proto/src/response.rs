@@ -100,6 +100,16 @@ impl HttpResponse {
         self.with_content_length(body_len)
     }
 
+    pub fn with_encoded_body(mut self, encoding: &'static str, body: Vec<u8>) -> Self {
+        if let Some(headers) = self.builder.headers_mut() {
+            headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+            headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+            headers.append(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+        }
+        self.body = HttpResponseBody::Binary(body);
+        self
+    }
+
     pub fn with_stream_body(
         mut self,
         stream: http_body_util::combinators::BoxBody<hyper::body::Bytes, hyper::Error>,
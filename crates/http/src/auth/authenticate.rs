@@ -13,8 +13,11 @@ use mail_parser::decoders::base64::base64_decode;
 use mail_send::Credentials;
 
 use common::auth::AccessToken;
+use directory::{Permission, backend::internal::manage::ManageDirectory};
 use std::future::Future;
 
+pub const IMPERSONATE_HEADER: &str = "X-Impersonate-User";
+
 pub trait Authenticator: Sync + Send {
     fn authenticate_headers(
         &self,
@@ -22,6 +25,13 @@ pub trait Authenticator: Sync + Send {
         session: &HttpSessionData,
         allow_api_access: bool,
     ) -> impl Future<Output = trc::Result<(Option<InFlight>, Arc<AccessToken>)>> + Send;
+
+    fn impersonate_headers(
+        &self,
+        req: &HttpRequest,
+        session: &HttpSessionData,
+        access_token: Arc<AccessToken>,
+    ) -> impl Future<Output = trc::Result<Arc<AccessToken>>> + Send;
 }
 
 impl Authenticator for Server {
@@ -112,6 +122,90 @@ impl Authenticator for Server {
                 .caused_by(trc::location!()))
         }
     }
+
+    async fn impersonate_headers(
+        &self,
+        req: &HttpRequest,
+        session: &HttpSessionData,
+        access_token: Arc<AccessToken>,
+    ) -> trc::Result<Arc<AccessToken>> {
+        let Some(name) = req
+            .headers()
+            .get(IMPERSONATE_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h.trim().to_lowercase())
+        else {
+            return Ok(access_token);
+        };
+
+        // Validate the access token
+        access_token.assert_has_permission(Permission::Impersonate)?;
+
+        let account_id = self
+            .core
+            .storage
+            .data
+            .get_principal_id(&name)
+            .await?
+            .ok_or_else(|| {
+                trc::AuthEvent::Failed
+                    .into_err()
+                    .details("Impersonated account does not exist.")
+                    .ctx(trc::Key::AccountName, name.clone())
+            })?;
+        let impersonated = self.get_access_token(account_id).await?;
+        impersonated.assert_has_permission(Permission::Authenticate)?;
+
+        // Tenant administrators can only impersonate members of their tenant
+        if !access_token.has_tenant_access(&impersonated) {
+            return Err(impersonation_denied(
+                &access_token,
+                account_id,
+                name,
+                "Cannot impersonate accounts outside of the tenant.",
+            ));
+        }
+
+        // Administrators, and accounts holding permissions the caller lacks, cannot be impersonated
+        if impersonated.permissions().into_iter().any(|permission| {
+            permission == Permission::Impersonate
+                || (permission.is_tenant_admin_permission() && !permission.is_user_permission())
+                || !access_token.has_permission(permission)
+        }) {
+            return Err(impersonation_denied(
+                &access_token,
+                account_id,
+                name,
+                "Cannot impersonate accounts with elevated permissions.",
+            ));
+        }
+
+        trc::event!(
+            Auth(trc::AuthEvent::Impersonate),
+            SpanId = session.session_id,
+            AccountName = access_token.name.clone(),
+            AccountId = access_token.primary_id(),
+            Id = account_id,
+            Details = name,
+        );
+
+        Ok(impersonated)
+    }
+}
+
+fn impersonation_denied(
+    access_token: &AccessToken,
+    account_id: u32,
+    name: String,
+    reason: &'static str,
+) -> trc::Error {
+    trc::SecurityEvent::Unauthorized
+        .into_err()
+        .reason(reason)
+        .details(name)
+        .ctx(trc::Key::AccountName, access_token.name.clone())
+        .ctx(trc::Key::AccountId, access_token.primary_id())
+        .ctx(trc::Key::Id, account_id)
 }
 
 pub trait HttpHeaders {
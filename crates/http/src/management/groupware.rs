@@ -0,0 +1,858 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::collections::{HashMap, HashSet};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use calcard::{Entry, Parser, common::timezone::Tz};
+use common::{
+    DavName, Server,
+    auth::AccessToken,
+    storage::index::{IndexableAndSerializableObject, ObjectIndexBuilder},
+};
+use directory::backend::internal::manage::ManageDirectory;
+use groupware::{
+    calendar::{
+        Calendar, CalendarEvent, CalendarEventData, CalendarPreferences, Timezone,
+        timezone::parse_tz,
+    },
+    contact::{AddressBook, ContactCard},
+    file::{FileNode, FileProperties},
+};
+use jmap_proto::types::{
+    collection::{Collection, SyncCollection},
+    value::AclGrant,
+};
+use serde::{Deserialize, Serialize};
+use store::write::BatchBuilder;
+use trc::AddContext;
+use utils::map::bitmap::Bitmap;
+
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Portable snapshot of an account's calendars, address books and files.
+/// Principals are referenced by name so archives can be moved across servers.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupwareArchive {
+    pub version: u32,
+    #[serde(default)]
+    pub change_ids: ChangeIds,
+    #[serde(default)]
+    pub calendars: Vec<CalendarExport>,
+    #[serde(default)]
+    pub events: Vec<EventExport>,
+    #[serde(default)]
+    pub address_books: Vec<AddressBookExport>,
+    #[serde(default)]
+    pub cards: Vec<CardExport>,
+    #[serde(default)]
+    pub files: Vec<FileExport>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeIds {
+    pub calendar: Option<u64>,
+    pub address_book: Option<u64>,
+    pub file_node: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarExport {
+    pub name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    pub sort_order: u32,
+    pub flags: u16,
+    pub time_zone: Option<String>,
+    #[serde(default)]
+    pub acls: Vec<AclExport>,
+    pub created: i64,
+    pub modified: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressBookExport {
+    pub name: String,
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub sort_order: u32,
+    pub is_default: bool,
+    #[serde(default)]
+    pub acls: Vec<AclExport>,
+    pub created: i64,
+    pub modified: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventExport {
+    pub names: Vec<ItemName>,
+    pub display_name: Option<String>,
+    pub flags: u16,
+    pub data: String,
+    pub created: i64,
+    pub modified: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardExport {
+    pub names: Vec<ItemName>,
+    pub display_name: Option<String>,
+    pub data: String,
+    pub created: i64,
+    pub modified: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileExport {
+    pub path: String,
+    pub display_name: Option<String>,
+    pub media_type: Option<String>,
+    #[serde(default)]
+    pub executable: bool,
+    #[serde(default)]
+    pub acls: Vec<AclExport>,
+    pub created: i64,
+    pub modified: i64,
+    pub contents: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemName {
+    pub container: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AclExport {
+    pub principal: String,
+    pub rights: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResult {
+    pub calendars: u32,
+    pub events: u32,
+    pub address_books: u32,
+    pub cards: u32,
+    pub files: u32,
+    pub skipped: Vec<String>,
+}
+
+/// Storage quota for a single calendar or file folder, `None` removes it.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionQuota {
+    pub collection: QuotaCollection,
+    pub path: String,
+    pub quota: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QuotaCollection {
+    Calendar,
+    File,
+}
+
+pub trait GroupwareArchiveManager: Sync + Send {
+    fn export_groupware(
+        &self,
+        account_id: u32,
+    ) -> impl Future<Output = trc::Result<GroupwareArchive>> + Send;
+
+    fn import_groupware(
+        &self,
+        account_id: u32,
+        archive: GroupwareArchive,
+    ) -> impl Future<Output = trc::Result<ImportResult>> + Send;
+}
+
+impl GroupwareArchiveManager for Server {
+    async fn export_groupware(&self, account_id: u32) -> trc::Result<GroupwareArchive> {
+        let mut archive = GroupwareArchive {
+            version: ARCHIVE_VERSION,
+            change_ids: ChangeIds {
+                calendar: self
+                    .store()
+                    .get_last_change_id(account_id, SyncCollection::Calendar)
+                    .await
+                    .caused_by(trc::location!())?,
+                address_book: self
+                    .store()
+                    .get_last_change_id(account_id, SyncCollection::AddressBook)
+                    .await
+                    .caused_by(trc::location!())?,
+                file_node: self
+                    .store()
+                    .get_last_change_id(account_id, SyncCollection::FileNode)
+                    .await
+                    .caused_by(trc::location!())?,
+            },
+            ..Default::default()
+        };
+        let mut principals = HashMap::new();
+
+        // Export calendars
+        let mut containers = HashMap::new();
+        for document_id in self
+            .get_document_ids(account_id, Collection::Calendar)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+        {
+            let Some(calendar) = self
+                .get_archive(account_id, Collection::Calendar, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let calendar = calendar
+                .deserialize::<Calendar>()
+                .caused_by(trc::location!())?;
+            let preferences = calendar.preferences(account_id);
+
+            archive.calendars.push(CalendarExport {
+                name: calendar.name.clone(),
+                display_name: preferences.name.clone(),
+                description: preferences.description.clone(),
+                color: preferences.color.clone(),
+                sort_order: preferences.sort_order,
+                flags: preferences.flags,
+                time_zone: match &preferences.time_zone {
+                    Timezone::IANA(id) => Tz::from_id(*id).map(|tz| tz.to_string()),
+                    Timezone::Custom(ical) => ical.to_string().into(),
+                    Timezone::Default => None,
+                },
+                acls: self.export_acls(&calendar.acls, &mut principals).await?,
+                created: calendar.created,
+                modified: calendar.modified,
+            });
+            containers.insert(document_id, calendar.name);
+        }
+
+        // Export events
+        for document_id in self
+            .get_document_ids(account_id, Collection::CalendarEvent)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+        {
+            let Some(event) = self
+                .get_archive(account_id, Collection::CalendarEvent, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let event = event
+                .deserialize::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+
+            archive.events.push(EventExport {
+                names: export_names(&event.names, &containers),
+                display_name: event.display_name,
+                flags: event.flags,
+                data: event.data.event.to_string(),
+                created: event.created,
+                modified: event.modified,
+            });
+        }
+
+        // Export address books
+        let mut containers = HashMap::new();
+        for document_id in self
+            .get_document_ids(account_id, Collection::AddressBook)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+        {
+            let Some(book) = self
+                .get_archive(account_id, Collection::AddressBook, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let book = book
+                .deserialize::<AddressBook>()
+                .caused_by(trc::location!())?;
+
+            archive.address_books.push(AddressBookExport {
+                name: book.name.clone(),
+                display_name: book.display_name,
+                description: book.description,
+                sort_order: book.sort_order,
+                is_default: book.is_default,
+                acls: self.export_acls(&book.acls, &mut principals).await?,
+                created: book.created,
+                modified: book.modified,
+            });
+            containers.insert(document_id, book.name);
+        }
+
+        // Export cards
+        for document_id in self
+            .get_document_ids(account_id, Collection::ContactCard)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+        {
+            let Some(card) = self
+                .get_archive(account_id, Collection::ContactCard, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            let card = card
+                .deserialize::<ContactCard>()
+                .caused_by(trc::location!())?;
+
+            archive.cards.push(CardExport {
+                names: export_names(&card.names, &containers),
+                display_name: card.display_name,
+                data: card.card.to_string(),
+                created: card.created,
+                modified: card.modified,
+            });
+        }
+
+        // Export files
+        let mut nodes = HashMap::new();
+        for document_id in self
+            .get_document_ids(account_id, Collection::FileNode)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+        {
+            if let Some(node) = self
+                .get_archive(account_id, Collection::FileNode, document_id)
+                .await
+                .caused_by(trc::location!())?
+            {
+                nodes.insert(
+                    document_id,
+                    node.deserialize::<FileNode>().caused_by(trc::location!())?,
+                );
+            }
+        }
+        for node in nodes.values() {
+            let Some(path) = node_path(node, &nodes) else {
+                continue;
+            };
+            let contents = if let Some(file) = &node.file {
+                self.blob_store()
+                    .get_blob(file.blob_hash.0.as_slice(), 0..usize::MAX)
+                    .await
+                    .caused_by(trc::location!())?
+                    .map(|bytes| STANDARD.encode(bytes))
+                    .unwrap_or_default()
+                    .into()
+            } else {
+                None
+            };
+
+            archive.files.push(FileExport {
+                path,
+                display_name: node.display_name.clone(),
+                media_type: node.file.as_ref().and_then(|f| f.media_type.clone()),
+                executable: node.file.as_ref().is_some_and(|f| f.executable),
+                acls: self.export_acls(&node.acls, &mut principals).await?,
+                created: node.created,
+                modified: node.modified,
+                contents,
+            });
+        }
+
+        // Parents sort before their children
+        archive.files.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(archive)
+    }
+
+    async fn import_groupware(
+        &self,
+        account_id: u32,
+        archive: GroupwareArchive,
+    ) -> trc::Result<ImportResult> {
+        if archive.version != ARCHIVE_VERSION {
+            return Err(trc::ResourceEvent::BadParameters
+                .into_err()
+                .details("Unsupported archive version")
+                .ctx(trc::Key::Value, archive.version));
+        }
+
+        let access_token = self
+            .get_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let mut result = ImportResult::default();
+        let mut principals = HashMap::new();
+
+        // Import calendars, merging into existing ones with the same name
+        let mut containers = HashMap::new();
+        let mut existing_names = HashSet::new();
+        for document_id in self
+            .get_document_ids(account_id, Collection::Calendar)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+        {
+            if let Some(calendar) = self
+                .get_archive(account_id, Collection::Calendar, document_id)
+                .await
+                .caused_by(trc::location!())?
+            {
+                let calendar = calendar
+                    .deserialize::<Calendar>()
+                    .caused_by(trc::location!())?;
+                containers.insert(calendar.name, document_id);
+            }
+        }
+        for document_id in self
+            .get_document_ids(account_id, Collection::CalendarEvent)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+        {
+            if let Some(event) = self
+                .get_archive(account_id, Collection::CalendarEvent, document_id)
+                .await
+                .caused_by(trc::location!())?
+            {
+                let event = event
+                    .deserialize::<CalendarEvent>()
+                    .caused_by(trc::location!())?;
+                existing_names.extend(event.names.into_iter().map(|n| (n.parent_id, n.name)));
+            }
+        }
+        for item in archive.calendars {
+            if containers.contains_key(&item.name) {
+                result
+                    .skipped
+                    .push(format!("Calendar {:?} already exists", item.name));
+                continue;
+            }
+            let calendar = Calendar {
+                name: item.name.clone(),
+                preferences: vec![CalendarPreferences {
+                    account_id,
+                    name: item.display_name,
+                    description: item.description,
+                    sort_order: item.sort_order,
+                    color: item.color,
+                    flags: item.flags,
+                    time_zone: item
+                        .time_zone
+                        .as_deref()
+                        .and_then(import_timezone)
+                        .unwrap_or_default(),
+                }],
+                acls: self.import_acls(item.acls, &mut principals).await?,
+                created: item.created,
+                modified: item.modified,
+                ..Default::default()
+            };
+            let document_id = self
+                .write_document(&access_token, account_id, Collection::Calendar, calendar)
+                .await?;
+            containers.insert(item.name, document_id);
+            result.calendars += 1;
+        }
+
+        // Import events
+        for item in archive.events {
+            let Some(names) = import_names(item.names, &containers, &existing_names) else {
+                result
+                    .skipped
+                    .push("Event has no new location to import into".to_string());
+                continue;
+            };
+            let Entry::ICalendar(ical) = Parser::new(&item.data).entry() else {
+                result
+                    .skipped
+                    .push(format!("Event {:?} is not valid iCalendar", names[0].name));
+                continue;
+            };
+            let event = CalendarEvent {
+                names,
+                display_name: item.display_name,
+                data: CalendarEventData::new(
+                    ical,
+                    Tz::Floating,
+                    self.core.groupware.max_ical_instances,
+                ),
+                flags: item.flags,
+                size: item.data.len() as u32,
+                created: item.created,
+                modified: item.modified,
+                ..Default::default()
+            };
+            self.write_document(&access_token, account_id, Collection::CalendarEvent, event)
+                .await?;
+            result.events += 1;
+        }
+
+        // Import address books
+        let mut containers = HashMap::new();
+        let mut existing_names = HashSet::new();
+        for document_id in self
+            .get_document_ids(account_id, Collection::AddressBook)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+        {
+            if let Some(book) = self
+                .get_archive(account_id, Collection::AddressBook, document_id)
+                .await
+                .caused_by(trc::location!())?
+            {
+                let book = book
+                    .deserialize::<AddressBook>()
+                    .caused_by(trc::location!())?;
+                containers.insert(book.name, document_id);
+            }
+        }
+        for document_id in self
+            .get_document_ids(account_id, Collection::ContactCard)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+        {
+            if let Some(card) = self
+                .get_archive(account_id, Collection::ContactCard, document_id)
+                .await
+                .caused_by(trc::location!())?
+            {
+                let card = card
+                    .deserialize::<ContactCard>()
+                    .caused_by(trc::location!())?;
+                existing_names.extend(card.names.into_iter().map(|n| (n.parent_id, n.name)));
+            }
+        }
+        for item in archive.address_books {
+            if containers.contains_key(&item.name) {
+                result
+                    .skipped
+                    .push(format!("Address book {:?} already exists", item.name));
+                continue;
+            }
+            let book = AddressBook {
+                name: item.name.clone(),
+                display_name: item.display_name,
+                description: item.description,
+                sort_order: item.sort_order,
+                is_default: item.is_default,
+                acls: self.import_acls(item.acls, &mut principals).await?,
+                created: item.created,
+                modified: item.modified,
+                ..Default::default()
+            };
+            let document_id = self
+                .write_document(&access_token, account_id, Collection::AddressBook, book)
+                .await?;
+            containers.insert(item.name, document_id);
+            result.address_books += 1;
+        }
+
+        // Import cards
+        for item in archive.cards {
+            let Some(names) = import_names(item.names, &containers, &existing_names) else {
+                result
+                    .skipped
+                    .push("Contact has no new location to import into".to_string());
+                continue;
+            };
+            let Entry::VCard(vcard) = Parser::new(&item.data).entry() else {
+                result
+                    .skipped
+                    .push(format!("Contact {:?} is not valid vCard", names[0].name));
+                continue;
+            };
+            let card = ContactCard {
+                names,
+                display_name: item.display_name,
+                card: vcard,
+                size: item.data.len() as u32,
+                created: item.created,
+                modified: item.modified,
+                ..Default::default()
+            };
+            self.write_document(&access_token, account_id, Collection::ContactCard, card)
+                .await?;
+            result.cards += 1;
+        }
+
+        // Import files, parents are always listed before their children
+        let mut nodes = HashMap::new();
+        for document_id in self
+            .get_document_ids(account_id, Collection::FileNode)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+        {
+            if let Some(node) = self
+                .get_archive(account_id, Collection::FileNode, document_id)
+                .await
+                .caused_by(trc::location!())?
+            {
+                nodes.insert(
+                    document_id,
+                    node.deserialize::<FileNode>().caused_by(trc::location!())?,
+                );
+            }
+        }
+        let mut paths = nodes
+            .iter()
+            .filter_map(|(document_id, node)| Some((node_path(node, &nodes)?, *document_id)))
+            .collect::<HashMap<_, _>>();
+        for item in archive.files {
+            let path = item.path.trim_matches('/');
+            let (parent_id, name) = match path.rsplit_once('/') {
+                Some((parent, name)) => match paths.get(parent) {
+                    Some(document_id) => (document_id + 1, name),
+                    None => {
+                        result
+                            .skipped
+                            .push(format!("File {path:?} has no parent folder"));
+                        continue;
+                    }
+                },
+                None => (0, path),
+            };
+            if name.is_empty() || paths.contains_key(path) {
+                result.skipped.push(format!("File {path:?} already exists"));
+                continue;
+            }
+            let file = if let Some(contents) = item.contents {
+                let bytes = STANDARD.decode(contents.as_bytes()).map_err(|err| {
+                    trc::EventType::Resource(trc::ResourceEvent::BadParameters)
+                        .from_base64_error(err)
+                })?;
+                Some(FileProperties {
+                    blob_hash: self
+                        .put_blob(account_id, &bytes, false)
+                        .await
+                        .caused_by(trc::location!())?
+                        .hash,
+                    size: bytes.len() as u32,
+                    media_type: item.media_type,
+                    executable: item.executable,
+                })
+            } else {
+                None
+            };
+            let node = FileNode {
+                parent_id,
+                name: name.to_string(),
+                display_name: item.display_name,
+                file,
+                created: item.created,
+                modified: item.modified,
+                dead_properties: Default::default(),
+                acls: self.import_acls(item.acls, &mut principals).await?,
+                bindings: Default::default(),
+                versions: Default::default(),
+                trash: None,
+            };
+            let document_id = self
+                .write_document(&access_token, account_id, Collection::FileNode, node)
+                .await?;
+            paths.insert(path.to_string(), document_id);
+            result.files += 1;
+        }
+
+        Ok(result)
+    }
+}
+
+pub(crate) trait GroupwareArchiveHelpers: Sync + Send {
+    fn export_acls(
+        &self,
+        acls: &[AclGrant],
+        principals: &mut HashMap<u32, Option<String>>,
+    ) -> impl Future<Output = trc::Result<Vec<AclExport>>> + Send;
+
+    fn import_acls(
+        &self,
+        acls: Vec<AclExport>,
+        principals: &mut HashMap<String, Option<u32>>,
+    ) -> impl Future<Output = trc::Result<Vec<AclGrant>>> + Send;
+
+    fn write_document<T>(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        collection: Collection,
+        object: T,
+    ) -> impl Future<Output = trc::Result<u32>> + Send
+    where
+        T: IndexableAndSerializableObject + Sync + Send;
+}
+
+impl GroupwareArchiveHelpers for Server {
+    async fn export_acls(
+        &self,
+        acls: &[AclGrant],
+        principals: &mut HashMap<u32, Option<String>>,
+    ) -> trc::Result<Vec<AclExport>> {
+        let mut result = Vec::with_capacity(acls.len());
+        for acl in acls {
+            let name = if let Some(name) = principals.get(&acl.account_id) {
+                name.clone()
+            } else {
+                let name = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_name(acl.account_id)
+                    .await
+                    .caused_by(trc::location!())?;
+                principals.insert(acl.account_id, name.clone());
+                name
+            };
+            if let Some(principal) = name {
+                result.push(AclExport {
+                    principal,
+                    rights: u64::from(acl.grants),
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    async fn import_acls(
+        &self,
+        acls: Vec<AclExport>,
+        principals: &mut HashMap<String, Option<u32>>,
+    ) -> trc::Result<Vec<AclGrant>> {
+        let mut result = Vec::with_capacity(acls.len());
+        for acl in acls {
+            let account_id = if let Some(account_id) = principals.get(&acl.principal) {
+                *account_id
+            } else {
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(&acl.principal)
+                    .await
+                    .caused_by(trc::location!())?;
+                principals.insert(acl.principal, account_id);
+                account_id
+            };
+            if let Some(account_id) = account_id {
+                result.push(AclGrant {
+                    account_id,
+                    grants: Bitmap::from(acl.rights),
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    async fn write_document<T>(
+        &self,
+        access_token: &AccessToken,
+        account_id: u32,
+        collection: Collection,
+        object: T,
+    ) -> trc::Result<u32>
+    where
+        T: IndexableAndSerializableObject + Sync + Send,
+    {
+        // Objects are written directly to preserve their original timestamps
+        let document_id = self
+            .store()
+            .assign_document_ids(account_id, collection, 1)
+            .await
+            .caused_by(trc::location!())?;
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(collection)
+            .create_document(document_id)
+            .custom(
+                ObjectIndexBuilder::<(), _>::new()
+                    .with_changes(object)
+                    .with_tenant_id(access_token),
+            )
+            .caused_by(trc::location!())?;
+        self.commit_batch(batch).await.caused_by(trc::location!())?;
+        Ok(document_id)
+    }
+}
+
+fn export_names(names: &[DavName], containers: &HashMap<u32, String>) -> Vec<ItemName> {
+    names
+        .iter()
+        .filter_map(|name| {
+            Some(ItemName {
+                container: containers.get(&name.parent_id)?.clone(),
+                name: name.name.clone(),
+            })
+        })
+        .collect()
+}
+
+fn import_names(
+    names: Vec<ItemName>,
+    containers: &HashMap<String, u32>,
+    existing: &HashSet<(u32, String)>,
+) -> Option<Vec<DavName>> {
+    let names = names
+        .into_iter()
+        .filter_map(|name| {
+            let parent_id = *containers.get(&name.container)?;
+            (!existing.contains(&(parent_id, name.name.clone()))).then_some(DavName {
+                name: name.name,
+                parent_id,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    (!names.is_empty()).then_some(names)
+}
+
+fn import_timezone(value: &str) -> Option<Timezone> {
+    if value.starts_with("BEGIN:") {
+        match Parser::new(value).entry() {
+            Entry::ICalendar(ical) => Some(Timezone::Custom(ical)),
+            _ => None,
+        }
+    } else {
+        parse_tz(value).map(|tz| Timezone::IANA(tz.as_id()))
+    }
+}
+
+fn node_path(node: &FileNode, nodes: &HashMap<u32, FileNode>) -> Option<String> {
+    let mut path = node.name.clone();
+    let mut parent_id = node.parent_id;
+
+    // Bounded walk guards against cycles in corrupted trees
+    for _ in 0..nodes.len() {
+        if parent_id == 0 {
+            return Some(path);
+        }
+        let parent = nodes.get(&(parent_id - 1))?;
+        path = format!("{}/{}", parent.name, path);
+        parent_id = parent.parent_id;
+    }
+
+    (parent_id == 0).then_some(path)
+}
@@ -0,0 +1,677 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Cursor, Read},
+};
+
+use calcard::{Entry, Parser, common::timezone::Tz};
+use common::{DavName, Server};
+use groupware::{
+    calendar::{
+        Calendar, CalendarEvent, CalendarEventData, CalendarPreferences, Timezone,
+        timezone::parse_tz,
+    },
+    contact::{AddressBook, ContactCard},
+};
+use jmap_proto::types::collection::Collection;
+use store::write::now;
+use trc::AddContext;
+
+use super::groupware::{GroupwareArchiveHelpers, ImportResult};
+
+/// Nested archives are only expanded this many levels deep.
+const MAX_ARCHIVE_DEPTH: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportSource {
+    GoogleTakeout,
+    ICloud,
+}
+
+pub trait ExternalExportImporter: Sync + Send {
+    /// Imports a Google Takeout or iCloud export, either a ZIP archive or a
+    /// single ICS or vCard file, into new calendars and a new address book.
+    fn import_external_export(
+        &self,
+        account_id: u32,
+        source: ExportSource,
+        data: &[u8],
+    ) -> impl Future<Output = trc::Result<ImportResult>> + Send;
+}
+
+impl ExportSource {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "google" => Some(ExportSource::GoogleTakeout),
+            "icloud" => Some(ExportSource::ICloud),
+            _ => None,
+        }
+    }
+
+    fn address_book_name(&self) -> (&'static str, &'static str) {
+        match self {
+            ExportSource::GoogleTakeout => ("google", "Google Contacts"),
+            ExportSource::ICloud => ("icloud", "iCloud Contacts"),
+        }
+    }
+}
+
+impl ExternalExportImporter for Server {
+    async fn import_external_export(
+        &self,
+        account_id: u32,
+        source: ExportSource,
+        data: &[u8],
+    ) -> trc::Result<ImportResult> {
+        let mut files = Vec::new();
+        collect_files(data, "export", 0, &mut files)?;
+
+        let access_token = self
+            .get_access_token(account_id)
+            .await
+            .caused_by(trc::location!())?;
+        let mut result = ImportResult::default();
+        let now = now() as i64;
+
+        // Import each calendar file into a new calendar
+        let mut calendar_names = self
+            .container_names(account_id, Collection::Calendar)
+            .await?;
+        for (file_name, contents) in files.iter().filter(|(name, _)| has_extension(name, "ics")) {
+            let calendar = split_icalendar(&unfold_lines(contents));
+            if calendar.objects.is_empty() {
+                result
+                    .skipped
+                    .push(format!("File {file_name:?} contains no events"));
+                continue;
+            }
+            let display_name = calendar
+                .name
+                .clone()
+                .unwrap_or_else(|| file_stem(file_name).to_string());
+            let name = unique_name(&mut calendar_names, &display_name);
+            let calendar_id = self
+                .write_document(
+                    &access_token,
+                    account_id,
+                    Collection::Calendar,
+                    Calendar {
+                        name,
+                        preferences: vec![CalendarPreferences {
+                            account_id,
+                            name: display_name,
+                            color: calendar.color.clone(),
+                            time_zone: calendar
+                                .time_zone
+                                .as_deref()
+                                .and_then(parse_tz)
+                                .map(|tz| Timezone::IANA(tz.as_id()))
+                                .unwrap_or_default(),
+                            ..Default::default()
+                        }],
+                        created: now,
+                        modified: now,
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            result.calendars += 1;
+
+            let mut item_names = HashSet::new();
+            for (uid, data) in calendar.objects() {
+                if data.len() > self.core.groupware.max_ical_size {
+                    result
+                        .skipped
+                        .push(format!("Event {uid:?} exceeds the maximum size"));
+                    continue;
+                }
+                let Entry::ICalendar(ical) = Parser::new(&data).entry() else {
+                    result
+                        .skipped
+                        .push(format!("Event {uid:?} is not valid iCalendar"));
+                    continue;
+                };
+                let event = CalendarEvent {
+                    names: vec![DavName {
+                        name: item_name(&mut item_names, uid, "ics"),
+                        parent_id: calendar_id,
+                    }],
+                    data: CalendarEventData::new(
+                        ical,
+                        Tz::Floating,
+                        self.core.groupware.max_ical_instances,
+                    ),
+                    size: data.len() as u32,
+                    created: now,
+                    modified: now,
+                    ..Default::default()
+                };
+                self.write_document(&access_token, account_id, Collection::CalendarEvent, event)
+                    .await?;
+                result.events += 1;
+            }
+        }
+
+        // Import all contacts into a single address book, Takeout repeats
+        // contacts in one file per label so they are deduplicated by UID
+        let mut cards = Vec::new();
+        let mut seen = HashSet::new();
+        for (_, contents) in files.iter().filter(|(name, _)| has_extension(name, "vcf")) {
+            for card in split_vcards(&unfold_lines(contents)) {
+                if seen.insert(card.uid.clone().unwrap_or_else(|| card.data.clone())) {
+                    cards.push(card);
+                }
+            }
+        }
+        if !cards.is_empty() {
+            let mut book_names = self
+                .container_names(account_id, Collection::AddressBook)
+                .await?;
+            let (name, display_name) = source.address_book_name();
+            let addressbook_id = self
+                .write_document(
+                    &access_token,
+                    account_id,
+                    Collection::AddressBook,
+                    AddressBook {
+                        name: unique_name(&mut book_names, name),
+                        display_name: display_name.to_string().into(),
+                        created: now,
+                        modified: now,
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            result.address_books += 1;
+
+            let mut item_names = HashSet::new();
+            for (idx, card) in cards.into_iter().enumerate() {
+                let uid = card.uid.unwrap_or_else(|| format!("contact-{idx}"));
+                if card.data.len() > self.core.groupware.max_vcard_size {
+                    result
+                        .skipped
+                        .push(format!("Contact {uid:?} exceeds the maximum size"));
+                    continue;
+                }
+                let Entry::VCard(vcard) = Parser::new(&card.data).entry() else {
+                    result
+                        .skipped
+                        .push(format!("Contact {uid:?} is not valid vCard"));
+                    continue;
+                };
+                let card = ContactCard {
+                    names: vec![DavName {
+                        name: item_name(&mut item_names, &uid, "vcf"),
+                        parent_id: addressbook_id,
+                    }],
+                    card: vcard,
+                    size: card.data.len() as u32,
+                    created: now,
+                    modified: now,
+                    ..Default::default()
+                };
+                self.write_document(&access_token, account_id, Collection::ContactCard, card)
+                    .await?;
+                result.cards += 1;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+trait ContainerNames: Sync + Send {
+    fn container_names(
+        &self,
+        account_id: u32,
+        collection: Collection,
+    ) -> impl Future<Output = trc::Result<HashSet<String>>> + Send;
+}
+
+impl ContainerNames for Server {
+    async fn container_names(
+        &self,
+        account_id: u32,
+        collection: Collection,
+    ) -> trc::Result<HashSet<String>> {
+        let mut names = HashSet::new();
+        for document_id in self
+            .get_document_ids(account_id, collection)
+            .await
+            .caused_by(trc::location!())?
+            .unwrap_or_default()
+        {
+            let Some(archive) = self
+                .get_archive(account_id, collection, document_id)
+                .await
+                .caused_by(trc::location!())?
+            else {
+                continue;
+            };
+            names.insert(if collection == Collection::Calendar {
+                archive
+                    .deserialize::<Calendar>()
+                    .caused_by(trc::location!())?
+                    .name
+            } else {
+                archive
+                    .deserialize::<AddressBook>()
+                    .caused_by(trc::location!())?
+                    .name
+            });
+        }
+        Ok(names)
+    }
+}
+
+fn collect_files(
+    data: &[u8],
+    name: &str,
+    depth: usize,
+    files: &mut Vec<(String, String)>,
+) -> trc::Result<()> {
+    if data.starts_with(b"PK\x03\x04") {
+        if depth >= MAX_ARCHIVE_DEPTH {
+            return Ok(());
+        }
+        let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(|err| {
+            trc::ResourceEvent::BadParameters
+                .into_err()
+                .reason(err)
+                .details("Failed to read ZIP archive")
+        })?;
+        for idx in 0..archive.len() {
+            let mut file = archive.by_index(idx).map_err(|err| {
+                trc::ResourceEvent::BadParameters
+                    .into_err()
+                    .reason(err)
+                    .details("Failed to read ZIP archive")
+            })?;
+            let name = file.name().to_string();
+            if file.is_dir()
+                || !["ics", "vcf", "zip"]
+                    .iter()
+                    .any(|ext| has_extension(&name, ext))
+            {
+                continue;
+            }
+            let mut contents = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut contents).map_err(|err| {
+                trc::ResourceEvent::BadParameters
+                    .into_err()
+                    .reason(err)
+                    .details("Failed to read ZIP archive")
+            })?;
+            // iCloud bundles each calendar and contact export in its own archive
+            collect_files(&contents, &name, depth + 1, files)?;
+        }
+    } else if has_extension(name, "ics") || has_extension(name, "vcf") {
+        files.push((name.to_string(), String::from_utf8_lossy(data).into_owned()));
+    } else {
+        // Single file uploads are identified by their contents
+        let contents = String::from_utf8_lossy(data);
+        let header = contents
+            .trim_start()
+            .split_once(['\r', '\n'])
+            .map_or("", |(line, _)| line.trim_end());
+        if header.eq_ignore_ascii_case("BEGIN:VCALENDAR") {
+            files.push((format!("{name}.ics"), contents.into_owned()));
+        } else if header.eq_ignore_ascii_case("BEGIN:VCARD") {
+            files.push((format!("{name}.vcf"), contents.into_owned()));
+        }
+    }
+
+    Ok(())
+}
+
+struct ParsedCalendar {
+    name: Option<String>,
+    color: Option<String>,
+    time_zone: Option<String>,
+    header: Vec<String>,
+    timezones: Vec<(String, Vec<String>)>,
+    objects: Vec<(String, Vec<Component>)>,
+}
+
+struct Component {
+    recurrence_id: Option<String>,
+    sequence: u32,
+    lines: Vec<String>,
+}
+
+struct ParsedCard {
+    uid: Option<String>,
+    data: String,
+}
+
+impl ParsedCalendar {
+    fn objects(&self) -> impl Iterator<Item = (&str, String)> {
+        self.objects.iter().map(|(uid, components)| {
+            let mut data = String::with_capacity(1024);
+            data.push_str("BEGIN:VCALENDAR\r\n");
+            for line in &self.header {
+                data.push_str(line);
+                data.push_str("\r\n");
+            }
+
+            // Only include the time zones referenced by the object
+            for (tz_id, lines) in &self.timezones {
+                let needle = format!("TZID={tz_id}");
+                let quoted_needle = format!("TZID=\"{tz_id}\"");
+                if components.iter().any(|component| {
+                    component
+                        .lines
+                        .iter()
+                        .any(|line| line.contains(&needle) || line.contains(&quoted_needle))
+                }) {
+                    for line in lines {
+                        data.push_str(line);
+                        data.push_str("\r\n");
+                    }
+                }
+            }
+
+            for component in components {
+                for line in &component.lines {
+                    data.push_str(line);
+                    data.push_str("\r\n");
+                }
+            }
+            data.push_str("END:VCALENDAR\r\n");
+
+            (uid.as_str(), data)
+        })
+    }
+}
+
+/// Unfolds content lines, also joining the base64 continuation lines that
+/// some Apple exporters write without the leading whitespace.
+fn unfold_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut is_binary = false;
+
+    for line in text.lines() {
+        if let Some(last) = lines.last_mut() {
+            if let Some(rest) = line.strip_prefix([' ', '\t']) {
+                last.push_str(rest);
+                continue;
+            } else if is_binary
+                && !line.is_empty()
+                && line
+                    .bytes()
+                    .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, b'+' | b'/' | b'='))
+            {
+                last.push_str(line);
+                continue;
+            }
+        }
+
+        if !line.is_empty() {
+            is_binary = property_params(line)
+                .to_ascii_uppercase()
+                .contains("ENCODING=B");
+            lines.push(line.to_string());
+        }
+    }
+
+    lines
+}
+
+fn split_icalendar(lines: &[String]) -> ParsedCalendar {
+    let mut calendar = ParsedCalendar {
+        name: None,
+        color: None,
+        time_zone: None,
+        header: Vec::new(),
+        timezones: Vec::new(),
+        objects: Vec::new(),
+    };
+    let mut object_ids: HashMap<String, usize> = HashMap::new();
+    let mut block: Option<(String, usize, Vec<String>)> = None;
+    let mut has_version = false;
+
+    for line in lines {
+        let (name, value) = property(line);
+
+        if let Some((_, depth, block_lines)) = &mut block {
+            block_lines.push(line.clone());
+            if name == "BEGIN" {
+                *depth += 1;
+            } else if name == "END" {
+                *depth = depth.saturating_sub(1);
+                if *depth == 0 {
+                    let (block_type, _, block_lines) = block.take().unwrap();
+                    add_component(&mut calendar, &mut object_ids, &block_type, block_lines);
+                }
+            }
+            continue;
+        }
+
+        match name.as_str() {
+            "BEGIN" if !value.eq_ignore_ascii_case("VCALENDAR") => {
+                block = Some((value.to_ascii_uppercase(), 1, vec![line.clone()]));
+            }
+            "VERSION" => {
+                has_version = true;
+                calendar.header.push(line.clone());
+            }
+            "PRODID" | "CALSCALE" => {
+                calendar.header.push(line.clone());
+            }
+            "X-WR-CALNAME" if !value.is_empty() => {
+                calendar.name = Some(unescape_text(value));
+            }
+            "X-WR-TIMEZONE" if !value.is_empty() => {
+                calendar.time_zone = Some(value.to_string());
+            }
+            "X-APPLE-CALENDAR-COLOR" if !value.is_empty() => {
+                calendar.color = Some(value.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    if !has_version {
+        calendar.header.insert(0, "VERSION:2.0".to_string());
+    }
+
+    calendar
+}
+
+fn add_component(
+    calendar: &mut ParsedCalendar,
+    object_ids: &mut HashMap<String, usize>,
+    block_type: &str,
+    lines: Vec<String>,
+) {
+    // Only the top level properties of the component are inspected
+    let mut uid = None;
+    let mut tz_id = None;
+    let mut recurrence_id = None;
+    let mut sequence = 0;
+    let mut depth = 0;
+    for line in &lines {
+        let (name, value) = property(line);
+        match name.as_str() {
+            "BEGIN" => depth += 1,
+            "END" => depth -= 1,
+            "UID" if depth == 1 => uid = Some(value.to_string()),
+            "TZID" if depth == 1 => tz_id = Some(value.to_string()),
+            "RECURRENCE-ID" if depth == 1 => recurrence_id = Some(value.to_string()),
+            "SEQUENCE" if depth == 1 => sequence = value.trim().parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    match block_type {
+        "VTIMEZONE" => {
+            if let Some(tz_id) = tz_id {
+                if !calendar.timezones.iter().any(|(id, _)| id == &tz_id) {
+                    calendar.timezones.push((tz_id, lines));
+                }
+            }
+        }
+        "VEVENT" | "VTODO" | "VJOURNAL" => {
+            let Some(uid) = uid else {
+                return;
+            };
+            let component = Component {
+                recurrence_id,
+                sequence,
+                lines,
+            };
+            let idx = *object_ids.entry(uid.clone()).or_insert_with(|| {
+                calendar.objects.push((uid, Vec::new()));
+                calendar.objects.len() - 1
+            });
+            let components = &mut calendar.objects[idx].1;
+
+            // Exports may repeat a UID, keep the most recent revision of each instance
+            if let Some(existing) = components
+                .iter_mut()
+                .find(|existing| existing.recurrence_id == component.recurrence_id)
+            {
+                if component.sequence >= existing.sequence {
+                    *existing = component;
+                }
+            } else {
+                components.push(component);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn split_vcards(lines: &[String]) -> Vec<ParsedCard> {
+    let mut cards = Vec::new();
+    let mut current: Option<ParsedCard> = None;
+
+    for line in lines {
+        let (name, value) = property(line);
+        match name.as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VCARD") => {
+                current = Some(ParsedCard {
+                    uid: None,
+                    data: String::with_capacity(512),
+                });
+            }
+            _ => {}
+        }
+        let Some(card) = &mut current else {
+            continue;
+        };
+
+        // Normalize the vCard 2.1 style binary encoding used in older exports
+        let params = property_params(line);
+        if let Some(pos) = params.to_ascii_uppercase().find("ENCODING=BASE64") {
+            card.data.push_str(&line[..pos]);
+            card.data.push_str("ENCODING=b");
+            card.data.push_str(&line[pos + 15..]);
+        } else {
+            card.data.push_str(line);
+        }
+        card.data.push_str("\r\n");
+
+        match name.as_str() {
+            "UID" if !value.is_empty() => {
+                card.uid = Some(value.to_string());
+            }
+            "END" if value.eq_ignore_ascii_case("VCARD") => {
+                cards.extend(current.take());
+            }
+            _ => {}
+        }
+    }
+
+    cards
+}
+
+/// Returns the upper-cased property name, without any vCard group prefix,
+/// and the raw value of a content line.
+fn property(line: &str) -> (String, &str) {
+    let (head, value) = line.split_once(':').unwrap_or((line, ""));
+    let name = head.split_once(';').map_or(head, |(name, _)| name);
+    let name = name.rsplit_once('.').map_or(name, |(_, name)| name);
+    (name.trim().to_ascii_uppercase(), value)
+}
+
+fn property_params(line: &str) -> &str {
+    line.split_once(':').map_or(line, |(head, _)| head)
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            match chars.next() {
+                Some('n' | 'N') => result.push(' '),
+                Some(ch) => result.push(ch),
+                None => {}
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+    result.trim().to_string()
+}
+
+fn unique_name(existing: &mut HashSet<String>, display_name: &str) -> String {
+    let mut base = String::with_capacity(display_name.len());
+    for ch in display_name.chars() {
+        if ch.is_alphanumeric() {
+            base.extend(ch.to_lowercase());
+        } else if !base.is_empty() && !base.ends_with('-') {
+            base.push('-');
+        }
+    }
+    let base = base.trim_end_matches('-');
+    let base = if base.is_empty() { "imported" } else { base };
+
+    let mut name = base.to_string();
+    let mut counter = 1;
+    while existing.contains(&name) {
+        counter += 1;
+        name = format!("{base}-{counter}");
+    }
+    existing.insert(name.clone());
+    name
+}
+
+fn item_name(existing: &mut HashSet<String>, uid: &str, extension: &str) -> String {
+    let base = uid
+        .chars()
+        .take(200)
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.' | '@') {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    let mut name = format!("{base}.{extension}");
+    let mut counter = 1;
+    while existing.contains(&name) {
+        counter += 1;
+        name = format!("{base}-{counter}.{extension}");
+    }
+    existing.insert(name.clone());
+    name
+}
+
+fn has_extension(name: &str, extension: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(_, ext)| ext.eq_ignore_ascii_case(extension))
+}
+
+fn file_stem(name: &str) -> &str {
+    let name = name.rsplit_once('/').map_or(name, |(_, name)| name);
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
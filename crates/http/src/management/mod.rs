@@ -9,6 +9,8 @@ pub mod dkim;
 pub mod dns;
 #[cfg(feature = "enterprise")]
 pub mod enterprise;
+pub mod groupware;
+pub mod groupware_import;
 pub mod log;
 pub mod principal;
 pub mod queue;
@@ -90,7 +92,13 @@ impl ManagementApi for Server {
         access_token: Arc<AccessToken>,
         session: &HttpSessionData,
     ) -> trc::Result<HttpResponse> {
-        let body = fetch_body(req, 1024 * 1024, session.session_id).await;
+        // Groupware archives can be considerably larger than other requests
+        let max_size = if req.uri().path().starts_with("/api/store/groupware/") {
+            self.core.jmap.upload_max_size
+        } else {
+            1024 * 1024
+        };
+        let body = fetch_body(req, max_size, session.session_id).await;
         let path = req.uri().path().split('/').skip(2).collect::<Vec<_>>();
 
         match path.first().copied().unwrap_or_default() {
@@ -16,6 +16,7 @@ use directory::{
         },
     },
 };
+use groupware::provision::GroupwareProvisioning;
 use http_proto::{request::decode_path_element, *};
 use hyper::{Method, header};
 use serde_json::json;
@@ -160,6 +161,7 @@ impl PrincipalManager for Server {
                 };
 
                 // Create principal
+                let typ = principal.typ();
                 let result = self
                     .core
                     .storage
@@ -184,6 +186,18 @@ impl PrincipalManager for Server {
                 self.increment_token_revision(result.changed_principals)
                     .await;
 
+                // Create default groupware collections
+                if self.core.groupware.provisioning_enabled
+                    && matches!(typ, Type::Individual | Type::Group)
+                {
+                    if let Err(err) = self.provision_groupware(result.id).await {
+                        trc::error!(
+                            err.account_id(result.id)
+                                .details("Failed to provision groupware collections")
+                        );
+                    }
+                }
+
                 Ok(JsonResponse::new(json!({
                     "data": result.id,
                 }))
@@ -406,6 +420,14 @@ impl PrincipalManager for Server {
                                     trc::error!(err.details("Failed to delete FTS index"));
                                 }
 
+                                // Remove groupware grants
+                                if let Err(err) = server.deprovision_groupware(principal.id()).await
+                                {
+                                    trc::error!(
+                                        err.details("Failed to deprovision groupware collections")
+                                    );
+                                }
+
                                 // Delete bayes model
                                 if has_bayes {
                                     let mut key =
@@ -518,6 +540,9 @@ impl PrincipalManager for Server {
                             // Remove FTS index
                             self.core.storage.fts.remove_all(account_id).await?;
 
+                            // Remove groupware grants
+                            self.deprovision_groupware(account_id).await?;
+
                             // Delete bayes model
                             if self
                                 .core
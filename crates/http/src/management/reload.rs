@@ -56,6 +56,23 @@ impl ManageReload for Server {
                 }))
                 .into_http_response())
             }
+            (Some("dav"), &Method::GET) => {
+                let result = self.reload_groupware().await?;
+                if !UrlParams::new(req.uri().query()).has_key("dry-run") {
+                    if let Some(core) = result.new_core {
+                        // Update core
+                        self.inner.shared_core.store(core.into());
+
+                        self.cluster_broadcast(BroadcastEvent::ReloadGroupware)
+                            .await;
+                    }
+                }
+
+                Ok(JsonResponse::new(json!({
+                    "data": result.config,
+                }))
+                .into_http_response())
+            }
             (Some("certificate"), &Method::GET) => Ok(JsonResponse::new(json!({
                 "data": self.reload_certificates().await?.config,
             }))
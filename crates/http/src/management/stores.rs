@@ -17,8 +17,12 @@ use directory::{
     backend::internal::manage::{self, ManageDirectory},
 };
 use email::message::{ingest::EmailIngest, metadata::MessageData};
+use groupware::{cache::GroupwareCache, check::GroupwareConsistency};
 use hyper::Method;
-use jmap_proto::types::{collection::Collection, property::Property};
+use jmap_proto::types::{
+    collection::{Collection, SyncCollection},
+    property::Property,
+};
 use serde_json::json;
 use services::index::Indexer;
 use store::{
@@ -32,6 +36,10 @@ use http_proto::{request::decode_path_element, *};
 
 #[cfg(feature = "enterprise")]
 use super::enterprise::undelete::UndeleteApi;
+use super::groupware::{
+    CollectionQuota, GroupwareArchive, GroupwareArchiveManager, QuotaCollection,
+};
+use super::groupware_import::{ExportSource, ExternalExportImporter};
 use std::future::Future;
 
 pub trait ManageStore: Sync + Send {
@@ -181,6 +189,7 @@ impl ManageStore for Server {
                     Some("lock-queue-report") => vec![KV_LOCK_QUEUE_REPORT].into(),
                     Some("lock-email-task") => vec![KV_LOCK_EMAIL_TASK].into(),
                     Some("lock-housekeeper") => vec![KV_LOCK_HOUSEKEEPER].into(),
+                    Some("calendar-mirror") => vec![KV_CALENDAR_MIRROR].into(),
                     _ => None,
                 };
 
@@ -209,6 +218,294 @@ impl ManageStore for Server {
                 self.housekeeper_request(HousekeeperEvent::Purge(PurgeType::Account(account_id)))
                     .await
             }
+            (Some("groupware"), Some(account), None, &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::GroupwareExport)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": self.export_groupware(account_id).await?,
+                }))
+                .into_http_response())
+            }
+            (Some("groupware"), Some(account), None, &Method::POST) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::GroupwareImport)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+                let archive =
+                    serde_json::from_slice::<GroupwareArchive>(body.as_deref().unwrap_or_default())
+                        .map_err(|err| {
+                            trc::EventType::Resource(trc::ResourceEvent::BadParameters)
+                                .from_json_error(err)
+                        })?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": self.import_groupware(account_id, archive).await?,
+                }))
+                .into_http_response())
+            }
+            (
+                Some("groupware"),
+                Some(account),
+                Some(source @ ("google" | "icloud")),
+                &Method::POST,
+            ) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::GroupwareImport)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+                let source = ExportSource::parse(source).unwrap();
+
+                Ok(JsonResponse::new(json!({
+                    "data": self
+                        .import_external_export(account_id, source, body.as_deref().unwrap_or_default())
+                        .await?,
+                }))
+                .into_http_response())
+            }
+            (Some("groupware"), Some(account), Some("rebuild"), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::GroupwareRebuild)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+                let account_token = self.get_access_token(account_id).await?;
+                let mut result = serde_json::Map::new();
+
+                for collection in [
+                    SyncCollection::Calendar,
+                    SyncCollection::AddressBook,
+                    SyncCollection::FileNode,
+                ] {
+                    let repair = self
+                        .rebuild_dav_resources(&account_token, account_id, collection)
+                        .await?;
+                    result.insert(
+                        collection.as_str().to_string(),
+                        json!({
+                            "missing": repair.missing,
+                            "stale": repair.stale,
+                            "changed": repair.changed,
+                            "previousChangeId": repair.previous_change_id,
+                            "changeId": repair.change_id,
+                        }),
+                    );
+                }
+
+                Ok(JsonResponse::new(json!({
+                    "data": result,
+                }))
+                .into_http_response())
+            }
+            (Some("groupware"), Some(account), Some("reset-sync"), &Method::POST) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::GroupwareRebuild)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+                let account_token = self.get_access_token(account_id).await?;
+                let mut result = serde_json::Map::new();
+
+                for collection in [
+                    SyncCollection::Calendar,
+                    SyncCollection::AddressBook,
+                    SyncCollection::FileNode,
+                ] {
+                    let sync_epoch = self
+                        .reset_sync_epoch(&account_token, account_id, collection)
+                        .await?;
+                    result.insert(collection.as_str().to_string(), json!(sync_epoch));
+                }
+
+                Ok(JsonResponse::new(json!({
+                    "data": result,
+                }))
+                .into_http_response())
+            }
+            (Some("groupware"), Some(account), Some("check"), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::GroupwareCheck)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+                let repair = UrlParams::new(req.uri().query())
+                    .parse("repair")
+                    .unwrap_or(false);
+                let report = self.check_groupware(account_id, repair).await?;
+                let mut hierarchy = serde_json::Map::new();
+                for (collection, rebuild) in report.hierarchy {
+                    hierarchy.insert(
+                        collection.as_str().to_string(),
+                        json!({
+                            "missing": rebuild.missing,
+                            "stale": rebuild.stale,
+                            "changed": rebuild.changed,
+                            "previousChangeId": rebuild.previous_change_id,
+                            "changeId": rebuild.change_id,
+                        }),
+                    );
+                }
+
+                Ok(JsonResponse::new(json!({
+                    "data": {
+                        "orphanedEvents": report.orphaned_events,
+                        "orphanedCards": report.orphaned_cards,
+                        "orphanedFiles": report.orphaned_files,
+                        "danglingAcls": report.dangling_acls.into_iter().map(|acl| json!({
+                            "collection": acl.collection.as_str(),
+                            "documentId": acl.document_id,
+                            "principalId": acl.principal_id,
+                        })).collect::<Vec<_>>(),
+                        "hierarchy": hierarchy,
+                        "repaired": repair,
+                    },
+                }))
+                .into_http_response())
+            }
+            (Some("groupware"), Some(account), Some("usage"), &Method::GET) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::GroupwareUsage)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+                let account_token = self.get_access_token(account_id).await?;
+                let max_largest = UrlParams::new(req.uri().query())
+                    .parse("largest")
+                    .unwrap_or(10usize);
+                let mut result = serde_json::Map::new();
+
+                for collection in [
+                    SyncCollection::Calendar,
+                    SyncCollection::AddressBook,
+                    SyncCollection::FileNode,
+                ] {
+                    let resources = self
+                        .fetch_dav_resources(&account_token, account_id, collection)
+                        .await?;
+                    let mut containers = resources
+                        .tree_with_depth(usize::MAX)
+                        .filter(|resource| resource.is_container())
+                        .collect::<Vec<_>>();
+                    containers.sort_unstable_by(|a, b| a.path().cmp(b.path()));
+
+                    result.insert(
+                        collection.as_str().to_string(),
+                        containers
+                            .into_iter()
+                            .map(|container| {
+                                let usage = resources.usage(container, max_largest);
+                                json!({
+                                    "path": container.path(),
+                                    "bytes": usage.bytes,
+                                    "items": usage.items,
+                                    "largest": usage.largest.into_iter().map(|item| json!({
+                                        "path": item.path(),
+                                        "size": item.size(),
+                                    })).collect::<Vec<_>>(),
+                                })
+                            })
+                            .collect::<Vec<_>>()
+                            .into(),
+                    );
+                }
+
+                Ok(JsonResponse::new(json!({
+                    "data": result,
+                }))
+                .into_http_response())
+            }
+            (Some("groupware"), Some(account), Some("quota"), &Method::POST) => {
+                // Validate the access token
+                access_token.assert_has_permission(Permission::GroupwareQuota)?;
+
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+                let request =
+                    serde_json::from_slice::<CollectionQuota>(body.as_deref().unwrap_or_default())
+                        .map_err(|err| {
+                            trc::EventType::Resource(trc::ResourceEvent::BadParameters)
+                                .from_json_error(err)
+                        })?;
+                let (collection, sync_collection) = match request.collection {
+                    QuotaCollection::Calendar => (Collection::Calendar, SyncCollection::Calendar),
+                    QuotaCollection::File => (Collection::FileNode, SyncCollection::FileNode),
+                };
+
+                // Map the path to a calendar or folder
+                let account_token = self.get_access_token(account_id).await?;
+                let document_id = self
+                    .fetch_dav_resources(&account_token, account_id, sync_collection)
+                    .await?
+                    .by_path(request.path.trim_matches('/'))
+                    .filter(|resource| resource.is_container())
+                    .map(|resource| resource.document_id())
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+
+                let mut batch = BatchBuilder::new();
+                batch
+                    .with_account_id(account_id)
+                    .with_collection(collection)
+                    .update_document(document_id);
+                if let Some(quota) = request.quota {
+                    batch.set(Property::Quota, quota.serialize());
+                } else {
+                    batch.clear(Property::Quota);
+                }
+                self.store()
+                    .write(batch.build_all())
+                    .await
+                    .caused_by(trc::location!())?;
+
+                Ok(JsonResponse::new(json!({
+                    "data": (),
+                }))
+                .into_http_response())
+            }
             (Some("reindex"), id, None, &Method::GET) => {
                 // Validate the access token
                 access_token.assert_has_permission(Permission::FtsReindex)?;
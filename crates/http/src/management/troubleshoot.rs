@@ -16,7 +16,7 @@ use common::{
     config::smtp::resolver::{Policy, Tlsa},
     psl,
 };
-use directory::backend::internal::manage;
+use directory::backend::internal::manage::{self, ManageDirectory};
 use http_body_util::{StreamBody, combinators::BoxBody};
 use hyper::{
     Method, StatusCode,
@@ -118,6 +118,63 @@ impl TroubleshootApi for Server {
                 }))
                 .into_http_response())
             }
+            ("dav", Some(account), method @ (&Method::GET | &Method::POST | &Method::DELETE)) => {
+                let account_id = self
+                    .core
+                    .storage
+                    .data
+                    .get_principal_id(decode_path_element(account).as_ref())
+                    .await?
+                    .ok_or_else(|| trc::ManageEvent::NotFound.into_err())?;
+
+                // Tenant administrators can only capture requests of members of their tenant
+                if !access_token.has_tenant_access(&*self.get_access_token(account_id).await?) {
+                    return Err(trc::SecurityEvent::Unauthorized
+                        .into_err()
+                        .details("Cannot capture requests of accounts outside of the tenant.")
+                        .ctx(trc::Key::AccountName, account.to_string()));
+                }
+
+                let response = match *method {
+                    Method::POST => {
+                        // Record DAV requests for the account for a limited time
+                        let duration = Duration::from_secs(
+                            params
+                                .parse::<u64>("duration")
+                                .unwrap_or(15 * 60)
+                                .clamp(1, 24 * 60 * 60),
+                        );
+                        let max_body_size =
+                            params.parse::<usize>("max-body-size").unwrap_or(64 * 1024);
+                        self.start_dav_capture(account_id, duration, max_body_size);
+                        json!({
+                            "expires": duration.as_secs(),
+                        })
+                    }
+                    Method::GET => {
+                        let (expires, requests) = self
+                            .dav_captured_requests(account_id)
+                            .ok_or_else(|| trc::ResourceEvent::NotFound.into_err())?;
+                        json!({
+                            "expires": expires.map(|expires| expires.as_secs()),
+                            "requests": requests,
+                        })
+                    }
+                    _ => {
+                        let requests = self
+                            .stop_dav_capture(account_id)
+                            .ok_or_else(|| trc::ResourceEvent::NotFound.into_err())?;
+                        json!({
+                            "requests": requests,
+                        })
+                    }
+                };
+
+                Ok(JsonResponse::new(json!({
+                        "data": response,
+                }))
+                .into_http_response())
+            }
             _ => Err(trc::ResourceEvent::NotFound.into_err()),
         }
     }
@@ -14,7 +14,16 @@ use common::{
     listener::{SessionData, SessionManager, SessionStream},
     manager::webadmin::Resource,
 };
-use dav::{DavMethod, request::DavRequestHandler};
+use dav::{
+    DavMethod,
+    common::{
+        apns::DavApnsHandler,
+        event_source::DavEventSourceHandler,
+        options::{DavOptionsHandler, dav_compliance},
+        push::DavPushUnregisterHandler,
+    },
+    request::DavRequestHandler,
+};
 use directory::Permission;
 use groupware::DavResourceName;
 use http_proto::{
@@ -206,29 +215,77 @@ impl ParseHttp for Server {
                 }
             }
             "dav" => {
+                let resource = path.next();
+                if resource == Some("eventsource") && req.method() == Method::GET {
+                    // Authenticate request
+                    let (_in_flight, access_token) =
+                        self.authenticate_headers(&req, &session, false).await?;
+
+                    // Change notifications are restricted to trusted clients
+                    access_token.assert_has_permission(Permission::DavEventSource)?;
+
+                    return self.handle_dav_event_source(req, access_token).await;
+                } else if resource == Some("apns") && req.method() == Method::POST {
+                    // Authenticate request
+                    let (_in_flight, access_token) =
+                        self.authenticate_headers(&req, &session, false).await?;
+
+                    return self
+                        .handle_apns_subscription(req, access_token, &session)
+                        .await;
+                } else if resource == Some("push") && req.method() == Method::DELETE {
+                    // Authenticate request
+                    let (_in_flight, access_token) =
+                        self.authenticate_headers(&req, &session, false).await?;
+
+                    return self.handle_push_unregister(&req, access_token).await;
+                }
+
                 let response = match (
-                    path.next().and_then(DavResourceName::parse),
+                    resource.and_then(DavResourceName::parse),
                     DavMethod::parse(req.method()),
                 ) {
-                    (Some(_), Some(DavMethod::OPTIONS)) => HttpResponse::new(StatusCode::OK)
-                        .with_header(
-                            "DAV",
-                            concat!(
-                                "1, 2, 3, access-control, extended-mkcol, calendar-access, ",
-                                "calendar-no-timezone, addressbook"
-                            ),
-                        )
-                        .with_header(
-                            "Allow",
-                            concat!(
-                                "OPTIONS, GET, HEAD, POST, PUT, DELETE, COPY, MOVE, MKCALENDAR, ",
-                                "MKCOL, PROPFIND, PROPPATCH, LOCK, UNLOCK, REPORT, ACL"
-                            ),
-                        ),
+                    (Some(resource), Some(DavMethod::OPTIONS)) => {
+                        // Anonymous requests are answered with all services
+                        let access_token = if req.headers().contains_key(header::AUTHORIZATION) {
+                            Some(self.authenticate_headers(&req, &session, false).await?.1)
+                        } else {
+                            None
+                        };
+
+                        if let Some(access_token) = &access_token {
+                            // Methods and classes depend on the resource and the caller's privileges
+                            if resource.is_enabled(access_token) {
+                                self.handle_dav_options(access_token, req.uri().path())
+                                    .await?
+                            } else {
+                                HttpResponse::new(StatusCode::NOT_FOUND)
+                            }
+                        } else {
+                            HttpResponse::new(StatusCode::OK)
+                                .with_header("DAV", dav_compliance(None))
+                                .with_header(
+                                    "Allow",
+                                    concat!(
+                                        "OPTIONS, GET, HEAD, POST, PUT, DELETE, COPY, MOVE, ",
+                                        "MKCALENDAR, MKCOL, PROPFIND, PROPPATCH, LOCK, UNLOCK, ",
+                                        "REPORT, ACL, BIND, UNBIND, REBIND"
+                                    ),
+                                )
+                        }
+                    }
                     (Some(resource), Some(method)) => {
                         // Authenticate request
                         let (_in_flight, access_token) =
                             self.authenticate_headers(&req, &session, false).await?;
+                        let access_token = self
+                            .impersonate_headers(&req, &session, access_token)
+                            .await?;
+
+                        // Services can be disabled per tenant or account
+                        if let Some(permission) = resource.service_permission() {
+                            access_token.assert_has_permission(permission)?;
+                        }
 
                         self.handle_dav_request(req, access_token, &session, resource, method)
                             .await
@@ -250,15 +307,25 @@ impl ParseHttp for Server {
                         .await
                         .map(|s| s.into_http_response());
                 }
-                ("caldav", _) => {
-                    return Ok(HttpResponse::new(StatusCode::TEMPORARY_REDIRECT)
-                        .with_no_cache()
-                        .with_location(DavResourceName::Cal.base_path()));
-                }
-                ("carddav", _) => {
+                (service @ ("caldav" | "carddav"), _) => {
+                    let resource = if service == "caldav" {
+                        DavResourceName::Cal
+                    } else {
+                        DavResourceName::Card
+                    };
+
+                    // Hide disabled services from authenticated clients
+                    if req.headers().contains_key(header::AUTHORIZATION) {
+                        let (_, access_token) =
+                            self.authenticate_headers(&req, &session, false).await?;
+                        if !resource.is_enabled(&access_token) {
+                            return Ok(HttpResponse::new(StatusCode::NOT_FOUND));
+                        }
+                    }
+
                     return Ok(HttpResponse::new(StatusCode::TEMPORARY_REDIRECT)
                         .with_no_cache()
-                        .with_location(DavResourceName::Card.base_path()));
+                        .with_location(resource.base_path()));
                 }
                 ("oauth-authorization-server", &Method::GET) => {
                     // Limit anonymous requests
@@ -53,6 +53,11 @@ pub enum RequestArguments {
     Identity,
     EmailSubmission,
     Quota,
+    Calendar,
+    CalendarEvent,
+    AddressBook,
+    ContactCard,
+    FileNode,
 }
 
 impl JsonObjectParser for ChangesRequest {
@@ -68,6 +73,11 @@ impl JsonObjectParser for ChangesRequest {
                 MethodObject::Identity => RequestArguments::Identity,
                 MethodObject::EmailSubmission => RequestArguments::EmailSubmission,
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::Calendar => RequestArguments::Calendar,
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::AddressBook => RequestArguments::AddressBook,
+                MethodObject::ContactCard => RequestArguments::ContactCard,
+                MethodObject::FileNode => RequestArguments::FileNode,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -45,6 +45,11 @@ pub enum RequestArguments {
     Principal,
     Quota,
     Blob(blob::GetArguments),
+    Calendar,
+    CalendarEvent,
+    AddressBook,
+    ContactCard,
+    FileNode,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -80,6 +85,11 @@ impl JsonObjectParser for GetRequest<RequestArguments> {
                 MethodObject::Principal => RequestArguments::Principal,
                 MethodObject::Blob => RequestArguments::Blob(Default::default()),
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::Calendar => RequestArguments::Calendar,
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::AddressBook => RequestArguments::AddressBook,
+                MethodObject::ContactCard => RequestArguments::ContactCard,
+                MethodObject::FileNode => RequestArguments::FileNode,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
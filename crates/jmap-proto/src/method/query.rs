@@ -100,6 +100,9 @@ pub enum Filter {
     IsActive(bool),
     Scope(String),
     ResourceType(String),
+    InCalendar(Id),
+    Uid(String),
+    InAddressBook(Id),
     _T(String),
 
     And,
@@ -137,6 +140,7 @@ pub enum SortProperty {
     AllInThreadHaveKeyword,
     SomeInThreadHaveKeyword,
     Used,
+    Start,
     _T(String),
 }
 
@@ -148,6 +152,9 @@ pub enum RequestArguments {
     SieveScript,
     Principal,
     Quota,
+    CalendarEvent,
+    ContactCard,
+    FileNode,
 }
 
 impl JsonObjectParser for QueryRequest<RequestArguments> {
@@ -163,6 +170,9 @@ impl JsonObjectParser for QueryRequest<RequestArguments> {
                 MethodObject::SieveScript => RequestArguments::SieveScript,
                 MethodObject::Principal => RequestArguments::Principal,
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::ContactCard => RequestArguments::ContactCard,
+                MethodObject::FileNode => RequestArguments::FileNode,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -425,6 +435,15 @@ pub fn parse_filter(parser: &mut Parser) -> trc::Result<Vec<Filter>> {
                                 .next_token::<String>()?
                                 .unwrap_string("resourceType")?,
                         ),
+                        (0x7261_646e_656c_6143_6e69, _) => Filter::InCalendar(
+                            parser.next_token::<Id>()?.unwrap_string("inCalendar")?,
+                        ),
+                        (0x0064_6975, _) => {
+                            Filter::Uid(parser.next_token::<String>()?.unwrap_string("uid")?)
+                        }
+                        (0x006b_6f6f_4273_7365_7264_6441_6e69, _) => Filter::InAddressBook(
+                            parser.next_token::<Id>()?.unwrap_string("inAddressBook")?,
+                        ),
                         _ => {
                             if parser.is_eof || parser.skip_string() {
                                 let filter = Filter::_T(
@@ -567,6 +586,7 @@ impl JsonObjectParser for SortProperty {
             0x4b65_7661_4864_6165_7268_546e_496c_6c61 => Ok(SortProperty::AllInThreadHaveKeyword),
             0x6576_6148_6461_6572_6854_6e49_656d_6f73 => Ok(SortProperty::SomeInThreadHaveKeyword),
             0x6465_7375 => Ok(SortProperty::Used),
+            0x0074_7261_7473 => Ok(SortProperty::Start),
             _ => {
                 if parser.is_eof || parser.skip_string() {
                     Ok(SortProperty::_T(
@@ -629,6 +649,9 @@ impl Display for Filter {
             Filter::IsActive(_) => "isActive",
             Filter::ResourceType(_) => "resourceType",
             Filter::Scope(_) => "scope",
+            Filter::InCalendar(_) => "inCalendar",
+            Filter::Uid(_) => "uid",
+            Filter::InAddressBook(_) => "inAddressBook",
             Filter::_T(v) => v.as_str(),
             Filter::And => "and",
             Filter::Or => "or",
@@ -660,6 +683,7 @@ impl Display for SortProperty {
             SortProperty::AllInThreadHaveKeyword => "allInThreadHaveKeyword",
             SortProperty::SomeInThreadHaveKeyword => "someInThreadHaveKeyword",
             SortProperty::Used => "used",
+            SortProperty::Start => "start",
             SortProperty::_T(s) => s,
         })
     }
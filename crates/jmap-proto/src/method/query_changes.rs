@@ -71,6 +71,9 @@ impl JsonObjectParser for QueryChangesRequest {
                 MethodObject::Mailbox => RequestArguments::Mailbox(Default::default()),
                 MethodObject::EmailSubmission => RequestArguments::EmailSubmission,
                 MethodObject::Quota => RequestArguments::Quota,
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::ContactCard => RequestArguments::ContactCard,
+                MethodObject::FileNode => RequestArguments::FileNode,
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
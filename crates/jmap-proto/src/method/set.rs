@@ -10,7 +10,7 @@ use utils::map::{bitmap::Bitmap, vec_map::VecMap};
 
 use crate::{
     error::set::{InvalidProperty, SetError},
-    object::{email_submission, mailbox, sieve},
+    object::{address_book, calendar, email_submission, file_node, mailbox, sieve},
     parser::{JsonObjectParser, Token, json::Parser},
     request::{
         RequestProperty, RequestPropertyParser,
@@ -52,6 +52,11 @@ pub enum RequestArguments {
     PushSubscription,
     SieveScript(sieve::SetArguments),
     VacationResponse,
+    Calendar(calendar::SetArguments),
+    CalendarEvent,
+    AddressBook(address_book::SetArguments),
+    ContactCard,
+    FileNode(file_node::SetArguments),
 }
 
 #[derive(Debug, Clone, Default, serde::Serialize)]
@@ -109,6 +114,11 @@ impl JsonObjectParser for SetRequest<RequestArguments> {
                 MethodObject::PushSubscription => RequestArguments::PushSubscription,
                 MethodObject::VacationResponse => RequestArguments::VacationResponse,
                 MethodObject::SieveScript => RequestArguments::SieveScript(Default::default()),
+                MethodObject::Calendar => RequestArguments::Calendar(Default::default()),
+                MethodObject::CalendarEvent => RequestArguments::CalendarEvent,
+                MethodObject::AddressBook => RequestArguments::AddressBook(Default::default()),
+                MethodObject::ContactCard => RequestArguments::ContactCard,
+                MethodObject::FileNode => RequestArguments::FileNode(Default::default()),
                 _ => {
                     return Err(trc::JmapEvent::UnknownMethod
                         .into_err()
@@ -213,7 +223,10 @@ impl JsonObjectParser for Object<SetValue> {
                     | Property::Location
                     | Property::Cid
                     | Property::Role
-                    | Property::PartId => parser
+                    | Property::PartId
+                    | Property::Color
+                    | Property::CalendarData
+                    | Property::CardData => parser
                         .next_token::<String>()?
                         .unwrap_string_or_null("")?
                         .map(|text| SetValue::Value(Value::Text(text)))
@@ -235,7 +248,9 @@ impl JsonObjectParser for Object<SetValue> {
                     Property::HasAttachment
                     | Property::IsSubscribed
                     | Property::IsEnabled
-                    | Property::IsActive => parser
+                    | Property::IsActive
+                    | Property::IsDefault
+                    | Property::IsVisible => parser
                         .next_token::<String>()?
                         .unwrap_bool_or_null("")?
                         .map(|bool| SetValue::Value(Value::Bool(bool)))
@@ -250,7 +265,7 @@ impl JsonObjectParser for Object<SetValue> {
                         .unwrap_string_or_null("")?
                         .map(SetValue::from)
                         .unwrap_or(SetValue::Value(Value::Null)),
-                    Property::MailboxIds => {
+                    Property::MailboxIds | Property::CalendarIds | Property::AddressBookIds => {
                         if key.patch.is_empty() {
                             SetValue::from(
                                 <SetValueMap<MaybeReference<Id, String>>>::parse(parser)?.values,
@@ -382,6 +397,9 @@ impl RequestPropertyParser for RequestArguments {
             RequestArguments::Mailbox(args) => args.parse(parser, property),
             RequestArguments::EmailSubmission(args) => args.parse(parser, property),
             RequestArguments::SieveScript(args) => args.parse(parser, property),
+            RequestArguments::Calendar(args) => args.parse(parser, property),
+            RequestArguments::AddressBook(args) => args.parse(parser, property),
+            RequestArguments::FileNode(args) => args.parse(parser, property),
             _ => Ok(false),
         }
     }
@@ -17,9 +17,12 @@ use crate::types::{
     value::{Object, Value},
 };
 
+pub mod address_book;
 pub mod blob;
+pub mod calendar;
 pub mod email;
 pub mod email_submission;
+pub mod file_node;
 pub mod mailbox;
 pub mod sieve;
 
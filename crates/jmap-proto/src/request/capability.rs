@@ -72,6 +72,8 @@ pub enum Capability {
     Blob = 1 << 8,
     #[serde(rename(serialize = "urn:ietf:params:jmap:quota"))]
     Quota = 1 << 9,
+    #[serde(rename(serialize = "urn:ietf:params:jmap:filenode"))]
+    FileNode = 1 << 10,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -341,6 +343,7 @@ impl JsonObjectParser for Capability {
                 0x0065_7665_6973 => Ok(Capability::Sieve),
                 0x626f_6c62 => Ok(Capability::Blob),
                 0x0061_746f_7571 => Ok(Capability::Quota),
+                0x6564_6f6e_656c_6966 => Ok(Capability::FileNode),
                 _ => Err(parser.error_capability()),
             },
             Err(err) if err.is_jmap_method_error() => Err(parser.error_capability()),
@@ -134,7 +134,12 @@ impl Request {
                                 | MethodObject::SieveScript
                                 | MethodObject::Principal
                                 | MethodObject::Quota
-                                | MethodObject::Blob,
+                                | MethodObject::Blob
+                                | MethodObject::Calendar
+                                | MethodObject::CalendarEvent
+                                | MethodObject::AddressBook
+                                | MethodObject::ContactCard
+                                | MethodObject::FileNode,
                             ) => GetRequest::parse(parser).map(RequestMethod::Get),
                             (MethodFunction::Get, MethodObject::SearchSnippet) => {
                                 GetSearchSnippetRequest::parse(parser)
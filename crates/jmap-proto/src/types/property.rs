@@ -119,6 +119,19 @@ pub enum Property {
     WarnLimit,
     SoftLimit,
     Scope,
+    CalendarIds,
+    Color,
+    IsDefault,
+    IsVisible,
+    Uid,
+    Title,
+    UtcStart,
+    UtcEnd,
+    CalendarData,
+    AddressBookIds,
+    CardData,
+    Created,
+    Modified,
     Digest(DigestProperty),
     Data(DataProperty),
     _T(String),
@@ -247,7 +260,10 @@ impl JsonObjectParser for SetProperty {
 
         if is_patch {
             match &property {
-                Property::MailboxIds | Property::Members => match Id::parse(parser) {
+                Property::MailboxIds
+                | Property::Members
+                | Property::CalendarIds
+                | Property::AddressBookIds => match Id::parse(parser) {
                     Ok(id) => {
                         patch.push(Value::Id(id));
                     }
@@ -335,6 +351,7 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x6c63 => Property::Acl,
             0x7365_7361_696c => Property::Aliases,
             0x7374_6e65_6d68_6361_7474 => Property::Attachments,
+            0x0073_6449_6b6f_6f42_7373_6572_6464 => Property::AddressBookIds,
             _ => return None,
         },
         b'b' => match hash {
@@ -349,6 +366,11 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x63 => Property::Cc,
             0x7465_7372_6168 => Property::Charset,
             0x6469 => Property::Cid,
+            0x7364_4972_6164_6e65_6c61 => Property::CalendarIds,
+            0x0061_7461_4472_6164_6e65_6c61 => Property::CalendarData,
+            0x726f_6c6f => Property::Color,
+            0x0061_7461_4464_7261 => Property::CardData,
+            0x6465_7461_6572 => Property::Created,
             _ => return None,
         },
         b'd' => match hash {
@@ -387,6 +409,8 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0065_7669_7463_4173 => Property::IsActive,
             0x6465_6c62_616e_4573 => Property::IsEnabled,
             0x0064_6562_6972_6373_6275_5373 => Property::IsSubscribed,
+            0x746c_7561_6665_4473 => Property::IsDefault,
+            0x656c_6269_7369_5673 => Property::IsVisible,
             _ => return None,
         },
         b'k' => match hash {
@@ -406,6 +430,7 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x7372_6562_6d65 => Property::Members,
             0x6449_6567_6173_7365 => Property::MessageId,
             0x0073_7468_6769_5279 => Property::MyRights,
+            0x0064_6569_6669_646f => Property::Modified,
             _ => return None,
         },
         b'n' => match hash {
@@ -452,6 +477,7 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0073_6461_6572_6854_6c61_746f => Property::TotalThreads,
             0x0065_7079 => Property::Type,
             0x7365_7079 => Property::Types,
+            0x656c_7469 => Property::Title,
             _ => return None,
         },
         b'u' => match hash {
@@ -459,6 +485,9 @@ fn parse_property(first_char: u8, hash: u128) -> Option<Property> {
             0x0073_6c69_616d_4564_6165_726e => Property::UnreadEmails,
             0x7364_6165_7268_5464_6165_726e => Property::UnreadThreads,
             0x6c72 => Property::Url,
+            0x6469 => Property::Uid,
+            0x0074_7261_7453_6374 => Property::UtcStart,
+            0x0064_6e45_6374 => Property::UtcEnd,
             _ => return None,
         },
         b'v' => match hash {
@@ -883,6 +912,19 @@ impl Display for Property {
             Property::Scope => write!(f, "scope"),
             Property::WarnLimit => write!(f, "warnLimit"),
             Property::SoftLimit => write!(f, "softLimit"),
+            Property::CalendarIds => write!(f, "calendarIds"),
+            Property::Color => write!(f, "color"),
+            Property::IsDefault => write!(f, "isDefault"),
+            Property::IsVisible => write!(f, "isVisible"),
+            Property::Uid => write!(f, "uid"),
+            Property::Title => write!(f, "title"),
+            Property::UtcStart => write!(f, "utcStart"),
+            Property::UtcEnd => write!(f, "utcEnd"),
+            Property::CalendarData => write!(f, "calendarData"),
+            Property::AddressBookIds => write!(f, "addressBookIds"),
+            Property::CardData => write!(f, "cardData"),
+            Property::Created => write!(f, "created"),
+            Property::Modified => write!(f, "modified"),
             Property::_T(s) => write!(f, "{s}"),
         }
     }
@@ -994,6 +1036,19 @@ impl Property {
             Property::WarnLimit => "warnLimit",
             Property::SoftLimit => "softLimit",
             Property::Scope => "scope",
+            Property::CalendarIds => "calendarIds",
+            Property::Color => "color",
+            Property::IsDefault => "isDefault",
+            Property::IsVisible => "isVisible",
+            Property::Uid => "uid",
+            Property::Title => "title",
+            Property::UtcStart => "utcStart",
+            Property::UtcEnd => "utcEnd",
+            Property::CalendarData => "calendarData",
+            Property::AddressBookIds => "addressBookIds",
+            Property::CardData => "cardData",
+            Property::Created => "created",
+            Property::Modified => "modified",
             Property::Data(data) => match data {
                 DataProperty::AsText => "data:asText",
                 DataProperty::AsBase64 => "data:asBase64",
@@ -1180,6 +1235,19 @@ impl From<&Property> for u8 {
             Property::WarnLimit => 101,
             Property::SoftLimit => 102,
             Property::Scope => 103,
+            Property::CalendarIds => 104,
+            Property::Color => 105,
+            Property::IsDefault => 106,
+            Property::IsVisible => 107,
+            Property::Uid => 108,
+            Property::Title => 109,
+            Property::UtcStart => 110,
+            Property::UtcEnd => 111,
+            Property::CalendarData => 112,
+            Property::AddressBookIds => 113,
+            Property::CardData => 114,
+            Property::Created => 115,
+            Property::Modified => 116,
             Property::Digest(_) | Property::Data(_) => unreachable!("invalid property"),
         }
     }
@@ -0,0 +1,299 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{Server, auth::AccessToken};
+use groupware::{DestroyArchive, cache::GroupwareCache, contact::AddressBook};
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::set::{SetRequest, SetResponse},
+    object::address_book::SetArguments,
+    response::references::EvalObjectReferences,
+    types::{
+        collection::{Collection, SyncCollection},
+        property::Property,
+        state::State,
+        value::{MaybePatchValue, Object, SetValue, Value},
+    },
+};
+use store::{
+    rand::{Rng, distr::Alphanumeric, rng},
+    roaring::RoaringBitmap,
+    write::BatchBuilder,
+};
+use trc::AddContext;
+
+use crate::JmapMethods;
+use std::future::Future;
+
+pub trait AddressBookSet: Sync + Send {
+    fn address_book_set(
+        &self,
+        request: SetRequest<SetArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+}
+
+impl AddressBookSet for Server {
+    async fn address_book_set(
+        &self,
+        mut request: SetRequest<SetArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::AddressBook)
+            .await
+            .caused_by(trc::location!())?;
+        let old_state = State::from(resources.container_change_id);
+        if let Some(if_in_state) = &request.if_in_state {
+            if &old_state != if_in_state {
+                return Err(trc::JmapEvent::StateMismatch.into_err());
+            }
+        }
+        let mut address_book_ids = resources
+            .resources
+            .iter()
+            .filter(|resource| resource.is_container())
+            .map(|resource| resource.document_id)
+            .collect::<RoaringBitmap>();
+        let mut response = self.prepare_set_response(&request, old_state).await?;
+        let will_destroy = request.unwrap_destroy();
+        let mut batch = BatchBuilder::new();
+        let mut new_default_id = None;
+
+        // Process creates
+        for (id, object) in request.unwrap_create() {
+            let mut book = AddressBook {
+                subscribers: vec![access_token.primary_id()],
+                ..Default::default()
+            };
+            if let Err(err) = address_book_set_item(object, &mut book, access_token, &response) {
+                response.not_created.append(id, err);
+                continue;
+            }
+            if book.display_name.is_none() {
+                response.not_created.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::Name)
+                        .with_description("Missing address book name."),
+                );
+                continue;
+            }
+
+            // Address books created over JMAP are given a random path
+            book.name = loop {
+                let name = rng()
+                    .sample_iter(Alphanumeric)
+                    .take(15)
+                    .map(|ch| char::from(ch.to_ascii_lowercase()))
+                    .collect::<String>();
+                if resources.by_path(&name).is_none() {
+                    break name;
+                }
+            };
+            let is_default = book.is_default;
+
+            // Write record
+            let document_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::AddressBook, 1)
+                .await
+                .caused_by(trc::location!())?;
+            book.insert(access_token, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+            address_book_ids.insert(document_id);
+            if is_default {
+                new_default_id = Some(document_id);
+            }
+            response.created(id, document_id);
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain address book
+            let document_id = id.document_id();
+            let book_ = if !address_book_ids.contains(document_id) {
+                None
+            } else {
+                self.get_archive(account_id, Collection::AddressBook, document_id)
+                    .await?
+            };
+            let book_ = if let Some(book) = book_ {
+                book
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+            let book = book_
+                .to_unarchived::<AddressBook>()
+                .caused_by(trc::location!())?;
+            let mut new_book = book
+                .deserialize::<AddressBook>()
+                .caused_by(trc::location!())?;
+            let was_default = new_book.is_default;
+            if let Err(err) = address_book_set_item(object, &mut new_book, access_token, &response)
+            {
+                response.not_updated.append(id, err);
+                continue 'update;
+            }
+            if !was_default && new_book.is_default {
+                new_default_id = Some(document_id);
+            }
+
+            // Write record
+            new_book
+                .update(access_token, book, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+            response.updated.append(id, None);
+        }
+
+        // Process deletions
+        for id in will_destroy {
+            let document_id = id.document_id();
+            let Some(path) = resources.container_by_id(document_id) else {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            };
+
+            // Address books can only be destroyed with their contacts when requested
+            let children_ids = resources
+                .children(document_id)
+                .map(|child| child.document_id())
+                .collect::<Vec<_>>();
+            if !children_ids.is_empty()
+                && !request
+                    .arguments
+                    .on_destroy_remove_contents
+                    .unwrap_or(false)
+            {
+                response.not_destroyed.append(
+                    id,
+                    SetError::new(SetErrorType::AddressBookHasContents)
+                        .with_description("Address book is not empty."),
+                );
+                continue;
+            }
+
+            let Some(book_) = self
+                .get_archive(account_id, Collection::AddressBook, document_id)
+                .await?
+            else {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            };
+            DestroyArchive(
+                book_
+                    .to_unarchived::<AddressBook>()
+                    .caused_by(trc::location!())?,
+            )
+            .delete_with_cards(
+                self,
+                access_token,
+                account_id,
+                document_id,
+                children_ids,
+                resources.format_resource(path).into(),
+                &mut batch,
+            )
+            .await
+            .caused_by(trc::location!())?;
+            address_book_ids.remove(document_id);
+            response.destroyed.push(id);
+        }
+
+        // Only one address book can be the default
+        if let Some(new_default_id) = new_default_id {
+            for document_id in &address_book_ids {
+                if document_id == new_default_id
+                    || response.updated.contains_key(&document_id.into())
+                {
+                    continue;
+                }
+                if let Some(book_) = self
+                    .get_archive(account_id, Collection::AddressBook, document_id)
+                    .await?
+                {
+                    let book = book_
+                        .to_unarchived::<AddressBook>()
+                        .caused_by(trc::location!())?;
+                    if book.inner.is_default {
+                        let mut new_book = book
+                            .deserialize::<AddressBook>()
+                            .caused_by(trc::location!())?;
+                        new_book.is_default = false;
+                        new_book
+                            .update(access_token, book, account_id, document_id, &mut batch)
+                            .caused_by(trc::location!())?;
+                    }
+                }
+            }
+        }
+
+        // Write changes
+        if !batch.is_empty() {
+            let change_id = self
+                .commit_batch(batch)
+                .await
+                .and_then(|ids| ids.last_change_id(account_id))
+                .caused_by(trc::location!())?;
+            response.new_state = State::Exact(change_id).into();
+        }
+
+        Ok(response)
+    }
+}
+
+fn address_book_set_item(
+    changes: Object<SetValue>,
+    book: &mut AddressBook,
+    access_token: &AccessToken,
+    response: &SetResponse,
+) -> Result<(), SetError> {
+    for (property, value) in changes.0 {
+        let value = response.eval_object_references(value)?;
+        match (&property, value) {
+            (Property::Name, MaybePatchValue::Value(Value::Text(value))) if !value.is_empty() => {
+                book.display_name = Some(value);
+            }
+            (Property::Description, MaybePatchValue::Value(Value::Text(value))) => {
+                book.description = Some(value);
+            }
+            (Property::Description, MaybePatchValue::Value(Value::Null)) => {
+                book.description = None;
+            }
+            (Property::SortOrder, MaybePatchValue::Value(Value::UnsignedInt(value))) => {
+                book.sort_order = value as u32;
+            }
+            (Property::IsDefault, MaybePatchValue::Value(Value::Bool(value))) => {
+                book.is_default = value;
+            }
+            (Property::IsSubscribed, MaybePatchValue::Value(Value::Bool(value))) => {
+                let account_id = access_token.primary_id();
+                if value {
+                    if !book.subscribers.contains(&account_id) {
+                        book.subscribers.push(account_id);
+                    }
+                } else {
+                    book.subscribers.retain(|id| *id != account_id);
+                }
+            }
+            _ => {
+                return Err(SetError::invalid_properties()
+                    .with_property(property)
+                    .with_description("Invalid property or value.".to_string()));
+            }
+        }
+    }
+
+    Ok(())
+}
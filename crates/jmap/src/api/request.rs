@@ -20,12 +20,17 @@ use jmap_proto::{
 use trc::JmapEvent;
 
 use crate::{
+    address_book::{get::AddressBookGet, set::AddressBookSet},
     blob::{copy::BlobCopy, get::BlobOperations, upload::BlobUpload},
+    calendar::{get::CalendarGet, set::CalendarSet},
+    calendar_event::{get::CalendarEventGet, query::CalendarEventQuery, set::CalendarEventSet},
     changes::{get::ChangesLookup, query::QueryChanges},
+    contact_card::{get::ContactCardGet, query::ContactCardQuery, set::ContactCardSet},
     email::{
         copy::JmapEmailCopy, get::EmailGet, import::EmailImport, parse::EmailParse,
         query::EmailQuery, set::EmailSet, snippet::EmailSearchSnippet,
     },
+    file_node::{get::FileNodeGet, query::FileNodeQuery, set::FileNodeSet},
     identity::{get::IdentityGet, set::IdentitySet},
     mailbox::{get::MailboxGet, query::MailboxQuery, set::MailboxSet},
     principal::{get::PrincipalGet, query::PrincipalQuery},
@@ -221,6 +226,31 @@ impl RequestHandler for Server {
                         .await?
                         .into()
                 }
+                get::RequestArguments::Calendar => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.calendar_get(req, access_token).await?.into()
+                }
+                get::RequestArguments::CalendarEvent => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.calendar_event_get(req, access_token).await?.into()
+                }
+                get::RequestArguments::AddressBook => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.address_book_get(req, access_token).await?.into()
+                }
+                get::RequestArguments::ContactCard => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.contact_card_get(req, access_token).await?.into()
+                }
+                get::RequestArguments::FileNode => {
+                    access_token.assert_has_access(req.account_id, Collection::FileNode)?;
+
+                    self.file_node_get(req, access_token).await?.into()
+                }
             },
             RequestMethod::Query(mut req) => match req.take_arguments() {
                 query::RequestArguments::Email(arguments) => {
@@ -255,6 +285,21 @@ impl RequestHandler for Server {
 
                     self.quota_query(req, access_token).await?.into()
                 }
+                query::RequestArguments::CalendarEvent => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.calendar_event_query(req, access_token).await?.into()
+                }
+                query::RequestArguments::ContactCard => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.contact_card_query(req, access_token).await?.into()
+                }
+                query::RequestArguments::FileNode => {
+                    access_token.assert_has_access(req.account_id, Collection::FileNode)?;
+
+                    self.file_node_query(req, access_token).await?.into()
+                }
             },
             RequestMethod::Set(mut req) => match req.take_arguments() {
                 set::RequestArguments::Email => {
@@ -300,6 +345,37 @@ impl RequestHandler for Server {
 
                     self.vacation_response_set(req, access_token).await?.into()
                 }
+                set::RequestArguments::Calendar(arguments) => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.calendar_set(req.with_arguments(arguments), access_token)
+                        .await?
+                        .into()
+                }
+                set::RequestArguments::CalendarEvent => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.calendar_event_set(req, access_token).await?.into()
+                }
+                set::RequestArguments::AddressBook(arguments) => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.address_book_set(req.with_arguments(arguments), access_token)
+                        .await?
+                        .into()
+                }
+                set::RequestArguments::ContactCard => {
+                    access_token.assert_is_member(req.account_id)?;
+
+                    self.contact_card_set(req, access_token).await?.into()
+                }
+                set::RequestArguments::FileNode(arguments) => {
+                    access_token.assert_has_access(req.account_id, Collection::FileNode)?;
+
+                    self.file_node_set(req.with_arguments(arguments), access_token)
+                        .await?
+                        .into()
+                }
             },
             RequestMethod::Changes(req) => self.changes(req, access_token).await?.into(),
             RequestMethod::Copy(req) => {
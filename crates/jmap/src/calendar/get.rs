@@ -0,0 +1,164 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::common::timezone::Tz;
+use common::{Server, auth::AccessToken};
+use groupware::{
+    cache::GroupwareCache,
+    calendar::{
+        ArchivedTimezone, CALENDAR_DEFAULT, CALENDAR_SUBSCRIBED, CALENDAR_VISIBLE, Calendar,
+    },
+};
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    types::{
+        collection::{Collection, SyncCollection},
+        property::Property,
+        state::State,
+        value::{Object, Value},
+    },
+};
+use store::roaring::RoaringBitmap;
+use trc::AddContext;
+
+use std::future::Future;
+
+pub trait CalendarGet: Sync + Send {
+    fn calendar_get(
+        &self,
+        request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<GetResponse>> + Send;
+}
+
+impl CalendarGet for Server {
+    async fn calendar_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::Name,
+            Property::Description,
+            Property::Color,
+            Property::SortOrder,
+            Property::IsDefault,
+            Property::IsSubscribed,
+            Property::IsVisible,
+            Property::Timezone,
+        ]);
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?;
+        let calendar_ids = resources
+            .resources
+            .iter()
+            .filter(|resource| resource.is_container())
+            .map(|resource| resource.document_id)
+            .collect::<RoaringBitmap>();
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            calendar_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: State::from(resources.container_change_id).into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the calendar object
+            let document_id = id.document_id();
+            if !calendar_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let calendar_ = if let Some(calendar) = self
+                .get_archive(account_id, Collection::Calendar, document_id)
+                .await?
+            {
+                calendar
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let calendar = calendar_
+                .unarchive::<Calendar>()
+                .caused_by(trc::location!())?;
+            let preferences = calendar.preferences(account_id);
+            let flags = preferences.flags.to_native();
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                match property {
+                    Property::Id => {
+                        result.append(Property::Id, Value::Id(id));
+                    }
+                    Property::Name => {
+                        result.append(Property::Name, Value::from(&preferences.name));
+                    }
+                    Property::Description => {
+                        result.append(Property::Description, Value::from(&preferences.description));
+                    }
+                    Property::Color => {
+                        result.append(Property::Color, Value::from(&preferences.color));
+                    }
+                    Property::SortOrder => {
+                        result.append(
+                            Property::SortOrder,
+                            Value::UnsignedInt(preferences.sort_order.to_native() as u64),
+                        );
+                    }
+                    Property::IsDefault => {
+                        result.append(
+                            Property::IsDefault,
+                            Value::Bool(flags & CALENDAR_DEFAULT != 0),
+                        );
+                    }
+                    Property::IsSubscribed => {
+                        result.append(
+                            Property::IsSubscribed,
+                            Value::Bool(flags & CALENDAR_SUBSCRIBED != 0),
+                        );
+                    }
+                    Property::IsVisible => {
+                        result.append(
+                            Property::IsVisible,
+                            Value::Bool(flags & CALENDAR_VISIBLE != 0),
+                        );
+                    }
+                    Property::Timezone => {
+                        result.append(
+                            Property::Timezone,
+                            if let ArchivedTimezone::IANA(tz) = &preferences.time_zone {
+                                Tz::from_id(tz.to_native())
+                                    .map(|tz| Value::Text(tz.to_string()))
+                                    .unwrap_or(Value::Null)
+                            } else {
+                                Value::Null
+                            },
+                        );
+                    }
+                    property => {
+                        result.append(property.clone(), Value::Null);
+                    }
+                }
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
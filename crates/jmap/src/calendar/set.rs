@@ -0,0 +1,331 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{Server, auth::AccessToken};
+use groupware::{
+    DestroyArchive,
+    cache::GroupwareCache,
+    calendar::{
+        CALENDAR_DEFAULT, CALENDAR_SUBSCRIBED, CALENDAR_VISIBLE, Calendar, CalendarPreferences,
+        Timezone, timezone::parse_tz,
+    },
+};
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::set::{SetRequest, SetResponse},
+    object::calendar::SetArguments,
+    response::references::EvalObjectReferences,
+    types::{
+        collection::{Collection, SyncCollection},
+        property::Property,
+        state::State,
+        value::{MaybePatchValue, Object, SetValue, Value},
+    },
+};
+use store::{
+    rand::{Rng, distr::Alphanumeric, rng},
+    roaring::RoaringBitmap,
+    write::BatchBuilder,
+};
+use trc::AddContext;
+
+use crate::JmapMethods;
+use std::future::Future;
+
+pub trait CalendarSet: Sync + Send {
+    fn calendar_set(
+        &self,
+        request: SetRequest<SetArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+}
+
+impl CalendarSet for Server {
+    async fn calendar_set(
+        &self,
+        mut request: SetRequest<SetArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?;
+        let old_state = State::from(resources.container_change_id);
+        if let Some(if_in_state) = &request.if_in_state {
+            if &old_state != if_in_state {
+                return Err(trc::JmapEvent::StateMismatch.into_err());
+            }
+        }
+        let mut calendar_ids = resources
+            .resources
+            .iter()
+            .filter(|resource| resource.is_container())
+            .map(|resource| resource.document_id)
+            .collect::<RoaringBitmap>();
+        let mut response = self.prepare_set_response(&request, old_state).await?;
+        let will_destroy = request.unwrap_destroy();
+        let mut batch = BatchBuilder::new();
+        let mut new_default_id = None;
+
+        // Process creates
+        for (id, object) in request.unwrap_create() {
+            let mut calendar = Calendar {
+                preferences: vec![CalendarPreferences {
+                    account_id,
+                    flags: CALENDAR_SUBSCRIBED | CALENDAR_VISIBLE,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            if let Err(err) = calendar_set_item(object, &mut calendar, account_id, &response) {
+                response.not_created.append(id, err);
+                continue;
+            }
+            if calendar.preferences[0].name.is_empty() {
+                response.not_created.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::Name)
+                        .with_description("Missing calendar name."),
+                );
+                continue;
+            }
+
+            // Calendars created over JMAP are given a random path
+            calendar.name = loop {
+                let name = rng()
+                    .sample_iter(Alphanumeric)
+                    .take(15)
+                    .map(|ch| char::from(ch.to_ascii_lowercase()))
+                    .collect::<String>();
+                if resources.by_path(&name).is_none() {
+                    break name;
+                }
+            };
+            let is_default = calendar.preferences[0].flags & CALENDAR_DEFAULT != 0;
+
+            // Write record
+            let document_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::Calendar, 1)
+                .await
+                .caused_by(trc::location!())?;
+            calendar
+                .insert(access_token, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+            calendar_ids.insert(document_id);
+            if is_default {
+                new_default_id = Some(document_id);
+            }
+            response.created(id, document_id);
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain calendar
+            let document_id = id.document_id();
+            let calendar_ = if !calendar_ids.contains(document_id) {
+                None
+            } else {
+                self.get_archive(account_id, Collection::Calendar, document_id)
+                    .await?
+            };
+            let calendar_ = if let Some(calendar) = calendar_ {
+                calendar
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+            let calendar = calendar_
+                .to_unarchived::<Calendar>()
+                .caused_by(trc::location!())?;
+            let mut new_calendar = calendar
+                .deserialize::<Calendar>()
+                .caused_by(trc::location!())?;
+            let was_default = new_calendar.preferences(account_id).flags & CALENDAR_DEFAULT != 0;
+            if let Err(err) = calendar_set_item(object, &mut new_calendar, account_id, &response) {
+                response.not_updated.append(id, err);
+                continue 'update;
+            }
+            if !was_default && new_calendar.preferences(account_id).flags & CALENDAR_DEFAULT != 0 {
+                new_default_id = Some(document_id);
+            }
+
+            // Write record
+            new_calendar
+                .update(access_token, calendar, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+            response.updated.append(id, None);
+        }
+
+        // Process deletions
+        for id in will_destroy {
+            let document_id = id.document_id();
+            let Some(path) = resources.container_by_id(document_id) else {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            };
+
+            // Calendars can only be destroyed with their events when requested
+            let children_ids = resources
+                .children(document_id)
+                .map(|child| child.document_id())
+                .collect::<Vec<_>>();
+            if !children_ids.is_empty()
+                && !request.arguments.on_destroy_remove_events.unwrap_or(false)
+            {
+                response.not_destroyed.append(
+                    id,
+                    SetError::new(SetErrorType::CalendarHasEvent)
+                        .with_description("Calendar is not empty."),
+                );
+                continue;
+            }
+
+            let Some(calendar_) = self
+                .get_archive(account_id, Collection::Calendar, document_id)
+                .await?
+            else {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            };
+            DestroyArchive(
+                calendar_
+                    .to_unarchived::<Calendar>()
+                    .caused_by(trc::location!())?,
+            )
+            .delete_with_events(
+                self,
+                access_token,
+                account_id,
+                document_id,
+                children_ids,
+                resources.format_resource(path).into(),
+                &mut batch,
+            )
+            .await
+            .caused_by(trc::location!())?;
+            calendar_ids.remove(document_id);
+            response.destroyed.push(id);
+        }
+
+        // Only one calendar can be the default
+        if let Some(new_default_id) = new_default_id {
+            for document_id in &calendar_ids {
+                if document_id == new_default_id
+                    || response.updated.contains_key(&document_id.into())
+                {
+                    continue;
+                }
+                if let Some(calendar_) = self
+                    .get_archive(account_id, Collection::Calendar, document_id)
+                    .await?
+                {
+                    let calendar = calendar_
+                        .to_unarchived::<Calendar>()
+                        .caused_by(trc::location!())?;
+                    if calendar.inner.preferences(account_id).flags.to_native() & CALENDAR_DEFAULT
+                        != 0
+                    {
+                        let mut new_calendar = calendar
+                            .deserialize::<Calendar>()
+                            .caused_by(trc::location!())?;
+                        new_calendar.preferences_mut(account_id).flags &= !CALENDAR_DEFAULT;
+                        new_calendar
+                            .update(access_token, calendar, account_id, document_id, &mut batch)
+                            .caused_by(trc::location!())?;
+                    }
+                }
+            }
+        }
+
+        // Write changes
+        if !batch.is_empty() {
+            let change_id = self
+                .commit_batch(batch)
+                .await
+                .and_then(|ids| ids.last_change_id(account_id))
+                .caused_by(trc::location!())?;
+            response.new_state = State::Exact(change_id).into();
+        }
+
+        Ok(response)
+    }
+}
+
+fn calendar_set_item(
+    changes: Object<SetValue>,
+    calendar: &mut Calendar,
+    account_id: u32,
+    response: &SetResponse,
+) -> Result<(), SetError> {
+    let preferences = calendar.preferences_mut(account_id);
+
+    for (property, value) in changes.0 {
+        let value = response.eval_object_references(value)?;
+        match (&property, value) {
+            (Property::Name, MaybePatchValue::Value(Value::Text(value))) if !value.is_empty() => {
+                preferences.name = value;
+            }
+            (Property::Description, MaybePatchValue::Value(Value::Text(value))) => {
+                preferences.description = Some(value);
+            }
+            (Property::Description, MaybePatchValue::Value(Value::Null)) => {
+                preferences.description = None;
+            }
+            (Property::Color, MaybePatchValue::Value(Value::Text(value))) => {
+                preferences.color = Some(value);
+            }
+            (Property::Color, MaybePatchValue::Value(Value::Null)) => {
+                preferences.color = None;
+            }
+            (Property::SortOrder, MaybePatchValue::Value(Value::UnsignedInt(value))) => {
+                preferences.sort_order = value as u32;
+            }
+            (
+                Property::IsDefault | Property::IsSubscribed | Property::IsVisible,
+                MaybePatchValue::Value(Value::Bool(value)),
+            ) => {
+                let flag = match property {
+                    Property::IsDefault => CALENDAR_DEFAULT,
+                    Property::IsSubscribed => CALENDAR_SUBSCRIBED,
+                    _ => CALENDAR_VISIBLE,
+                };
+                if value {
+                    preferences.flags |= flag;
+                } else {
+                    preferences.flags &= !flag;
+                }
+            }
+            (Property::Timezone, MaybePatchValue::Value(Value::Text(value))) => {
+                if let Some(tz) = parse_tz(&value) {
+                    preferences.time_zone = Timezone::IANA(tz.as_id());
+                } else {
+                    return Err(SetError::invalid_properties()
+                        .with_property(property)
+                        .with_description("Unknown time zone."));
+                }
+            }
+            (Property::Timezone, MaybePatchValue::Value(Value::Null)) => {
+                preferences.time_zone = Timezone::Default;
+            }
+            _ => {
+                return Err(SetError::invalid_properties()
+                    .with_property(property)
+                    .with_description("Invalid property or value.".to_string()));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::icalendar::ArchivedICalendarProperty;
+use common::{Server, auth::AccessToken};
+use groupware::{cache::GroupwareCache, calendar::CalendarEvent};
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    types::{
+        collection::{Collection, SyncCollection},
+        date::UTCDate,
+        id::Id,
+        property::Property,
+        state::State,
+        value::{Object, Value},
+    },
+};
+use store::roaring::RoaringBitmap;
+use trc::AddContext;
+
+use std::future::Future;
+
+pub trait CalendarEventGet: Sync + Send {
+    fn calendar_event_get(
+        &self,
+        request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<GetResponse>> + Send;
+}
+
+impl CalendarEventGet for Server {
+    async fn calendar_event_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::CalendarIds,
+            Property::Uid,
+            Property::Title,
+            Property::UtcStart,
+            Property::UtcEnd,
+            Property::Size,
+            Property::CalendarData,
+        ]);
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?;
+        let event_ids = resources
+            .resources
+            .iter()
+            .filter(|resource| !resource.is_container())
+            .map(|resource| resource.document_id)
+            .collect::<RoaringBitmap>();
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            event_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: State::from(resources.item_change_id).into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the event object
+            let document_id = id.document_id();
+            if !event_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let event_ = if let Some(event) = self
+                .get_archive(account_id, Collection::CalendarEvent, document_id)
+                .await?
+            {
+                event
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let event = event_
+                .unarchive::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                match property {
+                    Property::Id => {
+                        result.append(Property::Id, Value::Id(id));
+                    }
+                    Property::CalendarIds => {
+                        let mut obj = Object::with_capacity(event.names.len());
+                        for name in event.names.iter() {
+                            obj.append(
+                                Property::_T(Id::from(name.parent_id.to_native()).to_string()),
+                                true,
+                            );
+                        }
+                        result.append(Property::CalendarIds, Value::Object(obj));
+                    }
+                    Property::Uid => {
+                        result.append(Property::Uid, Value::from(event.data.event.uids().next()));
+                    }
+                    Property::Title => {
+                        result.append(
+                            Property::Title,
+                            Value::from(
+                                event
+                                    .data
+                                    .event
+                                    .components
+                                    .iter()
+                                    .flat_map(|component| component.entries.iter())
+                                    .find(|entry| {
+                                        matches!(entry.name, ArchivedICalendarProperty::Summary)
+                                    })
+                                    .and_then(|entry| entry.values.first())
+                                    .and_then(|value| value.as_text()),
+                            ),
+                        );
+                    }
+                    Property::UtcStart | Property::UtcEnd => {
+                        result.append(
+                            property.clone(),
+                            event
+                                .data
+                                .event_range()
+                                .map(|(start, duration)| {
+                                    Value::Date(UTCDate::from_timestamp(
+                                        if matches!(property, Property::UtcStart) {
+                                            start
+                                        } else {
+                                            start + duration as i64
+                                        },
+                                    ))
+                                })
+                                .unwrap_or(Value::Null),
+                        );
+                    }
+                    Property::Size => {
+                        result.append(
+                            Property::Size,
+                            Value::UnsignedInt(event.size.to_native() as u64),
+                        );
+                    }
+                    Property::CalendarData => {
+                        result.append(
+                            Property::CalendarData,
+                            Value::Text(event.data.event.to_string()),
+                        );
+                    }
+                    property => {
+                        result.append(property.clone(), Value::Null);
+                    }
+                }
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,164 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::JmapMethods;
+use common::{IDX_UID, Server, auth::AccessToken};
+use groupware::cache::GroupwareCache;
+use jmap_proto::{
+    method::query::{
+        Comparator, Filter, QueryRequest, QueryResponse, RequestArguments, SortProperty,
+    },
+    types::{
+        collection::{Collection, SyncCollection},
+        state::State,
+    },
+};
+use std::{collections::BTreeSet, future::Future};
+use store::{
+    query::{self},
+    roaring::RoaringBitmap,
+};
+use trc::AddContext;
+
+pub trait CalendarEventQuery: Sync + Send {
+    fn calendar_event_query(
+        &self,
+        request: QueryRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<QueryResponse>> + Send;
+}
+
+impl CalendarEventQuery for Server {
+    async fn calendar_event_query(
+        &self,
+        mut request: QueryRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<QueryResponse> {
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?;
+        let mut filters = Vec::with_capacity(request.filter.len());
+
+        for cond in std::mem::take(&mut request.filter) {
+            match cond {
+                Filter::InCalendar(calendar_id) => {
+                    filters.push(query::Filter::is_in_set(
+                        resources
+                            .children(calendar_id.document_id())
+                            .map(|child| child.document_id())
+                            .collect::<RoaringBitmap>(),
+                    ));
+                }
+                Filter::Uid(uid) => {
+                    filters.push(query::Filter::eq(IDX_UID, uid.into_bytes()));
+                }
+                Filter::After(date) => {
+                    let after = date.timestamp();
+                    filters.push(query::Filter::is_in_set(
+                        resources
+                            .resources
+                            .iter()
+                            .filter(|resource| {
+                                resource
+                                    .event_time_range()
+                                    .is_some_and(|(_, end)| end > after)
+                            })
+                            .map(|resource| resource.document_id)
+                            .collect::<RoaringBitmap>(),
+                    ));
+                }
+                Filter::Before(date) => {
+                    let before = date.timestamp();
+                    filters.push(query::Filter::is_in_set(
+                        resources
+                            .resources
+                            .iter()
+                            .filter(|resource| {
+                                resource
+                                    .event_time_range()
+                                    .is_some_and(|(start, _)| start < before)
+                            })
+                            .map(|resource| resource.document_id)
+                            .collect::<RoaringBitmap>(),
+                    ));
+                }
+                Filter::Id(ids) => {
+                    filters.push(query::Filter::is_in_set(
+                        ids.into_iter()
+                            .map(|id| id.document_id())
+                            .collect::<RoaringBitmap>(),
+                    ));
+                }
+                Filter::And | Filter::Or | Filter::Not | Filter::Close => {
+                    filters.push(cond.into());
+                }
+
+                other => {
+                    return Err(trc::JmapEvent::UnsupportedFilter
+                        .into_err()
+                        .details(other.to_string()));
+                }
+            }
+        }
+
+        let mut result_set = self
+            .filter(account_id, Collection::CalendarEvent, filters)
+            .await?;
+        result_set.apply_mask(
+            resources
+                .resources
+                .iter()
+                .filter(|resource| !resource.is_container())
+                .map(|resource| resource.document_id)
+                .collect(),
+        );
+        let (response, paginate) = self
+            .build_query_response(&result_set, State::from(resources.item_change_id), &request)
+            .await?;
+
+        if let Some(paginate) = paginate {
+            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
+
+            // Parse sort criteria
+            for comparator in request
+                .sort
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| vec![Comparator::ascending(SortProperty::Start)])
+            {
+                comparators.push(match comparator.property {
+                    SortProperty::Start => {
+                        let sorted_list = resources
+                            .resources
+                            .iter()
+                            .filter_map(|resource| {
+                                resource
+                                    .event_time_range()
+                                    .map(|(start, _)| (start, resource.document_id))
+                            })
+                            .collect::<BTreeSet<_>>();
+
+                        query::Comparator::sorted_list(
+                            sorted_list.into_iter().map(|v| v.1).collect(),
+                            comparator.is_ascending,
+                        )
+                    }
+
+                    other => {
+                        return Err(trc::JmapEvent::UnsupportedSort
+                            .into_err()
+                            .details(other.to_string()));
+                    }
+                });
+            }
+
+            self.sort(result_set, comparators, paginate, response).await
+        } else {
+            Ok(response)
+        }
+    }
+}
@@ -0,0 +1,621 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::collections::HashSet;
+
+use calcard::{
+    Entry, Parser,
+    common::timezone::Tz,
+    icalendar::{ICalendar, ICalendarComponentType},
+};
+use common::{
+    DavName, DavResources, IDX_UID, Server,
+    auth::{AccessToken, ResourceToken},
+    storage::index::ObjectIndexBuilder,
+};
+use groupware::{
+    cache::GroupwareCache,
+    calendar::{CalendarEvent, CalendarEventData},
+};
+use jmap_proto::{
+    error::set::SetError,
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    response::references::EvalObjectReferences,
+    types::{
+        collection::{Collection, SyncCollection, VanishedCollection},
+        id::Id,
+        property::Property,
+        state::State,
+        value::{MaybePatchValue, Object, SetValue, Value},
+    },
+};
+use store::{
+    query::Filter,
+    rand::{Rng, distr::Alphanumeric, rng},
+    roaring::RoaringBitmap,
+    write::BatchBuilder,
+};
+use trc::AddContext;
+
+use crate::JmapMethods;
+use std::future::Future;
+
+pub trait CalendarEventSet: Sync + Send {
+    fn calendar_event_set(
+        &self,
+        request: SetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+
+    fn assert_event_calendars(
+        &self,
+        resources: &DavResources,
+        resource_token: &ResourceToken,
+        calendar_ids: &[u32],
+        uid: &str,
+        pending: &[PendingEvent],
+    ) -> impl Future<Output = trc::Result<Result<(), SetError>>> + Send;
+}
+
+// Events added to a calendar earlier in the same request, not yet committed
+pub struct PendingEvent {
+    calendar_id: u32,
+    document_id: u32,
+    uid: String,
+}
+
+#[derive(Default)]
+struct EventChanges {
+    calendar_ids: Vec<u32>,
+    calendar_data: Option<String>,
+}
+
+impl CalendarEventSet for Server {
+    async fn calendar_event_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::Calendar)
+            .await
+            .caused_by(trc::location!())?;
+        let old_state = State::from(resources.item_change_id);
+        if let Some(if_in_state) = &request.if_in_state {
+            if &old_state != if_in_state {
+                return Err(trc::JmapEvent::StateMismatch.into_err());
+            }
+        }
+        let event_ids = resources
+            .resources
+            .iter()
+            .filter(|resource| !resource.is_container())
+            .map(|resource| resource.document_id)
+            .collect::<RoaringBitmap>();
+        let resource_token = self.get_resource_token(access_token, account_id).await?;
+        let mut response = self.prepare_set_response(&request, old_state).await?;
+        let will_destroy = request.unwrap_destroy();
+        let mut batch = BatchBuilder::new();
+        let mut pending = Vec::new();
+
+        // Process creates
+        'create: for (id, object) in request.unwrap_create() {
+            let mut changes = EventChanges::default();
+            if let Err(err) = changes.parse(object, &response) {
+                response.not_created.append(id, err);
+                continue 'create;
+            }
+            if changes.calendar_ids.is_empty() {
+                response.not_created.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::CalendarIds)
+                        .with_description("Event must belong to at least one calendar."),
+                );
+                continue 'create;
+            }
+            let Some(calendar_data) = changes.calendar_data else {
+                response.not_created.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::CalendarData)
+                        .with_description("Missing calendarData."),
+                );
+                continue 'create;
+            };
+
+            // Validate iCalendar data
+            let (ical, uid) =
+                match parse_calendar_data(&calendar_data, self.core.groupware.max_ical_size) {
+                    Ok(result) => result,
+                    Err(err) => {
+                        response.not_created.append(id, err);
+                        continue 'create;
+                    }
+                };
+            if let Err(err) = self
+                .assert_event_calendars(
+                    &resources,
+                    &resource_token,
+                    &changes.calendar_ids,
+                    &uid,
+                    &pending,
+                )
+                .await?
+            {
+                response.not_created.append(id, err);
+                continue 'create;
+            }
+
+            // Validate quota
+            match self
+                .has_available_quota(&resource_token, calendar_data.len() as u64)
+                .await
+            {
+                Ok(_) => (),
+                Err(err) => {
+                    if err.matches(trc::EventType::Limit(trc::LimitEvent::Quota))
+                        || err.matches(trc::EventType::Limit(trc::LimitEvent::TenantQuota))
+                    {
+                        response.not_created.append(id, SetError::over_quota());
+                        continue 'create;
+                    } else {
+                        return Err(err);
+                    }
+                }
+            }
+
+            // Write record
+            let document_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::CalendarEvent, 1)
+                .await
+                .caused_by(trc::location!())?;
+            CalendarEvent {
+                names: changes
+                    .calendar_ids
+                    .iter()
+                    .map(|calendar_id| {
+                        DavName::new(event_name(&resources, *calendar_id), *calendar_id)
+                    })
+                    .collect(),
+                data: CalendarEventData::new(
+                    ical,
+                    Tz::Floating,
+                    self.core.groupware.max_ical_instances,
+                ),
+                size: calendar_data.len() as u32,
+                ..Default::default()
+            }
+            .insert(access_token, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?;
+            pending.extend(
+                changes
+                    .calendar_ids
+                    .into_iter()
+                    .map(|calendar_id| PendingEvent {
+                        calendar_id,
+                        document_id,
+                        uid: uid.clone(),
+                    }),
+            );
+            response.created(id, document_id);
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain event
+            let document_id = id.document_id();
+            let event_ = if !event_ids.contains(document_id) {
+                None
+            } else {
+                self.get_archive(account_id, Collection::CalendarEvent, document_id)
+                    .await?
+            };
+            let event_ = if let Some(event) = event_ {
+                event
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+            let event = event_
+                .to_unarchived::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+            let mut changes = EventChanges {
+                calendar_ids: event
+                    .inner
+                    .names
+                    .iter()
+                    .map(|name| name.parent_id.to_native())
+                    .collect(),
+                calendar_data: None,
+            };
+            if let Err(err) = changes.parse(object, &response) {
+                response.not_updated.append(id, err);
+                continue 'update;
+            }
+            if changes.calendar_ids.is_empty() {
+                response.not_updated.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::CalendarIds)
+                        .with_description("Event must belong to at least one calendar."),
+                );
+                continue 'update;
+            }
+            let uid = event
+                .inner
+                .data
+                .event
+                .uids()
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let mut new_event = event
+                .deserialize::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+
+            // Replace iCalendar data
+            if let Some(calendar_data) = changes.calendar_data {
+                let ical =
+                    match parse_calendar_data(&calendar_data, self.core.groupware.max_ical_size) {
+                        Ok((ical, new_uid)) if new_uid == uid => ical,
+                        Ok(_) => {
+                            response.not_updated.append(
+                                id,
+                                SetError::invalid_properties()
+                                    .with_property(Property::CalendarData)
+                                    .with_description("The UID of an event cannot be changed."),
+                            );
+                            continue 'update;
+                        }
+                        Err(err) => {
+                            response.not_updated.append(id, err);
+                            continue 'update;
+                        }
+                    };
+
+                // Validate quota
+                let extra_bytes =
+                    (calendar_data.len() as u64).saturating_sub(new_event.size as u64);
+                if extra_bytes > 0 {
+                    match self.has_available_quota(&resource_token, extra_bytes).await {
+                        Ok(_) => (),
+                        Err(err) => {
+                            if err.matches(trc::EventType::Limit(trc::LimitEvent::Quota))
+                                || err.matches(trc::EventType::Limit(trc::LimitEvent::TenantQuota))
+                            {
+                                response.not_updated.append(id, SetError::over_quota());
+                                continue 'update;
+                            } else {
+                                return Err(err);
+                            }
+                        }
+                    }
+                }
+
+                new_event.size = calendar_data.len() as u32;
+                new_event.data = CalendarEventData::new(
+                    ical,
+                    Tz::Floating,
+                    self.core.groupware.max_ical_instances,
+                );
+            }
+
+            // Link the event to new calendars and unlink it from removed ones
+            let added_ids = changes
+                .calendar_ids
+                .iter()
+                .filter(|calendar_id| {
+                    !new_event
+                        .names
+                        .iter()
+                        .any(|name| name.parent_id == **calendar_id)
+                })
+                .copied()
+                .collect::<Vec<_>>();
+            if let Err(err) = self
+                .assert_event_calendars(&resources, &resource_token, &added_ids, &uid, &pending)
+                .await?
+            {
+                response.not_updated.append(id, err);
+                continue 'update;
+            }
+            batch.with_account_id(account_id);
+            for name in new_event
+                .names
+                .iter()
+                .filter(|name| !changes.calendar_ids.contains(&name.parent_id))
+            {
+                if let Some(calendar) = resources.container_by_id(name.parent_id) {
+                    batch.log_vanished_item(
+                        VanishedCollection::Calendar,
+                        resources.format_item(&format!("{}/{}", calendar.path(), name.name)),
+                    );
+                }
+            }
+            new_event
+                .names
+                .retain(|name| changes.calendar_ids.contains(&name.parent_id));
+            for calendar_id in added_ids {
+                new_event.names.push(DavName::new(
+                    event_name(&resources, calendar_id),
+                    calendar_id,
+                ));
+                pending.push(PendingEvent {
+                    calendar_id,
+                    document_id,
+                    uid: uid.clone(),
+                });
+            }
+
+            // Write record
+            new_event
+                .update(access_token, event, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+            response.updated.append(id, None);
+        }
+
+        // Process deletions
+        for id in will_destroy {
+            let document_id = id.document_id();
+            let event_ = if !event_ids.contains(document_id) {
+                None
+            } else {
+                self.get_archive(account_id, Collection::CalendarEvent, document_id)
+                    .await?
+            };
+            let Some(event_) = event_ else {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            };
+            let event = event_
+                .to_unarchived::<CalendarEvent>()
+                .caused_by(trc::location!())?;
+
+            // Remove the event from all calendars
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::CalendarEvent);
+            for name in event.inner.names.iter() {
+                if let Some(calendar) = resources.container_by_id(name.parent_id.to_native()) {
+                    batch.log_vanished_item(
+                        VanishedCollection::Calendar,
+                        resources.format_item(&format!("{}/{}", calendar.path(), name.name)),
+                    );
+                }
+            }
+            batch
+                .delete_document(document_id)
+                .custom(
+                    ObjectIndexBuilder::<_, ()>::new()
+                        .with_tenant_id(access_token)
+                        .with_current(event),
+                )
+                .caused_by(trc::location!())?
+                .commit_point();
+            response.destroyed.push(id);
+        }
+
+        // Write changes
+        if !batch.is_empty() {
+            let change_id = self
+                .commit_batch(batch)
+                .await
+                .and_then(|ids| ids.last_change_id(account_id))
+                .caused_by(trc::location!())?;
+            response.new_state = State::Exact(change_id).into();
+        }
+
+        Ok(response)
+    }
+
+    async fn assert_event_calendars(
+        &self,
+        resources: &DavResources,
+        resource_token: &ResourceToken,
+        calendar_ids: &[u32],
+        uid: &str,
+        pending: &[PendingEvent],
+    ) -> trc::Result<Result<(), SetError>> {
+        if calendar_ids.is_empty() {
+            return Ok(Ok(()));
+        }
+
+        // Limits apply to the owner of the calendar
+        let max_events = self
+            .core
+            .groupware
+            .item_limits(
+                resource_token.account_id,
+                resource_token.tenant.as_ref().map(|tenant| tenant.id),
+            )
+            .events_per_calendar;
+        let uid_hits = self
+            .store()
+            .filter(
+                resource_token.account_id,
+                Collection::CalendarEvent,
+                vec![Filter::eq(IDX_UID, uid.as_bytes().to_vec())],
+            )
+            .await
+            .caused_by(trc::location!())?
+            .results;
+
+        for calendar_id in calendar_ids {
+            if resources.container_by_id(*calendar_id).is_none() {
+                return Ok(Err(SetError::invalid_properties()
+                    .with_property(Property::CalendarIds)
+                    .with_description(format!(
+                        "Calendar {} does not exist.",
+                        Id::from(*calendar_id)
+                    ))));
+            }
+
+            let mut events = 0;
+            for child in resources.children(*calendar_id) {
+                if uid_hits.contains(child.document_id()) {
+                    return Ok(Err(SetError::already_exists()
+                        .with_existing_id(child.document_id().into())
+                        .with_description(
+                            "An event with the same UID exists in the calendar.",
+                        )));
+                }
+                events += 1;
+            }
+            for event in pending
+                .iter()
+                .filter(|event| event.calendar_id == *calendar_id)
+            {
+                if event.uid == uid {
+                    return Ok(Err(SetError::already_exists()
+                        .with_existing_id(event.document_id.into())
+                        .with_description(
+                            "An event with the same UID exists in the calendar.",
+                        )));
+                }
+                events += 1;
+            }
+            if max_events.is_some_and(|max_events| events >= max_events) {
+                return Ok(Err(SetError::over_quota().with_description(
+                    "The calendar has reached its maximum number of events.",
+                )));
+            }
+        }
+
+        Ok(Ok(()))
+    }
+}
+
+fn parse_calendar_data(
+    calendar_data: &str,
+    max_size: usize,
+) -> Result<(ICalendar, String), SetError> {
+    if calendar_data.len() > max_size {
+        return Err(SetError::too_large()
+            .with_property(Property::CalendarData)
+            .with_description(format!(
+                "Calendar data exceeds the maximum size of {max_size} bytes."
+            )));
+    }
+
+    match Parser::new(calendar_data).entry() {
+        Entry::ICalendar(ical) => {
+            if let Some(uid) = validate_ical(&ical) {
+                let uid = uid.to_string();
+                Ok((ical, uid))
+            } else {
+                Err(SetError::invalid_properties()
+                    .with_property(Property::CalendarData)
+                    .with_description(concat!(
+                        "Calendar data must contain a single component type ",
+                        "and exactly one UID."
+                    )))
+            }
+        }
+        _ => Err(SetError::invalid_properties()
+            .with_property(Property::CalendarData)
+            .with_description("Invalid iCalendar data.")),
+    }
+}
+
+impl EventChanges {
+    fn parse(&mut self, changes: Object<SetValue>, response: &SetResponse) -> Result<(), SetError> {
+        for (property, value) in changes.0 {
+            match (&property, response.eval_object_references(value)?) {
+                (Property::CalendarIds, MaybePatchValue::Value(Value::List(ids))) => {
+                    self.calendar_ids = ids
+                        .into_iter()
+                        .filter_map(|id| id.try_unwrap_id()?.document_id().into())
+                        .collect();
+                }
+                (Property::CalendarIds, MaybePatchValue::Patch(patch)) => {
+                    let mut patch = patch.into_iter();
+                    if let Some(id) = patch.next().unwrap().try_unwrap_id() {
+                        let document_id = id.document_id();
+                        if patch.next().unwrap().try_unwrap_bool().unwrap_or_default() {
+                            if !self.calendar_ids.contains(&document_id) {
+                                self.calendar_ids.push(document_id);
+                            }
+                        } else {
+                            self.calendar_ids.retain(|id| id != &document_id);
+                        }
+                    }
+                }
+                (Property::CalendarData, MaybePatchValue::Value(Value::Text(value))) => {
+                    self.calendar_data = Some(value);
+                }
+                _ => {
+                    return Err(SetError::invalid_properties()
+                        .with_property(property)
+                        .with_description("Invalid property or value.".to_string()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn event_name(resources: &DavResources, calendar_id: u32) -> String {
+    let calendar_path = resources
+        .container_by_id(calendar_id)
+        .map(|calendar| calendar.path().to_string())
+        .unwrap_or_default();
+    loop {
+        let name = format!(
+            "{}.ics",
+            rng()
+                .sample_iter(Alphanumeric)
+                .take(15)
+                .map(|ch| char::from(ch.to_ascii_lowercase()))
+                .collect::<String>()
+        );
+        if resources
+            .by_path(&format!("{calendar_path}/{name}"))
+            .is_none()
+        {
+            return name;
+        }
+    }
+}
+
+fn validate_ical(ical: &ICalendar) -> Option<&str> {
+    // Validate UIDs
+    let mut uids = HashSet::with_capacity(1);
+
+    // Validate component types
+    let mut types: [u8; 5] = [0; 5];
+    for comp in &ical.components {
+        *(match comp.component_type {
+            ICalendarComponentType::VEvent => &mut types[0],
+            ICalendarComponentType::VTodo => &mut types[1],
+            ICalendarComponentType::VJournal => &mut types[2],
+            ICalendarComponentType::VFreebusy => &mut types[3],
+            ICalendarComponentType::VAvailability => &mut types[4],
+            _ => {
+                continue;
+            }
+        }) += 1;
+
+        if let Some(uid) = comp.uid() {
+            uids.insert(uid);
+        }
+    }
+
+    if uids.len() == 1 && types.iter().filter(|&&v| v == 0).count() == 4 {
+        uids.into_iter().next()
+    } else {
+        None
+    }
+}
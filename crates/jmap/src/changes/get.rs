@@ -61,6 +61,31 @@ impl ChangesLookup for Server {
 
                 return Err(trc::JmapEvent::CannotCalculateChanges.into_err());
             }
+            RequestArguments::Calendar => {
+                access_token.assert_is_member(request.account_id)?;
+
+                (SyncCollection::Calendar, true)
+            }
+            RequestArguments::CalendarEvent => {
+                access_token.assert_is_member(request.account_id)?;
+
+                (SyncCollection::Calendar, false)
+            }
+            RequestArguments::AddressBook => {
+                access_token.assert_is_member(request.account_id)?;
+
+                (SyncCollection::AddressBook, true)
+            }
+            RequestArguments::ContactCard => {
+                access_token.assert_is_member(request.account_id)?;
+
+                (SyncCollection::AddressBook, false)
+            }
+            RequestArguments::FileNode => {
+                access_token.assert_has_access(request.account_id, Collection::FileNode)?;
+
+                (SyncCollection::FileNode, false)
+            }
         };
 
         let max_changes = std::cmp::min(
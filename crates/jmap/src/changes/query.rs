@@ -13,8 +13,9 @@ use jmap_proto::method::{
 use std::future::Future;
 
 use crate::{
-    email::query::EmailQuery, mailbox::query::MailboxQuery, quota::query::QuotaQuery,
-    submission::query::EmailSubmissionQuery,
+    calendar_event::query::CalendarEventQuery, contact_card::query::ContactCardQuery,
+    email::query::EmailQuery, file_node::query::FileNodeQuery, mailbox::query::MailboxQuery,
+    quota::query::QuotaQuery, submission::query::EmailSubmissionQuery,
 };
 
 use super::get::ChangesLookup;
@@ -47,6 +48,13 @@ impl QueryChanges for Server {
                             changes::RequestArguments::EmailSubmission
                         }
                         query::RequestArguments::Quota => changes::RequestArguments::Quota,
+                        query::RequestArguments::CalendarEvent => {
+                            changes::RequestArguments::CalendarEvent
+                        }
+                        query::RequestArguments::ContactCard => {
+                            changes::RequestArguments::ContactCard
+                        }
+                        query::RequestArguments::FileNode => changes::RequestArguments::FileNode,
                         _ => {
                             return Err(trc::JmapEvent::UnknownMethod
                                 .into_err()
@@ -98,6 +106,15 @@ impl QueryChanges for Server {
                     self.email_submission_query(query).await?
                 }
                 query::RequestArguments::Quota => self.quota_query(query, access_token).await?,
+                query::RequestArguments::CalendarEvent => {
+                    self.calendar_event_query(query, access_token).await?
+                }
+                query::RequestArguments::ContactCard => {
+                    self.contact_card_query(query, access_token).await?
+                }
+                query::RequestArguments::FileNode => {
+                    self.file_node_query(query, access_token).await?
+                }
                 _ => unreachable!(),
             };
 
@@ -0,0 +1,144 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::vcard::VCardProperty;
+use common::{Server, auth::AccessToken};
+use groupware::{cache::GroupwareCache, contact::ContactCard};
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    types::{
+        collection::{Collection, SyncCollection},
+        id::Id,
+        property::Property,
+        state::State,
+        value::{Object, Value},
+    },
+};
+use store::roaring::RoaringBitmap;
+use trc::AddContext;
+
+use std::future::Future;
+
+pub trait ContactCardGet: Sync + Send {
+    fn contact_card_get(
+        &self,
+        request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<GetResponse>> + Send;
+}
+
+impl ContactCardGet for Server {
+    async fn contact_card_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::AddressBookIds,
+            Property::Uid,
+            Property::Name,
+            Property::Size,
+            Property::CardData,
+        ]);
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::AddressBook)
+            .await
+            .caused_by(trc::location!())?;
+        let card_ids = resources
+            .resources
+            .iter()
+            .filter(|resource| !resource.is_container())
+            .map(|resource| resource.document_id)
+            .collect::<RoaringBitmap>();
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            card_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: State::from(resources.item_change_id).into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the contact card object
+            let document_id = id.document_id();
+            if !card_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let card_ = if let Some(card) = self
+                .get_archive(account_id, Collection::ContactCard, document_id)
+                .await?
+            {
+                card
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let card = card_
+                .unarchive::<ContactCard>()
+                .caused_by(trc::location!())?;
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                match property {
+                    Property::Id => {
+                        result.append(Property::Id, Value::Id(id));
+                    }
+                    Property::AddressBookIds => {
+                        let mut obj = Object::with_capacity(card.names.len());
+                        for name in card.names.iter() {
+                            obj.append(
+                                Property::_T(Id::from(name.parent_id.to_native()).to_string()),
+                                true,
+                            );
+                        }
+                        result.append(Property::AddressBookIds, Value::Object(obj));
+                    }
+                    Property::Uid => {
+                        result.append(Property::Uid, Value::from(card.card.uid()));
+                    }
+                    Property::Name => {
+                        result.append(
+                            Property::Name,
+                            Value::from(
+                                card.card
+                                    .properties(&VCardProperty::Fn)
+                                    .next()
+                                    .and_then(|entry| entry.values.first())
+                                    .and_then(|value| value.as_text()),
+                            ),
+                        );
+                    }
+                    Property::Size => {
+                        result.append(
+                            Property::Size,
+                            Value::UnsignedInt(card.size.to_native() as u64),
+                        );
+                    }
+                    Property::CardData => {
+                        result.append(Property::CardData, Value::Text(card.card.to_string()));
+                    }
+                    property => {
+                        result.append(property.clone(), Value::Null);
+                    }
+                }
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
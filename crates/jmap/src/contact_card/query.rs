@@ -0,0 +1,139 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::JmapMethods;
+use common::{IDX_EMAIL, IDX_UID, Server, auth::AccessToken};
+use groupware::cache::GroupwareCache;
+use jmap_proto::{
+    method::query::{Filter, QueryRequest, QueryResponse, RequestArguments, SortProperty},
+    types::{
+        collection::{Collection, SyncCollection},
+        state::State,
+    },
+};
+use std::{collections::BTreeSet, future::Future};
+use store::{
+    query::{self},
+    roaring::RoaringBitmap,
+};
+use trc::AddContext;
+use utils::sanitize_email;
+
+pub trait ContactCardQuery: Sync + Send {
+    fn contact_card_query(
+        &self,
+        request: QueryRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<QueryResponse>> + Send;
+}
+
+impl ContactCardQuery for Server {
+    async fn contact_card_query(
+        &self,
+        mut request: QueryRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<QueryResponse> {
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::AddressBook)
+            .await
+            .caused_by(trc::location!())?;
+        let mut filters = Vec::with_capacity(request.filter.len());
+
+        for cond in std::mem::take(&mut request.filter) {
+            match cond {
+                Filter::InAddressBook(address_book_id) => {
+                    filters.push(query::Filter::is_in_set(
+                        resources
+                            .children(address_book_id.document_id())
+                            .map(|child| child.document_id())
+                            .collect::<RoaringBitmap>(),
+                    ));
+                }
+                Filter::Uid(uid) => {
+                    filters.push(query::Filter::eq(IDX_UID, uid.into_bytes()));
+                }
+                Filter::Email(email) => {
+                    if let Some(email) = sanitize_email(&email) {
+                        filters.push(query::Filter::eq(IDX_EMAIL, email.into_bytes()));
+                    } else {
+                        filters.push(query::Filter::is_in_set(RoaringBitmap::new()));
+                    }
+                }
+                Filter::Id(ids) => {
+                    filters.push(query::Filter::is_in_set(
+                        ids.into_iter()
+                            .map(|id| id.document_id())
+                            .collect::<RoaringBitmap>(),
+                    ));
+                }
+                Filter::And | Filter::Or | Filter::Not | Filter::Close => {
+                    filters.push(cond.into());
+                }
+
+                other => {
+                    return Err(trc::JmapEvent::UnsupportedFilter
+                        .into_err()
+                        .details(other.to_string()));
+                }
+            }
+        }
+
+        let card_ids = resources
+            .resources
+            .iter()
+            .filter(|resource| !resource.is_container())
+            .map(|resource| resource.document_id)
+            .collect::<RoaringBitmap>();
+        let mut result_set = self
+            .filter(account_id, Collection::ContactCard, filters)
+            .await?;
+        result_set.apply_mask(card_ids.clone());
+        let (response, paginate) = self
+            .build_query_response(&result_set, State::from(resources.item_change_id), &request)
+            .await?;
+
+        if let Some(paginate) = paginate {
+            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
+
+            // Parse sort criteria, cards are returned in creation order by default
+            if let Some(sort) = request.sort.filter(|s| !s.is_empty()) {
+                for comparator in sort {
+                    comparators.push(match comparator.property {
+                        SortProperty::Size => {
+                            let sorted_list = resources
+                                .resources
+                                .iter()
+                                .filter(|resource| !resource.is_container())
+                                .map(|resource| (resource.size(), resource.document_id))
+                                .collect::<BTreeSet<_>>();
+
+                            query::Comparator::sorted_list(
+                                sorted_list.into_iter().map(|v| v.1).collect(),
+                                comparator.is_ascending,
+                            )
+                        }
+
+                        other => {
+                            return Err(trc::JmapEvent::UnsupportedSort
+                                .into_err()
+                                .details(other.to_string()));
+                        }
+                    });
+                }
+            } else {
+                comparators.push(query::Comparator::sorted_list(
+                    card_ids.into_iter().collect(),
+                    true,
+                ));
+            }
+
+            self.sort(result_set, comparators, paginate, response).await
+        } else {
+            Ok(response)
+        }
+    }
+}
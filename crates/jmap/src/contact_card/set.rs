@@ -0,0 +1,556 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use calcard::{Entry, Parser, vcard::VCard};
+use common::{
+    DavName, DavResources, IDX_UID, Server,
+    auth::{AccessToken, ResourceToken},
+    storage::index::ObjectIndexBuilder,
+};
+use groupware::{cache::GroupwareCache, contact::ContactCard};
+use jmap_proto::{
+    error::set::SetError,
+    method::set::{RequestArguments, SetRequest, SetResponse},
+    response::references::EvalObjectReferences,
+    types::{
+        collection::{Collection, SyncCollection, VanishedCollection},
+        id::Id,
+        property::Property,
+        state::State,
+        value::{MaybePatchValue, Object, SetValue, Value},
+    },
+};
+use store::{
+    query::Filter,
+    rand::{Rng, distr::Alphanumeric, rng},
+    roaring::RoaringBitmap,
+    write::BatchBuilder,
+};
+use trc::AddContext;
+
+use crate::JmapMethods;
+use std::future::Future;
+
+pub trait ContactCardSet: Sync + Send {
+    fn contact_card_set(
+        &self,
+        request: SetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+
+    fn assert_card_address_books(
+        &self,
+        resources: &DavResources,
+        resource_token: &ResourceToken,
+        address_book_ids: &[u32],
+        uid: Option<&str>,
+        pending: &[PendingCard],
+    ) -> impl Future<Output = trc::Result<Result<(), SetError>>> + Send;
+}
+
+// Cards added to an address book earlier in the same request, not yet committed
+pub struct PendingCard {
+    address_book_id: u32,
+    document_id: u32,
+    uid: Option<String>,
+}
+
+#[derive(Default)]
+struct CardChanges {
+    address_book_ids: Vec<u32>,
+    card_data: Option<String>,
+}
+
+impl ContactCardSet for Server {
+    async fn contact_card_set(
+        &self,
+        mut request: SetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::AddressBook)
+            .await
+            .caused_by(trc::location!())?;
+        let old_state = State::from(resources.item_change_id);
+        if let Some(if_in_state) = &request.if_in_state {
+            if &old_state != if_in_state {
+                return Err(trc::JmapEvent::StateMismatch.into_err());
+            }
+        }
+        let card_ids = resources
+            .resources
+            .iter()
+            .filter(|resource| !resource.is_container())
+            .map(|resource| resource.document_id)
+            .collect::<RoaringBitmap>();
+        let resource_token = self.get_resource_token(access_token, account_id).await?;
+        let mut response = self.prepare_set_response(&request, old_state).await?;
+        let will_destroy = request.unwrap_destroy();
+        let mut batch = BatchBuilder::new();
+        let mut pending = Vec::new();
+
+        // Process creates
+        'create: for (id, object) in request.unwrap_create() {
+            let mut changes = CardChanges::default();
+            if let Err(err) = changes.parse(object, &response) {
+                response.not_created.append(id, err);
+                continue 'create;
+            }
+            if changes.address_book_ids.is_empty() {
+                response.not_created.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::AddressBookIds)
+                        .with_description("Contact must belong to at least one address book."),
+                );
+                continue 'create;
+            }
+            let Some(card_data) = changes.card_data else {
+                response.not_created.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::CardData)
+                        .with_description("Missing cardData."),
+                );
+                continue 'create;
+            };
+
+            // Validate vCard data
+            let vcard = match parse_card_data(&card_data, self.core.groupware.max_vcard_size) {
+                Ok(vcard) => vcard,
+                Err(err) => {
+                    response.not_created.append(id, err);
+                    continue 'create;
+                }
+            };
+            if let Err(err) = self
+                .assert_card_address_books(
+                    &resources,
+                    &resource_token,
+                    &changes.address_book_ids,
+                    vcard.uid(),
+                    &pending,
+                )
+                .await?
+            {
+                response.not_created.append(id, err);
+                continue 'create;
+            }
+
+            // Validate quota
+            match self
+                .has_available_quota(&resource_token, card_data.len() as u64)
+                .await
+            {
+                Ok(_) => (),
+                Err(err) => {
+                    if err.matches(trc::EventType::Limit(trc::LimitEvent::Quota))
+                        || err.matches(trc::EventType::Limit(trc::LimitEvent::TenantQuota))
+                    {
+                        response.not_created.append(id, SetError::over_quota());
+                        continue 'create;
+                    } else {
+                        return Err(err);
+                    }
+                }
+            }
+
+            // Write record
+            let document_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::ContactCard, 1)
+                .await
+                .caused_by(trc::location!())?;
+            let uid = vcard.uid().map(|uid| uid.to_string());
+            ContactCard {
+                names: changes
+                    .address_book_ids
+                    .iter()
+                    .map(|address_book_id| {
+                        DavName::new(card_name(&resources, *address_book_id), *address_book_id)
+                    })
+                    .collect(),
+                card: vcard,
+                size: card_data.len() as u32,
+                ..Default::default()
+            }
+            .insert(access_token, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?;
+            pending.extend(changes.address_book_ids.into_iter().map(|address_book_id| {
+                PendingCard {
+                    address_book_id,
+                    document_id,
+                    uid: uid.clone(),
+                }
+            }));
+            response.created(id, document_id);
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain card
+            let document_id = id.document_id();
+            let card_ = if !card_ids.contains(document_id) {
+                None
+            } else {
+                self.get_archive(account_id, Collection::ContactCard, document_id)
+                    .await?
+            };
+            let card_ = if let Some(card) = card_ {
+                card
+            } else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+            let card = card_
+                .to_unarchived::<ContactCard>()
+                .caused_by(trc::location!())?;
+            let mut changes = CardChanges {
+                address_book_ids: card
+                    .inner
+                    .names
+                    .iter()
+                    .map(|name| name.parent_id.to_native())
+                    .collect(),
+                card_data: None,
+            };
+            if let Err(err) = changes.parse(object, &response) {
+                response.not_updated.append(id, err);
+                continue 'update;
+            }
+            if changes.address_book_ids.is_empty() {
+                response.not_updated.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::AddressBookIds)
+                        .with_description("Contact must belong to at least one address book."),
+                );
+                continue 'update;
+            }
+            let uid = card.inner.card.uid().map(|uid| uid.to_string());
+            let mut new_card = card
+                .deserialize::<ContactCard>()
+                .caused_by(trc::location!())?;
+
+            // Replace vCard data
+            if let Some(card_data) = changes.card_data {
+                let vcard = match parse_card_data(&card_data, self.core.groupware.max_vcard_size) {
+                    Ok(vcard) => vcard,
+                    Err(err) => {
+                        response.not_updated.append(id, err);
+                        continue 'update;
+                    }
+                };
+                if uid.is_some() && vcard.uid() != uid.as_deref() {
+                    response.not_updated.append(
+                        id,
+                        SetError::invalid_properties()
+                            .with_property(Property::CardData)
+                            .with_description("The UID of a contact cannot be changed."),
+                    );
+                    continue 'update;
+                }
+
+                // Validate quota
+                let extra_bytes = (card_data.len() as u64).saturating_sub(new_card.size as u64);
+                if extra_bytes > 0 {
+                    match self.has_available_quota(&resource_token, extra_bytes).await {
+                        Ok(_) => (),
+                        Err(err) => {
+                            if err.matches(trc::EventType::Limit(trc::LimitEvent::Quota))
+                                || err.matches(trc::EventType::Limit(trc::LimitEvent::TenantQuota))
+                            {
+                                response.not_updated.append(id, SetError::over_quota());
+                                continue 'update;
+                            } else {
+                                return Err(err);
+                            }
+                        }
+                    }
+                }
+
+                new_card.size = card_data.len() as u32;
+                new_card.card = vcard;
+            }
+
+            // Link the card to new address books and unlink it from removed ones
+            let added_ids = changes
+                .address_book_ids
+                .iter()
+                .filter(|address_book_id| {
+                    !new_card
+                        .names
+                        .iter()
+                        .any(|name| name.parent_id == **address_book_id)
+                })
+                .copied()
+                .collect::<Vec<_>>();
+            if let Err(err) = self
+                .assert_card_address_books(
+                    &resources,
+                    &resource_token,
+                    &added_ids,
+                    new_card.card.uid(),
+                    &pending,
+                )
+                .await?
+            {
+                response.not_updated.append(id, err);
+                continue 'update;
+            }
+            batch.with_account_id(account_id);
+            for name in new_card
+                .names
+                .iter()
+                .filter(|name| !changes.address_book_ids.contains(&name.parent_id))
+            {
+                if let Some(address_book) = resources.container_by_id(name.parent_id) {
+                    batch.log_vanished_item(
+                        VanishedCollection::AddressBook,
+                        resources.format_item(&format!("{}/{}", address_book.path(), name.name)),
+                    );
+                }
+            }
+            new_card
+                .names
+                .retain(|name| changes.address_book_ids.contains(&name.parent_id));
+            for address_book_id in added_ids {
+                new_card.names.push(DavName::new(
+                    card_name(&resources, address_book_id),
+                    address_book_id,
+                ));
+                pending.push(PendingCard {
+                    address_book_id,
+                    document_id,
+                    uid: new_card.card.uid().map(|uid| uid.to_string()),
+                });
+            }
+
+            // Write record
+            new_card
+                .update(access_token, card, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+            response.updated.append(id, None);
+        }
+
+        // Process deletions
+        for id in will_destroy {
+            let document_id = id.document_id();
+            let card_ = if !card_ids.contains(document_id) {
+                None
+            } else {
+                self.get_archive(account_id, Collection::ContactCard, document_id)
+                    .await?
+            };
+            let Some(card_) = card_ else {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            };
+            let card = card_
+                .to_unarchived::<ContactCard>()
+                .caused_by(trc::location!())?;
+
+            // Remove the card from all address books
+            batch
+                .with_account_id(account_id)
+                .with_collection(Collection::ContactCard);
+            for name in card.inner.names.iter() {
+                if let Some(address_book) = resources.container_by_id(name.parent_id.to_native()) {
+                    batch.log_vanished_item(
+                        VanishedCollection::AddressBook,
+                        resources.format_item(&format!("{}/{}", address_book.path(), name.name)),
+                    );
+                }
+            }
+            batch
+                .delete_document(document_id)
+                .custom(
+                    ObjectIndexBuilder::<_, ()>::new()
+                        .with_tenant_id(access_token)
+                        .with_current(card),
+                )
+                .caused_by(trc::location!())?
+                .commit_point();
+            response.destroyed.push(id);
+        }
+
+        // Write changes
+        if !batch.is_empty() {
+            let change_id = self
+                .commit_batch(batch)
+                .await
+                .and_then(|ids| ids.last_change_id(account_id))
+                .caused_by(trc::location!())?;
+            response.new_state = State::Exact(change_id).into();
+        }
+
+        Ok(response)
+    }
+
+    async fn assert_card_address_books(
+        &self,
+        resources: &DavResources,
+        resource_token: &ResourceToken,
+        address_book_ids: &[u32],
+        uid: Option<&str>,
+        pending: &[PendingCard],
+    ) -> trc::Result<Result<(), SetError>> {
+        if address_book_ids.is_empty() {
+            return Ok(Ok(()));
+        }
+
+        // Limits apply to the owner of the address book
+        let max_cards = self
+            .core
+            .groupware
+            .item_limits(
+                resource_token.account_id,
+                resource_token.tenant.as_ref().map(|tenant| tenant.id),
+            )
+            .cards_per_addressbook;
+        let uid_hits = if let Some(uid) = uid {
+            self.store()
+                .filter(
+                    resource_token.account_id,
+                    Collection::ContactCard,
+                    vec![Filter::eq(IDX_UID, uid.as_bytes().to_vec())],
+                )
+                .await
+                .caused_by(trc::location!())?
+                .results
+        } else {
+            RoaringBitmap::new()
+        };
+
+        for address_book_id in address_book_ids {
+            if resources.container_by_id(*address_book_id).is_none() {
+                return Ok(Err(SetError::invalid_properties()
+                    .with_property(Property::AddressBookIds)
+                    .with_description(format!(
+                        "Address book {} does not exist.",
+                        Id::from(*address_book_id)
+                    ))));
+            }
+
+            let mut cards = 0;
+            for child in resources.children(*address_book_id) {
+                if uid_hits.contains(child.document_id()) {
+                    return Ok(Err(SetError::already_exists()
+                        .with_existing_id(child.document_id().into())
+                        .with_description(
+                            "A contact with the same UID exists in the address book.",
+                        )));
+                }
+                cards += 1;
+            }
+            for card in pending
+                .iter()
+                .filter(|card| card.address_book_id == *address_book_id)
+            {
+                if uid.is_some() && card.uid.as_deref() == uid {
+                    return Ok(Err(SetError::already_exists()
+                        .with_existing_id(card.document_id.into())
+                        .with_description(
+                            "A contact with the same UID exists in the address book.",
+                        )));
+                }
+                cards += 1;
+            }
+            if max_cards.is_some_and(|max_cards| cards >= max_cards) {
+                return Ok(Err(SetError::over_quota().with_description(
+                    "The address book has reached its maximum number of contacts.",
+                )));
+            }
+        }
+
+        Ok(Ok(()))
+    }
+}
+
+fn parse_card_data(card_data: &str, max_size: usize) -> Result<VCard, SetError> {
+    if card_data.len() > max_size {
+        return Err(SetError::too_large()
+            .with_property(Property::CardData)
+            .with_description(format!(
+                "Card data exceeds the maximum size of {max_size} bytes."
+            )));
+    }
+
+    match Parser::new(card_data).strict().entry() {
+        Entry::VCard(vcard) => Ok(vcard),
+        _ => Err(SetError::invalid_properties()
+            .with_property(Property::CardData)
+            .with_description("Invalid vCard data.")),
+    }
+}
+
+impl CardChanges {
+    fn parse(&mut self, changes: Object<SetValue>, response: &SetResponse) -> Result<(), SetError> {
+        for (property, value) in changes.0 {
+            match (&property, response.eval_object_references(value)?) {
+                (Property::AddressBookIds, MaybePatchValue::Value(Value::List(ids))) => {
+                    self.address_book_ids = ids
+                        .into_iter()
+                        .filter_map(|id| id.try_unwrap_id()?.document_id().into())
+                        .collect();
+                }
+                (Property::AddressBookIds, MaybePatchValue::Patch(patch)) => {
+                    let mut patch = patch.into_iter();
+                    if let Some(id) = patch.next().unwrap().try_unwrap_id() {
+                        let document_id = id.document_id();
+                        if patch.next().unwrap().try_unwrap_bool().unwrap_or_default() {
+                            if !self.address_book_ids.contains(&document_id) {
+                                self.address_book_ids.push(document_id);
+                            }
+                        } else {
+                            self.address_book_ids.retain(|id| id != &document_id);
+                        }
+                    }
+                }
+                (Property::CardData, MaybePatchValue::Value(Value::Text(value))) => {
+                    self.card_data = Some(value);
+                }
+                _ => {
+                    return Err(SetError::invalid_properties()
+                        .with_property(property)
+                        .with_description("Invalid property or value.".to_string()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn card_name(resources: &DavResources, address_book_id: u32) -> String {
+    let address_book_path = resources
+        .container_by_id(address_book_id)
+        .map(|address_book| address_book.path().to_string())
+        .unwrap_or_default();
+    loop {
+        let name = format!(
+            "{}.vcf",
+            rng()
+                .sample_iter(Alphanumeric)
+                .take(15)
+                .map(|ch| char::from(ch.to_ascii_lowercase()))
+                .collect::<String>()
+        );
+        if resources
+            .by_path(&format!("{address_book_path}/{name}"))
+            .is_none()
+        {
+            return name;
+        }
+    }
+}
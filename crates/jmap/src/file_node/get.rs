@@ -0,0 +1,173 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{Server, auth::AccessToken};
+use groupware::{cache::GroupwareCache, file::FileNode};
+use jmap_proto::{
+    method::get::{GetRequest, GetResponse, RequestArguments},
+    types::{
+        blob::BlobId,
+        collection::{Collection, SyncCollection},
+        date::UTCDate,
+        id::Id,
+        property::Property,
+        state::State,
+        value::{Object, Value},
+    },
+};
+use store::BlobClass;
+use trc::AddContext;
+
+use std::future::Future;
+
+use super::visible_nodes;
+
+pub trait FileNodeGet: Sync + Send {
+    fn file_node_get(
+        &self,
+        request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<GetResponse>> + Send;
+}
+
+impl FileNodeGet for Server {
+    async fn file_node_get(
+        &self,
+        mut request: GetRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<GetResponse> {
+        let ids = request.unwrap_ids(self.core.jmap.get_max_objects)?;
+        let properties = request.unwrap_properties(&[
+            Property::Id,
+            Property::ParentId,
+            Property::Name,
+            Property::BlobId,
+            Property::Size,
+            Property::Type,
+            Property::Created,
+            Property::Modified,
+        ]);
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::FileNode)
+            .await
+            .caused_by(trc::location!())?;
+        let node_ids = visible_nodes(&resources, access_token, account_id);
+        let ids = if let Some(ids) = ids {
+            ids
+        } else {
+            node_ids
+                .iter()
+                .take(self.core.jmap.get_max_objects)
+                .map(Into::into)
+                .collect::<Vec<_>>()
+        };
+        let mut response = GetResponse {
+            account_id: request.account_id.into(),
+            state: State::from(resources.item_change_id).into(),
+            list: Vec::with_capacity(ids.len()),
+            not_found: vec![],
+        };
+
+        for id in ids {
+            // Obtain the node object
+            let document_id = id.document_id();
+            if !node_ids.contains(document_id) {
+                response.not_found.push(id.into());
+                continue;
+            }
+            let node_ = if let Some(node) = self
+                .get_archive(account_id, Collection::FileNode, document_id)
+                .await?
+            {
+                node
+            } else {
+                response.not_found.push(id.into());
+                continue;
+            };
+            let node = node_.unarchive::<FileNode>().caused_by(trc::location!())?;
+            let mut result = Object::with_capacity(properties.len());
+            for property in &properties {
+                match property {
+                    Property::Id => {
+                        result.append(Property::Id, Value::Id(id));
+                    }
+                    Property::ParentId => {
+                        let parent_id = node.parent_id.to_native();
+                        result.append(
+                            Property::ParentId,
+                            if parent_id > 0 {
+                                Value::Id(Id::from(parent_id - 1))
+                            } else {
+                                Value::Null
+                            },
+                        );
+                    }
+                    Property::Name => {
+                        result.append(Property::Name, Value::from(&node.name));
+                    }
+                    Property::BlobId => {
+                        result.append(
+                            Property::BlobId,
+                            node.file
+                                .as_ref()
+                                .map(|file| {
+                                    Value::BlobId(
+                                        BlobId::new(
+                                            (&file.blob_hash).into(),
+                                            BlobClass::Linked {
+                                                account_id,
+                                                collection: Collection::FileNode.into(),
+                                                document_id,
+                                            },
+                                        )
+                                        .with_section_size(file.size.to_native() as usize),
+                                    )
+                                })
+                                .unwrap_or(Value::Null),
+                        );
+                    }
+                    Property::Size => {
+                        result.append(
+                            Property::Size,
+                            node.file
+                                .as_ref()
+                                .map(|file| Value::UnsignedInt(file.size.to_native() as u64))
+                                .unwrap_or(Value::Null),
+                        );
+                    }
+                    Property::Type => {
+                        result.append(
+                            Property::Type,
+                            node.file
+                                .as_ref()
+                                .map(|file| Value::from(&file.media_type))
+                                .unwrap_or(Value::Null),
+                        );
+                    }
+                    Property::Created => {
+                        result.append(
+                            Property::Created,
+                            Value::Date(UTCDate::from_timestamp(node.created.to_native())),
+                        );
+                    }
+                    Property::Modified => {
+                        result.append(
+                            Property::Modified,
+                            Value::Date(UTCDate::from_timestamp(node.modified.to_native())),
+                        );
+                    }
+                    property => {
+                        result.append(property.clone(), Value::Null);
+                    }
+                }
+            }
+            response.list.push(result);
+        }
+
+        Ok(response)
+    }
+}
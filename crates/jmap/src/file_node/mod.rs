@@ -0,0 +1,40 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{DavResourcePath, DavResources, auth::AccessToken};
+use jmap_proto::types::acl::Acl;
+use store::roaring::RoaringBitmap;
+
+pub mod get;
+pub mod query;
+pub mod set;
+
+// Nodes shared with the requester follow the same ACLs as WebDAV
+pub(crate) fn visible_nodes(
+    resources: &DavResources,
+    access_token: &AccessToken,
+    account_id: u32,
+) -> RoaringBitmap {
+    if access_token.is_member(account_id) {
+        resources
+            .resources
+            .iter()
+            .map(|resource| resource.document_id)
+            .collect()
+    } else {
+        resources.shared_containers(access_token, [Acl::Read], true)
+    }
+}
+
+pub(crate) fn node_by_id(
+    resources: &DavResources,
+    document_id: u32,
+) -> Option<DavResourcePath<'_>> {
+    resources.paths.iter().find_map(|path| {
+        let resource = &resources.resources[path.resource_idx];
+        (resource.document_id == document_id).then_some(DavResourcePath { path, resource })
+    })
+}
@@ -0,0 +1,156 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use crate::JmapMethods;
+use common::{Server, auth::AccessToken};
+use groupware::cache::GroupwareCache;
+use jmap_proto::{
+    method::query::{
+        Comparator, Filter, QueryRequest, QueryResponse, RequestArguments, SortProperty,
+    },
+    types::{
+        collection::{Collection, SyncCollection},
+        state::State,
+    },
+};
+use std::{collections::BTreeSet, future::Future};
+use store::{
+    query::{self},
+    roaring::RoaringBitmap,
+};
+use trc::AddContext;
+
+use super::visible_nodes;
+
+pub trait FileNodeQuery: Sync + Send {
+    fn file_node_query(
+        &self,
+        request: QueryRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<QueryResponse>> + Send;
+}
+
+impl FileNodeQuery for Server {
+    async fn file_node_query(
+        &self,
+        mut request: QueryRequest<RequestArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<QueryResponse> {
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::FileNode)
+            .await
+            .caused_by(trc::location!())?;
+        let mut filters = Vec::with_capacity(request.filter.len());
+
+        for cond in std::mem::take(&mut request.filter) {
+            match cond {
+                Filter::ParentId(parent_id) => {
+                    let parent_id = parent_id.map(|id| id.document_id());
+                    filters.push(query::Filter::is_in_set(
+                        resources
+                            .paths
+                            .iter()
+                            .filter(|path| path.parent_id == parent_id)
+                            .map(|path| resources.resources[path.resource_idx].document_id)
+                            .collect::<RoaringBitmap>(),
+                    ));
+                }
+                Filter::Name(name) => {
+                    let name = name.to_lowercase();
+                    filters.push(query::Filter::is_in_set(
+                        resources
+                            .resources
+                            .iter()
+                            .filter(|resource| {
+                                resource
+                                    .container_name()
+                                    .is_some_and(|n| n.to_lowercase().contains(&name))
+                            })
+                            .map(|resource| resource.document_id)
+                            .collect::<RoaringBitmap>(),
+                    ));
+                }
+                Filter::Id(ids) => {
+                    filters.push(query::Filter::is_in_set(
+                        ids.into_iter()
+                            .map(|id| id.document_id())
+                            .collect::<RoaringBitmap>(),
+                    ));
+                }
+                Filter::And | Filter::Or | Filter::Not | Filter::Close => {
+                    filters.push(cond.into());
+                }
+
+                other => {
+                    return Err(trc::JmapEvent::UnsupportedFilter
+                        .into_err()
+                        .details(other.to_string()));
+                }
+            }
+        }
+
+        let mut result_set = self
+            .filter(account_id, Collection::FileNode, filters)
+            .await?;
+        result_set.apply_mask(visible_nodes(&resources, access_token, account_id));
+        let (response, paginate) = self
+            .build_query_response(&result_set, State::from(resources.item_change_id), &request)
+            .await?;
+
+        if let Some(paginate) = paginate {
+            let mut comparators = Vec::with_capacity(request.sort.as_ref().map_or(1, |s| s.len()));
+
+            // Parse sort criteria
+            for comparator in request
+                .sort
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| vec![Comparator::ascending(SortProperty::Name)])
+            {
+                comparators.push(match comparator.property {
+                    SortProperty::Name => {
+                        let sorted_list = resources
+                            .resources
+                            .iter()
+                            .filter_map(|resource| {
+                                resource
+                                    .container_name()
+                                    .map(|name| (name.to_lowercase(), resource.document_id))
+                            })
+                            .collect::<BTreeSet<_>>();
+
+                        query::Comparator::sorted_list(
+                            sorted_list.into_iter().map(|v| v.1).collect(),
+                            comparator.is_ascending,
+                        )
+                    }
+                    SortProperty::Size => {
+                        let sorted_list = resources
+                            .resources
+                            .iter()
+                            .map(|resource| (resource.size(), resource.document_id))
+                            .collect::<BTreeSet<_>>();
+
+                        query::Comparator::sorted_list(
+                            sorted_list.into_iter().map(|v| v.1).collect(),
+                            comparator.is_ascending,
+                        )
+                    }
+
+                    other => {
+                        return Err(trc::JmapEvent::UnsupportedSort
+                            .into_err()
+                            .details(other.to_string()));
+                    }
+                });
+            }
+
+            self.sort(result_set, comparators, paginate, response).await
+        } else {
+            Ok(response)
+        }
+    }
+}
@@ -0,0 +1,622 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::{
+    DavResources, Server,
+    auth::{AccessToken, ResourceToken},
+};
+use groupware::{
+    DestroyArchive,
+    cache::GroupwareCache,
+    file::{FileNode, FileProperties},
+};
+use jmap_proto::{
+    error::set::{SetError, SetErrorType},
+    method::set::{SetRequest, SetResponse},
+    object::file_node::SetArguments,
+    response::references::EvalObjectReferences,
+    types::{
+        acl::Acl,
+        blob::BlobId,
+        collection::{Collection, SyncCollection, VanishedCollection},
+        property::Property,
+        state::State,
+        value::{MaybePatchValue, Object, SetValue, Value},
+    },
+};
+use store::{
+    ValueKey,
+    write::{BatchBuilder, ValueClass},
+};
+use trc::AddContext;
+use utils::BlobHash;
+
+use crate::{JmapMethods, blob::download::BlobDownload};
+use std::future::Future;
+
+use super::{node_by_id, visible_nodes};
+
+pub trait FileNodeSet: Sync + Send {
+    fn file_node_set(
+        &self,
+        request: SetRequest<SetArguments>,
+        access_token: &AccessToken,
+    ) -> impl Future<Output = trc::Result<SetResponse>> + Send;
+
+    fn file_node_blob(
+        &self,
+        resources: &DavResources,
+        access_token: &AccessToken,
+        resource_token: &ResourceToken,
+        parent_id: Option<u32>,
+        blob_id: &BlobId,
+        current_size: u32,
+    ) -> impl Future<Output = trc::Result<Result<(BlobHash, u32), SetError>>> + Send;
+}
+
+#[derive(Default)]
+struct NodeChanges {
+    parent_id: Option<Option<u32>>,
+    name: Option<String>,
+    blob_id: Option<BlobId>,
+    media_type: Option<Option<String>>,
+}
+
+struct CreatedNode {
+    document_id: u32,
+    parent_id: Option<u32>,
+    path: String,
+    is_container: bool,
+}
+
+impl FileNodeSet for Server {
+    async fn file_node_set(
+        &self,
+        mut request: SetRequest<SetArguments>,
+        access_token: &AccessToken,
+    ) -> trc::Result<SetResponse> {
+        let account_id = request.account_id.document_id();
+        let resources = self
+            .fetch_dav_resources(access_token, account_id, SyncCollection::FileNode)
+            .await
+            .caused_by(trc::location!())?;
+        let old_state = State::from(resources.item_change_id);
+        if let Some(if_in_state) = &request.if_in_state {
+            if &old_state != if_in_state {
+                return Err(trc::JmapEvent::StateMismatch.into_err());
+            }
+        }
+        let is_member = access_token.is_member(account_id);
+        let node_ids = visible_nodes(&resources, access_token, account_id);
+        let resource_token = self.get_resource_token(access_token, account_id).await?;
+        let max_children = self
+            .core
+            .groupware
+            .item_limits(
+                resource_token.account_id,
+                resource_token.tenant.as_ref().map(|tenant| tenant.id),
+            )
+            .children_per_folder;
+        let mut response = self.prepare_set_response(&request, old_state).await?;
+        let will_destroy = request.unwrap_destroy();
+        let mut batch = BatchBuilder::new();
+        let mut created: Vec<CreatedNode> = Vec::new();
+
+        // Process creates
+        'create: for (id, object) in request.unwrap_create() {
+            let mut changes = NodeChanges::default();
+            if let Err(err) = changes.parse(object, &response) {
+                response.not_created.append(id, err);
+                continue 'create;
+            }
+            let Some(name) = changes.name else {
+                response.not_created.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::Name)
+                        .with_description("Missing node name."),
+                );
+                continue 'create;
+            };
+            let parent_id = changes.parent_id.flatten();
+
+            // Validate parent and ACLs
+            let parent_path = if let Some(parent_id) = parent_id {
+                if let Some(parent) = resources.container_by_id(parent_id) {
+                    if !is_member
+                        && !resources.has_access_to_container(
+                            access_token,
+                            parent_id,
+                            Acl::AddItems,
+                        )
+                    {
+                        response.not_created.append(
+                            id,
+                            SetError::forbidden().with_description(
+                                "You are not allowed to add items to this folder.",
+                            ),
+                        );
+                        continue 'create;
+                    }
+                    Some(parent.path().to_string())
+                } else if let Some(parent) = created
+                    .iter()
+                    .find(|node| node.document_id == parent_id && node.is_container)
+                {
+                    Some(parent.path.clone())
+                } else {
+                    response.not_created.append(
+                        id,
+                        SetError::invalid_properties()
+                            .with_property(Property::ParentId)
+                            .with_description("Parent folder does not exist."),
+                    );
+                    continue 'create;
+                }
+            } else if is_member {
+                None
+            } else {
+                response.not_created.append(
+                    id,
+                    SetError::forbidden()
+                        .with_description("You are not allowed to add items to this folder."),
+                );
+                continue 'create;
+            };
+
+            // Validate name and item limit
+            let path = node_path(parent_path.as_deref(), &name);
+            if resources.by_path(&path).is_some() || created.iter().any(|node| node.path == path) {
+                response.not_created.append(
+                    id,
+                    SetError::already_exists()
+                        .with_description("A node with the same name exists in this folder."),
+                );
+                continue 'create;
+            }
+            if max_children.is_some_and(|max_children| {
+                resources
+                    .paths
+                    .iter()
+                    .filter(|path| path.parent_id == parent_id)
+                    .count()
+                    + created
+                        .iter()
+                        .filter(|node| node.parent_id == parent_id)
+                        .count()
+                    >= max_children
+            }) {
+                response.not_created.append(
+                    id,
+                    SetError::over_quota()
+                        .with_description("The folder has reached its maximum number of items."),
+                );
+                continue 'create;
+            }
+
+            // Store file contents
+            let file = if let Some(blob_id) = &changes.blob_id {
+                match self
+                    .file_node_blob(
+                        &resources,
+                        access_token,
+                        &resource_token,
+                        parent_id,
+                        blob_id,
+                        0,
+                    )
+                    .await?
+                {
+                    Ok((blob_hash, size)) => Some(FileProperties {
+                        blob_hash,
+                        size,
+                        media_type: changes.media_type.flatten(),
+                        executable: false,
+                    }),
+                    Err(err) => {
+                        response.not_created.append(id, err);
+                        continue 'create;
+                    }
+                }
+            } else if changes.media_type.flatten().is_some() {
+                response.not_created.append(
+                    id,
+                    SetError::invalid_properties()
+                        .with_property(Property::Type)
+                        .with_description("Folders cannot have a media type."),
+                );
+                continue 'create;
+            } else {
+                None
+            };
+
+            // Write record
+            let document_id = self
+                .store()
+                .assign_document_ids(account_id, Collection::FileNode, 1)
+                .await
+                .caused_by(trc::location!())?;
+            created.push(CreatedNode {
+                document_id,
+                parent_id,
+                path,
+                is_container: file.is_none(),
+            });
+            FileNode {
+                parent_id: parent_id.map(|id| id + 1).unwrap_or(0),
+                name,
+                file,
+                ..Default::default()
+            }
+            .insert(access_token, account_id, document_id, &mut batch)
+            .caused_by(trc::location!())?;
+            response.created(id, document_id);
+        }
+
+        // Process updates
+        'update: for (id, object) in request.unwrap_update() {
+            // Make sure id won't be destroyed
+            if will_destroy.contains(&id) {
+                response.not_updated.append(id, SetError::will_destroy());
+                continue 'update;
+            }
+
+            // Obtain node
+            let document_id = id.document_id();
+            let node_ = if !node_ids.contains(document_id) {
+                None
+            } else {
+                self.get_archive(account_id, Collection::FileNode, document_id)
+                    .await?
+            };
+            let (Some(node_), Some(resource)) = (node_, node_by_id(&resources, document_id)) else {
+                response.not_updated.append(id, SetError::not_found());
+                continue 'update;
+            };
+            if !is_member
+                && !resources.has_access_to_container(access_token, document_id, Acl::Modify)
+            {
+                response.not_updated.append(
+                    id,
+                    SetError::forbidden()
+                        .with_description("You are not allowed to modify this node."),
+                );
+                continue 'update;
+            }
+            let node = node_
+                .to_unarchived::<FileNode>()
+                .caused_by(trc::location!())?;
+            let mut changes = NodeChanges::default();
+            if let Err(err) = changes.parse(object, &response) {
+                response.not_updated.append(id, err);
+                continue 'update;
+            }
+            let mut new_node = node.deserialize::<FileNode>().caused_by(trc::location!())?;
+
+            // Rename or move node
+            let old_parent_id = resource.parent_id();
+            let parent_id = changes.parent_id.unwrap_or(old_parent_id);
+            let name = changes.name.unwrap_or_else(|| new_node.name.clone());
+            if parent_id != old_parent_id || name != new_node.name {
+                let parent_path = if let Some(parent_id) = parent_id {
+                    match resources.container_by_id(parent_id) {
+                        Some(parent)
+                            if parent_id != document_id
+                                && !parent
+                                    .path()
+                                    .strip_prefix(resource.path())
+                                    .is_some_and(|path| path.starts_with('/')) =>
+                        {
+                            if parent_id != old_parent_id.unwrap_or(u32::MAX)
+                                && !is_member
+                                && !resources.has_access_to_container(
+                                    access_token,
+                                    parent_id,
+                                    Acl::AddItems,
+                                )
+                            {
+                                response.not_updated.append(
+                                    id,
+                                    SetError::forbidden().with_description(
+                                        "You are not allowed to add items to this folder.",
+                                    ),
+                                );
+                                continue 'update;
+                            }
+                            Some(parent.path().to_string())
+                        }
+                        _ => {
+                            response.not_updated.append(
+                                id,
+                                SetError::invalid_properties()
+                                    .with_property(Property::ParentId)
+                                    .with_description("Invalid parent folder."),
+                            );
+                            continue 'update;
+                        }
+                    }
+                } else if is_member {
+                    None
+                } else {
+                    response.not_updated.append(
+                        id,
+                        SetError::forbidden()
+                            .with_description("You are not allowed to add items to this folder."),
+                    );
+                    continue 'update;
+                };
+                let path = node_path(parent_path.as_deref(), &name);
+                if resources.by_path(&path).is_some()
+                    || created.iter().any(|node| node.path == path)
+                {
+                    response.not_updated.append(
+                        id,
+                        SetError::already_exists()
+                            .with_description("A node with the same name exists in this folder."),
+                    );
+                    continue 'update;
+                }
+
+                batch.with_account_id(account_id).log_vanished_item(
+                    VanishedCollection::FileNode,
+                    resources.format_resource(resource),
+                );
+                new_node.parent_id = parent_id.map(|id| id + 1).unwrap_or(0);
+                new_node.name = name;
+            }
+
+            // Replace file contents
+            match (&mut new_node.file, changes.blob_id, changes.media_type) {
+                (Some(file), blob_id, media_type) => {
+                    if let Some(blob_id) = blob_id {
+                        match self
+                            .file_node_blob(
+                                &resources,
+                                access_token,
+                                &resource_token,
+                                parent_id,
+                                &blob_id,
+                                file.size,
+                            )
+                            .await?
+                        {
+                            Ok((blob_hash, size)) => {
+                                file.blob_hash = blob_hash;
+                                file.size = size;
+                            }
+                            Err(err) => {
+                                response.not_updated.append(id, err);
+                                continue 'update;
+                            }
+                        }
+                    }
+                    if let Some(media_type) = media_type {
+                        file.media_type = media_type;
+                    }
+                }
+                (None, None, None) => {}
+                (None, _, _) => {
+                    response.not_updated.append(
+                        id,
+                        SetError::invalid_properties()
+                            .with_properties([Property::BlobId, Property::Type])
+                            .with_description("Folders cannot have contents."),
+                    );
+                    continue 'update;
+                }
+            }
+
+            // Write record
+            new_node
+                .update(access_token, node, account_id, document_id, &mut batch)
+                .caused_by(trc::location!())?;
+            response.updated.append(id, None);
+        }
+
+        // Process deletions
+        'destroy: for id in will_destroy {
+            let document_id = id.document_id();
+            let Some(resource) =
+                node_by_id(&resources, document_id).filter(|_| node_ids.contains(document_id))
+            else {
+                response.not_destroyed.append(id, SetError::not_found());
+                continue;
+            };
+
+            // Folders can only be destroyed with their children when requested
+            let mut ids = resources.subtree(resource.path()).collect::<Vec<_>>();
+            if ids.len() > 1
+                && !request
+                    .arguments
+                    .on_destroy_remove_children
+                    .unwrap_or(false)
+            {
+                response.not_destroyed.append(
+                    id,
+                    SetError::new(SetErrorType::NodeHasChildren)
+                        .with_description("Folder is not empty."),
+                );
+                continue;
+            }
+
+            // Validate ACLs
+            if !is_member {
+                let permissions = resources.shared_containers(access_token, [Acl::Delete], false);
+                if !ids
+                    .iter()
+                    .all(|node| permissions.contains(node.document_id()))
+                {
+                    response.not_destroyed.append(
+                        id,
+                        SetError::forbidden()
+                            .with_description("You are not allowed to delete this node."),
+                    );
+                    continue;
+                }
+            }
+
+            // Delete from the deepest node to the root
+            ids.sort_unstable_by_key(|b| std::cmp::Reverse(b.hierarchy_seq()));
+            for child in ids {
+                let Some(node_) = self
+                    .get_archive(account_id, Collection::FileNode, child.document_id())
+                    .await?
+                else {
+                    if child.document_id() == document_id {
+                        response.not_destroyed.append(id, SetError::not_found());
+                        continue 'destroy;
+                    }
+                    continue;
+                };
+                DestroyArchive(
+                    node_
+                        .to_unarchived::<FileNode>()
+                        .caused_by(trc::location!())?,
+                )
+                .delete(
+                    access_token,
+                    account_id,
+                    child.document_id(),
+                    &mut batch,
+                    resources.format_resource(child),
+                )
+                .caused_by(trc::location!())?;
+            }
+            response.destroyed.push(id);
+        }
+
+        // Write changes
+        if !batch.is_empty() {
+            let change_id = self
+                .commit_batch(batch)
+                .await
+                .and_then(|ids| ids.last_change_id(account_id))
+                .caused_by(trc::location!())?;
+            response.new_state = State::Exact(change_id).into();
+        }
+
+        Ok(response)
+    }
+
+    async fn file_node_blob(
+        &self,
+        resources: &DavResources,
+        access_token: &AccessToken,
+        resource_token: &ResourceToken,
+        parent_id: Option<u32>,
+        blob_id: &BlobId,
+        current_size: u32,
+    ) -> trc::Result<Result<(BlobHash, u32), SetError>> {
+        let Some(bytes) = self.blob_download(blob_id, access_token).await? else {
+            return Ok(Err(SetError::new(SetErrorType::BlobNotFound)
+                .with_property(Property::BlobId)
+                .with_description(format!("Blob {blob_id} not found."))));
+        };
+        let max_size = self.core.groupware.max_file_size;
+        if bytes.len() > max_size {
+            return Ok(Err(SetError::too_large()
+                .with_property(Property::BlobId)
+                .with_description(format!(
+                    "File exceeds the maximum size of {max_size} bytes."
+                ))));
+        }
+
+        // Validate account and folder quotas
+        let extra_bytes = (bytes.len() as u64).saturating_sub(current_size as u64);
+        if extra_bytes > 0 {
+            match self.has_available_quota(resource_token, extra_bytes).await {
+                Ok(_) => (),
+                Err(err) => {
+                    if err.matches(trc::EventType::Limit(trc::LimitEvent::Quota))
+                        || err.matches(trc::EventType::Limit(trc::LimitEvent::TenantQuota))
+                    {
+                        return Ok(Err(SetError::over_quota()));
+                    } else {
+                        return Err(err);
+                    }
+                }
+            }
+
+            // Folder quotas also cover the contents of their subfolders
+            let mut container_id = parent_id;
+            while let Some(container) = container_id.and_then(|id| resources.container_by_id(id)) {
+                if let Some(quota) = self
+                    .store()
+                    .get_value::<u64>(ValueKey {
+                        account_id: resource_token.account_id,
+                        collection: Collection::FileNode.into(),
+                        document_id: container.document_id(),
+                        class: ValueClass::Property(Property::Quota.into()),
+                    })
+                    .await
+                    .caused_by(trc::location!())?
+                {
+                    let used = resources
+                        .subtree(container.path())
+                        .map(|resource| resource.size() as u64)
+                        .sum::<u64>();
+                    if used + extra_bytes > quota {
+                        return Ok(Err(SetError::over_quota()
+                            .with_description("The folder has reached its quota.")));
+                    }
+                }
+                container_id = container.parent_id();
+            }
+        }
+
+        let blob_hash = self
+            .put_blob(resource_token.account_id, &bytes, false)
+            .await
+            .caused_by(trc::location!())?
+            .hash;
+
+        Ok(Ok((blob_hash, bytes.len() as u32)))
+    }
+}
+
+impl NodeChanges {
+    fn parse(&mut self, changes: Object<SetValue>, response: &SetResponse) -> Result<(), SetError> {
+        for (property, value) in changes.0 {
+            match (&property, response.eval_object_references(value)?) {
+                (Property::ParentId, MaybePatchValue::Value(Value::Id(id))) => {
+                    self.parent_id = Some(Some(id.document_id()));
+                }
+                (Property::ParentId, MaybePatchValue::Value(Value::Null)) => {
+                    self.parent_id = Some(None);
+                }
+                (Property::Name, MaybePatchValue::Value(Value::Text(value)))
+                    if !value.is_empty() && !value.contains('/') =>
+                {
+                    self.name = Some(value);
+                }
+                (Property::BlobId, MaybePatchValue::Value(Value::BlobId(value))) => {
+                    self.blob_id = Some(value);
+                }
+                (Property::Type, MaybePatchValue::Value(Value::Text(value))) => {
+                    self.media_type = Some(Some(value));
+                }
+                (Property::Type, MaybePatchValue::Value(Value::Null)) => {
+                    self.media_type = Some(None);
+                }
+                _ => {
+                    return Err(SetError::invalid_properties()
+                        .with_property(property)
+                        .with_description("Invalid property or value.".to_string()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn node_path(parent_path: Option<&str>, name: &str) -> String {
+    if let Some(parent_path) = parent_path {
+        format!("{parent_path}/{name}")
+    } else {
+        name.to_string()
+    }
+}
@@ -22,10 +22,15 @@ use store::{
 };
 use trc::AddContext;
 
+pub mod address_book;
 pub mod api;
 pub mod blob;
+pub mod calendar;
+pub mod calendar_event;
 pub mod changes;
+pub mod contact_card;
 pub mod email;
+pub mod file_node;
 pub mod identity;
 pub mod mailbox;
 pub mod principal;
@@ -46,6 +46,7 @@ impl BroadcastBatch<Vec<BroadcastEvent>> {
                 }
                 BroadcastEvent::ReloadSettings => 0,
                 BroadcastEvent::ReloadBlockedIps => 1,
+                BroadcastEvent::ReloadGroupware => 2,
             };
 
             serialized.extend_from_slice(&u64::MAX.to_le_bytes());
@@ -99,6 +100,7 @@ impl<T: AsRef<[u8]>> BroadcastBatch<T> {
                     match account_id {
                         0 => BroadcastEvent::ReloadSettings,
                         1 => BroadcastEvent::ReloadBlockedIps,
+                        2 => BroadcastEvent::ReloadGroupware,
                         _ => return None,
                     }
                 })
@@ -151,6 +151,21 @@ pub fn spawn_broadcast_subscriber(inner: Arc<Inner>, mut shutdown_rx: watch::Rec
                                                 );
                                             }
                                         },
+                                        BroadcastEvent::ReloadGroupware => {
+                                            match inner.build_server().reload_groupware().await {
+                                                Ok(result) => {
+                                                    if let Some(new_core) = result.new_core {
+                                                        inner.shared_core.store(new_core.into());
+                                                    }
+                                                }
+                                                Err(err) => {
+                                                    trc::error!(
+                                                        err.details("Failed to reload settings")
+                                                            .caused_by(trc::location!())
+                                                    );
+                                                }
+                                            }
+                                        },
                                     }
                                 } else if !has_errors {
                                     trc::event!(
@@ -195,5 +210,6 @@ fn log_event(event: BroadcastEvent) -> trc::Value {
         ]),
         BroadcastEvent::ReloadSettings => CompactString::const_new("ReloadSettings").into(),
         BroadcastEvent::ReloadBlockedIps => CompactString::const_new("ReloadBlockedIps").into(),
+        BroadcastEvent::ReloadGroupware => CompactString::const_new("ReloadGroupware").into(),
     }
 }
@@ -25,6 +25,7 @@ use common::telemetry::{
 };
 
 use email::message::delete::EmailDeletion;
+use groupware::{calendar::mirror::CalendarMirrorSync, contact::sync::DirectoryAddressBookSync};
 use smtp::reporting::SmtpReporting;
 use store::{PurgeStore, write::now};
 use tokio::sync::mpsc;
@@ -45,6 +46,8 @@ enum ActionClass {
     #[cfg(feature = "enterprise")]
     InternalMetrics,
     CalculateMetrics,
+    AddressBookSync(usize),
+    CalendarMirror(usize),
     #[cfg(feature = "enterprise")]
     AlertMetrics,
     #[cfg(feature = "enterprise")]
@@ -98,6 +101,20 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
             // Calculate expensive metrics
             queue.schedule(Instant::now(), ActionClass::CalculateMetrics);
 
+            // Directory-synchronized address books
+            if server.core.network.roles.sync_addressbooks {
+                for idx in 0..server.core.groupware.directory_addressbooks.len() {
+                    queue.schedule(Instant::now(), ActionClass::AddressBookSync(idx));
+                }
+            }
+
+            // Calendar mirrors
+            if server.core.network.roles.mirror_calendars {
+                for idx in 0..server.core.groupware.calendar_mirrors.len() {
+                    queue.schedule(Instant::now(), ActionClass::CalendarMirror(idx));
+                }
+            }
+
             // Add all ACME renewals to heap
             if server.core.network.roles.renew_acme {
                 for provider in server.core.acme.providers.values() {
@@ -171,6 +188,26 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                 _ => {}
                             }
 
+                            // Schedule newly added address books
+                            if server.core.network.roles.sync_addressbooks {
+                                for idx in 0..server.core.groupware.directory_addressbooks.len() {
+                                    let action = ActionClass::AddressBookSync(idx);
+                                    if !queue.has_action(&action) {
+                                        queue.schedule(Instant::now(), action);
+                                    }
+                                }
+                            }
+
+                            // Schedule newly added calendar mirrors
+                            if server.core.network.roles.mirror_calendars {
+                                for idx in 0..server.core.groupware.calendar_mirrors.len() {
+                                    let action = ActionClass::CalendarMirror(idx);
+                                    if !queue.has_action(&action) {
+                                        queue.schedule(Instant::now(), action);
+                                    }
+                                }
+                            }
+
                             // SPDX-SnippetBegin
                             // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
                             // SPDX-License-Identifier: LicenseRef-SEL
@@ -485,6 +522,65 @@ pub fn spawn_housekeeper(inner: Arc<Inner>, mut rx: mpsc::Receiver<HousekeeperEv
                                 });
                             }
 
+                            ActionClass::AddressBookSync(idx) => {
+                                if let Some(book) = server
+                                    .core
+                                    .groupware
+                                    .directory_addressbooks
+                                    .get(idx)
+                                    .cloned()
+                                {
+                                    trc::event!(
+                                        Housekeeper(trc::HousekeeperEvent::Run),
+                                        Type = "addressbook_sync",
+                                        Id = book.id.clone()
+                                    );
+
+                                    queue.schedule(
+                                        Instant::now() + book.refresh,
+                                        ActionClass::AddressBookSync(idx),
+                                    );
+
+                                    let server = server.clone();
+                                    tokio::spawn(async move {
+                                        if let Err(err) =
+                                            server.sync_directory_addressbook(&book).await
+                                        {
+                                            trc::error!(
+                                                err.details("Failed to synchronize address book")
+                                                    .id(book.id)
+                                            );
+                                        }
+                                    });
+                                }
+                            }
+                            ActionClass::CalendarMirror(idx) => {
+                                if let Some(mirror) =
+                                    server.core.groupware.calendar_mirrors.get(idx).cloned()
+                                {
+                                    trc::event!(
+                                        Housekeeper(trc::HousekeeperEvent::Run),
+                                        Type = "calendar_mirror",
+                                        Id = mirror.id.clone()
+                                    );
+
+                                    queue.schedule(
+                                        Instant::now() + mirror.interval,
+                                        ActionClass::CalendarMirror(idx),
+                                    );
+
+                                    let server = server.clone();
+                                    tokio::spawn(async move {
+                                        if let Err(err) = server.mirror_calendar(&mirror).await {
+                                            trc::error!(
+                                                err.details("Failed to mirror calendar")
+                                                    .id(mirror.id)
+                                            );
+                                        }
+                                    });
+                                }
+                            }
+
                             // SPDX-SnippetBegin
                             // SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
                             // SPDX-License-Identifier: LicenseRef-SEL
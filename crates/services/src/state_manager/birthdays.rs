@@ -0,0 +1,42 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::sync::Arc;
+
+use common::{IPC_CHANNEL_BUFFER, Inner, core::BuildServer};
+use groupware::calendar::birthdays::CalendarBirthdays;
+use store::ahash::AHashSet;
+use tokio::sync::mpsc;
+
+pub fn spawn_birthday_manager(inner: Arc<Inner>) -> mpsc::Sender<u32> {
+    let (birthday_tx, mut birthday_rx) = mpsc::channel::<u32>(IPC_CHANNEL_BUFFER);
+
+    tokio::spawn(async move {
+        // Birthday calendars are updated one account at a time, so that bursts
+        // of contact changes do not race to create the same events
+        while let Some(account_id) = birthday_rx.recv().await {
+            let mut account_ids = AHashSet::from_iter([account_id]);
+            while let Ok(account_id) = birthday_rx.try_recv() {
+                account_ids.insert(account_id);
+            }
+
+            let server = inner.build_server();
+            let Some(href_name) = server.core.groupware.birthday_calendar.clone() else {
+                continue;
+            };
+            for account_id in account_ids {
+                if let Err(err) = server.sync_birthday_calendar(account_id, &href_name).await {
+                    trc::error!(
+                        err.account_id(account_id)
+                            .details("Failed to update birthday calendar.")
+                    );
+                }
+            }
+        }
+    });
+
+    birthday_tx
+}
@@ -0,0 +1,172 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use common::Server;
+use groupware::{
+    DavResourceName,
+    push::{DavPush, dav_push_message, dav_push_topic},
+};
+use jmap_proto::types::{state::StateChange, type_state::DataType};
+use reqwest::{
+    Client, StatusCode,
+    header::{CONTENT_ENCODING, CONTENT_TYPE},
+};
+use trc::{AddContext, PushSubscriptionEvent};
+
+use super::ece::ece_encrypt;
+
+pub trait DavPushDelivery: Sync + Send {
+    /// Sends WebDAV-Push messages to the Web Push subscriptions of the
+    /// calendar and address book collections affected by a state change.
+    fn notify_dav_push(
+        &self,
+        state_change: StateChange,
+    ) -> impl Future<Output = trc::Result<()>> + Send;
+}
+
+impl DavPushDelivery for Server {
+    async fn notify_dav_push(&self, state_change: StateChange) -> trc::Result<()> {
+        let Some(web_push) = &self.core.groupware.web_push else {
+            return Ok(());
+        };
+        let mut client = None;
+
+        for (service, container_type, item_type) in [
+            (
+                DavResourceName::Cal,
+                DataType::Calendar,
+                DataType::CalendarEvent,
+            ),
+            (
+                DavResourceName::Card,
+                DataType::AddressBook,
+                DataType::ContactCard,
+            ),
+        ] {
+            let content_update = state_change.types.contains(item_type);
+            let property_update = state_change.types.contains(container_type);
+            if !content_update && !property_update {
+                continue;
+            }
+
+            // State changes are tracked per account rather than per collection,
+            // so every subscribed collection of the home is notified.
+            let subscriptions = self
+                .dav_push_subscriptions(service, state_change.account_id)
+                .await
+                .caused_by(trc::location!())?;
+            if subscriptions.is_empty() {
+                continue;
+            }
+
+            if client.is_none() {
+                let client_builder = Client::builder().timeout(web_push.timeout);
+
+                #[cfg(feature = "test_mode")]
+                let client_builder = client_builder.danger_accept_invalid_certs(true);
+
+                client = Some(client_builder.build().map_err(|err| {
+                    trc::EventType::PushSubscription(PushSubscriptionEvent::Error)
+                        .into_err()
+                        .reason(err)
+                        .details("Failed to create Web Push client")
+                })?);
+            }
+            let client = client.as_ref().unwrap();
+
+            let mut unregistered = Vec::new();
+            for subscription in &subscriptions {
+                let change_id = if content_update && subscription.content_update {
+                    Some(state_change.change_id)
+                } else if property_update && subscription.property_update {
+                    None
+                } else {
+                    continue;
+                };
+                let message = dav_push_message(
+                    dav_push_topic(service, state_change.account_id, subscription.document_id),
+                    change_id,
+                );
+
+                let mut request = client
+                    .post(&subscription.url)
+                    .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+                    .header("TTL", "86400");
+                let body = if let Some(keys) = &subscription.keys {
+                    match ece_encrypt(&keys.p256dh, &keys.auth, message.as_bytes()) {
+                        Ok(body) => {
+                            request = request.header(CONTENT_ENCODING, "aes128gcm");
+                            body
+                        }
+                        Err(err) => {
+                            trc::event!(
+                                PushSubscription(PushSubscriptionEvent::Error),
+                                Details = "Failed to encrypt push message",
+                                AccountId = subscription.account_id,
+                                Url = subscription.url.clone(),
+                                Reason = err
+                            );
+                            continue;
+                        }
+                    }
+                } else {
+                    message.into_bytes()
+                };
+
+                match request.body(body).send().await {
+                    Ok(response) if response.status().is_success() => {
+                        trc::event!(
+                            PushSubscription(PushSubscriptionEvent::Success),
+                            AccountId = subscription.account_id,
+                            Url = subscription.url.clone(),
+                        );
+                    }
+                    Ok(response)
+                        if matches!(
+                            response.status(),
+                            StatusCode::NOT_FOUND | StatusCode::GONE
+                        ) =>
+                    {
+                        // The push service no longer knows about the subscription
+                        trc::event!(
+                            PushSubscription(PushSubscriptionEvent::NotFound),
+                            AccountId = subscription.account_id,
+                            Url = subscription.url.clone(),
+                            Code = response.status().as_u16(),
+                        );
+                        unregistered.push(subscription.id);
+                    }
+                    Ok(response) => {
+                        trc::event!(
+                            PushSubscription(PushSubscriptionEvent::Error),
+                            Details = "Web Push request failed",
+                            AccountId = subscription.account_id,
+                            Url = subscription.url.clone(),
+                            Code = response.status().as_u16(),
+                        );
+                    }
+                    Err(err) => {
+                        trc::event!(
+                            PushSubscription(PushSubscriptionEvent::Error),
+                            Details = "Web Push request failed",
+                            AccountId = subscription.account_id,
+                            Url = subscription.url.clone(),
+                            Reason = err.to_string(),
+                        );
+                    }
+                }
+            }
+
+            if !unregistered.is_empty() {
+                self.remove_dav_push_subscriptions(service, state_change.account_id, &unregistered)
+                    .await
+                    .caused_by(trc::location!())?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -14,6 +14,7 @@ use common::{
     core::BuildServer,
     ipc::{BroadcastEvent, PushSubscription, StateEvent, UpdateSubscription},
 };
+use groupware::apns::ApnsPush;
 use jmap_proto::types::{id::Id, state::StateChange, type_state::DataType};
 use store::{ahash::AHashMap, rand};
 use tokio::sync::mpsc;
@@ -22,12 +23,13 @@ use utils::map::bitmap::Bitmap;
 
 use super::{
     Event, PURGE_EVERY, PushUpdate, SEND_TIMEOUT, Subscriber, SubscriberId, SubscriberType,
-    push::spawn_push_manager,
+    birthdays::spawn_birthday_manager, dav_push::DavPushDelivery, push::spawn_push_manager,
 };
 
 #[allow(clippy::unwrap_or_default)]
 pub fn spawn_state_manager(inner: Arc<Inner>, mut change_rx: mpsc::Receiver<StateEvent>) {
     let push_tx = spawn_push_manager(inner.clone());
+    let birthday_tx = spawn_birthday_manager(inner.clone());
 
     tokio::spawn(async move {
         let mut subscribers: AHashMap<u32, AHashMap<SubscriberId, Subscriber>> =
@@ -139,6 +141,43 @@ pub fn spawn_state_manager(inner: Arc<Inner>, mut change_rx: mpsc::Receiver<Stat
                     state_change,
                     broadcast,
                 } => {
+                    // Update birthday calendars and notify Apple devices and WebDAV-Push
+                    // subscribers, only on the node where the change originated
+                    if broadcast {
+                        let server = inner.build_server();
+                        if server.core.groupware.birthday_calendar.is_some()
+                            && state_change.types.contains(DataType::ContactCard)
+                            && birthday_tx.send(state_change.account_id).await.is_err()
+                        {
+                            trc::event!(
+                                Server(ServerEvent::ThreadError),
+                                Details = "Error sending birthday calendar update.",
+                                CausedBy = trc::location!()
+                            );
+                        }
+                        if server.core.groupware.apns.is_some() {
+                            let server = server.clone();
+                            tokio::spawn(async move {
+                                if let Err(err) = server.notify_apns(state_change).await {
+                                    trc::error!(
+                                        err.account_id(state_change.account_id)
+                                            .details("Failed to send APNs notifications.")
+                                    );
+                                }
+                            });
+                        }
+                        if server.core.groupware.web_push.is_some() {
+                            tokio::spawn(async move {
+                                if let Err(err) = server.notify_dav_push(state_change).await {
+                                    trc::error!(
+                                        err.account_id(state_change.account_id)
+                                            .details("Failed to send WebDAV-Push notifications.")
+                                    );
+                                }
+                            });
+                        }
+                    }
+
                     // Publish event to cluster
                     if broadcast {
                         if let Some(broadcast_tx) = &inner.ipc.broadcast_tx.clone() {
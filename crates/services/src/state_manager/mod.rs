@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
+pub mod birthdays;
+pub mod dav_push;
 pub mod ece;
 pub mod http;
 pub mod manager;
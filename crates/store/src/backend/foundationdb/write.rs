@@ -9,8 +9,8 @@ use super::{
     read::{ChunkedValue, read_chunked_value},
 };
 use crate::{
-    IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_IN_MEMORY_COUNTER, SUBSPACE_QUOTA, U64_LEN,
-    WITH_SUBSPACE,
+    IndexKey, Key, LogKey, LogShardKey, SUBSPACE_COUNTER, SUBSPACE_IN_MEMORY_COUNTER,
+    SUBSPACE_QUOTA, U64_LEN, WITH_SUBSPACE,
     backend::deserialize_i64_le,
     write::{
         AssignedIds, Batch, MAX_COMMIT_ATTEMPTS, MAX_COMMIT_TIME, Operation, ValueClass, ValueOp,
@@ -184,6 +184,21 @@ impl FdbStore {
 
                         trx.set(&key, set);
                     }
+                    Operation::LogShard {
+                        collection,
+                        shard_id,
+                        set,
+                    } => {
+                        let key = LogShardKey {
+                            account_id,
+                            collection: *collection,
+                            shard_id: *shard_id,
+                            change_id,
+                        }
+                        .serialize(WITH_SUBSPACE);
+
+                        trx.set(&key, set);
+                    }
                     Operation::AssertValue {
                         class,
                         assert_value,
@@ -4,7 +4,11 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{io::SeekFrom, ops::Range, path::PathBuf};
+use std::{
+    io::SeekFrom,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 use tokio::{
     fs::{self, File},
@@ -99,6 +103,27 @@ impl FsStore {
         Ok(())
     }
 
+    pub(crate) async fn put_blob_file(
+        &self,
+        key: &[u8],
+        path: &Path,
+        size: usize,
+    ) -> trc::Result<()> {
+        let blob_path = self.build_path(key);
+
+        if fs::metadata(&blob_path)
+            .await
+            .map_or(true, |m| m.len() as usize != size)
+        {
+            fs::create_dir_all(blob_path.parent().unwrap())
+                .await
+                .map_err(into_error)?;
+            fs::copy(path, &blob_path).await.map_err(into_error)?;
+        }
+
+        Ok(())
+    }
+
     pub(crate) async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
         let blob_path = self.build_path(key);
         if fs::metadata(&blob_path).await.is_ok() {
@@ -11,7 +11,8 @@ use mysql_async::{Conn, Error, IsolationLevel, TxOpts, params, prelude::Queryabl
 use rand::Rng;
 
 use crate::{
-    IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_IN_MEMORY_COUNTER, SUBSPACE_QUOTA, U64_LEN,
+    IndexKey, Key, LogKey, LogShardKey, SUBSPACE_COUNTER, SUBSPACE_IN_MEMORY_COUNTER,
+    SUBSPACE_QUOTA, U64_LEN,
     write::{
         AssignedIds, Batch, BitmapClass, MAX_COMMIT_ATTEMPTS, MAX_COMMIT_TIME, Operation,
         ValueClass, ValueOp,
@@ -295,6 +296,25 @@ impl MysqlStore {
 
                     trx.exec_drop(&s, (key, &*set)).await?;
                 }
+                Operation::LogShard {
+                    collection,
+                    shard_id,
+                    set,
+                } => {
+                    let key = LogShardKey {
+                        account_id,
+                        collection: *collection,
+                        shard_id: *shard_id,
+                        change_id,
+                    }
+                    .serialize(0);
+
+                    let s = trx
+                        .prep("INSERT INTO l (k, v) VALUES (?, ?) ON DUPLICATE KEY UPDATE v = VALUES(v)")
+                        .await?;
+
+                    trx.exec_drop(&s, (key, &*set)).await?;
+                }
                 Operation::AssertValue {
                     class,
                     assert_value,
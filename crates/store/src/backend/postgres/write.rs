@@ -12,7 +12,8 @@ use rand::Rng;
 use tokio_postgres::{IsolationLevel, error::SqlState};
 
 use crate::{
-    IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_IN_MEMORY_COUNTER, SUBSPACE_QUOTA, U64_LEN,
+    IndexKey, Key, LogKey, LogShardKey, SUBSPACE_COUNTER, SUBSPACE_IN_MEMORY_COUNTER,
+    SUBSPACE_QUOTA, U64_LEN,
     write::{
         AssignedIds, Batch, BitmapClass, MAX_COMMIT_ATTEMPTS, MAX_COMMIT_TIME, Operation,
         ValueClass, ValueOp,
@@ -291,6 +292,28 @@ impl PostgresStore {
 
                     trx.execute(&s, &[&key, &*set]).await?;
                 }
+                Operation::LogShard {
+                    collection,
+                    shard_id,
+                    set,
+                } => {
+                    let key = LogShardKey {
+                        account_id,
+                        collection: *collection,
+                        shard_id: *shard_id,
+                        change_id,
+                    }
+                    .serialize(0);
+
+                    let s = trx
+                        .prepare_cached(concat!(
+                            "INSERT INTO l (k, v) VALUES ($1, $2) ",
+                            "ON CONFLICT (k) DO UPDATE SET v = EXCLUDED.v"
+                        ))
+                        .await?;
+
+                    trx.execute(&s, &[&key, &*set]).await?;
+                }
                 Operation::AssertValue {
                     class,
                     assert_value,
@@ -18,7 +18,7 @@ use rocksdb::{
 
 use super::{CF_INDEXES, CF_LOGS, CfHandle, RocksDbStore, into_error};
 use crate::{
-    Deserialize, IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_IN_MEMORY_COUNTER,
+    Deserialize, IndexKey, Key, LogKey, LogShardKey, SUBSPACE_COUNTER, SUBSPACE_IN_MEMORY_COUNTER,
     SUBSPACE_QUOTA, U64_LEN,
     backend::deserialize_i64_le,
     write::{
@@ -264,6 +264,21 @@ impl RocksDBTransaction<'_, '_> {
 
                     txn.put_cf(&self.cf_logs, &key, set)?;
                 }
+                Operation::LogShard {
+                    collection,
+                    shard_id,
+                    set,
+                } => {
+                    let key = LogShardKey {
+                        account_id,
+                        collection: *collection,
+                        shard_id: *shard_id,
+                        change_id,
+                    }
+                    .serialize(0);
+
+                    txn.put_cf(&self.cf_logs, &key, set)?;
+                }
                 Operation::AssertValue {
                     class,
                     assert_value,
@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{fmt::Display, io::Write, ops::Range, time::Duration};
+use std::{fmt::Display, io::Write, ops::Range, path::Path, time::Duration};
 
 use s3::{Bucket, Region, creds::Credentials};
 use utils::{
@@ -145,6 +145,37 @@ impl S3Store {
         }
     }
 
+    pub(crate) async fn put_blob_file(&self, key: &[u8], path: &Path) -> trc::Result<()> {
+        let mut retries_left = self.max_retries;
+
+        loop {
+            let mut file = tokio::fs::File::open(path).await.map_err(into_error)?;
+            let response = self
+                .bucket
+                .put_object_stream(&mut file, self.build_key(key))
+                .await
+                .map_err(into_error)?;
+
+            match response.status_code() {
+                200..=299 => return Ok(()),
+                500..=599 if retries_left > 0 => {
+                    // wait backoff
+                    tokio::time::sleep(Duration::from_secs(
+                        1 << (self.max_retries - retries_left).min(6),
+                    ))
+                    .await;
+
+                    retries_left -= 1;
+                }
+                code => {
+                    return Err(trc::StoreEvent::S3Error
+                        .reason("Streamed upload failed")
+                        .ctx(trc::Key::Code, code));
+                }
+            }
+        }
+    }
+
     pub(crate) async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
         let mut retries_left = self.max_retries;
 
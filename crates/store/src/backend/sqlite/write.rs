@@ -6,7 +6,8 @@
 
 use super::{SqliteStore, into_error};
 use crate::{
-    IndexKey, Key, LogKey, SUBSPACE_COUNTER, SUBSPACE_IN_MEMORY_COUNTER, SUBSPACE_QUOTA, U64_LEN,
+    IndexKey, Key, LogKey, LogShardKey, SUBSPACE_COUNTER, SUBSPACE_IN_MEMORY_COUNTER,
+    SUBSPACE_QUOTA, U64_LEN,
     write::{AssignedIds, Batch, BitmapClass, Operation, ValueClass, ValueOp},
 };
 use rusqlite::{OptionalExtension, TransactionBehavior, params};
@@ -219,6 +220,26 @@ impl SqliteStore {
                             .map_err(into_error)
                             .caused_by(trc::location!())?;
                     }
+                    Operation::LogShard {
+                        collection,
+                        shard_id,
+                        set,
+                    } => {
+                        let key = LogShardKey {
+                            account_id,
+                            collection: *collection,
+                            shard_id: *shard_id,
+                            change_id,
+                        }
+                        .serialize(0);
+
+                        trx.prepare_cached("INSERT OR REPLACE INTO l (k, v) VALUES (?, ?)")
+                            .map_err(into_error)
+                            .caused_by(trc::location!())?
+                            .execute([&key, set])
+                            .map_err(into_error)
+                            .caused_by(trc::location!())?;
+                    }
                     Operation::AssertValue {
                         class,
                         assert_value,
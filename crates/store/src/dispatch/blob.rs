@@ -4,7 +4,7 @@
  * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
  */
 
-use std::{borrow::Cow, ops::Range, time::Instant};
+use std::{borrow::Cow, ops::Range, path::Path, time::Instant};
 
 use trc::{AddContext, StoreEvent};
 use utils::config::utils::ParseValue;
@@ -45,6 +45,7 @@ impl BlobStore {
 
         trc::event!(
             Store(StoreEvent::BlobRead),
+            SpanId = trc::current_span(),
             Key = key,
             Elapsed = start_time.elapsed(),
             Size = result
@@ -136,6 +137,38 @@ impl BlobStore {
         result
     }
 
+    /// Writes a blob from a file, backends that accept streamed writes never
+    /// hold the whole contents in memory. Database backends store each blob as
+    /// a single value and receive the complete file.
+    pub async fn put_blob_file(&self, key: &[u8], path: &Path, size: usize) -> trc::Result<()> {
+        let start_time = Instant::now();
+        let result = match (&self.backend, self.compression) {
+            (BlobBackend::Fs(store), CompressionAlgo::None) => {
+                store.put_blob_file(key, path, size).await
+            }
+            #[cfg(feature = "s3")]
+            (BlobBackend::S3(store), CompressionAlgo::None) => store.put_blob_file(key, path).await,
+            _ => {
+                let data = tokio::fs::read(path).await.map_err(|err| {
+                    trc::StoreEvent::FilesystemError
+                        .reason(err)
+                        .ctx(trc::Key::CausedBy, trc::location!())
+                })?;
+                return self.put_blob(key, &data).await;
+            }
+        }
+        .caused_by(trc::location!());
+
+        trc::event!(
+            Store(StoreEvent::BlobWrite),
+            Key = key,
+            Elapsed = start_time.elapsed(),
+            Size = size,
+        );
+
+        result
+    }
+
     pub async fn delete_blob(&self, key: &[u8]) -> trc::Result<bool> {
         let start_time = Instant::now();
         let result = match &self.backend {
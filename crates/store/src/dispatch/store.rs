@@ -129,6 +129,7 @@ impl Store {
 
         trc::event!(
             Store(StoreEvent::DataIterate),
+            SpanId = trc::current_span(),
             Elapsed = start_time.elapsed(),
         );
 
@@ -206,6 +207,7 @@ impl Store {
 
         trc::event!(
             Store(StoreEvent::DataWrite),
+            SpanId = trc::current_span(),
             Elapsed = start_time.elapsed(),
             Total = ops,
         );
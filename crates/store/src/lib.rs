@@ -88,6 +88,18 @@ pub struct LogKey {
     pub change_id: u64,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LogShardKey {
+    pub account_id: u32,
+    pub collection: u8,
+    pub shard_id: u32,
+    pub change_id: u64,
+}
+
+// Shard ids are container ids, the last one is reserved for the shard and sync epochs
+pub const LOG_SHARD_EPOCH: u32 = u32::MAX;
+pub const LOG_SHARD_FLAG: u8 = 0x80;
+
 pub const U64_LEN: usize = std::mem::size_of::<u64>();
 pub const U32_LEN: usize = std::mem::size_of::<u32>();
 pub const U16_LEN: usize = std::mem::size_of::<u16>();
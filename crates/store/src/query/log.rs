@@ -7,7 +7,12 @@
 use trc::AddContext;
 use utils::codec::leb128::Leb128Iterator;
 
-use crate::{IterateParams, LogKey, Store, U32_LEN, U64_LEN, write::key::DeserializeBigEndian};
+use crate::{
+    IterateParams, Key, LOG_SHARD_EPOCH, LogKey, LogShardKey, SUBSPACE_LOGS, SerializeInfallible,
+    Store, U32_LEN, U64_LEN,
+    roaring::RoaringBitmap,
+    write::{AnyClass, AnyKey, BatchBuilder, ValueClass, key::DeserializeBigEndian},
+};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Change {
@@ -42,6 +47,19 @@ pub trait DeserializeVanished: Sized + Sync + Send {
     fn deserialize_vanished<'x>(bytes: &mut impl Iterator<Item = &'x u8>) -> Option<Self>;
 }
 
+impl Query {
+    fn range(&self) -> (bool, u64, u64) {
+        match *self {
+            Query::All => (true, 0, u64::MAX),
+            Query::Since(change_id) => (false, change_id, u64::MAX),
+            Query::SinceInclusive(change_id) => (true, change_id, u64::MAX),
+            Query::RangeInclusive(from_change_id, to_change_id) => {
+                (true, from_change_id, to_change_id)
+            }
+        }
+    }
+}
+
 impl Default for Changes {
     fn default() -> Self {
         Self {
@@ -63,25 +81,165 @@ impl Store {
         query: Query,
     ) -> trc::Result<Changes> {
         let collection = collection.into();
-        let (is_inclusive, from_change_id, to_change_id) = match query {
-            Query::All => (true, 0, u64::MAX),
-            Query::Since(change_id) => (false, change_id, u64::MAX),
-            Query::SinceInclusive(change_id) => (true, change_id, u64::MAX),
-            Query::RangeInclusive(from_change_id, to_change_id) => {
-                (true, from_change_id, to_change_id)
-            }
-        };
-        let from_key = LogKey {
-            account_id,
-            collection,
-            change_id: from_change_id,
-        };
-        let to_key = LogKey {
-            account_id,
-            collection,
-            change_id: to_change_id,
-        };
+        let (is_inclusive, from_change_id, to_change_id) = query.range();
+        self.changes_in_range(
+            LogKey {
+                account_id,
+                collection,
+                change_id: from_change_id,
+            },
+            LogKey {
+                account_id,
+                collection,
+                change_id: to_change_id,
+            },
+            (is_inclusive, from_change_id, to_change_id),
+        )
+        .await
+    }
 
+    /// Returns the changes logged for a single container, without
+    /// scanning the changes of other containers in the same collection.
+    pub async fn shard_changes(
+        &self,
+        account_id: u32,
+        collection: impl Into<u8> + Sync + Send,
+        shard_id: u32,
+        query: Query,
+    ) -> trc::Result<Changes> {
+        let collection = collection.into();
+        let (is_inclusive, from_change_id, to_change_id) = query.range();
+        self.changes_in_range(
+            LogShardKey {
+                account_id,
+                collection,
+                shard_id,
+                change_id: from_change_id,
+            },
+            LogShardKey {
+                account_id,
+                collection,
+                shard_id,
+                change_id: to_change_id,
+            },
+            (is_inclusive, from_change_id, to_change_id),
+        )
+        .await
+    }
+
+    /// Returns the change id after which per-container changes are complete.
+    pub async fn get_shard_epoch(
+        &self,
+        account_id: u32,
+        collection: impl Into<u8> + Sync + Send,
+    ) -> trc::Result<Option<u64>> {
+        self.get_value::<u64>(AnyKey {
+            subspace: SUBSPACE_LOGS,
+            key: LogShardKey::epoch(account_id, collection.into()).serialize(0),
+        })
+        .await
+        .caused_by(trc::location!())
+    }
+
+    /// Returns the sync epoch of a collection, sync tokens issued for an
+    /// earlier epoch are no longer valid.
+    pub async fn get_sync_epoch(
+        &self,
+        account_id: u32,
+        collection: impl Into<u8> + Sync + Send,
+    ) -> trc::Result<u64> {
+        self.get_value::<u64>(AnyKey {
+            subspace: SUBSPACE_LOGS,
+            key: LogShardKey::sync_epoch(account_id, collection.into()).serialize(0),
+        })
+        .await
+        .map(|epoch| epoch.unwrap_or_default())
+        .caused_by(trc::location!())
+    }
+
+    /// Removes the per-container changes logged before `first_change_id` and
+    /// advances the shard epoch so older sync tokens fall back to the full log.
+    pub async fn delete_shard_changes(
+        &self,
+        account_id: u32,
+        collection: impl Into<u8> + Sync + Send,
+        first_change_id: u64,
+    ) -> trc::Result<()> {
+        let collection = collection.into();
+        let mut shard_ids = RoaringBitmap::new();
+        self.iterate(
+            IterateParams::new(
+                LogShardKey {
+                    account_id,
+                    collection,
+                    shard_id: 0,
+                    change_id: 0,
+                },
+                LogShardKey {
+                    account_id,
+                    collection,
+                    shard_id: LOG_SHARD_EPOCH - 1,
+                    change_id: u64::MAX,
+                },
+            )
+            .no_values(),
+            |key, _| {
+                if key.deserialize_be_u64(key.len() - U64_LEN)? < first_change_id {
+                    shard_ids.insert(key.deserialize_be_u32(U32_LEN + 1)?);
+                }
+                Ok(true)
+            },
+        )
+        .await
+        .caused_by(trc::location!())?;
+
+        for shard_id in shard_ids {
+            self.delete_range(
+                LogShardKey {
+                    account_id,
+                    collection,
+                    shard_id,
+                    change_id: 0,
+                },
+                LogShardKey {
+                    account_id,
+                    collection,
+                    shard_id,
+                    change_id: first_change_id,
+                },
+            )
+            .await
+            .caused_by(trc::location!())?;
+        }
+
+        // Older sync tokens can no longer be served from the per-container log
+        if self
+            .get_shard_epoch(account_id, collection)
+            .await?
+            .is_some_and(|epoch| epoch < first_change_id)
+        {
+            let mut batch = BatchBuilder::new();
+            batch.with_account_id(account_id).set(
+                ValueClass::Any(AnyClass {
+                    subspace: SUBSPACE_LOGS,
+                    key: LogShardKey::epoch(account_id, collection).serialize(0),
+                }),
+                first_change_id.serialize(),
+            );
+            self.write(batch.build_all())
+                .await
+                .caused_by(trc::location!())?;
+        }
+
+        Ok(())
+    }
+
+    async fn changes_in_range<K: Key>(
+        &self,
+        from_key: K,
+        to_key: K,
+        (is_inclusive, from_change_id, to_change_id): (bool, u64, u64),
+    ) -> trc::Result<Changes> {
         let mut changelog = Changes::default();
 
         self.iterate(
@@ -133,14 +291,7 @@ impl Store {
         query: Query,
     ) -> trc::Result<Vec<T>> {
         let collection = collection.into();
-        let (is_inclusive, from_change_id, to_change_id) = match query {
-            Query::All => (true, 0, u64::MAX),
-            Query::Since(change_id) => (false, change_id, u64::MAX),
-            Query::SinceInclusive(change_id) => (true, change_id, u64::MAX),
-            Query::RangeInclusive(from_change_id, to_change_id) => {
-                (true, from_change_id, to_change_id)
-            }
-        };
+        let (is_inclusive, from_change_id, to_change_id) = query.range();
         let from_key = LogKey {
             account_id,
             collection,
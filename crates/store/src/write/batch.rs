@@ -285,6 +285,61 @@ impl BatchBuilder {
         self
     }
 
+    pub fn log_item_shard_insert(&mut self, collection: impl Into<u8>, shard_id: u32) -> &mut Self {
+        if let (Some(account_id), Some(document_id)) =
+            (self.current_account_id, self.current_document_id)
+        {
+            self.changes
+                .get_mut_or_insert(account_id)
+                .log_shard_item_insert(collection.into(), shard_id, document_id);
+        }
+        self
+    }
+
+    pub fn log_item_shard_update(&mut self, collection: impl Into<u8>, shard_id: u32) -> &mut Self {
+        if let (Some(account_id), Some(document_id)) =
+            (self.current_account_id, self.current_document_id)
+        {
+            self.changes
+                .get_mut_or_insert(account_id)
+                .log_shard_item_update(collection.into(), shard_id, document_id);
+        }
+        self
+    }
+
+    pub fn log_item_shard_delete(&mut self, collection: impl Into<u8>, shard_id: u32) -> &mut Self {
+        if let (Some(account_id), Some(document_id)) =
+            (self.current_account_id, self.current_document_id)
+        {
+            self.changes
+                .get_mut_or_insert(account_id)
+                .log_shard_item_delete(collection.into(), shard_id, document_id);
+        }
+        self
+    }
+
+    pub fn log_container_shard_update(&mut self, collection: impl Into<u8>) -> &mut Self {
+        if let (Some(account_id), Some(document_id)) =
+            (self.current_account_id, self.current_document_id)
+        {
+            self.changes
+                .get_mut_or_insert(account_id)
+                .log_shard_container_update(collection.into(), document_id);
+        }
+        self
+    }
+
+    pub fn log_container_shard_delete(&mut self, collection: impl Into<u8>) -> &mut Self {
+        if let (Some(account_id), Some(document_id)) =
+            (self.current_account_id, self.current_document_id)
+        {
+            self.changes
+                .get_mut_or_insert(account_id)
+                .log_shard_container_delete(collection.into(), document_id);
+        }
+        self
+    }
+
     pub fn log_container_insert(&mut self, collection: impl Into<u8>) -> &mut Self {
         if let (Some(account_id), Some(document_id)) =
             (self.current_account_id, self.current_document_id)
@@ -369,6 +424,15 @@ impl BatchBuilder {
                     });
                 }
 
+                // Serialize per-container changes
+                for ((collection, shard_id), changes) in changelog.shards.into_iter() {
+                    self.ops.push(Operation::LogShard {
+                        collection,
+                        shard_id,
+                        set: changes.serialize(),
+                    });
+                }
+
                 // Serialize vanished items
                 for (collection, vanished) in changelog.vanished.into_iter() {
                     self.ops.push(Operation::Log {
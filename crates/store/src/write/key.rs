@@ -8,11 +8,11 @@ use std::convert::TryInto;
 use utils::{BLOB_HASH_LEN, codec::leb128::Leb128_};
 
 use crate::{
-    BitmapKey, Deserialize, IndexKey, IndexKeyPrefix, Key, LogKey, SUBSPACE_ACL,
-    SUBSPACE_BITMAP_ID, SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT, SUBSPACE_BLOB_LINK,
-    SUBSPACE_BLOB_RESERVE, SUBSPACE_COUNTER, SUBSPACE_DIRECTORY, SUBSPACE_FTS_INDEX,
-    SUBSPACE_IN_MEMORY_COUNTER, SUBSPACE_IN_MEMORY_VALUE, SUBSPACE_INDEXES, SUBSPACE_LOGS,
-    SUBSPACE_PROPERTY, SUBSPACE_QUEUE_EVENT, SUBSPACE_QUEUE_MESSAGE, SUBSPACE_QUOTA,
+    BitmapKey, Deserialize, IndexKey, IndexKeyPrefix, Key, LOG_SHARD_EPOCH, LOG_SHARD_FLAG, LogKey,
+    LogShardKey, SUBSPACE_ACL, SUBSPACE_BITMAP_ID, SUBSPACE_BITMAP_TAG, SUBSPACE_BITMAP_TEXT,
+    SUBSPACE_BLOB_LINK, SUBSPACE_BLOB_RESERVE, SUBSPACE_COUNTER, SUBSPACE_DIRECTORY,
+    SUBSPACE_FTS_INDEX, SUBSPACE_IN_MEMORY_COUNTER, SUBSPACE_IN_MEMORY_VALUE, SUBSPACE_INDEXES,
+    SUBSPACE_LOGS, SUBSPACE_PROPERTY, SUBSPACE_QUEUE_EVENT, SUBSPACE_QUEUE_MESSAGE, SUBSPACE_QUOTA,
     SUBSPACE_REPORT_IN, SUBSPACE_REPORT_OUT, SUBSPACE_SETTINGS, SUBSPACE_TASK_QUEUE,
     SUBSPACE_TELEMETRY_INDEX, SUBSPACE_TELEMETRY_METRIC, SUBSPACE_TELEMETRY_SPAN, U16_LEN, U32_LEN,
     U64_LEN, ValueKey, WITH_SUBSPACE,
@@ -228,6 +228,54 @@ impl Key for LogKey {
     }
 }
 
+impl Key for LogShardKey {
+    fn subspace(&self) -> u8 {
+        SUBSPACE_LOGS
+    }
+
+    fn serialize(&self, flags: u32) -> Vec<u8> {
+        {
+            if (flags & WITH_SUBSPACE) != 0 {
+                KeySerializer::new(std::mem::size_of::<LogShardKey>() + 1)
+                    .write(crate::SUBSPACE_LOGS)
+            } else {
+                KeySerializer::new(std::mem::size_of::<LogShardKey>())
+            }
+        }
+        .write(self.account_id)
+        .write(self.collection | LOG_SHARD_FLAG)
+        .write(self.shard_id)
+        .write(self.change_id)
+        .finalize()
+    }
+}
+
+impl LogShardKey {
+    pub fn epoch(account_id: u32, collection: u8) -> Self {
+        LogShardKey {
+            account_id,
+            collection,
+            shard_id: LOG_SHARD_EPOCH,
+            change_id: 0,
+        }
+    }
+
+    /// Returns whether a serialized log collection belongs to a per-container
+    /// log, vanished item logs use collections above 200.
+    pub fn is_shard_collection(collection: u8) -> bool {
+        collection & LOG_SHARD_FLAG != 0 && collection <= 200
+    }
+
+    pub fn sync_epoch(account_id: u32, collection: u8) -> Self {
+        LogShardKey {
+            account_id,
+            collection,
+            shard_id: LOG_SHARD_EPOCH,
+            change_id: 1,
+        }
+    }
+}
+
 impl<T: AsRef<ValueClass> + Sync + Send + Clone> Key for ValueKey<T> {
     fn subspace(&self) -> u8 {
         self.class.as_ref().subspace(self.collection)
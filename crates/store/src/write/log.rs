@@ -13,6 +13,7 @@ use super::key::KeySerializer;
 #[derive(Default, Debug)]
 pub(crate) struct ChangeLogBuilder {
     pub changes: VecMap<u8, Changes>,
+    pub shards: VecMap<(u8, u32), Changes>,
     pub vanished: VecMap<u8, VanishedItems>,
 }
 
@@ -54,13 +55,9 @@ impl ChangeLogBuilder {
         prefix: Option<u32>,
         document_id: u32,
     ) {
-        let id = build_id(prefix, document_id);
-        let changes = self.changes.get_mut_or_insert(collection.into());
-        if changes.item_deletes.remove(&id) {
-            changes.item_updates.insert(id);
-        } else {
-            changes.item_inserts.insert(id);
-        }
+        self.changes
+            .get_mut_or_insert(collection.into())
+            .insert_item(build_id(prefix, document_id));
     }
 
     pub fn log_container_update(&mut self, collection: impl Into<u8>, document_id: u32) {
@@ -85,8 +82,7 @@ impl ChangeLogBuilder {
     ) {
         self.changes
             .get_mut_or_insert(collection.into())
-            .item_updates
-            .insert(build_id(prefix, document_id));
+            .update_item(build_id(prefix, document_id));
     }
 
     pub fn log_container_delete(&mut self, collection: impl Into<u8>, document_id: u32) {
@@ -103,10 +99,40 @@ impl ChangeLogBuilder {
         prefix: Option<u32>,
         document_id: u32,
     ) {
-        let changes = self.changes.get_mut_or_insert(collection.into());
-        let id = build_id(prefix, document_id);
-        changes.item_updates.remove(&id);
-        changes.item_deletes.insert(id);
+        self.changes
+            .get_mut_or_insert(collection.into())
+            .delete_item(build_id(prefix, document_id));
+    }
+
+    pub fn log_shard_item_insert(&mut self, collection: u8, shard_id: u32, document_id: u32) {
+        self.shards
+            .get_mut_or_insert((collection, shard_id))
+            .insert_item(document_id as u64);
+    }
+
+    pub fn log_shard_item_update(&mut self, collection: u8, shard_id: u32, document_id: u32) {
+        self.shards
+            .get_mut_or_insert((collection, shard_id))
+            .update_item(document_id as u64);
+    }
+
+    pub fn log_shard_item_delete(&mut self, collection: u8, shard_id: u32, document_id: u32) {
+        self.shards
+            .get_mut_or_insert((collection, shard_id))
+            .delete_item(document_id as u64);
+    }
+
+    pub fn log_shard_container_update(&mut self, collection: u8, document_id: u32) {
+        self.shards
+            .get_mut_or_insert((collection, document_id))
+            .container_updates
+            .insert(document_id);
+    }
+
+    pub fn log_shard_container_delete(&mut self, collection: u8, document_id: u32) {
+        let changes = self.shards.get_mut_or_insert((collection, document_id));
+        changes.container_updates.remove(&document_id);
+        changes.container_deletes.insert(document_id);
     }
 
     pub fn log_vanished_item(&mut self, collection: impl Into<u8>, item: impl Into<VanishedItem>) {
@@ -127,6 +153,23 @@ fn build_id(prefix: Option<u32>, document_id: u32) -> u64 {
 }
 
 impl Changes {
+    fn insert_item(&mut self, id: u64) {
+        if self.item_deletes.remove(&id) {
+            self.item_updates.insert(id);
+        } else {
+            self.item_inserts.insert(id);
+        }
+    }
+
+    fn update_item(&mut self, id: u64) {
+        self.item_updates.insert(id);
+    }
+
+    fn delete_item(&mut self, id: u64) {
+        self.item_updates.remove(&id);
+        self.item_deletes.insert(id);
+    }
+
     pub fn has_container_changes(&self) -> bool {
         !self.container_inserts.is_empty()
             || !self.container_updates.is_empty()
@@ -154,6 +154,11 @@ pub enum Operation {
         collection: u8,
         set: Vec<u8>,
     },
+    LogShard {
+        collection: u8,
+        shard_id: u32,
+        set: Vec<u8>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
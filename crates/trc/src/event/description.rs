@@ -54,6 +54,7 @@ impl EventType {
             EventType::Ai(event) => event.description(),
             EventType::WebDav(event) => event.description(),
             EventType::Calendar(event) => event.description(),
+            EventType::Groupware(event) => event.description(),
         }
     }
 
@@ -104,6 +105,7 @@ impl EventType {
             EventType::Ai(event) => event.explain(),
             EventType::WebDav(event) => event.explain(),
             EventType::Calendar(event) => event.explain(),
+            EventType::Groupware(event) => event.explain(),
         }
     }
 }
@@ -1752,6 +1754,7 @@ impl AuthEvent {
             AuthEvent::Error => "Authentication error",
             AuthEvent::TokenExpired => "OAuth token expired",
             AuthEvent::ClientRegistration => "OAuth Client registration",
+            AuthEvent::Impersonate => "Account impersonation",
         }
     }
 
@@ -1764,6 +1767,7 @@ impl AuthEvent {
             AuthEvent::Error => "An error occurred with authentication",
             AuthEvent::TokenExpired => "OAuth authentication token has expired",
             AuthEvent::ClientRegistration => "OAuth client successfully registered",
+            AuthEvent::Impersonate => "An administrator is acting on behalf of another account",
         }
     }
 }
@@ -1851,10 +1855,14 @@ impl WebDavEvent {
             WebDavEvent::Lock => "WebDAV LOCK request",
             WebDavEvent::Unlock => "WebDAV UNLOCK request",
             WebDavEvent::Acl => "WebDAV ACL request",
+            WebDavEvent::Bind => "WebDAV BIND request",
+            WebDavEvent::Unbind => "WebDAV UNBIND request",
+            WebDavEvent::Rebind => "WebDAV REBIND request",
             WebDavEvent::Error => "WebDAV error",
             WebDavEvent::Head => "WebDAV HEAD request",
             WebDavEvent::Mkcalendar => "WebDAV MKCALENDAR request",
             WebDavEvent::Options => "WebDAV OPTIONS request",
+            WebDavEvent::SlowRequest => "Slow WebDAV request",
         }
     }
 
@@ -1877,10 +1885,14 @@ impl WebDavEvent {
                 "An ACL request has been made to the
                 server"
             }
+            WebDavEvent::Bind => "A BIND request has been made to the server",
+            WebDavEvent::Unbind => "An UNBIND request has been made to the server",
+            WebDavEvent::Rebind => "A REBIND request has been made to the server",
             WebDavEvent::Error => "An error occurred with the WebDAV request",
             WebDavEvent::Head => "A HEAD request has been made to the server",
             WebDavEvent::Mkcalendar => "A MKCALENDAR request has been made to the server",
             WebDavEvent::Options => "An OPTIONS request has been made to the server",
+            WebDavEvent::SlowRequest => "A WebDAV request exceeded the slow request threshold",
         }
     }
 }
@@ -1889,6 +1901,10 @@ impl CalendarEvent {
     pub fn description(&self) -> &'static str {
         match self {
             CalendarEvent::RuleExpansionError => "Calendar rule expansion error",
+            CalendarEvent::InviteSkipped => "Calendar invitation skipped",
+            CalendarEvent::MirrorCompleted => "Calendar mirror completed",
+            CalendarEvent::MirrorConflict => "Calendar mirror conflict",
+            CalendarEvent::MirrorError => "Calendar mirror error",
         }
     }
 
@@ -1897,6 +1913,56 @@ impl CalendarEvent {
             CalendarEvent::RuleExpansionError => {
                 "An error occurred while expanding calendar recurrences"
             }
+            CalendarEvent::InviteSkipped => {
+                "A calendar attachment received by email could not be added to a calendar"
+            }
+            CalendarEvent::MirrorCompleted => {
+                "A local calendar was mirrored to an external CalDAV server"
+            }
+            CalendarEvent::MirrorConflict => {
+                "An event changed on the external CalDAV server was not overwritten"
+            }
+            CalendarEvent::MirrorError => {
+                "An event could not be mirrored to an external CalDAV server"
+            }
+        }
+    }
+}
+
+impl GroupwareEvent {
+    pub fn description(&self) -> &'static str {
+        match self {
+            GroupwareEvent::CalendarCreated => "Calendar resource created",
+            GroupwareEvent::CalendarUpdated => "Calendar resource updated",
+            GroupwareEvent::CalendarDeleted => "Calendar resource deleted",
+            GroupwareEvent::ContactCreated => "Contact resource created",
+            GroupwareEvent::ContactUpdated => "Contact resource updated",
+            GroupwareEvent::ContactDeleted => "Contact resource deleted",
+            GroupwareEvent::FileCreated => "File resource created",
+            GroupwareEvent::FileUpdated => "File resource updated",
+            GroupwareEvent::FileDeleted => "File resource deleted",
+            GroupwareEvent::ContentRejected => "Content rejected by inspection",
+            GroupwareEvent::ContentInspectionError => "Content inspection failed",
+        }
+    }
+
+    pub fn explain(&self) -> &'static str {
+        match self {
+            GroupwareEvent::CalendarCreated => "A calendar or calendar event has been created",
+            GroupwareEvent::CalendarUpdated => "A calendar or calendar event has been updated",
+            GroupwareEvent::CalendarDeleted => "A calendar or calendar event has been deleted",
+            GroupwareEvent::ContactCreated => "An address book or contact card has been created",
+            GroupwareEvent::ContactUpdated => "An address book or contact card has been updated",
+            GroupwareEvent::ContactDeleted => "An address book or contact card has been deleted",
+            GroupwareEvent::FileCreated => "A file or folder has been created",
+            GroupwareEvent::FileUpdated => "A file or folder has been updated",
+            GroupwareEvent::FileDeleted => "A file or folder has been deleted",
+            GroupwareEvent::ContentRejected => {
+                "The content inspection service rejected an uploaded resource"
+            }
+            GroupwareEvent::ContentInspectionError => {
+                "The content inspection service could not be reached or returned an error"
+            }
         }
     }
 }
@@ -235,7 +235,9 @@ impl EventType {
                 AuthEvent::MissingTotp => Level::Trace,
                 AuthEvent::TooManyAttempts => Level::Warn,
                 AuthEvent::Error => Level::Error,
-                AuthEvent::Success | AuthEvent::ClientRegistration => Level::Info,
+                AuthEvent::Success | AuthEvent::ClientRegistration | AuthEvent::Impersonate => {
+                    Level::Info
+                }
             },
             EventType::Config(cause) => match cause {
                 ConfigEvent::ParseError
@@ -534,8 +536,19 @@ impl EventType {
                 AiEvent::LlmResponse => Level::Trace,
                 AiEvent::ApiError => Level::Warn,
             },
+            EventType::WebDav(WebDavEvent::SlowRequest) => Level::Warn,
             EventType::WebDav(_) => Level::Debug,
-            EventType::Calendar(CalendarEvent::RuleExpansionError) => Level::Debug,
+            EventType::Calendar(
+                CalendarEvent::RuleExpansionError | CalendarEvent::InviteSkipped,
+            ) => Level::Debug,
+            EventType::Calendar(CalendarEvent::MirrorCompleted | CalendarEvent::MirrorConflict) => {
+                Level::Info
+            }
+            EventType::Calendar(CalendarEvent::MirrorError) => Level::Warn,
+            EventType::Groupware(
+                GroupwareEvent::ContentRejected | GroupwareEvent::ContentInspectionError,
+            ) => Level::Warn,
+            EventType::Groupware(_) => Level::Info,
         }
     }
 }
@@ -8,6 +8,7 @@ pub mod conv;
 pub mod description;
 pub mod level;
 pub mod metrics;
+pub mod span;
 
 use compact_str::ToCompactString;
 use std::fmt::Display;
@@ -0,0 +1,22 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use std::future::Future;
+
+tokio::task_local! {
+    static CURRENT_SPAN: u64;
+}
+
+/// Runs a future with `span_id` as its current span, so that events emitted by
+/// lower layers (store reads, batch commits) can be attached to the request.
+pub async fn in_span<F: Future>(span_id: u64, future: F) -> F::Output {
+    CURRENT_SPAN.scope(span_id, future).await
+}
+
+/// Returns the span of the enclosing request, if any.
+pub fn current_span() -> Option<u64> {
+    CURRENT_SPAN.try_with(|span_id| *span_id).ok()
+}
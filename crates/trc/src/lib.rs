@@ -15,6 +15,7 @@ use std::{
     sync::Arc,
 };
 
+pub use crate::event::span::{current_span, in_span};
 pub use crate::ipc::collector::Collector;
 use compact_str::CompactString;
 pub use event_macro::event;
@@ -189,6 +190,7 @@ pub enum EventType {
     Ai(AiEvent),
     WebDav(WebDavEvent),
     Calendar(CalendarEvent),
+    Groupware(GroupwareEvent),
 }
 
 #[event_type]
@@ -937,6 +939,7 @@ pub enum AuthEvent {
     MissingTotp,
     TooManyAttempts,
     ClientRegistration,
+    Impersonate,
     Error,
 }
 
@@ -974,7 +977,11 @@ pub enum WebDavEvent {
     Lock,
     Unlock,
     Acl,
+    Bind,
+    Unbind,
+    Rebind,
     Options,
+    SlowRequest,
 
     // Errors
     Error,
@@ -983,6 +990,25 @@ pub enum WebDavEvent {
 #[event_type]
 pub enum CalendarEvent {
     RuleExpansionError,
+    InviteSkipped,
+    MirrorCompleted,
+    MirrorConflict,
+    MirrorError,
+}
+
+#[event_type]
+pub enum GroupwareEvent {
+    CalendarCreated,
+    CalendarUpdated,
+    CalendarDeleted,
+    ContactCreated,
+    ContactUpdated,
+    ContactDeleted,
+    FileCreated,
+    FileUpdated,
+    FileDeleted,
+    ContentRejected,
+    ContentInspectionError,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
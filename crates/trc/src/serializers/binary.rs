@@ -887,6 +887,26 @@ impl EventType {
             EventType::Store(StoreEvent::CacheHit) => 51,
             EventType::Store(StoreEvent::CacheStale) => 52,
             EventType::Store(StoreEvent::CacheUpdate) => 577,
+            EventType::Auth(AuthEvent::Impersonate) => 578,
+            EventType::WebDav(WebDavEvent::SlowRequest) => 579,
+            EventType::Groupware(GroupwareEvent::CalendarCreated) => 580,
+            EventType::Groupware(GroupwareEvent::CalendarUpdated) => 581,
+            EventType::Groupware(GroupwareEvent::CalendarDeleted) => 582,
+            EventType::Groupware(GroupwareEvent::ContactCreated) => 583,
+            EventType::Groupware(GroupwareEvent::ContactUpdated) => 584,
+            EventType::Groupware(GroupwareEvent::ContactDeleted) => 585,
+            EventType::Groupware(GroupwareEvent::FileCreated) => 586,
+            EventType::Groupware(GroupwareEvent::FileUpdated) => 587,
+            EventType::Groupware(GroupwareEvent::FileDeleted) => 588,
+            EventType::Calendar(CalendarEvent::InviteSkipped) => 589,
+            EventType::Calendar(CalendarEvent::MirrorCompleted) => 590,
+            EventType::Calendar(CalendarEvent::MirrorConflict) => 591,
+            EventType::Calendar(CalendarEvent::MirrorError) => 592,
+            EventType::Groupware(GroupwareEvent::ContentRejected) => 593,
+            EventType::Groupware(GroupwareEvent::ContentInspectionError) => 594,
+            EventType::WebDav(WebDavEvent::Bind) => 595,
+            EventType::WebDav(WebDavEvent::Unbind) => 596,
+            EventType::WebDav(WebDavEvent::Rebind) => 597,
         }
     }
 
@@ -1510,6 +1530,26 @@ impl EventType {
             51 => Some(EventType::Store(StoreEvent::CacheHit)),
             52 => Some(EventType::Store(StoreEvent::CacheStale)),
             577 => Some(EventType::Store(StoreEvent::CacheUpdate)),
+            578 => Some(EventType::Auth(AuthEvent::Impersonate)),
+            579 => Some(EventType::WebDav(WebDavEvent::SlowRequest)),
+            580 => Some(EventType::Groupware(GroupwareEvent::CalendarCreated)),
+            581 => Some(EventType::Groupware(GroupwareEvent::CalendarUpdated)),
+            582 => Some(EventType::Groupware(GroupwareEvent::CalendarDeleted)),
+            583 => Some(EventType::Groupware(GroupwareEvent::ContactCreated)),
+            584 => Some(EventType::Groupware(GroupwareEvent::ContactUpdated)),
+            585 => Some(EventType::Groupware(GroupwareEvent::ContactDeleted)),
+            586 => Some(EventType::Groupware(GroupwareEvent::FileCreated)),
+            587 => Some(EventType::Groupware(GroupwareEvent::FileUpdated)),
+            588 => Some(EventType::Groupware(GroupwareEvent::FileDeleted)),
+            589 => Some(EventType::Calendar(CalendarEvent::InviteSkipped)),
+            590 => Some(EventType::Calendar(CalendarEvent::MirrorCompleted)),
+            591 => Some(EventType::Calendar(CalendarEvent::MirrorConflict)),
+            592 => Some(EventType::Calendar(CalendarEvent::MirrorError)),
+            593 => Some(EventType::Groupware(GroupwareEvent::ContentRejected)),
+            594 => Some(EventType::Groupware(GroupwareEvent::ContentInspectionError)),
+            595 => Some(EventType::WebDav(WebDavEvent::Bind)),
+            596 => Some(EventType::WebDav(WebDavEvent::Unbind)),
+            597 => Some(EventType::WebDav(WebDavEvent::Rebind)),
             _ => None,
         }
     }
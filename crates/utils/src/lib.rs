@@ -74,6 +74,24 @@ impl BlobHash {
     }
 }
 
+/// Computes a `BlobHash` incrementally as data is received.
+#[derive(Default)]
+pub struct BlobHasher(blake3::Hasher);
+
+impl BlobHasher {
+    pub fn new() -> Self {
+        BlobHasher(blake3::Hasher::new())
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    pub fn finalize(&self) -> BlobHash {
+        BlobHash(self.0.finalize().into())
+    }
+}
+
 impl From<&ArchivedBlobHash> for BlobHash {
     fn from(value: &ArchivedBlobHash) -> Self {
         BlobHash(value.0)
@@ -774,7 +774,11 @@ pub trait TestInternalDirectory {
     async fn create_test_group(&self, login: &str, name: &str, emails: &[&str]) -> u32;
     async fn create_test_list(&self, login: &str, name: &str, emails: &[&str]) -> u32;
     async fn set_test_quota(&self, login: &str, quota: u32);
-    async fn add_permissions(&self, login: &str, permissions: impl IntoIterator<Item = Permission>);
+    async fn add_permissions(
+        &self,
+        login: &str,
+        permissions: impl IntoIterator<Item = Permission>,
+    ) -> ChangedPrincipals;
     async fn add_to_group(&self, login: &str, group: &str) -> ChangedPrincipals;
     async fn remove_from_group(&self, login: &str, group: &str) -> ChangedPrincipals;
     async fn remove_test_alias(&self, login: &str, alias: &str);
@@ -903,7 +907,7 @@ impl TestInternalDirectory for Store {
         &self,
         login: &str,
         permissions: impl IntoIterator<Item = Permission>,
-    ) {
+    ) -> ChangedPrincipals {
         self.update_principal(
             UpdatePrincipal::by_name(login).with_updates(
                 permissions
@@ -918,7 +922,7 @@ impl TestInternalDirectory for Store {
             ),
         )
         .await
-        .unwrap();
+        .unwrap()
     }
 
     async fn add_to_group(&self, login: &str, group: &str) -> ChangedPrincipals {
@@ -0,0 +1,515 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::types::id::Id;
+use serde_json::{Value, json};
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, jmap_method_request},
+};
+
+use super::JMAPTest;
+
+const LOGIN: &str = "jdoe@example.com";
+const SECRET: &str = "12345";
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running CalendarEvent tests...");
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(LOGIN, SECRET, "John Doe", &[LOGIN])
+            .await,
+    )
+    .to_string();
+    let account = account_id.as_str();
+    let unknown_id = Id::from(9999u32).to_string();
+
+    // Create test calendars
+    let response = jmap_method_request(
+        "Calendar/set",
+        json!({
+            "accountId": account,
+            "create": {
+                "work": { "name": "Work" },
+                "home": { "name": "Home" }
+            }
+        }),
+        LOGIN,
+        SECRET,
+    )
+    .await;
+    let work_id = created_id(&response, "work");
+    let home_id = created_id(&response, "home");
+
+    // Create events, rejecting invalid ones
+    let response = event_set(
+        account,
+        json!({
+            "create": {
+                "e1": event(&work_id, "event-1", "Meeting", "20250105T100000Z"),
+                "e2": event(&work_id, "event-1", "Duplicate", "20250105T100000Z"),
+                "e3": {
+                    "calendarData": ical("event-3", "No calendar", "20250105T100000Z")
+                },
+                "e4": {
+                    "calendarIds": { &work_id: true },
+                    "calendarData": "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:John\r\nEND:VCARD\r\n"
+                },
+                "e5": {
+                    "calendarIds": { &work_id: true },
+                    "calendarData": ical("event-5", "Two UIDs", "20250105T100000Z").replace(
+                        "END:VCALENDAR",
+                        "BEGIN:VEVENT\r\nUID:other\r\nEND:VEVENT\r\nEND:VCALENDAR"
+                    )
+                },
+                "e6": event(&unknown_id, "event-6", "Unknown", "20250105T100000Z")
+            }
+        }),
+    )
+    .await;
+    let e1_id = created_id(&response, "e1");
+    assert_set_error(&response, "/notCreated/e2", "alreadyExists");
+    assert_eq!(
+        response.pointer("/notCreated/e2/existingId"),
+        Some(&Value::from(e1_id.as_str())),
+        "Response: {response:?}"
+    );
+    for id in ["e3", "e4", "e5", "e6"] {
+        assert_set_error(&response, &format!("/notCreated/{id}"), "invalidProperties");
+    }
+
+    // Events created in the same request count towards the calendar limit
+    let response = event_set(
+        account,
+        json!({
+            "create": {
+                "e7": event(&work_id, "event-7", "Lunch", "20250106T120000Z"),
+                "e8": event(&work_id, "event-8", "Review", "20250107T150000Z"),
+                "e9": event(&work_id, "event-9", "Overflow", "20250108T090000Z")
+            }
+        }),
+    )
+    .await;
+    let e7_id = created_id(&response, "e7");
+    let e8_id = created_id(&response, "e8");
+    assert_set_error(&response, "/notCreated/e9", "overQuota");
+    let response = event_set(
+        account,
+        json!({
+            "create": {
+                "e9": event(&work_id, "event-9", "Overflow", "20250108T090000Z")
+            }
+        }),
+    )
+    .await;
+    assert_set_error(&response, "/notCreated/e9", "overQuota");
+
+    // UIDs are unique within a calendar but not across calendars
+    let response = event_set(
+        account,
+        json!({
+            "create": {
+                "e10": event(&home_id, "event-1", "Meeting at home", "20250105T100000Z")
+            }
+        }),
+    )
+    .await;
+    let e10_id = created_id(&response, "e10");
+    let response = event_set(
+        account,
+        json!({
+            "update": {
+                &e1_id: { format!("calendarIds/{home_id}"): true }
+            }
+        }),
+    )
+    .await;
+    assert_set_error(&response, &format!("/notUpdated/{e1_id}"), "alreadyExists");
+    assert_eq!(
+        response.pointer(&format!("/notUpdated/{e1_id}/existingId")),
+        Some(&Value::from(e10_id.as_str())),
+        "Response: {response:?}"
+    );
+
+    // Update events
+    let response = event_set(
+        account,
+        json!({
+            "update": {
+                &e1_id: {
+                    "calendarData": ical("event-1", "Rescheduled", "20250110T100000Z")
+                },
+                &e7_id: {
+                    "calendarData": ical("event-changed", "Lunch", "20250106T120000Z")
+                },
+                &e8_id: {
+                    "calendarIds": {}
+                },
+                &unknown_id: {
+                    "calendarData": ical("event-1", "Unknown", "20250110T100000Z")
+                }
+            },
+            "destroy": [&e10_id]
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/updated").map(object_keys),
+        Some(vec![e1_id.clone()]),
+        "Response: {response:?}"
+    );
+    assert_set_error(
+        &response,
+        &format!("/notUpdated/{e7_id}"),
+        "invalidProperties",
+    );
+    assert_set_error(
+        &response,
+        &format!("/notUpdated/{e8_id}"),
+        "invalidProperties",
+    );
+    assert_set_error(&response, &format!("/notUpdated/{unknown_id}"), "notFound");
+    let response = event_set(
+        account,
+        json!({
+            "update": {
+                &e1_id: { format!("calendarIds/{home_id}"): true }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/updated").map(object_keys),
+        Some(vec![e1_id.clone()]),
+        "Response: {response:?}"
+    );
+    let response = jmap_method_request(
+        "CalendarEvent/get",
+        json!({
+            "accountId": account,
+            "ids": [&e1_id],
+            "properties": ["calendarIds", "title"]
+        }),
+        LOGIN,
+        SECRET,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/list/0/title"),
+        Some(&Value::from("Rescheduled")),
+        "Response: {response:?}"
+    );
+    let mut calendar_ids = response
+        .pointer("/list/0/calendarIds")
+        .map(object_keys)
+        .unwrap_or_default();
+    calendar_ids.sort();
+    let mut expected_ids = vec![work_id.clone(), home_id.clone()];
+    expected_ids.sort();
+    assert_eq!(calendar_ids, expected_ids, "Response: {response:?}");
+
+    // Destroy events
+    let response = event_set(
+        account,
+        json!({
+            "destroy": [&e1_id, &e10_id]
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/destroyed"),
+        Some(&json!([&e1_id])),
+        "Response: {response:?}"
+    );
+    assert_set_error(&response, &format!("/notDestroyed/{e10_id}"), "notFound");
+
+    // Fetch events
+    let response = event_request(
+        "CalendarEvent/get",
+        account,
+        json!({
+            "ids": null,
+            "properties": ["id", "calendarIds", "uid", "title", "utcStart", "utcEnd"]
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/list"),
+        Some(&json!([
+            {
+                "id": &e7_id,
+                "calendarIds": { &work_id: true },
+                "uid": "event-7",
+                "title": "Lunch",
+                "utcStart": "2025-01-06T12:00:00Z",
+                "utcEnd": "2025-01-06T13:00:00Z"
+            },
+            {
+                "id": &e8_id,
+                "calendarIds": { &work_id: true },
+                "uid": "event-8",
+                "title": "Review",
+                "utcStart": "2025-01-07T15:00:00Z",
+                "utcEnd": "2025-01-07T16:00:00Z"
+            }
+        ])),
+        "Response: {response:?}"
+    );
+    let response = event_request(
+        "CalendarEvent/get",
+        account,
+        json!({
+            "ids": [&e7_id, &e1_id],
+            "properties": ["calendarData", "size"]
+        }),
+    )
+    .await;
+    let calendar_data = response
+        .pointer("/list/0/calendarData")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    assert!(
+        calendar_data.contains("UID:event-7") && calendar_data.contains("SUMMARY:Lunch"),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/list/0/size"),
+        Some(&Value::from(
+            ical("event-7", "Lunch", "20250106T120000Z").len()
+        )),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/notFound"),
+        Some(&json!([&e1_id])),
+        "Response: {response:?}"
+    );
+    let old_state = response
+        .pointer("/state")
+        .and_then(|v| v.as_str())
+        .unwrap()
+        .to_string();
+
+    // Track event changes
+    let response = event_set(
+        account,
+        json!({
+            "create": {
+                "e11": event(&home_id, "event-11", "Dinner", "20250105T190000Z")
+            },
+            "update": {
+                &e7_id: {
+                    "calendarData": ical("event-7", "Team lunch", "20250106T120000Z")
+                }
+            },
+            "destroy": [&e8_id]
+        }),
+    )
+    .await;
+    let e11_id = created_id(&response, "e11");
+    let new_state = response.pointer("/newState").cloned().unwrap();
+    let response = event_request(
+        "CalendarEvent/changes",
+        account,
+        json!({
+            "sinceState": &old_state
+        }),
+    )
+    .await;
+    assert_eq!(
+        (
+            response.pointer("/created"),
+            response.pointer("/updated"),
+            response.pointer("/destroyed"),
+            response.pointer("/newState"),
+            response.pointer("/hasMoreChanges"),
+        ),
+        (
+            Some(&json!([&e11_id])),
+            Some(&json!([&e7_id])),
+            Some(&json!([&e8_id])),
+            Some(&new_state),
+            Some(&Value::Bool(false)),
+        ),
+        "Response: {response:?}"
+    );
+    let response = event_request(
+        "CalendarEvent/changes",
+        account,
+        json!({
+            "sinceState": &new_state
+        }),
+    )
+    .await;
+    assert_eq!(
+        (
+            response.pointer("/created"),
+            response.pointer("/updated"),
+            response.pointer("/destroyed"),
+        ),
+        (Some(&json!([])), Some(&json!([])), Some(&json!([]))),
+        "Response: {response:?}"
+    );
+
+    // Query events
+    for (filter, sort, expected_ids) in [
+        (json!(null), json!(null), vec![&e11_id, &e7_id]),
+        (
+            json!(null),
+            json!([{ "property": "start", "isAscending": false }]),
+            vec![&e7_id, &e11_id],
+        ),
+        (
+            json!({ "inCalendar": &home_id }),
+            json!(null),
+            vec![&e11_id],
+        ),
+        (json!({ "uid": "event-7" }), json!(null), vec![&e7_id]),
+        (json!({ "uid": "event-8" }), json!(null), vec![]),
+        (
+            json!({ "after": "2025-01-06T00:00:00Z" }),
+            json!(null),
+            vec![&e7_id],
+        ),
+        (
+            json!({ "before": "2025-01-06T00:00:00Z" }),
+            json!(null),
+            vec![&e11_id],
+        ),
+        (
+            json!({
+                "operator": "AND",
+                "conditions": [
+                    { "inCalendar": &work_id },
+                    { "before": "2025-01-06T00:00:00Z" }
+                ]
+            }),
+            json!(null),
+            vec![],
+        ),
+    ] {
+        let response = event_request(
+            "CalendarEvent/query",
+            account,
+            json!({
+                "filter": filter,
+                "sort": sort
+            }),
+        )
+        .await;
+        assert_eq!(
+            response.pointer("/ids"),
+            Some(&json!(expected_ids)),
+            "Filter: {filter:?}, sort: {sort:?}, response: {response:?}"
+        );
+    }
+
+    destroy_all_calendars(account).await;
+    assert_is_empty(server).await;
+}
+
+async fn event_set(account_id: &str, arguments: Value) -> Value {
+    event_request("CalendarEvent/set", account_id, arguments).await
+}
+
+async fn event_request(method: &str, account_id: &str, mut arguments: Value) -> Value {
+    arguments["accountId"] = Value::from(account_id);
+    jmap_method_request(method, arguments, LOGIN, SECRET).await
+}
+
+async fn destroy_all_calendars(account_id: &str) {
+    let response = jmap_method_request(
+        "Calendar/get",
+        json!({
+            "accountId": account_id,
+            "ids": null,
+            "properties": ["id"]
+        }),
+        LOGIN,
+        SECRET,
+    )
+    .await;
+    let ids = response
+        .pointer("/list")
+        .and_then(|list| list.as_array())
+        .unwrap()
+        .iter()
+        .filter_map(|calendar| calendar.get("id").cloned())
+        .collect::<Vec<_>>();
+    let response = jmap_method_request(
+        "Calendar/set",
+        json!({
+            "accountId": account_id,
+            "destroy": ids,
+            "onDestroyRemoveEvents": true
+        }),
+        LOGIN,
+        SECRET,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/destroyed"),
+        Some(&Value::Array(ids)),
+        "Response: {response:?}"
+    );
+}
+
+fn event(calendar_id: &str, uid: &str, summary: &str, start: &str) -> Value {
+    json!({
+        "calendarIds": { calendar_id: true },
+        "calendarData": ical(uid, summary, start)
+    })
+}
+
+fn ical(uid: &str, summary: &str, start: &str) -> String {
+    format!(
+        concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "VERSION:2.0\r\n",
+            "PRODID:-//Stalwart Labs//Test//EN\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:{}\r\n",
+            "DTSTAMP:20250101T000000Z\r\n",
+            "DTSTART:{}\r\n",
+            "DURATION:PT1H\r\n",
+            "SUMMARY:{}\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n"
+        ),
+        uid, start, summary
+    )
+}
+
+pub fn created_id(response: &Value, id: &str) -> String {
+    response
+        .pointer(&format!("/created/{id}/id"))
+        .and_then(|id| id.as_str())
+        .unwrap_or_else(|| panic!("Missing created id {id}: {response:?}"))
+        .to_string()
+}
+
+pub fn assert_set_error(response: &Value, pointer: &str, error: &str) {
+    assert_eq!(
+        response
+            .pointer(&format!("{pointer}/type"))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default(),
+        error,
+        "Response: {response:?}"
+    );
+}
+
+pub fn object_keys(value: &Value) -> Vec<String> {
+    value
+        .as_object()
+        .map(|object| object.keys().cloned().collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,399 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::types::id::Id;
+use serde_json::{Value, json};
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty,
+        calendar_event::{assert_set_error, created_id, object_keys},
+        jmap_method_request,
+    },
+};
+
+use super::JMAPTest;
+
+const LOGIN: &str = "jdoe@example.com";
+const SECRET: &str = "12345";
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running ContactCard tests...");
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(LOGIN, SECRET, "John Doe", &[LOGIN])
+            .await,
+    )
+    .to_string();
+    let account = account_id.as_str();
+    let unknown_id = Id::from(9999u32).to_string();
+
+    // Create test address books
+    let response = jmap_method_request(
+        "AddressBook/set",
+        json!({
+            "accountId": account,
+            "create": {
+                "friends": { "name": "Friends" },
+                "work": { "name": "Work" }
+            }
+        }),
+        LOGIN,
+        SECRET,
+    )
+    .await;
+    let friends_id = created_id(&response, "friends");
+    let work_id = created_id(&response, "work");
+
+    // Create cards, rejecting invalid ones
+    let response = card_request(
+        "ContactCard/set",
+        account,
+        json!({
+            "create": {
+                "c1": card(&friends_id, "card-1", "Jane Doe", Some("jane@example.com")),
+                "c2": card(&friends_id, "card-1", "Jane Duplicate", None),
+                "c3": {
+                    "cardData": vcard("card-3", "No address book", None)
+                },
+                "c4": {
+                    "addressBookIds": { &friends_id: true },
+                    "cardData": "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nEND:VCALENDAR\r\n"
+                },
+                "c5": card(&unknown_id, "card-5", "Unknown", None)
+            }
+        }),
+    )
+    .await;
+    let c1_id = created_id(&response, "c1");
+    assert_set_error(&response, "/notCreated/c2", "alreadyExists");
+    assert_eq!(
+        response.pointer("/notCreated/c2/existingId"),
+        Some(&Value::from(c1_id.as_str())),
+        "Response: {response:?}"
+    );
+    for id in ["c3", "c4", "c5"] {
+        assert_set_error(&response, &format!("/notCreated/{id}"), "invalidProperties");
+    }
+
+    // Cards created in the same request count towards the address book limit
+    let response = card_request(
+        "ContactCard/set",
+        account,
+        json!({
+            "create": {
+                "c6": card(&friends_id, "card-6", "Alice", None),
+                "c7": card(&friends_id, "card-7", "Overflow", None)
+            }
+        }),
+    )
+    .await;
+    let c6_id = created_id(&response, "c6");
+    assert_set_error(&response, "/notCreated/c7", "overQuota");
+
+    // UIDs are unique within an address book but not across address books
+    let response = card_request(
+        "ContactCard/set",
+        account,
+        json!({
+            "create": {
+                "c8": card(&work_id, "card-1", "Jane at work", None)
+            }
+        }),
+    )
+    .await;
+    let c8_id = created_id(&response, "c8");
+    let response = card_request(
+        "ContactCard/set",
+        account,
+        json!({
+            "update": {
+                &c1_id: { format!("addressBookIds/{work_id}"): true }
+            }
+        }),
+    )
+    .await;
+    assert_set_error(&response, &format!("/notUpdated/{c1_id}"), "alreadyExists");
+    assert_eq!(
+        response.pointer(&format!("/notUpdated/{c1_id}/existingId")),
+        Some(&Value::from(c8_id.as_str())),
+        "Response: {response:?}"
+    );
+
+    // Update cards
+    let response = card_request(
+        "ContactCard/set",
+        account,
+        json!({
+            "update": {
+                &c1_id: {
+                    "cardData": vcard("card-1", "Jane Smith", Some("jane@example.com"))
+                },
+                &c6_id: {
+                    "cardData": vcard("card-changed", "Alice", None)
+                },
+                &unknown_id: {
+                    "cardData": vcard("card-1", "Unknown", None)
+                }
+            },
+            "destroy": [&c8_id]
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/updated").map(object_keys),
+        Some(vec![c1_id.clone()]),
+        "Response: {response:?}"
+    );
+    assert_set_error(
+        &response,
+        &format!("/notUpdated/{c6_id}"),
+        "invalidProperties",
+    );
+    assert_set_error(&response, &format!("/notUpdated/{unknown_id}"), "notFound");
+    let response = card_request(
+        "ContactCard/set",
+        account,
+        json!({
+            "update": {
+                &c1_id: { format!("addressBookIds/{work_id}"): true },
+                &c6_id: { "addressBookIds": {} }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/updated").map(object_keys),
+        Some(vec![c1_id.clone()]),
+        "Response: {response:?}"
+    );
+    assert_set_error(
+        &response,
+        &format!("/notUpdated/{c6_id}"),
+        "invalidProperties",
+    );
+
+    // Fetch cards
+    let response = card_request(
+        "ContactCard/get",
+        account,
+        json!({
+            "ids": null,
+            "properties": ["id", "uid", "name"]
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/list"),
+        Some(&json!([
+            {
+                "id": &c1_id,
+                "uid": "card-1",
+                "name": "Jane Smith"
+            },
+            {
+                "id": &c6_id,
+                "uid": "card-6",
+                "name": "Alice"
+            }
+        ])),
+        "Response: {response:?}"
+    );
+    let response = card_request(
+        "ContactCard/get",
+        account,
+        json!({
+            "ids": [&c1_id, &c8_id],
+            "properties": ["addressBookIds", "cardData", "size"]
+        }),
+    )
+    .await;
+    let mut address_book_ids = response
+        .pointer("/list/0/addressBookIds")
+        .map(object_keys)
+        .unwrap_or_default();
+    address_book_ids.sort();
+    let mut expected_ids = vec![friends_id.clone(), work_id.clone()];
+    expected_ids.sort();
+    assert_eq!(address_book_ids, expected_ids, "Response: {response:?}");
+    let card_data = response
+        .pointer("/list/0/cardData")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    assert!(
+        card_data.contains("UID:card-1") && card_data.contains("FN:Jane Smith"),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/list/0/size"),
+        Some(&Value::from(
+            vcard("card-1", "Jane Smith", Some("jane@example.com")).len()
+        )),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/notFound"),
+        Some(&json!([&c8_id])),
+        "Response: {response:?}"
+    );
+    let old_state = response.pointer("/state").cloned().unwrap();
+
+    // Track card changes
+    let response = card_request(
+        "ContactCard/set",
+        account,
+        json!({
+            "create": {
+                "c9": card(&work_id, "card-9", "Bob", Some("bob@example.com"))
+            },
+            "update": {
+                &c6_id: {
+                    "cardData": vcard("card-6", "Alice Cooper", None)
+                }
+            },
+            "destroy": [&c1_id]
+        }),
+    )
+    .await;
+    let c9_id = created_id(&response, "c9");
+    let new_state = response.pointer("/newState").cloned().unwrap();
+    let response = card_request(
+        "ContactCard/changes",
+        account,
+        json!({
+            "sinceState": &old_state
+        }),
+    )
+    .await;
+    assert_eq!(
+        (
+            response.pointer("/created"),
+            response.pointer("/updated"),
+            response.pointer("/destroyed"),
+            response.pointer("/newState"),
+        ),
+        (
+            Some(&json!([&c9_id])),
+            Some(&json!([&c6_id])),
+            Some(&json!([&c1_id])),
+            Some(&new_state),
+        ),
+        "Response: {response:?}"
+    );
+
+    // Query cards
+    for (filter, sort, expected_ids) in [
+        (json!(null), json!(null), vec![&c6_id, &c9_id]),
+        (
+            json!(null),
+            json!([{ "property": "size", "isAscending": false }]),
+            vec![&c9_id, &c6_id],
+        ),
+        (
+            json!({ "inAddressBook": &work_id }),
+            json!(null),
+            vec![&c9_id],
+        ),
+        (json!({ "uid": "card-6" }), json!(null), vec![&c6_id]),
+        (json!({ "uid": "card-1" }), json!(null), vec![]),
+        (
+            json!({ "email": "bob@example.com" }),
+            json!(null),
+            vec![&c9_id],
+        ),
+        (json!({ "email": "jane@example.com" }), json!(null), vec![]),
+        (
+            json!({
+                "operator": "AND",
+                "conditions": [
+                    { "inAddressBook": &friends_id },
+                    { "email": "bob@example.com" }
+                ]
+            }),
+            json!(null),
+            vec![],
+        ),
+    ] {
+        let response = card_request(
+            "ContactCard/query",
+            account,
+            json!({
+                "filter": filter,
+                "sort": sort
+            }),
+        )
+        .await;
+        assert_eq!(
+            response.pointer("/ids"),
+            Some(&json!(expected_ids)),
+            "Filter: {filter:?}, sort: {sort:?}, response: {response:?}"
+        );
+    }
+
+    destroy_all_address_books(account).await;
+    assert_is_empty(server).await;
+}
+
+async fn card_request(method: &str, account_id: &str, mut arguments: Value) -> Value {
+    arguments["accountId"] = Value::from(account_id);
+    jmap_method_request(method, arguments, LOGIN, SECRET).await
+}
+
+async fn destroy_all_address_books(account_id: &str) {
+    let response = jmap_method_request(
+        "AddressBook/get",
+        json!({
+            "accountId": account_id,
+            "ids": null,
+            "properties": ["id"]
+        }),
+        LOGIN,
+        SECRET,
+    )
+    .await;
+    let ids = response
+        .pointer("/list")
+        .and_then(|list| list.as_array())
+        .unwrap()
+        .iter()
+        .filter_map(|address_book| address_book.get("id").cloned())
+        .collect::<Vec<_>>();
+    let response = jmap_method_request(
+        "AddressBook/set",
+        json!({
+            "accountId": account_id,
+            "destroy": ids,
+            "onDestroyRemoveContents": true
+        }),
+        LOGIN,
+        SECRET,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/destroyed"),
+        Some(&Value::Array(ids)),
+        "Response: {response:?}"
+    );
+}
+
+fn card(address_book_id: &str, uid: &str, name: &str, email: Option<&str>) -> Value {
+    json!({
+        "addressBookIds": { address_book_id: true },
+        "cardData": vcard(uid, name, email)
+    })
+}
+
+fn vcard(uid: &str, name: &str, email: Option<&str>) -> String {
+    let email = email
+        .map(|email| format!("EMAIL:{email}\r\n"))
+        .unwrap_or_default();
+    format!("BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{uid}\r\nFN:{name}\r\n{email}END:VCARD\r\n")
+}
@@ -0,0 +1,423 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use jmap_proto::types::id::Id;
+use serde_json::{Value, json};
+
+use crate::{
+    directory::internal::TestInternalDirectory,
+    jmap::{
+        assert_is_empty,
+        calendar_event::{assert_set_error, created_id, object_keys},
+        jmap_method_request,
+    },
+};
+
+use super::JMAPTest;
+
+const LOGIN: &str = "jdoe@example.com";
+const SECRET: &str = "12345";
+
+pub async fn test(params: &mut JMAPTest) {
+    println!("Running FileNode tests...");
+    let server = params.server.clone();
+    let account_id = Id::from(
+        server
+            .core
+            .storage
+            .data
+            .create_test_user(LOGIN, SECRET, "John Doe", &[LOGIN])
+            .await,
+    )
+    .to_string();
+    let account = account_id.as_str();
+    let unknown_id = Id::from(9999u32).to_string();
+    let hello_text = "Hello, world!";
+    let hello_blob_id = upload_blob(account, hello_text).await;
+
+    // Create folders and files, rejecting invalid ones
+    let response = node_request(
+        "FileNode/set",
+        account,
+        json!({
+            "create": {
+                "docs": { "name": "Documents" },
+                "hello": {
+                    "parentId": "#docs",
+                    "name": "hello.txt",
+                    "blobId": &hello_blob_id,
+                    "type": "text/plain"
+                },
+                "n1": {
+                    "parentId": "#docs",
+                    "name": "hello.txt",
+                    "blobId": &hello_blob_id
+                },
+                "n2": { "blobId": &hello_blob_id },
+                "n3": { "name": "a/b" },
+                "n4": { "parentId": &unknown_id, "name": "orphan" },
+                "n5": { "name": "typed", "type": "text/plain" }
+            }
+        }),
+    )
+    .await;
+    let docs_id = created_id(&response, "docs");
+    let hello_id = created_id(&response, "hello");
+    assert_set_error(&response, "/notCreated/n1", "alreadyExists");
+    for id in ["n2", "n3", "n4", "n5"] {
+        assert_set_error(&response, &format!("/notCreated/{id}"), "invalidProperties");
+    }
+
+    // Fetch nodes and download their contents
+    let response = node_request(
+        "FileNode/get",
+        account,
+        json!({
+            "ids": [&docs_id, &hello_id, &unknown_id],
+            "properties": ["id", "parentId", "name", "size", "type"]
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/list"),
+        Some(&json!([
+            {
+                "id": &docs_id,
+                "parentId": null,
+                "name": "Documents",
+                "size": null,
+                "type": null
+            },
+            {
+                "id": &hello_id,
+                "parentId": &docs_id,
+                "name": "hello.txt",
+                "size": hello_text.len(),
+                "type": "text/plain"
+            }
+        ])),
+        "Response: {response:?}"
+    );
+    assert_eq!(
+        response.pointer("/notFound"),
+        Some(&json!([&unknown_id])),
+        "Response: {response:?}"
+    );
+    assert_eq!(node_contents(account, &hello_id).await, hello_text);
+    let response = node_request(
+        "FileNode/get",
+        account,
+        json!({
+            "ids": [&docs_id],
+            "properties": ["blobId", "created", "modified"]
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/list/0/blobId"),
+        Some(&Value::Null),
+        "Response: {response:?}"
+    );
+    for property in ["created", "modified"] {
+        assert!(
+            response
+                .pointer(&format!("/list/0/{property}"))
+                .and_then(|v| v.as_str())
+                .is_some(),
+            "Response: {response:?}"
+        );
+    }
+
+    // Replace file contents, folders have no contents
+    let updated_text = "# Hello\n\nUpdated contents.";
+    let updated_blob_id = upload_blob(account, updated_text).await;
+    let response = node_request(
+        "FileNode/set",
+        account,
+        json!({
+            "update": {
+                &hello_id: {
+                    "blobId": &updated_blob_id,
+                    "type": "text/markdown"
+                },
+                &docs_id: { "blobId": &updated_blob_id },
+                &unknown_id: { "name": "unknown" }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/updated").map(object_keys),
+        Some(vec![hello_id.clone()]),
+        "Response: {response:?}"
+    );
+    assert_set_error(
+        &response,
+        &format!("/notUpdated/{docs_id}"),
+        "invalidProperties",
+    );
+    assert_set_error(&response, &format!("/notUpdated/{unknown_id}"), "notFound");
+    let response = node_request(
+        "FileNode/get",
+        account,
+        json!({
+            "ids": [&hello_id],
+            "properties": ["size", "type"]
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/list/0"),
+        Some(&json!({
+            "id": &hello_id,
+            "size": updated_text.len(),
+            "type": "text/markdown"
+        })),
+        "Response: {response:?}"
+    );
+    assert_eq!(node_contents(account, &hello_id).await, updated_text);
+
+    // Move nodes across the hierarchy
+    let response = node_request(
+        "FileNode/set",
+        account,
+        json!({
+            "create": {
+                "archive": { "name": "Archive" },
+                "sub": { "parentId": &docs_id, "name": "Subfolder" }
+            }
+        }),
+    )
+    .await;
+    let archive_id = created_id(&response, "archive");
+    let sub_id = created_id(&response, "sub");
+    let response = node_request(
+        "FileNode/set",
+        account,
+        json!({
+            "update": {
+                &hello_id: { "parentId": &archive_id }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/updated").map(object_keys),
+        Some(vec![hello_id.clone()]),
+        "Response: {response:?}"
+    );
+    let response = node_request(
+        "FileNode/set",
+        account,
+        json!({
+            "update": {
+                &archive_id: { "parentId": &docs_id },
+                &docs_id: { "parentId": &sub_id },
+                &sub_id: { "parentId": null, "name": "Archive" }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/updated").map(object_keys),
+        Some(vec![archive_id.clone()]),
+        "Response: {response:?}"
+    );
+    assert_set_error(
+        &response,
+        &format!("/notUpdated/{docs_id}"),
+        "invalidProperties",
+    );
+    assert_set_error(&response, &format!("/notUpdated/{sub_id}"), "alreadyExists");
+    let response = node_request(
+        "FileNode/set",
+        account,
+        json!({
+            "update": {
+                &docs_id: { "parentId": &docs_id }
+            }
+        }),
+    )
+    .await;
+    assert_set_error(
+        &response,
+        &format!("/notUpdated/{docs_id}"),
+        "invalidProperties",
+    );
+    let response = node_request(
+        "FileNode/get",
+        account,
+        json!({
+            "ids": [&docs_id, &archive_id, &hello_id, &sub_id],
+            "properties": ["parentId", "name"]
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/list"),
+        Some(&json!([
+            { "id": &docs_id, "parentId": null, "name": "Documents" },
+            { "id": &archive_id, "parentId": &docs_id, "name": "Archive" },
+            { "id": &hello_id, "parentId": &archive_id, "name": "hello.txt" },
+            { "id": &sub_id, "parentId": &docs_id, "name": "Subfolder" }
+        ])),
+        "Response: {response:?}"
+    );
+    assert_eq!(node_contents(account, &hello_id).await, updated_text);
+
+    // Folders are only destroyed with their children when requested
+    let response = node_request(
+        "FileNode/set",
+        account,
+        json!({
+            "destroy": [&docs_id]
+        }),
+    )
+    .await;
+    assert_set_error(
+        &response,
+        &format!("/notDestroyed/{docs_id}"),
+        "nodeHasChildren",
+    );
+    let old_state = response.pointer("/newState").cloned().unwrap();
+
+    // Track node changes
+    let response = node_request(
+        "FileNode/set",
+        account,
+        json!({
+            "create": {
+                "notes": {
+                    "name": "notes.txt",
+                    "blobId": &hello_blob_id
+                }
+            },
+            "update": {
+                &hello_id: { "name": "hello.md" }
+            },
+            "destroy": [&sub_id]
+        }),
+    )
+    .await;
+    let notes_id = created_id(&response, "notes");
+    let new_state = response.pointer("/newState").cloned().unwrap();
+    let response = node_request(
+        "FileNode/changes",
+        account,
+        json!({
+            "sinceState": &old_state
+        }),
+    )
+    .await;
+    assert_eq!(
+        (
+            response.pointer("/created"),
+            response.pointer("/updated"),
+            response.pointer("/destroyed"),
+            response.pointer("/newState"),
+        ),
+        (
+            Some(&json!([&notes_id])),
+            Some(&json!([&hello_id])),
+            Some(&json!([&sub_id])),
+            Some(&new_state),
+        ),
+        "Response: {response:?}"
+    );
+    assert_eq!(node_contents(account, &notes_id).await, hello_text);
+
+    // Destroy all nodes
+    let response = node_request(
+        "FileNode/set",
+        account,
+        json!({
+            "destroy": [&docs_id, &notes_id],
+            "onDestroyRemoveChildren": true
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/destroyed"),
+        Some(&json!([&docs_id, &notes_id])),
+        "Response: {response:?}"
+    );
+    let response = node_request(
+        "FileNode/get",
+        account,
+        json!({
+            "ids": null,
+            "properties": ["id"]
+        }),
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/list"),
+        Some(&json!([])),
+        "Response: {response:?}"
+    );
+
+    server.core.storage.data.blob_expire_all().await;
+    assert_is_empty(server).await;
+}
+
+async fn node_request(method: &str, account_id: &str, mut arguments: Value) -> Value {
+    arguments["accountId"] = Value::from(account_id);
+    jmap_method_request(method, arguments, LOGIN, SECRET).await
+}
+
+async fn upload_blob(account_id: &str, text: &str) -> String {
+    let response = jmap_method_request(
+        "Blob/upload",
+        json!({
+            "accountId": account_id,
+            "create": {
+                "file": {
+                    "data": [{ "data:asText": text }]
+                }
+            }
+        }),
+        LOGIN,
+        SECRET,
+    )
+    .await;
+    response
+        .pointer("/created/file/id")
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Response: {response:?}"))
+        .to_string()
+}
+
+async fn node_contents(account_id: &str, node_id: &str) -> String {
+    let response = node_request(
+        "FileNode/get",
+        account_id,
+        json!({
+            "ids": [node_id],
+            "properties": ["blobId"]
+        }),
+    )
+    .await;
+    let blob_id = response
+        .pointer("/list/0/blobId")
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Response: {response:?}"));
+    let response = jmap_method_request(
+        "Blob/get",
+        json!({
+            "accountId": account_id,
+            "ids": [blob_id],
+            "properties": ["data:asText"]
+        }),
+        LOGIN,
+        SECRET,
+    )
+    .await;
+    response
+        .pointer("/list/0/data:asText")
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Response: {response:?}"))
+        .to_string()
+}
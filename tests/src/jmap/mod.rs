@@ -54,6 +54,8 @@ pub mod auth_acl;
 pub mod auth_limits;
 pub mod auth_oauth;
 pub mod blob;
+pub mod calendar_event;
+pub mod contact_card;
 pub mod crypto;
 pub mod delivery;
 pub mod email_changes;
@@ -67,6 +69,7 @@ pub mod email_set;
 pub mod email_submission;
 pub mod enterprise;
 pub mod event_source;
+pub mod file_node;
 pub mod mailbox;
 pub mod permissions;
 pub mod purge;
@@ -109,6 +112,9 @@ async fn jmap_tests() {
     push_subscription::test(&mut params).await;
     sieve_script::test(&mut params).await;
     vacation_response::test(&mut params).await;
+    calendar_event::test(&mut params).await;
+    contact_card::test(&mut params).await;
+    file_node::test(&mut params).await;
     email_submission::test(&mut params).await;
     websocket::test(&mut params).await;
     quota::test(&mut params).await;
@@ -435,6 +441,26 @@ pub async fn jmap_json_request(
     serde_json::from_str(&jmap_raw_request(body, username, secret).await).unwrap()
 }
 
+pub async fn jmap_method_request(
+    method: &str,
+    arguments: serde_json::Value,
+    username: &str,
+    secret: &str,
+) -> serde_json::Value {
+    let response = jmap_json_request(
+        serde_json::json!([[method, arguments, "0"]]).to_string(),
+        username,
+        secret,
+    )
+    .await;
+    assert_eq!(
+        response.pointer("/methodResponses/0/0"),
+        Some(&serde_json::Value::from(method)),
+        "Response: {response:?}"
+    );
+    response.pointer("/methodResponses/0/1").unwrap().clone()
+}
+
 pub fn find_values(string: &str, name: &str) -> Vec<String> {
     let mut last_pos = 0;
     let mut values = Vec::new();
@@ -901,6 +927,12 @@ auto-expunge = "1s"
 [changes]
 max-history = "1"
 
+[calendar]
+max-events = 3
+
+[contacts]
+max-cards = 2
+
 [store."auth"]
 type = "sqlite"
 path = "{TMP}/auth.db"
@@ -13,8 +13,8 @@ use jmap_proto::types::{
 use store::{
     rand,
     write::{
-        AnyKey, BatchBuilder, BitmapClass, BitmapHash, BlobOp, DirectoryClass, InMemoryClass,
-        Operation, QueueClass, QueueEvent, TagValue, ValueClass,
+        AnyClass, AnyKey, BatchBuilder, BitmapClass, BitmapHash, BlobOp, DirectoryClass,
+        InMemoryClass, Operation, QueueClass, QueueEvent, TagValue, ValueClass,
     },
     *,
 };
@@ -101,7 +101,9 @@ pub async fn test(db: Store) {
                     );
                 }
 
-                batch.log_item_insert(SyncCollection::from(collection), None);
+                batch
+                    .log_item_insert(SyncCollection::from(collection), None)
+                    .log_item_shard_insert(SyncCollection::from(collection), document_id % 20);
 
                 /*batch.any_op(Operation::ChangeId {
                     change_id: document_id as u64 + account_id as u64 + collection as u64,
@@ -149,6 +151,22 @@ pub async fn test(db: Store) {
             }
         }
 
+        // Create shard and sync epochs
+        for collection in [0u8, 1, 2, 3] {
+            for key in [
+                LogShardKey::epoch(account_id, collection),
+                LogShardKey::sync_epoch(account_id, collection),
+            ] {
+                batch.set(
+                    ValueClass::Any(AnyClass {
+                        subspace: SUBSPACE_LOGS,
+                        key: key.serialize(0),
+                    }),
+                    rand::random::<u64>().serialize(),
+                );
+            }
+        }
+
         db.write(batch.build_all()).await.unwrap();
     }
 
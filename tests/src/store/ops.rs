@@ -7,11 +7,12 @@
 use std::collections::HashSet;
 
 use ahash::AHashSet;
-use jmap_proto::types::collection::SyncCollection;
+use jmap_proto::types::collection::{Collection, SyncCollection};
 use store::{
-    Store, ValueKey,
+    Key, LogKey, LogShardKey, SUBSPACE_LOGS, SerializeInfallible, Store, ValueKey,
+    query::log::{Change, Query},
     rand::{self, Rng},
-    write::{AlignedBytes, Archive, Archiver, BatchBuilder, DirectoryClass, ValueClass},
+    write::{AlignedBytes, AnyClass, Archive, Archiver, BatchBuilder, DirectoryClass, ValueClass},
 };
 
 // FDB max value
@@ -202,6 +203,127 @@ pub async fn test(db: Store) {
     }
     assert_eq!(change_ids, assigned_ids);
 
+    println!("Running per-container change log tests...");
+    let account_id = 1;
+    let collection = SyncCollection::Calendar;
+    let mut change_ids = Vec::new();
+    for (document_id, shard_id) in [(0u32, 10u32), (1, 20), (2, 10), (3, 20)] {
+        let mut batch = BatchBuilder::new();
+        batch
+            .with_account_id(account_id)
+            .with_collection(Collection::CalendarEvent)
+            .update_document(document_id)
+            .log_item_insert(collection, None)
+            .log_item_shard_insert(collection, shard_id);
+        change_ids.push(
+            db.write(batch.build_all())
+                .await
+                .unwrap()
+                .last_change_id(account_id)
+                .unwrap(),
+        );
+    }
+    for (shard_id, query, expected) in [
+        (
+            10,
+            Query::All,
+            vec![Change::InsertItem(0), Change::InsertItem(2)],
+        ),
+        (
+            20,
+            Query::All,
+            vec![Change::InsertItem(1), Change::InsertItem(3)],
+        ),
+        (10, Query::Since(change_ids[0]), vec![Change::InsertItem(2)]),
+        (20, Query::Since(change_ids[3]), vec![]),
+        (30, Query::All, vec![]),
+    ] {
+        assert_eq!(
+            db.shard_changes(account_id, collection, shard_id, query)
+                .await
+                .unwrap()
+                .changes,
+            expected,
+            "shard {shard_id} query {query:?}"
+        );
+    }
+    assert_eq!(
+        db.changes(account_id, collection, Query::All)
+            .await
+            .unwrap()
+            .changes
+            .len(),
+        4
+    );
+
+    // Truncate the per-container logs, the shard epoch moves forward
+    db.write(
+        BatchBuilder::new()
+            .with_account_id(account_id)
+            .set(
+                ValueClass::Any(AnyClass {
+                    subspace: SUBSPACE_LOGS,
+                    key: LogShardKey::epoch(account_id, collection.into()).serialize(0),
+                }),
+                change_ids[0].serialize(),
+            )
+            .build_all(),
+    )
+    .await
+    .unwrap();
+    db.delete_shard_changes(account_id, collection, change_ids[2])
+        .await
+        .unwrap();
+    for (shard_id, expected) in [
+        (10, vec![Change::InsertItem(2)]),
+        (20, vec![Change::InsertItem(3)]),
+    ] {
+        assert_eq!(
+            db.shard_changes(account_id, collection, shard_id, Query::All)
+                .await
+                .unwrap()
+                .changes,
+            expected
+        );
+    }
+    assert_eq!(
+        db.get_shard_epoch(account_id, collection).await.unwrap(),
+        Some(change_ids[2])
+    );
+
+    // Truncating at an older change id keeps the epoch
+    db.delete_shard_changes(account_id, collection, change_ids[1])
+        .await
+        .unwrap();
+    assert_eq!(
+        db.get_shard_epoch(account_id, collection).await.unwrap(),
+        Some(change_ids[2])
+    );
+
+    // Remove the change log
+    db.delete_range(
+        LogKey {
+            account_id,
+            collection: 0,
+            change_id: 0,
+        },
+        LogKey {
+            account_id,
+            collection: u8::MAX,
+            change_id: u64::MAX,
+        },
+    )
+    .await
+    .unwrap();
+    db.write(
+        BatchBuilder::new()
+            .with_account_id(account_id)
+            .clear(ValueClass::ChangeId)
+            .build_all(),
+    )
+    .await
+    .unwrap();
+
     println!("Running chunking tests...");
     for (test_num, value) in [
         vec![b'A'; 0],
@@ -143,6 +143,33 @@ pub async fn test(test: &WebDavTest) {
             .properties(&sharee_principal)
             .get(DavProperty::WebDav(WebDavProperty::DisplayName))
             .with_values(["John Doe"]);
+        owner_client
+            .request_with_headers(
+                "REPORT",
+                &owner_folder,
+                [("depth", "1")],
+                ACL_PRINCIPAL_QUERY,
+            )
+            .await
+            .with_status(StatusCode::BAD_REQUEST);
+        sharee_client
+            .request("REPORT", &owner_folder, ACL_PRINCIPAL_QUERY)
+            .await
+            .with_status(StatusCode::FORBIDDEN);
+
+        // principal-match REPORT only returns members owned by the current user
+        owner_client
+            .request("REPORT", &owner_folder, OWNER_MATCH_QUERY)
+            .await
+            .with_status(StatusCode::MULTI_STATUS)
+            .with_hrefs([owner_folder.as_str(), owner_file.as_str()]);
+        for query in [OWNER_MATCH_QUERY, SELF_MATCH_QUERY] {
+            sharee_client
+                .request("REPORT", &owner_folder, query)
+                .await
+                .with_status(StatusCode::MULTI_STATUS)
+                .with_href_count(0);
+        }
 
         // Test 7: Verify current-user-privilege-set and owner
         let response = sharee_client
@@ -243,17 +270,26 @@ pub async fn test(test: &WebDavTest) {
                 [DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet)],
             )
             .await;
-        for href in [owner_folder.as_str(), owner_file.as_str()] {
-            response
-                .properties(href)
-                .get(DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet))
-                .with_values([
-                    "D:privilege.D:read",
-                    "D:privilege.D:read-current-user-privilege-set",
-                    "D:privilege.D:write-content",
-                    "D:privilege.D:write-properties",
-                ]);
-        }
+        response
+            .properties(&owner_folder)
+            .get(DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet))
+            .with_values([
+                "D:privilege.D:read",
+                "D:privilege.D:read-current-user-privilege-set",
+                "D:privilege.D:write-content",
+                "D:privilege.D:write-properties",
+            ]);
+        response
+            .properties(&owner_file)
+            .get(DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet))
+            .with_values([
+                "D:privilege.D:read",
+                "D:privilege.D:read-current-user-privilege-set",
+                "D:privilege.D:write",
+                "D:privilege.D:write-content",
+                "D:privilege.D:write-properties",
+                "D:privilege.D:unlock",
+            ]);
 
         // Test 10: Delete operations should fail
         for (path, dest) in [(&owner_folder, &sharee_folder), (&owner_file, &sharee_file)] {
@@ -291,6 +327,34 @@ pub async fn test(test: &WebDavTest) {
                 .await
                 .with_status(StatusCode::OK);
         }
+        let response = sharee_client
+            .propfind(
+                &owner_folder,
+                [DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet)],
+            )
+            .await;
+        response
+            .properties(&owner_folder)
+            .get(DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet))
+            .with_values([
+                "D:privilege.D:read",
+                "D:privilege.D:read-current-user-privilege-set",
+                "D:privilege.D:write",
+                "D:privilege.D:write-properties",
+                "D:privilege.D:write-content",
+                "D:privilege.D:bind",
+                "D:privilege.D:unbind",
+            ]);
+        if !is_file {
+            sharee_client
+                .request(
+                    "PUT",
+                    &format!("{owner_folder}test-file-new"),
+                    resource_type.generate(),
+                )
+                .await
+                .with_status(StatusCode::CREATED);
+        }
         sharee_client
             .request_with_headers(
                 "MOVE",
@@ -396,3 +460,15 @@ const ACL_PRINCIPAL_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
        <D:displayname/>
      </D:prop>
    </D:acl-principal-prop-set>"#;
+
+const OWNER_MATCH_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+   <D:principal-match xmlns:D="DAV:">
+     <D:principal-property>
+       <D:owner/>
+     </D:principal-property>
+   </D:principal-match>"#;
+
+const SELF_MATCH_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+   <D:principal-match xmlns:D="DAV:">
+     <D:self/>
+   </D:principal-match>"#;
@@ -0,0 +1,137 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use dav_proto::schema::property::{CalDavProperty, DavProperty};
+use hyper::StatusCode;
+
+use super::WebDavTest;
+
+pub async fn test(test: &WebDavTest) {
+    println!("Running default alarm tests...");
+    let client = test.client("john");
+    let calendar_href = "/dav/cal/john/default/";
+    let event_href = "/dav/cal/john/default/timed.ics";
+    let all_day_href = "/dav/cal/john/default/all-day.ics";
+    let alarm_date = DavProperty::CalDav(CalDavProperty::DefaultAlarmVEventDate);
+    let alarm_date_time = DavProperty::CalDav(CalDavProperty::DefaultAlarmVEventDateTime);
+
+    for (href, ical) in [
+        (event_href, TEST_ICAL_TIMED),
+        (all_day_href, TEST_ICAL_ALL_DAY),
+    ] {
+        client
+            .request("PUT", href, ical)
+            .await
+            .with_status(StatusCode::CREATED);
+    }
+
+    // Only VALARM components are accepted as default alarms
+    client
+        .proppatch(
+            calendar_href,
+            [(alarm_date_time.clone(), "BEGIN:VEVENT\nEND:VEVENT")],
+            [],
+            [],
+        )
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .into_propfind_response(None)
+        .properties(calendar_href)
+        .get(&alarm_date_time)
+        .with_status(StatusCode::PRECONDITION_FAILED);
+    client
+        .proppatch(
+            calendar_href,
+            [(alarm_date_time.clone(), TEST_VALARM)],
+            [],
+            [],
+        )
+        .await
+        .with_status(StatusCode::MULTI_STATUS);
+    let response = client
+        .propfind(calendar_href, [&alarm_date, &alarm_date_time])
+        .await;
+    let properties = response.properties(calendar_href);
+    properties
+        .get(&alarm_date)
+        .with_status(StatusCode::NOT_FOUND);
+    let alarm = properties.get(&alarm_date_time).value();
+    assert!(alarm.starts_with("BEGIN:VALARM"), "{alarm}");
+    assert!(alarm.contains("TRIGGER:-PT15M"), "{alarm}");
+
+    // Default alarms are only added when the client prefers so
+    let event = client
+        .request("GET", event_href, "")
+        .await
+        .with_status(StatusCode::OK);
+    assert!(!event.body.as_ref().unwrap().contains("BEGIN:VALARM"));
+    let event = client
+        .request_with_headers("GET", event_href, [("prefer", "default-alarms")], "")
+        .await
+        .with_status(StatusCode::OK);
+    let body = event.body.as_ref().unwrap();
+    assert!(body.contains("BEGIN:VALARM"), "{body}");
+    assert!(body.contains("TRIGGER:-PT15M"), "{body}");
+
+    // All-day events use their own default alarm
+    let event = client
+        .request_with_headers("GET", all_day_href, [("prefer", "default-alarms")], "")
+        .await
+        .with_status(StatusCode::OK);
+    assert!(!event.body.as_ref().unwrap().contains("BEGIN:VALARM"));
+
+    // Removing the property removes the default alarm
+    client
+        .proppatch(calendar_href, [], [alarm_date_time.clone()], [])
+        .await
+        .with_status(StatusCode::MULTI_STATUS);
+    client
+        .propfind(calendar_href, [&alarm_date_time])
+        .await
+        .properties(calendar_href)
+        .get(&alarm_date_time)
+        .with_status(StatusCode::NOT_FOUND);
+    let event = client
+        .request_with_headers("GET", event_href, [("prefer", "default-alarms")], "")
+        .await
+        .with_status(StatusCode::OK);
+    assert!(!event.body.as_ref().unwrap().contains("BEGIN:VALARM"));
+
+    client.delete_default_containers().await;
+    test.assert_is_empty().await;
+}
+
+const TEST_VALARM: &str = "BEGIN:VALARM
+ACTION:DISPLAY
+DESCRIPTION:Reminder
+TRIGGER:-PT15M
+END:VALARM";
+
+const TEST_ICAL_TIMED: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Example Corp.//CalDAV Client//EN
+BEGIN:VEVENT
+UID:1f9f3e0c-8a2b-4c4d-9e6f-0a1b2c3d4e5f
+DTSTAMP:20060206T001102Z
+DTSTART:20060104T140000Z
+DURATION:PT1H
+SUMMARY:Meeting
+END:VEVENT
+END:VCALENDAR
+"#;
+
+const TEST_ICAL_ALL_DAY: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Example Corp.//CalDAV Client//EN
+BEGIN:VEVENT
+UID:6a7b8c9d-0e1f-4a2b-8c3d-4e5f6a7b8c9d
+DTSTAMP:20060206T001102Z
+DTSTART;VALUE=DATE:20060105
+DURATION:P1D
+SUMMARY:Holiday
+END:VEVENT
+END:VCALENDAR
+"#;
@@ -5,6 +5,9 @@
  */
 
 use super::WebDavTest;
+use flate2::{Compression, write::GzEncoder};
+use hyper::StatusCode;
+use std::io::Write;
 
 pub async fn test(test: &WebDavTest) {
     println!("Running basic tests...");
@@ -43,6 +46,53 @@ pub async fn test(test: &WebDavTest) {
             ["/dav/cal/", "/dav/cal/jane/", "/dav/cal/support/"],
         );
 
+    // Deeply nested request bodies and bodies with too many elements are
+    // rejected while they are read, well below the request size limit
+    let groupware = &test.server.core.groupware;
+    let depth = groupware.max_request_depth + 1;
+    let body = format!(
+        concat!(
+            "<D:propertyupdate xmlns:D=\"DAV:\" xmlns:X=\"urn:x\">",
+            "<D:set><D:prop><X:value>{}{}</X:value></D:prop></D:set>",
+            "</D:propertyupdate>"
+        ),
+        "<X:a>".repeat(depth),
+        "</X:a>".repeat(depth)
+    );
+    assert!(body.len() < groupware.max_request_size);
+    john.request("PROPPATCH", "/dav/file/john/", &body)
+        .await
+        .with_status(StatusCode::BAD_REQUEST);
+    let body = format!(
+        "<D:propfind xmlns:D=\"DAV:\"><D:prop>{}</D:prop></D:propfind>",
+        "<D:getetag/>".repeat(groupware.max_request_elements + 1)
+    );
+    assert!(body.len() < groupware.max_request_size);
+    john.request("PROPFIND", "/dav/file/john/", &body)
+        .await
+        .with_status(StatusCode::BAD_REQUEST);
+
+    // Compressed bodies are checked by the tokenizer once decoded
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body.as_bytes()).unwrap();
+    john.request_with_body(
+        "PROPFIND",
+        "/dav/file/john/",
+        [("content-encoding", "gzip")],
+        Some(encoder.finish().unwrap().into()),
+    )
+    .await
+    .with_status(StatusCode::BAD_REQUEST);
+
+    // Bodies exceeding the request size limit are rejected
+    let body = format!(
+        "<D:propfind xmlns:D=\"DAV:\"><D:prop>{}</D:prop></D:propfind>",
+        " ".repeat(groupware.max_request_size)
+    );
+    john.request("PROPFIND", "/dav/file/john/", &body)
+        .await
+        .with_status(StatusCode::PAYLOAD_TOO_LARGE);
+
     john.delete_default_containers().await;
     jane.delete_default_containers().await;
     jane.delete_default_containers_by_account("support").await;
@@ -0,0 +1,185 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use dav_proto::schema::property::{DavProperty, WebDavProperty};
+use groupware::calendar::birthdays::CalendarBirthdays;
+use hyper::StatusCode;
+
+use super::WebDavTest;
+
+pub async fn test(test: &WebDavTest) {
+    println!("Running birthday calendar tests...");
+    let client = test.client("john");
+    let card_href = "/dav/card/john/default/birthday.vcf";
+    let calendar_href = "/dav/cal/john/birthdays/";
+
+    // No calendar is created until a contact has a date
+    test.server
+        .sync_birthday_calendar(client.account_id, "birthdays")
+        .await
+        .unwrap();
+    client
+        .request("PROPFIND", calendar_href, "")
+        .await
+        .with_status(StatusCode::NOT_FOUND);
+
+    // Contacts with a birthday and an anniversary generate two yearly events
+    client
+        .request("PUT", card_href, TEST_VCARD_DATES)
+        .await
+        .with_status(StatusCode::CREATED);
+    test.server
+        .sync_birthday_calendar(client.account_id, "birthdays")
+        .await
+        .unwrap();
+    let response = client
+        .request_with_headers("PROPFIND", calendar_href, [("depth", "1")], "")
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_href_count(3);
+    let birthday_href = response
+        .hrefs()
+        .into_iter()
+        .find(|href| href.contains("/birthday-"))
+        .unwrap()
+        .to_string();
+    let event = client
+        .request("GET", &birthday_href, "")
+        .await
+        .with_status(StatusCode::OK);
+    let body = event.body.as_ref().unwrap();
+    assert!(body.contains("DTSTART;VALUE=DATE:19850415"), "{body}");
+    assert!(body.contains("RRULE:FREQ=YEARLY"), "{body}");
+    assert!(body.contains("SUMMARY:Birthday: Jane Doe"), "{body}");
+
+    // The calendar is read-only
+    client
+        .request("PUT", &format!("{calendar_href}new.ics"), TEST_ICAL)
+        .await
+        .with_status(StatusCode::FORBIDDEN);
+    client
+        .request("PUT", &birthday_href, TEST_ICAL)
+        .await
+        .with_status(StatusCode::FORBIDDEN);
+    client
+        .request("DELETE", &birthday_href, "")
+        .await
+        .with_status(StatusCode::FORBIDDEN);
+    client
+        .request("DELETE", calendar_href, "")
+        .await
+        .with_status(StatusCode::FORBIDDEN);
+
+    let response = client
+        .propfind_with_headers(
+            calendar_href,
+            [DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet)],
+            [("depth", "1")],
+        )
+        .await;
+    response
+        .properties(calendar_href)
+        .get(DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet))
+        .with_values([
+            "D:privilege.D:read",
+            "D:privilege.D:write-properties",
+            "D:privilege.D:read-acl",
+            "D:privilege.D:read-current-user-privilege-set",
+            "D:privilege.D:write-acl",
+            "D:privilege.A:read-free-busy",
+        ]);
+    response
+        .properties(&birthday_href)
+        .get(DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet))
+        .with_values([
+            "D:privilege.D:read",
+            "D:privilege.D:read-current-user-privilege-set",
+            "D:privilege.D:write-properties",
+        ]);
+
+    // Depth:0 requests served from the cache report the same privileges
+    client
+        .propfind_with_headers(
+            calendar_href,
+            [DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet)],
+            [("depth", "0")],
+        )
+        .await
+        .properties(calendar_href)
+        .get(DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet))
+        .with_values([
+            "D:privilege.D:read",
+            "D:privilege.D:write-properties",
+            "D:privilege.D:read-acl",
+            "D:privilege.D:read-current-user-privilege-set",
+            "D:privilege.D:write-acl",
+            "D:privilege.A:read-free-busy",
+        ]);
+
+    // Removing a date from the contact removes its event
+    client
+        .request("PUT", card_href, TEST_VCARD_BIRTHDAY)
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    test.server
+        .sync_birthday_calendar(client.account_id, "birthdays")
+        .await
+        .unwrap();
+    client
+        .request_with_headers("PROPFIND", calendar_href, [("depth", "1")], "")
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_hrefs([calendar_href, birthday_href.as_str()]);
+
+    // The calendar is removed once no contact has a date left
+    client
+        .request("DELETE", card_href, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    test.server
+        .sync_birthday_calendar(client.account_id, "birthdays")
+        .await
+        .unwrap();
+    client
+        .request("PROPFIND", calendar_href, "")
+        .await
+        .with_status(StatusCode::NOT_FOUND);
+
+    client.delete_default_containers().await;
+    test.assert_is_empty().await;
+}
+
+const TEST_VCARD_DATES: &str = r#"BEGIN:VCARD
+VERSION:4.0
+UID:urn:uuid:7f3c4a0e-2f6a-4d3b-9c1e-5a8b6d2e4f10
+FN:Jane Doe
+N:Doe;Jane;;;
+BDAY:19850415
+ANNIVERSARY:20100610
+END:VCARD
+"#;
+
+const TEST_VCARD_BIRTHDAY: &str = r#"BEGIN:VCARD
+VERSION:4.0
+UID:urn:uuid:7f3c4a0e-2f6a-4d3b-9c1e-5a8b6d2e4f10
+FN:Jane Doe
+N:Doe;Jane;;;
+BDAY:19850415
+END:VCARD
+"#;
+
+const TEST_ICAL: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Example Corp.//CalDAV Client//EN
+BEGIN:VEVENT
+UID:a6d4c8f2-1b3e-4f5a-9c7d-2e8b0f1a3c5d
+DTSTAMP:20060206T001102Z
+DTSTART:20060104T140000Z
+DURATION:PT1H
+SUMMARY:Event
+END:VEVENT
+END:VCALENDAR
+"#;
@@ -0,0 +1,150 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{DummyWebDavClient, WebDavTest};
+use common::ScheduleCollection;
+use dav_proto::schema::property::{DavProperty, WebDavProperty};
+use groupware::{
+    DavResourceName, DestroyArchive,
+    calendar::{Calendar, birthdays::CalendarBirthdays, scheduling::CalendarScheduling},
+};
+use hyper::StatusCode;
+use jmap_proto::types::collection::Collection;
+use store::write::BatchBuilder;
+
+const CACHED_PROPERTIES: [DavProperty; 5] = [
+    DavProperty::WebDav(WebDavProperty::ResourceType),
+    DavProperty::WebDav(WebDavProperty::DisplayName),
+    DavProperty::WebDav(WebDavProperty::GetCTag),
+    DavProperty::WebDav(WebDavProperty::SyncToken),
+    DavProperty::WebDav(WebDavProperty::CurrentUserPrivilegeSet),
+];
+
+pub async fn test(test: &WebDavTest) {
+    println!("Running cached PROPFIND tests...");
+    let owner_client = test.client("bill");
+    let sharee_client = test.client("john");
+    let account_id = owner_client.account_id;
+
+    // Regular calendars and address books
+    for resource_type in [DavResourceName::Cal, DavResourceName::Card] {
+        owner_client
+            .assert_cached_propfind(&format!("{}/bill/default/", resource_type.base_path()))
+            .await;
+    }
+
+    // Birthday calendar
+    let card_href = "/dav/card/bill/default/birthday.vcf";
+    let birthday_href = "/dav/cal/bill/birthdays/";
+    owner_client
+        .request("PUT", card_href, TEST_VCARD_BIRTHDAY)
+        .await
+        .with_status(StatusCode::CREATED);
+    test.server
+        .sync_birthday_calendar(account_id, "birthdays")
+        .await
+        .unwrap();
+    owner_client.assert_cached_propfind(birthday_href).await;
+    owner_client
+        .request("DELETE", card_href, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    test.server
+        .sync_birthday_calendar(account_id, "birthdays")
+        .await
+        .unwrap();
+
+    // Scheduling inbox and outbox
+    let access_token = test.server.get_access_token(account_id).await.unwrap();
+    let resources = test
+        .server
+        .schedule_resources(&access_token, account_id)
+        .await
+        .unwrap();
+    for collection in [ScheduleCollection::Inbox, ScheduleCollection::Outbox] {
+        let href = resources.format_resource(resources.schedule_collection(collection).unwrap());
+        owner_client.assert_cached_propfind(&href).await;
+    }
+    let mut batch = BatchBuilder::new();
+    for collection in [ScheduleCollection::Inbox, ScheduleCollection::Outbox] {
+        let document_id = resources
+            .schedule_collection(collection)
+            .unwrap()
+            .document_id();
+        let calendar = test
+            .server
+            .get_archive(account_id, Collection::Calendar, document_id)
+            .await
+            .unwrap()
+            .unwrap();
+        DestroyArchive(calendar.to_unarchived::<Calendar>().unwrap())
+            .delete(&access_token, account_id, document_id, None, &mut batch)
+            .unwrap();
+    }
+    test.server.commit_batch(batch).await.unwrap();
+
+    // Shared collections with restricted privileges
+    let sharee_principal = format!("{}/john/", DavResourceName::Principal.base_path());
+    for resource_type in [DavResourceName::Cal, DavResourceName::Card] {
+        let shared_href = format!("{}/bill/test-cached/", resource_type.base_path());
+        owner_client
+            .request("MKCOL", &shared_href, "")
+            .await
+            .with_status(StatusCode::CREATED);
+        owner_client
+            .acl(&shared_href, sharee_principal.as_str(), ["read"])
+            .await
+            .with_status(StatusCode::OK);
+        owner_client.assert_cached_propfind(&shared_href).await;
+        sharee_client.assert_cached_propfind(&shared_href).await;
+        owner_client
+            .request("DELETE", &shared_href, "")
+            .await
+            .with_status(StatusCode::NO_CONTENT);
+    }
+
+    owner_client.delete_default_containers().await;
+    test.assert_is_empty().await;
+}
+
+impl DummyWebDavClient {
+    // Depth:0 requests for these properties are answered from the cached
+    // hierarchy, the collection entry of a Depth:1 request from its archive.
+    pub async fn assert_cached_propfind(&self, href: &str) {
+        let cached = self
+            .propfind_with_headers(href, CACHED_PROPERTIES, [("depth", "0")])
+            .await;
+        let full = self
+            .propfind_with_headers(href, CACHED_PROPERTIES, [("depth", "1")])
+            .await;
+        let cached_props = cached.properties(href);
+        let full_props = full.properties(href);
+
+        for property in CACHED_PROPERTIES {
+            let cached_prop = cached_props.get(&property);
+            let full_prop = full_props.get(&property);
+            let mut cached_values = cached_prop.values.to_vec();
+            let mut full_values = full_prop.values.to_vec();
+            cached_values.sort_unstable();
+            full_values.sort_unstable();
+
+            if (cached_prop.prop.status, cached_values) != (full_prop.prop.status, full_values) {
+                cached.response.dump_response();
+                full.response.dump_response();
+                panic!("Cached and full PROPFIND differ for {href} property {property:?}");
+            }
+        }
+    }
+}
+
+const TEST_VCARD_BIRTHDAY: &str = r#"BEGIN:VCARD
+VERSION:4.0
+UID:urn:uuid:0b6c1f2e-8d3a-4e5f-9a7b-1c2d3e4f5a6b
+FN:Jane Doe
+N:Doe;Jane;;;
+BDAY:19850415
+END:VCARD
+"#;
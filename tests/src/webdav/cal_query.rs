@@ -113,6 +113,36 @@ pub async fn test(test: &WebDavTest) {
         .calendar_data()
         .with_values([ICAL_RFC_ABCD5_ICS.replace('\n', "\r\n").as_str()]);
 
+    // Alarms are only returned when selected
+    for (calendar_data, expected) in [
+        (
+            REPORT_5_NO_ALARMS,
+            ICAL_RFC_ABCD5_ICS.replace(
+                "BEGIN:VALARM\nACTION:AUDIO\nTRIGGER;RELATED=START:-PT10M\nEND:VALARM\n",
+                "",
+            ),
+        ),
+        (
+            REPORT_5_ALARM_TRIGGERS,
+            ICAL_RFC_ABCD5_ICS.replace("ACTION:AUDIO\n", ""),
+        ),
+    ] {
+        let response = client
+            .request(
+                "REPORT",
+                &cal_path,
+                &REPORT_5.replace("<C:calendar-data/>", calendar_data),
+            )
+            .await
+            .with_status(StatusCode::MULTI_STATUS)
+            .with_hrefs([rfc_file_name(5).as_str()])
+            .into_propfind_response(None);
+        response
+            .properties(&rfc_file_name(5))
+            .calendar_data()
+            .with_values([expected.replace('\n', "\r\n").as_str()]);
+    }
+
     // Test 6: Retrieval of Event by UID
     client
         .request("REPORT", &cal_path, REPORT_6)
@@ -175,6 +205,105 @@ pub async fn test(test: &WebDavTest) {
         remove_dtstamp(REPORT_11_RESPONSE)
     );
 
+    // Free/busy queries on the calendar home aggregate all calendars
+    let home_path = format!("{}/john/", DavResourceName::Cal.base_path());
+    assert_eq!(
+        remove_dtstamp(
+            client
+                .request("REPORT", &home_path, REPORT_10)
+                .await
+                .with_status(StatusCode::OK)
+                .body
+                .as_ref()
+                .unwrap()
+        ),
+        remove_dtstamp(REPORT_10_RESPONSE)
+    );
+    assert_eq!(
+        remove_dtstamp(
+            client
+                .request(
+                    "GET",
+                    &format!("{home_path}?start=20060104T140000Z&end=20060105T220000Z"),
+                    ""
+                )
+                .await
+                .with_status(StatusCode::OK)
+                .body
+                .as_ref()
+                .unwrap()
+        ),
+        remove_dtstamp(REPORT_10_RESPONSE)
+    );
+    client
+        .request("GET", &format!("{home_path}?start=invalid"), "")
+        .await
+        .with_status(StatusCode::BAD_REQUEST);
+
+    // Transparent calendars do not contribute to the aggregated free/busy time
+    client
+        .request("PROPPATCH", &cal_path, PROPPATCH_TRANSPARENT)
+        .await
+        .with_status(StatusCode::MULTI_STATUS);
+    assert_eq!(
+        remove_dtstamp(
+            client
+                .request("REPORT", &home_path, REPORT_10)
+                .await
+                .with_status(StatusCode::OK)
+                .body
+                .as_ref()
+                .unwrap()
+        ),
+        remove_dtstamp(REPORT_10_TRANSPARENT_RESPONSE)
+    );
+    assert_eq!(
+        remove_dtstamp(
+            client
+                .request("REPORT", &cal_path, REPORT_10)
+                .await
+                .with_status(StatusCode::OK)
+                .body
+                .as_ref()
+                .unwrap()
+        ),
+        remove_dtstamp(REPORT_10_RESPONSE)
+    );
+
+    // Time outside the available periods of a VAVAILABILITY is busy
+    client
+        .request("PROPPATCH", &cal_path, PROPPATCH_AVAILABILITY)
+        .await
+        .with_status(StatusCode::MULTI_STATUS);
+    assert_eq!(
+        remove_dtstamp(
+            client
+                .request("REPORT", &home_path, REPORT_10)
+                .await
+                .with_status(StatusCode::OK)
+                .body
+                .as_ref()
+                .unwrap()
+        ),
+        remove_dtstamp(REPORT_10_AVAILABILITY_RESPONSE)
+    );
+    client
+        .request("PROPPATCH", &cal_path, PROPPATCH_REMOVE_AVAILABILITY)
+        .await
+        .with_status(StatusCode::MULTI_STATUS);
+    assert_eq!(
+        remove_dtstamp(
+            client
+                .request("REPORT", &home_path, REPORT_10)
+                .await
+                .with_status(StatusCode::OK)
+                .body
+                .as_ref()
+                .unwrap()
+        ),
+        remove_dtstamp(REPORT_10_TRANSPARENT_RESPONSE)
+    );
+
     client.delete_default_containers().await;
     test.assert_is_empty().await;
 }
@@ -640,6 +769,27 @@ const REPORT_5: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
    </C:calendar-query>
 "#;
 
+const REPORT_5_NO_ALARMS: &str = r#"<C:calendar-data>
+         <C:comp name="VCALENDAR">
+           <C:allprop/>
+           <C:comp name="VTODO">
+             <C:allprop/>
+           </C:comp>
+         </C:comp>
+       </C:calendar-data>"#;
+
+const REPORT_5_ALARM_TRIGGERS: &str = r#"<C:calendar-data>
+         <C:comp name="VCALENDAR">
+           <C:allprop/>
+           <C:comp name="VTODO">
+             <C:allprop/>
+             <C:comp name="VALARM">
+               <C:prop name="TRIGGER"/>
+             </C:comp>
+           </C:comp>
+         </C:comp>
+       </C:calendar-data>"#;
+
 const REPORT_6: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
    <C:calendar-query xmlns:C="urn:ietf:params:xml:ns:caldav">
      <D:prop xmlns:D="DAV:">
@@ -738,6 +888,73 @@ END:VFREEBUSY
 END:VCALENDAR
 "#;
 
+const REPORT_10_TRANSPARENT_RESPONSE: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Stalwart Labs Ltd.//Stalwart Server//EN
+BEGIN:VFREEBUSY
+DTSTART:20060104T140000Z
+DTEND:20060105T220000Z
+END:VFREEBUSY
+END:VCALENDAR
+"#;
+
+const PROPPATCH_TRANSPARENT: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+   <D:propertyupdate xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+     <D:set>
+       <D:prop>
+         <C:schedule-calendar-transp><C:transparent/></C:schedule-calendar-transp>
+       </D:prop>
+     </D:set>
+   </D:propertyupdate>
+"#;
+
+const REPORT_10_AVAILABILITY_RESPONSE: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Stalwart Labs Ltd.//Stalwart Server//EN
+BEGIN:VFREEBUSY
+DTSTART:20060104T140000Z
+DTEND:20060105T220000Z
+FREEBUSY;FBTYPE=BUSY-UNAVAILABLE:20060104T140000Z/20060104T160000Z;20060105T090000Z/20060105T220000Z
+END:VFREEBUSY
+END:VCALENDAR
+"#;
+
+const PROPPATCH_AVAILABILITY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+   <D:propertyupdate xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+     <D:set>
+       <D:prop>
+         <C:calendar-availability><![CDATA[BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Example Corp.//CalDAV Client//EN
+BEGIN:VAVAILABILITY
+UID:availability-1@example.com
+DTSTAMP:20060101T000000Z
+DTSTART:20060104T000000Z
+DTEND:20060106T000000Z
+BEGIN:AVAILABLE
+UID:available-1@example.com
+DTSTAMP:20060101T000000Z
+DTSTART:20060104T160000Z
+DTEND:20060105T090000Z
+END:AVAILABLE
+END:VAVAILABILITY
+END:VCALENDAR
+]]></C:calendar-availability>
+       </D:prop>
+     </D:set>
+   </D:propertyupdate>
+"#;
+
+const PROPPATCH_REMOVE_AVAILABILITY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+   <D:propertyupdate xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+     <D:remove>
+       <D:prop>
+         <C:calendar-availability/>
+       </D:prop>
+     </D:remove>
+   </D:propertyupdate>
+"#;
+
 const REPORT_11: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
    <C:free-busy-query xmlns:C="urn:ietf:params:xml:ns:caldav">
      <C:time-range start="20060101T000000Z"
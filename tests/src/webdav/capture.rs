@@ -0,0 +1,238 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{DavResponse, DummyWebDavClient, WebDavTest};
+use directory::{
+    Permission, Type,
+    backend::internal::{PrincipalField, PrincipalSet, PrincipalValue, manage::ManageDirectory},
+};
+use hyper::StatusCode;
+use serde_json::Value;
+use std::time::Duration;
+
+pub async fn test(test: &WebDavTest) {
+    println!("Running DAV request capture tests...");
+    let admin = test.client("admin");
+    let john = test.client("john");
+
+    // Credentials are redacted from captured headers and bodies
+    admin
+        .capture("POST", "john?duration=60")
+        .await
+        .with_status(StatusCode::OK);
+    john.request_with_headers(
+        "PROPPATCH",
+        "/dav/file/john/",
+        [("cookie", "session=abc"), ("x-auth-token", "abc")],
+        PROPPATCH_PASSWORD,
+    )
+    .await;
+    let requests = admin.captured_requests("john").await;
+    assert_eq!(requests.len(), 1, "{requests:?}");
+    let request = &requests[0];
+    assert_eq!(request["method"], "PROPPATCH");
+    for (name, value) in [
+        ("authorization", "[redacted]"),
+        ("cookie", "[redacted]"),
+        ("x-auth-token", "[redacted]"),
+        (
+            "content-length",
+            PROPPATCH_PASSWORD.len().to_string().as_str(),
+        ),
+    ] {
+        assert_eq!(captured_header(request, name), Some(value), "{request}");
+    }
+    let body = request["requestBody"].as_str().unwrap();
+    assert!(
+        body.contains("<X:password>[redacted]</X:password>") && !body.contains("hunter2"),
+        "{body}"
+    );
+
+    // Bodies are truncated to the requested size
+    admin
+        .capture("POST", "john?duration=60&max-body-size=16")
+        .await
+        .with_status(StatusCode::OK);
+    john.request_with_headers(
+        "PROPFIND",
+        "/dav/file/john/",
+        [("depth", "0")],
+        PROPFIND_BODY,
+    )
+    .await
+    .with_status(StatusCode::MULTI_STATUS);
+    let requests = admin.captured_requests("john").await;
+    assert_eq!(requests.len(), 1, "{requests:?}");
+    assert_eq!(
+        requests[0]["requestBody"].as_str().unwrap(),
+        format!(
+            "{}\n[truncated, {} bytes total]",
+            &PROPFIND_BODY[..16],
+            PROPFIND_BODY.len()
+        )
+    );
+    assert!(
+        requests[0]["responseBody"]
+            .as_str()
+            .unwrap()
+            .contains("[truncated, "),
+        "{requests:?}"
+    );
+
+    // Expired captures stop recording but can still be fetched and stopped
+    admin
+        .capture("POST", "john?duration=1")
+        .await
+        .with_status(StatusCode::OK);
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    john.request_with_headers(
+        "PROPFIND",
+        "/dav/file/john/",
+        [("depth", "0")],
+        PROPFIND_BODY,
+    )
+    .await
+    .with_status(StatusCode::MULTI_STATUS);
+    let response = admin
+        .capture("GET", "john")
+        .await
+        .with_status(StatusCode::OK)
+        .json();
+    assert_eq!(response["data"]["expires"], Value::Null, "{response}");
+    assert_eq!(
+        response["data"]["requests"],
+        Value::Array(vec![]),
+        "{response}"
+    );
+    admin
+        .capture("DELETE", "john")
+        .await
+        .with_status(StatusCode::OK);
+    admin
+        .capture("GET", "john")
+        .await
+        .with_status(StatusCode::NOT_FOUND);
+
+    // Tenant administrators can only capture requests of their tenant members
+    let store = test.server.store();
+    for principal in [
+        PrincipalSet::new(u32::MAX, Type::Tenant)
+            .with_field(PrincipalField::Name, "capture")
+            .with_field(
+                PrincipalField::Roles,
+                vec!["tenant-admin".to_string(), "user".to_string()],
+            )
+            .with_field(
+                PrincipalField::EnabledPermissions,
+                vec![Permission::Troubleshoot.name().to_string()],
+            ),
+        PrincipalSet::new(u32::MAX, Type::Domain)
+            .with_field(PrincipalField::Name, "capture.org")
+            .with_field(PrincipalField::Tenant, "capture"),
+        PrincipalSet::new(u32::MAX, Type::Individual)
+            .with_field(PrincipalField::Name, "admin@capture.org")
+            .with_field(PrincipalField::Tenant, "capture")
+            .with_field(PrincipalField::Roles, vec!["user".to_string()])
+            .with_field(
+                PrincipalField::EnabledPermissions,
+                vec![Permission::Troubleshoot.name().to_string()],
+            )
+            .with_field(
+                PrincipalField::Secrets,
+                PrincipalValue::String("tenant-secret".to_string()),
+            ),
+        PrincipalSet::new(u32::MAX, Type::Individual)
+            .with_field(PrincipalField::Name, "user@capture.org")
+            .with_field(PrincipalField::Tenant, "capture")
+            .with_field(PrincipalField::Roles, vec!["user".to_string()]),
+    ] {
+        store.create_principal(principal, None, None).await.unwrap();
+    }
+    let tenant_admin_id = store
+        .get_principal_id("admin@capture.org")
+        .await
+        .unwrap()
+        .unwrap();
+    let tenant_admin = DummyWebDavClient::new(
+        tenant_admin_id,
+        "admin@capture.org",
+        "tenant-secret",
+        "admin@capture.org",
+    );
+
+    admin
+        .capture("POST", "john?duration=60")
+        .await
+        .with_status(StatusCode::OK);
+    for method in ["POST", "GET", "DELETE"] {
+        tenant_admin
+            .capture(method, "john")
+            .await
+            .with_status(StatusCode::FORBIDDEN);
+    }
+    admin
+        .capture("DELETE", "john")
+        .await
+        .with_status(StatusCode::OK);
+    for method in ["POST", "GET", "DELETE"] {
+        tenant_admin
+            .capture(method, "user@capture.org")
+            .await
+            .with_status(StatusCode::OK);
+    }
+}
+
+impl DummyWebDavClient {
+    async fn capture(&self, method: &str, query: &str) -> DavResponse {
+        self.request(method, &format!("/api/troubleshoot/dav/{query}"), "")
+            .await
+    }
+
+    async fn captured_requests(&self, account: &str) -> Vec<Value> {
+        match self
+            .capture("GET", account)
+            .await
+            .with_status(StatusCode::OK)
+            .json()
+            .get_mut("data")
+            .and_then(|data| data.get_mut("requests"))
+            .map(Value::take)
+        {
+            Some(Value::Array(requests)) => requests,
+            result => panic!("Unexpected capture response: {result:?}"),
+        }
+    }
+}
+
+impl DavResponse {
+    pub fn json(self) -> Value {
+        serde_json::from_str(self.body.as_ref().unwrap()).unwrap()
+    }
+}
+
+fn captured_header<'x>(request: &'x Value, name: &str) -> Option<&'x str> {
+    request["requestHeaders"]
+        .as_array()?
+        .iter()
+        .find(|header| header[0] == name)
+        .and_then(|header| header[1].as_str())
+}
+
+const PROPPATCH_PASSWORD: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propertyupdate xmlns:D="DAV:" xmlns:X="urn:example">
+  <D:set>
+    <D:prop>
+      <X:password>hunter2</X:password>
+    </D:prop>
+  </D:set>
+</D:propertyupdate>"#;
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propfind xmlns:D="DAV:">
+  <D:prop>
+    <D:displayname/>
+  </D:prop>
+</D:propfind>"#;
@@ -115,7 +115,7 @@ END:VCARD
         .with_values([VCARD3.replace('\n', "\r\n").as_str()]);
 
     // Test 4: Search using limit
-    client
+    let response = client
         .request("REPORT", &default_path, QUERY4)
         .await
         .with_status(StatusCode::MULTI_STATUS)
@@ -131,12 +131,199 @@ END:VCARD
             "D:multistatus.D:response.D:responsedescription",
             "The number of matches exceeds the limit of 2",
         )
-        .with_href_count(3);
+        .with_hrefs([default_path.as_str(), uri_sarah, uri_carlos]);
+
+    // Resume the search from the continuation token
+    let next_page = response
+        .header("link")
+        .strip_prefix('<')
+        .and_then(|link| link.strip_suffix(">; rel=\"next\""))
+        .unwrap()
+        .to_string();
+    assert!(next_page.starts_with(&format!("{default_path}?page=")));
+    client
+        .request("REPORT", &next_page, QUERY4)
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_hrefs([uri_acme]);
+    client
+        .request("REPORT", &format!("{default_path}?page=abc"), QUERY4)
+        .await
+        .with_status(StatusCode::BAD_REQUEST);
+
+    // Test 5: Search indexed properties requiring all filters to match
+    client
+        .request("REPORT", &default_path, QUERY5)
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_hrefs([uri_carlos]);
+
+    // Test 6: Queries are skipped when the CTag is sent in If-None-Match,
+    // entity tags in the If header are not used as validators
+    let ctag = client
+        .propfind(
+            &default_path,
+            [DavProperty::WebDav(WebDavProperty::GetCTag)],
+        )
+        .await
+        .properties(&default_path)
+        .get(DavProperty::WebDav(WebDavProperty::GetCTag))
+        .value()
+        .to_string();
+    client
+        .request_with_headers(
+            "REPORT",
+            &default_path,
+            [("if-none-match", ctag.as_str())],
+            QUERY5,
+        )
+        .await
+        .with_status(StatusCode::NOT_MODIFIED)
+        .with_header("etag", &ctag);
+    client
+        .request_with_headers(
+            "REPORT",
+            &default_path,
+            [("if-none-match", "\"0\"")],
+            QUERY5,
+        )
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_hrefs([uri_carlos]);
+    client
+        .request_with_headers(
+            "REPORT",
+            &default_path,
+            [("if", format!("(Not [{ctag}])").as_str())],
+            QUERY5,
+        )
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_hrefs([uri_carlos]);
+
+    // Changes to other address books do not invalidate the CTag, while readers
+    // without access to the collection always get the query results
+    let other_path = format!("{}/john/other-query/", DavResourceName::Card.base_path());
+    client
+        .request("MKCOL", &other_path, "")
+        .await
+        .with_status(StatusCode::CREATED);
+    client
+        .request("PUT", &format!("{other_path}contact.vcf"), VCARD_OTHER)
+        .await
+        .with_status(StatusCode::CREATED);
+    client
+        .acl(
+            &other_path,
+            &format!("{}/jane/", DavResourceName::Principal.base_path()),
+            ["read"],
+        )
+        .await
+        .with_status(StatusCode::OK);
+    client
+        .request_with_headers(
+            "REPORT",
+            &default_path,
+            [("if-none-match", ctag.as_str())],
+            QUERY5,
+        )
+        .await
+        .with_status(StatusCode::NOT_MODIFIED);
+    test.client("jane")
+        .request_with_headers(
+            "REPORT",
+            &default_path,
+            [("if-none-match", ctag.as_str())],
+            QUERY5,
+        )
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_href_count(0);
+    client
+        .request("DELETE", uri_sarah, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    client
+        .request("PUT", uri_sarah, VCARD1)
+        .await
+        .with_status(StatusCode::CREATED);
+    client
+        .request_with_headers(
+            "REPORT",
+            &default_path,
+            [("if-none-match", ctag.as_str())],
+            QUERY5,
+        )
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_hrefs([uri_carlos]);
+    client
+        .request("DELETE", &other_path, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+
+    // Test 7: Search the directory gateway
+    let directory_path = format!("{}/john/directory/", DavResourceName::Card.base_path());
+    let jane_href = format!("{directory_path}{}.vcf", test.client("jane").account_id);
+    client
+        .request_with_headers("PROPFIND", &directory_path, [("depth", "1")], "")
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_hrefs([directory_path.as_str()])
+        .into_propfind_response(None)
+        .properties(&directory_path)
+        .get(DavProperty::WebDav(WebDavProperty::ResourceType))
+        .with_values(["D:collection", "B:addressbook", "B:directory"])
+        .with_status(StatusCode::OK);
+    let response = client
+        .request("REPORT", &directory_path, QUERY_DIRECTORY)
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_hrefs([jane_href.as_str()])
+        .into_propfind_response(None);
+    response
+        .properties(&jane_href)
+        .get(DavProperty::CardDav(CardDavProperty::AddressData(
+            Default::default(),
+        )))
+        .with_values([format!(
+            "BEGIN:VCARD\r\nUID:urn:stalwart:directory:{}\r\nFN:Jane Doe-Smith\r\nEMAIL;PREF=1:jane.smith@example.com\r\nEND:VCARD\r\n",
+            test.client("jane").account_id
+        )
+        .as_str()]);
+    client
+        .request("GET", &jane_href, "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_header("content-type", "text/vcard; charset=utf-8");
+    client
+        .request("PUT", &jane_href, VCARD1)
+        .await
+        .with_status(StatusCode::FORBIDDEN);
 
     client.delete_default_containers().await;
     test.assert_is_empty().await;
 }
 
+const QUERY_DIRECTORY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+   <C:addressbook-query xmlns:D="DAV:"
+                     xmlns:C="urn:ietf:params:xml:ns:carddav">
+     <D:prop>
+       <C:address-data>
+         <C:prop name="FN"/>
+         <C:prop name="EMAIL"/>
+         <C:prop name="UID"/>
+       </C:address-data>
+     </D:prop>
+     <C:filter>
+       <C:prop-filter name="EMAIL">
+         <C:text-match collation="i;unicode-casemap"
+                       match-type="starts-with"
+         >jane.smith</C:text-match>
+       </C:prop-filter>
+     </C:filter>
+   </C:addressbook-query>"#;
+
 const QUERY1: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
    <C:addressbook-query xmlns:D="DAV:"
                      xmlns:C="urn:ietf:params:xml:ns:carddav">
@@ -223,6 +410,33 @@ const QUERY4: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
      </C:limit>
    </C:addressbook-query>"#;
 
+const QUERY5: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+   <C:addressbook-query xmlns:D="DAV:"
+                     xmlns:C="urn:ietf:params:xml:ns:carddav">
+     <D:prop>
+       <D:getetag/>
+     </D:prop>
+     <C:filter test="allof">
+       <C:prop-filter name="FN">
+         <C:text-match collation="i;unicode-casemap"
+                       match-type="contains"
+         >MARTINEZ</C:text-match>
+       </C:prop-filter>
+       <C:prop-filter name="TEL">
+         <C:text-match collation="i;unicode-casemap"
+                       match-type="starts-with"
+         >+34-911</C:text-match>
+       </C:prop-filter>
+     </C:filter>
+   </C:addressbook-query>"#;
+
+const VCARD_OTHER: &str = r#"BEGIN:VCARD
+VERSION:4.0
+FN:Other Contact
+UID:urn:uuid:7d6c3f5e-2a1b-4c8d-9e0f-1a2b3c4d5e6f
+END:VCARD
+"#;
+
 const VCARD1: &str = r#"BEGIN:VCARD
 VERSION:4.0
 FN:Sarah Johnson
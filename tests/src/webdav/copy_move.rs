@@ -7,9 +7,11 @@
 use super::{DavResponse, WebDavTest};
 use crate::webdav::GenerateTestDavResource;
 use ahash::AHashSet;
+use dav::file::copy_move::FAIL_MOVE_DELETE;
 use dav_proto::Depth;
 use groupware::DavResourceName;
 use hyper::StatusCode;
+use std::sync::atomic::Ordering;
 
 pub async fn test(test: &WebDavTest) {
     let client = test.client("jane");
@@ -94,13 +96,13 @@ pub async fn test(test: &WebDavTest) {
 
         // Copying and moving to the same or root containers is invalid
         for method in ["COPY", "MOVE"] {
-            for destination in [
-                "/dav",
-                "/dav/cal",
-                "/dav/card",
-                "/dav/file",
-                "/dav/pal",
-                hierarchy_root.as_str(),
+            for (destination, status) in [
+                ("/dav", StatusCode::BAD_REQUEST),
+                ("/dav/cal", StatusCode::CONFLICT),
+                ("/dav/card", StatusCode::CONFLICT),
+                ("/dav/file", StatusCode::CONFLICT),
+                ("/dav/pal", StatusCode::CONFLICT),
+                (hierarchy_root.as_str(), StatusCode::FORBIDDEN),
             ] {
                 client
                     .request_with_headers(
@@ -110,7 +112,7 @@ pub async fn test(test: &WebDavTest) {
                         "",
                     )
                     .await
-                    .with_status(StatusCode::BAD_GATEWAY);
+                    .with_status(status);
             }
         }
 
@@ -346,7 +348,7 @@ pub async fn test(test: &WebDavTest) {
                         "",
                     )
                     .await
-                    .with_status(StatusCode::BAD_GATEWAY);
+                    .with_status(StatusCode::FORBIDDEN);
                 client
                     .request_with_headers(
                         method,
@@ -355,7 +357,7 @@ pub async fn test(test: &WebDavTest) {
                         "",
                     )
                     .await
-                    .with_status(StatusCode::BAD_GATEWAY);
+                    .with_status(StatusCode::CONFLICT);
             }
         }
 
@@ -369,7 +371,7 @@ pub async fn test(test: &WebDavTest) {
                     "",
                 )
                 .await
-                .with_status(StatusCode::BAD_GATEWAY);
+                .with_status(StatusCode::FORBIDDEN);
         }
 
         // Test 9: Rename file
@@ -554,7 +556,7 @@ pub async fn test(test: &WebDavTest) {
                         "",
                     )
                     .await
-                    .with_status(StatusCode::BAD_GATEWAY);
+                    .with_status(StatusCode::CONFLICT);
             }
 
             // Test 16: Copy a container under a different container
@@ -608,6 +610,17 @@ pub async fn test(test: &WebDavTest) {
                         &format!("{folder2}uid_test"),
                     );
             }
+
+            // Overwriting the resource that holds the UID does not conflict
+            client
+                .request_with_headers(
+                    "COPY",
+                    &uid_file_src,
+                    [("destination", format!("{folder2}uid_test").as_str())],
+                    "",
+                )
+                .await
+                .with_status(StatusCode::NO_CONTENT);
         }
 
         // Delete all containers and create a new one
@@ -663,8 +676,7 @@ pub async fn test(test: &WebDavTest) {
                     "",
                 )
                 .await
-                .with_status(StatusCode::PRECONDITION_FAILED)
-                .with_empty_body();
+                .with_status(StatusCode::PRECONDITION_FAILED);
 
             client
                 .request_with_headers(
@@ -677,8 +689,7 @@ pub async fn test(test: &WebDavTest) {
                     "",
                 )
                 .await
-                .with_status(StatusCode::PRECONDITION_FAILED)
-                .with_empty_body();
+                .with_status(StatusCode::PRECONDITION_FAILED);
 
             let iff = format!(
                 "<{test_file1_path}> (Not [{test_etag_1}]) <{test_file2_path}> (Not [{test_etag_2}])",
@@ -694,8 +705,7 @@ pub async fn test(test: &WebDavTest) {
                     "",
                 )
                 .await
-                .with_status(StatusCode::PRECONDITION_FAILED)
-                .with_empty_body();
+                .with_status(StatusCode::PRECONDITION_FAILED);
         }
 
         // Test 18: Successful DAV preconditions
@@ -782,6 +792,31 @@ pub async fn test(test: &WebDavTest) {
         }
     }
 
+    // A failed MOVE across accounts restores the source tree and removes the copied nodes
+    println!("Running failed MOVE rollback tests...");
+    let (hierarchy_root, hierarchy) = client.create_hierarchy("/dav/file/jane", 2, 2, 3).await;
+    FAIL_MOVE_DELETE.store(true, Ordering::Relaxed);
+    client
+        .request_with_headers(
+            "MOVE",
+            &hierarchy_root,
+            [("destination", "/dav/file/support/Failed_Move/")],
+            "",
+        )
+        .await
+        .with_status(StatusCode::INTERNAL_SERVER_ERROR);
+    FAIL_MOVE_DELETE.store(false, Ordering::Relaxed);
+    test.clear_cache();
+    client.validate_values(&hierarchy).await;
+    client
+        .request("DELETE", "/dav/file/support/Failed_Move/", "")
+        .await
+        .with_status(StatusCode::NOT_FOUND);
+    client
+        .request("DELETE", &hierarchy_root, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+
     client.delete_default_containers().await;
     client.delete_default_containers_by_account("support").await;
     mike_noquota.delete_default_containers().await;
@@ -0,0 +1,88 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::{DavResponse, DummyWebDavClient, WebDavTest};
+use crate::directory::internal::TestInternalDirectory;
+use dav_proto::schema::property::{DavProperty, WebDavProperty};
+use directory::Permission;
+use groupware::DavResourceName;
+use hyper::StatusCode;
+
+const IMPERSONATE_HEADER: &str = "x-impersonate-user";
+
+pub async fn test(test: &WebDavTest) {
+    println!("Running impersonation tests...");
+    let admin = test.client("admin");
+    let john = test.client("john");
+
+    // Accounts without the Impersonate permission are rejected
+    john.impersonate("jane")
+        .await
+        .with_status(StatusCode::FORBIDDEN);
+
+    // Administrators can act on behalf of regular accounts
+    admin.assert_impersonates("john").await;
+    admin
+        .impersonate("unknown")
+        .await
+        .with_status(StatusCode::UNAUTHORIZED);
+
+    // Grant John the Impersonate permission and Jane a permission John lacks
+    for (account, permission) in [
+        ("john", Permission::Impersonate),
+        ("jane", Permission::GroupwareQuota),
+    ] {
+        test.server
+            .increment_token_revision(
+                test.server
+                    .store()
+                    .add_permissions(account, [permission])
+                    .await,
+            )
+            .await;
+    }
+
+    // Regular accounts can be impersonated by peers
+    john.assert_impersonates("bill").await;
+
+    // Administrators, impersonators and accounts that outrank the caller cannot be impersonated
+    for (client, account) in [(john, "admin"), (admin, "john"), (john, "jane")] {
+        client
+            .impersonate(account)
+            .await
+            .with_status(StatusCode::FORBIDDEN);
+    }
+}
+
+impl DummyWebDavClient {
+    async fn impersonate(&self, account: &str) -> DavResponse {
+        self.request_with_headers(
+            "PROPFIND",
+            DavResourceName::File.collection_path(),
+            [(IMPERSONATE_HEADER, account), ("depth", "0")],
+            "",
+        )
+        .await
+    }
+
+    async fn assert_impersonates(&self, account: &str) {
+        let path = DavResourceName::File.collection_path();
+        let principal_path = format!(
+            "D:href:{}/{account}/",
+            DavResourceName::Principal.base_path()
+        );
+        self.propfind_with_headers(
+            path,
+            ["D:current-user-principal"],
+            [(IMPERSONATE_HEADER, account), ("depth", "0")],
+        )
+        .await
+        .properties(path)
+        .get(DavProperty::WebDav(WebDavProperty::CurrentUserPrincipal))
+        .with_values([principal_path.as_str()])
+        .with_status(StatusCode::OK);
+    }
+}
@@ -157,7 +157,162 @@ pub async fn test(test: &WebDavTest) {
             .await
             .with_status(StatusCode::NO_CONTENT);
 
-        // Test 11: Locking with a large dead property should fail
+        // Test 11: Shared locks can coexist but conflict with exclusive locks
+        let path = format!("{base_path}/shared-lock");
+        let mut shared_tokens = Vec::new();
+        for owner in ["owner-1", "owner-2"] {
+            shared_tokens.push(
+                client
+                    .lock_create(&path, owner, false, "0", "Second-123")
+                    .await
+                    .with_status(StatusCode::CREATED)
+                    .with_value(
+                        "D:prop.D:lockdiscovery.D:activelock.D:lockscope.D:shared",
+                        "",
+                    )
+                    .lock_token()
+                    .to_string(),
+            );
+        }
+        assert_ne!(shared_tokens[0], shared_tokens[1]);
+        client
+            .lock_create(&path, "owner-3", true, "0", "Second-123")
+            .await
+            .with_status(StatusCode::LOCKED);
+        client
+            .request_with_headers(
+                "LOCK",
+                &path,
+                [
+                    ("if", format!("(<{}>)", shared_tokens[0]).as_str()),
+                    ("depth", "0"),
+                ],
+                &LOCK_REQUEST
+                    .replace("$TYPE", "exclusive")
+                    .replace("$OWNER", "owner-3"),
+            )
+            .await
+            .with_status(StatusCode::LOCKED);
+        for shared_token in &shared_tokens {
+            client
+                .unlock(&path, shared_token)
+                .await
+                .with_status(StatusCode::NO_CONTENT);
+        }
+
+        // Test 12: Locks default to Depth: infinity and are refreshed through their descendants
+        let path = format!("{base_path}/infinity-lock");
+        let child_path = format!("{path}/child");
+        client
+            .lock_create(&path, "super-owner", true, "1", "Second-123")
+            .await
+            .with_status(StatusCode::BAD_REQUEST);
+        let lock_token = client
+            .request_with_headers(
+                "LOCK",
+                &path,
+                [("timeout", "Second-123")],
+                &LOCK_REQUEST
+                    .replace("$TYPE", "exclusive")
+                    .replace("$OWNER", "super-owner"),
+            )
+            .await
+            .with_status(StatusCode::CREATED)
+            .with_value("D:prop.D:lockdiscovery.D:activelock.D:depth", "infinity")
+            .lock_token()
+            .to_string();
+        client
+            .lock_refresh(&child_path, &lock_token, "0", "Second-789")
+            .await
+            .with_status(StatusCode::OK)
+            .with_value("D:prop.D:lockdiscovery.D:activelock.D:depth", "infinity")
+            .with_value(
+                "D:prop.D:lockdiscovery.D:activelock.D:lockroot.D:href",
+                &path,
+            )
+            .with_any_value(
+                "D:prop.D:lockdiscovery.D:activelock.D:timeout",
+                ["Second-789", "Second-788"],
+            );
+        client
+            .lock_refresh(
+                &format!("{base_path}/unrelated"),
+                &lock_token,
+                "0",
+                "Second-789",
+            )
+            .await
+            .with_status(StatusCode::PRECONDITION_FAILED);
+        client
+            .unlock(&child_path, &lock_token)
+            .await
+            .with_status(StatusCode::NO_CONTENT);
+
+        // Test 13: Both the source and destination locks of a MOVE must be submitted
+        if resource_type == DavResourceName::File {
+            let source_path = format!("{base_path}/move-source.txt");
+            let destination_path = format!("{base_path}/move-destination.txt");
+            let mut lock_tokens = Vec::new();
+            for path in [&source_path, &destination_path] {
+                client
+                    .request("PUT", path, &contents)
+                    .await
+                    .with_status(StatusCode::CREATED);
+                lock_tokens.push(
+                    client
+                        .lock_create(path, "super-owner", true, "0", "Second-123")
+                        .await
+                        .with_status(StatusCode::CREATED)
+                        .lock_token()
+                        .to_string(),
+                );
+            }
+            let condition = format!("<{source_path}> (<{}>)", lock_tokens[0]);
+            client
+                .request_with_headers(
+                    "MOVE",
+                    &source_path,
+                    [
+                        ("destination", destination_path.as_str()),
+                        ("if", condition.as_str()),
+                    ],
+                    "",
+                )
+                .await
+                .with_status(StatusCode::LOCKED)
+                .with_value("D:error.D:lock-token-submitted.D:href", &destination_path);
+            let condition = format!(
+                "<{source_path}> ([\"unknown-etag\"]) (<{}>) <{destination_path}> (<{}>)",
+                lock_tokens[0], lock_tokens[1]
+            );
+            client
+                .request_with_headers(
+                    "MOVE",
+                    &source_path,
+                    [
+                        ("destination", destination_path.as_str()),
+                        ("if", condition.as_str()),
+                    ],
+                    "",
+                )
+                .await
+                .with_status(StatusCode::NO_CONTENT);
+            for (path, lock_token) in [&source_path, &destination_path]
+                .into_iter()
+                .zip(&lock_tokens)
+            {
+                client
+                    .unlock(path, lock_token)
+                    .await
+                    .with_status(StatusCode::NO_CONTENT);
+            }
+            client
+                .request("DELETE", &destination_path, "")
+                .await
+                .with_status(StatusCode::NO_CONTENT);
+        }
+
+        // Test 14: Locking with a large dead property should fail
         let path = format!("{base_path}/invalid-lock");
         client
             .lock_create(
@@ -173,7 +328,7 @@ pub async fn test(test: &WebDavTest) {
             .await
             .with_status(StatusCode::PAYLOAD_TOO_LARGE);
 
-        // Test 12: Too many locks should fail
+        // Test 15: Too many locks should fail
         for i in 0..test.server.core.groupware.max_locks_per_user {
             client
                 .lock_create(
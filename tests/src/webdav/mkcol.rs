@@ -101,6 +101,22 @@ pub async fn test(test: &WebDavTest) {
             .with_value("D:mkcol-response.D:propstat.D:prop.D:resourcetype", "");
     }
 
+    // Valid properties are reported as failed dependencies when another one fails
+    client
+        .mkcol(
+            "MKCOL",
+            "/dav/file/john/my-named-files",
+            ["D:collection", "B:addressbook"],
+            [("D:displayname", "Named Files")],
+        )
+        .await
+        .with_status(StatusCode::FORBIDDEN)
+        .with_value("D:mkcol-response.D:propstat.D:prop.D:displayname", "")
+        .with_values(
+            "D:mkcol-response.D:propstat.D:status",
+            ["HTTP/1.1 403 Forbidden", "HTTP/1.1 424 Failed Dependency"],
+        );
+
     // Create using extended MKCOL
     for (path, expected_properties, resource_types) in [
         (
@@ -182,6 +198,30 @@ pub async fn test(test: &WebDavTest) {
             ["HTTP/1.1 200 OK"],
         );
 
+    // Calendars only accept the component types in their supported set
+    client
+        .mkcol(
+            "MKCALENDAR",
+            "/dav/cal/john/my-tasks",
+            [],
+            [(
+                "A:supported-calendar-component-set",
+                "<A:comp name=\"VTODO\"/>",
+            )],
+        )
+        .await
+        .with_status(StatusCode::CREATED);
+    client
+        .request_with_headers(
+            "PUT",
+            "/dav/cal/john/my-tasks/event.ics",
+            [("content-type", "text/calendar; charset=utf-8")],
+            TEST_ICAL_1,
+        )
+        .await
+        .with_status(StatusCode::PRECONDITION_FAILED)
+        .with_failed_precondition("A:supported-calendar-component", "");
+
     // Delete everything
     for path in [
         "/dav/file/john/my-files",
@@ -191,6 +231,7 @@ pub async fn test(test: &WebDavTest) {
         "/dav/card/john/my-named-cards",
         "/dav/cal/john/my-named-events",
         "/dav/cal/john/my-named-events2",
+        "/dav/cal/john/my-tasks",
     ] {
         client
             .request("DELETE", path, "")
@@ -5,8 +5,10 @@
  */
 
 use crate::{
-    AssertConfig, TEST_USERS, add_test_certs, directory::internal::TestInternalDirectory,
-    jmap::assert_is_empty, store::TempDir,
+    AssertConfig, TEST_USERS, add_test_certs,
+    directory::internal::TestInternalDirectory,
+    jmap::{assert_is_empty, enterprise::EnterpriseCore},
+    store::TempDir,
 };
 use ::managesieve::core::ManageSieveSessionManager;
 use ::store::Stores;
@@ -46,16 +48,24 @@ use tokio::sync::watch;
 use utils::config::Config;
 
 pub mod acl;
+pub mod alarms;
 pub mod basic;
+pub mod birthdays;
+pub mod cached_propfind;
 pub mod cal_query;
+pub mod capture;
 pub mod card_query;
 pub mod copy_move;
+pub mod impersonate;
 pub mod lock;
 pub mod mkcol;
+pub mod mount;
 pub mod multiget;
 pub mod principals;
 pub mod prop;
+pub mod push;
 pub mod put_get;
+pub mod sharing;
 pub mod sync;
 
 #[tokio::test]
@@ -74,14 +84,22 @@ pub async fn webdav_tests() {
     put_get::test(&handle).await;
     mkcol::test(&handle).await;
     copy_move::test(&handle).await;
+    mount::test(&handle).await;
     prop::test(&handle).await;
     multiget::test(&handle).await;
     sync::test(&handle).await;
     lock::test(&handle).await;
     principals::test(&handle).await;
     acl::test(&handle).await;
+    sharing::test(&handle).await;
+    push::test(&handle).await;
     card_query::test(&handle).await;
     cal_query::test(&handle).await;
+    birthdays::test(&handle).await;
+    cached_propfind::test(&handle).await;
+    alarms::test(&handle).await;
+    impersonate::test(&handle).await;
+    capture::test(&handle).await;
 
     // Print elapsed time
     let elapsed = start_time.elapsed();
@@ -108,6 +126,7 @@ pub struct WebDavTest {
 async fn init_webdav_tests(store_id: &str, delete_if_exists: bool) -> WebDavTest {
     // Load and parse config
     let temp_dir = TempDir::new("webdav_tests", delete_if_exists);
+    std::fs::create_dir_all(temp_dir.path.join("mount")).unwrap();
     let mut config = Config::new(
         add_test_certs(SERVER)
             .replace("{STORE}", store_id)
@@ -131,7 +150,9 @@ async fn init_webdav_tests(store_id: &str, delete_if_exists: bool) -> WebDavTest
 
     // Parse core
     let tracers = Telemetry::parse(&mut config, &stores);
-    let core = Core::parse(&mut config, stores, Default::default()).await;
+    let core = Core::parse(&mut config, stores, Default::default())
+        .await
+        .enable_enterprise();
     let data = Data::parse(&mut config);
     let cache = Caches::parse(&mut config);
 
@@ -304,6 +325,23 @@ impl DummyWebDavClient {
         query: &str,
         headers: impl IntoIterator<Item = (&'static str, &str)>,
         body: impl Into<String>,
+    ) -> DavResponse {
+        let body = body.into();
+        self.request_with_body(
+            method,
+            query,
+            headers,
+            (!body.is_empty()).then(|| body.into()),
+        )
+        .await
+    }
+
+    pub async fn request_with_body(
+        &self,
+        method: &str,
+        query: &str,
+        headers: impl IntoIterator<Item = (&'static str, &str)>,
+        body: Option<reqwest::Body>,
     ) -> DavResponse {
         let mut request = reqwest::Client::builder()
             .timeout(Duration::from_millis(500))
@@ -315,8 +353,7 @@ impl DummyWebDavClient {
                 format!("https://127.0.0.1:8899{query}"),
             );
 
-        let body = body.into();
-        if !body.is_empty() {
+        if let Some(body) = body {
             request = request.body(body);
         }
 
@@ -1132,6 +1169,20 @@ domains = "SELECT 1 FROM emails WHERE address LIKE '%@' || ? LIMIT 1"
 type = "internal"
 store = "{STORE}"
 
+[contacts.directory-gateway]
+enable = true
+
+[dav.request]
+max-size = 2097152
+
+[file-storage]
+max-versions = 2
+
+[file-storage.mount.external]
+account = "john"
+path = "external/data"
+root = "{TMP}/mount"
+
 [oauth]
 key = "parerga_und_paralipomena"
 
@@ -0,0 +1,114 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use super::WebDavTest;
+use flate2::{Compression, write::GzEncoder};
+use hyper::StatusCode;
+use std::io::Write;
+
+pub async fn test(test: &WebDavTest) {
+    println!("Running external storage mount tests...");
+    let john = test.client("john");
+    let jane = test.client("jane");
+
+    // Prepare the mounted directory, with links pointing outside of it
+    let root = test.temp_dir.path.join("mount");
+    let outside = test.temp_dir.path.join("outside");
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::write(outside.join("secret"), "top secret").unwrap();
+    std::fs::write(root.join("notes"), "hello world").unwrap();
+    std::os::unix::fs::symlink(outside.join("secret"), root.join("secret")).unwrap();
+    std::os::unix::fs::symlink(&outside, root.join("outside")).unwrap();
+
+    // The mount point is a regular folder
+    for path in ["/dav/file/john/external/", "/dav/file/john/external/data/"] {
+        john.request("MKCOL", path, "")
+            .await
+            .with_status(StatusCode::CREATED);
+    }
+
+    // Mounted files are listed, symbolic links are not
+    john.request_with_headers(
+        "PROPFIND",
+        "/dav/file/john/external/data/",
+        [("depth", "1")],
+        "",
+    )
+    .await
+    .with_status(StatusCode::MULTI_STATUS)
+    .with_hrefs([
+        "/dav/file/john/external/data/",
+        "/dav/file/john/external/data/notes",
+    ]);
+
+    // Mounted files are streamed back
+    john.request("GET", "/dav/file/john/external/data/notes", "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_header("content-length", "11")
+        .with_body("hello world");
+    john.request("HEAD", "/dav/file/john/external/data/notes", "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_header("content-length", "11");
+
+    // Accounts without access to the mount point are rejected
+    jane.request("GET", "/dav/file/john/external/data/notes", "")
+        .await
+        .with_status(StatusCode::FORBIDDEN);
+
+    // Symbolic links cannot be used to escape the mount root
+    for (method, path) in [
+        ("GET", "/dav/file/john/external/data/secret"),
+        ("GET", "/dav/file/john/external/data/outside/secret"),
+        ("PUT", "/dav/file/john/external/data/outside/new"),
+        ("MKCOL", "/dav/file/john/external/data/outside/new/"),
+    ] {
+        john.request(method, path, "top secret")
+            .await
+            .with_status(StatusCode::FORBIDDEN);
+    }
+    assert!(!outside.join("new").exists());
+
+    // Files can be written inside the mount root
+    john.request("PUT", "/dav/file/john/external/data/new", "new file")
+        .await
+        .with_status(StatusCode::CREATED);
+    assert_eq!(
+        std::fs::read_to_string(root.join("new")).unwrap(),
+        "new file"
+    );
+    john.request("DELETE", "/dav/file/john/external/data/new", "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    assert!(!root.join("new").exists());
+
+    // Compressed uploads are decoded before being written
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"compressed file").unwrap();
+    john.request_with_body(
+        "PUT",
+        "/dav/file/john/external/data/compressed",
+        [("content-encoding", "gzip")],
+        Some(encoder.finish().unwrap().into()),
+    )
+    .await
+    .with_status(StatusCode::CREATED);
+    assert_eq!(
+        std::fs::read_to_string(root.join("compressed")).unwrap(),
+        "compressed file"
+    );
+    john.request("DELETE", "/dav/file/john/external/data/compressed", "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+
+    // Remove the mount point, the mounted files are left untouched
+    john.request("DELETE", "/dav/file/john/external/", "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    assert!(root.join("notes").exists());
+    test.assert_is_empty().await;
+}
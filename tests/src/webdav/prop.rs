@@ -341,6 +341,14 @@ pub async fn test(test: &WebDavTest) {
                         .get(DavProperty::CalDav(CalDavProperty::SupportedCalendarData))
                         .with_values([
                             concat!("A:calendar-data-type.", "[content-type]:text/calendar"),
+                            concat!(
+                                "A:calendar-data-type.",
+                                "[content-type]:application/calendar+json"
+                            ),
+                            concat!(
+                                "A:calendar-data-type.",
+                                "[content-type]:application/calendar+xml"
+                            ),
                             "A:calendar-data-type.[version]:2.0",
                             "A:calendar-data-type.[version]:1.0",
                         ]);
@@ -572,6 +580,24 @@ pub async fn test(test: &WebDavTest) {
                             ],
                         )
                         .await;
+                    client
+                        .patch_and_check(
+                            path,
+                            [
+                                (
+                                    DavProperty::CalDav(CalDavProperty::CalendarColor),
+                                    "#FF2968FF",
+                                ),
+                                (DavProperty::CalDav(CalDavProperty::CalendarOrder), "3"),
+                            ],
+                        )
+                        .await;
+                    client
+                        .patch_and_check(
+                            path,
+                            [(DavProperty::CalDav(CalDavProperty::CalendarColor), "")],
+                        )
+                        .await;
                     client
                         .patch_and_check(
                             path,
@@ -671,6 +697,7 @@ pub async fn test(test: &WebDavTest) {
                                 DavProperty::CalDav(CalDavProperty::CalendarTimezone),
                                 TEST_ICAL_2,
                             ),
+                            (DavProperty::CalDav(CalDavProperty::CalendarColor), "blue"),
                         ],
                         [],
                         [],
@@ -686,6 +713,10 @@ pub async fn test(test: &WebDavTest) {
                     .get(DavProperty::CalDav(CalDavProperty::CalendarTimezone))
                     .with_status(StatusCode::PRECONDITION_FAILED)
                     .with_description("Invalid calendar timezone");
+                props
+                    .get(DavProperty::CalDav(CalDavProperty::CalendarColor))
+                    .with_status(StatusCode::CONFLICT)
+                    .with_description("Invalid calendar color");
             }
         }
 
@@ -1119,7 +1150,8 @@ impl DummyWebDavClient {
         let mut request = concat!(
             "<?xml version=\"1.0\" encoding=\"utf-8\"?>",
             "<D:propfind xmlns:D=\"DAV:\" xmlns:A=\"urn:ietf:params:xml:ns:caldav\" ",
-            "xmlns:B=\"urn:ietf:params:xml:ns:carddav\" xmlns:C=\"http://calendarserver.org/ns/\">",
+            "xmlns:B=\"urn:ietf:params:xml:ns:carddav\" xmlns:C=\"http://calendarserver.org/ns/\" ",
+            "xmlns:I=\"http://apple.com/ns/ical/\" xmlns:P=\"https://bitfire.at/webdav-push\">",
             "<D:prop>"
         )
         .to_string();
@@ -1149,7 +1181,8 @@ impl DummyWebDavClient {
         let mut request = concat!(
             "<?xml version=\"1.0\" encoding=\"utf-8\"?>",
             "<D:propertyupdate xmlns:D=\"DAV:\" xmlns:A=\"urn:ietf:params:xml:ns:caldav\" ",
-            "xmlns:B=\"urn:ietf:params:xml:ns:carddav\" xmlns:C=\"http://calendarserver.org/ns/\">",
+            "xmlns:B=\"urn:ietf:params:xml:ns:carddav\" xmlns:C=\"http://calendarserver.org/ns/\" ",
+            "xmlns:I=\"http://apple.com/ns/ical/\">",
             "<D:remove><D:prop>"
         )
         .to_string();
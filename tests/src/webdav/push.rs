@@ -0,0 +1,92 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use hyper::StatusCode;
+
+use super::{DavResponse, DummyWebDavClient, WebDavTest};
+
+pub async fn test(test: &WebDavTest) {
+    println!("Running WebDAV-Push tests...");
+    let john = test.client("john");
+    let jane = test.client("jane");
+    let calendar_href = "/dav/cal/john/default/";
+    let push_resource = "https://push.example.org/yohd4yai5Phiz1wi";
+
+    // Collections advertise the Web Push transport
+    let response = john
+        .propfind(
+            calendar_href,
+            ["P:transports", "P:topic", "P:supported-triggers"],
+        )
+        .await;
+    let properties = response.properties(calendar_href);
+    properties.get("P:transports").with_values(["P:web-push"]);
+    assert!(!properties.get("P:topic").value().is_empty());
+
+    // Only secure push resources are accepted
+    john.push_register(calendar_href, "http://push.example.org/abc")
+        .await
+        .with_status(StatusCode::BAD_REQUEST);
+
+    // Register a subscription, registering it again updates it
+    let response = john
+        .push_register(calendar_href, push_resource)
+        .await
+        .with_status(StatusCode::CREATED);
+    let registration_href = response.header("location").to_string();
+    assert!(registration_href.starts_with("/dav/push/cal/"));
+    assert!(!response.header("expires").is_empty());
+    john.push_register(calendar_href, push_resource)
+        .await
+        .with_status(StatusCode::NO_CONTENT)
+        .with_header("location", &registration_href);
+
+    // Collections of other accounts cannot be subscribed to without access
+    jane.push_register(calendar_href, push_resource)
+        .await
+        .with_status(StatusCode::FORBIDDEN);
+
+    // Only the subscriber can remove the subscription
+    jane.request("DELETE", &registration_href, "")
+        .await
+        .with_status(StatusCode::NOT_FOUND);
+    john.request("DELETE", &registration_href, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    john.request("DELETE", &registration_href, "")
+        .await
+        .with_status(StatusCode::NOT_FOUND);
+
+    john.request("DELETE", calendar_href, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    test.assert_is_empty().await;
+}
+
+impl DummyWebDavClient {
+    pub async fn push_register(&self, query: &str, push_resource: &str) -> DavResponse {
+        let request = format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"utf-8\"?>",
+                "<P:push-register xmlns:D=\"DAV:\" xmlns:P=\"https://bitfire.at/webdav-push\">",
+                "<P:subscription><P:web-push-subscription>",
+                "<P:push-resource>{}</P:push-resource>",
+                "</P:web-push-subscription></P:subscription>",
+                "<P:trigger><P:content-update><D:depth>1</D:depth></P:content-update></P:trigger>",
+                "</P:push-register>"
+            ),
+            push_resource
+        );
+
+        self.request_with_headers(
+            "POST",
+            query,
+            [("content-type", "application/xml; charset=utf-8")],
+            &request,
+        )
+        .await
+    }
+}
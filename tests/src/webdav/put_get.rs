@@ -58,6 +58,118 @@ pub async fn test(test: &WebDavTest) {
             .with_body(content);
     }
 
+    // Cards are converted to jCard when requested (RFC 7095)
+    let response = client
+        .request_with_headers(
+            "GET",
+            "/dav/card/john/default/card2.vcf",
+            [("accept", "application/vcard+json")],
+            "",
+        )
+        .await
+        .with_status(StatusCode::OK)
+        .with_header("content-type", "application/vcard+json; charset=utf-8");
+    let jcard = serde_json::from_str::<serde_json::Value>(response.body.as_ref().unwrap())
+        .expect("invalid jCard");
+    assert_eq!(jcard[0], "vcard");
+    let properties = jcard[1].as_array().unwrap();
+    for expected in [
+        serde_json::json!(["fn", {}, "text", "Joe Citizen"]),
+        serde_json::json!(["n", {}, "text", ["Citizen", "Joe", "", "", ""]]),
+        serde_json::json!(["rev", {}, "timestamp", "2020-04-11T07:24:29Z"]),
+    ] {
+        assert!(
+            properties.contains(&expected),
+            "missing {expected} in {jcard}"
+        );
+    }
+
+    // Events are converted to jCal when requested (RFC 7265)
+    let response = client
+        .request_with_headers(
+            "GET",
+            "/dav/cal/john/default/event2.ics",
+            [("accept", "application/calendar+json")],
+            "",
+        )
+        .await
+        .with_status(StatusCode::OK)
+        .with_header("content-type", "application/calendar+json; charset=utf-8");
+    let jcal = serde_json::from_str::<serde_json::Value>(response.body.as_ref().unwrap())
+        .expect("invalid jCal");
+    assert_eq!(jcal[0], "vcalendar");
+    let event = &jcal[2][0];
+    assert_eq!(event[0], "vevent");
+    let properties = event[1].as_array().unwrap();
+    for expected in [
+        serde_json::json!(["uid", {}, "text", "0000001"]),
+        serde_json::json!(["summary", {}, "text", "Treasure Hunting"]),
+    ] {
+        assert!(
+            properties.contains(&expected),
+            "missing {expected} in {jcal}"
+        );
+    }
+
+    // jCal and jCard bodies are parsed and validated like their text equivalents
+    for (path, ct, content, expected) in [
+        (
+            "/dav/cal/john/default/event3.ics",
+            "application/calendar+json",
+            TEST_JCAL,
+            ["SUMMARY:Team meeting", "RRULE:FREQ=WEEKLY;COUNT=4;BYDAY=MO"],
+        ),
+        (
+            "/dav/card/john/default/card3.vcf",
+            "application/vcard+json",
+            TEST_JCARD,
+            ["FN:Jane Doe", "N:Doe;Jane;;;"],
+        ),
+    ] {
+        client
+            .request_with_headers("PUT", path, [("content-type", ct)], content)
+            .await
+            .with_status(StatusCode::CREATED);
+        let response = client
+            .request("GET", path, "")
+            .await
+            .with_status(StatusCode::OK);
+        let body = response.body.as_ref().unwrap();
+        for expected in expected {
+            assert!(body.contains(expected), "missing {expected} in {body}");
+        }
+        client
+            .request("DELETE", path, "")
+            .await
+            .with_status(StatusCode::NO_CONTENT);
+    }
+    for (path, ct, content, precondition) in [
+        (
+            "/dav/cal/john/default/event3.ics",
+            "application/calendar+json",
+            r#"["vevent", [["summary", {}, "text", "No calendar"]], []]"#,
+            "A:supported-calendar-data",
+        ),
+        (
+            "/dav/cal/john/default/event3.ics",
+            "application/calendar+json",
+            r#"["vcalendar", [["dtstart", {}, "date-time", "not a date"]], []]"#,
+            "A:supported-calendar-data",
+        ),
+        (
+            "/dav/card/john/default/card3.vcf",
+            "application/vcard+json",
+            r#"["vcalendar", [], []]"#,
+            "B:supported-address-data",
+        ),
+    ] {
+        client
+            .request_with_headers("PUT", path, [("content-type", ct)], content)
+            .await
+            .with_status(StatusCode::PRECONDITION_FAILED)
+            .with_failed_precondition(precondition, "");
+    }
+
     // PUT under a non-existing parent should fail
     for (path, contents) in [
         ("/dav/file/john/foo/file1.txt", TEST_FILE_1),
@@ -149,6 +261,41 @@ pub async fn test(test: &WebDavTest) {
         }
     }
 
+    // File uploads of unknown length are streamed to the blob store
+    let path = "/dav/file/john/streamed.txt";
+    let contents = TEST_FILE_1.repeat(64);
+    client
+        .request_with_body(
+            "PUT",
+            path,
+            [("content-type", "text/plain")],
+            Some(chunked_body(&contents, 1000)),
+        )
+        .await
+        .with_status(StatusCode::CREATED);
+    client
+        .request("GET", path, "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_header("content-length", &contents.len().to_string())
+        .with_body(&contents);
+    client
+        .request("DELETE", path, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+
+    // Streamed uploads are rejected as soon as they exceed the file limit
+    let path = "/dav/file/john/chunky-streamed.txt";
+    let contents = "a".repeat(conf.max_file_size + 1);
+    client
+        .request_with_body("PUT", path, [], Some(chunked_body(&contents, 64 * 1024)))
+        .await
+        .with_status(StatusCode::PAYLOAD_TOO_LARGE);
+    client
+        .request("GET", path, "")
+        .await
+        .with_status(StatusCode::NOT_FOUND);
+
     // PUT requests cannot exceed quota
     let mike_noquota = test.client("mike");
     for resource_type in [
@@ -299,6 +446,30 @@ pub async fn test(test: &WebDavTest) {
             .to_string();
     }
 
+    // Updates with prefer return=representation return the stored resource
+    for (path, (content, ct, etag)) in &mut files {
+        let condition = format!("([{}])", etag);
+        *content = content.replace("X-TEST:SEQ2", "X-TEST:SEQ3");
+        *etag = client
+            .request_with_headers(
+                "PUT",
+                path,
+                [
+                    ("content-type", &**ct),
+                    ("if", condition.as_str()),
+                    ("prefer", "return=representation"),
+                ],
+                content.as_str(),
+            )
+            .await
+            .with_status(StatusCode::OK)
+            .with_header("preference-applied", "return=representation")
+            .with_header("content-type", ct)
+            .with_body(content.as_str())
+            .etag()
+            .to_string();
+    }
+
     // Test GET
     for (path, (content, ct, etag)) in &files {
         client
@@ -339,6 +510,28 @@ pub async fn test(test: &WebDavTest) {
             .with_failed_precondition(precond_key, precond_value);
     }
 
+    // PUT requests cannot change the UID of an existing resource
+    for (path, ct, content, precond_key) in [
+        (
+            "/dav/card/john/default/card1.vcf",
+            "text/vcard; charset=utf-8",
+            TEST_VCARD_2,
+            "B:no-uid-conflict.D:href",
+        ),
+        (
+            "/dav/cal/john/default/event1.ics",
+            "text/calendar; charset=utf-8",
+            TEST_ICAL_2,
+            "A:no-uid-conflict.D:href",
+        ),
+    ] {
+        client
+            .request_with_headers("PUT", path, [("content-type", ct)], content)
+            .await
+            .with_status(StatusCode::PRECONDITION_FAILED)
+            .with_failed_precondition(precond_key, path);
+    }
+
     // iCal containing different component types should fail
     client
         .request_with_headers(
@@ -399,6 +592,100 @@ END:VCALENDAR
         .with_status(StatusCode::PRECONDITION_FAILED)
         .with_failed_precondition("A:valid-calendar-object-resource", "");
 
+    // Managed attachments are referenced from the event and removed with it
+    let event_path = "/dav/cal/john/default/attachments.ics";
+    client
+        .request_with_headers(
+            "PUT",
+            event_path,
+            [("content-type", "text/calendar; charset=utf-8")],
+            TEST_ICAL_ATTACHMENTS,
+        )
+        .await
+        .with_status(StatusCode::CREATED);
+    let managed_id = client
+        .request_with_headers(
+            "POST",
+            &format!("{event_path}?action=attachment-add"),
+            [
+                ("content-type", "text/plain"),
+                ("content-disposition", "attachment;filename=notes.txt"),
+            ],
+            TEST_FILE_1,
+        )
+        .await
+        .with_status(StatusCode::CREATED)
+        .header("cal-managed-id")
+        .to_string();
+    let response = client
+        .request("GET", event_path, "")
+        .await
+        .with_status(StatusCode::OK);
+    let ical = response.body.as_ref().unwrap();
+    assert!(ical.contains(&format!("MANAGED-ID={managed_id}")), "{ical}");
+    assert!(ical.contains("FILENAME=notes.txt"), "{ical}");
+    client
+        .request("GET", &format!("{event_path}?managed-id={managed_id}"), "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_header("content-type", "text/plain")
+        .with_body(TEST_FILE_1);
+    client
+        .request(
+            "POST",
+            &format!("{event_path}?action=attachment-remove&managed-id=unknown"),
+            "",
+        )
+        .await
+        .with_status(StatusCode::BAD_REQUEST);
+    let updated_id = client
+        .request_with_headers(
+            "POST",
+            &format!("{event_path}?action=attachment-update&managed-id={managed_id}"),
+            [("content-type", "text/plain")],
+            TEST_FILE_2,
+        )
+        .await
+        .with_status(StatusCode::NO_CONTENT)
+        .header("cal-managed-id")
+        .to_string();
+    assert_ne!(updated_id, managed_id);
+    client
+        .request("GET", &format!("{event_path}?managed-id={managed_id}"), "")
+        .await
+        .with_status(StatusCode::NOT_FOUND);
+    client
+        .request("GET", &format!("{event_path}?managed-id={updated_id}"), "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_body(TEST_FILE_2);
+    client
+        .request(
+            "POST",
+            &format!("{event_path}?action=attachment-remove&managed-id={updated_id}"),
+            "",
+        )
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    let response = client
+        .request("GET", event_path, "")
+        .await
+        .with_status(StatusCode::OK);
+    assert!(!response.body.as_ref().unwrap().contains("MANAGED-ID"));
+    client
+        .request_with_headers(
+            "POST",
+            &format!("{event_path}?action=attachment-add"),
+            [("content-type", "text/plain")],
+            TEST_FILE_1,
+        )
+        .await
+        .with_status(StatusCode::CREATED);
+    client
+        .request("DELETE", event_path, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+
     // Deleting unknown/invalid destinations should fail
     for (path, expect) in [
         ("/dav/file/john/unknown.txt", StatusCode::NOT_FOUND),
@@ -435,7 +722,544 @@ END:VCALENDAR
             .with_status(StatusCode::NOT_FOUND);
     }
 
+    // Previous versions of a file are kept on PUT
+    let path = "/dav/file/john/versioned.txt";
+    for (num, content) in ["version 1", "version 2", "version 3"].iter().enumerate() {
+        client
+            .request_with_headers("PUT", path, [("content-type", "text/plain")], *content)
+            .await
+            .with_status(if num == 0 {
+                StatusCode::CREATED
+            } else {
+                StatusCode::NO_CONTENT
+            });
+    }
+    client
+        .request("REPORT", path, VERSION_TREE_QUERY)
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_hrefs([
+            path,
+            &format!("{path}?version=2"),
+            &format!("{path}?version=1"),
+        ])
+        .with_values(
+            "D:multistatus.D:response.D:propstat.D:prop.D:version-name",
+            ["3", "2", "1"],
+        );
+    client
+        .request("GET", &format!("{path}?version=1"), "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_body("version 1");
+    client
+        .request("GET", &format!("{path}?version=7"), "")
+        .await
+        .with_status(StatusCode::NOT_FOUND);
+
+    // Restore a previous version
+    client
+        .request(
+            "POST",
+            &format!("{path}?action=restore-version&version=2"),
+            "",
+        )
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    client
+        .request("GET", path, "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_body("version 2");
+    client
+        .request("REPORT", path, VERSION_TREE_QUERY)
+        .await
+        .with_status(StatusCode::MULTI_STATUS)
+        .with_values(
+            "D:multistatus.D:response.D:propstat.D:prop.D:version-name",
+            ["4", "3", "2"],
+        );
+    client
+        .request(
+            "POST",
+            &format!("{path}?action=restore-version&version=1"),
+            "",
+        )
+        .await
+        .with_status(StatusCode::CONFLICT);
+    client
+        .request("DELETE", path, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+
+    // Interrupted uploads are resumed with partial PUTs
+    let path = "/dav/file/john/ranged.txt";
+    for (range, content, expect) in [
+        ("bytes 0-4/*", "hello", StatusCode::CREATED),
+        ("bytes 5-10/11", " world", StatusCode::NO_CONTENT),
+        ("bytes 20-21/*", "!!", StatusCode::RANGE_NOT_SATISFIABLE),
+        ("bytes 11-12/*", "!", StatusCode::BAD_REQUEST),
+    ] {
+        client
+            .request_with_headers(
+                "PUT",
+                path,
+                [("content-type", "text/plain"), ("content-range", range)],
+                content,
+            )
+            .await
+            .with_status(expect);
+    }
+    let etag = client
+        .request("GET", path, "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_header("accept-ranges", "bytes")
+        .with_body("hello world")
+        .etag()
+        .to_string();
+
+    // Byte ranges are served when the validator matches (RFC 9110)
+    for (headers, expect, content_range, body) in [
+        (
+            vec![("range", "bytes=6-")],
+            StatusCode::PARTIAL_CONTENT,
+            Some("bytes 6-10/11"),
+            "world",
+        ),
+        (
+            vec![("range", "bytes=0-4")],
+            StatusCode::PARTIAL_CONTENT,
+            Some("bytes 0-4/11"),
+            "hello",
+        ),
+        (
+            vec![("range", "bytes=-5"), ("if-range", etag.as_str())],
+            StatusCode::PARTIAL_CONTENT,
+            Some("bytes 6-10/11"),
+            "world",
+        ),
+        (
+            vec![("range", "bytes=-5"), ("if-range", "\"stale\"")],
+            StatusCode::OK,
+            None,
+            "hello world",
+        ),
+        (
+            vec![("range", "bytes=20-")],
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            Some("bytes */11"),
+            "",
+        ),
+    ] {
+        let response = client
+            .request_with_headers("GET", path, headers, "")
+            .await
+            .with_status(expect);
+        let response = if let Some(content_range) = content_range {
+            response.with_header("content-range", content_range)
+        } else {
+            response
+        };
+        if expect != StatusCode::RANGE_NOT_SATISFIABLE {
+            response.with_body(body);
+        }
+    }
+    client
+        .request("DELETE", path, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+
+    // Timezones by reference omit standard VTIMEZONE components (RFC 7809)
+    let with_tz = TEST_TZ_BY_REF.replace("\n", "\r\n");
+    let without_tz = with_tz
+        .split_once("BEGIN:VTIMEZONE")
+        .map(|(start, rest)| format!("{start}{}", rest.split_once("END:VTIMEZONE\r\n").unwrap().1))
+        .unwrap();
+    for (path, headers) in [
+        ("/dav/cal/john/default/tz-full.ics", vec![]),
+        (
+            "/dav/cal/john/default/tz-ref.ics",
+            vec![("caldav-timezones", "F")],
+        ),
+    ] {
+        let mut put_headers = headers.clone();
+        put_headers.push(("content-type", "text/calendar; charset=utf-8"));
+        client
+            .request_with_headers("PUT", path, put_headers, &with_tz)
+            .await
+            .with_status(StatusCode::CREATED);
+        client
+            .request_with_headers("GET", path, headers.clone(), "")
+            .await
+            .with_status(StatusCode::OK)
+            .with_body(if headers.is_empty() {
+                &with_tz
+            } else {
+                &without_tz
+            });
+    }
+    client
+        .request_with_headers(
+            "GET",
+            "/dav/cal/john/default/tz-full.ics",
+            [("caldav-timezones", "F")],
+            "",
+        )
+        .await
+        .with_status(StatusCode::OK)
+        .with_body(&without_tz);
+    for path in [
+        "/dav/cal/john/default/tz-full.ics",
+        "/dav/cal/john/default/tz-ref.ics",
+    ] {
+        client
+            .request("DELETE", path, "")
+            .await
+            .with_status(StatusCode::NO_CONTENT);
+    }
+
+    // Outlook properties and Windows timezone names are repaired on the parsed
+    // object, so folded lines and quoted parameters are handled
+    let path = "/dav/cal/john/default/outlook.ics";
+    let response = client
+        .request_with_headers(
+            "PUT",
+            path,
+            [("content-type", "text/calendar; charset=utf-8")],
+            &TEST_ICAL_OUTLOOK.replace("\n", "\r\n"),
+        )
+        .await
+        .with_status(StatusCode::CREATED);
+    let body = response.body.as_ref().unwrap();
+    for expected in [
+        "<S:property>TRANSP</S:property><S:line>10</S:line><S:action>added</S:action>",
+        "<S:property>STATUS</S:property><S:line>10</S:line><S:action>added</S:action>",
+        "<S:property>DTSTART</S:property><S:line>7</S:line><S:action>replaced</S:action>",
+        "<S:property>DTEND</S:property><S:line>8</S:line><S:action>replaced</S:action>",
+    ] {
+        assert!(body.contains(expected), "missing {expected} in {body}");
+    }
+    let response = client
+        .request("GET", path, "")
+        .await
+        .with_status(StatusCode::OK);
+    let body = response.body.as_ref().unwrap();
+    for expected in [
+        "DTSTART;TZID=America/Los_Angeles:20250106T100000",
+        "DTEND;TZID=America/Los_Angeles:20250106T110000",
+        "TRANSP:OPAQUE",
+        "STATUS:TENTATIVE",
+    ] {
+        assert!(body.contains(expected), "missing {expected} in {body}");
+    }
+    client
+        .request("DELETE", path, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+
+    // Members with a server assigned name (RFC 5995)
+    for (collection, resource_type, content_type, advertised) in [
+        (
+            "/dav/cal/john/default/",
+            DavResourceName::Cal,
+            "text/calendar; charset=utf-8",
+            true,
+        ),
+        (
+            "/dav/card/john/default/",
+            DavResourceName::Card,
+            "text/vcard; charset=utf-8",
+            true,
+        ),
+        (
+            "/dav/file/john/",
+            DavResourceName::File,
+            "text/plain",
+            false,
+        ),
+    ] {
+        let add_member_href = format!("{collection}?add-member");
+        if advertised {
+            client
+                .propfind(collection, ["D:add-member"])
+                .await
+                .properties(collection)
+                .get("D:add-member")
+                .with_values([format!("D:href:{add_member_href}").as_str()]);
+        }
+
+        let content = resource_type.generate();
+        let response = client
+            .request_with_headers(
+                "POST",
+                &add_member_href,
+                [("content-type", content_type)],
+                &content,
+            )
+            .await
+            .with_status(StatusCode::CREATED);
+        let member_href = response.header("location").to_string();
+        assert!(
+            member_href.starts_with(collection) && member_href.len() > collection.len(),
+            "unexpected member href {member_href:?}"
+        );
+        client
+            .request("GET", &member_href, "")
+            .await
+            .with_status(StatusCode::OK)
+            .with_body(&content);
+        client
+            .request("DELETE", &member_href, "")
+            .await
+            .with_status(StatusCode::NO_CONTENT);
+    }
+    client
+        .request("POST", "/dav/cal/john/?add-member", "")
+        .await
+        .with_status(StatusCode::METHOD_NOT_ALLOWED);
+
+    // Recurrence overrides uploaded as a separate resource are merged into the
+    // recurring event and served back as uploaded, each with its own ETag
+    let master_href = "/dav/cal/john/default/recurring.ics";
+    let override_href = "/dav/cal/john/default/recurring-override.ics";
+    let master = TEST_ICAL_RECURRING.replace("\n", "\r\n");
+    let override_ = TEST_ICAL_OVERRIDE.replace("\n", "\r\n");
+    for (href, content) in [(master_href, &master), (override_href, &override_)] {
+        client
+            .request_with_headers(
+                "PUT",
+                href,
+                [("content-type", "text/calendar; charset=utf-8")],
+                content,
+            )
+            .await
+            .with_status(StatusCode::CREATED);
+    }
+    let master_etag = client
+        .request("GET", master_href, "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_body(&master)
+        .etag()
+        .to_string();
+    let override_etag = client
+        .request("GET", override_href, "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_body(&override_)
+        .etag()
+        .to_string();
+    assert_ne!(master_etag, override_etag);
+
+    // Updating the override does not change the ETag of the recurring event
+    let override_ = override_.replace("Moved occurrence", "Moved again");
+    let new_override_etag = client
+        .request_with_headers(
+            "PUT",
+            override_href,
+            [
+                ("content-type", "text/calendar; charset=utf-8"),
+                ("if-match", override_etag.as_str()),
+            ],
+            &override_,
+        )
+        .await
+        .with_status(StatusCode::NO_CONTENT)
+        .etag()
+        .to_string();
+    assert_ne!(new_override_etag, override_etag);
+    client
+        .request("GET", override_href, "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_header("etag", &new_override_etag)
+        .with_body(&override_);
+    client
+        .request("GET", master_href, "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_header("etag", &master_etag)
+        .with_body(&master);
+    client
+        .propfind(override_href, ["D:getetag"])
+        .await
+        .properties(override_href)
+        .get("D:getetag")
+        .with_values([new_override_etag.as_str()]);
+
+    // Override resources only accept recurrence overrides
+    client
+        .request_with_headers(
+            "PUT",
+            override_href,
+            [("content-type", "text/calendar; charset=utf-8")],
+            &master,
+        )
+        .await
+        .with_status(StatusCode::PRECONDITION_FAILED);
+
+    // Deleting the override resource keeps the recurring event
+    client
+        .request("DELETE", override_href, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    client
+        .request("GET", override_href, "")
+        .await
+        .with_status(StatusCode::NOT_FOUND);
+    client
+        .request("GET", master_href, "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_body(&master);
+    client
+        .request("DELETE", master_href, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+
     client.delete_default_containers().await;
     mike_noquota.delete_default_containers().await;
     test.assert_is_empty().await;
 }
+
+// Sends the contents in chunks, without a Content-Length header
+fn chunked_body(contents: &str, chunk_size: usize) -> reqwest::Body {
+    let chunks = contents
+        .as_bytes()
+        .chunks(chunk_size)
+        .map(|chunk| Ok::<_, std::io::Error>(bytes::Bytes::copy_from_slice(chunk)))
+        .collect::<Vec<_>>();
+    reqwest::Body::wrap_stream(futures::stream::iter(chunks))
+}
+
+const VERSION_TREE_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:version-tree xmlns:D="DAV:">
+  <D:prop>
+    <D:version-name/>
+  </D:prop>
+</D:version-tree>"#;
+
+const TEST_ICAL_ATTACHMENTS: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Example Corp.//CalDAV Client//EN
+BEGIN:VEVENT
+UID:attachments@example.com
+DTSTAMP:20231001T100000Z
+SUMMARY:Event with attachments
+DTSTART:20231001T120000Z
+DTEND:20231001T130000Z
+END:VEVENT
+END:VCALENDAR
+"#;
+
+const TEST_ICAL_RECURRING: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Example Corp.//CalDAV Client//EN
+BEGIN:VEVENT
+UID:split-resources@example.com
+DTSTAMP:20231001T100000Z
+SUMMARY:Weekly meeting
+DTSTART:20231002T090000Z
+DTEND:20231002T100000Z
+RRULE:FREQ=WEEKLY;COUNT=10
+END:VEVENT
+END:VCALENDAR
+"#;
+
+const TEST_ICAL_OVERRIDE: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Example Corp.//CalDAV Client//EN
+BEGIN:VEVENT
+UID:split-resources@example.com
+DTSTAMP:20231001T100000Z
+RECURRENCE-ID:20231009T090000Z
+SUMMARY:Moved occurrence
+DTSTART:20231009T140000Z
+DTEND:20231009T150000Z
+END:VEVENT
+END:VCALENDAR
+"#;
+
+const TEST_TZ_BY_REF: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Example Corp.//CalDAV Client//EN
+BEGIN:VTIMEZONE
+TZID:Europe/Amsterdam
+BEGIN:STANDARD
+DTSTART:19701025T030000
+RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU
+TZOFFSETFROM:+0200
+TZOFFSETTO:+0100
+END:STANDARD
+BEGIN:DAYLIGHT
+DTSTART:19700329T020000
+RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU
+TZOFFSETFROM:+0100
+TZOFFSETTO:+0200
+END:DAYLIGHT
+END:VTIMEZONE
+BEGIN:VEVENT
+UID:tz-by-reference@example.com
+DTSTAMP:20231001T100000Z
+SUMMARY:Event with a standard timezone
+DTSTART;TZID=Europe/Amsterdam:20231001T120000
+DTEND;TZID=Europe/Amsterdam:20231001T130000
+END:VEVENT
+END:VCALENDAR
+"#;
+
+const TEST_JCAL: &str = r#"["vcalendar",
+  [
+    ["version", {}, "text", "2.0"],
+    ["prodid", {}, "text", "-//Example Corp.//CalDAV Client//EN"]
+  ],
+  [
+    ["vevent",
+      [
+        ["uid", {}, "text", "jcal-event@example.com"],
+        ["dtstamp", {}, "date-time", "2025-01-01T09:00:00Z"],
+        ["dtstart", {"tzid": "Europe/Rome"}, "date-time", "2025-01-06T10:00:00"],
+        ["duration", {}, "duration", "PT1H"],
+        ["rrule", {}, "recur", {"freq": "WEEKLY", "count": 4, "byday": "MO"}],
+        ["summary", {}, "text", "Team meeting"]
+      ],
+      [
+        ["valarm",
+          [
+            ["action", {}, "text", "DISPLAY"],
+            ["description", {}, "text", "Reminder"],
+            ["trigger", {"related": "START"}, "duration", "-PT15M"]
+          ],
+          []
+        ]
+      ]
+    ]
+  ]
+]"#;
+
+const TEST_JCARD: &str = r#"["vcard",
+  [
+    ["version", {}, "text", "4.0"],
+    ["uid", {}, "uri", "urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1"],
+    ["fn", {}, "text", "Jane Doe"],
+    ["n", {}, "text", ["Doe", "Jane", "", "", ""]],
+    ["email", {"type": "work"}, "text", "jane@example.com"],
+    ["bday", {}, "date", "1985-04-12"]
+  ]
+]"#;
+
+const TEST_ICAL_OUTLOOK: &str = r#"BEGIN:VCALENDAR
+VERSION:2.0
+PRODID:-//Microsoft Corporation//Outlook 16.0 MIMEDIR//EN
+BEGIN:VEVENT
+UID:outlook-compat@example.com
+DTSTAMP:20250101T090000Z
+DTSTART;TZID="Pacific Standard Time":2025010
+ 6T100000
+DTEND;TZID="Pacific Standard Time":20250106T110000
+SUMMARY:Outlook meeting
+X-MICROSOFT-CDO-BUSYSTATUS:TENTATIVE
+END:VEVENT
+END:VCALENDAR
+"#;
@@ -0,0 +1,198 @@
+/*
+ * SPDX-FileCopyrightText: 2020 Stalwart Labs Ltd <hello@stalw.art>
+ *
+ * SPDX-License-Identifier: AGPL-3.0-only OR LicenseRef-SEL
+ */
+
+use dav_proto::schema::property::{DavProperty, WebDavProperty};
+use hyper::StatusCode;
+
+use super::{DavResponse, DummyWebDavClient, WebDavTest};
+
+pub async fn test(test: &WebDavTest) {
+    println!("Running calendar sharing tests...");
+    let owner_client = test.client("john");
+    let sharee_client = test.client("jane");
+    let calendar_href = "/dav/cal/john/team/";
+    let notifications_href = "/dav/cal/jane/notifications/";
+
+    owner_client
+        .request("MKCOL", calendar_href, "")
+        .await
+        .with_status(StatusCode::CREATED);
+
+    // Calendars cannot be shared with their owner
+    owner_client
+        .share(calendar_href, [("/dav/pal/john/", true)], [])
+        .await
+        .with_status(StatusCode::FORBIDDEN)
+        .with_failed_precondition("D:allowed-principal", "");
+
+    // Invite the sharee by e-mail address
+    owner_client
+        .share(calendar_href, [("mailto:jane.smith@example.com", true)], [])
+        .await
+        .with_status(StatusCode::OK);
+    owner_client
+        .propfind(calendar_href, ["C:invite", "C:allowed-sharing-modes"])
+        .await
+        .properties(calendar_href)
+        .get("C:invite")
+        .with_some_values([
+            "C:user.D:href:/dav/pal/jane/",
+            "C:user.C:invite-noresponse",
+            "C:user.C:access.C:read-write",
+        ]);
+
+    // Only the owner can share the calendar
+    sharee_client
+        .share(calendar_href, [("/dav/pal/bill/", false)], [])
+        .await
+        .with_status(StatusCode::FORBIDDEN);
+
+    // The invitation is listed in the notification collection of the sharee
+    let response = sharee_client
+        .propfind(notifications_href, ["C:notificationtype"])
+        .await;
+    assert_eq!(response.hrefs.len(), 2);
+    let notification_href = response
+        .hrefs
+        .keys()
+        .find(|href| href.as_str() != notifications_href)
+        .unwrap()
+        .to_string();
+    response
+        .properties(&notification_href)
+        .get("C:notificationtype")
+        .with_values(["C:invite-notification"]);
+    sharee_client
+        .request("GET", &notification_href, "")
+        .await
+        .with_status(StatusCode::OK)
+        .with_value(
+            "C:notification.C:invite-notification.C:hosturl.D:href",
+            calendar_href,
+        )
+        .with_value(
+            "C:notification.C:invite-notification.C:organizer.D:href",
+            "/dav/pal/john/",
+        )
+        .with_value(
+            "C:notification.C:invite-notification.C:access.C:read-write",
+            "",
+        );
+    sharee_client
+        .request("DELETE", &notification_href, "")
+        .await
+        .with_status(StatusCode::FORBIDDEN);
+
+    // Accept the invitation
+    sharee_client
+        .invite_reply("/dav/cal/jane/", calendar_href, true)
+        .await
+        .with_status(StatusCode::OK)
+        .with_value("C:shared-as.D:href", calendar_href);
+    sharee_client
+        .propfind(notifications_href, ["C:notificationtype"])
+        .await
+        .with_hrefs([notifications_href]);
+    owner_client
+        .propfind(calendar_href, ["C:invite"])
+        .await
+        .properties(calendar_href)
+        .get("C:invite")
+        .with_some_values(["C:user.C:invite-accepted"]);
+    sharee_client
+        .propfind(calendar_href, ["D:displayname"])
+        .await
+        .with_hrefs([calendar_href]);
+
+    // Removing the sharee revokes access to the calendar
+    owner_client
+        .share(calendar_href, [], ["mailto:jane.smith@example.com"])
+        .await
+        .with_status(StatusCode::OK);
+    sharee_client
+        .propfind_with_headers(
+            "/dav/cal/",
+            [DavProperty::WebDav(WebDavProperty::GetETag)],
+            [("prefer", "depth-noroot")],
+        )
+        .await
+        .with_hrefs(["/dav/cal/jane/"]);
+
+    owner_client
+        .request("DELETE", calendar_href, "")
+        .await
+        .with_status(StatusCode::NO_CONTENT);
+    for (client, account) in [(owner_client, "john"), (sharee_client, "jane")] {
+        client
+            .request("DELETE", &format!("/dav/cal/{account}/default"), "")
+            .await
+            .with_status(StatusCode::NO_CONTENT);
+    }
+    test.assert_is_empty().await;
+}
+
+impl DummyWebDavClient {
+    pub async fn share<'x>(
+        &self,
+        query: &str,
+        set: impl IntoIterator<Item = (&'x str, bool)>,
+        remove: impl IntoIterator<Item = &'x str>,
+    ) -> DavResponse {
+        let mut request = concat!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>",
+            "<C:share xmlns:D=\"DAV:\" xmlns:C=\"http://calendarserver.org/ns/\">"
+        )
+        .to_string();
+        for (href, read_write) in set {
+            request.push_str(&format!(
+                "<C:set><D:href>{href}</D:href>{}</C:set>",
+                if read_write {
+                    "<C:read-write/>"
+                } else {
+                    "<C:read/>"
+                }
+            ));
+        }
+        for href in remove {
+            request.push_str(&format!("<C:remove><D:href>{href}</D:href></C:remove>"));
+        }
+        request.push_str("</C:share>");
+
+        self.request_with_headers(
+            "POST",
+            query,
+            [("content-type", "application/xml; charset=utf-8")],
+            &request,
+        )
+        .await
+    }
+
+    pub async fn invite_reply(&self, query: &str, host_url: &str, accepted: bool) -> DavResponse {
+        let request = format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"utf-8\"?>",
+                "<C:invite-reply xmlns:D=\"DAV:\" xmlns:C=\"http://calendarserver.org/ns/\">",
+                "<D:href>mailto:{}</D:href>{}<C:hosturl><D:href>{}</D:href></C:hosturl>",
+                "</C:invite-reply>"
+            ),
+            self.email,
+            if accepted {
+                "<C:invite-accepted/>"
+            } else {
+                "<C:invite-declined/>"
+            },
+            host_url
+        );
+
+        self.request_with_headers(
+            "POST",
+            query,
+            [("content-type", "application/xml; charset=utf-8")],
+            &request,
+        )
+        .await
+    }
+}
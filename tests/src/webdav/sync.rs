@@ -10,6 +10,7 @@ use ahash::AHashSet;
 use dav_proto::Depth;
 use groupware::DavResourceName;
 use hyper::StatusCode;
+use jmap_proto::types::collection::SyncCollection;
 
 pub async fn test(test: &WebDavTest) {
     let client = test.client("john");
@@ -175,7 +176,8 @@ pub async fn test(test: &WebDavTest) {
             .map(|x| x.0.as_str())
             .chain([folder_name.as_str()])
             .collect::<AHashSet<_>>();
-        for _ in 0..10 {
+        let late_file = format!("{new_collection}late-file");
+        for page in 0..10 {
             let response = client
                 .sync_collection(
                     &user_base_path,
@@ -186,6 +188,13 @@ pub async fn test(test: &WebDavTest) {
                 )
                 .await;
             sync_token = response.sync_token().to_string();
+            if page == 0 {
+                // Changes made while paging are not part of the remaining pages
+                client
+                    .request("PUT", &late_file, resource_type.generate())
+                    .await
+                    .with_status(StatusCode::CREATED);
+            }
             let hrefs = response.hrefs();
             if hrefs.is_empty() {
                 break;
@@ -223,7 +232,35 @@ pub async fn test(test: &WebDavTest) {
         }
         assert!(expected_changes.is_empty(), "{:?}", expected_changes);
 
-        // Test 10: Expect changes after deletion
+        // Test 10: Changes made while paging are returned by the next sync
+        let response = client
+            .sync_collection(
+                &user_base_path,
+                &sync_token,
+                Depth::Infinity,
+                None,
+                ["D:getetag"],
+            )
+            .await;
+        assert_eq!(response.hrefs(), vec![late_file.clone()]);
+        client
+            .request("DELETE", &late_file, "")
+            .await
+            .with_status(StatusCode::NO_CONTENT);
+        sync_token = client
+            .sync_collection(
+                &user_base_path,
+                response.sync_token(),
+                Depth::Infinity,
+                None,
+                ["D:getetag"],
+            )
+            .await
+            .with_href_count(1)
+            .sync_token()
+            .to_string();
+
+        // Test 11: Expect changes after deletion
         client
             .request("DELETE", &new_file, "")
             .await
@@ -285,6 +322,58 @@ pub async fn test(test: &WebDavTest) {
                 "D:multistatus.D:response.D:status",
                 "HTTP/1.1 404 Not Found",
             );
+
+        // Test 12: Tokens issued before a sync epoch reset through the management API
+        // are rejected, while tokens issued afterwards carry the new epoch
+        let sync_token = client
+            .sync_collection(&user_base_path, "", Depth::Infinity, None, ["D:getetag"])
+            .await
+            .sync_token()
+            .to_string();
+        let sync_collection = match resource_type {
+            DavResourceName::Cal => SyncCollection::Calendar,
+            DavResourceName::Card => SyncCollection::AddressBook,
+            _ => SyncCollection::FileNode,
+        };
+        let response = test
+            .client("admin")
+            .request("POST", "/api/store/groupware/john/reset-sync", "")
+            .await
+            .with_status(StatusCode::OK)
+            .json();
+        let sync_epoch = response["data"][sync_collection.as_str()]
+            .as_u64()
+            .unwrap_or_else(|| panic!("Unexpected reset-sync response: {response}"));
+        client
+            .sync_collection_request(
+                &user_base_path,
+                &sync_token,
+                Depth::Infinity,
+                None,
+                ["D:getetag"],
+            )
+            .await
+            .with_status(StatusCode::FORBIDDEN)
+            .with_failed_precondition("D:valid-sync-token", "");
+        let sync_token = client
+            .sync_collection(&user_base_path, "", Depth::Infinity, None, ["D:getetag"])
+            .await
+            .sync_token()
+            .to_string();
+        assert!(
+            sync_token.ends_with(&format!("@{sync_epoch:x}")),
+            "{sync_token} does not belong to epoch {sync_epoch}"
+        );
+        let response = client
+            .sync_collection(
+                &user_base_path,
+                &sync_token,
+                Depth::Infinity,
+                None,
+                ["D:getetag"],
+            )
+            .await;
+        assert_eq!(response.hrefs(), Vec::<String>::new());
     }
 
     client.delete_default_containers().await;
@@ -299,6 +388,19 @@ impl DummyWebDavClient {
         depth: Depth,
         limit: Option<usize>,
         properties: impl IntoIterator<Item = &str>,
+    ) -> DavResponse {
+        self.sync_collection_request(path, sync_token, depth, limit, properties)
+            .await
+            .with_status(StatusCode::MULTI_STATUS)
+    }
+
+    pub async fn sync_collection_request(
+        &self,
+        path: &str,
+        sync_token: &str,
+        depth: Depth,
+        limit: Option<usize>,
+        properties: impl IntoIterator<Item = &str>,
     ) -> DavResponse {
         let mut request = concat!(
             "<?xml version=\"1.0\" encoding=\"utf-8\"?>",
@@ -329,8 +431,6 @@ impl DummyWebDavClient {
 
         request.push_str("</D:sync-collection>");
 
-        self.request("REPORT", path, &request)
-            .await
-            .with_status(StatusCode::MULTI_STATUS)
+        self.request("REPORT", path, &request).await
     }
 }